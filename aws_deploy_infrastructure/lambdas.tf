@@ -25,11 +25,13 @@ resource "aws_lambda_function" "pdf_processing" {
 
   environment {
     variables = {
+      ENVIRONMENT              = var.environment
       RUST_BACKTRACE           = "full"
       DATABASE_URL             = "postgres://${var.db_admin_name}:${var.db_admin_pwd}@${aws_db_instance.postgres.endpoint}/${var.db_name}"
       LAMBDA_BUCKET            = aws_s3_bucket.lambda_bucket.id
       PDF_PROCESSING_QUEUE_URL = aws_sqs_queue.pdf_processing_queue.url
       ML_PREDICTION_QUEUE_URL  = aws_sqs_queue.ml_prediction_queue.url
+      PIPELINE_TOPIC_ARN       = join("", aws_sns_topic.pipeline_events[*].arn)
     }
   }
 
@@ -56,6 +58,7 @@ resource "aws_lambda_function" "postgres_dataload" {
 
   environment {
     variables = {
+      ENVIRONMENT              = var.environment
       RUST_BACKTRACE           = "1"
       DATABASE_URL             = "postgres://${var.db_admin_name}:${var.db_admin_pwd}@${aws_db_instance.postgres.endpoint}/${var.db_name}"
       PDF_PROCESSING_QUEUE_URL = aws_sqs_queue.pdf_processing_queue.url
@@ -90,6 +93,7 @@ resource "aws_lambda_function" "get_data" {
 
   environment {
     variables = {
+      ENVIRONMENT        = var.environment
       RUST_BACKTRACE     = "1"
       DATABASE_URL       = "postgres://${var.db_admin_name}:${var.db_admin_pwd}@${aws_db_instance.postgres.endpoint}/${var.db_name}"
       LAMBDA_BUCKET_NAME = aws_s3_bucket.lambda_bucket.id
@@ -119,10 +123,11 @@ resource "aws_lambda_function" "ml_bid_predictor" {
 
   environment {
     variables = {
+      ENVIRONMENT          = var.environment
       RUST_BACKTRACE       = "1"
       DATABASE_URL         = "postgres://${var.db_admin_name}:${var.db_admin_pwd}@${aws_db_instance.postgres.endpoint}/${var.db_name}"
       AI_SUMMARY_QUEUE_URL = aws_sqs_queue.ai_summary_queue.url
-      SNS_TOPIC_ARN        = aws_sns_topic.ml_predictions.arn
+      PIPELINE_TOPIC_ARN   = join("", aws_sns_topic.pipeline_events[*].arn)
       MODEL_VERSION        = "v1.0"
       PREDICTION_THRESHOLD = "0.5"
       BATCH_SIZE           = "100"
@@ -154,10 +159,12 @@ resource "aws_lambda_function" "ai_summary" {
 
   environment {
     variables = {
-      RUST_BACKTRACE    = "1"
-      DATABASE_URL      = "postgres://${var.db_admin_name}:${var.db_admin_pwd}@${aws_db_instance.postgres.endpoint}/${var.db_name}"
-      SNS_QUEUE_URL     = aws_sqs_queue.sns_queue.url
-      ANTHROPIC_API_KEY = var.anthropic_api_key
+      ENVIRONMENT        = var.environment
+      RUST_BACKTRACE     = "1"
+      DATABASE_URL       = "postgres://${var.db_admin_name}:${var.db_admin_pwd}@${aws_db_instance.postgres.endpoint}/${var.db_name}"
+      SNS_QUEUE_URL      = aws_sqs_queue.sns_queue.url
+      ANTHROPIC_API_KEY  = var.anthropic_api_key
+      PIPELINE_TOPIC_ARN = join("", aws_sns_topic.pipeline_events[*].arn)
     }
   }
 
@@ -181,6 +188,7 @@ resource "aws_lambda_function" "sns_notification" {
 
   environment {
     variables = {
+      ENVIRONMENT         = var.environment
       RUST_BACKTRACE      = "1"
       DATABASE_URL        = "postgres://${var.db_admin_name}:${var.db_admin_pwd}@${aws_db_instance.postgres.endpoint}/${var.db_name}"
       NOTIFICATION_EMAILS = var.notification_emails_str
@@ -211,8 +219,12 @@ resource "aws_lambda_function" "etenders_scraper" {
 
   environment {
     variables = {
-      RUST_BACKTRACE              = "1"
-      TENDER_PROCESSING_QUEUE_URL = aws_sqs_queue.tender_processing_queue.url
+      ENVIRONMENT                  = var.environment
+      RUST_BACKTRACE               = "1"
+      TENDER_PROCESSING_QUEUE_URL  = aws_sqs_queue.tender_processing_queue.url
+      SCRAPER_TIME_MARGIN_SECS     = "60" # Checkpoint and reinvoke when less than this remains
+      SCRAPER_PAGES_PER_INVOCATION = "10"
+      SCRAPER_MAX_INVOCATIONS      = "100"
     }
   }
 
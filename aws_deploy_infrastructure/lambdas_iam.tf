@@ -65,7 +65,7 @@ resource "aws_iam_role_policy" "lambda_s3_access" {
   })
 }
 
-# SNS publishing policy for ML prediction notifications
+# SNS publishing policy for ML prediction notifications and pipeline fan-out
 resource "aws_iam_role_policy" "lambda_sns_access" {
   name = "lambda_sns_access"
   role = aws_iam_role.lambda_role.id
@@ -81,7 +81,7 @@ resource "aws_iam_role_policy" "lambda_sns_access" {
           "sns:Unsubscribe",
           "sns:GetTopicAttributes"
         ]
-        Resource = aws_sns_topic.ml_predictions.arn
+        Resource = concat([aws_sns_topic.ml_predictions.arn], aws_sns_topic.pipeline_events[*].arn)
       }
     ]
   })
@@ -138,3 +138,54 @@ resource "aws_iam_role_policy" "lambda_sqs_access" {
     ]
   })
 }
+
+# Resource discovery looks up queues/buckets by their etenders:resource tag
+resource "aws_iam_role_policy" "lambda_resource_discovery" {
+  name = "lambda_resource_discovery"
+  role = aws_iam_role.lambda_role.id
+
+  policy = jsonencode({
+    Version = "2012-10-17"
+    Statement = [
+      {
+        Effect   = "Allow"
+        Action   = ["tag:GetResources"]
+        Resource = "*"
+      }
+    ]
+  })
+}
+
+# The scraper reinvokes itself to finish a page range that doesn't fit in one timeout
+resource "aws_iam_role_policy" "lambda_scraper_self_invoke" {
+  name = "lambda_scraper_self_invoke"
+  role = aws_iam_role.lambda_role.id
+
+  policy = jsonencode({
+    Version = "2012-10-17"
+    Statement = [
+      {
+        Effect   = "Allow"
+        Action   = ["lambda:InvokeFunction"]
+        Resource = aws_lambda_function.etenders_scraper.arn
+      }
+    ]
+  })
+}
+
+# Stages report `.waitForTaskToken` results when orchestrated by Step Functions
+resource "aws_iam_role_policy" "lambda_step_functions_callback" {
+  name = "lambda_step_functions_callback"
+  role = aws_iam_role.lambda_role.id
+
+  policy = jsonencode({
+    Version = "2012-10-17"
+    Statement = [
+      {
+        Effect   = "Allow"
+        Action   = ["states:SendTaskSuccess", "states:SendTaskFailure"]
+        Resource = "*"
+      }
+    ]
+  })
+}
@@ -1,6 +1,10 @@
 resource "aws_s3_bucket" "lambda_bucket" {
   bucket        = "module2-lambda-deployments"
   force_destroy = true
+
+  tags = {
+    "etenders:resource" = "module2-lambda-deployments"
+  }
 }
 
 # Block all public access
@@ -12,3 +12,60 @@ resource "aws_sns_topic" "ml_predictions" {
 # ai_summary → sns-notification-queue (SQS) → sns_notification Lambda → emails
 
 
+
+# Pipeline fan-out topic (enabled per environment with var.pipeline_fanout)
+# Producers publish each stage message once with a `destination` attribute; every queue
+# subscribes with raw delivery and a filter policy on it (see the queue crate)
+locals {
+  pipeline_subscribers = var.pipeline_fanout ? {
+    "pdf-processing-queue"   = aws_sqs_queue.pdf_processing_queue
+    "ml-prediction-queue"    = aws_sqs_queue.ml_prediction_queue
+    "ai-summary-queue"       = aws_sqs_queue.ai_summary_queue
+    "sns-notification-queue" = aws_sqs_queue.sns_queue
+  } : {}
+}
+
+resource "aws_sns_topic" "pipeline_events" {
+  count = var.pipeline_fanout ? 1 : 0
+  name  = "pipeline-events"
+
+  tags = {
+    Name                = "Pipeline Events"
+    "etenders:resource" = "pipeline-events"
+  }
+}
+
+resource "aws_sns_topic_subscription" "pipeline_events" {
+  for_each = local.pipeline_subscribers
+
+  topic_arn            = aws_sns_topic.pipeline_events[0].arn
+  protocol             = "sqs"
+  endpoint             = each.value.arn
+  raw_message_delivery = true # Message attributes arrive as SQS message attributes
+
+  filter_policy = jsonencode({
+    destination = [each.key]
+  })
+}
+
+# Allow the topic to deliver to the subscribed queues
+resource "aws_sqs_queue_policy" "pipeline_events" {
+  for_each = local.pipeline_subscribers
+
+  queue_url = each.value.url
+
+  policy = jsonencode({
+    Version = "2012-10-17"
+    Statement = [
+      {
+        Effect    = "Allow"
+        Principal = { Service = "sns.amazonaws.com" }
+        Action    = "sqs:SendMessage"
+        Resource  = each.value.arn
+        Condition = {
+          ArnEquals = { "aws:SourceArn" = aws_sns_topic.pipeline_events[0].arn }
+        }
+      }
+    ]
+  })
+}
@@ -11,7 +11,8 @@ resource "aws_sqs_queue" "pdf_processing_queue" {
   })
 
   tags = {
-    Name = "PDF Processing Queue"
+    Name                = "PDF Processing Queue"
+    "etenders:resource" = "pdf-processing-queue"
   }
 }
 
@@ -32,6 +33,7 @@ resource "aws_lambda_event_source_mapping" "pdf_processing_sqs_trigger" {
 
   batch_size                         = 1 # Process one PDF at a time
   maximum_batching_window_in_seconds = 0 # Disable extra buffering; one message per invoke
+  function_response_types            = ["ReportBatchItemFailures"] # Only retryable failures are redelivered
 
   scaling_config {
     maximum_concurrency = 200 # Control concurrency here instead of reserved concurrency
@@ -116,7 +118,8 @@ resource "aws_sqs_queue" "ml_prediction_queue" {
   })
 
   tags = {
-    Name = "ML Prediction Queue"
+    Name                = "ML Prediction Queue"
+    "etenders:resource" = "ml-prediction-queue"
   }
 }
 
@@ -137,6 +140,7 @@ resource "aws_lambda_event_source_mapping" "ml_prediction_sqs_trigger" {
 
   batch_size                         = 1 # Process one trigger at a time
   maximum_batching_window_in_seconds = 0
+  function_response_types            = ["ReportBatchItemFailures"] # Only retryable failures are redelivered
 
   scaling_config {
     maximum_concurrency = 5 # Limit ML processing concurrency
@@ -156,7 +160,8 @@ resource "aws_sqs_queue" "ai_summary_queue" {
   })
 
   tags = {
-    Name = "AI Summary Queue"
+    Name                = "AI Summary Queue"
+    "etenders:resource" = "ai-summary-queue"
   }
 }
 
@@ -177,6 +182,7 @@ resource "aws_lambda_event_source_mapping" "ai_summary_sqs_trigger" {
 
   batch_size                         = 1 # Process one summary at a time
   maximum_batching_window_in_seconds = 0
+  function_response_types            = ["ReportBatchItemFailures"] # Only retryable failures are redelivered
 
   scaling_config {
     maximum_concurrency = 3 # Limit AI API concurrency to avoid rate limits
@@ -196,7 +202,8 @@ resource "aws_sqs_queue" "sns_queue" {
   })
 
   tags = {
-    Name = "SNS Notification Queue"
+    Name                = "SNS Notification Queue"
+    "etenders:resource" = "sns-notification-queue"
   }
 }
 
@@ -217,6 +224,7 @@ resource "aws_lambda_event_source_mapping" "sns_notification_sqs_trigger" {
 
   batch_size                         = 1 # Process one notification at a time
   maximum_batching_window_in_seconds = 0
+  function_response_types            = ["ReportBatchItemFailures"] # Only retryable failures are redelivered
 
   scaling_config {
     maximum_concurrency = 10 # Allow multiple email notifications in parallel
@@ -236,7 +244,8 @@ resource "aws_sqs_queue" "tender_processing_queue" {
   })
 
   tags = {
-    Name = "Tender Processing Queue"
+    Name                = "Tender Processing Queue"
+    "etenders:resource" = "tender-processing-queue"
   }
 }
 
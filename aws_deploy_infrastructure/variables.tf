@@ -45,4 +45,15 @@ variable "anthropic_api_key" {
   type        = string
   sensitive   = true
   # no default - will be provided by github actions
-}
\ No newline at end of file
+}
+variable "environment" {
+  description = "Deployment environment (dev, staging or prod); non-prod uses prefixed resources and its own DB schema"
+  type        = string
+  default     = "prod"
+}
+
+variable "pipeline_fanout" {
+  description = "Send stage messages through the pipeline-events SNS topic (sets PIPELINE_TOPIC_ARN) instead of straight to each queue"
+  type        = bool
+  default     = false
+}
@@ -1,21 +1,191 @@
-use crate::types::{AISummaryResult, MLPredictionResult, TenderRecord, PdfContent};
+use crate::chunked::{self, ContinuationNeeded};
+use crate::claude_budget::{self, BudgetExhausted};
+use crate::decision::{EARLY_INTEREST, PARSE_FALLBACK_RECOMMENDATION};
+use crate::pii;
+use crate::prompt_context::PromptContext;
+use crate::replay;
+use crate::second_opinion::SecondOpinion;
+use crate::summary_cache::{self, CacheKey};
+use crate::summary_quality::Regeneration;
+use crate::tenants::{CompanyProfile, DEFAULT_TENANT};
+use crate::types::{AISummaryResult, MLPredictionResult, TenderContext, TenderRecord, PdfContent};
 use anyhow::Result;
+use pipeline_contract::Procedure;
+use pipeline_contract::timeouts::{self, Call};
 use tracing::{info, debug, warn};
 use chrono::Utc;
 use serde_json::{json, Value};
 use anthropic_sdk;
+use fault_injection::Fault;
+use retry::Policy;
+use sqlx::PgPool;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Claude model used for every summary
+pub const MODEL: &str = "claude-sonnet-4-20250514";
+/// Bump whenever the prompt wording or context assembly changes, so cached results aren't reused
+pub const PROMPT_VERSION: &str = "7";
+/// Processing note on results built from an unparseable response; those are never cached
+pub const UNPARSED_NOTE: &str = "Claude response could not be parsed as JSON";
+
+/// Input budget (estimated tokens, instructions included) for title-only prompts
+const TITLE_INPUT_TOKENS: usize = 2000;
+/// Input budget (estimated tokens) for full PDF prompts - mostly PDF text, which is cut first
+const FULL_INPUT_TOKENS: usize = 6500;
 
 /// AI service for generating summaries using Claude
 pub struct AIService {
     api_key: String,
+    /// `MODEL`, unless this service gives a second opinion
+    model: String,
+    cache: Option<PgPool>,
+    /// Daily Claude call limit, counted in `claude_usage`
+    budget: Option<(PgPool, i32)>,
+    /// Where notes from chunked reads of long documents are kept (see `chunked`)
+    chunks: Option<PgPool>,
+    /// When the invocation must hand its work on, with the margin already taken off
+    deadline: Option<SystemTime>,
+    /// Limit on each Claude request, retries included
+    claude_timeout: Option<Duration>,
+    second_opinion: Option<SecondOpinion>,
+    regeneration: Option<Regeneration>,
+    /// Where Claude's replies are kept as replay fixtures (see `replay`)
+    recording: Option<PathBuf>,
 }
 
 impl AIService {
     /// Create new AI service
     pub fn new(api_key: String) -> Self {
         info!("✅ Claude AI service initialized");
-        Self { api_key }
+        Self { api_key, model: MODEL.to_string(), cache: None, budget: None, chunks: None, deadline: None, claude_timeout: None, second_opinion: None, regeneration: None, recording: None }
+    }
+    
+    /// Reuse stored results for content Claude has already summarised (see summary_cache)
+    pub fn with_cache(mut self, pool: PgPool) -> Self {
+        self.cache = Some(pool);
+        self
+    }
+    
+    /// Cap Claude calls per day; once used up, summaries fail with `BudgetExhausted`
+    pub fn with_budget(mut self, pool: PgPool, daily_limit: i32) -> Self {
+        info!("💰 Claude daily call budget: {}", daily_limit);
+        self.budget = Some((pool, daily_limit));
+        self
+    }
+    
+    /// Read documents too long for one prompt in parts, keeping each part's notes (see `chunked`).
+    /// Without it they are truncated to fit
+    pub fn with_chunks(mut self, pool: PgPool) -> Self {
+        self.chunks = Some(pool);
+        self
+    }
+    
+    /// Stop reading a long document with `ContinuationNeeded` once less than `margin` is left before `deadline`
+    pub fn with_deadline(mut self, deadline: SystemTime, margin: Duration) -> Self {
+        self.deadline = Some(deadline.checked_sub(margin).unwrap_or(SystemTime::UNIX_EPOCH));
+        self
+    }
+    
+    /// Give up on a Claude request (retries included) after `limit`, failing with `TimedOut`
+    pub fn with_claude_timeout(mut self, limit: Option<Duration>) -> Self {
+        self.claude_timeout = limit;
+        self
+    }
+    
+    /// Have tenders with a borderline ML score evaluated by a second model too (see `second_opinion`)
+    pub fn with_second_opinion(mut self, second_opinion: SecondOpinion) -> Self {
+        info!("🗳️ Second opinion: {}", second_opinion.describe());
+        self.second_opinion = Some(second_opinion);
+        self
+    }
+    
+    pub fn second_opinion(&self) -> Option<&SecondOpinion> {
+        self.second_opinion.as_ref()
+    }
+    
+    /// Generate summaries that score low on `summary_quality` once more (see `summary_quality`)
+    pub fn with_regeneration(mut self, regeneration: Regeneration) -> Self {
+        info!("🧪 Summary regeneration: {}", regeneration.describe());
+        self.regeneration = Some(regeneration);
+        self
+    }
+    
+    pub fn regeneration(&self) -> Option<&Regeneration> {
+        self.regeneration.as_ref()
+    }
+    
+    /// Write each reply parsed into a summary to `dir` as a replay fixture (see `replay`)
+    pub fn with_recording(mut self, dir: PathBuf) -> Self {
+        info!("📼 Recording Claude responses to {}", dir.display());
+        self.recording = Some(dir);
+        self
+    }
+    
+    /// The same service asking `model` instead, without a second opinion or regeneration of its own
+    pub fn for_model(&self, model: &str) -> Self {
+        Self {
+            api_key: self.api_key.clone(),
+            model: model.to_string(),
+            cache: self.cache.clone(),
+            budget: self.budget.clone(),
+            chunks: self.chunks.clone(),
+            deadline: self.deadline,
+            claude_timeout: self.claude_timeout,
+            second_opinion: None,
+            regeneration: None,
+            recording: self.recording.clone(),
+        }
+    }
+    
+    fn past_deadline(&self) -> bool {
+        self.deadline.is_some_and(|deadline| SystemTime::now() >= deadline)
+    }
+    
+    /// Notes on each part of a document too long for one prompt, or None when it fits (or there's
+    /// no chunk store). Parts read by an earlier invocation are loaded rather than read again; past
+    /// the deadline this returns `ContinuationNeeded`, but only after reading at least one part, so
+    /// every continuation makes progress
+    pub async fn read_document(&self, tender: &TenderRecord, pdf_text: &str) -> Result<Option<String>> {
+        let Some(pool) = &self.chunks else {
+            return Ok(None);
+        };
+        if pdf_text.len() <= chunked::CHUNKED_ABOVE {
+            return Ok(None);
+        }
+        
+        let parts = chunked::split(pdf_text, chunked::CHUNK_BYTES, chunked::MAX_CHUNKS);
+        let content_hash = chunked::document_hash(pdf_text);
+        let mut notes = chunked::load(pool, &content_hash).await?;
+        info!("📚 Reading {} bytes of PDF text for resource_id {} in {} parts ({} already read)",
+              pdf_text.len(), tender.resource_id, parts.len(), notes.len());
+        
+        let mut read_now = 0;
+        for (index, part) in parts.iter().enumerate() {
+            if notes.contains_key(&index) {
+                continue;
+            }
+            if read_now > 0 && self.past_deadline() {
+                return Err(ContinuationNeeded { read: notes.len(), total: parts.len() }.into());
+            }
+            
+            let prompt = chunked::notes_prompt(&tender.title, index, parts.len(), part);
+            let reply = self.complete(&prompt, chunked::NOTES_MAX_TOKENS).await?;
+            let reply = reply.trim().to_string();
+            chunked::store(pool, &content_hash, tender.resource_id, index, parts.len(), &reply).await?;
+            debug!("📝 Read part {} of {} for resource_id {}", index + 1, parts.len(), tender.resource_id);
+            notes.insert(index, reply);
+            read_now += 1;
+        }
+        
+        // The summaries written from the notes need time of their own
+        if read_now > 0 && self.past_deadline() {
+            return Err(ContinuationNeeded { read: parts.len(), total: parts.len() }.into());
+        }
+        
+        let notes: Vec<String> = (0..parts.len()).map(|index| notes.remove(&index).unwrap_or_default()).collect();
+        Ok(Some(chunked::combine(&notes)))
     }
     
     /// Safely truncate a string at the specified byte position, respecting UTF-8 character boundaries
@@ -34,41 +204,22 @@ impl AIService {
     /// Generate AI summary - title only version (lightweight)
     pub async fn generate_title_summary(
         &self,
-        tender_title: &str,
-        contracting_authority: &str,
+        tender: &TenderRecord,
         ml_prediction: &MLPredictionResult,
-        resource_id: i64,
+        profile: &CompanyProfile,
+        context: &TenderContext,
+        refresh: bool,
     ) -> Result<AISummaryResult> {
-        info!("🤖 Generating title-only AI summary for resource_id: {}", resource_id);
+        info!("🤖 Generating title-only AI summary for resource_id: {} (tenant: {})", tender.resource_id, profile.tenant_id);
         
-        let prompt = format!(
-            r#"You are an expert tender analyst for an IT SERVICE CONSULTANCY specializing in software development, technical support, and IT systems. 
+        let prompt_around = |tender_context: &str| format!(
+            r#"You are an expert tender analyst for {}. 
 
 🚨 CRITICAL: You are the FINAL DECISION MAKER. The ML prediction is just a rough filter - you have full authority to override it.
 
-🚨 DEFAULT TO "NO BID" unless this is CLEARLY an IT consultancy opportunity. We get too many false positives.
+🚨 DEFAULT TO "NO BID" unless this is CLEARLY within our scope. We get too many false positives.
 
-TENDER TITLE: "{}"
-CONTRACTING AUTHORITY: "{}"
-ML PREDICTION: {} (confidence: {:.1}% - treat as unreliable)
-ML REASONING: {}
-
-🎯 OUR STRICT IT CONSULTANCY SCOPE:
-✅ SOFTWARE DEVELOPMENT: Custom applications, web development, mobile apps
-✅ IT CONSULTING: Systems analysis, technical architecture, IT strategy
-✅ TECHNICAL SUPPORT: IT helpdesk, system administration, technical maintenance
-✅ SYSTEMS INTEGRATION: API development, database design, cloud services
-✅ IT INFRASTRUCTURE: Network setup, server configuration, cybersecurity
-
-🚫 WE ABSOLUTELY DO NOT DO:
-❌ CONSTRUCTION & BUILDING: Any physical building work, renovations, extensions
-❌ CATERING & FOOD: School meals, catering services, food provision, kitchen equipment
-❌ CLEANING & MAINTENANCE: Cleaning services, grounds maintenance, facilities management  
-❌ MEDICAL & HEALTHCARE: Medical equipment, healthcare services, clinical supplies
-❌ PHYSICAL SECURITY: Security guards, CCTV installation, access control systems
-❌ UTILITIES & INFRASTRUCTURE: Water, sewerage, electrical installation, plumbing, HVAC
-❌ PROFESSIONAL SERVICES: Legal, accounting, architectural, surveying, consulting (non-IT)
-❌ SUPPLIES & EQUIPMENT: Office supplies, furniture, vehicles, non-IT equipment
+{}
 
 🔍 ANALYSIS REQUIRED:
 1. 🚨 IMMEDIATE REJECTION CHECK: Is this obviously non-IT? (construction, catering, cleaning, medical, etc.)
@@ -83,16 +234,33 @@ ML REASONING: {}
 
 🎯 RESPONSE FORMAT: Your recommendation field MUST contain either "BID" or "NO BID" - be explicit and conservative.
 
-Format as JSON with fields: summary, key_points (array), recommendation, confidence_assessment"#,
-            tender_title,
-            contracting_authority,
-            if ml_prediction.should_bid { "RECOMMEND BID" } else { "DO NOT BID" },
-            ml_prediction.confidence * 100.0,
-            ml_prediction.reasoning
+Format as JSON with fields: summary, one_liner (the tender in one sentence of at most 100 characters), key_points (array), recommendation, confidence_assessment"#,
+            profile.description,
+            tender_context
         );
         
-        let response = self.call_claude(&prompt, 1000).await?;
-        self.parse_ai_response(response, "TITLE_ONLY", resource_id)
+        let tender_context = PromptContext::new(TITLE_INPUT_TOKENS)
+            .around(&prompt_around(""))
+            .tender_title(&tender.title, &tender.contracting_authority)
+            .ml_prediction(ml_prediction)
+            .profile(profile)
+            .authority(context)
+            .similar_tenders(context)
+            .render();
+        let prompt = prompt_around(&tender_context.text);
+        
+        let key = CacheKey::new(PROMPT_VERSION, &self.model, &[
+            "TITLE_ONLY",
+            &tender.title,
+            &tender.contracting_authority,
+            &profile.description,
+            &profile.scope,
+            &profile.exclusions,
+        ]);
+        let mut result = self.summarise(&prompt, 1000, "TITLE_ONLY", tender.resource_id, &key, refresh).await?;
+        result.tenant_id = profile.tenant_id.clone();
+        result.processing_notes.extend(tender_context.note());
+        Ok(result)
     }
     
     /// Generate AI summary - full PDF version (comprehensive)
@@ -101,62 +269,21 @@ Format as JSON with fields: summary, key_points (array), recommendation, confide
         tender: &TenderRecord,
         pdf_content: &PdfContent,
         ml_prediction: &MLPredictionResult,
+        profile: &CompanyProfile,
+        context: &TenderContext,
+        refresh: bool,
     ) -> Result<AISummaryResult> {
-        info!("🤖 Generating full AI summary for resource_id: {}", tender.resource_id);
-        
-        // Truncate PDF content if too long (keep within token limits - Claude has higher limits than GPT-4)
-        let truncated_pdf = if pdf_content.pdf_text.len() > 15000 {
-            warn!("📄 Truncating PDF content from {} to 15000 chars", pdf_content.pdf_text.len());
-            format!("{}[TRUNCATED]", Self::safe_truncate(&pdf_content.pdf_text, 15000))
-        } else {
-            pdf_content.pdf_text.clone()
-        };
-        
-        let detected_codes_str = pdf_content.detected_codes.join(", ");
+        info!("🤖 Generating full AI summary for resource_id: {} (tenant: {})", tender.resource_id, profile.tenant_id);
         
-        let prompt = format!(
-            r#"You are an expert tender analyst for an IT SERVICE CONSULTANCY specializing in software development, technical support, and IT systems.
+        let prompt_around = |tender_context: &str| format!(
+            r#"You are an expert tender analyst for {}.
 
 🚨 CRITICAL: You are the FINAL DECISION MAKER. The ML prediction is just a rough filter - you have full authority to override it.
 
-🚨 DEFAULT TO "NO BID" unless this is CLEARLY an IT consultancy opportunity. We get too many false positives.
+🚨 DEFAULT TO "NO BID" unless this is CLEARLY within our scope. We get too many false positives.
 
-TENDER DETAILS:
-Title: "{}"
-Contracting Authority: "{}"
-Value: {}
-Deadline: {}
-Status: "{}"
-Procedure: "{}"
-
-PDF CONTENT:
 {}
 
-DETECTED PROCUREMENT CODES: {}
-CODES COUNT: {}
-
-ML PREDICTION: {} (confidence: {:.1}% - treat as unreliable)
-ML REASONING: {}
-
-🎯 OUR STRICT IT CONSULTANCY SCOPE:
-✅ SOFTWARE DEVELOPMENT: Custom applications, web development, mobile apps, databases
-✅ IT CONSULTING: Systems analysis, technical architecture, IT strategy, digital transformation
-✅ TECHNICAL SUPPORT: IT helpdesk, system administration, technical maintenance, user training
-✅ SYSTEMS INTEGRATION: API development, database design, cloud services, software integration
-✅ IT INFRASTRUCTURE: Network setup, server configuration, cybersecurity, IT procurement
-
-🚫 WE ABSOLUTELY DO NOT DO:
-❌ CONSTRUCTION & BUILDING: Any physical building work, renovations, extensions, refurbishments
-❌ CATERING & FOOD: School meals, catering services, food provision, kitchen equipment, dining services
-❌ CLEANING & MAINTENANCE: Cleaning services, grounds maintenance, facilities management, janitorial
-❌ MEDICAL & HEALTHCARE: Medical equipment, healthcare services, clinical supplies, patient care
-❌ PHYSICAL SECURITY: Security guards, CCTV installation, access control systems, patrol services
-❌ UTILITIES & INFRASTRUCTURE: Water, sewerage, electrical installation, plumbing, HVAC, heating
-❌ PROFESSIONAL SERVICES: Legal, accounting, architectural, surveying, HR, non-IT consulting
-❌ SUPPLIES & EQUIPMENT: Office supplies, furniture, vehicles, non-IT equipment, stationery
-❌ TRANSPORT & LOGISTICS: Vehicle services, delivery, transport, fleet management
-❌ WASTE MANAGEMENT: Waste collection, recycling, environmental services
-
 🔍 COMPREHENSIVE ANALYSIS:
 1. 🚨 IMMEDIATE REJECTION CHECK: Scan for obvious non-IT indicators in title and content
 2. CONTENT DEEP DIVE: Analyze the full PDF content for hidden non-IT requirements
@@ -174,33 +301,199 @@ ML REASONING: {}
 
 🎯 RESPONSE REQUIREMENT: Your recommendation field MUST contain either "BID" or "NO BID" - be explicit and extremely conservative.
 
-Format as JSON with fields: summary, key_points (array), recommendation, confidence_assessment"#,
-            tender.title,
-            tender.contracting_authority,
-            tender.value.as_ref().map(|v| v.to_string()).unwrap_or_else(|| "Not specified".to_string()),
-            tender.deadline.map(|d| d.to_string()).unwrap_or_else(|| "Not specified".to_string()),
-            tender.status,
-            tender.procedure,
-            truncated_pdf,
-            detected_codes_str,
-            pdf_content.codes_count,
-            if ml_prediction.should_bid { "RECOMMEND BID" } else { "DO NOT BID" },
-            ml_prediction.confidence * 100.0,
-            ml_prediction.reasoning
+Format as JSON with fields: summary, one_liner (the tender in one sentence of at most 100 characters), key_points (array), recommendation, confidence_assessment"#,
+            profile.description,
+            tender_context
+        );
+        
+        // Sections are listed in prompt order; the budget cuts the low-priority ones first
+        let tender_context = PromptContext::new(FULL_INPUT_TOKENS)
+            .around(&prompt_around(""))
+            .tender(tender)
+            .extraction_quality(pdf_content.extraction_quality)
+            .eligibility(&pdf_content.pdf_text)
+            .document_or_notes(&pdf_content.pdf_text, context)
+            .codes(context, &pdf_content.detected_codes)
+            .ml_prediction(ml_prediction)
+            .authority(context)
+            .similar_tenders(context)
+            .profile(profile)
+            .render();
+        debug!("📏 Prompt context: ~{} tokens (PDF text: {} bytes)", tender_context.tokens, pdf_content.pdf_text.len());
+        let prompt = prompt_around(&tender_context.text);
+        
+        // The ML prediction and buyer history are left out of the key so a re-advertised tender still hits
+        let key = CacheKey::new(PROMPT_VERSION, &self.model, &[
+            "FULL_PDF",
+            &tender.title,
+            &tender.contracting_authority,
+            &pdf_content.pdf_text,
+            &profile.description,
+            &profile.scope,
+            &profile.exclusions,
+        ]);
+        let mut result = self.summarise(&prompt, 2000, "FULL_PDF", tender.resource_id, &key, refresh).await?;
+        result.tenant_id = profile.tenant_id.clone();
+        result.processing_notes.extend(tender_context.note());
+        Ok(result)
+    }
+    
+    /// Generate AI summary - early-interest version for PINs and DPS notices, which announce
+    /// or frame a future competition: the question is whether to register interest, not whether to bid
+    #[allow(clippy::too_many_arguments)]
+    pub async fn generate_early_interest_summary(
+        &self,
+        tender: &TenderRecord,
+        procedure: Procedure,
+        pdf_content: Option<&PdfContent>,
+        ml_prediction: &MLPredictionResult,
+        profile: &CompanyProfile,
+        context: &TenderContext,
+        refresh: bool,
+    ) -> Result<AISummaryResult> {
+        info!("🤖 Generating early-interest AI summary ({}) for resource_id: {} (tenant: {})", procedure.label(), tender.resource_id, profile.tenant_id);
+        
+        let stage = match procedure {
+            Procedure::Dps => "a DYNAMIC PURCHASING SYSTEM. Suppliers apply to join it and are then invited to bid on individual call-offs - joining is not a bid.",
+            _ => "a PRIOR INFORMATION NOTICE. It announces a future competition; there is nothing to bid on yet, but the buyer may run market consultation or shortlist from expressions of interest.",
+        };
+        
+        let prompt_around = |tender_context: &str| format!(
+            r#"You are an expert tender analyst for {}.
+
+📣 This notice is {}
+
+{}
+
+🔍 ANALYSIS REQUIRED:
+1. 🚨 IMMEDIATE REJECTION CHECK: Is this obviously non-IT? (construction, catering, cleaning, medical, etc.)
+2. IT SCOPE VERIFICATION: Would the eventual contracts genuinely need IT consultancy expertise?
+3. NEXT STEPS: What should we do now - register on the portal, respond to market consultation, apply to join, or watch for the call for competition - and by when?
+
+⚠️ GUIDANCE:
+- Do NOT give a bid/no-bid verdict - assess whether the opportunity is worth following
+- If you see ANY non-IT keywords, or the scope is unclear, answer "NO INTEREST"
+- Only answer "REGISTER INTEREST" if the future work is clearly within our IT consultancy scope
+
+🎯 RESPONSE FORMAT: Your recommendation field MUST contain either "REGISTER INTEREST" or "NO INTEREST".
+
+Format as JSON with fields: summary, one_liner (the tender in one sentence of at most 100 characters), key_points (array), recommendation, confidence_assessment"#,
+            profile.description,
+            stage,
+            tender_context
         );
         
-        let response = self.call_claude(&prompt, 2000).await?;
-        self.parse_ai_response(response, "FULL_PDF", tender.resource_id)
+        let tender_context = match pdf_content {
+            Some(pdf_content) => PromptContext::new(FULL_INPUT_TOKENS)
+                .around(&prompt_around(""))
+                .tender(tender)
+                .extraction_quality(pdf_content.extraction_quality)
+                .document_or_notes(&pdf_content.pdf_text, context)
+                .codes(context, &pdf_content.detected_codes)
+                .ml_prediction(ml_prediction)
+                .authority(context)
+                .profile(profile)
+                .render(),
+            None => PromptContext::new(TITLE_INPUT_TOKENS)
+                .around(&prompt_around(""))
+                .tender_title(&tender.title, &tender.contracting_authority)
+                .ml_prediction(ml_prediction)
+                .profile(profile)
+                .authority(context)
+                .render(),
+        };
+        let prompt = prompt_around(&tender_context.text);
+        
+        let content = pdf_content.map(|pdf| pdf.pdf_text.as_str()).unwrap_or_default();
+        let key = CacheKey::new(PROMPT_VERSION, &self.model, &[
+            EARLY_INTEREST,
+            procedure.name(),
+            &tender.title,
+            &tender.contracting_authority,
+            content,
+            &profile.description,
+            &profile.scope,
+            &profile.exclusions,
+        ]);
+        let max_tokens = if pdf_content.is_some() { 2000 } else { 1000 };
+        let mut result = self.summarise(&prompt, max_tokens, EARLY_INTEREST, tender.resource_id, &key, refresh).await?;
+        result.tenant_id = profile.tenant_id.clone();
+        result.processing_notes.extend(tender_context.note());
+        Ok(result)
+    }
+    
+    /// The cached result for the key unless refreshing, otherwise a new Claude call (which is then cached)
+    async fn summarise(
+        &self,
+        prompt: &str,
+        max_tokens: i32,
+        summary_type: &str,
+        resource_id: i64,
+        key: &CacheKey,
+        refresh: bool,
+    ) -> Result<AISummaryResult> {
+        // The cache only saves money - any failure falls through to Claude
+        if let (Some(pool), false) = (&self.cache, refresh) {
+            match summary_cache::get(pool, key).await {
+                Ok(Some(mut result)) => {
+                    info!("♻️ Reusing cached Claude result for resource_id: {} (hash: {})", resource_id, key.content_hash);
+                    result.resource_id = resource_id;
+                    result.created_at = Utc::now();
+                    result.processing_notes.push(format!("Reused cached Claude result (prompt v{}, {})", key.prompt_version, key.model));
+                    return Ok(result);
+                },
+                Ok(None) => debug!("🔍 No cached result for resource_id: {}", resource_id),
+                Err(e) => warn!("⚠️ Summary cache lookup failed for resource_id {}: {}", resource_id, e),
+            }
+        }
+        
+        let response = self.complete(prompt, max_tokens).await?;
+        if let Some(dir) = &self.recording {
+            replay::record(dir, summary_type, &response);
+        }
+        let result = Self::parse_ai_response(response, summary_type, resource_id)?;
+        
+        if let Some(pool) = &self.cache {
+            if !result.processing_notes.iter().any(|note| note == UNPARSED_NOTE) {
+                if let Err(e) = summary_cache::put(pool, key, &result).await {
+                    warn!("⚠️ Failed to cache Claude result for resource_id {}: {}", resource_id, e);
+                }
+            }
+        }
+        Ok(result)
+    }
+    
+    /// Claude's raw reply to the prompt, within the daily budget; never cached (tender Q&A uses this directly).
+    /// Contact details are redacted from the prompt first (see `pii`)
+    pub async fn complete(&self, prompt: &str, max_tokens: i32) -> Result<String> {
+        let prompt = &pii::redact(prompt);
+        
+        // Unlike the cache, a failed budget check stops the call - the budget is the point
+        if let Some((pool, limit)) = &self.budget {
+            if !claude_budget::try_reserve(pool, *limit).await? {
+                return Err(BudgetExhausted { reason: format!("daily limit of {} calls reached", limit) }.into());
+            }
+        }
+        
+        // Overloaded/timeout errors usually clear within seconds; running out of credit doesn't.
+        // The retries share one time limit, so a hung API fails the call rather than the lambda
+        let policy = Policy::new("claude")
+            .base_delay(Duration::from_secs(2))
+            .max_delay(Duration::from_secs(20))
+            .retry_if(|e: &anyhow::Error| !e.is::<BudgetExhausted>());
+        let call = policy.run(|| self.call_claude(prompt, max_tokens));
+        timeouts::within(Call::Claude, self.claude_timeout, call).await?
     }
     
     /// Call Claude API
     async fn call_claude(&self, prompt: &str, max_tokens: i32) -> Result<String> {
         debug!("🔗 Calling Claude API with prompt length: {}", prompt.len());
+        fault_injection::check(Fault::Claude)?;
         
         let request = anthropic_sdk::Client::new()
             .version("2023-06-01")
             .auth(&self.api_key)
-            .model("claude-sonnet-4-20250514")
+            .model(&self.model)
             .messages(&json!([
                 {"role": "user", "content": prompt}
             ]))
@@ -221,7 +514,14 @@ Format as JSON with fields: summary, key_points (array), recommendation, confide
                 }
             })
             .await
-            .map_err(|e| anyhow::anyhow!("Failed to execute Claude request: {}", e))?;
+            .map_err(|e| {
+                let error = e.to_string();
+                if claude_budget::is_out_of_credit(&error) {
+                    anyhow::Error::new(BudgetExhausted { reason: error })
+                } else {
+                    anyhow::anyhow!("Failed to execute Claude request: {}", error)
+                }
+            })?;
 
         let response_text = Arc::try_unwrap(message).unwrap().into_inner().unwrap();
         
@@ -229,8 +529,8 @@ Format as JSON with fields: summary, key_points (array), recommendation, confide
         Ok(response_text)
     }
     
-    /// Parse AI response into structured result
-    fn parse_ai_response(&self, response: String, summary_type: &str, resource_id: i64) -> Result<AISummaryResult> {
+    /// Parse AI response into structured result; checked against recorded responses by `replay`
+    pub fn parse_ai_response(response: String, summary_type: &str, resource_id: i64) -> Result<AISummaryResult> {
         debug!("🔍 Parsing Claude response for resource_id: {}", resource_id);
         
         // LOG THE COMPLETE RESPONSE FOR DEBUGGING
@@ -286,6 +586,9 @@ Format as JSON with fields: summary, key_points (array), recommendation, confide
                 }
                 
                 let summary = json_response["summary"].as_str().unwrap_or(&response).to_string();
+                let one_liner = json_response["one_liner"].as_str()
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty());
                 let key_points = json_response["key_points"]
                     .as_array()
                     .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
@@ -342,13 +645,16 @@ Format as JSON with fields: summary, key_points (array), recommendation, confide
                 
                 Ok(AISummaryResult {
                     resource_id,
+                    tenant_id: DEFAULT_TENANT.to_string(),
                     summary_type: summary_type.to_string(),
                     ai_summary: summary,
+                    one_liner,
                     key_points,
                     recommendation,
                     confidence_assessment,
                     processing_notes,
                     created_at: Utc::now(),
+                    quality_score: None,
                 })
             },
             Err(parse_error) => {
@@ -362,13 +668,16 @@ Format as JSON with fields: summary, key_points (array), recommendation, confide
                 
                 Ok(AISummaryResult {
                     resource_id,
+                    tenant_id: DEFAULT_TENANT.to_string(),
                     summary_type: summary_type.to_string(),
                     ai_summary: response.clone(),
+                    one_liner: None,
                     key_points: vec!["Claude response was in plain text format".to_string()],
                     recommendation: extracted_recommendation,
                     confidence_assessment: "Unknown - response format issue".to_string(),
-                    processing_notes: vec!["Claude response could not be parsed as JSON".to_string()],
+                    processing_notes: vec![UNPARSED_NOTE.to_string()],
                     created_at: Utc::now(),
+                    quality_score: None,
                 })
             }
         }
@@ -467,6 +776,6 @@ Format as JSON with fields: summary, key_points (array), recommendation, confide
         }
         
         // Default fallback
-        "Review the summary for recommendations".to_string()
+        PARSE_FALLBACK_RECOMMENDATION.to_string()
     }
 }
@@ -0,0 +1,234 @@
+//! Long tender documents read in parts, so a summary isn't limited to what fits in one prompt.
+//!
+//! A PDF longer than `CHUNKED_ABOVE` bytes is split into parts of about `CHUNK_BYTES`, each
+//! condensed into notes by its own Claude call, and every tenant's summary is then written
+//! from the notes instead of the truncated text. Reading a large document can take longer
+//! than the lambda may run, so each part's notes are stored in `ai_summary_chunks` as soon
+//! as Claude returns them. When the invocation's deadline gets close, ai_summary queues a
+//! continuation message and the next invocation reads only the parts still missing; a
+//! timeout or retry resumes the same way.
+//!
+//! Notes are keyed by a hash of the document (with the prompt version and model), not by
+//! tenant, so every tenant shares one read. Like the summary cache they are kept, so a
+//! re-advertised tender with the same document isn't read again.
+
+use crate::ai_service::{MODEL, PROMPT_VERSION};
+use crate::summary_cache::CacheKey;
+use anyhow::Result;
+use environment::Environment;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Documents up to this length (bytes) go into the prompt as they are, truncated to fit
+pub const CHUNKED_ABOVE: usize = 30_000;
+/// Target length of each part (bytes)
+pub const CHUNK_BYTES: usize = 20_000;
+/// Parts read at most; text past the last one is left out, as truncation did before
+pub const MAX_CHUNKS: usize = 12;
+/// Reply limit for one part's notes
+pub const NOTES_MAX_TOKENS: i32 = 800;
+
+const DEFAULT_TIME_MARGIN_SECS: u64 = 120;
+
+/// Reading paused at the invocation's deadline; the stored notes are picked up by a
+/// continuation message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContinuationNeeded {
+    pub read: usize,
+    pub total: usize,
+}
+
+impl fmt::Display for ContinuationNeeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Document read paused near the lambda deadline ({} of {} parts read)",
+            self.read, self.total
+        )
+    }
+}
+
+impl std::error::Error for ContinuationNeeded {}
+
+/// Time left before the deadline at which no further part is started: one part's Claude
+/// call with retries, plus the summaries written from the notes
+pub fn time_margin_from_env() -> Duration {
+    let secs = std::env::var("AI_SUMMARY_TIME_MARGIN_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_TIME_MARGIN_SECS);
+    Duration::from_secs(secs)
+}
+
+/// The text in at most `max_chunks` parts of about `chunk_bytes`, each ending at a
+/// paragraph, line or word break where there is one in its second half
+pub fn split(text: &str, chunk_bytes: usize, max_chunks: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() && chunks.len() < max_chunks {
+        if rest.len() <= chunk_bytes {
+            chunks.push(rest);
+            break;
+        }
+
+        let mut end = chunk_bytes;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let cut = ["\n\n", "\n", " "]
+            .iter()
+            .find_map(|separator| rest[..end].rfind(separator).filter(|at| *at >= end / 2))
+            .unwrap_or(end);
+        chunks.push(rest[..cut].trim_end());
+        rest = rest[cut..].trim_start();
+    }
+    chunks
+}
+
+/// Key of the document's stored notes
+pub fn document_hash(text: &str) -> String {
+    CacheKey::new(PROMPT_VERSION, MODEL, &["CHUNK_NOTES", text]).content_hash
+}
+
+/// Prompt for the notes on one part (`index` counts from 0)
+pub fn notes_prompt(title: &str, index: usize, total: usize, part: &str) -> String {
+    format!(
+        r#"You are reading part {} of {} of the tender document for "{}".
+
+Write concise notes on this part only, for an analyst who will decide whether to bid without seeing the document: the scope of work and deliverables, the services or goods required, eligibility and qualification requirements, evaluation criteria, contract value, duration and key dates. Quote figures and requirements exactly. If this part holds nothing of the kind, say so in one line.
+
+Reply with the notes only, as plain-text bullet points.
+
+PART {} OF {}:
+{}"#,
+        index + 1,
+        total,
+        title,
+        index + 1,
+        total,
+        part
+    )
+}
+
+/// Every part's notes in order, labelled, for the summary prompt
+pub fn combine(notes: &[String]) -> String {
+    notes
+        .iter()
+        .enumerate()
+        .map(|(index, part)| format!("Part {} of {}:\n{}", index + 1, notes.len(), part))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+pub async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "ai_summary_chunks", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS ai_summary_chunks (
+                content_hash TEXT NOT NULL,
+                chunk_index INTEGER NOT NULL,
+                chunk_count INTEGER NOT NULL,
+                resource_id BIGINT NOT NULL,
+                notes TEXT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (content_hash, chunk_index)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "ai_summary_chunks").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Notes already stored for the document, by part index
+pub async fn load(pool: &PgPool, content_hash: &str) -> Result<HashMap<usize, String>> {
+    let rows =
+        sqlx::query("SELECT chunk_index, notes FROM ai_summary_chunks WHERE content_hash = $1")
+            .bind(content_hash)
+            .fetch_all(pool)
+            .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| (row.get::<i32, _>("chunk_index") as usize, row.get("notes")))
+        .collect())
+}
+
+/// Store one part's notes; `resource_id` records the tender they were read for
+pub async fn store(
+    pool: &PgPool,
+    content_hash: &str,
+    resource_id: i64,
+    index: usize,
+    total: usize,
+    notes: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO ai_summary_chunks (content_hash, chunk_index, chunk_count, resource_id, notes)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (content_hash, chunk_index)
+        DO UPDATE SET notes = EXCLUDED.notes, created_at = NOW()
+        "#,
+    )
+    .bind(content_hash)
+    .bind(index as i32)
+    .bind(total as i32)
+    .bind(resource_id)
+    .bind(notes)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_prefers_breaks() {
+        let text = "First paragraph here.\n\nSecond paragraph, a little longer.\nThird line";
+        let chunks = split(text, 30, 10);
+        assert_eq!(
+            chunks,
+            vec![
+                "First paragraph here.",
+                "Second paragraph, a little",
+                "longer.\nThird line"
+            ]
+        );
+        assert_eq!(split("  short  ", 30, 10), vec!["short"]);
+        assert!(split("   ", 30, 10).is_empty());
+    }
+
+    #[test]
+    fn test_split_limits_and_char_boundaries() {
+        let text = "é".repeat(50);
+        let chunks = split(&text, 15, 3);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.len() <= 15 && !chunk.is_empty()));
+        assert_eq!(chunks[0], "é".repeat(7));
+    }
+
+    #[test]
+    fn test_combine_labels_parts() {
+        let notes = vec![
+            "- Scope: payroll".to_string(),
+            "- Nothing relevant".to_string(),
+        ];
+        assert_eq!(
+            combine(&notes),
+            "Part 1 of 2:\n- Scope: payroll\n\nPart 2 of 2:\n- Nothing relevant"
+        );
+        assert_ne!(document_hash("a"), document_hash("b"));
+    }
+}
@@ -0,0 +1,89 @@
+//! Daily cap on Claude calls, counted in `claude_usage`.
+//!
+//! Set with `CLAUDE_DAILY_CALL_BUDGET`; unlimited when unset. Cache hits don't count.
+//! Once the day's calls are used up (or Anthropic reports the account is out of credit)
+//! summaries fall back to the extractive `FallbackSummarizer`.
+
+use anyhow::Result;
+use environment::Environment;
+use sqlx::PgPool;
+use std::fmt;
+
+/// The Claude budget is used up; callers fall back to a non-LLM summary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetExhausted {
+    pub reason: String,
+}
+
+impl fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Claude budget exhausted: {}", self.reason)
+    }
+}
+
+impl std::error::Error for BudgetExhausted {}
+
+/// Anthropic error text meaning the account can't make calls until it is topped up
+const OUT_OF_CREDIT_MARKERS: &[&str] = &["credit balance is too low", "billing"];
+
+/// Whether a Claude error means the account budget, rather than the request, is the problem
+pub fn is_out_of_credit(error: &str) -> bool {
+    let lower = error.to_lowercase();
+    OUT_OF_CREDIT_MARKERS.iter().any(|m| lower.contains(m))
+}
+
+pub fn daily_limit_from_env() -> Option<i32> {
+    std::env::var("CLAUDE_DAILY_CALL_BUDGET")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+}
+
+pub async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "claude_usage", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS claude_usage (
+                day DATE PRIMARY KEY,
+                calls INTEGER NOT NULL DEFAULT 0
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "claude_usage").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Count one call against today's budget; false (and nothing counted) once `limit` is reached
+pub async fn try_reserve(pool: &PgPool, limit: i32) -> Result<bool> {
+    let reserved = sqlx::query_scalar::<_, i32>(
+        r#"
+        INSERT INTO claude_usage (day, calls)
+        SELECT CURRENT_DATE, 1 WHERE $1 > 0
+        ON CONFLICT (day) DO UPDATE SET calls = claude_usage.calls + 1
+        WHERE claude_usage.calls < $1
+        RETURNING calls
+        "#,
+    )
+    .bind(limit)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(reserved.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_out_of_credit_errors() {
+        assert!(is_out_of_credit(
+            "Your credit balance is too low to access the Anthropic API"
+        ));
+        assert!(!is_out_of_credit("overloaded_error: Overloaded"));
+    }
+}
@@ -1,6 +1,13 @@
-use crate::types::{Config, PdfContent, TenderRecord};
+use crate::tenants::{CompanyProfile, WatchType, WatchlistEntry};
+use crate::ticket_service::Ticket;
+use crate::types::{
+    AuthorityHistory, CodeDescription, Config, PdfContent, SimilarTender, TenderContext,
+    TenderRecord,
+};
 use anyhow::Result;
-use chrono;
+use db::rows::{PdfContentRow, TenderRow};
+use environment::Environment;
+use fault_injection::Fault;
 use sqlx::{Pool, Postgres, Row};
 use tracing::{debug, info, warn};
 
@@ -12,49 +19,30 @@ pub struct Database {
 impl Database {
     /// Create new database connection
     pub async fn new(config: &Config) -> Result<Self> {
-        let pool = sqlx::postgres::PgPoolOptions::new()
-            .max_connections(5)
-            .connect(&config.database_url)
-            .await?;
+        let pool = db::connect(&config.database_url, 5).await?;
 
         info!("✅ Database connection established");
         Ok(Self { pool })
     }
 
+    pub fn pool(&self) -> &Pool<Postgres> {
+        &self.pool
+    }
+
     /// Get complete PDF content from pdf_content table
     pub async fn get_pdf_content(&self, resource_id: i64) -> Result<Option<PdfContent>> {
         debug!("🔍 Fetching PDF content for resource_id: {}", resource_id);
 
-        let row = sqlx::query(
-            r#"
-            SELECT
-                resource_id,
-                pdf_text,
-                detected_codes,
-                codes_count,
-                extraction_timestamp
-            FROM pdf_content
-            WHERE resource_id = $1
-            "#,
-        )
+        let row: Option<PdfContentRow> = sqlx::query_as(&format!(
+            "{} WHERE resource_id = $1",
+            PdfContentRow::select()
+        ))
         .bind(resource_id)
         .fetch_optional(&self.pool)
         .await?;
 
         if let Some(row) = row {
-            let detected_codes: Vec<String> = row
-                .get::<Option<Vec<String>>, _>("detected_codes")
-                .unwrap_or_default();
-
-            let pdf_content = PdfContent {
-                resource_id: row.get("resource_id"),
-                pdf_text: row.get("pdf_text"),
-                detected_codes,
-                codes_count: row.get::<Option<i32>, _>("codes_count").unwrap_or(0),
-                extraction_timestamp: row
-                    .get::<chrono::NaiveDateTime, _>("extraction_timestamp")
-                    .and_utc(),
-            };
+            let pdf_content = PdfContent::from(row);
 
             info!(
                 "✅ Found PDF content for resource_id: {}, text length: {}",
@@ -72,103 +60,143 @@ impl Database {
     pub async fn get_tender_record(&self, resource_id: i64) -> Result<Option<TenderRecord>> {
         debug!("🔍 Fetching tender record for resource_id: {}", resource_id);
 
+        let row: Option<TenderRow> =
+            sqlx::query_as(&format!("{} WHERE resource_id = $1", TenderRow::select()))
+                .bind(resource_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        if let Some(row) = row {
+            let tender = TenderRecord::from(row);
+
+            info!("✅ Found tender record for resource_id: {}", resource_id);
+            Ok(Some(tender))
+        } else {
+            warn!("⚠️ No tender record found for resource_id: {}", resource_id);
+            Ok(None)
+        }
+    }
+
+    /// Clarification question deadline pdf_processing found in the tender's PDF
+    pub async fn get_clarification_deadline(
+        &self,
+        resource_id: i64,
+    ) -> Result<Option<chrono::NaiveDateTime>> {
+        let deadline = sqlx::query_scalar(
+            "SELECT clarification_deadline FROM pdf_content WHERE resource_id = $1",
+        )
+        .bind(resource_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(deadline.flatten())
+    }
+
+    /// pdf_processing's quality score for the tender's extracted text
+    pub async fn get_extraction_quality(&self, resource_id: i64) -> Result<Option<f32>> {
+        let quality =
+            sqlx::query_scalar("SELECT extraction_quality FROM pdf_content WHERE resource_id = $1")
+                .bind(resource_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(quality.flatten())
+    }
+
+    /// Code descriptions, authority history and similar tenders for the Claude prompt
+    pub async fn get_tender_context(
+        &self,
+        tender: &TenderRecord,
+        detected_codes: &[String],
+    ) -> Result<TenderContext> {
+        let codes = if detected_codes.is_empty() {
+            Vec::new()
+        } else {
+            let rows = sqlx::query(
+                "SELECT code, description FROM detection_codes WHERE code = ANY($1) ORDER BY code",
+            )
+            .bind(detected_codes)
+            .fetch_all(&self.pool)
+            .await?;
+
+            // Keep codes the table doesn't know about, just without a description
+            detected_codes
+                .iter()
+                .map(|code| CodeDescription {
+                    code: code.clone(),
+                    description: rows
+                        .iter()
+                        .find(|row| row.get::<String, _>("code") == *code)
+                        .and_then(|row| row.get("description")),
+                })
+                .collect()
+        };
+
         let row = sqlx::query(
             r#"
             SELECT
-                resource_id,
-                title,
-                ca as contracting_authority,
-                info,
-                published,
-                deadline,
-                procedure,
-                status,
-                pdf_url,
-                awarddate,
-                value,
-                cycle,
-                bid,
-                ml_processed,
-                ml_bid,
-                ml_confidence,
-                ml_reasoning,
-                ml_status
+                COUNT(*) AS tenders,
+                COUNT(*) FILTER (WHERE bid = 1) AS bids,
+                MAX(published) AS last_published
             FROM tender_records
-            WHERE resource_id = $1
+            WHERE ca = $1 AND resource_id <> $2
             "#,
         )
-        .bind(resource_id)
-        .fetch_optional(&self.pool)
+        .bind(&tender.contracting_authority)
+        .bind(tender.resource_id)
+        .fetch_one(&self.pool)
         .await?;
+        let tenders: i64 = row.get("tenders");
+        let authority = (tenders > 0).then(|| AuthorityHistory {
+            tenders,
+            bids: row.get("bids"),
+            last_published: row.get("last_published"),
+        });
 
-        if let Some(row) = row {
-            let tender = TenderRecord {
+        let rows = sqlx::query(
+            r#"
+            SELECT resource_id, title, bid
+            FROM tender_records
+            WHERE ca = $1 AND resource_id <> $2 AND bid IS NOT NULL
+            ORDER BY published DESC NULLS LAST
+            LIMIT 5
+            "#,
+        )
+        .bind(&tender.contracting_authority)
+        .bind(tender.resource_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let similar = rows
+            .iter()
+            .map(|row| SimilarTender {
                 resource_id: row.get("resource_id"),
                 title: row.get("title"),
-                contracting_authority: row.get("contracting_authority"),
-                info: row.get("info"),
-                published: row.get("published"),
-                deadline: row.get("deadline"),
-                procedure: row.get("procedure"),
-                status: row.get("status"),
-                pdf_url: row.get("pdf_url"),
-                awarddate: row.get("awarddate"),
-                value: row.get("value"),
-                cycle: row.get("cycle"),
                 bid: row.get("bid"),
-                pdf_content: None,      // Will be populated separately if needed
-                detected_codes: None,   // Will be populated from pdf_content table
-                codes_count: None,      // Will be populated from pdf_content table
-                processing_stage: None, // Runtime field, not stored in database
-                ml_processed: row.get("ml_processed"),
-                ml_bid: row.get("ml_bid"),
-                ml_confidence: row.get("ml_confidence"),
-                ml_reasoning: row.get("ml_reasoning"),
-                ml_status: row.get("ml_status"),
-            };
+            })
+            .collect();
 
-            info!("✅ Found tender record for resource_id: {}", resource_id);
-            Ok(Some(tender))
-        } else {
-            warn!("⚠️ No tender record found for resource_id: {}", resource_id);
-            Ok(None)
-        }
+        Ok(TenderContext {
+            codes,
+            authority,
+            similar,
+            document_notes: None,
+        })
     }
 
     /// Store AI summary result
     pub async fn store_ai_summary(&self, summary: &crate::types::AISummaryResult) -> Result<()> {
         info!(
-            "💾 Storing AI summary for resource_id: {}",
-            summary.resource_id
+            "💾 Storing AI summary for resource_id: {} (tenant: {})",
+            summary.resource_id, summary.tenant_id
         );
+        fault_injection::check(Fault::DbWrite)?;
 
-        // Create ai_summaries table if it doesn't exist
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS ai_summaries (
-                resource_id BIGINT PRIMARY KEY,
-                summary_type TEXT NOT NULL,
-                ai_summary TEXT NOT NULL,
-                key_points JSONB NOT NULL,
-                recommendation TEXT NOT NULL,
-                confidence_assessment TEXT NOT NULL,
-                processing_notes JSONB NOT NULL,
-                created_at TIMESTAMP WITH TIME ZONE NOT NULL,
-                updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Insert or update summary
+        // Insert or update summary (one row per tender per tenant)
         sqlx::query(
             r#"
             INSERT INTO ai_summaries
-            (resource_id, summary_type, ai_summary, key_points, recommendation,
-             confidence_assessment, processing_notes, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-            ON CONFLICT (resource_id)
+            (resource_id, tenant_id, summary_type, ai_summary, key_points, recommendation,
+             confidence_assessment, processing_notes, created_at, quality_score)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (resource_id, tenant_id)
             DO UPDATE SET
                 summary_type = EXCLUDED.summary_type,
                 ai_summary = EXCLUDED.ai_summary,
@@ -176,10 +204,12 @@ impl Database {
                 recommendation = EXCLUDED.recommendation,
                 confidence_assessment = EXCLUDED.confidence_assessment,
                 processing_notes = EXCLUDED.processing_notes,
+                quality_score = EXCLUDED.quality_score,
                 updated_at = CURRENT_TIMESTAMP
             "#,
         )
         .bind(summary.resource_id)
+        .bind(&summary.tenant_id)
         .bind(&summary.summary_type)
         .bind(&summary.ai_summary)
         .bind(serde_json::to_value(&summary.key_points)?)
@@ -187,6 +217,7 @@ impl Database {
         .bind(&summary.confidence_assessment)
         .bind(serde_json::to_value(&summary.processing_notes)?)
         .bind(summary.created_at)
+        .bind(summary.quality_score)
         .execute(&self.pool)
         .await?;
 
@@ -196,4 +227,234 @@ impl Database {
         );
         Ok(())
     }
+
+    /// Create the ai_summaries and tenant tables, migrating ai_summaries to one row per tenant
+    pub async fn ensure_tenant_tables(&self) -> Result<()> {
+        db::ensure_schema(&self.pool, "ai_summary_tenants", 2, async {
+            sqlx::query(
+                r#"
+                CREATE TABLE IF NOT EXISTS ai_summaries (
+                    resource_id BIGINT NOT NULL,
+                    tenant_id TEXT NOT NULL DEFAULT 'default',
+                    summary_type TEXT NOT NULL,
+                    ai_summary TEXT NOT NULL,
+                    key_points JSONB NOT NULL,
+                    recommendation TEXT NOT NULL,
+                    confidence_assessment TEXT NOT NULL,
+                    processing_notes JSONB NOT NULL,
+                    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+                    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+                    PRIMARY KEY (resource_id, tenant_id)
+                )
+                "#,
+            )
+            .execute(&self.pool)
+            .await?;
+            Environment::ensure_environment_column(&self.pool, "ai_summaries").await?;
+
+            // Tables created before tenants were keyed on resource_id alone
+            sqlx::query(
+                "ALTER TABLE ai_summaries ADD COLUMN IF NOT EXISTS tenant_id TEXT NOT NULL DEFAULT 'default'",
+            )
+            .execute(&self.pool)
+            .await?;
+            // summary_quality's score; NULL for summaries written before it
+            sqlx::query("ALTER TABLE ai_summaries ADD COLUMN IF NOT EXISTS quality_score REAL")
+                .execute(&self.pool)
+                .await?;
+            sqlx::raw_sql(
+                r#"
+                DO $$
+                BEGIN
+                    IF (SELECT array_length(conkey, 1) FROM pg_constraint
+                        WHERE conrelid = 'ai_summaries'::regclass AND contype = 'p') = 1 THEN
+                        ALTER TABLE ai_summaries DROP CONSTRAINT ai_summaries_pkey;
+                        ALTER TABLE ai_summaries ADD PRIMARY KEY (resource_id, tenant_id);
+                    END IF;
+                END $$;
+                "#,
+            )
+            .execute(&self.pool)
+            .await?;
+
+            sqlx::query(
+                r#"
+                CREATE TABLE IF NOT EXISTS tenants (
+                    tenant_id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    description TEXT NOT NULL,
+                    scope TEXT NOT NULL,
+                    exclusions TEXT NOT NULL DEFAULT '',
+                    notification_emails TEXT[] NOT NULL DEFAULT '{}',
+                    min_value NUMERIC,
+                    active BOOLEAN NOT NULL DEFAULT TRUE,
+                    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+                )
+                "#,
+            )
+            .execute(&self.pool)
+            .await?;
+
+            sqlx::query(
+                r#"
+                CREATE TABLE IF NOT EXISTS tenant_watchlists (
+                    id BIGSERIAL PRIMARY KEY,
+                    tenant_id TEXT NOT NULL REFERENCES tenants(tenant_id) ON DELETE CASCADE,
+                    watch_type TEXT NOT NULL,
+                    value TEXT NOT NULL,
+                    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+                    UNIQUE (tenant_id, watch_type, value)
+                )
+                "#,
+            )
+            .execute(&self.pool)
+            .await?;
+
+            sqlx::query(
+                "ALTER TABLE tenant_watchlists ADD COLUMN IF NOT EXISTS always_notify BOOLEAN NOT NULL DEFAULT FALSE",
+            )
+            .execute(&self.pool)
+            .await?;
+
+            anyhow::Ok(())
+        })
+        .await
+    }
+
+    /// Active company profiles with their watchlists; the built-in default profile when none are configured
+    pub async fn load_profiles(&self) -> Result<Vec<CompanyProfile>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT tenant_id, name, description, scope, exclusions, notification_emails, min_value
+            FROM tenants
+            WHERE active
+            ORDER BY tenant_id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        if rows.is_empty() {
+            debug!("No tenants configured - using the default profile");
+            return Ok(vec![CompanyProfile::default_profile()]);
+        }
+
+        let watch_rows = sqlx::query(
+            "SELECT tenant_id, watch_type, value, always_notify FROM tenant_watchlists ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let profiles: Vec<CompanyProfile> = rows
+            .iter()
+            .map(|row| {
+                let tenant_id: String = row.get("tenant_id");
+                let watchlist = watch_rows
+                    .iter()
+                    .filter(|w| w.get::<String, _>("tenant_id") == tenant_id)
+                    .filter_map(|w| {
+                        let watch_type: String = w.get("watch_type");
+                        match WatchType::parse(&watch_type) {
+                            Some(watch_type) => Some(WatchlistEntry {
+                                watch_type,
+                                value: w.get("value"),
+                                always_notify: w.get("always_notify"),
+                            }),
+                            None => {
+                                warn!(
+                                    "⚠️ Unknown watch_type '{}' for tenant {}",
+                                    watch_type, tenant_id
+                                );
+                                None
+                            }
+                        }
+                    })
+                    .collect();
+
+                CompanyProfile {
+                    name: row.get("name"),
+                    description: row.get("description"),
+                    scope: row.get("scope"),
+                    exclusions: row.get("exclusions"),
+                    notification_emails: row.get("notification_emails"),
+                    min_value: row.get("min_value"),
+                    watchlist,
+                    tenant_id,
+                }
+            })
+            .collect();
+
+        info!("🏢 Loaded {} tenant profiles", profiles.len());
+        Ok(profiles)
+    }
+
+    /// Add the ticket columns to tender_records (used when ticketing is enabled)
+    pub async fn ensure_ticket_columns(&self) -> Result<()> {
+        db::ensure_schema(&self.pool, "ai_summary_tickets", 1, async {
+            for query in [
+                "ALTER TABLE tender_records ADD COLUMN IF NOT EXISTS ticket_provider TEXT",
+                "ALTER TABLE tender_records ADD COLUMN IF NOT EXISTS ticket_key TEXT",
+                "ALTER TABLE tender_records ADD COLUMN IF NOT EXISTS ticket_url TEXT",
+                "ALTER TABLE tender_records ADD COLUMN IF NOT EXISTS ticket_created_at TIMESTAMP WITH TIME ZONE",
+            ] {
+                sqlx::query(query).execute(&self.pool).await?;
+            }
+            anyhow::Ok(())
+        })
+        .await
+    }
+
+    /// Ticket previously raised for a tender, if any
+    pub async fn get_ticket(&self, resource_id: i64) -> Result<Option<Ticket>> {
+        let row =
+            sqlx::query("SELECT ticket_key, ticket_url FROM tender_records WHERE resource_id = $1")
+                .bind(resource_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(row.and_then(|r| {
+            let key: Option<String> = r.get("ticket_key");
+            let url: Option<String> = r.get("ticket_url");
+            key.map(|key| Ticket {
+                key,
+                url: url.unwrap_or_default(),
+            })
+        }))
+    }
+
+    /// Number of tickets raised so far (drives assignee rotation)
+    pub async fn ticket_count(&self) -> Result<i64> {
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM tender_records WHERE ticket_key IS NOT NULL")
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(count)
+    }
+
+    /// Store the ticket key/link on the tender
+    pub async fn store_ticket(
+        &self,
+        resource_id: i64,
+        provider: &str,
+        ticket: &Ticket,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE tender_records
+            SET ticket_provider = $2,
+                ticket_key = $3,
+                ticket_url = $4,
+                ticket_created_at = NOW()
+            WHERE resource_id = $1
+            "#,
+        )
+        .bind(resource_id)
+        .bind(provider)
+        .bind(&ticket.key)
+        .bind(&ticket.url)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,453 @@
+//! Whether a summarised tender is notified, and at what priority.
+//!
+//! Pure functions over the Claude result and ML prediction, so the rules can be tested
+//! without SQS or Claude. NotificationService logs and acts on the outcome. A
+//! recommendation forced by hand (`tender_overrides`) is the final word over all of them.
+
+use crate::ai_service::UNPARSED_NOTE;
+use crate::second_opinion::DISAGREES_NOTE;
+use crate::types::{AISummaryResult, MLPredictionResult};
+use tender_overrides::Forced;
+
+/// Recommendation `parse_ai_response` uses when Claude's reply wasn't JSON
+pub const PARSE_FALLBACK_RECOMMENDATION: &str = "Review the summary for recommendations";
+/// Summary type of the early-interest assessment given to PINs and DPS notices
+pub const EARLY_INTEREST: &str = "EARLY_INTEREST";
+/// Processing note recording which watch rule forced a notification (read back for the email)
+pub const WATCH_RULE_NOTE: &str = "📌 WATCH RULE: ";
+/// Processing note recording a recommendation forced by hand
+pub const OVERRIDE_NOTE: &str = "✋ MANUAL OVERRIDE: ";
+
+/// Why a tender was (or wasn't) notified
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    /// The tenant forced the recommendation to BID (`bid`) or NO BID
+    Override { bid: bool },
+    /// The tenant has an always-notify watch rule for the buyer
+    WatchRule,
+    /// Claude's reply couldn't be parsed, so the ML prediction decides
+    ParseFallback { ml_bid: bool },
+    /// Claude is the final arbiter; `bid` is also set by REGISTER INTEREST on early-interest notices
+    Claude { bid: bool },
+    /// Claude recommended it, but the second opinion on a borderline ML score didn't
+    Disputed,
+}
+
+impl Reason {
+    pub fn notify(&self) -> bool {
+        match self {
+            Reason::Override { bid } => *bid,
+            Reason::WatchRule => true,
+            Reason::ParseFallback { ml_bid } => *ml_bid,
+            Reason::Claude { bid } => *bid,
+            Reason::Disputed => false,
+        }
+    }
+
+    /// Recorded in the decision audit trail
+    pub fn name(&self) -> &'static str {
+        match self {
+            Reason::Override { .. } => "manual_override",
+            Reason::WatchRule => "watch_rule",
+            Reason::ParseFallback { .. } => "parse_fallback",
+            Reason::Claude { .. } => "claude",
+            Reason::Disputed => "second_opinion_disputed",
+        }
+    }
+}
+
+/// Flags read back from a summary's processing notes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Indicators {
+    pub claude_override: bool,
+    pub non_it: bool,
+    pub watchlist_match: bool,
+    pub watch_rule: bool,
+}
+
+impl Indicators {
+    pub fn from_notes(notes: &[String], watch_rule_note: &str) -> Self {
+        let contains = |marker: &str| notes.iter().any(|note| note.contains(marker));
+        let starts_with = |prefix: &str| notes.iter().any(|note| note.starts_with(prefix));
+        Self {
+            claude_override: contains("OVERRODE") || contains("overrode"),
+            non_it: contains("NON-IT INDICATOR"),
+            watchlist_match: starts_with("👀 WATCHLIST MATCH: "),
+            watch_rule: starts_with(watch_rule_note),
+        }
+    }
+}
+
+/// "BID" but not "NO BID", case-insensitively
+pub fn recommends_bid(recommendation: &str) -> bool {
+    let lower = recommendation.to_lowercase();
+    lower.contains("bid") && !lower.contains("no bid")
+}
+
+/// "REGISTER INTEREST" but not "NO INTEREST", the early-interest prompt's two answers
+pub fn recommends_interest(recommendation: &str) -> bool {
+    let lower = recommendation.to_lowercase();
+    lower.contains("register interest") && !lower.contains("no interest")
+}
+
+pub fn is_parse_fallback(summary: &AISummaryResult) -> bool {
+    summary.recommendation == PARSE_FALLBACK_RECOMMENDATION
+        && summary
+            .processing_notes
+            .iter()
+            .any(|note| note == UNPARSED_NOTE)
+}
+
+/// Whether the summary says go ahead: BID, or REGISTER INTEREST on an early-interest notice
+pub fn recommends(summary: &AISummaryResult) -> bool {
+    if summary.summary_type == EARLY_INTEREST {
+        recommends_interest(&summary.recommendation)
+    } else {
+        recommends_bid(&summary.recommendation)
+    }
+}
+
+/// Claude-first: a forced recommendation decides outright, a watch rule always notifies,
+/// an unparseable reply defers to ML, otherwise only an explicit BID (or REGISTER INTEREST
+/// on an early-interest notice) from Claude notifies - unless a second opinion disagreed
+pub fn notification_reason(
+    summary: &AISummaryResult,
+    ml_prediction: &MLPredictionResult,
+    watch_rule: bool,
+    forced: Option<Forced>,
+) -> Reason {
+    let disputed = summary
+        .processing_notes
+        .iter()
+        .any(|note| note.starts_with(DISAGREES_NOTE));
+    if let Some(forced) = forced {
+        Reason::Override {
+            bid: forced == Forced::Bid,
+        }
+    } else if watch_rule {
+        Reason::WatchRule
+    } else if is_parse_fallback(summary) {
+        Reason::ParseFallback {
+            ml_bid: ml_prediction.should_bid,
+        }
+    } else if recommends(summary) && disputed {
+        Reason::Disputed
+    } else {
+        Reason::Claude {
+            bid: recommends(summary),
+        }
+    }
+}
+
+/// Notification priority shown in the email
+pub fn priority(indicators: Indicators, ml_bid: bool, summary_type: &str) -> &'static str {
+    if summary_type == EARLY_INTEREST {
+        // Nothing to bid on yet, however good the fit
+        if indicators.watchlist_match {
+            "HIGH"
+        } else {
+            "NORMAL"
+        }
+    } else if indicators.claude_override && ml_bid {
+        // Rare now that NO BID results aren't notified
+        "CRITICAL"
+    } else if indicators.watchlist_match || (ml_bid && !indicators.non_it) {
+        "URGENT"
+    } else if indicators.non_it {
+        "MEDIUM"
+    } else if summary_type == "FULL_PDF" {
+        "HIGH"
+    } else {
+        "NORMAL"
+    }
+}
+
+pub fn action_required(indicators: Indicators, ml_bid: bool, summary_type: &str) -> &'static str {
+    if summary_type == EARLY_INTEREST {
+        "Early market notice - register interest or join the purchasing system, no bid decision needed yet"
+    } else if indicators.watch_rule {
+        "Watched contracting authority - sent regardless of ML/AI verdict, review the recommendation"
+    } else if indicators.claude_override && ml_bid {
+        "🚨 CRITICAL: Claude AI OVERRODE ML bid recommendation - review immediately for accuracy"
+    } else if ml_bid {
+        "REVIEW IMMEDIATELY: ML recommends bidding - Claude analysis confirms opportunity"
+    } else if indicators.non_it {
+        "⚠️ Review recommended: Some non-IT indicators detected but passed initial screening"
+    } else {
+        "Review completed AI summary for strategic assessment"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FeatureScores;
+    use chrono::Utc;
+
+    fn summary(recommendation: &str, notes: &[&str], summary_type: &str) -> AISummaryResult {
+        AISummaryResult {
+            resource_id: 1,
+            tenant_id: "default".to_string(),
+            summary_type: summary_type.to_string(),
+            ai_summary: String::new(),
+            one_liner: None,
+            key_points: Vec::new(),
+            recommendation: recommendation.to_string(),
+            confidence_assessment: String::new(),
+            processing_notes: notes.iter().map(|n| n.to_string()).collect(),
+            created_at: Utc::now(),
+            quality_score: None,
+        }
+    }
+
+    fn ml(should_bid: bool, confidence: f64) -> MLPredictionResult {
+        MLPredictionResult {
+            should_bid,
+            confidence,
+            reasoning: String::new(),
+            feature_scores: FeatureScores::default(),
+        }
+    }
+
+    #[test]
+    fn test_notification_matrix() {
+        // (Claude recommendation, parse-failure note, ML bid, watch rule, expected reason)
+        let cases = [
+            ("BID", false, true, false, Reason::Claude { bid: true }),
+            ("BID", false, false, false, Reason::Claude { bid: true }),
+            (
+                "Bid - strong fit",
+                false,
+                false,
+                false,
+                Reason::Claude { bid: true },
+            ),
+            ("NO BID", false, true, false, Reason::Claude { bid: false }),
+            (
+                "No bid: construction works",
+                false,
+                true,
+                false,
+                Reason::Claude { bid: false },
+            ),
+            (
+                "Unclear scope",
+                false,
+                true,
+                false,
+                Reason::Claude { bid: false },
+            ),
+            ("", false, true, false, Reason::Claude { bid: false }),
+            (
+                PARSE_FALLBACK_RECOMMENDATION,
+                true,
+                true,
+                false,
+                Reason::ParseFallback { ml_bid: true },
+            ),
+            (
+                PARSE_FALLBACK_RECOMMENDATION,
+                true,
+                false,
+                false,
+                Reason::ParseFallback { ml_bid: false },
+            ),
+            // The fallback text alone, without the parse note, is just a non-BID answer
+            (
+                PARSE_FALLBACK_RECOMMENDATION,
+                false,
+                true,
+                false,
+                Reason::Claude { bid: false },
+            ),
+            // The note alone doesn't make a real recommendation a fallback
+            ("NO BID", true, true, false, Reason::Claude { bid: false }),
+            ("NO BID", false, false, true, Reason::WatchRule),
+            (
+                PARSE_FALLBACK_RECOMMENDATION,
+                true,
+                false,
+                true,
+                Reason::WatchRule,
+            ),
+            // Extractive fallback summaries carry the ML verdict
+            (
+                "BID (ML prediction at 80% confidence - not reviewed by Claude)",
+                false,
+                true,
+                false,
+                Reason::Claude { bid: true },
+            ),
+            (
+                "NO BID (ML prediction at 20% confidence - not reviewed by Claude)",
+                false,
+                false,
+                false,
+                Reason::Claude { bid: false },
+            ),
+        ];
+
+        for (recommendation, parse_failed, ml_bid, watch_rule, expected) in cases {
+            let notes: &[&str] = if parse_failed { &[UNPARSED_NOTE] } else { &[] };
+            let result = summary(recommendation, notes, "FULL_PDF");
+            // Confidence never changes the decision
+            for confidence in [0.0, 0.49, 0.5, 1.0] {
+                let reason =
+                    notification_reason(&result, &ml(ml_bid, confidence), watch_rule, None);
+                assert_eq!(
+                    reason, expected,
+                    "recommendation {:?}, parse_failed {}, ml_bid {}, watch_rule {}, confidence {}",
+                    recommendation, parse_failed, ml_bid, watch_rule, confidence
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_early_interest_notifies_on_register_interest() {
+        let cases = [
+            ("REGISTER INTEREST", true),
+            ("Register interest - DPS covers cloud services", true),
+            ("NO INTEREST", false),
+            // A BID answer to the early-interest prompt isn't one of its answers
+            ("BID", false),
+        ];
+        for (recommendation, expected) in cases {
+            let result = summary(recommendation, &[], EARLY_INTEREST);
+            assert_eq!(
+                notification_reason(&result, &ml(false, 0.5), false, None),
+                Reason::Claude { bid: expected },
+                "{}",
+                recommendation
+            );
+        }
+    }
+
+    #[test]
+    fn test_second_opinion_disagreement_suppresses_bid() {
+        let note = format!(
+            "{}second-model recommends NO BID (Low): Catering",
+            DISAGREES_NOTE
+        );
+        let disputed = summary("BID", &[note.as_str()], "FULL_PDF");
+        assert_eq!(
+            notification_reason(&disputed, &ml(true, 0.06), false, None),
+            Reason::Disputed
+        );
+        assert!(!Reason::Disputed.notify());
+
+        // A watch rule still notifies, and a NO BID the second model liked stays Claude's call
+        assert_eq!(
+            notification_reason(&disputed, &ml(true, 0.06), true, None),
+            Reason::WatchRule
+        );
+        let no_bid = summary("NO BID", &[note.as_str()], "FULL_PDF");
+        assert_eq!(
+            notification_reason(&no_bid, &ml(true, 0.06), false, None),
+            Reason::Claude { bid: false }
+        );
+    }
+
+    #[test]
+    fn test_forced_recommendation_decides_outright() {
+        let note = format!("{}second-model recommends NO BID", DISAGREES_NOTE);
+        let disputed = summary("BID", &[note.as_str()], "FULL_PDF");
+        let unparsed = summary(PARSE_FALLBACK_RECOMMENDATION, &[UNPARSED_NOTE], "FULL_PDF");
+
+        // Over a second opinion, the ML fallback and a watch rule alike
+        assert_eq!(
+            notification_reason(&disputed, &ml(false, 0.1), false, Some(Forced::Bid)),
+            Reason::Override { bid: true }
+        );
+        assert_eq!(
+            notification_reason(&unparsed, &ml(true, 0.9), true, Some(Forced::NoBid)),
+            Reason::Override { bid: false }
+        );
+        assert!(Reason::Override { bid: true }.notify());
+        assert!(!Reason::Override { bid: false }.notify());
+    }
+
+    #[test]
+    fn test_priority_matrix() {
+        let flags = |claude_override, non_it, watchlist_match| Indicators {
+            claude_override,
+            non_it,
+            watchlist_match,
+            watch_rule: false,
+        };
+
+        // (override, non-IT, watchlist, ML bid, summary type, expected)
+        let cases = [
+            (true, false, false, true, "TITLE_ONLY", "CRITICAL"),
+            (true, true, true, true, "FULL_PDF", "CRITICAL"),
+            (true, false, false, false, "TITLE_ONLY", "NORMAL"),
+            (false, false, true, false, "TITLE_ONLY", "URGENT"),
+            (false, true, true, false, "FULL_PDF", "URGENT"),
+            (false, false, false, true, "TITLE_ONLY", "URGENT"),
+            (false, true, false, true, "TITLE_ONLY", "MEDIUM"),
+            (false, true, false, false, "FULL_PDF", "MEDIUM"),
+            (false, false, false, false, "FULL_PDF", "HIGH"),
+            (false, false, false, false, "TITLE_ONLY", "NORMAL"),
+            (true, false, false, true, EARLY_INTEREST, "NORMAL"),
+            (false, false, true, true, EARLY_INTEREST, "HIGH"),
+        ];
+
+        for (claude_override, non_it, watchlist, ml_bid, summary_type, expected) in cases {
+            assert_eq!(
+                priority(
+                    flags(claude_override, non_it, watchlist),
+                    ml_bid,
+                    summary_type
+                ),
+                expected,
+                "override {}, non_it {}, watchlist {}, ml_bid {}, {}",
+                claude_override,
+                non_it,
+                watchlist,
+                ml_bid,
+                summary_type
+            );
+        }
+    }
+
+    #[test]
+    fn test_action_required_prefers_watch_rule() {
+        let indicators = Indicators {
+            claude_override: true,
+            watch_rule: true,
+            ..Indicators::default()
+        };
+        assert!(action_required(indicators, true, "FULL_PDF")
+            .starts_with("Watched contracting authority"));
+        assert!(action_required(Indicators::default(), true, "FULL_PDF")
+            .starts_with("REVIEW IMMEDIATELY"));
+        assert!(action_required(Indicators::default(), false, "TITLE_ONLY")
+            .starts_with("Review completed"));
+        assert!(
+            action_required(indicators, true, EARLY_INTEREST).starts_with("Early market notice")
+        );
+    }
+
+    #[test]
+    fn test_indicators_from_notes() {
+        let notes = vec![
+            "⚠️ Claude OVERRODE ML prediction".to_string(),
+            "👀 WATCHLIST MATCH: keyword \"payroll\"".to_string(),
+            format!("{}authority \"HSE\"", WATCH_RULE_NOTE),
+        ];
+
+        assert_eq!(
+            Indicators::from_notes(&notes, WATCH_RULE_NOTE),
+            Indicators {
+                claude_override: true,
+                non_it: false,
+                watchlist_match: true,
+                watch_rule: true,
+            }
+        );
+        assert_eq!(
+            Indicators::from_notes(&[], WATCH_RULE_NOTE),
+            Indicators::default()
+        );
+    }
+}
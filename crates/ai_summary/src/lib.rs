@@ -0,0 +1,15 @@
+//! Claude evaluation of a tender, shared by the lambda binary, tender_evaluation and tender_api
+pub mod ai_service;
+pub mod chunked;
+pub mod claude_budget;
+pub mod decision;
+pub mod pii;
+pub mod prompt_context;
+pub mod qa;
+pub mod replay;
+pub mod response_skeleton;
+pub mod second_opinion;
+pub mod summary_cache;
+pub mod summary_quality;
+pub mod tenants;
+pub mod types;
@@ -1,19 +1,45 @@
-use lambda_runtime::{service_fn, LambdaEvent, Error, run};
-use aws_lambda_events::event::sqs::SqsEvent;
+use stage_runtime::Error;
 use tracing::{info, error, warn};
-use tracing_subscriber;
-use serde_json;
+use serde_json::{self, Value};
 use anyhow::Result;
+use pipeline_contract::{Completed, ErrorCode, Handoff, Routing, StageError, StageEvent, StageMessage, StageResults};
+use pipeline_contract::diagnostics::{self, ConfigReport};
+use pipeline_contract::timeouts::{Call, TimedOut, Timeouts};
+use pipeline_status::{Claim, Stage};
+use pipeline_status::lifecycle::{self, State};
+use decision_audit::{Decision, Kind};
 
-mod types;
 mod database;
-mod ai_service;
+mod summarizer;
+mod routing_policy;
 mod notification_service;
+mod ticket_service;
 
-use types::{AISummaryMessage, IncomingMessage, Config, MLPredictionResult, FeatureScores};
+use ai_summary::{ai_service, chunked, claude_budget, decision, prompt_context, replay, response_skeleton, second_opinion, summary_cache, summary_quality, tenants, types};
+
+use types::{AISummaryMessage, AISummaryResult, IncomingMessage, Config, MLPredictionResult, FeatureScores, PdfContent, TenderContext, TenderRecord};
 use database::Database;
 use ai_service::AIService;
+use summarizer::{ClaudeWithFallback, FallbackSummarizer, SummaryRequest, Summarizer, WithFallback, FALLBACK_NOTE};
 use notification_service::NotificationService;
+use tenants::CompanyProfile;
+use ticket_service::TicketService;
+use response_skeleton::ResponseSkeleton;
+use reminders::Reminder;
+use tender_overrides::Forced;
+use routing_policy::{Route, RoutingPolicy};
+use second_opinion::SecondOpinion;
+use summary_quality::Regeneration;
+use resource_discovery::{Resource, ResourceDiscovery};
+use analytics::win_model::{self, WinModel};
+use chunked::ContinuationNeeded;
+use claude_budget::BudgetExhausted;
+use pipeline_contract::TaskInput;
+use queue::Publisher;
+use std::time::SystemTime;
+
+/// Name recorded against this lambda's lifecycle transitions
+const ACTOR: &str = "ai_summary";
 
 /// Safely truncate a string at the specified byte position, respecting UTF-8 character boundaries
 fn safe_truncate(text: &str, max_bytes: usize) -> String {
@@ -28,9 +54,70 @@ fn safe_truncate(text: &str, max_bytes: usize) -> String {
     format!("{}...", &text[..end])
 }
 
-async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<String, Error> {
+/// Effective configuration, returned for `{"payload": {"diagnostic": "config"}}`
+fn config_report() -> ConfigReport {
+    ConfigReport::new("ai_summary", env!("CARGO_PKG_VERSION"))
+        .envs(&[
+            "ANTHROPIC_API_KEY", "AI_SUMMARY_ROUTING_POLICY", "CLAUDE_DAILY_CALL_BUDGET",
+            "RESPONSE_SKELETON_ENABLED", "TICKET_PROVIDER", "TICKET_PROJECT", "TICKET_VALUE_THRESHOLD",
+            "TICKET_ASSIGNEES", "JIRA_BASE_URL", "JIRA_EMAIL", "JIRA_API_TOKEN", "LINEAR_API_KEY",
+            "WIN_MODEL_MIN_OUTCOMES", "REMINDER_LEAD_DAYS", "PIPELINE_TOPIC_ARN", "AI_SUMMARY_TIME_MARGIN_SECS",
+            "SECOND_OPINION_BAND", "SECOND_OPINION_THRESHOLD", "SECOND_OPINION_MODEL",
+            "CLAUDE_TIMEOUT_SECS", "DB_QUERY_TIMEOUT_SECS", "SNIPPET_SUMMARIZER", "SNIPPET_SENTENCES",
+            "STAGE_RUNTIME", "FAULT_CLAUDE_RATE", "FAULT_DB_WRITE_RATE", "CLAUDE_RECORD_DIR",
+        ])
+        .build("model", ai_service::MODEL)
+        .build("prompt_version", ai_service::PROMPT_VERSION)
+        .effective("routing_policy", match RoutingPolicy::from_env() {
+            Ok(policy) => format!("{:?}", policy),
+            Err(e) => e.to_string(),
+        })
+        .effective("claude_daily_call_budget", claude_budget::daily_limit_from_env()
+            .map_or_else(|| "unlimited".to_string(), |limit| limit.to_string()))
+        .effective("response_skeletons", response_skeleton::enabled_from_env())
+        .effective("time_margin_secs", chunked::time_margin_from_env().as_secs())
+        .effective("timeouts", Timeouts::from_env().describe())
+        .effective("webhook_snippets", snippet::Snippets::from_env().describe())
+        .effective("fault_injection", fault_injection::describe())
+        .effective("claude_recording", replay::record_dir_from_env()
+            .map_or_else(|| "off".to_string(), |dir| dir.display().to_string()))
+        .effective("second_opinion", match SecondOpinion::from_env() {
+            Ok(Some(second_opinion)) => second_opinion.describe(),
+            Ok(None) => "disabled".to_string(),
+            Err(e) => e.to_string(),
+        })
+        .effective("summary_regeneration", match Regeneration::from_env() {
+            Ok(Some(regeneration)) => regeneration.describe(),
+            Ok(None) => "disabled".to_string(),
+            Err(e) => e.to_string(),
+        })
+        .effective("ticketing", match TicketService::from_env() {
+            Ok(Some(service)) => service.describe(),
+            Ok(None) => "disabled".to_string(),
+            Err(e) => e.to_string(),
+        })
+}
+
+/// Runs under Lambda or as a service polling the AI summary queue (see `stage_runtime`)
+struct AiSummary;
+
+impl stage_runtime::Stage for AiSummary {
+    fn queue(&self) -> Resource {
+        Resource::AiSummaryQueue
+    }
+
+    async fn handle(&self, event: StageEvent, deadline: SystemTime) -> Result<Value, Error> {
+        function_handler(event, deadline).await
+    }
+}
+
+async fn function_handler(event: StageEvent, deadline: SystemTime) -> Result<Value, Error> {
     info!("=== AI SUMMARY LAMBDA STARTED ===");
     
+    if let Some(diagnostic) = event.diagnostic() {
+        return diagnostics::respond(diagnostic, config_report).map_err(|e| Error::from(e.as_str()));
+    }
+    
     // Initialize configuration
     let config = Config::from_env().map_err(|e| {
         error!("Failed to load configuration: {}", e);
@@ -43,41 +130,198 @@ async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<String, Error>
         Error::from(e.to_string().as_str())
     })?;
     
-    let ai_service = AIService::new(config.anthropic_api_key.clone());
+    summary_cache::ensure_table(database.pool()).await.map_err(|e| {
+        error!("Failed to create summary cache table: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+    chunked::ensure_table(database.pool()).await.map_err(|e| {
+        error!("Failed to create document notes table: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+    
+    let routing_policy = RoutingPolicy::from_env().map_err(|e| {
+        error!("Invalid routing policy: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+    routing_policy::ensure_table(database.pool()).await.map_err(|e| {
+        error!("Failed to create batch table: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+    
+    // Scheduled release of the nightly batch: {"payload": {"release_batch": true}}
+    if let StageEvent::Task(task) = &event {
+        if task.payload.get("release_batch").and_then(Value::as_bool) == Some(true) {
+            return release_batch(&database).await.map_err(|e| {
+                error!("Failed to release the nightly batch: {}", e);
+                Error::from(e.to_string().as_str())
+            });
+        }
+    }
+    claude_budget::ensure_table(database.pool()).await.map_err(|e| {
+        error!("Failed to create Claude usage table: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+    let timeouts = Timeouts::from_env();
+    let mut ai_service = AIService::new(config.anthropic_api_key.clone())
+        .with_cache(database.pool().clone())
+        .with_chunks(database.pool().clone())
+        .with_claude_timeout(timeouts.claude);
+    // Long documents read from the queue are continued by another message near the timeout. A
+    // direct invocation has nowhere to continue, so reads the whole document (a retry resumes it)
+    if !event.is_direct() {
+        ai_service = ai_service.with_deadline(deadline, chunked::time_margin_from_env());
+    }
+    if let Some(limit) = claude_budget::daily_limit_from_env() {
+        ai_service = ai_service.with_budget(database.pool().clone(), limit);
+    }
+    // Optional second model's vote on tenders with a borderline ML score
+    let second_opinion = SecondOpinion::from_env().map_err(|e| {
+        error!("Invalid second opinion configuration: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+    if let Some(second_opinion) = second_opinion {
+        ai_service = ai_service.with_second_opinion(second_opinion);
+    }
+    // Optional second attempt at summaries that fail the quality checks
+    let regeneration = Regeneration::from_env().map_err(|e| {
+        error!("Invalid summary regeneration configuration: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+    if let Some(regeneration) = regeneration {
+        ai_service = ai_service.with_regeneration(regeneration);
+    }
+    // Replay fixtures of Claude's replies, for parser regression tests (see `replay`)
+    if let Some(dir) = replay::record_dir_from_env() {
+        ai_service = ai_service.with_recording(dir);
+    }
+    // Out of Claude budget, tenders still get an extractive summary and their notification
+    let summarizer = WithFallback { primary: ai_service, fallback: FallbackSummarizer };
     
-    let notification_service = NotificationService::new(&config).await.map_err(|e| {
+    let notification_service = NotificationService::new().await.map_err(|e| {
         error!("Failed to initialize notification service: {}", e);
         Error::from(e.to_string().as_str())
+    })?.with_win_model(load_win_model(&database).await);
+    
+    // Tenant profiles and the per-tenant ai_summaries key
+    database.ensure_tenant_tables().await.map_err(|e| {
+        error!("Failed to create tenant tables: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+    
+    // Optional Jira/Linear ticketing for high-value BID recommendations
+    let ticket_service = TicketService::from_env().map_err(|e| {
+        error!("Invalid ticketing configuration: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+    if ticket_service.is_some() {
+        database.ensure_ticket_columns().await.map_err(|e| {
+            error!("Failed to add ticket columns: {}", e);
+            Error::from(e.to_string().as_str())
+        })?;
+    }
+    
+    reminders::ensure_table(database.pool()).await.map_err(|e| {
+        error!("Failed to create reminders table: {}", e);
+        Error::from(e.to_string().as_str())
     })?;
+    tender_overrides::ensure_table(database.pool()).await.map_err(|e| {
+        error!("Failed to create tender overrides table: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+    
+    // Optional draft response skeletons for BID recommendations
+    if response_skeleton::enabled_from_env() {
+        response_skeleton::ensure_table(database.pool()).await.map_err(|e| {
+            error!("Failed to create response skeleton table: {}", e);
+            Error::from(e.to_string().as_str())
+        })?;
+    }
+    
+    // Process SQS records (or the single Step Functions task)
+    let mut results = StageResults::new(&event);
+    let messages = event.into_messages();
+    info!("Processing {} messages", messages.len());
+    
+    // Under Step Functions the notification payloads are returned rather than queued.
+    // A `.waitForTaskToken` state keeps Claude calls behind the AI summary queue's rate limit
+    for message in &messages {
+        let handoff = Handoff::new(message);
+        let result = process_summary_message(message, &database, &summarizer, &notification_service, ticket_service.as_ref(), &routing_policy, &timeouts, &handoff).await;
+        results.record(message, &handoff, result).await;
+    }
+    
+    // Retryable failures (e.g. Claude errors) go back to SQS; permanent ones are acknowledged
+    results.into_response().map_err(|e| Error::from(e.to_string().as_str()))
+}
+
+/// Fit the win-probability model from bid_outcomes (best effort - emails just go out without the figure)
+async fn load_win_model(database: &Database) -> Option<WinModel> {
+    let min_outcomes = win_model::min_outcomes_from_env();
+    let loaded = async {
+        analytics::outcomes::ensure_table(database.pool()).await?;
+        win_model::load(database.pool(), min_outcomes).await
+    }.await;
+    
+    match loaded {
+        Ok(Some(model)) => {
+            info!("🏆 Win model fitted on {} bid outcomes", model.outcomes());
+            Some(model)
+        }
+        Ok(None) => {
+            info!("Fewer than {} bid outcomes recorded - no win probability in emails", min_outcomes);
+            None
+        }
+        Err(e) => {
+            warn!("⚠️ Failed to load the win model: {}", e);
+            None
+        }
+    }
+}
+
+/// Send every parked message back to the AI summary queue, marked as released
+async fn release_batch(database: &Database) -> Result<Value> {
+    let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest()).load().await;
+    let queue_url = ResourceDiscovery::new(&aws_config).resolve(Resource::AiSummaryQueue).await?;
+    let sqs_client = aws_sdk_sqs::Client::new(&aws_config);
     
-    // Process SQS records
-    let sqs_records = &event.payload.records;
-    info!("Processing {} SQS records", sqs_records.len());
-    
-    for record in sqs_records {
-        if let Some(body) = &record.body {
-            match process_summary_message(body, &database, &ai_service, &notification_service).await {
-                Ok(_) => info!("✅ Successfully processed message"),
-                Err(e) => {
-                    error!("❌ Failed to process message: {}", e);
-                    // Continue processing other messages rather than failing entire batch
-                }
+    let parked = routing_policy::parked(database.pool()).await?;
+    info!("🌙 Releasing {} parked summaries", parked.len());
+    
+    let mut released = 0;
+    for (resource_id, mut message) in parked {
+        message.batched = true;
+        // Leave the tender parked if the send fails; the next release picks it up
+        let routing = Routing::to_stage(Stage::AiSummary.name()).priority(&message.priority);
+        match routing
+            .apply(sqs_client.send_message().queue_url(&queue_url).message_body(serde_json::to_string(&message)?))
+            .send()
+            .await
+        {
+            Ok(_) => {
+                routing_policy::remove(database.pool(), resource_id).await?;
+                released += 1;
             }
-        } else {
-            warn!("⚠️ SQS record has no body, skipping");
+            Err(e) => warn!("⚠️ Failed to release resource_id {}: {}", resource_id, e),
         }
     }
     
-    Ok("Completed AI summary processing".to_string())
+    info!("✅ Released {} summaries to the AI summary queue", released);
+    Ok(serde_json::json!({ "released": released }))
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn process_summary_message(
-    message_body: &str,
+    message: &StageMessage,
     database: &Database,
-    ai_service: &AIService,
+    summarizer: &ClaudeWithFallback,
     notification_service: &NotificationService,
-) -> Result<()> {
-    info!("🔄 Processing AI summary message");
+    ticket_service: Option<&TicketService>,
+    routing_policy: &RoutingPolicy,
+    timeouts: &Timeouts,
+    handoff: &Handoff,
+) -> Result<Completed, StageError> {
+    info!("🔄 Processing AI summary message (correlation id {:?})", message.routing.correlation_id);
+    let message_body = message.body.as_str();
     
     // Parse the incoming message with better error handling
     let incoming_message: IncomingMessage = serde_json::from_str(message_body)
@@ -93,14 +337,14 @@ async fn process_summary_message(
                 error!("🔍 Detected null reasoning field in ML prediction");
             }
             
-            anyhow::anyhow!("JSON parsing failed: {} - Message: {}", e, message_body)
+            StageError::new(ErrorCode::InvalidMessage, format!("JSON parsing failed: {} - Message: {}", e, message_body))
         })?;
     
     // Convert to standardized format
-    let (resource_id, ai_message) = match incoming_message {
+    let (resource_id, mut ai_message) = match incoming_message {
         IncomingMessage::AISummary(msg) => {
             let resource_id: i64 = msg.resource_id.parse()
-                .map_err(|e| anyhow::anyhow!("Failed to parse resource_id '{}': {}", msg.resource_id, e))?;
+                .map_err(|e| StageError::new(ErrorCode::InvalidMessage, format!("Failed to parse resource_id '{}': {}", msg.resource_id, e)))?;
             (resource_id, msg)
         },
         IncomingMessage::TenderRecord(tender) => {
@@ -128,12 +372,126 @@ async fn process_summary_message(
                 pdf_content: tender.pdf_content.unwrap_or_default(),
                 priority: "NORMAL".to_string(),
                 timestamp: chrono::Utc::now(),
+                tenant_id: None,
+                refresh: false,
+                batched: false,
+                continuation: 0,
             };
             
             (tender.resource_id, ai_message)
         }
     };
     
+    // The priority attribute wins; the body's copy covers messages sent without attributes
+    if let Some(priority) = &message.routing.priority {
+        ai_message.priority = priority.clone();
+    }
+    
+    // A refresh deliberately re-summarises a tender that has already been through this stage
+    if !ai_message.refresh && !lifecycle::may_enter(database.pool(), resource_id, State::Summarized, &[], ACTOR).await {
+        return Err(StageError::new(ErrorCode::InvalidState, "Tender isn't ready for a summary (see tender_lifecycle_transitions)").for_tender(resource_id));
+    }
+    
+    // Low-priority tenders can wait for the nightly batch. A state machine is waiting on an
+    // orchestrated message, so those are always summarised now
+    if !handoff.orchestrated() && routing_policy.route(&ai_message, resource_id, chrono::Utc::now()) == Route::Batch {
+        routing_policy::park(database.pool(), resource_id, &ai_message).await
+            .map_err(|e| StageError::new(ErrorCode::Database, e.to_string()).for_tender(resource_id))?;
+        info!("🌙 Parked resource_id {} ({} priority) for the nightly batch", resource_id, ai_message.priority);
+        return Ok(Completed::new(resource_id, "Parked for the nightly batch"));
+    }
+    
+    // Failed rather than dropped, so the message comes back if the other invocation died
+    if let Claim::Held { until } = pipeline_status::claim(database.pool(), resource_id, Stage::AiSummary, message_body).await {
+        info!("⏭️ Tender {} is already being summarised by another invocation - retrying after {}", resource_id, until);
+        return Err(StageError::new(ErrorCode::Claimed, format!("Already being processed, claimed until {}", until)).for_tender(resource_id));
+    }
+    let result = match summarise_tender(resource_id, &ai_message, database, summarizer, notification_service, ticket_service, timeouts, handoff).await {
+        Ok(Progress::Summarised) => {
+            pipeline_status::completed(database.pool(), resource_id, Stage::AiSummary).await;
+            Ok(Completed::new(resource_id, "AI summary completed"))
+        }
+        Ok(Progress::Paused(paused)) => continue_later(resource_id, &ai_message, message, database, handoff, paused).await,
+        // Retrying the whole message would be turned away now the tender has moved on, and would
+        // notify the other tenants twice. The stage is left failed with a retry for just the failed
+        // tenant, which pipeline_watchdog requeues
+        Ok(Progress::PartlyFailed(failed)) => {
+            let error = failed.iter().map(|(tenant, e)| format!("tenant {}: {}", tenant, e)).collect::<Vec<_>>().join("; ");
+            let tenants: Vec<String> = failed.into_iter().map(|(tenant, _)| tenant).collect();
+            let retry = tenants::retry_message(&ai_message, &tenants).and_then(|retry| serde_json::to_string(&retry).ok());
+            warn!("⚠️ resource_id {} summarised, but not for {}", resource_id, error);
+            pipeline_status::failed_with_retry(database.pool(), resource_id, Stage::AiSummary, &error, retry.as_deref()).await;
+            Ok(Completed::new(resource_id, format!("AI summary completed, except for {}", error)))
+        }
+        Err(e) => Err(e),
+    };
+    let result = result.map_err(|e| e.for_tender(resource_id));
+    match &result {
+        Ok(_) => {}
+        // Permanent failures are recorded as rejected so pipeline_watchdog leaves them alone
+        Err(e) if e.code == ErrorCode::Timeout => pipeline_status::timed_out(database.pool(), resource_id, Stage::AiSummary, &e.to_string()).await,
+        Err(e) if e.is_retryable() => pipeline_status::failed(database.pool(), resource_id, Stage::AiSummary, &e.to_string()).await,
+        Err(e) => pipeline_status::rejected(database.pool(), resource_id, Stage::AiSummary, &e.to_string()).await,
+    }
+    result
+}
+
+/// Queue the message again so the next invocation finishes reading a long document from its
+/// stored notes. A task token travels with it, and that invocation reports the outcome. If the
+/// send fails the message is retried instead, which resumes from the notes just the same
+async fn continue_later(
+    resource_id: i64,
+    ai_message: &AISummaryMessage,
+    message: &StageMessage,
+    database: &Database,
+    handoff: &Handoff,
+    paused: ContinuationNeeded,
+) -> Result<Completed, StageError> {
+    let forward_error = |e: String| StageError::new(ErrorCode::ForwardFailed, format!("Failed to queue the continuation: {}", e));
+    
+    let mut continuation = ai_message.clone();
+    continuation.continuation += 1;
+    let payload = serde_json::to_value(&continuation).map_err(|e| forward_error(e.to_string()))?;
+    let body = match &message.task_token {
+        Some(task_token) => serde_json::to_value(TaskInput { payload, task_token: Some(task_token.clone()) })
+            .map_err(|e| forward_error(e.to_string()))?,
+        None => payload,
+    };
+    let routing = handoff.routing(Stage::AiSummary.name()).priority(&ai_message.priority);
+    Publisher::from_env().await
+        .send(Resource::AiSummaryQueue, &body.to_string(), &routing)
+        .await
+        .map_err(|e| forward_error(e.to_string()))?;
+    
+    pipeline_status::continued(database.pool(), resource_id, Stage::AiSummary).await;
+    handoff.defer();
+    info!("⏩ {} for resource_id {} - continuing in invocation {}", paused, resource_id, continuation.continuation + 1);
+    Ok(Completed::new(resource_id, format!("{} - continuing in a later invocation", paused)))
+}
+
+/// How far `summarise_tender` got
+enum Progress {
+    Summarised,
+    /// Reading the document stopped near the deadline; a continuation message finishes it
+    Paused(ContinuationNeeded),
+    /// Some tenants were evaluated but these (tenant, error) weren't
+    PartlyFailed(Vec<(String, String)>),
+}
+
+/// Summarise the tender for each tenant profile, storing and notifying as needed
+#[allow(clippy::too_many_arguments)]
+async fn summarise_tender(
+    resource_id: i64,
+    ai_message: &AISummaryMessage,
+    database: &Database,
+    summarizer: &ClaudeWithFallback,
+    notification_service: &NotificationService,
+    ticket_service: Option<&TicketService>,
+    timeouts: &Timeouts,
+    handoff: &Handoff,
+) -> Result<Progress, StageError> {
+    let db_error = |e: anyhow::Error| StageError::new(ErrorCode::Database, e.to_string());
+    
     info!("📋 Processing summary for resource_id: {}, priority: {}, ML confidence: {:.1}%", 
           resource_id, ai_message.priority, ai_message.ml_prediction.confidence * 100.0);
     
@@ -143,53 +501,233 @@ async fn process_summary_message(
           ai_message.ml_prediction.confidence * 100.0);
     
     // Get tender record for context (needed for both processing paths and notification)
-    let tender = database.get_tender_record(resource_id).await?
-        .ok_or_else(|| anyhow::anyhow!("Tender record not found for resource_id: {}", resource_id))?;
+    let mut tender = timeouts.run(Call::Database, database.get_tender_record(resource_id)).await?.map_err(db_error)?
+        .ok_or_else(|| StageError::new(ErrorCode::InvalidState, format!("Tender record not found for resource_id: {}", resource_id)))?;
     
-    // Determine processing strategy based on available content
-    let summary_result = if ai_message.pdf_content.is_empty() || ai_message.pdf_content.len() < 100 {
+    // Only shown in the email and reminded about, so a failed lookup just leaves it out
+    match database.get_clarification_deadline(resource_id).await {
+        Ok(deadline) => tender.clarification_deadline = deadline,
+        Err(e) => warn!("⚠️ Failed to load clarification deadline for resource_id {}: {}", resource_id, e),
+    }
+    
+    // Load the full PDF once; every tenant is evaluated against the same content
+    let pdf_content = if ai_message.pdf_content.is_empty() || ai_message.pdf_content.len() < 100 {
         info!("📝 Using title-only processing (no/minimal PDF content)");
+        None
+    } else if ai_message.pdf_content.len() > 1000 {
+        info!("✅ Using PDF content from message (length: {})", ai_message.pdf_content.len());
         
-        ai_service.generate_title_summary(
-            &tender.title,
-            &tender.contracting_authority,
-            &ai_message.ml_prediction,
+        // Create PdfContent from message data
+        Some(PdfContent {
             resource_id,
-        ).await?
+            pdf_text: ai_message.pdf_content.clone(),
+            detected_codes: vec![], // Will be populated from database if available
+            codes_count: 0,
+            extraction_timestamp: chrono::Utc::now(),
+            // Only adds a prompt warning, so a failed lookup just leaves it out
+            extraction_quality: database.get_extraction_quality(resource_id).await.unwrap_or_else(|e| {
+                warn!("⚠️ Failed to load extraction quality for resource_id {}: {}", resource_id, e);
+                None
+            }),
+        })
     } else {
-        info!("📄 Checking if we need to fetch complete PDF content");
+        info!("🔍 Fetching complete PDF content from database");
         
-        // Check if we have full PDF content or need to fetch from database
-        let pdf_content = if ai_message.pdf_content.len() > 1000 {
-            info!("✅ Using PDF content from message (length: {})", ai_message.pdf_content.len());
-            
-            // Create PdfContent from message data
-            crate::types::PdfContent {
-                resource_id,
-                pdf_text: ai_message.pdf_content,
-                detected_codes: vec![], // Will be populated from database if available
-                codes_count: 0,
-                extraction_timestamp: chrono::Utc::now(),
+        Some(timeouts.run(Call::Database, database.get_pdf_content(resource_id)).await?.map_err(db_error)?
+            .ok_or_else(|| StageError::new(ErrorCode::InvalidState, format!("No PDF content found in database for resource_id: {}", resource_id)))?)
+    };
+    
+    // Extra prompt context is nice to have - a lookup failure shouldn't stop the summary
+    let detected_codes = pdf_content.as_ref().map(|p| p.detected_codes.as_slice()).unwrap_or_default();
+    let mut context = database.get_tender_context(&tender, detected_codes).await.unwrap_or_else(|e| {
+        warn!("⚠️ Failed to load prompt context for resource_id {}: {}", resource_id, e);
+        TenderContext::default()
+    });
+    
+    // A document too long for one prompt is read in parts first, once for every tenant
+    if let Some(pdf_content) = &pdf_content {
+        match summarizer.primary.read_document(&tender, &pdf_content.pdf_text).await {
+            Ok(notes) => context.document_notes = notes,
+            Err(e) => match e.downcast::<ContinuationNeeded>() {
+                Ok(paused) => return Ok(Progress::Paused(paused)),
+                // The summaries fall back to the extractive one too
+                Err(e) if e.is::<BudgetExhausted>() => warn!("⚠️ {} - not reading resource_id {} in parts", e, resource_id),
+                Err(e) if e.is::<TimedOut>() => return Err(StageError::new(ErrorCode::Timeout, format!("Failed to read the document in parts: {}", e))),
+                Err(e) => return Err(StageError::new(ErrorCode::Upstream, format!("Failed to read the document in parts: {}", e))),
+            },
+        }
+    }
+    
+    // Evaluate the tender against each company profile (just the default one unless tenants are configured)
+    let profiles = timeouts.run(Call::Database, database.load_profiles()).await?.map_err(db_error)?;
+    let mut evaluated = 0;
+    let mut notified = false;
+    let mut last_error = None;
+    let mut failed = Vec::new();
+    for profile in &profiles {
+        // One evaluation is enough to prove the pipeline works; don't spend a Claude call per tenant
+        if environment::is_canary(resource_id) && evaluated > 0 {
+            break;
+        }
+        if let Some(only) = &ai_message.tenant_id {
+            if *only != profile.tenant_id {
+                continue;
             }
-        } else {
-            info!("🔍 Fetching complete PDF content from database");
-            
-            database.get_pdf_content(resource_id).await?
-                .ok_or_else(|| anyhow::anyhow!("No PDF content found in database for resource_id: {}", resource_id))?
-        };
+        }
+        if !profile.accepts_value(&tender) {
+            info!("⏭️ Skipping tenant {} - tender value below their minimum", profile.tenant_id);
+            continue;
+        }
         
+        // One tenant failing (e.g. a Claude error) must not stop the others being evaluated
+        match evaluate_for_tenant(
+            profile,
+            &tender,
+            pdf_content.as_ref(),
+            &context,
+            ai_message,
+            database,
+            summarizer,
+            notification_service,
+            ticket_service,
+            handoff,
+        ).await {
+            Ok(notify) => {
+                evaluated += 1;
+                notified |= notify;
+            },
+            Err(e) => {
+                error!("❌ Failed to evaluate resource_id {} for tenant {}: {}", resource_id, profile.tenant_id, e);
+                failed.push((profile.tenant_id.clone(), e.to_string()));
+                last_error = Some(e);
+            },
+        }
+    }
+    
+    if evaluated == 0 {
+        // Every evaluation failing (usually Claude) is worth retrying; no tenant accepting the tender isn't
+        return Err(match last_error {
+            Some(e) if e.is::<TimedOut>() => StageError::new(ErrorCode::Timeout, format!("No tenant profile evaluated resource_id: {}: {}", resource_id, e)),
+            Some(e) => StageError::new(ErrorCode::Upstream, format!("No tenant profile evaluated resource_id: {}: {}", resource_id, e)),
+            None => StageError::new(ErrorCode::InvalidState, format!("No tenant profile evaluated resource_id: {} (tenant filter: {:?})", resource_id, ai_message.tenant_id)),
+        });
+    }
+    
+    // Summarized was recorded with the first stored summary; sns_notification records Notified
+    if !ai_message.refresh && !notified {
+        lifecycle::advance(database.pool(), resource_id, State::Suppressed, ACTOR).await;
+    }
+    
+    if !failed.is_empty() {
+        return Ok(Progress::PartlyFailed(failed));
+    }
+    Ok(Progress::Summarised)
+}
+
+/// Summarise, store and notify for one company profile; returns whether a notification was queued
+#[allow(clippy::too_many_arguments)]
+async fn evaluate_for_tenant(
+    profile: &CompanyProfile,
+    tender: &TenderRecord,
+    pdf_content: Option<&PdfContent>,
+    context: &TenderContext,
+    ai_message: &AISummaryMessage,
+    database: &Database,
+    summarizer: &ClaudeWithFallback,
+    notification_service: &NotificationService,
+    ticket_service: Option<&TicketService>,
+    handoff: &Handoff,
+) -> Result<bool> {
+    let resource_id = tender.resource_id;
+    
+    // Canaries always call Claude - a cache hit would hide a broken Claude integration
+    let refresh = ai_message.refresh || environment::is_canary(resource_id);
+    if let Some(pdf_content) = pdf_content {
         info!("📊 Using full PDF processing (PDF text length: {})", pdf_content.pdf_text.len());
-        ai_service.generate_full_summary(&tender, &pdf_content, &ai_message.ml_prediction).await?
+    }
+    let mut summary_result = summarizer.summarize(&SummaryRequest {
+        tender,
+        pdf_content,
+        ml_prediction: &ai_message.ml_prediction,
+        profile,
+        context,
+        refresh,
+    }).await?;
+    
+    // Watchlist hits raise the notification priority; an always-notify rule forces the notification
+    for matched in profile.watchlist_matches(tender) {
+        info!("👀 Watchlist match for tenant {}: {}", profile.tenant_id, matched);
+        summary_result.processing_notes.push(format!("👀 WATCHLIST MATCH: {}", matched));
+    }
+    let watch_rule = profile.always_notify_rule(tender);
+    if let Some(rule) = watch_rule {
+        summary_result.processing_notes.push(format!("{}{}", decision::WATCH_RULE_NOTE, rule.provenance()));
+    }
+    
+    // A recommendation forced by hand has the final word on notifying; snoozes are left to sns_notification
+    let forced = match tender_overrides::get(database.pool(), resource_id, &profile.tenant_id).await {
+        Ok(tender_override) => tender_override.and_then(|o| o.recommendation),
+        Err(e) => {
+            warn!("⚠️ Failed to read the override for {} (tenant {}): {}", resource_id, profile.tenant_id, e);
+            None
+        }
     };
+    if let Some(forced) = forced {
+        info!("✋ Recommendation for {} forced to {} by tenant {}", resource_id, forced.recommendation(), profile.tenant_id);
+        summary_result.processing_notes.push(format!("{}{}", decision::OVERRIDE_NOTE, forced.recommendation()));
+    }
+    
+    // Scored and, when low, flagged for a refresh
+    let quality = summary_quality::annotate(tender, &mut summary_result);
+    if quality.flagged() {
+        warn!("🧪 Low quality summary for resource_id {} (score {:.2}): {}", resource_id, quality.score, quality.issues.join(", "));
+    }
     
     // Store the result
     database.store_ai_summary(&summary_result).await?;
+    // Before any notification is queued, so sns_notification never sees the tender a step behind
+    if !ai_message.refresh {
+        lifecycle::advance(database.pool(), resource_id, State::Summarized, ACTOR).await;
+    }
     
-    info!("✅ AI summary completed for resource_id: {} (type: {})", 
-          resource_id, summary_result.summary_type);
+    info!("✅ AI summary completed for resource_id: {} (type: {}, tenant: {})", 
+          resource_id, summary_result.summary_type, profile.tenant_id);
     
     // Determine if we should send notification based on ML and Claude agreement
-    if NotificationService::should_send_notification(&summary_result, &ai_message.ml_prediction) {
+    // Canary tenders always reach sns_notification (which suppresses the email) so that stage is verified too
+    let canary = environment::is_canary(resource_id);
+    if canary {
+        info!("🐤 Canary tender {} - forwarding notification regardless of recommendation", resource_id);
+    }
+    let notify = canary || NotificationService::should_send_notification(&summary_result, &ai_message.ml_prediction, watch_rule, forced);
+    audit_evaluation(database, tender, pdf_content, profile, &summary_result, ai_message, watch_rule.is_some(), forced, notify).await;
+    
+    // Raise the bid-preparation ticket first so the email can link to it (not for PINs and DPS
+    // notices, where there's no bid to prepare yet).
+    // Ticketing is best-effort - a tracker outage must not block the notification
+    let early_interest = summary_result.summary_type == decision::EARLY_INTEREST;
+    let ticket = match ticket_service {
+        Some(service) if notify && !canary && !early_interest => service
+            .create_ticket(database, tender, &summary_result)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("⚠️ Failed to create ticket for {}: {}", resource_id, e);
+                None
+            }),
+        _ => None,
+    };
+    
+    // Likewise the draft response skeleton - best-effort, and only when Claude itself said BID
+    // (not for extractive fallback summaries, whose recommendation follows the ML model)
+    let claude_bid = decision::recommends_bid(&summary_result.recommendation)
+        && !summary_result.processing_notes.iter().any(|note| note == FALLBACK_NOTE);
+    let skeleton = if notify && !canary && claude_bid && response_skeleton::enabled_from_env() {
+        draft_response_skeleton(database, &summarizer.primary, tender, pdf_content, &summary_result, profile).await
+    } else {
+        None
+    };
+    
+    if notify {
         info!("📧 Sending notification - Claude analysis supports notification");
         
         // Add notification sent flag to processing notes
@@ -201,11 +739,21 @@ async fn process_summary_message(
         
         // Send notification about completed AI summary
         notification_service.send_summary_complete_notification(
-            &tender,
+            tender,
+            pdf_content,
+            context,
             &updated_summary,
             &ai_message.ml_prediction,
+            ticket.as_ref(),
+            skeleton.as_ref(),
+            profile,
+            handoff,
         ).await?;
         
+        if !canary {
+            schedule_clarification_reminder(database, tender, profile, skeleton.as_ref()).await;
+        }
+        
         // Log summary for monitoring
         info!("📋 Summary preview (email sent): {}", safe_truncate(&updated_summary.ai_summary, 200));
     } else {
@@ -221,7 +769,145 @@ async fn process_summary_message(
         info!("📋 Summary preview (no email sent): {}", safe_truncate(&updated_summary.ai_summary, 200));
     }
     
-    Ok(())
+    // Webhook delivery is best-effort - never fail the summary because of it
+    if canary {
+        return Ok(notify);
+    }
+    if let Err(e) = notification_service
+        .send_webhook_event(
+            tender,
+            &summary_result,
+            &ai_message.ml_prediction,
+            notify,
+            ticket.as_ref(),
+        )
+        .await
+    {
+        warn!("⚠️ Failed to queue webhook event for {}: {}", resource_id, e);
+    }
+    
+    Ok(notify)
+}
+
+/// Generate and store the tenant's response skeleton; failures are logged and yield `None`
+async fn draft_response_skeleton(
+    database: &Database,
+    ai_service: &AIService,
+    tender: &TenderRecord,
+    pdf_content: Option<&PdfContent>,
+    summary_result: &AISummaryResult,
+    profile: &CompanyProfile,
+) -> Option<ResponseSkeleton> {
+    let skeleton = match response_skeleton::generate(ai_service, tender, pdf_content, summary_result, profile).await {
+        Ok(skeleton) => skeleton,
+        Err(e) => {
+            warn!("⚠️ Failed to draft response skeleton for {}: {}", tender.resource_id, e);
+            return None;
+        }
+    };
+    info!("📝 Drafted response skeleton for {}: {} checklist items, {} sections, {} questions",
+          tender.resource_id, skeleton.compliance_checklist.len(), skeleton.section_headings.len(), skeleton.clarification_questions.len());
+    
+    // The email still carries the skeleton if storing it fails
+    if let Err(e) = response_skeleton::store(database.pool(), tender.resource_id, &profile.tenant_id, &skeleton).await {
+        warn!("⚠️ Failed to store response skeleton for {}: {}", tender.resource_id, e);
+    }
+    Some(skeleton)
+}
+
+/// Remind the tenant a few days before clarification questions close; failures are logged
+async fn schedule_clarification_reminder(
+    database: &Database,
+    tender: &TenderRecord,
+    profile: &CompanyProfile,
+    skeleton: Option<&ResponseSkeleton>,
+) {
+    let Some(deadline) = tender.clarification_deadline else {
+        return;
+    };
+    let Some(due_at) = reminders::due_at(deadline, reminders::lead_days_from_env(), chrono::Utc::now()) else {
+        info!("⏰ Clarification deadline for {} has passed - no reminder", tender.resource_id);
+        return;
+    };
+    
+    let mut body = format!(
+        "Clarification questions for this tender close on {}.\n\n{}\n{}\nhttps://etenders.gov.ie/epps/opportunity/opportunityDetailAction.do?opportunityId={}\n",
+        deadline.format("%a %-d %b %Y, %H:%M"),
+        tender.title,
+        tender.contracting_authority,
+        tender.resource_id
+    );
+    if let Some(skeleton) = skeleton.filter(|s| !s.clarification_questions.is_empty()) {
+        body.push_str("\nQuestions drafted with the response skeleton:\n");
+        for question in &skeleton.clarification_questions {
+            body.push_str(&format!("- {}\n", question));
+        }
+    }
+    
+    let reminder = Reminder {
+        resource_id: tender.resource_id,
+        tenant_id: profile.tenant_id.clone(),
+        kind: reminders::Kind::ClarificationDeadline,
+        event_at: deadline,
+        due_at,
+        subject: format!("Clarification deadline {}: {}", deadline.format("%-d %b"), tender.title),
+        body,
+        recipients: profile.notification_emails.clone(),
+    };
+    match reminders::schedule(database.pool(), &reminder).await {
+        Ok(()) => info!("⏰ Clarification reminder for {} scheduled for {}", tender.resource_id, due_at),
+        Err(e) => warn!("⚠️ Failed to schedule clarification reminder for {}: {}", tender.resource_id, e),
+    }
+}
+
+/// Record Claude's recommendation and the notify decision in the audit trail
+#[allow(clippy::too_many_arguments)]
+async fn audit_evaluation(
+    database: &Database,
+    tender: &TenderRecord,
+    pdf_content: Option<&PdfContent>,
+    profile: &CompanyProfile,
+    summary_result: &AISummaryResult,
+    ai_message: &AISummaryMessage,
+    watch_rule: bool,
+    forced: Option<Forced>,
+    notify: bool,
+) {
+    let resource_id = tender.resource_id;
+    let pdf_text = pdf_content.map(|p| p.pdf_text.as_str()).unwrap_or_default();
+    let ml_bid = ai_message.ml_prediction.should_bid.to_string();
+    let inputs = [tender.title.as_str(), pdf_text, profile.tenant_id.as_str(), ml_bid.as_str()];
+    
+    // The extractive fallback never saw Claude, so it has no prompt or model
+    let fallback = summary_result.processing_notes.iter().any(|note| note == FALLBACK_NOTE);
+    let cached = summary_result.processing_notes.iter().any(|note| note.starts_with("Reused cached Claude result"));
+    let mut ai_decision = Decision::new(resource_id, Kind::Ai, "ai_summary", summary_result.recommendation.clone())
+        .inputs(&inputs)
+        .version("tenant", profile.tenant_id.clone())
+        .detail(serde_json::json!({
+            "summary_type": summary_result.summary_type,
+            "confidence_assessment": summary_result.confidence_assessment,
+            "cached": cached,
+            "second_opinion": second_opinion::outcome(summary_result),
+        }));
+    ai_decision = if fallback {
+        ai_decision.version("summarizer", "extractive_fallback")
+    } else {
+        ai_decision.version("prompt", ai_service::PROMPT_VERSION).version("model", ai_service::MODEL)
+    };
+    decision_audit::record(database.pool(), &ai_decision).await;
+    
+    let reason = decision::notification_reason(summary_result, &ai_message.ml_prediction, watch_rule, forced);
+    let notify_decision = Decision::new(resource_id, Kind::Notification, "ai_summary", if notify { "notify" } else { "suppress" })
+        .inputs(&inputs)
+        .version("tenant", profile.tenant_id.clone())
+        .detail(serde_json::json!({
+            "reason": reason.name(),
+            "recommendation": summary_result.recommendation,
+            "forced": forced,
+            "ml_bid": ai_message.ml_prediction.should_bid,
+        }));
+    decision_audit::record(database.pool(), &notify_decision).await;
 }
 
 #[tokio::main]
@@ -235,6 +921,6 @@ async fn main() -> Result<(), Error> {
     
     info!("=== AI Summary Lambda Starting ===");
     
-    // Run the lambda
-    run(service_fn(function_handler)).await
+    // Under Lambda or as a service (STAGE_RUNTIME), tagging every log line with the deployment environment
+    stage_runtime::run(AiSummary).await
 }
@@ -1,137 +1,213 @@
-use crate::types::{AISummaryResult, Config, MLPredictionResult, SNSMessage, TenderRecord};
+use crate::decision::{self, Indicators, Reason, WATCH_RULE_NOTE};
+use crate::prompt_context::extract_eligibility;
+use crate::response_skeleton::ResponseSkeleton;
+use crate::tenants::{CompanyProfile, WatchlistEntry};
+use crate::ticket_service::Ticket;
+use crate::types::{
+    AISummaryResult, MLPredictionResult, PdfContent, SNSMessage, TenderContext, TenderRecord,
+};
+use analytics::win_model::WinModel;
 use anyhow::Result;
 use aws_config::BehaviorVersion;
 use aws_sdk_sqs::Client as SqsClient;
+use bigdecimal::ToPrimitive;
 use chrono::Utc;
-use serde_json;
-use tracing::{info, warn};
+use pipeline_contract::{Handoff, Procedure, Routing};
+use pipeline_status::Stage;
+use queue::Publisher;
+use resource_discovery::{Resource, ResourceDiscovery};
+use sha2::{Digest, Sha256};
+use snippet::Snippets;
+use tender_overrides::Forced;
+use tracing::info;
+
+/// ML features listed in the email
+const EMAIL_TOP_FEATURES: usize = 3;
 
 /// Notification service for sending messages to SQS notification queue
 pub struct NotificationService {
     sqs_client: SqsClient,
-    queue_url: String,
+    publisher: Publisher,
+    webhook_queue_url: Option<String>,
+    /// Card title in webhook events
+    snippets: Snippets,
+    /// Win-probability model; `None` until enough bid outcomes are recorded
+    win_model: Option<WinModel>,
 }
 
 impl NotificationService {
     /// Create new notification service
-    pub async fn new(config: &Config) -> Result<Self> {
+    pub async fn new() -> Result<Self> {
         let aws_config = aws_config::defaults(BehaviorVersion::latest()).load().await;
 
         let sqs_client = SqsClient::new(&aws_config);
 
+        let discovery = ResourceDiscovery::new(&aws_config);
+        // Optional: only present where webhook_dispatcher is deployed
+        let webhook_queue_url = discovery.resolve_optional(Resource::WebhookQueue).await;
+        if webhook_queue_url.is_none() {
+            info!("Webhook queue not found - webhook events disabled");
+        }
+
         info!("✅ Notification service initialized for SQS queue");
         Ok(Self {
             sqs_client,
-            queue_url: config.sns_queue_url.clone(),
+            publisher: Publisher::new(&aws_config),
+            webhook_queue_url,
+            snippets: Snippets::from_env(),
+            win_model: None,
         })
     }
 
-    /// Determine if notification should be sent - Claude is the expert, trust its decision
+    pub fn with_win_model(mut self, win_model: Option<WinModel>) -> Self {
+        self.win_model = win_model;
+        self
+    }
+
+    /// Determine if notification should be sent - Claude is the expert, trust its decision,
+    /// unless the tenant forced the recommendation or has an always-notify watch rule for the
+    /// buyer (rules in `decision`)
     pub fn should_send_notification(
         summary_result: &AISummaryResult,
         ml_prediction: &MLPredictionResult,
+        watch_rule: Option<&WatchlistEntry>,
+        forced: Option<Forced>,
     ) -> bool {
         info!("🔍 Notification decision analysis (Claude-first approach):");
-
-        // PRIMARY DECISION: Claude's recommendation (Claude is the final arbiter)
-        let recommendation_lower = summary_result.recommendation.to_lowercase();
-
-        // Check if this is a JSON parsing fallback case
-        let is_json_fallback = summary_result.recommendation
-            == "Review the summary for recommendations"
-            && summary_result
-                .processing_notes
-                .iter()
-                .any(|note| note.contains("could not be parsed as JSON"));
-
         info!(
             "   Claude recommendation: '{}'",
             summary_result.recommendation
         );
-        info!("   Is JSON parsing fallback: {}", is_json_fallback);
 
-        // Special handling for JSON parsing fallback - fall back to ML prediction
-        if is_json_fallback {
-            info!("🔍 JSON parsing fallback detected - using ML prediction as backup");
-            info!(
-                "   ML prediction: {} (confidence: {:.1}%)",
-                if ml_prediction.should_bid {
-                    "BID"
+        let reason = decision::notification_reason(
+            summary_result,
+            ml_prediction,
+            watch_rule.is_some(),
+            forced,
+        );
+        match (reason, watch_rule) {
+            (Reason::Override { bid }, _) => info!(
+                "   {} Recommendation forced to {} by hand",
+                if bid {
+                    "✅ APPROVED:"
+                } else {
+                    "❌ SUPPRESSED:"
+                },
+                if bid { "BID" } else { "NO BID" }
+            ),
+            (Reason::WatchRule, Some(rule)) => info!(
+                "   ✅ APPROVED: {} - regardless of ML/Claude verdict",
+                rule.provenance()
+            ),
+            (Reason::ParseFallback { ml_bid }, _) => info!(
+                "   {} JSON parsing failed - ML prediction decides: {} (confidence: {:.1}%)",
+                if ml_bid {
+                    "✅ FALLBACK APPROVAL:"
                 } else {
-                    "NO BID"
+                    "❌ SUPPRESSED:"
                 },
+                if ml_bid { "BID" } else { "NO BID" },
                 ml_prediction.confidence * 100.0
-            );
-
-            if ml_prediction.should_bid {
-                info!("   ✅ FALLBACK APPROVAL: ML recommends BID, JSON parsing failed");
-                return true;
-            } else {
-                info!("   ❌ SUPPRESSED: ML recommends NO BID, JSON parsing failed");
-                return false;
+            ),
+            (Reason::Claude { bid: true }, _) => {
+                info!("   ✅ APPROVED: Claude recommends BID - trusting AI expert decision")
             }
+            (Reason::Disputed, _) => {
+                info!("   ❌ SUPPRESSED: Claude recommends BID but the second opinion disagrees")
+            }
+            _ => info!("   ❌ SUPPRESSED: Claude does not recommend BID"),
         }
-
-        // Look for explicit BID recommendation from Claude
-        let claude_says_bid =
-            recommendation_lower.contains("bid") && !recommendation_lower.contains("no bid");
-
-        info!("   Claude says BID: {}", claude_says_bid);
-
-        if claude_says_bid {
-            info!("   ✅ APPROVED: Claude recommends BID - trusting AI expert decision");
-            true
-        } else {
-            info!("   ❌ SUPPRESSED: Claude does not recommend BID");
-            false
-        }
+        reason.notify()
     }
 
     /// Send notification that AI summary is complete
+    #[allow(clippy::too_many_arguments)]
     pub async fn send_summary_complete_notification(
         &self,
         tender: &TenderRecord,
+        pdf_content: Option<&PdfContent>,
+        context: &TenderContext,
         summary_result: &AISummaryResult,
         ml_prediction: &MLPredictionResult,
+        ticket: Option<&Ticket>,
+        skeleton: Option<&ResponseSkeleton>,
+        profile: &CompanyProfile,
+        handoff: &Handoff,
     ) -> Result<()> {
         info!(
-            "📢 Sending AI summary complete notification for: {}",
-            tender.resource_id
+            "📢 Sending AI summary complete notification for: {} (tenant: {})",
+            tender.resource_id, profile.tenant_id
         );
 
-        // Check if Claude overrode the ML prediction
-        let claude_override = summary_result
+        let indicators = Indicators::from_notes(&summary_result.processing_notes, WATCH_RULE_NOTE);
+
+        let watchlist_matches: Vec<&str> = summary_result
             .processing_notes
             .iter()
-            .any(|note| note.contains("OVERRODE") || note.contains("overrode"));
+            .filter_map(|note| note.strip_prefix("👀 WATCHLIST MATCH: "))
+            .collect();
 
-        let has_non_it_indicators = summary_result
+        let watch_rule = summary_result
             .processing_notes
             .iter()
-            .any(|note| note.contains("NON-IT INDICATOR"));
-
-        let priority = if claude_override && ml_prediction.should_bid {
-            // This case should rarely happen now due to notification filtering
-            "CRITICAL" // Claude overrode ML's bid recommendation - needs immediate attention
-        } else if ml_prediction.should_bid && !has_non_it_indicators {
-            "URGENT" // ML bid recommendation confirmed by Claude
-        } else if has_non_it_indicators {
-            "MEDIUM" // Has some concerns but not filtered out
-        } else if summary_result.summary_type == "FULL_PDF" {
-            "HIGH"
+            .find_map(|note| note.strip_prefix(WATCH_RULE_NOTE));
+
+        let priority = decision::priority(
+            indicators,
+            ml_prediction.should_bid,
+            &summary_result.summary_type,
+        );
+        let action_required = decision::action_required(
+            indicators,
+            ml_prediction.should_bid,
+            &summary_result.summary_type,
+        );
+
+        // PINs and DPS notices get sns_notification's early-interest email
+        let procedure = Procedure::normalize(&tender.procedure);
+        let template = if summary_result.summary_type == decision::EARLY_INTEREST {
+            "early_interest"
         } else {
-            "NORMAL"
+            "opportunity"
         };
 
-        let action_required = if claude_override && ml_prediction.should_bid {
-            "🚨 CRITICAL: Claude AI OVERRODE ML bid recommendation - review immediately for accuracy"
-        } else if ml_prediction.should_bid {
-            "REVIEW IMMEDIATELY: ML recommends bidding - Claude analysis confirms opportunity"
-        } else if has_non_it_indicators {
-            "⚠️ Review recommended: Some non-IT indicators detected but passed initial screening"
+        // Chance of winning if we bid - shown apart from the relevance score
+        let win_estimate = self.win_model.as_ref().map(|model| {
+            model.estimate(
+                &tender.contracting_authority,
+                tender.value.as_ref().and_then(|v| v.to_f64()),
+                &tender.procedure,
+            )
+        });
+
+        // Codes with their catalogue descriptions when the catalogue knows them
+        let detected_codes: Vec<serde_json::Value> = if context.codes.is_empty() {
+            pdf_content
+                .map(|pdf| pdf.detected_codes.as_slice())
+                .or(tender.detected_codes.as_deref())
+                .unwrap_or_default()
+                .iter()
+                .map(|code| serde_json::json!({ "code": code, "description": null }))
+                .collect()
         } else {
-            "Review completed AI summary for strategic assessment"
+            context
+                .codes
+                .iter()
+                .map(|c| serde_json::json!({ "code": c.code, "description": c.description }))
+                .collect()
         };
+        let top_features: Vec<serde_json::Value> = ml_prediction
+            .feature_scores
+            .top(EMAIL_TOP_FEATURES)
+            .into_iter()
+            .map(|(name, score)| serde_json::json!({ "name": name, "score": score }))
+            .collect();
+        let eligibility = pdf_content
+            .map(|pdf| extract_eligibility(&pdf.pdf_text))
+            .unwrap_or_default();
+        // Lets sns_notification tell an amended document from a repeat of the same one
+        let document_hash =
+            pdf_content.map(|pdf| hex::encode(Sha256::digest(pdf.pdf_text.as_bytes())));
 
         let sns_message = SNSMessage {
             message_type: "AI_SUMMARY_COMPLETE".to_string(),
@@ -143,54 +219,137 @@ impl NotificationService {
             timestamp: Utc::now(),
             metadata: serde_json::json!({
                 "resource_id": tender.resource_id,
+                "tenant_id": profile.tenant_id,
+                "tenant_name": profile.name,
+                "notification_emails": profile.notification_emails,
+                "watchlist_matches": watchlist_matches,
+                "watch_rule": watch_rule,
                 "contracting_authority": tender.contracting_authority,
                 "estimated_value": tender.value,
                 "deadline": tender.deadline,
+                "clarification_deadline": tender.clarification_deadline,
+                "buyer_contact": tender.buyer_contact,
                 "summary_type": summary_result.summary_type,
-                "claude_override": claude_override,
-                "has_non_it_indicators": has_non_it_indicators,
+                "claude_override": indicators.claude_override,
+                "has_non_it_indicators": indicators.non_it,
                 "processing_notes": summary_result.processing_notes,
                 "notification_sent": true,
                 "ml_prediction": {
                     "should_bid": ml_prediction.should_bid,
                     "confidence": ml_prediction.confidence,
-                    "reasoning": ml_prediction.reasoning
+                    "reasoning": ml_prediction.reasoning,
+                    "top_features": top_features
                 },
+                "detected_codes": detected_codes,
+                "eligibility": eligibility,
+                "win_estimate": win_estimate,
                 "ml_status": tender.ml_status,
                 "ml_processed": tender.ml_processed,
                 "ai_summary": summary_result.ai_summary,
+                "one_liner": summary_result.one_liner,
                 "key_points": summary_result.key_points,
                 "recommendation": summary_result.recommendation,
                 "confidence_assessment": summary_result.confidence_assessment,
                 "pdf_url": tender.pdf_url,
+                "document_hash": document_hash,
                 "status": tender.status,
                 "procedure": tender.procedure,
+                "procedure_type": procedure.name(),
+                "procedure_label": procedure.label(),
+                "template": template,
+                "ticket_key": ticket.map(|t| t.key.as_str()),
+                "ticket_url": ticket.map(|t| t.url.as_str()),
+                "response_skeleton": skeleton,
                 "portal_link": format!("https://etenders.gov.ie/epps/opportunity/opportunityDetailAction.do?opportunityId={}", tender.resource_id)
             }),
         };
 
+        if handoff.capture(Stage::Notification, &sns_message) {
+            info!(
+                "🧭 Orchestrated by Step Functions - returning notification for tender {}",
+                tender.resource_id
+            );
+            return Ok(());
+        }
+
         self.send_sqs_notification(&sns_message).await?;
         Ok(())
     }
 
+    /// Publish an AI_SUMMARY_COMPLETE event for third-party webhooks (no-op if not configured)
+    pub async fn send_webhook_event(
+        &self,
+        tender: &TenderRecord,
+        summary_result: &AISummaryResult,
+        ml_prediction: &MLPredictionResult,
+        notification_sent: bool,
+        ticket: Option<&Ticket>,
+    ) -> Result<()> {
+        let Some(webhook_queue_url) = &self.webhook_queue_url else {
+            return Ok(());
+        };
+
+        let event = serde_json::json!({
+            "event_type": "AI_SUMMARY_COMPLETE",
+            "resource_id": tender.resource_id,
+            "tenant_id": summary_result.tenant_id,
+            "occurred_at": Utc::now(),
+            "payload": {
+                "title": tender.title,
+                "contracting_authority": tender.contracting_authority,
+                "estimated_value": tender.value,
+                "deadline": tender.deadline,
+                "clarification_deadline": tender.clarification_deadline,
+                "pdf_url": tender.pdf_url,
+                "summary_type": summary_result.summary_type,
+                "ai_summary": summary_result.ai_summary,
+                "snippet": self.snippets.title(&summary_result.ai_summary, summary_result.one_liner.as_deref()),
+                "key_points": summary_result.key_points,
+                "recommendation": summary_result.recommendation,
+                "confidence_assessment": summary_result.confidence_assessment,
+                "ml_prediction": {
+                    "should_bid": ml_prediction.should_bid,
+                    "confidence": ml_prediction.confidence
+                },
+                "notification_sent": notification_sent,
+                "ticket_key": ticket.map(|t| t.key.as_str()),
+                "ticket_url": ticket.map(|t| t.url.as_str()),
+                "portal_link": format!("https://etenders.gov.ie/epps/opportunity/opportunityDetailAction.do?opportunityId={}", tender.resource_id)
+            }
+        });
+
+        self.sqs_client
+            .send_message()
+            .queue_url(webhook_queue_url)
+            .message_body(event.to_string())
+            .send()
+            .await?;
+
+        info!(
+            "🔗 Queued AI_SUMMARY_COMPLETE webhook event for tender {}",
+            tender.resource_id
+        );
+        Ok(())
+    }
+
     /// Send notification message to SQS queue
     async fn send_sqs_notification(&self, message: &SNSMessage) -> Result<()> {
         let message_body = serde_json::to_string(message)?;
 
-        info!("📤 Sending notification to SQS queue: {}", self.queue_url);
+        info!("📤 Sending notification for tender {}", message.resource_id);
 
-        let response = self
-            .sqs_client
-            .send_message()
-            .queue_url(&self.queue_url)
-            .message_body(message_body)
-            .send()
+        let message_id = self
+            .publisher
+            .send(
+                Resource::NotificationQueue,
+                &message_body,
+                &Routing::to_stage(Stage::Notification.name()),
+            )
             .await?;
 
         info!(
             "✅ SQS notification sent for tender {} (MessageId: {})",
-            message.resource_id,
-            response.message_id().unwrap_or("unknown")
+            message.resource_id, message_id
         );
 
         Ok(())
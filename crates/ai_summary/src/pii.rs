@@ -0,0 +1,79 @@
+//! Personal details kept out of Claude prompts.
+//!
+//! Tender documents name the buyer's contact people and give their email addresses and
+//! phone numbers. Claude doesn't need them to assess a tender, so every prompt passes
+//! through `redact` on its way out (`AIService::complete`): email addresses become
+//! `[email]`, phone numbers `[phone]`, and the name after a "Contact:"-style label
+//! `[name]`. The scraped buyer contact (`TenderRecord::buyer_contact`) is never put in a
+//! prompt at all; the notification email shows it as scraped.
+
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+static EMAIL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\w.+-]+@[\w-]+(?:\.[\w-]+)+").expect("valid email pattern"));
+
+/// Candidates only; `is_phone` decides
+static PHONE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:\+|\(0|\b0)[\d ()-]{7,18}\d").expect("valid phone pattern"));
+
+static CONTACT_NAME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?P<label>\b(?i:contact(?:\s+(?:person|name|point))?|attention(?:\s+of)?|attn))\s*[:-]\s*(?:(?:Mr|Mrs|Ms|Dr)\.?\s+)?\p{Lu}[\p{L}'’-]+(?:[ \t]+\p{Lu}[\p{L}'’-]+){0,3}",
+    )
+    .expect("valid contact name pattern")
+});
+
+/// The text with email addresses, phone numbers and labelled contact names replaced
+pub fn redact(text: &str) -> String {
+    let text = EMAIL.replace_all(text, "[email]");
+    let text = PHONE.replace_all(&text, |caps: &Captures| {
+        let candidate = &caps[0];
+        if is_phone(candidate) {
+            "[phone]".to_string()
+        } else {
+            candidate.to_string()
+        }
+    });
+    CONTACT_NAME
+        .replace_all(&text, "${label}: [name]")
+        .into_owned()
+}
+
+/// International or Irish trunk-prefixed numbers of 9 to 13 digits, written with
+/// separators unless international; a CPV code with its check digit ("03000000-1") isn't one
+fn is_phone(candidate: &str) -> bool {
+    let digits = candidate.chars().filter(char::is_ascii_digit).count();
+    let separated = candidate.contains([' ', '-', '(']);
+    let cpv = candidate.len() == 10
+        && candidate.as_bytes()[8] == b'-'
+        && candidate.bytes().filter(u8::is_ascii_digit).count() == 9;
+    (9..=13).contains(&digits) && (candidate.starts_with('+') || separated) && !cpv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacts_contact_details() {
+        let text = "Queries to Contact person: Máire Ní Bhriain, procurement@kerrycoco.ie or \
+            +353 66 718 3500 (or (01) 234 5678, 087-123 4567) before the deadline.";
+        assert_eq!(
+            redact(text),
+            "Queries to Contact person: [name], [email] or [phone] (or [phone], [phone]) \
+             before the deadline."
+        );
+        assert_eq!(
+            redact("For the attention of: Mr. John Murphy\nTel: 021 496 1234"),
+            "For the attention of: [name]\nTel: [phone]"
+        );
+    }
+
+    #[test]
+    fn test_keeps_tender_numbers() {
+        let text = "CPV 72000000 and 03000000-1, value €250,000, closing 2026-09-14 at 12:00. \
+            Reference 0123456789 and lot 01 of 2026. Contact the Procurement Unit";
+        assert_eq!(redact(text), text);
+    }
+}
@@ -0,0 +1,633 @@
+//! Tender context for the Claude prompts, assembled from prioritised sections.
+//!
+//! The budget is a per-call input budget in estimated tokens (`estimate_tokens`), less the
+//! prompt's own instructions (`around`). When the rendered context would exceed it, sections
+//! are cut back lowest priority first (later sections before earlier ones within a priority):
+//! the buyer history and similar tenders, then the document body, then the eligibility lines
+//! and codes. A section with a minimum length is truncated at a word break while it can keep
+//! that much, and dropped otherwise. Required sections (title, scope) are never cut. Every cut
+//! is listed in `Rendered::note`, which goes in the summary's processing notes.
+
+use crate::tenants::CompanyProfile;
+use crate::types::{MLPredictionResult, TenderContext, TenderRecord};
+use std::fmt;
+
+/// Appended to a section that was truncated to fit the budget
+const TRUNCATION_MARKER: &str = "[TRUNCATED]";
+/// Eligibility lines kept from the PDF
+const MAX_ELIGIBILITY_LINES: usize = 10;
+/// Extraction quality below which Claude is warned the text may be garbled; matches
+/// pdf_processing's default `EXTRACTION_MIN_QUALITY`
+const LOW_EXTRACTION_QUALITY: f32 = 0.5;
+
+/// Words that mark a line of the tender document as an eligibility/qualification requirement
+const ELIGIBILITY_KEYWORDS: &[&str] = &[
+    "turnover",
+    "insurance",
+    "certif",
+    "accredit",
+    "clearance",
+    "eligib",
+    "qualif",
+    "minimum of",
+    "years experience",
+    "years' experience",
+    "years of experience",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+    Required,
+}
+
+#[derive(Debug, Clone)]
+struct Section {
+    heading: &'static str,
+    body: String,
+    priority: Priority,
+    /// Shortest body (in tokens) worth keeping when truncating; 0 means drop rather than truncate
+    min_tokens: usize,
+    /// Estimated tokens of the heading line and the body
+    heading_tokens: usize,
+    body_tokens: usize,
+}
+
+impl Section {
+    fn tokens(&self) -> usize {
+        self.heading_tokens + self.body_tokens
+    }
+}
+
+/// A section cut back to fit the budget
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cut {
+    Truncated {
+        heading: &'static str,
+        from_tokens: usize,
+        to_tokens: usize,
+    },
+    Dropped {
+        heading: &'static str,
+        tokens: usize,
+    },
+}
+
+impl fmt::Display for Cut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cut::Truncated {
+                heading,
+                from_tokens,
+                to_tokens,
+            } => write!(
+                f,
+                "{} truncated from ~{} to ~{} tokens",
+                heading, from_tokens, to_tokens
+            ),
+            Cut::Dropped { heading, tokens } => {
+                write!(f, "{} dropped (~{} tokens)", heading, tokens)
+            }
+        }
+    }
+}
+
+/// The context as placed in the prompt, with what was cut to fit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendered {
+    pub text: String,
+    /// Estimated tokens of `text`
+    pub tokens: usize,
+    /// The call's input budget
+    pub budget: usize,
+    pub cuts: Vec<Cut>,
+}
+
+impl Rendered {
+    /// Processing note listing the cuts, when anything was cut
+    pub fn note(&self) -> Option<String> {
+        if self.cuts.is_empty() {
+            return None;
+        }
+        let cuts: Vec<String> = self.cuts.iter().map(Cut::to_string).collect();
+        Some(format!(
+            "Prompt context cut to fit the {}-token input budget: {}",
+            self.budget,
+            cuts.join("; ")
+        ))
+    }
+}
+
+/// Builder for the tender context placed between a prompt's preamble and its instructions
+#[derive(Debug, Clone)]
+pub struct PromptContext {
+    budget: usize,
+    /// Tokens of the prompt outside the context
+    reserved: usize,
+    sections: Vec<Section>,
+}
+
+impl PromptContext {
+    /// `budget` is the call's input budget in estimated tokens
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            reserved: 0,
+            sections: Vec::new(),
+        }
+    }
+
+    /// Count the prompt the context is placed in (rendered with an empty context) against the budget
+    pub fn around(mut self, prompt: &str) -> Self {
+        self.reserved = estimate_tokens(prompt);
+        self
+    }
+
+    /// Add a section that is dropped whole when it doesn't fit; empty bodies are skipped
+    pub fn section(
+        self,
+        heading: &'static str,
+        body: impl Into<String>,
+        priority: Priority,
+    ) -> Self {
+        self.truncatable(heading, body, priority, 0)
+    }
+
+    /// Add a section that is truncated (down to `min_tokens`) before being dropped
+    pub fn truncatable(
+        mut self,
+        heading: &'static str,
+        body: impl Into<String>,
+        priority: Priority,
+        min_tokens: usize,
+    ) -> Self {
+        let body = body.into();
+        if !body.trim().is_empty() {
+            self.sections.push(Section {
+                heading,
+                heading_tokens: estimate_tokens(heading) + 1,
+                body_tokens: estimate_tokens(&body),
+                body,
+                priority,
+                min_tokens,
+            });
+        }
+        self
+    }
+
+    /// Title and buyer only, for title-only summaries
+    pub fn tender_title(self, title: &str, contracting_authority: &str) -> Self {
+        self.section(
+            "TENDER DETAILS",
+            format!(
+                "Title: \"{}\"\nContracting Authority: \"{}\"",
+                title, contracting_authority
+            ),
+            Priority::Required,
+        )
+    }
+
+    pub fn tender(self, tender: &TenderRecord) -> Self {
+        let not_specified = || "Not specified".to_string();
+        self.section(
+            "TENDER DETAILS",
+            format!(
+                "Title: \"{}\"\nContracting Authority: \"{}\"\nValue: {}\nDeadline: {}\nStatus: \"{}\"\nProcedure: \"{}\"",
+                tender.title,
+                tender.contracting_authority,
+                tender.value.as_ref().map(|v| v.to_string()).unwrap_or_else(not_specified),
+                tender.deadline.map(|d| d.to_string()).unwrap_or_else(not_specified),
+                tender.status,
+                tender.procedure
+            ),
+            Priority::Required,
+        )
+    }
+
+    /// The extracted PDF text, truncated rather than dropped while 500 tokens of it still fit
+    pub fn document(self, pdf_text: &str) -> Self {
+        self.truncatable("PDF CONTENT", pdf_text, Priority::Medium, 500)
+    }
+
+    /// The notes from a chunked read of the document when it had one, otherwise its text
+    pub fn document_or_notes(self, pdf_text: &str, context: &TenderContext) -> Self {
+        match &context.document_notes {
+            Some(notes) => self.truncatable(
+                "PDF CONTENT (NOTES ON EACH PART OF THE DOCUMENT)",
+                notes,
+                Priority::Medium,
+                500,
+            ),
+            None => self.document(pdf_text),
+        }
+    }
+
+    /// A warning when pdf_processing scored the extracted text as poor (unscored text gets none)
+    pub fn extraction_quality(self, quality: Option<f32>) -> Self {
+        match quality {
+            Some(score) if score < LOW_EXTRACTION_QUALITY => self.section(
+                "⚠️ EXTRACTION WARNING",
+                format!(
+                    "The PDF text was extracted with low confidence (quality {:.2} of 1). It may be \
+                     garbled, incomplete or in the wrong order - don't read missing details as absent \
+                     from the tender, and say in your reasoning that the document was hard to read.",
+                    score
+                ),
+                Priority::High,
+            ),
+            _ => self,
+        }
+    }
+
+    /// Eligibility requirements pulled out of the PDF, so they survive truncation of the text
+    pub fn eligibility(self, pdf_text: &str) -> Self {
+        let lines: Vec<String> = extract_eligibility(pdf_text)
+            .into_iter()
+            .map(|line| format!("- {}", line))
+            .collect();
+        self.section("ELIGIBILITY REQUIREMENTS", lines.join("\n"), Priority::High)
+    }
+
+    /// Detected codes with their descriptions (falls back to the bare codes); kept over the document body
+    pub fn codes(self, context: &TenderContext, detected_codes: &[String]) -> Self {
+        let body = if context.codes.is_empty() {
+            detected_codes.join(", ")
+        } else {
+            context
+                .codes
+                .iter()
+                .map(|c| match &c.description {
+                    Some(description) => format!("- {}: {}", c.code, description),
+                    None => format!("- {}", c.code),
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        self.section("DETECTED PROCUREMENT CODES", body, Priority::High)
+    }
+
+    pub fn ml_prediction(self, ml_prediction: &MLPredictionResult) -> Self {
+        self.section(
+            "ML PREDICTION",
+            format!(
+                "{} (confidence: {:.1}% - treat as unreliable)\nReasoning: {}",
+                if ml_prediction.should_bid {
+                    "RECOMMEND BID"
+                } else {
+                    "DO NOT BID"
+                },
+                ml_prediction.confidence * 100.0,
+                ml_prediction.reasoning
+            ),
+            Priority::High,
+        )
+    }
+
+    /// How often the buyer tenders and how often we bid for them
+    pub fn authority(self, context: &TenderContext) -> Self {
+        let Some(history) = &context.authority else {
+            return self;
+        };
+        let mut body = format!(
+            "{} earlier tenders, {} of which we bid for",
+            history.tenders, history.bids
+        );
+        if let Some(last) = history.last_published {
+            body.push_str(&format!("\nMost recent: {}", last.format("%Y-%m-%d")));
+        }
+        self.section("CONTRACTING AUTHORITY HISTORY", body, Priority::Low)
+    }
+
+    /// Earlier labelled tenders from the same buyer
+    pub fn similar_tenders(self, context: &TenderContext) -> Self {
+        let body = context
+            .similar
+            .iter()
+            .map(|t| {
+                let label = match t.bid {
+                    Some(1) => "BID",
+                    Some(_) => "NO BID",
+                    None => "UNLABELLED",
+                };
+                format!("- [{}] {} ({})", label, t.title, t.resource_id)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.section("SIMILAR PAST TENDERS", body, Priority::Low)
+    }
+
+    /// The company's scope and exclusions - the basis of every decision, so never cut
+    pub fn profile(self, profile: &CompanyProfile) -> Self {
+        self.section(
+            "🎯 OUR STRICT SCOPE",
+            profile.scope.clone(),
+            Priority::Required,
+        )
+        .section(
+            "🚫 WE ABSOLUTELY DO NOT DO",
+            profile.exclusions.clone(),
+            Priority::Required,
+        )
+    }
+
+    /// Render the sections in the order they were added, cut back to fit the budget
+    pub fn render(&self) -> Rendered {
+        let budget = self.budget.saturating_sub(self.reserved);
+        let (sections, cuts) = fit(self.sections.clone(), budget);
+        let text = sections
+            .iter()
+            .map(|s| format!("{}:\n{}", s.heading, s.body))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        Rendered {
+            tokens: estimate_tokens(&text),
+            text,
+            budget: self.budget,
+            cuts,
+        }
+    }
+}
+
+/// Estimated Claude tokens in `text`, in the manner of a BPE tokenizer: about one token per four
+/// letters or digits of a word (at least one per word) and one per punctuation mark or symbol.
+/// Errs high for English prose, so a budget in these tokens holds
+pub fn estimate_tokens(text: &str) -> usize {
+    words(text)
+        .map(|(start, end)| word_tokens(&text[start..end]))
+        .sum()
+}
+
+fn word_tokens(word: &str) -> usize {
+    let (letters, symbols) = word.chars().fold((0, 0), |(letters, symbols), c| {
+        if c.is_alphanumeric() {
+            (letters + 1, symbols)
+        } else {
+            (letters, symbols + 1)
+        }
+    });
+    usize::div_ceil(letters, 4) + symbols
+}
+
+/// Byte ranges of the whitespace-separated words in `text`
+fn words(text: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut start = None;
+    text.char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+        .filter_map(move |(i, c)| match (c.is_whitespace(), start) {
+            (false, None) => {
+                start = Some(i);
+                None
+            }
+            (true, Some(from)) => {
+                start = None;
+                Some((from, i))
+            }
+            _ => None,
+        })
+}
+
+/// Longest run of whole words from the start of `text` within `max_tokens`
+fn prefix_within(text: &str, max_tokens: usize) -> &str {
+    let mut tokens = 0;
+    let mut end = 0;
+    for (start, word_end) in words(text) {
+        tokens += word_tokens(&text[start..word_end]);
+        if tokens > max_tokens {
+            break;
+        }
+        end = word_end;
+    }
+    &text[..end]
+}
+
+fn total_tokens(sections: &[Section]) -> usize {
+    sections.iter().map(Section::tokens).sum()
+}
+
+fn fit(mut sections: Vec<Section>, budget: usize) -> (Vec<Section>, Vec<Cut>) {
+    let marker_tokens = estimate_tokens(TRUNCATION_MARKER);
+    let mut cuts = Vec::new();
+    for priority in [Priority::Low, Priority::Medium, Priority::High] {
+        for i in (0..sections.len()).rev() {
+            let over = total_tokens(&sections).saturating_sub(budget);
+            if over == 0 {
+                return (sections, cuts);
+            }
+            if sections[i].priority != priority {
+                continue;
+            }
+
+            let section = &mut sections[i];
+            let from_tokens = section.body_tokens;
+            let keep = from_tokens.saturating_sub(over + marker_tokens);
+            if section.min_tokens > 0 && keep >= section.min_tokens {
+                section.body = format!(
+                    "{} {}",
+                    prefix_within(&section.body, keep),
+                    TRUNCATION_MARKER
+                );
+                section.body_tokens = estimate_tokens(&section.body);
+                cuts.push(Cut::Truncated {
+                    heading: section.heading,
+                    from_tokens,
+                    to_tokens: section.body_tokens,
+                });
+            } else {
+                cuts.push(Cut::Dropped {
+                    heading: section.heading,
+                    tokens: from_tokens,
+                });
+                sections.remove(i);
+            }
+        }
+    }
+    (sections, cuts)
+}
+
+/// Lines of the tender document that state eligibility or qualification requirements
+pub fn extract_eligibility(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.len() < 20 || line.len() > 300 {
+            continue;
+        }
+        let lower = line.to_lowercase();
+        let mentions_iso = lower
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| word == "iso");
+        if !mentions_iso && !ELIGIBILITY_KEYWORDS.iter().any(|k| lower.contains(k)) {
+            continue;
+        }
+        if !lines.iter().any(|l| l.eq_ignore_ascii_case(line)) {
+            lines.push(line.to_string());
+        }
+        if lines.len() == MAX_ELIGIBILITY_LINES {
+            break;
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_estimate() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("a quick tender"), 5);
+        assert_eq!(estimate_tokens("€500,000."), 5);
+        assert_eq!(estimate_tokens("A:\nalpha"), 4);
+        assert_eq!(
+            estimate_tokens("one two"),
+            estimate_tokens("one") + estimate_tokens("two")
+        );
+    }
+
+    #[test]
+    fn test_everything_kept_within_budget() {
+        let rendered = PromptContext::new(1000)
+            .section("A", "alpha", Priority::Required)
+            .section("B", "beta", Priority::Low)
+            .render();
+
+        assert_eq!(rendered.text, "A:\nalpha\n\nB:\nbeta");
+        assert_eq!(rendered.tokens, 7);
+        assert!(rendered.cuts.is_empty());
+        assert_eq!(rendered.note(), None);
+    }
+
+    #[test]
+    fn test_low_priority_dropped_before_medium() {
+        // Each section is 2 heading tokens and 3 body tokens
+        let rendered = PromptContext::new(12)
+            .section("REQ", "x".repeat(10), Priority::Required)
+            .section("MED", "y".repeat(10), Priority::Medium)
+            .section("LOW", "z".repeat(10), Priority::Low)
+            .render();
+
+        assert!(rendered.text.contains("MED"));
+        assert!(!rendered.text.contains("LOW"));
+        assert!(rendered.tokens <= 12);
+        assert_eq!(
+            rendered.cuts,
+            vec![Cut::Dropped {
+                heading: "LOW",
+                tokens: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn test_body_cut_before_codes_and_eligibility() {
+        let rendered = PromptContext::new(40)
+            .section("TITLE", "Managed print services", Priority::Required)
+            .eligibility("Suppliers must hold ISO 27001 certification.")
+            .document(&"word ".repeat(100))
+            .codes(&TenderContext::default(), &["79800000".to_string()])
+            .render();
+
+        assert!(rendered.text.contains("ISO 27001"));
+        assert!(rendered.text.contains("79800000"));
+        assert!(!rendered.text.contains("PDF CONTENT"));
+        assert_eq!(
+            rendered.note().unwrap(),
+            "Prompt context cut to fit the 40-token input budget: PDF CONTENT dropped (~100 tokens)"
+        );
+    }
+
+    #[test]
+    fn test_prompt_around_the_context_counts_against_the_budget() {
+        let context = PromptContext::new(10)
+            .section("REQ", "required", Priority::Required)
+            .section("LOW", "low", Priority::Low);
+
+        assert!(context.clone().render().cuts.is_empty());
+        let rendered = context
+            .around("Summarise this tender for the bid team")
+            .render();
+        assert!(!rendered.text.contains("LOW"));
+        assert_eq!(rendered.budget, 10);
+    }
+
+    #[test]
+    fn test_truncatable_section_keeps_its_head() {
+        let rendered = PromptContext::new(60)
+            .section("REQ", "required", Priority::Required)
+            .truncatable("DOC", "word ".repeat(200), Priority::Medium, 10)
+            .render();
+
+        assert!(rendered.tokens <= 60);
+        assert!(rendered
+            .text
+            .ends_with(&format!("word {}", TRUNCATION_MARKER)));
+        assert!(rendered.text.contains("DOC:\nword word"));
+        assert_eq!(
+            rendered.note().unwrap(),
+            "Prompt context cut to fit the 60-token input budget: DOC truncated from ~200 to ~54 tokens"
+        );
+    }
+
+    #[test]
+    fn test_required_sections_never_cut() {
+        let rendered = PromptContext::new(5)
+            .section("REQ", "r".repeat(50), Priority::Required)
+            .section("HIGH", "h".repeat(50), Priority::High)
+            .render();
+
+        assert_eq!(rendered.text, format!("REQ:\n{}", "r".repeat(50)));
+        assert!(rendered.tokens > rendered.budget);
+    }
+
+    #[test]
+    fn test_truncation_respects_char_boundaries() {
+        let rendered = PromptContext::new(30)
+            .truncatable("DOC", "é ".repeat(100), Priority::Low, 10)
+            .render();
+
+        assert!(rendered.tokens <= 30);
+        assert!(rendered.text.ends_with(TRUNCATION_MARKER));
+    }
+
+    #[test]
+    fn test_extraction_warning_only_for_low_quality() {
+        let warned = PromptContext::new(1000)
+            .extraction_quality(Some(0.2))
+            .render()
+            .text;
+        assert!(warned.starts_with("⚠️ EXTRACTION WARNING:"));
+        assert!(warned.contains("quality 0.20"));
+
+        assert!(PromptContext::new(1000)
+            .extraction_quality(Some(0.8))
+            .render()
+            .text
+            .is_empty());
+        assert!(PromptContext::new(1000)
+            .extraction_quality(None)
+            .render()
+            .text
+            .is_empty());
+    }
+
+    #[test]
+    fn test_extract_eligibility_lines() {
+        let text = "Section 3\n\
+            Tenderers must have a minimum annual turnover of €500,000.\n\
+            Professional indemnity insurance of €1m is required.\n\
+            The supervisor will review all submissions weekly.\n\
+            Suppliers must hold ISO 27001 certification.\n\
+            Tenderers must have a minimum annual turnover of €500,000.";
+
+        assert_eq!(
+            extract_eligibility(text),
+            vec![
+                "Tenderers must have a minimum annual turnover of €500,000.",
+                "Professional indemnity insurance of €1m is required.",
+                "Suppliers must hold ISO 27001 certification.",
+            ]
+        );
+    }
+}
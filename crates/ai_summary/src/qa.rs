@@ -0,0 +1,283 @@
+//! Free-text questions about a stored tender, for bid writers.
+//!
+//! Claude answers from the tender's stored PDF text and summary and cites the pages it
+//! used. Page boundaries come from `pdf_content.page_offsets`; tenders extracted before
+//! those were recorded are shown to Claude as one unpaged document and get no citations.
+//! When the PDF is long, the pages sharing most words with the question are picked.
+//! Served by tender_api at `POST /ask`.
+
+use crate::ai_service::AIService;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+
+pub const MAX_QUESTION_CHARS: usize = 500;
+/// Bytes of PDF text shown to Claude, as for full-PDF summaries
+const DOCUMENT_BUDGET: usize = 24000;
+const MAX_ANSWER_TOKENS: i32 = 1000;
+
+/// Too common in tender documents to say which page a question is about
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "with", "that", "this", "what", "which", "when", "where", "who", "how",
+    "are", "is", "does", "will", "shall", "must", "any", "there", "their", "from", "tender",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<'a> {
+    /// 1-based, counting pages that failed to extract
+    pub number: usize,
+    pub text: &'a str,
+}
+
+/// Split the text at its stored page offsets; `None` when they don't fit the text
+pub fn split_pages<'a>(text: &'a str, offsets: &[i32]) -> Option<Vec<Page<'a>>> {
+    let mut pages = Vec::with_capacity(offsets.len());
+    for (i, &start) in offsets.iter().enumerate() {
+        let end = offsets
+            .get(i + 1)
+            .map_or(Some(text.len()), |&end| usize::try_from(end).ok())?;
+        let start = usize::try_from(start).ok()?;
+        pages.push(Page {
+            number: i + 1,
+            text: text.get(start..end)?,
+        });
+    }
+    Some(pages)
+}
+
+fn terms(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.len() >= 3)
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// Pages sharing most words with the question, within `budget` bytes, back in page order
+pub fn select_pages<'a>(pages: &[Page<'a>], question: &str, budget: usize) -> Vec<Page<'a>> {
+    let question_terms = terms(question);
+    let mut ranked: Vec<(usize, &Page)> = pages
+        .iter()
+        .filter(|page| !page.text.trim().is_empty())
+        .map(|page| (terms(page.text).intersection(&question_terms).count(), page))
+        .collect();
+    // Stable: equally relevant pages keep page order, so early pages win ties
+    ranked.sort_by_key(|(matches, _)| std::cmp::Reverse(*matches));
+
+    let mut remaining = budget;
+    let mut selected: Vec<Page> = ranked
+        .into_iter()
+        .filter(|(_, page)| {
+            let fits = page.text.len() <= remaining;
+            if fits {
+                remaining -= page.text.len();
+            }
+            fits
+        })
+        .map(|(_, page)| page.clone())
+        .collect();
+    selected.sort_by_key(|page| page.number);
+    selected
+}
+
+/// What is stored about a tender
+#[derive(Debug, Clone, Default)]
+pub struct TenderDocument {
+    pub title: String,
+    pub contracting_authority: String,
+    /// Claude's earlier summary and key points, when the tender was summarised
+    pub summary: Option<String>,
+    pub key_points: Vec<String>,
+    pub pdf_text: Option<String>,
+    pub page_offsets: Option<Vec<i32>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Citation {
+    pub page: usize,
+    pub quote: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Answer {
+    pub answer: String,
+    pub citations: Vec<Citation>,
+    /// Pages shown to Claude; empty when the PDF has no page offsets (or no page fits the
+    /// budget), in which case its text was shown unpaged
+    pub pages_considered: Vec<usize>,
+}
+
+fn prompt(document: &TenderDocument, question: &str, pages: &[Page]) -> String {
+    let mut context = format!(
+        "TENDER: {}\nCONTRACTING AUTHORITY: {}\n",
+        document.title, document.contracting_authority
+    );
+    if let Some(summary) = &document.summary {
+        context.push_str(&format!("\nEARLIER SUMMARY:\n{}\n", summary));
+        for point in &document.key_points {
+            context.push_str(&format!("- {}\n", point));
+        }
+    }
+
+    let citing = !pages.is_empty();
+    if citing {
+        context.push_str("\nTENDER DOCUMENT (selected pages):\n");
+        for page in pages {
+            context.push_str(&format!("\n[Page {}]\n{}\n", page.number, page.text.trim()));
+        }
+    } else if let Some(text) = &document.pdf_text {
+        let mut end = DOCUMENT_BUDGET.min(text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        context.push_str(&format!("\nTENDER DOCUMENT:\n{}\n", &text[..end]));
+    }
+
+    format!(
+        r#"You are helping a bid writer understand a public tender. Answer the question using only the tender information below. If the answer isn't there, say so plainly rather than guessing.
+
+{}
+QUESTION: {}
+
+Format as JSON with fields: answer (string), citations (array of {{"page": number, "quote": short exact quote}}).{}"#,
+        context,
+        question,
+        if citing {
+            " Cite the [Page N] each fact comes from."
+        } else {
+            " Page numbers are not available, so leave citations empty."
+        }
+    )
+}
+
+/// Claude's JSON reply, keeping only citations of pages it was shown; a reply that
+/// isn't JSON becomes the answer as is
+fn parse_answer(response: &str, shown: &[usize]) -> (String, Vec<Citation>) {
+    let json = match (response.find('{'), response.rfind('}')) {
+        (Some(start), Some(end)) if start < end => {
+            serde_json::from_str::<Value>(&response[start..=end]).ok()
+        }
+        _ => None,
+    };
+    let Some(answer) = json
+        .as_ref()
+        .and_then(|json| json["answer"].as_str())
+        .map(str::to_string)
+    else {
+        return (response.trim().to_string(), Vec::new());
+    };
+
+    let citations = json
+        .as_ref()
+        .and_then(|json| json["citations"].as_array())
+        .map(|citations| {
+            citations
+                .iter()
+                .filter_map(|citation| {
+                    Some(Citation {
+                        page: usize::try_from(citation["page"].as_u64()?).ok()?,
+                        quote: citation["quote"].as_str().unwrap_or_default().to_string(),
+                    })
+                })
+                .filter(|citation| shown.contains(&citation.page))
+                .collect()
+        })
+        .unwrap_or_default();
+    (answer, citations)
+}
+
+pub async fn ask(
+    ai_service: &AIService,
+    document: &TenderDocument,
+    question: &str,
+) -> Result<Answer> {
+    let pages = match (&document.pdf_text, &document.page_offsets) {
+        (Some(text), Some(offsets)) => split_pages(text, offsets)
+            .map(|pages| select_pages(&pages, question, DOCUMENT_BUDGET))
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    let pages_considered: Vec<usize> = pages.iter().map(|page| page.number).collect();
+
+    let response = ai_service
+        .complete(&prompt(document, question, &pages), MAX_ANSWER_TOKENS)
+        .await?;
+    let (answer, citations) = parse_answer(&response, &pages_considered);
+    Ok(Answer {
+        answer,
+        citations,
+        pages_considered,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_pages() {
+        let text = "Page one.Page two.";
+        let pages = split_pages(text, &[0, 9, 18]).unwrap();
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[1].text, "Page two.");
+        // A page that failed to extract is empty but keeps its number
+        assert_eq!(pages[1].number, 2);
+        assert_eq!(pages[2].text, "");
+
+        assert!(split_pages(text, &[0, 50]).is_none());
+        assert!(split_pages(text, &[9, 0]).is_none());
+    }
+
+    #[test]
+    fn test_select_pages_prefers_matching_pages_within_budget() {
+        let pages = vec![
+            Page {
+                number: 1,
+                text: "Instructions to tenderers and general conditions.",
+            },
+            Page {
+                number: 2,
+                text: "Clarification questions must be submitted by 3 March.",
+            },
+            Page {
+                number: 3,
+                text: "Award criteria: price 40%, quality 60%.",
+            },
+        ];
+
+        let selected = select_pages(
+            &pages,
+            "When is the deadline for clarification questions?",
+            60,
+        );
+        assert_eq!(
+            selected.iter().map(|p| p.number).collect::<Vec<_>>(),
+            vec![2]
+        );
+
+        let selected = select_pages(&pages, "What are the award criteria?", 1000);
+        assert_eq!(
+            selected.iter().map(|p| p.number).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_parse_answer_drops_unshown_pages() {
+        let response = r#"Here you go: {"answer": "Price is 40% of the score.", "citations": [{"page": 3, "quote": "price 40%"}, {"page": 9, "quote": "made up"}]}"#;
+        let (answer, citations) = parse_answer(response, &[2, 3]);
+        assert_eq!(answer, "Price is 40% of the score.");
+        assert_eq!(
+            citations,
+            vec![Citation {
+                page: 3,
+                quote: "price 40%".to_string()
+            }]
+        );
+
+        let (answer, citations) = parse_answer("The document doesn't say.", &[1]);
+        assert_eq!(answer, "The document doesn't say.");
+        assert!(citations.is_empty());
+    }
+}
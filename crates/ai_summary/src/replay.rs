@@ -0,0 +1,181 @@
+//! Recorded Claude replies, replayed through the parser and the notification rules.
+//!
+//! `AIService::parse_ai_response` has to cope with whatever Claude sends back: clean JSON,
+//! JSON in a ```json block or after a sentence of prose, plain text, replies cut off at
+//! max_tokens. With CLAUDE_RECORD_DIR set, each reply parsed into a summary is also written
+//! there as a fixture: the reply with contact details redacted (`pii::redact`), its summary
+//! type, and what the parser and `decision` made of it. The file is named after the reply's
+//! hash, so a reply seen twice is kept once.
+//!
+//! Fixtures worth keeping are copied into `fixtures/claude_responses/`, and
+//! `test_recorded_responses_replay` checks each still gives its recorded outcome - so a
+//! change to the JSON extraction or the recommendation parsing is tried against every reply
+//! shape seen so far. When an outcome changes on purpose, edit the fixture's `expect`.
+
+use crate::ai_service::{AIService, UNPARSED_NOTE};
+use crate::decision::{self, Indicators, WATCH_RULE_NOTE};
+use crate::pii;
+use crate::types::AISummaryResult;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// The fixtures `test_recorded_responses_replay` checks, relative to the crate
+pub const FIXTURES: &str = "fixtures/claude_responses";
+
+/// Directory to record replies to, from CLAUDE_RECORD_DIR
+pub fn record_dir_from_env() -> Option<PathBuf> {
+    std::env::var("CLAUDE_RECORD_DIR")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// What a reply came to
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outcome {
+    pub recommendation: String,
+    /// The reply (or a block in it) parsed as JSON
+    pub json: bool,
+    /// `decision::is_parse_fallback`: no recommendation could be read, so ML decides
+    pub parse_fallback: bool,
+    /// `decision::recommends`: BID, or REGISTER INTEREST on an early-interest notice
+    pub recommends: bool,
+    pub claude_override: bool,
+    pub non_it: bool,
+}
+
+impl Outcome {
+    pub fn of(summary: &AISummaryResult) -> Self {
+        let indicators = Indicators::from_notes(&summary.processing_notes, WATCH_RULE_NOTE);
+        Self {
+            recommendation: summary.recommendation.clone(),
+            json: !summary
+                .processing_notes
+                .iter()
+                .any(|note| note == UNPARSED_NOTE),
+            parse_fallback: decision::is_parse_fallback(summary),
+            recommends: decision::recommends(summary),
+            claude_override: indicators.claude_override,
+            non_it: indicators.non_it,
+        }
+    }
+}
+
+/// One recorded reply and the outcome it should keep giving
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub summary_type: String,
+    /// Claude's reply, contact details redacted
+    pub response: String,
+    pub expect: Outcome,
+}
+
+impl Fixture {
+    /// The fixture for a reply, with the outcome the current parser gives it
+    pub fn record(summary_type: &str, response: &str) -> Result<Self> {
+        let mut fixture = Self {
+            summary_type: summary_type.to_string(),
+            response: pii::redact(response),
+            expect: Outcome::default(),
+        };
+        fixture.expect = fixture.replay()?;
+        Ok(fixture)
+    }
+
+    /// What the current parser and decision rules make of the reply
+    pub fn replay(&self) -> Result<Outcome> {
+        let summary = AIService::parse_ai_response(self.response.clone(), &self.summary_type, 0)?;
+        Ok(Outcome::of(&summary))
+    }
+
+    /// `<first 16 hex digits of the reply's SHA-256>.json`
+    pub fn file_name(&self) -> String {
+        let hash = hex::encode(Sha256::digest(self.response.as_bytes()));
+        format!("{}.json", &hash[..16])
+    }
+}
+
+/// Write a reply to `dir` as a fixture, unless it's there already. Failures are only logged;
+/// recording never fails a summary
+pub fn record(dir: &Path, summary_type: &str, response: &str) {
+    let write = || -> Result<PathBuf> {
+        let fixture = Fixture::record(summary_type, response)?;
+        let path = dir.join(fixture.file_name());
+        if !path.exists() {
+            std::fs::create_dir_all(dir)?;
+            std::fs::write(&path, serde_json::to_string_pretty(&fixture)? + "\n")?;
+        }
+        Ok(path)
+    };
+    match write() {
+        Ok(path) => debug!("📼 Recorded Claude response to {}", path.display()),
+        Err(e) => warn!(
+            "⚠️ Failed to record Claude response to {}: {}",
+            dir.display(),
+            e
+        ),
+    }
+}
+
+/// Every fixture in `dir`, with its file name, in file name order
+pub fn load(dir: &Path) -> Result<Vec<(String, Fixture)>> {
+    let mut fixtures = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let fixture = serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|e| anyhow::anyhow!("{}: {}", name, e))?;
+        fixtures.push((name, fixture));
+    }
+    fixtures.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(fixtures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorded_responses_replay() {
+        let fixtures = load(&Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURES)).unwrap();
+        assert!(fixtures.len() >= 10, "only {} fixtures", fixtures.len());
+
+        let changed: Vec<String> = fixtures
+            .iter()
+            .filter_map(|(name, fixture)| {
+                let outcome = fixture.replay().unwrap();
+                (outcome != fixture.expect)
+                    .then(|| format!("{}: expected {:?}, got {:?}", name, fixture.expect, outcome))
+            })
+            .collect();
+        assert!(changed.is_empty(), "\n{}", changed.join("\n"));
+    }
+
+    #[test]
+    fn test_recording_redacts_and_keeps_one_copy() {
+        let dir = std::env::temp_dir().join(format!("claude_replay_{}", std::process::id()));
+        let response = r#"{"summary": "Cloud migration for the council. Contact: Mary Byrne, mary.byrne@council.ie", "recommendation": "BID"}"#;
+        record(&dir, "FULL_PDF", response);
+        record(&dir, "FULL_PDF", response);
+
+        let fixtures = load(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(fixtures.len(), 1);
+        let (name, fixture) = &fixtures[0];
+        assert_eq!(name, &fixture.file_name());
+        assert!(!fixture.response.contains("Byrne"));
+        assert!(fixture.response.contains("[email]"));
+        assert_eq!(fixture.expect.recommendation, "BID");
+        assert!(fixture.expect.json && fixture.expect.recommends);
+    }
+}
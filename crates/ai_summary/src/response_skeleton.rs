@@ -0,0 +1,206 @@
+//! Draft response skeleton for tenders Claude recommends bidding on.
+//!
+//! A second Claude call turns the tender document into a starting point for the bid
+//! writers: a compliance checklist, suggested section headings for the response, and
+//! questions worth raising with the contracting authority during clarification. It is
+//! opt-in (`RESPONSE_SKELETON_ENABLED=true`) since it doubles the Claude calls for BID
+//! tenders and counts against `CLAUDE_DAILY_CALL_BUDGET`. Skeletons are kept per tenant
+//! in `response_skeletons` and included in the notification email. Generation is
+//! best-effort: a failure is logged and the email goes out without one.
+
+use crate::ai_service::{AIService, MODEL, PROMPT_VERSION};
+use crate::tenants::CompanyProfile;
+use crate::types::{AISummaryResult, PdfContent, TenderRecord};
+use anyhow::{anyhow, Result};
+use environment::Environment;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+/// Bytes of PDF text shown to Claude, as for full-PDF summaries
+const DOCUMENT_BUDGET: usize = 24000;
+const MAX_SKELETON_TOKENS: i32 = 1500;
+/// Items kept per list, so the email stays readable
+const MAX_ITEMS: usize = 15;
+
+pub fn enabled_from_env() -> bool {
+    std::env::var("RESPONSE_SKELETON_ENABLED")
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResponseSkeleton {
+    /// Mandatory requirements the response must evidence
+    pub compliance_checklist: Vec<String>,
+    /// Suggested outline of the response document
+    pub section_headings: Vec<String>,
+    /// Ambiguities to raise with the contracting authority
+    pub clarification_questions: Vec<String>,
+}
+
+impl ResponseSkeleton {
+    pub fn is_empty(&self) -> bool {
+        self.compliance_checklist.is_empty()
+            && self.section_headings.is_empty()
+            && self.clarification_questions.is_empty()
+    }
+}
+
+pub async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "response_skeletons", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS response_skeletons (
+                resource_id BIGINT NOT NULL,
+                tenant_id TEXT NOT NULL DEFAULT 'default',
+                skeleton JSONB NOT NULL,
+                prompt_version TEXT NOT NULL,
+                model TEXT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (resource_id, tenant_id)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "response_skeletons").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+fn prompt(
+    tender: &TenderRecord,
+    pdf_content: Option<&PdfContent>,
+    summary: &AISummaryResult,
+    profile: &CompanyProfile,
+) -> String {
+    let mut context = format!(
+        "TENDER: {}\nCONTRACTING AUTHORITY: {}\nPROCEDURE: {}\n",
+        tender.title, tender.contracting_authority, tender.procedure
+    );
+    if let Some(deadline) = tender.deadline {
+        context.push_str(&format!("SUBMISSION DEADLINE: {}\n", deadline));
+    }
+    context.push_str(&format!("\nSUMMARY:\n{}\n", summary.ai_summary));
+    for point in &summary.key_points {
+        context.push_str(&format!("- {}\n", point));
+    }
+    if let Some(pdf_content) = pdf_content {
+        let text = &pdf_content.pdf_text;
+        let mut end = DOCUMENT_BUDGET.min(text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        context.push_str(&format!("\nTENDER DOCUMENT:\n{}\n", &text[..end]));
+    }
+
+    format!(
+        r#"You are helping {} prepare a response to a public tender it has decided to bid for. Using only the tender information below, draft a skeleton for the bid writers.
+
+{}
+Format as JSON with fields:
+- compliance_checklist (array of strings): each mandatory requirement, certificate, form or threshold the response must meet or evidence
+- section_headings (array of strings): suggested section headings for the response, following the award criteria where they are given
+- clarification_questions (array of strings): ambiguities or gaps worth raising with the contracting authority before the clarification deadline
+Keep each item to one line. Leave a list empty rather than inventing items."#,
+        profile.description, context
+    )
+}
+
+fn string_list(value: &serde_json::Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .take(MAX_ITEMS)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The skeleton in Claude's JSON reply; `None` when there is no JSON or every list is empty
+fn parse_skeleton(response: &str) -> Option<ResponseSkeleton> {
+    let (start, end) = (response.find('{')?, response.rfind('}')?);
+    if start >= end {
+        return None;
+    }
+    let json: serde_json::Value = serde_json::from_str(&response[start..=end]).ok()?;
+    let skeleton = ResponseSkeleton {
+        compliance_checklist: string_list(&json["compliance_checklist"]),
+        section_headings: string_list(&json["section_headings"]),
+        clarification_questions: string_list(&json["clarification_questions"]),
+    };
+    (!skeleton.is_empty()).then_some(skeleton)
+}
+
+pub async fn generate(
+    ai_service: &AIService,
+    tender: &TenderRecord,
+    pdf_content: Option<&PdfContent>,
+    summary: &AISummaryResult,
+    profile: &CompanyProfile,
+) -> Result<ResponseSkeleton> {
+    let response = ai_service
+        .complete(
+            &prompt(tender, pdf_content, summary, profile),
+            MAX_SKELETON_TOKENS,
+        )
+        .await?;
+    parse_skeleton(&response).ok_or_else(|| anyhow!("Claude returned no usable response skeleton"))
+}
+
+pub async fn store(
+    pool: &PgPool,
+    resource_id: i64,
+    tenant_id: &str,
+    skeleton: &ResponseSkeleton,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO response_skeletons (resource_id, tenant_id, skeleton, prompt_version, model)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (resource_id, tenant_id) DO UPDATE SET
+            skeleton = EXCLUDED.skeleton,
+            prompt_version = EXCLUDED.prompt_version,
+            model = EXCLUDED.model,
+            created_at = NOW()
+        "#,
+    )
+    .bind(resource_id)
+    .bind(tenant_id)
+    .bind(serde_json::to_value(skeleton)?)
+    .bind(PROMPT_VERSION)
+    .bind(MODEL)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_skeleton() {
+        let response = r#"Here is the skeleton:
+{"compliance_checklist": ["Tax clearance certificate", "  "], "section_headings": ["Understanding of requirements", "Methodology"], "clarification_questions": []}"#;
+        let skeleton = parse_skeleton(response).unwrap();
+        assert_eq!(
+            skeleton.compliance_checklist,
+            vec!["Tax clearance certificate"]
+        );
+        assert_eq!(skeleton.section_headings.len(), 2);
+        assert!(skeleton.clarification_questions.is_empty());
+
+        assert!(parse_skeleton("I can't help with that.").is_none());
+        assert!(
+            parse_skeleton(r#"{"compliance_checklist": [], "section_headings": []}"#).is_none()
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Routing policy: which summaries run in real time and which wait for the nightly batch.
+//!
+//! Configured with `AI_SUMMARY_ROUTING_POLICY` (JSON), e.g.
+//! `{"batch_priorities": ["NORMAL"], "batch_window": {"start_hour": 1, "end_hour": 5}}`.
+//! Without it every message is summarised immediately.
+//!
+//! A batched message is parked in `ai_summary_batch` and acknowledged. A scheduled
+//! `{"payload": {"release_batch": true}}` invocation sends the parked messages back to the
+//! AI summary queue marked `batched`, so they are summarised overnight at the queue's pace
+//! rather than competing with URGENT tenders for Claude's rate limit during the day.
+
+use crate::types::AISummaryMessage;
+use anyhow::Result;
+use chrono::{DateTime, Timelike, Utc};
+use environment::Environment;
+use serde::Deserialize;
+use sqlx::{PgPool, Row};
+
+/// UTC hours `[start_hour, end_hour)`; wraps past midnight when start > end
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct BatchWindow {
+    pub start_hour: u32,
+    pub end_hour: u32,
+}
+
+impl Default for BatchWindow {
+    fn default() -> Self {
+        Self {
+            start_hour: 1,
+            end_hour: 5,
+        }
+    }
+}
+
+impl BatchWindow {
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let hour = at.hour();
+        if self.start_hour <= self.end_hour {
+            (self.start_hour..self.end_hour).contains(&hour)
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct RoutingPolicy {
+    /// Message priorities held for the nightly batch; empty means everything is real time
+    pub batch_priorities: Vec<String>,
+    /// Batchable messages arriving inside the window are summarised straight away
+    pub batch_window: BatchWindow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    RealTime,
+    Batch,
+}
+
+impl RoutingPolicy {
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("AI_SUMMARY_ROUTING_POLICY") {
+            Ok(json) if !json.trim().is_empty() => serde_json::from_str(&json)
+                .map_err(|e| anyhow::anyhow!("Invalid AI_SUMMARY_ROUTING_POLICY: {}", e)),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn route(&self, message: &AISummaryMessage, resource_id: i64, now: DateTime<Utc>) -> Route {
+        let batchable = self
+            .batch_priorities
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(&message.priority));
+
+        // Released messages, continuations, forced refreshes and canaries never wait
+        if !batchable
+            || message.batched
+            || message.continuation > 0
+            || message.refresh
+            || environment::is_canary(resource_id)
+            || self.batch_window.contains(now)
+        {
+            Route::RealTime
+        } else {
+            Route::Batch
+        }
+    }
+}
+
+pub async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "ai_summary_batch", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS ai_summary_batch (
+                resource_id BIGINT PRIMARY KEY,
+                priority TEXT NOT NULL,
+                message JSONB NOT NULL,
+                parked_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "ai_summary_batch").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Hold a message for the nightly batch; a newer message for the same tender replaces it
+pub async fn park(pool: &PgPool, resource_id: i64, message: &AISummaryMessage) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO ai_summary_batch (resource_id, priority, message)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (resource_id) DO UPDATE SET
+            priority = EXCLUDED.priority,
+            message = EXCLUDED.message
+        "#,
+    )
+    .bind(resource_id)
+    .bind(&message.priority)
+    .bind(serde_json::to_value(message)?)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Parked messages, oldest first
+pub async fn parked(pool: &PgPool) -> Result<Vec<(i64, AISummaryMessage)>> {
+    let rows = sqlx::query("SELECT resource_id, message FROM ai_summary_batch ORDER BY parked_at")
+        .fetch_all(pool)
+        .await?;
+
+    rows.iter()
+        .map(|row| {
+            let message = serde_json::from_value(row.get("message"))?;
+            Ok((row.get("resource_id"), message))
+        })
+        .collect()
+}
+
+pub async fn remove(pool: &PgPool, resource_id: i64) -> Result<()> {
+    sqlx::query("DELETE FROM ai_summary_batch WHERE resource_id = $1")
+        .bind(resource_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FeatureScores, MLPredictionResult};
+    use chrono::TimeZone;
+
+    fn message(priority: &str) -> AISummaryMessage {
+        AISummaryMessage {
+            resource_id: "42".to_string(),
+            tender_title: "Tender".to_string(),
+            ml_prediction: MLPredictionResult {
+                should_bid: false,
+                confidence: 0.5,
+                reasoning: String::new(),
+                feature_scores: FeatureScores {
+                    codes_count_score: 0.0,
+                    has_codes_score: 0.0,
+                    title_length_score: 0.0,
+                    ca_score: 0.0,
+                    text_features_score: 0.0,
+                    total_score: 0.0,
+                },
+            },
+            pdf_content: String::new(),
+            priority: priority.to_string(),
+            timestamp: Utc::now(),
+            tenant_id: None,
+            refresh: false,
+            batched: false,
+            continuation: 0,
+        }
+    }
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, 1, hour, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn test_default_policy_is_real_time() {
+        let policy = RoutingPolicy::default();
+        assert_eq!(
+            policy.route(&message("NORMAL"), 42, at(12)),
+            Route::RealTime
+        );
+    }
+
+    #[test]
+    fn test_normal_batched_outside_window_only() {
+        let policy: RoutingPolicy =
+            serde_json::from_str(r#"{"batch_priorities": ["normal"]}"#).unwrap();
+
+        assert_eq!(policy.route(&message("NORMAL"), 42, at(12)), Route::Batch);
+        assert_eq!(policy.route(&message("NORMAL"), 42, at(2)), Route::RealTime);
+        assert_eq!(
+            policy.route(&message("URGENT"), 42, at(12)),
+            Route::RealTime
+        );
+        assert_eq!(
+            policy.route(&message("NORMAL"), -1_700_000_000, at(12)),
+            Route::RealTime
+        );
+
+        let mut released = message("NORMAL");
+        released.batched = true;
+        assert_eq!(policy.route(&released, 42, at(12)), Route::RealTime);
+
+        let mut continuation = message("NORMAL");
+        continuation.continuation = 1;
+        assert_eq!(policy.route(&continuation, 42, at(12)), Route::RealTime);
+    }
+
+    #[test]
+    fn test_window_wraps_midnight() {
+        let window = BatchWindow {
+            start_hour: 22,
+            end_hour: 4,
+        };
+        assert!(window.contains(at(23)));
+        assert!(window.contains(at(3)));
+        assert!(!window.contains(at(4)));
+        assert!(!window.contains(at(12)));
+    }
+}
@@ -0,0 +1,199 @@
+//! Second opinion on borderline tenders: a second model votes, and a tender is only
+//! notified when both models recommend it.
+//!
+//! The ML score is least reliable close to its threshold, which is where most false
+//! positives come from. With `SECOND_OPINION_BAND` set, a tender whose ML confidence is
+//! within the band of `SECOND_OPINION_THRESHOLD` (default 0.054, ml_bid_predictor's) is
+//! evaluated again by `SECOND_OPINION_MODEL` with the same prompt. The second analysis is
+//! kept in the summary's processing notes, and a BID the second model disagrees with is
+//! suppressed (`decision::Reason::Disputed`). The second call counts against the Claude
+//! budget; when it fails, the first analysis decides alone.
+
+use crate::decision;
+use crate::types::{AISummaryResult, MLPredictionResult};
+use anyhow::{anyhow, Result};
+
+/// Processing note prefixes recording the second model's vote
+pub const AGREES_NOTE: &str = "🗳️ SECOND OPINION AGREES: ";
+pub const DISAGREES_NOTE: &str = "🗳️ SECOND OPINION DISAGREES: ";
+pub const UNAVAILABLE_NOTE: &str = "🗳️ SECOND OPINION UNAVAILABLE: ";
+
+/// ml_bid_predictor's base threshold
+const DEFAULT_THRESHOLD: f64 = 0.054;
+const DEFAULT_MODEL: &str = "claude-3-5-haiku-20241022";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecondOpinion {
+    pub model: String,
+    pub threshold: f64,
+    pub band: f64,
+}
+
+impl SecondOpinion {
+    /// None (no second opinions) unless `SECOND_OPINION_BAND` is set
+    pub fn from_env() -> Result<Option<Self>> {
+        let var = |name: &str| std::env::var(name).ok();
+        Self::parse(
+            var("SECOND_OPINION_BAND").as_deref(),
+            var("SECOND_OPINION_THRESHOLD").as_deref(),
+            var("SECOND_OPINION_MODEL").as_deref(),
+        )
+    }
+
+    fn parse(
+        band: Option<&str>,
+        threshold: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<Option<Self>> {
+        let Some(band) = band.map(str::trim).filter(|band| !band.is_empty()) else {
+            return Ok(None);
+        };
+        let band: f64 = band
+            .parse()
+            .map_err(|_| anyhow!("Invalid SECOND_OPINION_BAND '{}'", band))?;
+        if band.is_nan() || band <= 0.0 {
+            return Err(anyhow!(
+                "SECOND_OPINION_BAND must be positive, got {}",
+                band
+            ));
+        }
+        let threshold = match threshold.map(str::trim).filter(|t| !t.is_empty()) {
+            Some(threshold) => threshold
+                .parse()
+                .map_err(|_| anyhow!("Invalid SECOND_OPINION_THRESHOLD '{}'", threshold))?,
+            None => DEFAULT_THRESHOLD,
+        };
+        let model = model
+            .map(str::trim)
+            .filter(|model| !model.is_empty())
+            .unwrap_or(DEFAULT_MODEL)
+            .to_string();
+
+        Ok(Some(Self {
+            model,
+            threshold,
+            band,
+        }))
+    }
+
+    /// Whether the ML confidence is close enough to the threshold to need a second opinion
+    pub fn applies(&self, ml_prediction: &MLPredictionResult) -> bool {
+        (ml_prediction.confidence - self.threshold).abs() <= self.band
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} within {} of {}", self.model, self.band, self.threshold)
+    }
+}
+
+/// Record the second model's analysis on the first; returns whether they agree
+pub fn vote(first: &mut AISummaryResult, second: &AISummaryResult, model: &str) -> bool {
+    let agree = decision::recommends(first) == decision::recommends(second);
+    first.processing_notes.push(format!(
+        "{}{} recommends {} ({}): {}",
+        if agree { AGREES_NOTE } else { DISAGREES_NOTE },
+        model,
+        second.recommendation,
+        second.confidence_assessment,
+        second.ai_summary
+    ));
+    agree
+}
+
+/// How the second opinion went, for the audit trail; None when there wasn't one
+pub fn outcome(summary: &AISummaryResult) -> Option<&'static str> {
+    summary.processing_notes.iter().find_map(|note| {
+        if note.starts_with(AGREES_NOTE) {
+            Some("agrees")
+        } else if note.starts_with(DISAGREES_NOTE) {
+            Some("disagrees")
+        } else if note.starts_with(UNAVAILABLE_NOTE) {
+            Some("unavailable")
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FeatureScores;
+    use chrono::Utc;
+
+    fn summary(recommendation: &str) -> AISummaryResult {
+        AISummaryResult {
+            resource_id: 1,
+            tenant_id: "default".to_string(),
+            summary_type: "FULL_PDF".to_string(),
+            ai_summary: "Cloud migration".to_string(),
+            one_liner: None,
+            key_points: Vec::new(),
+            recommendation: recommendation.to_string(),
+            confidence_assessment: "Medium".to_string(),
+            processing_notes: Vec::new(),
+            created_at: Utc::now(),
+            quality_score: None,
+        }
+    }
+
+    fn ml(confidence: f64) -> MLPredictionResult {
+        MLPredictionResult {
+            should_bid: confidence >= DEFAULT_THRESHOLD,
+            confidence,
+            reasoning: String::new(),
+            feature_scores: FeatureScores::default(),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(SecondOpinion::parse(None, Some("0.1"), None).unwrap(), None);
+        assert_eq!(SecondOpinion::parse(Some(" "), None, None).unwrap(), None);
+
+        let policy = SecondOpinion::parse(Some("0.02"), None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(policy.model, DEFAULT_MODEL);
+        assert_eq!(policy.threshold, DEFAULT_THRESHOLD);
+
+        let policy = SecondOpinion::parse(Some("0.1"), Some("0.5"), Some("claude-opus-4-1"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(policy.model, "claude-opus-4-1");
+        assert_eq!(policy.threshold, 0.5);
+
+        assert!(SecondOpinion::parse(Some("wide"), None, None).is_err());
+        assert!(SecondOpinion::parse(Some("-0.1"), None, None).is_err());
+        assert!(SecondOpinion::parse(Some("0.1"), Some("half"), None).is_err());
+    }
+
+    #[test]
+    fn test_applies_within_band() {
+        let policy = SecondOpinion::parse(Some("0.02"), None, None)
+            .unwrap()
+            .unwrap();
+        assert!(policy.applies(&ml(0.054)));
+        assert!(policy.applies(&ml(0.04)));
+        assert!(policy.applies(&ml(0.07)));
+        assert!(!policy.applies(&ml(0.01)));
+        assert!(!policy.applies(&ml(0.9)));
+    }
+
+    #[test]
+    fn test_vote_records_second_analysis() {
+        let mut first = summary("BID");
+        assert!(vote(&mut first, &summary("Bid - good fit"), "second-model"));
+        assert_eq!(outcome(&first), Some("agrees"));
+        assert_eq!(
+            first.processing_notes[0],
+            "🗳️ SECOND OPINION AGREES: second-model recommends Bid - good fit (Medium): Cloud migration"
+        );
+
+        let mut first = summary("BID");
+        assert!(!vote(&mut first, &summary("NO BID"), "second-model"));
+        assert_eq!(outcome(&first), Some("disagrees"));
+
+        assert_eq!(outcome(&summary("BID")), None);
+    }
+}
@@ -0,0 +1,437 @@
+//! Summarizer abstraction: Claude normally, a deterministic extractive summary when the
+//! Claude budget is exhausted so notifications still carry useful content.
+
+use crate::ai_service::AIService;
+use crate::claude_budget::BudgetExhausted;
+use crate::decision::{self, EARLY_INTEREST};
+use crate::second_opinion;
+use crate::summary_quality;
+use crate::tenants::CompanyProfile;
+use crate::types::{AISummaryResult, MLPredictionResult, PdfContent, TenderContext, TenderRecord};
+use anyhow::Result;
+use chrono::Utc;
+use pipeline_contract::Procedure;
+use std::collections::{HashMap, HashSet};
+use tracing::warn;
+
+/// Processing note marking a summary that Claude never saw
+pub const FALLBACK_NOTE: &str =
+    "⚠️ FALLBACK SUMMARY - Claude budget exhausted, extractive summary only";
+/// Sentences kept in an extractive summary
+const SUMMARY_SENTENCES: usize = 3;
+
+const STOPWORDS: &[&str] = &[
+    "the",
+    "and",
+    "for",
+    "with",
+    "that",
+    "this",
+    "from",
+    "are",
+    "will",
+    "shall",
+    "must",
+    "any",
+    "all",
+    "not",
+    "which",
+    "have",
+    "has",
+    "been",
+    "its",
+    "their",
+    "such",
+    "other",
+    "may",
+    "under",
+    "into",
+    "each",
+    "per",
+    "who",
+    "where",
+    "within",
+    "these",
+    "those",
+    "should",
+    "would",
+    "also",
+    "tender",
+    "tenderer",
+    "tenderers",
+    "contracting",
+    "authority",
+];
+
+/// Everything a summarizer needs for one tenant's evaluation of a tender
+pub struct SummaryRequest<'a> {
+    pub tender: &'a TenderRecord,
+    /// None for title-only summaries
+    pub pdf_content: Option<&'a PdfContent>,
+    pub ml_prediction: &'a MLPredictionResult,
+    pub profile: &'a CompanyProfile,
+    pub context: &'a TenderContext,
+    /// Skip any cached result
+    pub refresh: bool,
+}
+
+impl SummaryRequest<'_> {
+    pub fn procedure(&self) -> Procedure {
+        Procedure::normalize(&self.tender.procedure)
+    }
+
+    pub fn summary_type(&self) -> &'static str {
+        if self.procedure().is_early_interest() {
+            EARLY_INTEREST
+        } else if self.pdf_content.is_some() {
+            "FULL_PDF"
+        } else {
+            "TITLE_ONLY"
+        }
+    }
+}
+
+pub(crate) trait Summarizer {
+    async fn summarize(&self, request: &SummaryRequest<'_>) -> Result<AISummaryResult>;
+}
+
+impl Summarizer for AIService {
+    async fn summarize(&self, request: &SummaryRequest<'_>) -> Result<AISummaryResult> {
+        let mut result = evaluate(self, request).await?;
+        if let Some(regeneration) = self.regeneration() {
+            result = regenerate_if_poor(self, request, result, regeneration.below).await;
+        }
+
+        // A borderline ML score gets a second model's vote; an unparseable first reply
+        // leaves the decision to ML anyway
+        let Some(second_opinion) = self
+            .second_opinion()
+            .filter(|second_opinion| second_opinion.applies(request.ml_prediction))
+        else {
+            return Ok(result);
+        };
+        if decision::is_parse_fallback(&result) {
+            return Ok(result);
+        }
+        match evaluate(&self.for_model(&second_opinion.model), request).await {
+            Ok(second) if !decision::is_parse_fallback(&second) => {
+                if !second_opinion::vote(&mut result, &second, &second_opinion.model) {
+                    warn!(
+                        "🗳️ {} disagrees with the recommendation for resource_id {}",
+                        second_opinion.model, request.tender.resource_id
+                    );
+                }
+            }
+            Ok(_) => result.processing_notes.push(format!(
+                "{}{} reply could not be parsed",
+                second_opinion::UNAVAILABLE_NOTE,
+                second_opinion.model
+            )),
+            Err(e) => {
+                warn!(
+                    "⚠️ Second opinion failed for resource_id {}: {}",
+                    request.tender.resource_id, e
+                );
+                result.processing_notes.push(format!(
+                    "{}{} failed: {}",
+                    second_opinion::UNAVAILABLE_NOTE,
+                    second_opinion.model,
+                    e
+                ));
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// A second attempt, skipping the cached reply, when the result scores below `below`;
+/// whichever scores higher is kept. A failed retry keeps the first result
+async fn regenerate_if_poor(
+    service: &AIService,
+    request: &SummaryRequest<'_>,
+    mut result: AISummaryResult,
+    below: f32,
+) -> AISummaryResult {
+    let first = summary_quality::assess(request.tender, &result);
+    if first.score >= below {
+        return result;
+    }
+    warn!(
+        "🧪 Summary for resource_id {} scored {:.2} ({}), regenerating",
+        request.tender.resource_id,
+        first.score,
+        first.issues.join(", ")
+    );
+
+    let retry_request = SummaryRequest {
+        tender: request.tender,
+        pdf_content: request.pdf_content,
+        ml_prediction: request.ml_prediction,
+        profile: request.profile,
+        context: request.context,
+        refresh: true,
+    };
+    match evaluate(service, &retry_request).await {
+        Ok(mut retry) => {
+            let second = summary_quality::assess(request.tender, &retry);
+            let note = format!(
+                "{}scored {:.2}, then {:.2}",
+                summary_quality::REGENERATED_NOTE,
+                first.score,
+                second.score
+            );
+            if second.score > first.score {
+                retry.processing_notes.push(note);
+                return retry;
+            }
+            result.processing_notes.push(note);
+        }
+        Err(e) => {
+            warn!(
+                "⚠️ Regenerating the summary for resource_id {} failed: {}",
+                request.tender.resource_id, e
+            );
+        }
+    }
+    result
+}
+
+/// One model's evaluation, by the prompt for the tender's procedure and content
+async fn evaluate(service: &AIService, request: &SummaryRequest<'_>) -> Result<AISummaryResult> {
+    let procedure = request.procedure();
+    if procedure.is_early_interest() {
+        return service
+            .generate_early_interest_summary(
+                request.tender,
+                procedure,
+                request.pdf_content,
+                request.ml_prediction,
+                request.profile,
+                request.context,
+                request.refresh,
+            )
+            .await;
+    }
+    match request.pdf_content {
+        None => {
+            service
+                .generate_title_summary(
+                    request.tender,
+                    request.ml_prediction,
+                    request.profile,
+                    request.context,
+                    request.refresh,
+                )
+                .await
+        }
+        Some(pdf_content) => {
+            service
+                .generate_full_summary(
+                    request.tender,
+                    pdf_content,
+                    request.ml_prediction,
+                    request.profile,
+                    request.context,
+                    request.refresh,
+                )
+                .await
+        }
+    }
+}
+
+/// Uses `fallback` when `primary` reports `BudgetExhausted`; other errors are returned as is
+pub struct WithFallback<P, F> {
+    pub primary: P,
+    pub fallback: F,
+}
+
+impl<P: Summarizer, F: Summarizer> Summarizer for WithFallback<P, F> {
+    async fn summarize(&self, request: &SummaryRequest<'_>) -> Result<AISummaryResult> {
+        match self.primary.summarize(request).await {
+            Err(e) if e.is::<BudgetExhausted>() => {
+                warn!(
+                    "⚠️ {} - using fallback summary for resource_id {}",
+                    e, request.tender.resource_id
+                );
+                self.fallback.summarize(request).await
+            }
+            result => result,
+        }
+    }
+}
+
+/// Claude, falling back to the extractive summary when the budget runs out
+pub type ClaudeWithFallback = WithFallback<AIService, FallbackSummarizer>;
+
+/// Deterministic extractive summary: the most informative sentences (by TF-IDF) plus the
+/// tender's key facts. The recommendation follows the ML prediction, flagged as such
+pub struct FallbackSummarizer;
+
+impl Summarizer for FallbackSummarizer {
+    async fn summarize(&self, request: &SummaryRequest<'_>) -> Result<AISummaryResult> {
+        Ok(fallback_summary(request))
+    }
+}
+
+pub fn fallback_summary(request: &SummaryRequest<'_>) -> AISummaryResult {
+    let tender = request.tender;
+    let text = match request.pdf_content {
+        Some(pdf) => pdf.pdf_text.as_str(),
+        None => tender.info.as_str(),
+    };
+
+    let sentences = top_sentences(text, SUMMARY_SENTENCES);
+    let summary = if sentences.is_empty() {
+        format!("{} ({})", tender.title, tender.contracting_authority)
+    } else {
+        sentences.join(" ")
+    };
+
+    let mut key_points = vec![format!(
+        "Contracting authority: {}",
+        tender.contracting_authority
+    )];
+    if let Some(deadline) = tender.deadline {
+        key_points.push(format!("Deadline: {}", deadline.format("%Y-%m-%d %H:%M")));
+    }
+    if let Some(value) = &tender.value {
+        key_points.push(format!("Estimated value: €{}", value));
+    }
+    let codes = request
+        .pdf_content
+        .map(|pdf| pdf.detected_codes.as_slice())
+        .or(tender.detected_codes.as_deref())
+        .unwrap_or_default();
+    if !codes.is_empty() {
+        key_points.push(format!("Detected codes: {}", codes.join(", ")));
+    }
+
+    let ml = request.ml_prediction;
+    let verdict = match (request.procedure().is_early_interest(), ml.should_bid) {
+        (true, true) => "REGISTER INTEREST",
+        (true, false) => "NO INTEREST",
+        (false, true) => "BID",
+        (false, false) => "NO BID",
+    };
+    let recommendation = format!(
+        "{} (ML prediction at {:.0}% confidence - not reviewed by Claude)",
+        verdict,
+        ml.confidence * 100.0
+    );
+
+    AISummaryResult {
+        resource_id: tender.resource_id,
+        tenant_id: request.profile.tenant_id.clone(),
+        summary_type: request.summary_type().to_string(),
+        ai_summary: summary,
+        one_liner: None,
+        key_points,
+        recommendation,
+        confidence_assessment: "Low - automatic extractive summary".to_string(),
+        processing_notes: vec![FALLBACK_NOTE.to_string()],
+        created_at: Utc::now(),
+        quality_score: None,
+    }
+}
+
+fn terms(sentence: &str) -> Vec<String> {
+    sentence
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|t| t.len() > 2 && !STOPWORDS.contains(&t.as_str()))
+        .collect()
+}
+
+fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    for line in text.lines() {
+        let mut start = 0;
+        for (i, c) in line.char_indices() {
+            let end = i + c.len_utf8();
+            let at_break =
+                matches!(c, '.' | '!' | '?') && line[end..].starts_with(char::is_whitespace);
+            if at_break {
+                sentences.push(line[start..end].trim());
+                start = end;
+            }
+        }
+        sentences.push(line[start..].trim());
+    }
+    sentences
+        .into_iter()
+        .filter(|s| (40..=400).contains(&s.len()))
+        .collect()
+}
+
+/// The `count` highest scoring sentences (mean TF-IDF of their terms), in document order
+pub fn top_sentences(text: &str, count: usize) -> Vec<String> {
+    let sentences = split_sentences(text);
+    let sentence_terms: Vec<Vec<String>> = sentences.iter().map(|s| terms(s)).collect();
+
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for terms in &sentence_terms {
+        for term in terms.iter().collect::<HashSet<_>>() {
+            *document_frequency.entry(term.as_str()).or_default() += 1;
+        }
+    }
+
+    let n = sentences.len() as f64;
+    let mut scored: Vec<(usize, f64)> = sentence_terms
+        .iter()
+        .enumerate()
+        .filter(|(_, terms)| !terms.is_empty())
+        .map(|(i, terms)| {
+            let mut frequency: HashMap<&str, usize> = HashMap::new();
+            for term in terms {
+                *frequency.entry(term.as_str()).or_default() += 1;
+            }
+            let score: f64 = frequency
+                .iter()
+                .map(|(term, tf)| *tf as f64 * (n / document_frequency[term] as f64).ln_1p())
+                .sum();
+            (i, score / terms.len() as f64)
+        })
+        .collect();
+
+    // Highest score first; ties go to the earlier sentence so the result is deterministic
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    let mut chosen: Vec<usize> = scored.iter().take(count).map(|(i, _)| *i).collect();
+    chosen.sort_unstable();
+
+    let mut seen = HashSet::new();
+    chosen
+        .into_iter()
+        .map(|i| sentences[i].to_string())
+        .filter(|s| seen.insert(s.to_lowercase()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_sentences_prefers_distinctive_content() {
+        let text = "The tender documents are available on the portal for all tenderers.\n\
+            The contracting authority requires a cloud migration of the payroll platform to Azure.\n\
+            The tender documents are available on the portal for all tenderers.\n\
+            Short line.\n\
+            Responses must be submitted through the portal before the deadline stated.";
+
+        let top = top_sentences(text, 1);
+        assert_eq!(
+            top,
+            vec!["The contracting authority requires a cloud migration of the payroll platform to Azure."]
+        );
+        assert_eq!(top_sentences(text, 3), top_sentences(text, 3));
+    }
+
+    #[test]
+    fn test_split_sentences_keeps_decimals_together() {
+        let sentences = split_sentences(
+            "The estimated value is €1.5 million over three years of service. Extension options apply for a further year!",
+        );
+        assert_eq!(sentences.len(), 2);
+        assert!(sentences[0].contains("€1.5 million"));
+    }
+}
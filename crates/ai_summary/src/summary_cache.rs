@@ -0,0 +1,121 @@
+//! Claude results cached by (prompt version, model, content hash).
+//!
+//! A re-advertised tender or a retried message with the same text gets the stored
+//! structured result back instead of paying for another Claude call.
+
+use crate::types::AISummaryResult;
+use anyhow::Result;
+use environment::Environment;
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Row};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    pub prompt_version: &'static str,
+    pub model: String,
+    pub content_hash: String,
+}
+
+impl CacheKey {
+    /// Hash the parts that decide Claude's answer (hex-encoded SHA-256)
+    pub fn new(prompt_version: &'static str, model: &str, parts: &[&str]) -> Self {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part.as_bytes());
+            // Separator so ("ab", "c") and ("a", "bc") hash differently
+            hasher.update([0]);
+        }
+
+        Self {
+            prompt_version,
+            model: model.to_string(),
+            content_hash: hex::encode(hasher.finalize()),
+        }
+    }
+}
+
+pub async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "ai_summary_cache", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS ai_summary_cache (
+                prompt_version TEXT NOT NULL,
+                model TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                result JSONB NOT NULL,
+                hits INTEGER NOT NULL DEFAULT 0,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                last_hit_at TIMESTAMPTZ,
+                PRIMARY KEY (prompt_version, model, content_hash)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "ai_summary_cache").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// The cached result for the key, counting the hit
+pub async fn get(pool: &PgPool, key: &CacheKey) -> Result<Option<AISummaryResult>> {
+    let row = sqlx::query(
+        r#"
+        UPDATE ai_summary_cache
+        SET hits = hits + 1, last_hit_at = NOW()
+        WHERE prompt_version = $1 AND model = $2 AND content_hash = $3
+        RETURNING result
+        "#,
+    )
+    .bind(key.prompt_version)
+    .bind(&key.model)
+    .bind(&key.content_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    match row {
+        Some(row) => Ok(Some(serde_json::from_value(row.get("result"))?)),
+        None => Ok(None),
+    }
+}
+
+/// Store (or replace, after a forced refresh) the result for the key
+pub async fn put(pool: &PgPool, key: &CacheKey, result: &AISummaryResult) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO ai_summary_cache (prompt_version, model, content_hash, result)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (prompt_version, model, content_hash)
+        DO UPDATE SET result = EXCLUDED.result, created_at = NOW()
+        "#,
+    )
+    .bind(key.prompt_version)
+    .bind(&key.model)
+    .bind(&key.content_hash)
+    .bind(serde_json::to_value(result)?)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_depends_on_every_part() {
+        let key = CacheKey::new("2", "model", &["FULL_PDF", "text"]);
+
+        assert_eq!(key, CacheKey::new("2", "model", &["FULL_PDF", "text"]));
+        assert_ne!(key, CacheKey::new("2", "model", &["FULL_PDF", "text!"]));
+        assert_ne!(key, CacheKey::new("3", "model", &["FULL_PDF", "text"]));
+        assert_ne!(
+            CacheKey::new("2", "model", &["ab", "c"]),
+            CacheKey::new("2", "model", &["a", "bc"])
+        );
+        assert_eq!(key.content_hash.len(), 64);
+    }
+}
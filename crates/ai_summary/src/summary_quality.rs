@@ -0,0 +1,239 @@
+//! Heuristic self-check of every summary: does it say what a reader needs?
+//!
+//! `assess` scores a summary from 0 to 1 on checks that catch truncated or off-format
+//! Claude replies: a summary of some length that ends in a full sentence, key points, a
+//! clear BID / NO BID (or interest) recommendation, a confidence assessment, and, when
+//! the tender has them, its deadline and value. The score is stored with the summary
+//! (`ai_summaries.quality_score`) and a summary below the minimum is flagged in its
+//! processing notes, so it can be found and refreshed.
+//!
+//! With `SUMMARY_REGENERATE_BELOW` set (a score, e.g. 0.6) a Claude summary scoring lower
+//! is generated once more, bypassing the summary cache, and the better of the two kept.
+//! The retry counts against the Claude budget; the checks themselves cost nothing.
+
+use crate::types::{AISummaryResult, TenderRecord};
+use anyhow::{anyhow, Result};
+
+/// Processing note prefix flagging a low-scoring summary, followed by what it lacks
+pub const LOW_QUALITY_NOTE: &str = "🧪 LOW QUALITY SUMMARY: ";
+/// Processing note prefix recording a regeneration
+pub const REGENERATED_NOTE: &str = "🧪 REGENERATED: ";
+
+/// Summaries scoring below this are flagged
+pub const FLAG_BELOW: f32 = 0.6;
+/// Shorter summaries are taken as cut off or empty
+const MIN_SUMMARY_CHARS: usize = 80;
+const MIN_KEY_POINTS: usize = 2;
+
+const DEADLINE_WORDS: &[&str] = &["deadline", "closing", "closes", "submission date", "due"];
+const VALUE_WORDS: &[&str] = &["€", "eur", "value", "budget", "worth"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assessment {
+    /// Share of the applicable checks passed
+    pub score: f32,
+    /// What failed, for the processing note
+    pub issues: Vec<&'static str>,
+}
+
+impl Assessment {
+    pub fn flagged(&self) -> bool {
+        self.score < FLAG_BELOW
+    }
+}
+
+pub fn assess(tender: &TenderRecord, summary: &AISummaryResult) -> Assessment {
+    let text = format!(
+        "{}\n{}\n{}",
+        summary.ai_summary,
+        summary.key_points.join("\n"),
+        summary.recommendation
+    )
+    .to_lowercase();
+    let mentions = |words: &[&str]| words.iter().any(|word| text.contains(word));
+
+    let body = summary.ai_summary.trim();
+    let recommendation = summary.recommendation.to_lowercase();
+    let confidence = summary.confidence_assessment.trim();
+    let mut checks = vec![
+        (
+            body.chars().count() >= MIN_SUMMARY_CHARS,
+            "summary missing or too short",
+        ),
+        (
+            body.ends_with(['.', '!', '?', ')', '"', '”']),
+            "summary looks truncated",
+        ),
+        (
+            summary
+                .key_points
+                .iter()
+                .filter(|point| !point.trim().is_empty())
+                .count()
+                >= MIN_KEY_POINTS,
+            "too few key points",
+        ),
+        (
+            recommendation.contains("bid") || recommendation.contains("interest"),
+            "no clear recommendation",
+        ),
+        (
+            !confidence.is_empty() && !confidence.to_lowercase().starts_with("unknown"),
+            "no confidence assessment",
+        ),
+    ];
+    if tender.deadline.is_some() {
+        checks.push((mentions(DEADLINE_WORDS), "deadline not mentioned"));
+    }
+    if tender.value.is_some() {
+        checks.push((mentions(VALUE_WORDS), "value not mentioned"));
+    }
+
+    let passed = checks.iter().filter(|(passed, _)| *passed).count();
+    Assessment {
+        score: passed as f32 / checks.len() as f32,
+        issues: checks
+            .into_iter()
+            .filter(|(passed, _)| !passed)
+            .map(|(_, issue)| issue)
+            .collect(),
+    }
+}
+
+/// Store the score on the summary and flag it when low
+pub fn annotate(tender: &TenderRecord, summary: &mut AISummaryResult) -> Assessment {
+    let assessment = assess(tender, summary);
+    summary.quality_score = Some(assessment.score);
+    if assessment.flagged() {
+        summary.processing_notes.push(format!(
+            "{}{:.2} ({})",
+            LOW_QUALITY_NOTE,
+            assessment.score,
+            assessment.issues.join(", ")
+        ));
+    }
+    assessment
+}
+
+/// Regenerate Claude summaries scoring below `below`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regeneration {
+    pub below: f32,
+}
+
+impl Regeneration {
+    /// None (no regeneration) unless `SUMMARY_REGENERATE_BELOW` is set
+    pub fn from_env() -> Result<Option<Self>> {
+        Self::parse(std::env::var("SUMMARY_REGENERATE_BELOW").ok().as_deref())
+    }
+
+    fn parse(below: Option<&str>) -> Result<Option<Self>> {
+        let Some(below) = below.map(str::trim).filter(|below| !below.is_empty()) else {
+            return Ok(None);
+        };
+        let below: f32 = below
+            .parse()
+            .map_err(|_| anyhow!("Invalid SUMMARY_REGENERATE_BELOW '{}'", below))?;
+        if !(0.0..=1.0).contains(&below) {
+            return Err(anyhow!(
+                "SUMMARY_REGENERATE_BELOW must be between 0 and 1, got {}",
+                below
+            ));
+        }
+        Ok(Some(Self { below }))
+    }
+
+    pub fn describe(&self) -> String {
+        format!("below {}", self.below)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigdecimal::BigDecimal;
+    use chrono::{NaiveDate, Utc};
+
+    fn tender() -> TenderRecord {
+        TenderRecord {
+            resource_id: 1,
+            title: "Cloud migration".to_string(),
+            contracting_authority: "HSE".to_string(),
+            deadline: NaiveDate::from_ymd_opt(2026, 11, 20)
+                .unwrap()
+                .and_hms_opt(12, 0, 0),
+            value: Some(BigDecimal::from(250_000)),
+            ..Default::default()
+        }
+    }
+
+    fn summary(ai_summary: &str, key_points: &[&str], recommendation: &str) -> AISummaryResult {
+        AISummaryResult {
+            resource_id: 1,
+            tenant_id: "default".to_string(),
+            summary_type: "FULL_PDF".to_string(),
+            ai_summary: ai_summary.to_string(),
+            one_liner: None,
+            key_points: key_points.iter().map(|p| p.to_string()).collect(),
+            recommendation: recommendation.to_string(),
+            confidence_assessment: "High".to_string(),
+            processing_notes: Vec::new(),
+            created_at: Utc::now(),
+            quality_score: None,
+        }
+    }
+
+    #[test]
+    fn test_complete_summary_scores_full() {
+        let good = summary(
+            "The HSE wants its payroll platform migrated to Azure, with two years of managed support afterwards.",
+            &["Estimated value €250,000", "Deadline 20 November 2026"],
+            "BID - strong fit with our Azure practice",
+        );
+        let assessment = assess(&tender(), &good);
+        assert_eq!(assessment.score, 1.0);
+        assert!(assessment.issues.is_empty());
+    }
+
+    #[test]
+    fn test_truncated_summary_is_flagged() {
+        let mut cut_off = summary(
+            "The HSE wants its payroll platform migrated to Azure, with two years of managed support and a",
+            &["Cloud migration"],
+            "Review the summary for recommendations",
+        );
+        let assessment = annotate(&tender(), &mut cut_off);
+        assert_eq!(
+            assessment.issues,
+            vec![
+                "summary looks truncated",
+                "too few key points",
+                "no clear recommendation",
+                "deadline not mentioned",
+                "value not mentioned",
+            ]
+        );
+        assert!(assessment.flagged());
+        assert_eq!(cut_off.quality_score, Some(assessment.score));
+        assert!(cut_off.processing_notes[0]
+            .starts_with("🧪 LOW QUALITY SUMMARY: 0.29 (summary looks truncated"));
+
+        // Checks for facts the tender doesn't have don't apply
+        let mut no_facts = tender();
+        no_facts.deadline = None;
+        no_facts.value = None;
+        assert_eq!(assess(&no_facts, &cut_off).score, 0.4);
+    }
+
+    #[test]
+    fn test_parse_regeneration() {
+        assert_eq!(Regeneration::parse(None).unwrap(), None);
+        assert_eq!(Regeneration::parse(Some(" ")).unwrap(), None);
+        assert_eq!(
+            Regeneration::parse(Some("0.6")).unwrap(),
+            Some(Regeneration { below: 0.6 })
+        );
+        assert!(Regeneration::parse(Some("1.5")).is_err());
+        assert!(Regeneration::parse(Some("most")).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::types::{AISummaryMessage, TenderRecord};
+use bigdecimal::BigDecimal;
+
+/// Tenant used when no profiles are configured (and for rows written before tenants existed)
+pub const DEFAULT_TENANT: &str = "default";
+
+/// What a watchlist entry matches against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchType {
+    Keyword,
+    ContractingAuthority,
+}
+
+impl WatchType {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "keyword" => Some(WatchType::Keyword),
+            "contracting_authority" | "ca" => Some(WatchType::ContractingAuthority),
+            _ => None,
+        }
+    }
+}
+
+/// Watchlist entry - a keyword or buyer a tenant always wants to hear about
+#[derive(Debug, Clone)]
+pub struct WatchlistEntry {
+    pub watch_type: WatchType,
+    pub value: String,
+    /// Contracting authority rules only: notify on every match, whatever the ML/Claude verdict
+    pub always_notify: bool,
+}
+
+impl WatchlistEntry {
+    fn matches(&self, tender: &TenderRecord) -> bool {
+        let value = self.value.trim().to_lowercase();
+        !value.is_empty()
+            && match self.watch_type {
+                WatchType::Keyword => tender.title.to_lowercase().contains(&value),
+                WatchType::ContractingAuthority => {
+                    tender.contracting_authority.to_lowercase().contains(&value)
+                }
+            }
+    }
+
+    /// Why a notification was sent, for the email
+    pub fn provenance(&self) -> String {
+        format!(
+            "always-notify watch rule: contracting authority matches \"{}\"",
+            self.value.trim()
+        )
+    }
+}
+
+/// Company a tender is evaluated for: its scope, exclusions and notification recipients
+#[derive(Debug, Clone)]
+pub struct CompanyProfile {
+    pub tenant_id: String,
+    pub name: String,
+    /// One line describing the company, used to open the Claude prompt
+    pub description: String,
+    /// Services the company provides, one per line
+    pub scope: String,
+    /// Work the company never bids for, one per line
+    pub exclusions: String,
+    /// Overrides NOTIFICATION_EMAILS for this tenant's notifications when non-empty
+    pub notification_emails: Vec<String>,
+    /// Tenders with a known value below this are not evaluated
+    pub min_value: Option<BigDecimal>,
+    pub watchlist: Vec<WatchlistEntry>,
+}
+
+impl CompanyProfile {
+    /// The original single-company IT consultancy profile
+    pub fn default_profile() -> Self {
+        Self {
+            tenant_id: DEFAULT_TENANT.to_string(),
+            name: "Default".to_string(),
+            description: "an IT SERVICE CONSULTANCY specializing in software development, technical support, and IT systems".to_string(),
+            scope: [
+                "✅ SOFTWARE DEVELOPMENT: Custom applications, web development, mobile apps, databases",
+                "✅ IT CONSULTING: Systems analysis, technical architecture, IT strategy, digital transformation",
+                "✅ TECHNICAL SUPPORT: IT helpdesk, system administration, technical maintenance, user training",
+                "✅ SYSTEMS INTEGRATION: API development, database design, cloud services, software integration",
+                "✅ IT INFRASTRUCTURE: Network setup, server configuration, cybersecurity, IT procurement",
+            ]
+            .join("\n"),
+            exclusions: [
+                "❌ CONSTRUCTION & BUILDING: Any physical building work, renovations, extensions, refurbishments",
+                "❌ CATERING & FOOD: School meals, catering services, food provision, kitchen equipment, dining services",
+                "❌ CLEANING & MAINTENANCE: Cleaning services, grounds maintenance, facilities management, janitorial",
+                "❌ MEDICAL & HEALTHCARE: Medical equipment, healthcare services, clinical supplies, patient care",
+                "❌ PHYSICAL SECURITY: Security guards, CCTV installation, access control systems, patrol services",
+                "❌ UTILITIES & INFRASTRUCTURE: Water, sewerage, electrical installation, plumbing, HVAC, heating",
+                "❌ PROFESSIONAL SERVICES: Legal, accounting, architectural, surveying, HR, non-IT consulting",
+                "❌ SUPPLIES & EQUIPMENT: Office supplies, furniture, vehicles, non-IT equipment, stationery",
+                "❌ TRANSPORT & LOGISTICS: Vehicle services, delivery, transport, fleet management",
+                "❌ WASTE MANAGEMENT: Waste collection, recycling, environmental services",
+            ]
+            .join("\n"),
+            notification_emails: Vec::new(),
+            min_value: None,
+            watchlist: Vec::new(),
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.tenant_id == DEFAULT_TENANT
+    }
+
+    /// Whether the tender's value clears this tenant's minimum (unknown values always do)
+    pub fn accepts_value(&self, tender: &TenderRecord) -> bool {
+        match (&self.min_value, &tender.value) {
+            (Some(min), Some(value)) => value >= min,
+            _ => true,
+        }
+    }
+
+    /// Watchlist entries matching the tender's title or contracting authority
+    pub fn watchlist_matches(&self, tender: &TenderRecord) -> Vec<String> {
+        self.watchlist
+            .iter()
+            .filter(|entry| entry.matches(tender))
+            .map(|entry| entry.value.clone())
+            .collect()
+    }
+
+    /// The first always-notify contracting authority rule matching the tender
+    pub fn always_notify_rule(&self, tender: &TenderRecord) -> Option<&WatchlistEntry> {
+        self.watchlist.iter().find(|entry| {
+            entry.always_notify
+                && entry.watch_type == WatchType::ContractingAuthority
+                && entry.matches(tender)
+        })
+    }
+}
+
+/// Message that evaluates the tender again for the tenant that failed when the others didn't.
+/// The tender has moved past the summary stage by then, so it goes as a refresh. `None` when
+/// more than one tenant failed, as a message names a single tenant
+pub fn retry_message(message: &AISummaryMessage, failed: &[String]) -> Option<AISummaryMessage> {
+    match failed {
+        [tenant] => Some(AISummaryMessage {
+            tenant_id: Some(tenant.clone()),
+            refresh: true,
+            continuation: 0,
+            ..message.clone()
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn tender(title: &str, ca: &str, value: Option<&str>) -> TenderRecord {
+        TenderRecord {
+            resource_id: 1,
+            title: title.to_string(),
+            contracting_authority: ca.to_string(),
+            value: value.map(|v| BigDecimal::from_str(v).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_watchlist_matches_case_insensitively() {
+        let mut profile = CompanyProfile::default_profile();
+        profile.watchlist = vec![
+            WatchlistEntry {
+                watch_type: WatchType::Keyword,
+                value: "Data Platform".to_string(),
+                always_notify: false,
+            },
+            WatchlistEntry {
+                watch_type: WatchType::ContractingAuthority,
+                value: "HSE".to_string(),
+                always_notify: false,
+            },
+        ];
+
+        let t = tender("New data platform build", "Department of Finance", None);
+        assert_eq!(profile.watchlist_matches(&t), vec!["Data Platform"]);
+
+        let t = tender("Helpdesk services", "HSE Ireland", None);
+        assert_eq!(profile.watchlist_matches(&t), vec!["HSE"]);
+    }
+
+    #[test]
+    fn test_always_notify_rule_only_for_flagged_authorities() {
+        let mut profile = CompanyProfile::default_profile();
+        profile.watchlist = vec![
+            WatchlistEntry {
+                watch_type: WatchType::Keyword,
+                value: "Revenue".to_string(),
+                always_notify: true,
+            },
+            WatchlistEntry {
+                watch_type: WatchType::ContractingAuthority,
+                value: "Office of Public Works".to_string(),
+                always_notify: false,
+            },
+            WatchlistEntry {
+                watch_type: WatchType::ContractingAuthority,
+                value: "Revenue Commissioners".to_string(),
+                always_notify: true,
+            },
+        ];
+
+        let t = tender("Office cleaning", "Revenue Commissioners", None);
+        let rule = profile.always_notify_rule(&t).unwrap();
+        assert_eq!(rule.value, "Revenue Commissioners");
+        assert!(rule.provenance().contains("\"Revenue Commissioners\""));
+
+        let t = tender("Revenue system upgrade", "Office of Public Works", None);
+        assert!(profile.always_notify_rule(&t).is_none());
+    }
+
+    #[test]
+    fn test_min_value_skips_only_known_low_values() {
+        let mut profile = CompanyProfile::default_profile();
+        profile.min_value = Some(BigDecimal::from(50000));
+
+        assert!(!profile.accepts_value(&tender("t", "ca", Some("10000"))));
+        assert!(profile.accepts_value(&tender("t", "ca", Some("50000"))));
+        assert!(profile.accepts_value(&tender("t", "ca", None)));
+    }
+
+    #[test]
+    fn test_retry_message_targets_the_one_failed_tenant() {
+        let message = AISummaryMessage {
+            resource_id: "42".to_string(),
+            continuation: 2,
+            ..Default::default()
+        };
+
+        let retry = retry_message(&message, &["acme".to_string()]).unwrap();
+        assert_eq!(retry.resource_id, "42");
+        assert_eq!(retry.tenant_id.as_deref(), Some("acme"));
+        assert!(retry.refresh);
+        assert_eq!(retry.continuation, 0);
+
+        let two = ["acme".to_string(), DEFAULT_TENANT.to_string()];
+        assert!(retry_message(&message, &two).is_none());
+        assert!(retry_message(&message, &[]).is_none());
+    }
+}
@@ -0,0 +1,354 @@
+use crate::database::Database;
+use crate::types::{AISummaryResult, TenderRecord};
+use anyhow::{Context, Result};
+use bigdecimal::BigDecimal;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::info;
+
+/// Default minimum estimated value (EUR) before a bid-preparation ticket is raised
+const DEFAULT_VALUE_THRESHOLD: &str = "100000";
+
+/// Supported issue trackers, selected with TICKET_PROVIDER
+#[derive(Debug, Clone)]
+pub enum TicketBackend {
+    Jira {
+        base_url: String,
+        email: String,
+        api_token: String,
+        project_key: String,
+    },
+    Linear {
+        api_key: String,
+        team_id: String,
+    },
+}
+
+impl TicketBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TicketBackend::Jira { .. } => "jira",
+            TicketBackend::Linear { .. } => "linear",
+        }
+    }
+}
+
+/// Ticket created in the tracker, stored on the tender and linked in the email
+#[derive(Debug, Clone)]
+pub struct Ticket {
+    pub key: String,
+    pub url: String,
+}
+
+/// Creates bid-preparation tickets for high-value BID recommendations
+pub struct TicketService {
+    backend: TicketBackend,
+    http_client: Client,
+    value_threshold: BigDecimal,
+    assignees: Vec<String>,
+}
+
+impl TicketService {
+    /// Build the ticket service from environment; `None` when ticketing is disabled
+    pub fn from_env() -> Result<Option<Self>> {
+        let provider = match std::env::var("TICKET_PROVIDER") {
+            Ok(p) if !p.trim().is_empty() => p.trim().to_lowercase(),
+            _ => return Ok(None),
+        };
+
+        let backend = match provider.as_str() {
+            "jira" => TicketBackend::Jira {
+                base_url: std::env::var("JIRA_BASE_URL")
+                    .context("JIRA_BASE_URL must be set for the jira provider")?
+                    .trim_end_matches('/')
+                    .to_string(),
+                email: std::env::var("JIRA_EMAIL")
+                    .context("JIRA_EMAIL must be set for the jira provider")?,
+                api_token: std::env::var("JIRA_API_TOKEN")
+                    .context("JIRA_API_TOKEN must be set for the jira provider")?,
+                project_key: std::env::var("TICKET_PROJECT")
+                    .context("TICKET_PROJECT must be set to the Jira project key")?,
+            },
+            "linear" => TicketBackend::Linear {
+                api_key: std::env::var("LINEAR_API_KEY")
+                    .context("LINEAR_API_KEY must be set for the linear provider")?,
+                team_id: std::env::var("TICKET_PROJECT")
+                    .context("TICKET_PROJECT must be set to the Linear team id")?,
+            },
+            other => return Err(anyhow::anyhow!("Unsupported TICKET_PROVIDER '{}'", other)),
+        };
+
+        let threshold_str = std::env::var("TICKET_VALUE_THRESHOLD")
+            .unwrap_or_else(|_| DEFAULT_VALUE_THRESHOLD.to_string());
+        let value_threshold = BigDecimal::from_str(threshold_str.trim())
+            .with_context(|| format!("Invalid TICKET_VALUE_THRESHOLD '{}'", threshold_str))?;
+
+        // Jira account ids or Linear user ids, assigned round-robin
+        let assignees: Vec<String> = std::env::var("TICKET_ASSIGNEES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        let http_client = Client::builder().timeout(Duration::from_secs(15)).build()?;
+
+        let service = Self {
+            backend,
+            http_client,
+            value_threshold,
+            assignees,
+        };
+        info!("✅ Ticketing enabled ({})", service.describe());
+        Ok(Some(service))
+    }
+
+    /// Provider, value threshold and assignee count, for logs and the config report
+    pub fn describe(&self) -> String {
+        format!(
+            "{}, threshold: {}, {} assignee(s)",
+            self.backend.name(),
+            self.value_threshold,
+            self.assignees.len()
+        )
+    }
+
+    /// Tenders without an estimated value never qualify
+    pub fn meets_value_threshold(&self, tender: &TenderRecord) -> bool {
+        tender
+            .value
+            .as_ref()
+            .map(|value| *value >= self.value_threshold)
+            .unwrap_or(false)
+    }
+
+    /// Create a ticket for the tender unless one already exists
+    ///
+    /// Returns the ticket (new or existing) when the tender qualifies.
+    pub async fn create_ticket(
+        &self,
+        database: &Database,
+        tender: &TenderRecord,
+        summary_result: &AISummaryResult,
+    ) -> Result<Option<Ticket>> {
+        if !self.meets_value_threshold(tender) {
+            info!(
+                "🎫 Tender {} below ticket value threshold ({}) - no ticket",
+                tender.resource_id, self.value_threshold
+            );
+            return Ok(None);
+        }
+
+        if let Some(existing) = database.get_ticket(tender.resource_id).await? {
+            info!(
+                "🎫 Tender {} already has ticket {}",
+                tender.resource_id, existing.key
+            );
+            return Ok(Some(existing));
+        }
+
+        let assignee = pick_assignee(&self.assignees, database.ticket_count().await?);
+        let title = format!("Bid preparation: {}", tender.title);
+        let description = ticket_description(tender, summary_result);
+
+        let ticket = match &self.backend {
+            TicketBackend::Jira { .. } => {
+                self.create_jira_issue(&title, &description, tender, assignee)
+                    .await?
+            }
+            TicketBackend::Linear { .. } => {
+                self.create_linear_issue(&title, &description, tender, assignee)
+                    .await?
+            }
+        };
+
+        database
+            .store_ticket(tender.resource_id, self.backend.name(), &ticket)
+            .await?;
+
+        info!(
+            "🎫 Created {} ticket {} for tender {} (assignee: {})",
+            self.backend.name(),
+            ticket.key,
+            tender.resource_id,
+            assignee.unwrap_or("unassigned")
+        );
+        Ok(Some(ticket))
+    }
+
+    async fn create_jira_issue(
+        &self,
+        title: &str,
+        description: &str,
+        tender: &TenderRecord,
+        assignee: Option<&str>,
+    ) -> Result<Ticket> {
+        let TicketBackend::Jira {
+            base_url,
+            email,
+            api_token,
+            project_key,
+        } = &self.backend
+        else {
+            unreachable!("create_jira_issue called for non-Jira backend");
+        };
+
+        // v2 accepts a plain-text description (v3 requires Atlassian Document Format)
+        let mut fields = json!({
+            "project": { "key": project_key },
+            "issuetype": { "name": "Task" },
+            "summary": title,
+            "description": description,
+        });
+        if let Some(deadline) = tender.deadline {
+            fields["duedate"] = json!(deadline.format("%Y-%m-%d").to_string());
+        }
+        if let Some(account_id) = assignee {
+            fields["assignee"] = json!({ "id": account_id });
+        }
+
+        let response: Value = self
+            .http_client
+            .post(format!("{}/rest/api/2/issue", base_url))
+            .basic_auth(email, Some(api_token))
+            .json(&json!({ "fields": fields }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let key = response
+            .get("key")
+            .and_then(|v| v.as_str())
+            .context("Jira response missing issue key")?
+            .to_string();
+
+        Ok(Ticket {
+            url: format!("{}/browse/{}", base_url, key),
+            key,
+        })
+    }
+
+    async fn create_linear_issue(
+        &self,
+        title: &str,
+        description: &str,
+        tender: &TenderRecord,
+        assignee: Option<&str>,
+    ) -> Result<Ticket> {
+        let TicketBackend::Linear { api_key, team_id } = &self.backend else {
+            unreachable!("create_linear_issue called for non-Linear backend");
+        };
+
+        let mut input = json!({
+            "teamId": team_id,
+            "title": title,
+            "description": description,
+        });
+        if let Some(deadline) = tender.deadline {
+            input["dueDate"] = json!(deadline.format("%Y-%m-%d").to_string());
+        }
+        if let Some(user_id) = assignee {
+            input["assigneeId"] = json!(user_id);
+        }
+
+        let response: Value = self
+            .http_client
+            .post("https://api.linear.app/graphql")
+            .header("Authorization", api_key)
+            .json(&json!({
+                "query": "mutation IssueCreate($input: IssueCreateInput!) { issueCreate(input: $input) { success issue { identifier url } } }",
+                "variables": { "input": input }
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(errors) = response.get("errors") {
+            return Err(anyhow::anyhow!("Linear API error: {}", errors));
+        }
+
+        let issue = response
+            .pointer("/data/issueCreate/issue")
+            .context("Linear response missing issue")?;
+        let text = |key: &str| {
+            issue
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .with_context(|| format!("Linear issue missing {}", key))
+        };
+
+        Ok(Ticket {
+            key: text("identifier")?,
+            url: text("url")?,
+        })
+    }
+}
+
+/// Round-robin assignee based on how many tickets have been raised so far
+fn pick_assignee(assignees: &[String], tickets_created: i64) -> Option<&str> {
+    if assignees.is_empty() {
+        return None;
+    }
+    let index = tickets_created.rem_euclid(assignees.len() as i64) as usize;
+    Some(assignees[index].as_str())
+}
+
+fn ticket_description(tender: &TenderRecord, summary_result: &AISummaryResult) -> String {
+    let mut description = format!(
+        "Contracting authority: {}\nEstimated value: {}\nDeadline: {}\nRecommendation: {}\n\n{}\n",
+        tender.contracting_authority,
+        tender
+            .value
+            .as_ref()
+            .map(|v| format!("€{}", v))
+            .unwrap_or_else(|| "Not specified".to_string()),
+        tender
+            .deadline
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "Not specified".to_string()),
+        summary_result.recommendation,
+        summary_result.ai_summary
+    );
+
+    if !summary_result.key_points.is_empty() {
+        description.push_str("\nKey points:\n");
+        for point in &summary_result.key_points {
+            description.push_str(&format!("- {}\n", point));
+        }
+    }
+
+    description.push_str(&format!(
+        "\nDocuments:\n- Tender: https://etenders.gov.ie/epps/opportunity/opportunityDetailAction.do?opportunityId={}\n",
+        tender.resource_id
+    ));
+    if !tender.pdf_url.is_empty() {
+        description.push_str(&format!("- PDF: {}\n", tender.pdf_url));
+    }
+
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_assignee_rotates() {
+        let assignees = vec!["alice".to_string(), "bob".to_string()];
+        assert_eq!(pick_assignee(&assignees, 0), Some("alice"));
+        assert_eq!(pick_assignee(&assignees, 1), Some("bob"));
+        assert_eq!(pick_assignee(&assignees, 2), Some("alice"));
+    }
+
+    #[test]
+    fn test_pick_assignee_without_assignees() {
+        assert_eq!(pick_assignee(&[], 5), None);
+    }
+}
@@ -1,85 +1,19 @@
-use bigdecimal::BigDecimal;
-use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+pub use tender_core::{
+    AISummaryMessage, FeatureScores, MLPredictionResult, SNSMessage, TenderRecord,
+};
+
 /// Enum to handle different message types that can be sent to AI Summary Lambda
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum IncomingMessage {
     AISummary(AISummaryMessage),
     TenderRecord(TenderRecord),
 }
 
-/// AI Summary queue message structure (matches ml_bid_predictor)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AISummaryMessage {
-    pub resource_id: String,
-    pub tender_title: String,
-    pub ml_prediction: MLPredictionResult,
-    #[serde(default)]
-    pub pdf_content: String, // May be truncated/empty - we'll fetch full content if needed
-    pub priority: String, // "URGENT" or "NORMAL"
-    pub timestamp: DateTime<Utc>,
-}
-
-/// ML Prediction result structure (matches ml_bid_predictor)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MLPredictionResult {
-    pub should_bid: bool,
-    pub confidence: f64,
-    #[serde(default = "default_reasoning")]
-    pub reasoning: String,
-    pub feature_scores: FeatureScores,
-}
-
-fn default_reasoning() -> String {
-    "No reasoning provided".to_string()
-}
-
-/// Feature scores for transparency and debugging (matches ml_bid_predictor)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FeatureScores {
-    #[serde(default)]
-    pub codes_count_score: f64,
-    #[serde(default)]
-    pub has_codes_score: f64,
-    #[serde(default)]
-    pub title_length_score: f64,
-    #[serde(default)]
-    pub ca_score: f64,
-    #[serde(default)]
-    pub text_features_score: f64,
-    #[serde(default)]
-    pub total_score: f64,
-}
-
-/// Complete tender record from database
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TenderRecord {
-    pub resource_id: i64,
-    pub title: String,
-    pub contracting_authority: String,
-    pub info: String,
-    pub published: Option<NaiveDateTime>,
-    pub deadline: Option<NaiveDateTime>,
-    pub procedure: String,
-    pub status: String,
-    pub pdf_url: String,
-    pub awarddate: Option<NaiveDate>,
-    pub value: Option<BigDecimal>,
-    pub cycle: String,
-    pub bid: Option<i32>,
-    pub pdf_content: Option<String>,
-    pub detected_codes: Option<Vec<String>>,
-    pub codes_count: Option<i32>,
-    pub processing_stage: Option<String>,
-    pub ml_processed: Option<bool>,
-    pub ml_bid: Option<bool>,
-    pub ml_confidence: Option<BigDecimal>,
-    pub ml_reasoning: Option<String>,
-    pub ml_status: Option<String>,
-}
-
 /// PDF content from the pdf_content table
 #[derive(Debug, Clone)]
 pub struct PdfContent {
@@ -88,32 +22,80 @@ pub struct PdfContent {
     pub detected_codes: Vec<String>,
     pub codes_count: i32,
     pub extraction_timestamp: DateTime<Utc>,
+    /// pdf_processing's quality score for the text (0 to 1); None for older rows
+    pub extraction_quality: Option<f32>,
+}
+
+impl From<db::rows::PdfContentRow> for PdfContent {
+    fn from(row: db::rows::PdfContentRow) -> Self {
+        Self {
+            resource_id: row.resource_id,
+            pdf_text: row.pdf_text,
+            detected_codes: row.detected_codes.unwrap_or_default(),
+            codes_count: row.codes_count.unwrap_or(0),
+            extraction_timestamp: row.extraction_timestamp.unwrap_or_default(),
+            extraction_quality: row.extraction_quality,
+        }
+    }
 }
 
 /// AI Summary result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AISummaryResult {
     pub resource_id: i64,
-    pub summary_type: String, // "TITLE_ONLY" or "FULL_PDF"
+    #[serde(default = "default_tenant")]
+    pub tenant_id: String,
+    pub summary_type: String, // "TITLE_ONLY", "FULL_PDF" or "EARLY_INTEREST" (PINs and DPS notices)
     pub ai_summary: String,
+    /// Claude's one-sentence version of the summary, for email previews and chat card
+    /// titles; None for fallback summaries and results from before it was asked for
+    #[serde(default)]
+    pub one_liner: Option<String>,
     pub key_points: Vec<String>,
     pub recommendation: String,
     pub confidence_assessment: String,
     pub processing_notes: Vec<String>,
     pub created_at: DateTime<Utc>,
+    /// `summary_quality::assess` score (0 to 1); None until assessed
+    #[serde(default)]
+    pub quality_score: Option<f32>,
 }
 
-/// SNS message structure for notifications
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SNSMessage {
-    pub message_type: String, // "AI_SUMMARY_COMPLETE"
-    pub resource_id: String,
+fn default_tenant() -> String {
+    crate::tenants::DEFAULT_TENANT.to_string()
+}
+
+/// A detected procurement code with its description from the detection_codes table
+#[derive(Debug, Clone)]
+pub struct CodeDescription {
+    pub code: String,
+    pub description: Option<String>,
+}
+
+/// How often the contracting authority has tendered before, and how often we bid
+#[derive(Debug, Clone)]
+pub struct AuthorityHistory {
+    pub tenders: i64,
+    pub bids: i64,
+    pub last_published: Option<NaiveDateTime>,
+}
+
+/// An earlier, labelled tender from the same contracting authority
+#[derive(Debug, Clone)]
+pub struct SimilarTender {
+    pub resource_id: i64,
     pub title: String,
-    pub priority: String, // "HIGH", "URGENT", "LOW"
-    pub summary: String,
-    pub action_required: String,
-    pub timestamp: DateTime<Utc>,
-    pub metadata: serde_json::Value,
+    pub bid: Option<i32>,
+}
+
+/// Context loaded once per tender and shared by every tenant's prompt
+#[derive(Debug, Clone, Default)]
+pub struct TenderContext {
+    pub codes: Vec<CodeDescription>,
+    pub authority: Option<AuthorityHistory>,
+    pub similar: Vec<SimilarTender>,
+    /// Notes on each part of a document too long for one prompt (see `chunked`)
+    pub document_notes: Option<String>,
 }
 
 /// Configuration from environment
@@ -121,7 +103,6 @@ pub struct SNSMessage {
 pub struct Config {
     pub database_url: String,
     pub anthropic_api_key: String,
-    pub sns_queue_url: String,
 }
 
 impl Config {
@@ -155,23 +136,11 @@ impl Config {
             }
         };
 
-        let sns_queue_url = match std::env::var("SNS_QUEUE_URL") {
-            Ok(url) => {
-                tracing::info!("✓ SNS_QUEUE_URL found (length: {})", url.len());
-                url
-            }
-            Err(e) => {
-                tracing::error!("✗ SNS_QUEUE_URL not found: {:?}", e);
-                return Err(anyhow::anyhow!("SNS_QUEUE_URL not set"));
-            }
-        };
-
         tracing::info!("✅ All configuration loaded successfully");
 
         Ok(Self {
             database_url,
             anthropic_api_key,
-            sns_queue_url,
         })
     }
 }
@@ -0,0 +1,221 @@
+//! Monthly trends for tender_api and the weekly report.
+//!
+//! `analytics_monthly` is a materialized view with one row per (month, contracting
+//! authority). analytics_refresh refreshes it on a schedule, so readers never scan
+//! the base tables. A tender counts as IT when its PDF matched detection codes or the
+//! bid model predicted a bid. "Recommended" is Claude's BID recommendation for the
+//! default tenant, and win rates come from `bid_outcomes`. `win_model` estimates the
+//! chance of winning a single tender from the same outcomes. `public` has the
+//! aggregate-only figures, with small groups suppressed, that can be published externally.
+
+pub mod outcomes;
+pub mod public;
+pub mod win_model;
+
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+
+pub const DEFAULT_MONTHS: u32 = 12;
+pub const MAX_MONTHS: u32 = 60;
+/// Authorities listed by `by_authority` unless the caller asks for more
+pub const DEFAULT_AUTHORITIES: i64 = 20;
+pub const MAX_AUTHORITIES: i64 = 200;
+
+/// Creates `bid_outcomes` too, since the view reads it
+pub async fn ensure_views(pool: &PgPool) -> Result<()> {
+    outcomes::ensure_table(pool).await?;
+
+    db::ensure_schema(pool, "analytics", 1, async {
+        sqlx::query(
+            r#"
+            CREATE MATERIALIZED VIEW IF NOT EXISTS analytics_monthly AS
+            SELECT
+                date_trunc('month', t.published)::DATE AS month,
+                t.ca AS contracting_authority,
+                COUNT(*) AS tenders,
+                COUNT(*) FILTER (WHERE it.is_it) AS it_tenders,
+                COALESCE(SUM(t.value) FILTER (WHERE it.is_it), 0)::FLOAT8 AS it_value_sum,
+                COUNT(t.value) FILTER (WHERE it.is_it) AS it_value_count,
+                COUNT(*) FILTER (
+                    WHERE it.is_it
+                    AND s.recommendation ILIKE '%bid%'
+                    AND s.recommendation NOT ILIKE '%no bid%'
+                ) AS recommended,
+                COUNT(*) FILTER (WHERE t.bid = 1) AS bids,
+                COUNT(*) FILTER (WHERE o.outcome = 'won') AS won,
+                COUNT(*) FILTER (WHERE o.outcome = 'lost') AS lost
+            FROM tender_records t
+            LEFT JOIN pdf_content p ON p.resource_id = t.resource_id
+            LEFT JOIN ai_summaries s ON s.resource_id = t.resource_id AND s.tenant_id = 'default'
+            LEFT JOIN bid_outcomes o ON o.resource_id = t.resource_id
+            CROSS JOIN LATERAL (
+                SELECT COALESCE(p.codes_count, 0) > 0 OR COALESCE(t.ml_bid, FALSE) AS is_it
+            ) it
+            -- Canary tenders have negative ids
+            WHERE t.published IS NOT NULL AND t.resource_id > 0
+            GROUP BY 1, 2
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // REFRESH ... CONCURRENTLY needs a unique index
+        sqlx::query(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_analytics_monthly_key \
+             ON analytics_monthly (month, contracting_authority)",
+        )
+        .execute(pool)
+        .await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Recompute the view; readers keep seeing the old rows until it finishes
+pub async fn refresh(pool: &PgPool) -> Result<()> {
+    sqlx::query("REFRESH MATERIALIZED VIEW CONCURRENTLY analytics_monthly")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// First day of the window of `months` calendar months ending with the current one
+pub fn window_start(today: NaiveDate, months: u32) -> NaiveDate {
+    let first = today.with_day(1).expect("every month has a first day");
+    first
+        .checked_sub_months(chrono::Months::new(months.clamp(1, MAX_MONTHS) - 1))
+        .unwrap_or(first)
+}
+
+/// `numerator / denominator`, or `None` when there is nothing to divide by
+fn ratio(numerator: f64, denominator: i64) -> Option<f64> {
+    (denominator > 0).then(|| numerator / denominator as f64)
+}
+
+/// One month's figures, for all authorities or for one
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MonthlyTrend {
+    pub month: NaiveDate,
+    /// `None` in the all-authority totals
+    pub contracting_authority: Option<String>,
+    pub tenders: i64,
+    pub it_tenders: i64,
+    /// Over IT tenders with a stated value
+    pub avg_it_value: Option<f64>,
+    /// Share of IT tenders Claude recommended bidding on
+    pub recommendation_rate: Option<f64>,
+    pub bids: i64,
+    pub won: i64,
+    pub lost: i64,
+    /// Won / (won + lost); withdrawn bids and bids without an outcome are left out
+    pub win_rate: Option<f64>,
+}
+
+impl MonthlyTrend {
+    fn from_row(row: &PgRow) -> Self {
+        let it_tenders: i64 = row.get("it_tenders");
+        let recommended: i64 = row.get("recommended");
+        let won: i64 = row.get("won");
+        let lost: i64 = row.get("lost");
+
+        Self {
+            month: row.get("month"),
+            contracting_authority: row.get("contracting_authority"),
+            tenders: row.get("tenders"),
+            it_tenders,
+            avg_it_value: ratio(row.get("it_value_sum"), row.get("it_value_count")),
+            recommendation_rate: ratio(recommended as f64, it_tenders),
+            bids: row.get("bids"),
+            won,
+            lost,
+            win_rate: ratio(won as f64, won + lost),
+        }
+    }
+}
+
+/// Totals across authorities per month, oldest first
+pub async fn monthly(pool: &PgPool, since: NaiveDate) -> Result<Vec<MonthlyTrend>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            month,
+            NULL::TEXT AS contracting_authority,
+            SUM(tenders)::BIGINT AS tenders,
+            SUM(it_tenders)::BIGINT AS it_tenders,
+            SUM(it_value_sum)::FLOAT8 AS it_value_sum,
+            SUM(it_value_count)::BIGINT AS it_value_count,
+            SUM(recommended)::BIGINT AS recommended,
+            SUM(bids)::BIGINT AS bids,
+            SUM(won)::BIGINT AS won,
+            SUM(lost)::BIGINT AS lost
+        FROM analytics_monthly
+        WHERE month >= $1
+        GROUP BY month
+        ORDER BY month
+        "#,
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(MonthlyTrend::from_row).collect())
+}
+
+/// Per-month figures for the `limit` authorities with the most IT tenders since `since`,
+/// busiest authority first and oldest month first within it
+pub async fn by_authority(
+    pool: &PgPool,
+    since: NaiveDate,
+    limit: i64,
+) -> Result<Vec<MonthlyTrend>> {
+    let rows = sqlx::query(
+        r#"
+        WITH top AS (
+            SELECT contracting_authority, SUM(it_tenders) AS total
+            FROM analytics_monthly
+            WHERE month >= $1
+            GROUP BY contracting_authority
+            HAVING SUM(it_tenders) > 0
+            ORDER BY total DESC, contracting_authority
+            LIMIT $2
+        )
+        SELECT m.*
+        FROM analytics_monthly m
+        JOIN top ON top.contracting_authority = m.contracting_authority
+        WHERE m.month >= $1
+        ORDER BY top.total DESC, m.contracting_authority, m.month
+        "#,
+    )
+    .bind(since)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(MonthlyTrend::from_row).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_window_start_counts_the_current_month() {
+        assert_eq!(window_start(date(2025, 3, 14), 1), date(2025, 3, 1));
+        assert_eq!(window_start(date(2025, 3, 14), 12), date(2024, 4, 1));
+        assert_eq!(window_start(date(2025, 3, 31), 0), date(2025, 3, 1));
+        assert_eq!(window_start(date(2025, 3, 1), 1000), date(2020, 4, 1));
+    }
+
+    #[test]
+    fn test_ratio_needs_a_denominator() {
+        assert_eq!(ratio(1.0, 4), Some(0.25));
+        assert_eq!(ratio(0.0, 0), None);
+    }
+}
@@ -0,0 +1,154 @@
+//! What happened to the tenders we bid on, kept in `bid_outcomes`.
+//!
+//! The portal's award data isn't loaded. Outcomes are recorded by hand with
+//! `ops_cli outcomes`, or imported from the CRM by webhook_dispatcher. Win rates only
+//! count tenders that have an outcome.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use environment::Environment;
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Won,
+    Lost,
+    /// We bid but pulled out (or the tender was cancelled) before an award
+    Withdrawn,
+}
+
+impl Outcome {
+    /// Value stored in `bid_outcomes.outcome`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Won => "won",
+            Outcome::Lost => "lost",
+            Outcome::Withdrawn => "withdrawn",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "won" => Some(Outcome::Won),
+            "lost" => Some(Outcome::Lost),
+            "withdrawn" => Some(Outcome::Withdrawn),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BidOutcome {
+    pub resource_id: i64,
+    pub title: String,
+    pub contracting_authority: String,
+    pub outcome: Outcome,
+    pub recorded_by: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+pub async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "bid_outcomes", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS bid_outcomes (
+                resource_id BIGINT PRIMARY KEY,
+                outcome TEXT NOT NULL CHECK (outcome IN ('won', 'lost', 'withdrawn')),
+                recorded_by TEXT NOT NULL,
+                recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "bid_outcomes").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Record (or correct) a tender's outcome
+pub async fn record(
+    pool: &PgPool,
+    resource_id: i64,
+    outcome: Outcome,
+    recorded_by: &str,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+        INSERT INTO bid_outcomes (resource_id, outcome, recorded_by)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (resource_id) DO UPDATE SET
+            outcome = EXCLUDED.outcome,
+            recorded_by = EXCLUDED.recorded_by,
+            recorded_at = NOW()
+        "#,
+    )
+    .bind(resource_id)
+    .bind(outcome.as_str())
+    .bind(recorded_by)
+    .execute(&mut *tx)
+    .await?;
+
+    // Having an outcome means we bid; never overwrite a label someone already set
+    sqlx::query("UPDATE tender_records SET bid = 1 WHERE resource_id = $1 AND bid IS NULL")
+        .bind(resource_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// The tender's outcome, if one has been recorded
+pub async fn get(pool: &PgPool, resource_id: i64) -> Result<Option<Outcome>> {
+    let outcome: Option<String> =
+        sqlx::query_scalar("SELECT outcome FROM bid_outcomes WHERE resource_id = $1")
+            .bind(resource_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(outcome.as_deref().and_then(Outcome::parse))
+}
+
+/// Returns false when the tender had no outcome
+pub async fn remove(pool: &PgPool, resource_id: i64) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM bid_outcomes WHERE resource_id = $1")
+        .bind(resource_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Most recently recorded first
+pub async fn recent(pool: &PgPool, limit: i64) -> Result<Vec<BidOutcome>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT o.resource_id, o.outcome, o.recorded_by, o.recorded_at, t.title, t.ca
+        FROM bid_outcomes o
+        JOIN tender_records t ON t.resource_id = o.resource_id
+        ORDER BY o.recorded_at DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(BidOutcome {
+                resource_id: row.get("resource_id"),
+                title: row.get("title"),
+                contracting_authority: row.get("ca"),
+                outcome: Outcome::parse(row.get("outcome"))?,
+                recorded_by: row.get("recorded_by"),
+                recorded_at: row.get("recorded_at"),
+            })
+        })
+        .collect())
+}
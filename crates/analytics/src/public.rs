@@ -0,0 +1,214 @@
+//! Aggregate-only IT tender statistics that are safe to publish outside the company.
+//!
+//! Each breakdown - by month, by CPV division or by contracting authority - counts the IT
+//! tenders (same definition as `analytics_monthly`) published in the window, with the
+//! median stated value. Nothing narrower than a group of `min_cell` tenders is shown:
+//!
+//! - groups with fewer tenders are pooled into one unlabelled cell (`key` null), so a
+//!   small authority's handful of tenders can't be picked out. The pooled cell is left out
+//!   when it is itself under `min_cell`, as one small group would be all it holds
+//! - a median is only given over at least `min_cell` stated values, and is rounded to
+//!   the nearest €1,000, so it never is, or pins down, one tender's value
+//!
+//! `min_cell` comes from PUBLIC_TRENDS_MIN_CELL (default 5, at least 2) and can't be
+//! lowered by the caller. A tender with codes in several CPV divisions counts once in
+//! each; IT tenders without detected codes (bid model only) are left out of that breakdown.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+
+const DEFAULT_MIN_CELL: i64 = 5;
+const MEDIAN_ROUNDING: f64 = 1000.0;
+
+/// Smallest group shown on its own, from PUBLIC_TRENDS_MIN_CELL
+pub fn min_cell_from_env() -> i64 {
+    std::env::var("PUBLIC_TRENDS_MIN_CELL")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_MIN_CELL)
+        .max(2)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    /// `YYYY-MM` of the publication date
+    Month,
+    /// First two digits of the detected CPV codes, e.g. `72` (IT services)
+    CpvDivision,
+    Authority,
+}
+
+impl Dimension {
+    pub const ALL: [Dimension; 3] = [
+        Dimension::Month,
+        Dimension::CpvDivision,
+        Dimension::Authority,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Dimension::Month => "month",
+            Dimension::CpvDivision => "cpv_division",
+            Dimension::Authority => "authority",
+        }
+    }
+
+    /// The group each tender falls in, and any join it needs
+    fn key_sql(self) -> (&'static str, &'static str) {
+        match self {
+            Dimension::Month => ("to_char(t.published, 'YYYY-MM')", ""),
+            Dimension::CpvDivision => (
+                "d.division",
+                "CROSS JOIN LATERAL (\
+                     SELECT DISTINCT left(code, 2) AS division \
+                     FROM unnest(p.detected_codes) AS code\
+                 ) d",
+            ),
+            Dimension::Authority => ("t.ca", ""),
+        }
+    }
+
+    /// Months in order; everything else largest first
+    fn order_sql(self) -> &'static str {
+        match self {
+            Dimension::Month => "key NULLS LAST",
+            _ => "key IS NULL, tenders DESC, key",
+        }
+    }
+}
+
+/// One published figure
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Cell {
+    /// The month, division or authority; null for the cell pooling the small groups
+    pub key: Option<String>,
+    pub tenders: i64,
+    /// Null when fewer than `min_cell` of the tenders state a value
+    pub median_value: Option<f64>,
+    /// Groups too small to show that were pooled into this cell (0 for a named cell)
+    pub pooled_groups: i64,
+}
+
+impl Cell {
+    /// `groups` is how many groups the row covers: 1, or more for the pooled row
+    fn new(
+        key: Option<String>,
+        tenders: i64,
+        groups: i64,
+        valued: i64,
+        median: Option<f64>,
+        min_cell: i64,
+    ) -> Self {
+        let pooled = key.is_none();
+        Self {
+            key,
+            tenders,
+            median_value: median
+                .filter(|_| valued >= min_cell)
+                .map(|median| (median / MEDIAN_ROUNDING).round() * MEDIAN_ROUNDING),
+            pooled_groups: if pooled { groups } else { 0 },
+        }
+    }
+}
+
+/// Drop the pooled cell when it holds fewer than `min_cell` tenders
+fn publishable(cells: Vec<Cell>, min_cell: i64) -> Vec<Cell> {
+    cells
+        .into_iter()
+        .filter(|cell| cell.key.is_some() || cell.tenders >= min_cell)
+        .collect()
+}
+
+/// The breakdown of IT tenders published since `since` by `dimension`
+pub async fn breakdown(
+    pool: &PgPool,
+    since: NaiveDate,
+    dimension: Dimension,
+    min_cell: i64,
+) -> Result<Vec<Cell>> {
+    let (key, join) = dimension.key_sql();
+    let query = format!(
+        r#"
+        WITH base AS (
+            SELECT {key} AS key, t.value::FLOAT8 AS value
+            FROM tender_records t
+            LEFT JOIN pdf_content p ON p.resource_id = t.resource_id
+            {join}
+            -- Canary tenders have negative ids
+            WHERE t.published >= $1 AND t.resource_id > 0
+            AND (COALESCE(p.codes_count, 0) > 0 OR COALESCE(t.ml_bid, FALSE))
+            AND {key} IS NOT NULL
+        ),
+        sizes AS (
+            SELECT key, COUNT(*) AS n FROM base GROUP BY key
+        )
+        SELECT
+            CASE WHEN s.n >= $2 THEN b.key END AS key,
+            COUNT(*) AS tenders,
+            COUNT(DISTINCT b.key) AS groups,
+            COUNT(b.value) AS valued,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY b.value) AS median_value
+        FROM base b
+        JOIN sizes s ON s.key = b.key
+        GROUP BY 1
+        ORDER BY {order}
+        "#,
+        key = key,
+        join = join,
+        order = dimension.order_sql(),
+    );
+    let rows = sqlx::query(&query)
+        .bind(since)
+        .bind(min_cell)
+        .fetch_all(pool)
+        .await?;
+
+    let cells = rows
+        .iter()
+        .map(|row| {
+            Cell::new(
+                row.get("key"),
+                row.get("tenders"),
+                row.get("groups"),
+                row.get("valued"),
+                row.get("median_value"),
+                min_cell,
+            )
+        })
+        .collect();
+    Ok(publishable(cells, min_cell))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_medians_need_enough_values_and_are_rounded() {
+        let cell = Cell::new(Some("72".to_string()), 12, 1, 5, Some(84_499.0), 5);
+        assert_eq!(cell.median_value, Some(84_000.0));
+        assert_eq!(cell.pooled_groups, 0);
+
+        let few_values = Cell::new(Some("72".to_string()), 12, 1, 4, Some(84_499.0), 5);
+        assert_eq!(few_values.median_value, None);
+        assert_eq!(few_values.tenders, 12);
+
+        let pooled = Cell::new(None, 9, 4, 6, Some(12_600.0), 5);
+        assert_eq!(pooled.median_value, Some(13_000.0));
+        assert_eq!(pooled.pooled_groups, 4);
+    }
+
+    #[test]
+    fn test_pooled_cell_under_min_cell_is_left_out() {
+        let named = Cell::new(Some("2025-01".to_string()), 12, 1, 0, None, 5);
+        let small_pool = Cell::new(None, 3, 1, 3, Some(40_000.0), 5);
+        let cells = publishable(vec![named.clone(), small_pool], 5);
+        assert_eq!(cells, vec![named.clone()]);
+
+        let pool = Cell::new(None, 5, 2, 0, None, 5);
+        let cells = publishable(vec![named.clone(), pool.clone()], 5);
+        assert_eq!(cells, vec![named, pool]);
+    }
+}
@@ -0,0 +1,272 @@
+//! Probability of winning a tender if we bid, from the won/lost rows in `bid_outcomes`.
+//!
+//! This answers a different question from the bid model ("is this tender for us?"), so
+//! the email shows it as a separate figure. The model is refitted from the outcomes each
+//! time it is loaded, so it is always current.
+//!
+//! Three factors each get their own win rate:
+//! - the contracting authority's history with us
+//! - the value band
+//! - the procedure, as a competition proxy: open procedures draw more bidders than
+//!   restricted or negotiated ones
+//!
+//! Each rate is smoothed towards the overall win rate, so one or two outcomes don't swing
+//! the estimate. The factors are then combined naive-Bayes style in log-odds. There is
+//! no estimate until `min_outcomes` outcomes have been recorded.
+
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+
+pub const DEFAULT_MIN_OUTCOMES: usize = 20;
+/// Pseudo-outcomes at the overall rate added to each factor's own outcomes
+const PRIOR_STRENGTH: f64 = 5.0;
+/// Never claim certainty either way
+const MIN_PROBABILITY: f64 = 0.02;
+const MAX_PROBABILITY: f64 = 0.98;
+
+/// Value bands (upper bounds, exclusive), as in the anonymized dataset
+const VALUE_BANDS: &[(f64, &str)] = &[
+    (25_000.0, "<25k"),
+    (100_000.0, "25k-100k"),
+    (500_000.0, "100k-500k"),
+    (1_000_000.0, "500k-1m"),
+];
+
+/// `WIN_MODEL_MIN_OUTCOMES`, or the default
+pub fn min_outcomes_from_env() -> usize {
+    std::env::var("WIN_MODEL_MIN_OUTCOMES")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_MIN_OUTCOMES)
+}
+
+pub fn value_band(value: Option<f64>) -> &'static str {
+    match value {
+        None => "unknown",
+        Some(v) => VALUE_BANDS
+            .iter()
+            .find(|(upper, _)| v < *upper)
+            .map(|(_, band)| *band)
+            .unwrap_or(">1m"),
+    }
+}
+
+/// Case and whitespace differences in the portal's names count as the same name
+fn key(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn logit(p: f64) -> f64 {
+    (p / (1.0 - p)).ln()
+}
+
+/// One decided bid (withdrawn bids say nothing about winning)
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub contracting_authority: String,
+    pub value: Option<f64>,
+    pub procedure: String,
+    pub won: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Tally {
+    won: usize,
+    total: usize,
+}
+
+impl Tally {
+    fn add(&mut self, won: bool) {
+        self.total += 1;
+        if won {
+            self.won += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WinEstimate {
+    pub probability: f64,
+    /// Outcomes the model was fitted on
+    pub outcomes: usize,
+    /// One line per factor, for the email
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WinModel {
+    overall: Tally,
+    authorities: HashMap<String, Tally>,
+    value_bands: HashMap<&'static str, Tally>,
+    procedures: HashMap<String, Tally>,
+}
+
+impl WinModel {
+    /// `None` until there are `min_outcomes` samples
+    pub fn fit(samples: &[Sample], min_outcomes: usize) -> Option<Self> {
+        if samples.is_empty() || samples.len() < min_outcomes {
+            return None;
+        }
+
+        let mut model = Self {
+            overall: Tally::default(),
+            authorities: HashMap::new(),
+            value_bands: HashMap::new(),
+            procedures: HashMap::new(),
+        };
+        for sample in samples {
+            model.overall.add(sample.won);
+            model
+                .authorities
+                .entry(key(&sample.contracting_authority))
+                .or_default()
+                .add(sample.won);
+            model
+                .value_bands
+                .entry(value_band(sample.value))
+                .or_default()
+                .add(sample.won);
+            model
+                .procedures
+                .entry(key(&sample.procedure))
+                .or_default()
+                .add(sample.won);
+        }
+        Some(model)
+    }
+
+    pub fn outcomes(&self) -> usize {
+        self.overall.total
+    }
+
+    pub fn estimate(
+        &self,
+        contracting_authority: &str,
+        value: Option<f64>,
+        procedure: &str,
+    ) -> WinEstimate {
+        // Laplace-smoothed so 0 or 100% overall still leaves room to move
+        let base = (self.overall.won as f64 + 1.0) / (self.overall.total as f64 + 2.0);
+        let mut log_odds = logit(base);
+        let mut reasons = vec![format!(
+            "Won {} of {} decided bids overall",
+            self.overall.won, self.overall.total
+        )];
+
+        let band = value_band(value);
+        let factors = [
+            (
+                self.authorities.get(&key(contracting_authority)),
+                format!("with {}", contracting_authority.trim()),
+            ),
+            (
+                self.value_bands.get(band),
+                format!("in the {} value band", band),
+            ),
+            (
+                self.procedures.get(&key(procedure)),
+                format!("under the {} procedure", procedure.trim()),
+            ),
+        ];
+        for (tally, context) in factors {
+            match tally {
+                Some(tally) => {
+                    let rate = (tally.won as f64 + PRIOR_STRENGTH * base)
+                        / (tally.total as f64 + PRIOR_STRENGTH);
+                    log_odds += logit(rate) - logit(base);
+                    reasons.push(format!("Won {} of {} {}", tally.won, tally.total, context));
+                }
+                None => reasons.push(format!("No decided bids {}", context)),
+            }
+        }
+
+        WinEstimate {
+            probability: (1.0 / (1.0 + (-log_odds).exp())).clamp(MIN_PROBABILITY, MAX_PROBABILITY),
+            outcomes: self.overall.total,
+            reasons,
+        }
+    }
+}
+
+/// Fit the model on every won/lost outcome; `None` until there are `min_outcomes`
+pub async fn load(pool: &PgPool, min_outcomes: usize) -> Result<Option<WinModel>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT t.ca, t.value::FLOAT8 AS value, t.procedure, o.outcome = 'won' AS won
+        FROM bid_outcomes o
+        JOIN tender_records t ON t.resource_id = o.resource_id
+        WHERE o.outcome IN ('won', 'lost')
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let samples: Vec<Sample> = rows
+        .iter()
+        .map(|row| Sample {
+            contracting_authority: row.get("ca"),
+            value: row.get("value"),
+            procedure: row.get("procedure"),
+            won: row.get("won"),
+        })
+        .collect();
+    Ok(WinModel::fit(&samples, min_outcomes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(authority: &str, value: f64, won: bool) -> Sample {
+        Sample {
+            contracting_authority: authority.to_string(),
+            value: Some(value),
+            procedure: "Open".to_string(),
+            won,
+        }
+    }
+
+    fn history() -> Vec<Sample> {
+        let mut samples = Vec::new();
+        for i in 0..10 {
+            samples.push(sample("Kerry County Council", 80_000.0, i < 7));
+            samples.push(sample("HSE", 2_000_000.0, i < 1));
+        }
+        samples
+    }
+
+    #[test]
+    fn test_no_model_until_enough_outcomes() {
+        assert!(WinModel::fit(&history(), 21).is_none());
+        assert!(WinModel::fit(&[], 0).is_none());
+        assert_eq!(WinModel::fit(&history(), 20).unwrap().outcomes(), 20);
+    }
+
+    #[test]
+    fn test_history_moves_the_estimate() {
+        let model = WinModel::fit(&history(), 20).unwrap();
+
+        let kerry = model.estimate("kerry  county council", Some(60_000.0), "open");
+        let hse = model.estimate("HSE", Some(1_500_000.0), "Open");
+        let unknown = model.estimate("Galway County Council", None, "Restricted");
+
+        assert!(kerry.probability > 0.6, "{:?}", kerry);
+        assert!(hse.probability < 0.25, "{:?}", hse);
+        // No history on any factor: the overall rate
+        assert!((unknown.probability - 9.0 / 22.0).abs() < 1e-9);
+        assert_eq!(
+            kerry.reasons,
+            vec![
+                "Won 8 of 20 decided bids overall",
+                "Won 7 of 10 with kerry  county council",
+                "Won 7 of 10 in the 25k-100k value band",
+                "Won 8 of 20 under the open procedure",
+            ]
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use environment::Environment;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use serde_json::{json, Value};
+use std::time::Instant;
+use tracing::{error, info, Instrument};
+
+/// Triggered on a schedule (EventBridge, nightly); the event body is not used
+async fn function_handler(_event: LambdaEvent<Value>) -> Result<Value, Error> {
+    info!("=== ANALYTICS REFRESH STARTED ===");
+
+    let database_url = std::env::var("DATABASE_URL").map_err(|_| {
+        error!("Failed to load configuration: DATABASE_URL environment variable not set");
+        Error::from("DATABASE_URL environment variable not set")
+    })?;
+
+    // The view lives on the primary; replicas pick up the refresh through replication
+    let pool = db::connect(&database_url, 1)
+        .await
+        .map_err(|e| Error::from(format!("Failed to connect to database: {}", e).as_str()))?;
+    analytics::ensure_views(&pool)
+        .await
+        .map_err(|e| Error::from(format!("Failed to create analytics views: {}", e).as_str()))?;
+
+    let started = Instant::now();
+    analytics::refresh(&pool)
+        .await
+        .map_err(|e| Error::from(format!("Failed to refresh analytics views: {}", e).as_str()))?;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    info!("📈 analytics_monthly refreshed in {}ms", elapsed_ms);
+
+    info!("=== ANALYTICS REFRESH COMPLETED ===");
+    Ok(json!({ "refreshed": ["analytics_monthly"], "elapsed_ms": elapsed_ms }))
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    let environment = Environment::from_env();
+    run(service_fn(move |event| {
+        function_handler(event).instrument(environment.span())
+    }))
+    .await
+}
@@ -0,0 +1,233 @@
+//! Scheduled job (e.g. nightly) keeping the log tables small.
+//!
+//! Rows older than ARCHIVE_AFTER_MONTHS (default 6) whole months move from each table in
+//! `types::TABLES` to `<table>_archive`, a table with the same columns partitioned by
+//! month (`<table>_archive_YYYY_MM`). Each month moves in one statement, deleted from the
+//! hot table and inserted into its partition together, so a failed run leaves every row
+//! in exactly one place and the next run carries on. An append-only table's move turns on
+//! the setting its trigger checks (`ArchivedTable::unlock`) for that transaction only. The
+//! archive tables can be queried like the originals; a month that is no longer wanted is
+//! dropped as a whole partition.
+
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+use environment::Environment;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+use tracing::{error, info, Instrument};
+
+mod types;
+
+use types::{cutoff, next_month, ArchivedTable, Config, TableReport, TABLES};
+
+/// Triggered on a schedule (EventBridge, nightly); the event body is not used
+async fn function_handler(_event: LambdaEvent<Value>) -> Result<Value, Error> {
+    info!("=== DATA ARCHIVE STARTED ===");
+
+    let config = Config::from_env().map_err(|e| {
+        error!("Failed to load configuration: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+    if config.after_months == 0 {
+        info!("ARCHIVE_AFTER_MONTHS is 0, nothing to archive");
+        return Ok(json!({ "cutoff": null, "tables": [] }));
+    }
+
+    let pool = db::connect(&config.database_url, 1)
+        .await
+        .map_err(|e| Error::from(format!("Failed to connect to database: {}", e).as_str()))?;
+
+    let cutoff = cutoff(Utc::now().date_naive(), config.after_months);
+    info!("Archiving rows from before {}", cutoff);
+
+    let mut reports = Vec::new();
+    for table in TABLES {
+        let report = archive_table(&pool, table, cutoff).await.map_err(|e| {
+            Error::from(format!("Failed to archive {}: {}", table.table, e).as_str())
+        })?;
+        if report.rows > 0 {
+            info!(
+                "🗄️ Moved {} rows from {} to {} ({})",
+                report.rows,
+                table.table,
+                table.archive(),
+                report.months.join(", ")
+            );
+        }
+        reports.push(report);
+    }
+
+    info!("=== DATA ARCHIVE COMPLETED ===");
+    Ok(json!({ "cutoff": cutoff.to_string(), "tables": reports }))
+}
+
+async fn archive_table(
+    pool: &PgPool,
+    table: &ArchivedTable,
+    cutoff: NaiveDate,
+) -> Result<TableReport> {
+    let mut report = TableReport {
+        table: table.table,
+        ..TableReport::default()
+    };
+    if !table_exists(pool, table.table).await? {
+        report.missing = true;
+        return Ok(report);
+    }
+
+    ensure_archive(pool, table).await?;
+    let columns = sync_columns(pool, table).await?;
+
+    let months: Vec<NaiveDate> = sqlx::query_scalar(&format!(
+        "SELECT DISTINCT date_trunc('month', {column})::date AS month FROM {table} WHERE {column} < $1::date ORDER BY month",
+        column = table.column,
+        table = table.table
+    ))
+    .bind(cutoff)
+    .fetch_all(pool)
+    .await?;
+
+    // An archive restored by `ops_cli backup import` is a plain table
+    let partitioned = is_partitioned(pool, &table.archive()).await?;
+
+    for month in months {
+        let end = next_month(month);
+        if partitioned {
+            sqlx::query(&format!(
+                "CREATE TABLE IF NOT EXISTS {} PARTITION OF {} FOR VALUES FROM ('{}') TO ('{}')",
+                table.partition(month),
+                table.archive(),
+                month,
+                end
+            ))
+            .execute(pool)
+            .await?;
+        }
+
+        let mut tx = pool.begin().await?;
+        if let Some(setting) = table.unlock {
+            sqlx::query(&format!("SET LOCAL {} = 'on'", setting))
+                .execute(&mut *tx)
+                .await?;
+        }
+        let moved = sqlx::query(&format!(
+            r#"
+            WITH moved AS (
+                DELETE FROM {table}
+                WHERE {column} >= $1::date AND {column} < $2::date
+                RETURNING {columns}
+            )
+            INSERT INTO {archive} ({columns})
+            SELECT {columns} FROM moved
+            "#,
+            table = table.table,
+            column = table.column,
+            archive = table.archive(),
+            columns = columns
+        ))
+        .bind(month)
+        .bind(end.min(cutoff))
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        report.rows += moved.rows_affected();
+        report.months.push(month.format("%Y-%m").to_string());
+    }
+
+    Ok(report)
+}
+
+/// The archive table, created from the hot table's columns
+async fn ensure_archive(pool: &PgPool, table: &ArchivedTable) -> Result<()> {
+    db::ensure_schema(pool, &format!("data_archive_{}", table.table), 1, async {
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (LIKE {}) PARTITION BY RANGE ({})",
+            table.archive(),
+            table.table,
+            table.column
+        ))
+        .execute(pool)
+        .await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Add any column the hot table gained since the archive was created; returns the hot
+/// table's columns, quoted and comma-separated, for the move
+async fn sync_columns(pool: &PgPool, table: &ArchivedTable) -> Result<String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT a.attname::TEXT AS name,
+               format_type(a.atttypid, a.atttypmod) AS type,
+               EXISTS (
+                   SELECT 1 FROM pg_attribute b
+                   WHERE b.attrelid = to_regclass($2) AND b.attname = a.attname
+                     AND b.attnum > 0 AND NOT b.attisdropped
+               ) AS archived
+        FROM pg_attribute a
+        WHERE a.attrelid = to_regclass($1) AND a.attnum > 0 AND NOT a.attisdropped
+        ORDER BY a.attnum
+        "#,
+    )
+    .bind(table.table)
+    .bind(table.archive())
+    .fetch_all(pool)
+    .await?;
+
+    let mut columns = Vec::new();
+    for row in &rows {
+        let name: String = row.get("name");
+        if !row.get::<bool, _>("archived") {
+            let column_type: String = row.get("type");
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN IF NOT EXISTS \"{}\" {}",
+                table.archive(),
+                name,
+                column_type
+            ))
+            .execute(pool)
+            .await?;
+            info!("Added column {} to {}", name, table.archive());
+        }
+        columns.push(format!("\"{}\"", name));
+    }
+    Ok(columns.join(", "))
+}
+
+async fn is_partitioned(pool: &PgPool, table: &str) -> Result<bool> {
+    Ok(
+        sqlx::query(
+            "SELECT relkind = 'p' AS partitioned FROM pg_class WHERE oid = to_regclass($1)",
+        )
+        .bind(table)
+        .fetch_one(pool)
+        .await?
+        .get("partitioned"),
+    )
+}
+
+async fn table_exists(pool: &PgPool, table: &str) -> Result<bool> {
+    Ok(sqlx::query("SELECT to_regclass($1) IS NOT NULL AS exists")
+        .bind(table)
+        .fetch_one(pool)
+        .await?
+        .get("exists"))
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    let environment = Environment::from_env();
+    run(service_fn(move |event| {
+        function_handler(event).instrument(environment.span())
+    }))
+    .await
+}
@@ -0,0 +1,135 @@
+use chrono::{Datelike, Months, NaiveDate};
+use serde::Serialize;
+
+pub const DEFAULT_AFTER_MONTHS: u32 = 6;
+
+/// A log table whose old rows move to `<table>_archive`, partitioned by month on `column`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArchivedTable {
+    pub table: &'static str,
+    pub column: &'static str,
+    /// Setting turned on for the move, for a table whose trigger otherwise rejects deletes
+    pub unlock: Option<&'static str>,
+}
+
+/// The tables that grow with every tender and are only read recently: webhook deliveries,
+/// the emails sent (repeat checks only need a tender's recent ones), lifecycle transitions
+/// (the per-stage record), the daily Claude call counts and the decision audit trail.
+/// decision_audit is append-only, so its move runs with the setting its trigger allows, and
+/// tender_api's /audit and `ops_cli tail` read the archive as well.
+pub const TABLES: &[ArchivedTable] = &[
+    ArchivedTable {
+        table: "webhook_deliveries",
+        column: "delivered_at",
+        unlock: None,
+    },
+    ArchivedTable {
+        table: "notification_log",
+        column: "sent_at",
+        unlock: None,
+    },
+    ArchivedTable {
+        table: "tender_lifecycle_transitions",
+        column: "recorded_at",
+        unlock: None,
+    },
+    ArchivedTable {
+        table: "claude_usage",
+        column: "day",
+        unlock: None,
+    },
+    ArchivedTable {
+        table: "decision_audit",
+        column: "decided_at",
+        unlock: Some(decision_audit::ARCHIVING_SETTING),
+    },
+];
+
+impl ArchivedTable {
+    pub fn archive(&self) -> String {
+        format!("{}_archive", self.table)
+    }
+
+    /// The archive's partition for the month starting `month`
+    pub fn partition(&self, month: NaiveDate) -> String {
+        format!("{}_archive_{}", self.table, month.format("%Y_%m"))
+    }
+}
+
+/// Rows before this date are archived: the first day of the month `after_months` months
+/// before the current one, so whole months move together
+pub fn cutoff(today: NaiveDate, after_months: u32) -> NaiveDate {
+    let month = today.with_day(1).unwrap_or(today);
+    month
+        .checked_sub_months(Months::new(after_months))
+        .unwrap_or(month)
+}
+
+pub fn next_month(month: NaiveDate) -> NaiveDate {
+    month
+        .checked_add_months(Months::new(1))
+        .unwrap_or(NaiveDate::MAX)
+}
+
+/// Configuration from environment
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database_url: String,
+    /// Rows older than this many whole months are archived; 0 archives nothing
+    pub after_months: u32,
+}
+
+impl Config {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let database_url = std::env::var("DATABASE_URL")
+            .map_err(|_| anyhow::anyhow!("DATABASE_URL environment variable not set"))?;
+
+        let after_months = std::env::var("ARCHIVE_AFTER_MONTHS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_AFTER_MONTHS);
+
+        Ok(Self {
+            database_url,
+            after_months,
+        })
+    }
+}
+
+/// Returned by the lambda so a manual invocation shows what moved
+#[derive(Debug, Default, Serialize)]
+pub struct TableReport {
+    pub table: &'static str,
+    pub rows: u64,
+    /// Months moved, as YYYY-MM
+    pub months: Vec<String>,
+    /// The table doesn't exist in this environment (its lambda hasn't run)
+    pub missing: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_cutoff_is_a_month_start() {
+        assert_eq!(cutoff(date(2026, 10, 16), 6), date(2026, 4, 1));
+        assert_eq!(cutoff(date(2026, 2, 28), 3), date(2025, 11, 1));
+        assert_eq!(cutoff(date(2026, 10, 1), 0), date(2026, 10, 1));
+        assert_eq!(next_month(date(2025, 12, 1)), date(2026, 1, 1));
+    }
+
+    #[test]
+    fn test_partition_names() {
+        let table = TABLES[0];
+        assert_eq!(table.archive(), "webhook_deliveries_archive");
+        assert_eq!(
+            table.partition(date(2026, 3, 1)),
+            "webhook_deliveries_archive_2026_03"
+        );
+    }
+}
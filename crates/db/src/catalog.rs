@@ -0,0 +1,404 @@
+//! What every pipeline table is for, and a generator for the schema docs.
+//!
+//! Each crate creates its own tables with `ensure_schema`, so no single file shows the
+//! whole schema. `registry::TABLES` describes every table and column the pipeline owns;
+//! `introspect` reads the tables a database actually has and `markdown` renders both as
+//! one document with a mermaid ER diagram (`ops_cli`'s `schema_docs` binary). Add a table
+//! or column to the registry in the same change that creates it: `schema_docs --check`
+//! fails while anything in the database is undescribed.
+
+use anyhow::{Context, Result};
+use sqlx::{PgPool, Row};
+use std::fmt::Write;
+
+mod registry;
+
+pub use registry::{COMMON_COLUMNS, TABLES};
+
+/// One table's entry in the registry
+#[derive(Debug, Clone, Copy)]
+pub struct TableDoc {
+    pub name: &'static str,
+    /// Crate(s) that create and write the table
+    pub owner: &'static str,
+    pub description: &'static str,
+    /// Column name and meaning; empty when the columns are those of a table it mirrors
+    pub columns: &'static [(&'static str, &'static str)],
+}
+
+/// A table (or view) as it exists in the database
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveTable {
+    pub name: String,
+    /// `table`, `partitioned table` or `materialized view`
+    pub kind: String,
+    pub columns: Vec<LiveColumn>,
+    pub primary_key: Vec<String>,
+    pub foreign_keys: Vec<ForeignKey>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveColumn {
+    pub name: String,
+    pub data_type: String,
+    pub not_null: bool,
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignKey {
+    pub columns: String,
+    pub references: String,
+}
+
+/// The registry entry for a table
+pub fn lookup(table: &str) -> Option<&'static TableDoc> {
+    TABLES.iter().find(|doc| doc.name == table)
+}
+
+/// The table a monthly archive (`<table>_archive`, see data_archive) holds old rows of
+pub fn archive_source(table: &str) -> Option<&'static TableDoc> {
+    lookup(table.strip_suffix("_archive")?)
+}
+
+/// The meaning of a column. Archives have their table's columns and mirrors with no
+/// columns of their own the mirrored table's (`pdf_content_next` has `pdf_content`'s);
+/// every table has `COMMON_COLUMNS`
+pub fn column_meaning(table: &str, column: &str) -> Option<&'static str> {
+    let columns = match lookup(table) {
+        Some(doc) if doc.columns.is_empty() => table
+            .strip_suffix("_next")
+            .and_then(lookup)
+            .map(|doc| doc.columns)
+            .unwrap_or_default(),
+        Some(doc) => doc.columns,
+        None => archive_source(table)?.columns,
+    };
+    columns
+        .iter()
+        .chain(COMMON_COLUMNS)
+        .find(|(name, _)| *name == column)
+        .map(|(_, meaning)| *meaning)
+}
+
+/// Every table, partitioned table and materialized view in the pool's schema. Partitions
+/// are left out; their parent describes them
+pub async fn introspect(pool: &PgPool) -> Result<Vec<LiveTable>> {
+    let tables = sqlx::query(
+        r#"
+        SELECT c.oid::INT8 AS oid, c.relname::TEXT AS name,
+               CASE c.relkind WHEN 'p' THEN 'partitioned table'
+                              WHEN 'm' THEN 'materialized view'
+                              ELSE 'table' END AS kind
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = current_schema() AND c.relkind IN ('r', 'p', 'm') AND NOT c.relispartition
+        ORDER BY c.relname
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to list tables")?;
+
+    let mut live = Vec::with_capacity(tables.len());
+    for table in &tables {
+        let oid: i64 = table.get("oid");
+        let name: String = table.get("name");
+
+        let columns = sqlx::query(
+            r#"
+            SELECT a.attname::TEXT AS name,
+                   format_type(a.atttypid, a.atttypmod) AS data_type,
+                   a.attnotnull AS not_null,
+                   pg_get_expr(d.adbin, d.adrelid) AS "default"
+            FROM pg_attribute a
+            LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+            WHERE a.attrelid = $1::OID AND a.attnum > 0 AND NOT a.attisdropped
+            ORDER BY a.attnum
+            "#,
+        )
+        .bind(oid)
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to read the columns of {}", name))?
+        .iter()
+        .map(|row| LiveColumn {
+            name: row.get("name"),
+            data_type: row.get("data_type"),
+            not_null: row.get("not_null"),
+            default: row.get("default"),
+        })
+        .collect();
+
+        let constraints = sqlx::query(
+            r#"
+            SELECT c.contype::TEXT AS kind,
+                   c.confrelid::REGCLASS::TEXT AS "references",
+                   (SELECT string_agg(a.attname, ', ' ORDER BY k.n)
+                    FROM unnest(c.conkey) WITH ORDINALITY AS k(attnum, n)
+                    JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum) AS columns
+            FROM pg_constraint c
+            WHERE c.conrelid = $1::OID AND c.contype IN ('p', 'f')
+            ORDER BY c.conname
+            "#,
+        )
+        .bind(oid)
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to read the constraints of {}", name))?;
+
+        let mut primary_key = Vec::new();
+        let mut foreign_keys = Vec::new();
+        for constraint in &constraints {
+            let columns: String = constraint
+                .get::<Option<String>, _>("columns")
+                .unwrap_or_default();
+            if constraint.get::<String, _>("kind") == "p" {
+                primary_key = columns.split(", ").map(str::to_string).collect();
+            } else {
+                foreign_keys.push(ForeignKey {
+                    columns,
+                    references: constraint.get("references"),
+                });
+            }
+        }
+
+        live.push(LiveTable {
+            name,
+            kind: table.get("kind"),
+            columns,
+            primary_key,
+            foreign_keys,
+        });
+    }
+    Ok(live)
+}
+
+/// Tables and columns in the database that the registry doesn't describe, as
+/// `table` or `table.column`
+pub fn undocumented(tables: &[LiveTable]) -> Vec<String> {
+    let mut missing = Vec::new();
+    for table in tables {
+        if lookup(&table.name).is_none() && archive_source(&table.name).is_none() {
+            missing.push(table.name.clone());
+            continue;
+        }
+        for column in &table.columns {
+            if column_meaning(&table.name, &column.name).is_none() {
+                missing.push(format!("{}.{}", table.name, column.name));
+            }
+        }
+    }
+    missing
+}
+
+/// The schema document: an ER diagram, then every table with its columns. Tables join on
+/// `resource_id` without foreign keys, so those links are drawn dashed
+pub fn markdown(schema: &str, tables: &[LiveTable]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Database schema\n");
+    let _ = writeln!(
+        out,
+        "The pipeline's tables in schema `{}`, generated by `schema_docs`. Column meanings come \
+         from the registry in `db::catalog`; edit them there, not here.\n",
+        schema
+    );
+
+    let _ = writeln!(out, "```mermaid\nerDiagram");
+    let has_tenders = tables.iter().any(|table| table.name == "tender_records");
+    for table in tables {
+        for key in &table.foreign_keys {
+            let _ = writeln!(
+                out,
+                "    {} ||--o{{ {} : \"{}\"",
+                key.references, table.name, key.columns
+            );
+        }
+        let by_resource_id = table.columns.iter().any(|c| c.name == "resource_id");
+        if has_tenders && by_resource_id && table.name != "tender_records" {
+            let _ = writeln!(
+                out,
+                "    tender_records ||..o{{ {} : resource_id",
+                table.name
+            );
+        }
+    }
+    for table in tables {
+        let _ = writeln!(out, "    {} {{", table.name);
+        for column in &table.columns {
+            let key = if table.primary_key.contains(&column.name) {
+                " PK"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                out,
+                "        {} {}{}",
+                mermaid_type(&column.data_type),
+                column.name,
+                key
+            );
+        }
+        let _ = writeln!(out, "    }}");
+    }
+    let _ = writeln!(out, "```\n");
+
+    for table in tables {
+        let _ = writeln!(out, "## {}\n", table.name);
+        if let Some(doc) = lookup(&table.name) {
+            let _ = writeln!(out, "{}. Owner: {}.\n", doc.description, doc.owner);
+        } else if let Some(source) = archive_source(&table.name) {
+            let _ = writeln!(
+                out,
+                "Rows of `{}` older than ARCHIVE_AFTER_MONTHS, moved here by data_archive \
+                 and partitioned by month.\n",
+                source.name
+            );
+        } else {
+            let _ = writeln!(out, "*Not in the registry.*\n");
+        }
+        if table.kind != "table" {
+            let _ = writeln!(out, "A {}.\n", table.kind);
+        }
+
+        let _ = writeln!(out, "| Column | Type | Null | Default | Meaning |");
+        let _ = writeln!(out, "|---|---|---|---|---|");
+        for column in &table.columns {
+            let _ = writeln!(
+                out,
+                "| {}{} | {} | {} | {} | {} |",
+                column.name,
+                if table.primary_key.contains(&column.name) {
+                    " (PK)"
+                } else {
+                    ""
+                },
+                cell(&column.data_type),
+                if column.not_null { "no" } else { "yes" },
+                column.default.as_deref().map(cell).unwrap_or_default(),
+                column_meaning(&table.name, &column.name)
+                    .map(cell)
+                    .unwrap_or_default()
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    let absent: Vec<&TableDoc> = TABLES
+        .iter()
+        .filter(|doc| !tables.iter().any(|table| table.name == doc.name))
+        .collect();
+    if !absent.is_empty() {
+        let _ = writeln!(out, "## Not created in this database\n");
+        let _ = writeln!(
+            out,
+            "Each table is created the first time its owner runs, or only while a feature is on.\n"
+        );
+        for doc in absent {
+            let _ = writeln!(out, "- `{}` ({}): {}", doc.name, doc.owner, doc.description);
+        }
+    }
+    out
+}
+
+/// Mermaid attribute types are single words
+fn mermaid_type(data_type: &str) -> String {
+    let base = data_type.split('(').next().unwrap_or(data_type).trim();
+    base.replace(' ', "_").replace("[]", "_array")
+}
+
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, data_type: &str) -> LiveColumn {
+        LiveColumn {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            not_null: name == "id",
+            default: None,
+        }
+    }
+
+    fn table(name: &str, columns: &[(&str, &str)]) -> LiveTable {
+        LiveTable {
+            name: name.to_string(),
+            kind: "table".to_string(),
+            columns: columns.iter().map(|(n, t)| column(n, t)).collect(),
+            primary_key: vec![],
+            foreign_keys: vec![],
+        }
+    }
+
+    #[test]
+    fn test_registry_has_unique_tables() {
+        for (i, doc) in TABLES.iter().enumerate() {
+            assert!(
+                TABLES[i + 1..].iter().all(|other| other.name != doc.name),
+                "{} is registered twice",
+                doc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_undocumented_tables_and_columns() {
+        let tables = vec![
+            table(
+                "tender_records",
+                &[
+                    ("resource_id", "bigint"),
+                    ("environment", "text"),
+                    ("colour", "text"),
+                ],
+            ),
+            table(
+                "webhook_deliveries_archive",
+                &[("delivered_at", "timestamp with time zone")],
+            ),
+            table("pdf_content_next", &[("pdf_text", "text")]),
+            table("scratch", &[("id", "integer")]),
+        ];
+        assert_eq!(
+            undocumented(&tables),
+            vec!["tender_records.colour", "scratch"]
+        );
+    }
+
+    #[test]
+    fn test_markdown_links_tables() {
+        let mut webhooks = table("webhooks", &[("id", "integer"), ("url", "text")]);
+        webhooks.primary_key = vec!["id".to_string()];
+        let mut deliveries = table(
+            "webhook_deliveries",
+            &[("webhook_id", "integer"), ("resource_id", "bigint")],
+        );
+        deliveries.foreign_keys.push(ForeignKey {
+            columns: "webhook_id".to_string(),
+            references: "webhooks".to_string(),
+        });
+        let tables = vec![
+            table(
+                "tender_records",
+                &[
+                    ("resource_id", "bigint"),
+                    ("title", "character varying(500)"),
+                ],
+            ),
+            webhooks,
+            deliveries,
+        ];
+
+        let doc = markdown("dev", &tables);
+        assert!(doc.contains("    webhooks ||--o{ webhook_deliveries : \"webhook_id\""));
+        assert!(doc.contains("    tender_records ||..o{ webhook_deliveries : resource_id"));
+        assert!(doc.contains("        character_varying title\n"));
+        assert!(doc.contains("| id (PK) | integer | no |  | "));
+        assert!(doc.contains("## Not created in this database"));
+        assert!(!doc.contains("- `webhooks`"));
+    }
+}
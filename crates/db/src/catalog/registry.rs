@@ -0,0 +1,487 @@
+use super::TableDoc;
+
+/// Columns every table carries (see `Environment::ensure_environment_column`)
+pub const COMMON_COLUMNS: &[(&str, &str)] = &[(
+    "environment",
+    "Deployment environment (dev, staging, prod) that wrote the row",
+)];
+
+pub const TABLES: &[TableDoc] = &[
+    TableDoc {
+        name: "tender_records",
+        owner: "postgres_dataload",
+        description: "One row per tender from the portal, with the ML prediction and the notification, ticket and CRM state written back by later stages",
+        columns: &[
+            ("id", "Surrogate key"),
+            ("title", "Tender title as listed on the portal"),
+            ("resource_id", "The portal's id for the tender; every other table refers to tenders by it. Canary tenders are negative"),
+            ("ca", "Contracting authority"),
+            ("info", "Description from the tender's detail page, set by the scraper; empty when it couldn't be read"),
+            ("published", "Publication date (portal time)"),
+            ("deadline", "Response deadline (portal time)"),
+            ("procedure", "Procedure as the portal words it (see `pipeline_contract::tender_procedure`)"),
+            ("status", "Normalised status label (Open, Closed, Cancelled, Awarded) or the portal's text"),
+            ("pdf_url", "Tender notice PDF"),
+            ("awarddate", "Award date, once awarded"),
+            ("value", "Estimated value in euro"),
+            ("cycle", "The portal's cycle column"),
+            ("bid", "Training label: 1 bid, 0 not worth bidding, NULL unlabelled"),
+            ("notification_sent", "A notification email went out for the tender"),
+            ("notification_sent_at", "When it went out"),
+            ("created_at", "When dataload first stored the tender"),
+            ("updated_at", "Last change by any stage"),
+            ("ml_processed", "ml_bid_predictor has scored the tender"),
+            ("ml_bid", "The bid model's prediction"),
+            ("ml_confidence", "The bid model's score (0-1)"),
+            ("ml_reasoning", "Why the model scored it so, for the email"),
+            ("ml_status", "ML stage state (pending, completed, ...)"),
+            ("filtered_out_reason", "Why dataload saved the tender without queueing it (status, value or deadline filter)"),
+            ("buyer_contact_name", "Buyer's contact person from the notice page (kept out of Claude prompts)"),
+            ("buyer_contact_email", "Buyer's contact email from the notice page"),
+            ("buyer_contact_phone", "Buyer's contact phone number from the notice page"),
+            ("cpv_codes", "CPV codes the detail page lists, main code first (the buyer's classification, unlike pdf_content.detected_codes)"),
+            ("nuts_codes", "NUTS codes of the place of performance from the detail page"),
+            ("ticket_provider", "Ticketing system a bid ticket was raised in"),
+            ("ticket_key", "The ticket's key"),
+            ("ticket_url", "Link to the ticket"),
+            ("ticket_created_at", "When the ticket was raised"),
+            ("crm_provider", "CRM a deal was opened in (hubspot or dynamics)"),
+            ("crm_deal_id", "The CRM deal or opportunity id"),
+            ("crm_synced_at", "Last CRM import of the deal's stage"),
+        ],
+    },
+    TableDoc {
+        name: "tender_documents",
+        owner: "postgres_dataload",
+        description: "Every document attached to a tender, from the portal's document list",
+        columns: &[
+            ("resource_id", "Tender"),
+            ("position", "Order on the portal"),
+            ("name", "Document name"),
+            ("file_type", "pdf, docx, ..."),
+            ("size_bytes", "Size when the portal gives it"),
+            ("url", "Download link"),
+            ("created_at", "When it was stored"),
+        ],
+    },
+    TableDoc {
+        name: "tender_lots",
+        owner: "postgres_dataload",
+        description: "The lots of tenders divided into lots, from the detail page",
+        columns: &[
+            ("resource_id", "Tender"),
+            ("lot_number", "Lot number"),
+            ("title", "Lot title"),
+            ("created_at", "When it was stored"),
+        ],
+    },
+    TableDoc {
+        name: "pdf_content",
+        owner: "pdf_processing",
+        description: "Text extracted from each tender's PDF, with the detection codes found in it",
+        columns: &[
+            ("resource_id", "Tender"),
+            ("pdf_text", "Extracted text"),
+            ("extraction_timestamp", "When it was extracted"),
+            ("processing_status", "COMPLETED or the failure"),
+            ("metadata", "Extraction details: codes_source, and from pdf_processing the page map (page, start, length), page_count, language, the PDF's title, author and creation date, and each detected code's description, count and positions"),
+            ("detected_codes", "Detection codes found in the text"),
+            ("codes_count", "Number of detected codes"),
+            ("page_offsets", "Where each page starts in pdf_text, for page citations; NULL for older rows"),
+            ("clarification_deadline", "Clarification question deadline found in the text (Irish local time), for reminders"),
+            ("extraction_quality", "Text quality score (0-1); NULL for older rows"),
+            ("extraction_method", "text (the PDF's text layer) or ocr"),
+        ],
+    },
+    TableDoc {
+        name: "pdf_content_next",
+        owner: "pdf_processing",
+        description: "pdf_content with a BIGINT resource_id, dual-written while PDF_CONTENT_MIGRATION is on (see `db::migration`)",
+        columns: &[],
+    },
+    TableDoc {
+        name: "detection_codes",
+        owner: "pdf_processing, ops_cli",
+        description: "Catalogue of codes matched in tender PDFs (`ops_cli codes`)",
+        columns: &[
+            ("code", "The code as matched in the text"),
+            ("description", "Catalogue description, shown in emails"),
+            ("category", "Grouping, e.g. CPV"),
+            ("active", "Inactive codes are not matched"),
+            ("created_at", "When it was added"),
+            ("updated_at", "Last change"),
+        ],
+    },
+    TableDoc {
+        name: "unknown_codes_seen",
+        owner: "pdf_processing",
+        description: "CPV codes declared in tender PDFs that detection_codes doesn't have, listed in the weekly report as candidates",
+        columns: &[
+            ("code", "Eight-digit CPV code, without the check digit"),
+            ("tenders", "Tenders that declared it"),
+            ("first_seen_at", "When it was first seen"),
+            ("last_seen_at", "When it was last seen"),
+            ("last_resource_id", "The latest tender that declared it"),
+        ],
+    },
+    TableDoc {
+        name: "ai_summaries",
+        owner: "ai_summary",
+        description: "Claude's analysis of each tender, per tenant",
+        columns: &[
+            ("resource_id", "Tender"),
+            ("tenant_id", "Whose profile the analysis was written for"),
+            ("summary_type", "FULL_PDF, TITLE_ONLY or EARLY_INTEREST"),
+            ("ai_summary", "The summary text"),
+            ("key_points", "Key points (JSON array)"),
+            ("recommendation", "BID / NO BID (REGISTER INTEREST / NO INTEREST for early-interest notices)"),
+            ("confidence_assessment", "Claude's confidence"),
+            ("processing_notes", "Decision notes (JSON array): watchlist matches, watch rules, second opinions, ..."),
+            ("quality_score", "Heuristic completeness score (0-1, see `ai_summary::summary_quality`); NULL for older rows"),
+            ("created_at", "When it was written"),
+            ("updated_at", "Last rewrite"),
+        ],
+    },
+    TableDoc {
+        name: "ai_summary_cache",
+        owner: "ai_summary",
+        description: "Claude results keyed by prompt version, model and a hash of the inputs, so identical requests aren't paid for twice",
+        columns: &[
+            ("prompt_version", "Prompt version the result was produced with"),
+            ("model", "Claude model"),
+            ("content_hash", "Hash of the prompt inputs"),
+            ("result", "The parsed result"),
+            ("hits", "Times it was reused"),
+            ("created_at", "When it was stored"),
+            ("last_hit_at", "Last reuse"),
+        ],
+    },
+    TableDoc {
+        name: "ai_summary_chunks",
+        owner: "ai_summary",
+        description: "Notes on each part of a long tender document, so a read interrupted at the lambda deadline resumes where it stopped",
+        columns: &[
+            ("content_hash", "Hash of the document, prompt version and model"),
+            ("chunk_index", "Part, from 0"),
+            ("chunk_count", "Parts in the document"),
+            ("resource_id", "Tender the document was read for"),
+            ("notes", "Claude's notes on the part"),
+            ("created_at", "When they were stored"),
+        ],
+    },
+    TableDoc {
+        name: "ai_summary_batch",
+        owner: "ai_summary",
+        description: "Low-priority messages parked by the routing policy for the next batch run",
+        columns: &[
+            ("resource_id", "Tender"),
+            ("priority", "Routing priority"),
+            ("message", "The parked AI summary message"),
+            ("parked_at", "When it was parked"),
+        ],
+    },
+    TableDoc {
+        name: "claude_usage",
+        owner: "ai_summary",
+        description: "Claude calls per day, against the daily budget",
+        columns: &[("day", "Day (UTC)"), ("calls", "Calls made")],
+    },
+    TableDoc {
+        name: "response_skeletons",
+        owner: "ai_summary",
+        description: "Draft bid response outline for BID-recommended tenders, per tenant",
+        columns: &[
+            ("resource_id", "Tender"),
+            ("tenant_id", "Tenant"),
+            ("skeleton", "Sections and requirements (JSON)"),
+            ("prompt_version", "Prompt version"),
+            ("model", "Claude model"),
+            ("created_at", "When it was drafted"),
+        ],
+    },
+    TableDoc {
+        name: "tenants",
+        owner: "ai_summary",
+        description: "Company profiles each tender is evaluated for; the built-in default profile is used when there are none",
+        columns: &[
+            ("tenant_id", "Key"),
+            ("name", "Company name"),
+            ("description", "What the company does, for the prompt"),
+            ("scope", "Work it bids for"),
+            ("exclusions", "Work it never bids for"),
+            ("notification_emails", "Recipients of its notifications"),
+            ("min_value", "Tenders valued below this are not notified"),
+            ("active", "Inactive tenants are skipped"),
+            ("created_at", "When it was added"),
+        ],
+    },
+    TableDoc {
+        name: "tenant_watchlists",
+        owner: "ai_summary",
+        description: "Authorities and keywords a tenant wants flagged",
+        columns: &[
+            ("id", "Key"),
+            ("tenant_id", "Tenant"),
+            ("watch_type", "keyword or contracting_authority"),
+            ("value", "What to match"),
+            ("always_notify", "Notify a match whatever Claude recommends (a watch rule)"),
+            ("created_at", "When it was added"),
+        ],
+    },
+    TableDoc {
+        name: "pipeline_status",
+        owner: "pipeline_status",
+        description: "Each stage's latest attempt per tender, with the message so pipeline_watchdog can requeue a stalled stage",
+        columns: &[
+            ("resource_id", "Tender"),
+            ("stage", "pdf_processing, ml_prediction, ai_summary or notification"),
+            ("status", "started, completed, failed, timed_out, continued, requeued or rejected"),
+            ("attempts", "Times the stage picked the tender up"),
+            ("requeues", "Times pipeline_watchdog requeued it"),
+            ("message", "The stage's input message"),
+            ("last_error", "Error of the last failed or timed-out attempt"),
+            ("started_at", "Start of the current attempt (the stall clock)"),
+            ("completed_at", "When the stage completed"),
+            ("updated_at", "Last change"),
+        ],
+    },
+    TableDoc {
+        name: "tender_lifecycle",
+        owner: "pipeline_status",
+        description: "Each tender's current lifecycle state (scraped -> loaded -> ... -> notified|suppressed -> closed)",
+        columns: &[
+            ("resource_id", "Tender"),
+            ("state", "Current state"),
+            ("updated_by", "Component that moved it there"),
+            ("updated_at", "When"),
+        ],
+    },
+    TableDoc {
+        name: "tender_lifecycle_transitions",
+        owner: "pipeline_status",
+        description: "Every attempted lifecycle transition, accepted or rejected",
+        columns: &[
+            ("id", "Key"),
+            ("resource_id", "Tender"),
+            ("from_state", "State before; NULL for a new tender"),
+            ("to_state", "State requested"),
+            ("actor", "Component that asked"),
+            ("outcome", "accepted, repeated or rejected"),
+            ("recorded_at", "When"),
+        ],
+    },
+    TableDoc {
+        name: "decision_audit",
+        owner: "decision_audit",
+        description: "Append-only record of every automated routing, ML, Claude and notification decision, purge receipts and manual overrides",
+        columns: &[
+            ("id", "Key"),
+            ("resource_id", "Tender"),
+            ("kind", "routing, ml, ai, notification, purge or override"),
+            ("component", "Component that decided"),
+            ("outcome", "What was decided"),
+            ("inputs_hash", "Hash of the decision's inputs"),
+            ("versions", "Model, prompt and config versions in effect"),
+            ("detail", "Decision details (JSON)"),
+            ("decided_at", "When"),
+        ],
+    },
+    TableDoc {
+        name: "notification_log",
+        owner: "sns_notification",
+        description: "Every tender notification emailed, read by the suppression window for repeats",
+        columns: &[
+            ("id", "Key"),
+            ("resource_id", "Tender"),
+            ("tenant_id", "Tenant notified"),
+            ("message_type", "Message type (AI_SUMMARY_COMPLETE, ML_RESULT, ...)"),
+            ("deadline", "Deadline the notification gave"),
+            ("document_hash", "SHA-256 of the PDF text it described; NULL for title-only notifications"),
+            ("reason", "Why it was sent (first, window_passed, deadline_moved, document_changed, no_window)"),
+            ("sent_at", "When it was emailed"),
+        ],
+    },
+    TableDoc {
+        name: "webhooks",
+        owner: "webhook_dispatcher",
+        description: "Registered webhook endpoints for pipeline events",
+        columns: &[
+            ("id", "Key"),
+            ("url", "Endpoint"),
+            ("secret", "HMAC signing secret"),
+            ("event_types", "Events delivered; empty for all"),
+            ("active", "Inactive webhooks get nothing"),
+            ("description", "What it is for"),
+            ("created_at", "When it was registered"),
+        ],
+    },
+    TableDoc {
+        name: "webhook_deliveries",
+        owner: "webhook_dispatcher",
+        description: "Every webhook delivery attempt",
+        columns: &[
+            ("id", "Key"),
+            ("webhook_id", "Webhook"),
+            ("event_type", "Event delivered"),
+            ("resource_id", "Tender"),
+            ("attempt", "Attempt number"),
+            ("status_code", "HTTP status; NULL when no response"),
+            ("success", "Delivered"),
+            ("error", "Why not"),
+            ("duration_ms", "Request time"),
+            ("delivered_at", "When"),
+        ],
+    },
+    TableDoc {
+        name: "tender_feedback",
+        owner: "feedback",
+        description: "Recipients' verdicts from the email feedback links",
+        columns: &[
+            ("resource_id", "Tender"),
+            ("tenant_id", "Tenant whose email it was"),
+            ("verdict", "not_relevant or good_call"),
+            ("created_at", "First verdict"),
+            ("updated_at", "Last change of mind"),
+        ],
+    },
+    TableDoc {
+        name: "tender_tags",
+        owner: "tender_tags",
+        description: "Manual tender tags, controlled vocabulary or free-form",
+        columns: &[
+            ("resource_id", "Tender"),
+            ("tag", "Normalised tag"),
+            ("created_by", "Who tagged it"),
+            ("created_at", "When"),
+        ],
+    },
+    TableDoc {
+        name: "tender_overrides",
+        owner: "tender_overrides",
+        description: "Recommendations forced to BID or NO BID by hand, and snoozed tenders, per tenant",
+        columns: &[
+            ("resource_id", "Tender"),
+            ("tenant_id", "Tenant"),
+            ("recommendation", "bid or no_bid, replacing Claude's; NULL to keep Claude's"),
+            ("snoozed_until", "No emails or reminders until then; NULL when not snoozed"),
+            ("reason", "Why, as given with the latest change"),
+            ("set_by", "Who made the latest change"),
+            ("set_at", "When"),
+        ],
+    },
+    TableDoc {
+        name: "saved_searches",
+        owner: "saved_searches",
+        description: "Users' saved filters, run against newly loaded tenders by saved_search_evaluator",
+        columns: &[
+            ("id", "Key"),
+            ("owner_email", "Who gets the matches"),
+            ("name", "Search name"),
+            ("criteria", "Keywords, authorities, value range and regions (JSON)"),
+            ("active", "Inactive searches are not run"),
+            ("last_evaluated_at", "Tenders loaded after this are new to the search"),
+            ("created_at", "When it was saved"),
+        ],
+    },
+    TableDoc {
+        name: "saved_search_matches",
+        owner: "saved_searches",
+        description: "Tenders already sent to a saved search's owner",
+        columns: &[
+            ("search_id", "Saved search"),
+            ("resource_id", "Tender"),
+            ("notified_at", "When it was emailed"),
+        ],
+    },
+    TableDoc {
+        name: "reminders",
+        owner: "reminders",
+        description: "Scheduled reminder emails about upcoming tender dates, sent by reminder_scheduler",
+        columns: &[
+            ("id", "Key"),
+            ("resource_id", "Tender"),
+            ("tenant_id", "Tenant"),
+            ("kind", "Which date (clarification_deadline)"),
+            ("event_at", "The date itself (Irish local time)"),
+            ("due_at", "When to send"),
+            ("subject", "Email subject"),
+            ("body", "Email body"),
+            ("recipients", "Recipients; the tenant's when empty"),
+            ("sent_at", "When it was sent; NULL until then"),
+            ("cancelled_at", "When a forced NO BID or a snooze past the date dropped it; NULL otherwise"),
+            ("created_at", "When it was scheduled"),
+        ],
+    },
+    TableDoc {
+        name: "bid_outcomes",
+        owner: "analytics",
+        description: "Won, lost and withdrawn bids, recorded by hand or imported from the CRM",
+        columns: &[
+            ("resource_id", "Tender"),
+            ("outcome", "won, lost or withdrawn"),
+            ("recorded_by", "Who or what recorded it"),
+            ("recorded_at", "When"),
+        ],
+    },
+    TableDoc {
+        name: "analytics_monthly",
+        owner: "analytics",
+        description: "Materialized view of monthly volumes, values, BID rate and outcomes per authority, refreshed by analytics_refresh",
+        columns: &[
+            ("month", "Publication month"),
+            ("contracting_authority", "Authority"),
+            ("tenders", "Tenders published"),
+            ("it_tenders", "Of which IT (detected codes or an ML bid)"),
+            ("it_value_sum", "Total value of the IT tenders"),
+            ("it_value_count", "IT tenders with a value"),
+            ("recommended", "IT tenders Claude recommended"),
+            ("bids", "Tenders labelled bid = 1"),
+            ("won", "Bids won"),
+            ("lost", "Bids lost"),
+        ],
+    },
+    TableDoc {
+        name: "ml_feature_snapshots",
+        owner: "feature_drift",
+        description: "The bid model's training feature statistics per model version, captured on feature_drift's first run",
+        columns: &[
+            ("model_version", "Model version"),
+            ("snapshot", "Feature means and standard deviations"),
+            ("created_at", "When it was captured"),
+        ],
+    },
+    TableDoc {
+        name: "canary_runs",
+        owner: "pipeline_canary",
+        description: "Synthetic canary tenders injected by pipeline_canary and how far they got",
+        columns: &[
+            ("resource_id", "Canary tender (negative)"),
+            ("injected_at", "When it was injected"),
+            ("status", "pending, passed or failed"),
+            ("failed_stage", "Stage it didn't get past"),
+            ("resolved_at", "When the run was judged"),
+        ],
+    },
+    TableDoc {
+        name: "schema_versions",
+        owner: "db",
+        description: "Version of each component's DDL applied by `db::ensure_schema`",
+        columns: &[
+            ("component", "Component"),
+            ("version", "DDL version applied"),
+            ("updated_at", "When"),
+        ],
+    },
+    TableDoc {
+        name: "migration_mismatches",
+        owner: "db",
+        description: "Differences between legacy and new tables found while a migration verifies (see `db::migration`)",
+        columns: &[
+            ("id", "Key"),
+            ("component", "Migrating component"),
+            ("row_key", "Row that differed"),
+            ("fields", "Fields that differed"),
+            ("detected_at", "When"),
+        ],
+    },
+];
@@ -0,0 +1,239 @@
+//! Postgres connection pools shared by every lambda.
+//!
+//! `DATABASE_URL` is the primary. Reporting readers (tender_api, sheets_sync,
+//! weekly_report) can be pointed at a read replica with `DATABASE_READ_URL`, so
+//! dashboard and export queries don't compete with pipeline writes. Read pools are
+//! read-only sessions either way, so a reporting query can never write by accident.
+//!
+//! Every pool is pinned to the deployment's schema (see `environment`). Pool sizes and
+//! timeouts default per crate and can be tuned per lambda with `DB_*` variables.
+//! Connecting retries network failures and acquire timeouts (a cold Aurora, a
+//! failover) through `retry::Policy`; bad credentials or URLs fail straight away.
+//!
+//! Table setup goes through `ensure_schema` (see `schema`); tables moving to a new
+//! schema can dual-write and verify through `migration`. `catalog` describes every table
+//! the pipeline owns; `rows` has typed rows of the tables several lambdas read.
+
+use anyhow::Result;
+use environment::Environment;
+use retry::Policy;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::{info, warn};
+
+pub mod catalog;
+pub mod migration;
+pub mod rows;
+pub mod tables;
+mod schema;
+
+pub use schema::ensure_schema;
+
+/// Optional read replica connection string
+pub const READ_URL_VAR: &str = "DATABASE_READ_URL";
+
+/// Pool settings. Each crate passes its own defaults; `DB_*` variables override them
+/// without a code change (see `with_env_overrides`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolSettings {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// How long a query waits for a free connection before failing
+    pub acquire_timeout: Duration,
+    /// Idle connections are closed after this; None keeps them until the pool drops
+    pub idle_timeout: Option<Duration>,
+    /// Prepared statements cached per connection (0 disables, e.g. behind PgBouncer)
+    pub statement_cache_capacity: usize,
+    /// Acquires slower than this are logged as warnings
+    pub slow_acquire_threshold: Duration,
+}
+
+impl PoolSettings {
+    /// Defaults for a lambda: one invocation per instance, frozen between invocations,
+    /// so no connections are kept warm and a stuck acquire fails well inside the timeout
+    pub fn lambda(max_connections: u32) -> Self {
+        Self {
+            max_connections,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(10),
+            idle_timeout: Some(Duration::from_secs(60)),
+            statement_cache_capacity: 100,
+            slow_acquire_threshold: Duration::from_millis(500),
+        }
+    }
+
+    pub fn acquire_timeout(mut self, timeout: Duration) -> Self {
+        self.acquire_timeout = timeout;
+        self
+    }
+
+    /// Apply `DB_MAX_CONNECTIONS`, `DB_MIN_CONNECTIONS`, `DB_ACQUIRE_TIMEOUT_SECS`,
+    /// `DB_IDLE_TIMEOUT_SECS` (0 = never), `DB_STATEMENT_CACHE_CAPACITY` and `DB_SLOW_ACQUIRE_MS`
+    pub fn with_env_overrides(self) -> Self {
+        self.with_overrides(|name| std::env::var(name).ok())
+    }
+
+    fn with_overrides(mut self, lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let number = |name: &str| -> Option<u64> {
+            let value = lookup(name)?;
+            match value.trim().parse() {
+                Ok(number) => Some(number),
+                Err(_) => {
+                    warn!("⚠️ Ignoring {}='{}' - not a whole number", name, value);
+                    None
+                }
+            }
+        };
+
+        if let Some(max) = number("DB_MAX_CONNECTIONS") {
+            self.max_connections = (max as u32).max(1);
+        }
+        if let Some(min) = number("DB_MIN_CONNECTIONS") {
+            self.min_connections = min as u32;
+        }
+        if let Some(secs) = number("DB_ACQUIRE_TIMEOUT_SECS") {
+            self.acquire_timeout = Duration::from_secs(secs);
+        }
+        if let Some(secs) = number("DB_IDLE_TIMEOUT_SECS") {
+            self.idle_timeout = (secs > 0).then(|| Duration::from_secs(secs));
+        }
+        if let Some(capacity) = number("DB_STATEMENT_CACHE_CAPACITY") {
+            self.statement_cache_capacity = capacity as usize;
+        }
+        if let Some(ms) = number("DB_SLOW_ACQUIRE_MS") {
+            self.slow_acquire_threshold = Duration::from_millis(ms);
+        }
+        self.min_connections = self.min_connections.min(self.max_connections);
+        self
+    }
+
+    fn pool_options(&self, options: PgPoolOptions) -> PgPoolOptions {
+        // sqlx times every acquire: all of them at debug, slow ones at warn
+        // (target `sqlx::pool::acquire`), which is where acquire latency shows up
+        options
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .idle_timeout(self.idle_timeout)
+            .acquire_time_level(log::LevelFilter::Debug)
+            .acquire_slow_level(log::LevelFilter::Warn)
+            .acquire_slow_threshold(self.slow_acquire_threshold)
+    }
+
+    async fn connect(&self, options: PgPoolOptions, database_url: &str) -> Result<PgPool> {
+        let connect_options = database_url
+            .parse::<PgConnectOptions>()?
+            .statement_cache_capacity(self.statement_cache_capacity);
+        let pool = Policy::new("postgres_connect")
+            .base_delay(Duration::from_millis(500))
+            .retry_if(is_transient)
+            .run(|| {
+                self.pool_options(options.clone())
+                    .connect_with(connect_options.clone())
+            })
+            .await?;
+        info!(
+            "✅ Database pool ready (max {}, min {}, acquire timeout {:?})",
+            self.max_connections, self.min_connections, self.acquire_timeout
+        );
+        Ok(pool)
+    }
+}
+
+/// Connection failures that may clear on their own
+fn is_transient(e: &sqlx::Error) -> bool {
+    matches!(
+        e,
+        sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::PoolTimedOut
+    )
+}
+
+/// Read-write pool on the primary with the lambda defaults
+pub async fn connect(database_url: &str, max_connections: u32) -> Result<PgPool> {
+    connect_with(database_url, PoolSettings::lambda(max_connections)).await
+}
+
+/// Read-write pool on the primary; `DB_*` variables override `settings`
+pub async fn connect_with(database_url: &str, settings: PoolSettings) -> Result<PgPool> {
+    settings
+        .with_env_overrides()
+        .connect(Environment::from_env().pool_options(), database_url)
+        .await
+}
+
+/// Read-only pool: the replica when `DATABASE_READ_URL` is set, otherwise the primary
+pub async fn connect_read_only(database_url: &str, max_connections: u32) -> Result<PgPool> {
+    PoolSettings::lambda(max_connections)
+        .with_env_overrides()
+        .connect(
+            Environment::from_env().read_only_pool_options(),
+            &read_url(database_url),
+        )
+        .await
+}
+
+fn read_url(database_url: &str) -> String {
+    match replica_url() {
+        Some(url) => {
+            info!("📖 Reads go to the replica ({})", READ_URL_VAR);
+            url
+        }
+        None => database_url.to_string(),
+    }
+}
+
+fn replica_url() -> Option<String> {
+    std::env::var(READ_URL_VAR)
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+}
+
+/// A read pool for queries and a write pool for the few writes a reader makes
+/// (schema setup, feedback). Without a replica both are the same primary pool
+pub struct Pools {
+    pub read: PgPool,
+    pub write: PgPool,
+}
+
+impl Pools {
+    pub async fn connect(database_url: &str, max_connections: u32) -> Result<Self> {
+        let write = connect(database_url, max_connections).await?;
+        let read = if replica_url().is_some() {
+            connect_read_only(database_url, max_connections).await?
+        } else {
+            write.clone()
+        };
+        Ok(Self { read, write })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_env_overrides() {
+        let env: HashMap<&str, &str> = [
+            ("DB_MAX_CONNECTIONS", "3"),
+            ("DB_MIN_CONNECTIONS", "8"),
+            ("DB_IDLE_TIMEOUT_SECS", "0"),
+            ("DB_STATEMENT_CACHE_CAPACITY", "zero"),
+        ]
+        .into_iter()
+        .collect();
+
+        let settings = PoolSettings::lambda(5)
+            .acquire_timeout(Duration::from_secs(5))
+            .with_overrides(|name| env.get(name).map(|v| v.to_string()));
+
+        assert_eq!(settings.max_connections, 3);
+        // Never more idle connections than the pool may hold
+        assert_eq!(settings.min_connections, 3);
+        assert_eq!(settings.idle_timeout, None);
+        // Unparseable values keep the default
+        assert_eq!(settings.statement_cache_capacity, 100);
+        assert_eq!(settings.acquire_timeout, Duration::from_secs(5));
+    }
+}
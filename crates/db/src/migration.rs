@@ -0,0 +1,178 @@
+//! Dual-write and verification for moving a table to a new schema without downtime.
+//!
+//! A storage helper being migrated keeps writing its legacy table and, depending on the
+//! component's mode (`<COMPONENT>_MIGRATION`, e.g. `PDF_CONTENT_MIGRATION`), also writes
+//! the new one:
+//!
+//! - `off` (the default): the legacy table only
+//! - `dual_write`: both tables; everything still reads the legacy one
+//! - `verify`: both tables, and every write is read back from both and compared.
+//!   Differences are logged and kept in `migration_mismatches`
+//!
+//! The cutover is: deploy with `dual_write`, backfill the new table, switch to `verify`
+//! until `migration_mismatches` stays empty, then move the readers over and drop the
+//! legacy writes. Writes to the new table are best-effort and never fail the legacy one.
+
+use anyhow::Result;
+use environment::Environment;
+use serde_json::Value;
+use sqlx::PgPool;
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Off,
+    DualWrite,
+    Verify,
+}
+
+impl Mode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "off" => Some(Mode::Off),
+            "dual_write" => Some(Mode::DualWrite),
+            "verify" => Some(Mode::Verify),
+            _ => None,
+        }
+    }
+
+    /// The mode for `component` from `<COMPONENT>_MIGRATION`; unknown values are off
+    pub fn from_env(component: &str) -> Self {
+        let var = format!("{}_MIGRATION", component.to_uppercase());
+        let value = std::env::var(&var).unwrap_or_default();
+        Self::parse(&value).unwrap_or_else(|| {
+            warn!(
+                "⚠️ Ignoring {}='{}' - expected off, dual_write or verify",
+                var, value
+            );
+            Mode::Off
+        })
+    }
+
+    pub fn writes_new(&self) -> bool {
+        matches!(self, Mode::DualWrite | Mode::Verify)
+    }
+
+    pub fn verifies(&self) -> bool {
+        matches!(self, Mode::Verify)
+    }
+}
+
+pub async fn ensure_mismatch_table(pool: &PgPool) -> Result<()> {
+    crate::ensure_schema(pool, "migration_mismatches", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS migration_mismatches (
+                id BIGSERIAL PRIMARY KEY,
+                component TEXT NOT NULL,
+                row_key TEXT NOT NULL,
+                fields TEXT[] NOT NULL,
+                detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "migration_mismatches").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Fields that differ between two reads of the same row, sorted. A row missing on one
+/// side (JSON null) is reported as `<missing in legacy>` or `<missing in new>`
+pub fn diff(legacy: &Value, new: &Value) -> Vec<String> {
+    match (legacy, new) {
+        (Value::Null, Value::Null) => Vec::new(),
+        (Value::Null, _) => vec!["<missing in legacy>".to_string()],
+        (_, Value::Null) => vec!["<missing in new>".to_string()],
+        (Value::Object(legacy), Value::Object(new)) => {
+            let mut fields: Vec<String> = legacy
+                .keys()
+                .chain(new.keys())
+                .filter(|key| legacy.get(*key) != new.get(*key))
+                .cloned()
+                .collect();
+            fields.sort();
+            fields.dedup();
+            fields
+        }
+        _ if legacy == new => Vec::new(),
+        _ => vec!["<row>".to_string()],
+    }
+}
+
+async fn read(pool: &PgPool, query: &str, key: &str) -> Result<Value> {
+    let row: Option<Value> = sqlx::query_scalar(query)
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.unwrap_or(Value::Null))
+}
+
+/// Read one row from both schemas and compare them. Each query takes the row key as
+/// text (`$1`) and returns a single JSONB column normalised to a shape both schemas share.
+/// Mismatches are logged and recorded; returns the differing fields
+pub async fn verify(
+    pool: &PgPool,
+    component: &str,
+    key: &str,
+    legacy_query: &str,
+    new_query: &str,
+) -> Result<Vec<String>> {
+    let legacy = read(pool, legacy_query, key).await?;
+    let new = read(pool, new_query, key).await?;
+    let fields = diff(&legacy, &new);
+    if !fields.is_empty() {
+        warn!(
+            "🔀 {} migration mismatch for {}: {}",
+            component,
+            key,
+            fields.join(", ")
+        );
+        ensure_mismatch_table(pool).await?;
+        sqlx::query(
+            "INSERT INTO migration_mismatches (component, row_key, fields) VALUES ($1, $2, $3)",
+        )
+        .bind(component)
+        .bind(key)
+        .bind(&fields)
+        .execute(pool)
+        .await?;
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_mode_parse() {
+        assert_eq!(Mode::parse(""), Some(Mode::Off));
+        assert_eq!(Mode::parse(" Dual_Write "), Some(Mode::DualWrite));
+        assert_eq!(Mode::parse("verify"), Some(Mode::Verify));
+        assert_eq!(Mode::parse("on"), None);
+        assert!(Mode::Verify.writes_new() && Mode::Verify.verifies());
+        assert!(Mode::DualWrite.writes_new() && !Mode::DualWrite.verifies());
+        assert!(!Mode::Off.writes_new());
+    }
+
+    #[test]
+    fn test_diff() {
+        let legacy = json!({"resource_id": "5850990", "codes_count": 2, "page_offsets": [0, 812]});
+        assert!(diff(&legacy, &legacy.clone()).is_empty());
+
+        let new = json!({"resource_id": "5850990", "codes_count": 3, "extra": true});
+        assert_eq!(
+            diff(&legacy, &new),
+            vec!["codes_count", "extra", "page_offsets"]
+        );
+
+        assert_eq!(diff(&legacy, &Value::Null), vec!["<missing in new>"]);
+        assert_eq!(diff(&Value::Null, &new), vec!["<missing in legacy>"]);
+        assert!(diff(&Value::Null, &Value::Null).is_empty());
+    }
+}
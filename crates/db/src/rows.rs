@@ -0,0 +1,198 @@
+//! Typed rows of the tables several lambdas read.
+//!
+//! Each struct's fields are the table's column names and types, decoded with
+//! `sqlx::FromRow`, and `select()` builds the column list from the same `COLUMNS`. The
+//! tests check `COLUMNS` against the struct's fields and against `catalog`, so renaming a
+//! column (or misspelling one) fails here rather than as a decode error in production.
+//! A `TenderRow` converts into the pipeline's `tender_core::TenderRecord`; crates convert
+//! other rows into their own types with `From`.
+
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use tender_core::{BuyerContact, TenderRecord};
+
+/// A row of tender_records, as loaded by postgres_dataload and scored by ml_bid_predictor
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+#[cfg_attr(test, derive(serde::Serialize))]
+pub struct TenderRow {
+    pub resource_id: i64,
+    pub title: String,
+    /// Contracting authority
+    pub ca: String,
+    pub info: String,
+    pub published: Option<NaiveDateTime>,
+    pub deadline: Option<NaiveDateTime>,
+    pub procedure: String,
+    pub status: String,
+    pub pdf_url: String,
+    pub awarddate: Option<NaiveDate>,
+    pub value: Option<BigDecimal>,
+    pub cycle: String,
+    pub bid: Option<i32>,
+    pub ml_processed: Option<bool>,
+    pub ml_bid: Option<bool>,
+    pub ml_confidence: Option<BigDecimal>,
+    pub ml_reasoning: Option<String>,
+    pub ml_status: Option<String>,
+    pub buyer_contact_name: Option<String>,
+    pub buyer_contact_email: Option<String>,
+    pub buyer_contact_phone: Option<String>,
+    pub cpv_codes: Option<Vec<String>>,
+    pub nuts_codes: Option<Vec<String>>,
+}
+
+impl TenderRow {
+    pub const TABLE: &'static str = "tender_records";
+    pub const COLUMNS: &'static [&'static str] = &[
+        "resource_id",
+        "title",
+        "ca",
+        "info",
+        "published",
+        "deadline",
+        "procedure",
+        "status",
+        "pdf_url",
+        "awarddate",
+        "value",
+        "cycle",
+        "bid",
+        "ml_processed",
+        "ml_bid",
+        "ml_confidence",
+        "ml_reasoning",
+        "ml_status",
+        "buyer_contact_name",
+        "buyer_contact_email",
+        "buyer_contact_phone",
+        "cpv_codes",
+        "nuts_codes",
+    ];
+
+    /// `SELECT <columns> FROM tender_records`, for the caller's WHERE clause
+    pub fn select() -> String {
+        select(Self::TABLE, Self::COLUMNS)
+    }
+}
+
+/// A stored tender; pdf_processing's fields travel in the queue messages, not the table
+impl From<TenderRow> for TenderRecord {
+    fn from(row: TenderRow) -> Self {
+        Self {
+            resource_id: row.resource_id,
+            title: row.title,
+            contracting_authority: row.ca,
+            info: row.info,
+            published: row.published,
+            deadline: row.deadline,
+            procedure: row.procedure,
+            status: row.status,
+            pdf_url: row.pdf_url,
+            awarddate: row.awarddate,
+            value: row.value,
+            cycle: row.cycle,
+            bid: row.bid,
+            ml_processed: row.ml_processed,
+            ml_bid: row.ml_bid,
+            ml_confidence: row.ml_confidence.and_then(|c| c.to_f64()),
+            ml_reasoning: row.ml_reasoning,
+            ml_status: row.ml_status,
+            buyer_contact: BuyerContact::new(
+                row.buyer_contact_name,
+                row.buyer_contact_email,
+                row.buyer_contact_phone,
+            ),
+            cpv_codes: row.cpv_codes.unwrap_or_default(),
+            nuts_codes: row.nuts_codes.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A row of pdf_content, the text pdf_processing extracted from a tender's PDF
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+#[cfg_attr(test, derive(serde::Serialize))]
+pub struct PdfContentRow {
+    pub resource_id: i64,
+    pub pdf_text: String,
+    pub detected_codes: Option<Vec<String>>,
+    pub codes_count: Option<i32>,
+    pub extraction_timestamp: Option<DateTime<Utc>>,
+    /// Text quality score (0 to 1); None for older rows
+    pub extraction_quality: Option<f32>,
+    /// Clarification question deadline found in the text (Irish local time)
+    pub clarification_deadline: Option<NaiveDateTime>,
+}
+
+impl PdfContentRow {
+    pub const TABLE: &'static str = "pdf_content";
+    pub const COLUMNS: &'static [&'static str] = &[
+        "resource_id",
+        "pdf_text",
+        "detected_codes",
+        "codes_count",
+        "extraction_timestamp",
+        "extraction_quality",
+        "clarification_deadline",
+    ];
+
+    /// `SELECT <columns> FROM pdf_content`, for the caller's WHERE clause
+    pub fn select() -> String {
+        select(Self::TABLE, Self::COLUMNS)
+    }
+}
+
+fn select(table: &str, columns: &[&str]) -> String {
+    format!("SELECT {} FROM {}", columns.join(", "), table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog;
+
+    /// The struct's fields, in order, as FromRow maps them
+    fn fields<T: serde::Serialize>(row: T) -> Vec<String> {
+        match serde_json::to_value(row).unwrap() {
+            serde_json::Value::Object(map) => map.keys().cloned().collect(),
+            other => panic!("not a struct: {}", other),
+        }
+    }
+
+    fn assert_mapping(table: &str, columns: &[&str], fields: Vec<String>) {
+        let mut sorted: Vec<&str> = columns.to_vec();
+        sorted.sort_unstable();
+        let mut fields = fields;
+        fields.sort_unstable();
+        assert_eq!(fields, sorted, "{}: COLUMNS and the struct differ", table);
+
+        for column in columns {
+            assert!(
+                catalog::column_meaning(table, column).is_some(),
+                "{}.{} is not a column in db::catalog",
+                table,
+                column
+            );
+        }
+    }
+
+    #[test]
+    fn test_tender_row_mapping() {
+        assert_mapping(
+            TenderRow::TABLE,
+            TenderRow::COLUMNS,
+            fields(TenderRow::default()),
+        );
+        assert!(TenderRow::select().starts_with("SELECT resource_id, title, ca, info,"));
+        assert!(TenderRow::select().ends_with(" FROM tender_records"));
+    }
+
+    #[test]
+    fn test_pdf_content_row_mapping() {
+        assert_mapping(
+            PdfContentRow::TABLE,
+            PdfContentRow::COLUMNS,
+            fields(PdfContentRow::default()),
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Schema setup that is safe under concurrent cold starts.
+//!
+//! Every lambda creates and alters its tables on start-up. Several instances starting
+//! together used to run the same `CREATE TABLE` / `ALTER TABLE` statements at once and
+//! occasionally deadlocked. `ensure_schema` runs a component's DDL under one shared
+//! advisory lock, and records the component's version in `schema_versions` so later
+//! starts skip the DDL (and the lock) entirely.
+//!
+//! Bump a component's version whenever its DDL changes, or the change never runs
+//! against a database that already has the old version recorded.
+
+use anyhow::Result;
+use environment::Environment;
+use sqlx::{Connection, PgConnection, PgPool};
+use std::future::Future;
+use tracing::info;
+
+/// Advisory lock key shared by all schema setup ("etenders" in ASCII), so DDL on
+/// related tables from different lambdas is serialised too
+const SCHEMA_LOCK_KEY: i64 = 0x6574_656e_6465_7273;
+
+async fn recorded_version(pool: &PgPool, component: &str) -> Option<i32> {
+    // Missing table (first start) reads as no version
+    sqlx::query_scalar("SELECT version FROM schema_versions WHERE component = $1")
+        .bind(component)
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Run `setup` unless `component` is already at `version` (or newer, from a later deploy)
+pub async fn ensure_schema<F>(pool: &PgPool, component: &str, version: i32, setup: F) -> Result<()>
+where
+    F: Future<Output = Result<()>>,
+{
+    if recorded_version(pool, component)
+        .await
+        .is_some_and(|current| current >= version)
+    {
+        return Ok(());
+    }
+
+    // The lock is held on its own connection so `setup` can use the pool even when the
+    // pool only has one connection. If the lambda dies, closing the connection frees the lock
+    let mut lock = PgConnection::connect_with(&pool.connect_options()).await?;
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(SCHEMA_LOCK_KEY)
+        .execute(&mut lock)
+        .await?;
+
+    let result = setup_locked(pool, component, version, setup).await;
+
+    // Best effort - the lock goes with the connection anyway
+    let _ = sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(SCHEMA_LOCK_KEY)
+        .execute(&mut lock)
+        .await;
+    let _ = lock.close().await;
+    result
+}
+
+async fn setup_locked<F>(pool: &PgPool, component: &str, version: i32, setup: F) -> Result<()>
+where
+    F: Future<Output = Result<()>>,
+{
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_versions (
+            component TEXT PRIMARY KEY,
+            version INTEGER NOT NULL,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Environment::ensure_environment_column(pool, "schema_versions").await?;
+
+    // Another instance may have finished while we waited for the lock
+    if recorded_version(pool, component)
+        .await
+        .is_some_and(|current| current >= version)
+    {
+        return Ok(());
+    }
+
+    setup.await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO schema_versions (component, version)
+        VALUES ($1, $2)
+        ON CONFLICT (component) DO UPDATE SET version = EXCLUDED.version, updated_at = NOW()
+        "#,
+    )
+    .bind(component)
+    .bind(version)
+    .execute(pool)
+    .await?;
+
+    info!("🗄️ Schema for {} is at version {}", component, version);
+    Ok(())
+}
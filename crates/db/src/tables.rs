@@ -0,0 +1,199 @@
+//! Setup for the tables more than one lambda writes.
+//!
+//! postgres_dataload and get_data both load `tender_records`; pdf_processing and get_data
+//! both store `pdf_content`. Each table's DDL and version live here only, so a version
+//! bump can't leave one lambda recording a version whose columns it never created.
+
+use crate::ensure_schema;
+use anyhow::Result;
+use environment::Environment;
+use sqlx::PgPool;
+
+/// Create `tender_records`, adding the columns older tables lack
+pub async fn ensure_tender_records(pool: &PgPool) -> Result<()> {
+    ensure_schema(pool, "tender_records", 4, async {
+        // Create tender_records table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS tender_records (
+                id SERIAL PRIMARY KEY,
+                title TEXT NOT NULL,
+                resource_id BIGINT NOT NULL UNIQUE,
+                ca TEXT NOT NULL,
+                info TEXT NOT NULL,
+                published TIMESTAMP WITHOUT TIME ZONE,
+                deadline TIMESTAMP WITHOUT TIME ZONE,
+                procedure TEXT NOT NULL,
+                status TEXT NOT NULL,
+                pdf_url TEXT NOT NULL,
+                awarddate DATE,
+                value DECIMAL(15,2),
+                cycle TEXT NOT NULL,
+                bid INTEGER DEFAULT NULL,
+                notification_sent BOOLEAN DEFAULT FALSE,
+                notification_sent_at TIMESTAMP WITH TIME ZONE DEFAULT NULL,
+                created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+                ml_processed BOOLEAN DEFAULT FALSE,
+                ml_bid BOOLEAN,
+                ml_confidence DECIMAL(5,4),
+                ml_reasoning TEXT,
+                ml_status VARCHAR(20) DEFAULT 'pending',
+                filtered_out_reason TEXT,
+                buyer_contact_name TEXT,
+                buyer_contact_email TEXT,
+                buyer_contact_phone TEXT,
+                cpv_codes TEXT[],
+                nuts_codes TEXT[]
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // Add missing columns if they don't exist (for existing tables that were created before these columns were added)
+        sqlx::query(
+            r#"
+            DO $$
+            BEGIN
+                IF NOT EXISTS (
+                    SELECT 1 FROM information_schema.columns
+                    WHERE table_schema = current_schema() AND table_name='tender_records' AND column_name='notification_sent'
+                ) THEN
+                    ALTER TABLE tender_records ADD COLUMN notification_sent BOOLEAN DEFAULT FALSE;
+                END IF;
+
+                IF NOT EXISTS (
+                    SELECT 1 FROM information_schema.columns
+                    WHERE table_schema = current_schema() AND table_name='tender_records' AND column_name='notification_sent_at'
+                ) THEN
+                    ALTER TABLE tender_records ADD COLUMN notification_sent_at TIMESTAMP WITH TIME ZONE DEFAULT NULL;
+                END IF;
+
+                IF NOT EXISTS (
+                    SELECT 1 FROM information_schema.columns
+                    WHERE table_schema = current_schema() AND table_name='tender_records' AND column_name='updated_at'
+                ) THEN
+                    ALTER TABLE tender_records ADD COLUMN updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP;
+                END IF;
+
+                IF NOT EXISTS (
+                    SELECT 1 FROM information_schema.columns
+                    WHERE table_schema = current_schema() AND table_name='tender_records' AND column_name='ml_processed'
+                ) THEN
+                    ALTER TABLE tender_records ADD COLUMN ml_processed BOOLEAN DEFAULT FALSE;
+                END IF;
+
+                IF NOT EXISTS (
+                    SELECT 1 FROM information_schema.columns
+                    WHERE table_schema = current_schema() AND table_name='tender_records' AND column_name='ml_bid'
+                ) THEN
+                    ALTER TABLE tender_records ADD COLUMN ml_bid BOOLEAN;
+                END IF;
+
+                IF NOT EXISTS (
+                    SELECT 1 FROM information_schema.columns
+                    WHERE table_schema = current_schema() AND table_name='tender_records' AND column_name='ml_confidence'
+                ) THEN
+                    ALTER TABLE tender_records ADD COLUMN ml_confidence DECIMAL(5,4);
+                END IF;
+
+                IF NOT EXISTS (
+                    SELECT 1 FROM information_schema.columns
+                    WHERE table_schema = current_schema() AND table_name='tender_records' AND column_name='ml_reasoning'
+                ) THEN
+                    ALTER TABLE tender_records ADD COLUMN ml_reasoning TEXT;
+                END IF;
+
+                IF NOT EXISTS (
+                    SELECT 1 FROM information_schema.columns
+                    WHERE table_schema = current_schema() AND table_name='tender_records' AND column_name='ml_status'
+                ) THEN
+                    ALTER TABLE tender_records ADD COLUMN ml_status VARCHAR(20) DEFAULT 'pending';
+                END IF;
+            END $$;
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // Set when the prefilter keeps a tender out of the pipeline
+        sqlx::query("ALTER TABLE tender_records ADD COLUMN IF NOT EXISTS filtered_out_reason TEXT")
+            .execute(pool)
+            .await?;
+
+        // The buyer's contact point from the notice page, set by the scraper
+        sqlx::query(
+            r#"
+            ALTER TABLE tender_records
+                ADD COLUMN IF NOT EXISTS buyer_contact_name TEXT,
+                ADD COLUMN IF NOT EXISTS buyer_contact_email TEXT,
+                ADD COLUMN IF NOT EXISTS buyer_contact_phone TEXT
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // The buyer's CPV and NUTS codes from the detail page, set by the scraper
+        sqlx::query(
+            r#"
+            ALTER TABLE tender_records
+                ADD COLUMN IF NOT EXISTS cpv_codes TEXT[],
+                ADD COLUMN IF NOT EXISTS nuts_codes TEXT[]
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "tender_records").await?;
+
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Create `pdf_content`, adding the columns older tables lack
+pub async fn ensure_pdf_content(pool: &PgPool) -> Result<()> {
+    ensure_schema(pool, "pdf_content", 4, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS pdf_content (
+                resource_id BIGINT PRIMARY KEY,
+                pdf_text TEXT NOT NULL,
+                extraction_timestamp TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+                processing_status TEXT NOT NULL,
+                metadata JSONB DEFAULT '{}'::JSONB,
+                detected_codes TEXT[],
+                codes_count INTEGER DEFAULT 0
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // Where each page starts in pdf_text, for page citations; NULL for older rows
+        sqlx::query("ALTER TABLE pdf_content ADD COLUMN IF NOT EXISTS page_offsets INTEGER[]")
+            .execute(pool)
+            .await?;
+
+        // Clarification question deadline found in the text (Irish local time), for reminders
+        sqlx::query(
+            "ALTER TABLE pdf_content ADD COLUMN IF NOT EXISTS clarification_deadline TIMESTAMP",
+        )
+        .execute(pool)
+        .await?;
+
+        // Score from quality::score (NULL for older rows) and "text" or "ocr"
+        sqlx::query("ALTER TABLE pdf_content ADD COLUMN IF NOT EXISTS extraction_quality REAL")
+            .execute(pool)
+            .await?;
+        sqlx::query("ALTER TABLE pdf_content ADD COLUMN IF NOT EXISTS extraction_method TEXT")
+            .execute(pool)
+            .await?;
+
+        Environment::ensure_environment_column(pool, "pdf_content").await?;
+
+        anyhow::Ok(())
+    })
+    .await
+}
@@ -0,0 +1,326 @@
+//! Append-only trail of every automated decision about a tender, kept in `decision_audit`.
+//!
+//! pdf_processing records where it routed the tender, ml_bid_predictor its prediction,
+//! ai_summary Claude's recommendation and whether to notify, and sns_notification whether
+//! the email went out. Each row holds a hash of the decision's inputs and the model,
+//! prompt and config versions that produced it, so "why was this flagged?" can be
+//! answered later even after the prompt or weights have moved on. tender_api serves the
+//! trail at `GET /audit?resource_id=`.
+//!
+//! `ops_cli purge` appends a deletion receipt for each tender it purges. Entries hold only
+//! hashes and verdicts, never tender text, so a purge leaves them in place. Manual
+//! overrides and snoozes (`tender_overrides`) are recorded too, with who set them and why.
+//!
+//! A trigger rejects UPDATE and DELETE on the table, except deleting a canary tender's
+//! rows when pipeline_canary cleans up after a passed run, and data_archive moving old rows
+//! to `decision_audit_archive` (with `ARCHIVING_SETTING` on). `trail` reads both tables.
+//! Recording is best-effort, like
+//! `pipeline_status`: a failure is logged and never fails the decision it describes.
+//! Canary tenders are not recorded.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use environment::Environment;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Row};
+use tracing::warn;
+
+/// Turned on (`SET LOCAL`) by data_archive for the transaction that moves old rows out
+pub const ARCHIVING_SETTING: &str = "decision_audit.archiving";
+
+/// Columns of an entry, in both `decision_audit` and its archive
+const COLUMNS: &str =
+    "id, resource_id, kind, component, outcome, inputs_hash, versions, detail, decided_at";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    /// Which stage a tender goes to next
+    Routing,
+    Ml,
+    Ai,
+    Notification,
+    /// A deletion receipt from `ops_cli purge`
+    Purge,
+    /// A forced recommendation or snooze set by hand (see `tender_overrides`)
+    Override,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 6] = [
+        Kind::Routing,
+        Kind::Ml,
+        Kind::Ai,
+        Kind::Notification,
+        Kind::Purge,
+        Kind::Override,
+    ];
+
+    /// Value stored in `decision_audit.kind`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Routing => "routing",
+            Kind::Ml => "ml",
+            Kind::Ai => "ai",
+            Kind::Notification => "notification",
+            Kind::Purge => "purge",
+            Kind::Override => "override",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == value)
+    }
+}
+
+/// Hex-encoded SHA-256 of the parts, in order
+pub fn hash_inputs(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        // Separator so ("ab", "c") and ("a", "bc") hash differently
+        hasher.update([0]);
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// One decision, built up and then passed to `record`
+#[derive(Debug, Clone)]
+pub struct Decision {
+    resource_id: i64,
+    kind: Kind,
+    /// Lambda that made the decision
+    component: &'static str,
+    outcome: String,
+    inputs_hash: Option<String>,
+    versions: Map<String, Value>,
+    detail: Value,
+}
+
+impl Decision {
+    pub fn new(
+        resource_id: i64,
+        kind: Kind,
+        component: &'static str,
+        outcome: impl Into<String>,
+    ) -> Self {
+        Self {
+            resource_id,
+            kind,
+            component,
+            outcome: outcome.into(),
+            inputs_hash: None,
+            versions: Map::new(),
+            detail: Value::Null,
+        }
+    }
+
+    /// Hash of what the decision was made from (see `hash_inputs`)
+    pub fn inputs(mut self, parts: &[&str]) -> Self {
+        self.inputs_hash = Some(hash_inputs(parts));
+        self
+    }
+
+    /// A model, prompt or config version in effect, e.g. `("prompt", "2")`
+    pub fn version(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.versions
+            .insert(name.to_string(), Value::String(value.into()));
+        self
+    }
+
+    /// Free-form context, e.g. the confidence or the rule that fired
+    pub fn detail(mut self, detail: Value) -> Self {
+        self.detail = detail;
+        self
+    }
+}
+
+/// A recorded decision, as served by tender_api
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub resource_id: i64,
+    pub kind: String,
+    pub component: String,
+    pub outcome: String,
+    pub inputs_hash: Option<String>,
+    pub versions: Value,
+    pub detail: Value,
+    pub decided_at: DateTime<Utc>,
+}
+
+pub async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "decision_audit", 3, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS decision_audit (
+                id BIGSERIAL PRIMARY KEY,
+                resource_id BIGINT NOT NULL,
+                kind TEXT NOT NULL,
+                component TEXT NOT NULL,
+                outcome TEXT NOT NULL,
+                inputs_hash TEXT,
+                versions JSONB NOT NULL DEFAULT '{}',
+                detail JSONB NOT NULL DEFAULT 'null',
+                decided_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_decision_audit_resource ON decision_audit (resource_id, decided_at)",
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(&format!(
+            r#"
+            CREATE OR REPLACE FUNCTION decision_audit_append_only() RETURNS trigger AS $$
+            BEGIN
+                -- Canary tenders (negative ids) are removed once their run passes
+                IF TG_OP = 'DELETE' AND OLD.resource_id < 0 THEN
+                    RETURN OLD;
+                END IF;
+                -- data_archive moving old rows to decision_audit_archive
+                IF TG_OP = 'DELETE' AND current_setting('{setting}', true) = 'on' THEN
+                    RETURN OLD;
+                END IF;
+                RAISE EXCEPTION 'decision_audit is append-only';
+            END;
+            $$ LANGUAGE plpgsql
+            "#,
+            setting = ARCHIVING_SETTING
+        ))
+        .execute(pool)
+        .await?;
+        sqlx::query("DROP TRIGGER IF EXISTS decision_audit_append_only ON decision_audit")
+            .execute(pool)
+            .await?;
+        sqlx::query(
+            r#"
+            CREATE TRIGGER decision_audit_append_only
+            BEFORE UPDATE OR DELETE ON decision_audit
+            FOR EACH ROW EXECUTE FUNCTION decision_audit_append_only()
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "decision_audit").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Append the decision, returning any error (for callers where a missing entry matters)
+pub async fn append(pool: &PgPool, decision: &Decision) -> Result<()> {
+    ensure_table(pool).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO decision_audit
+            (resource_id, kind, component, outcome, inputs_hash, versions, detail)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(decision.resource_id)
+    .bind(decision.kind.name())
+    .bind(decision.component)
+    .bind(&decision.outcome)
+    .bind(&decision.inputs_hash)
+    .bind(Value::Object(decision.versions.clone()))
+    .bind(&decision.detail)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Append the decision; failures are logged, never returned
+pub async fn record(pool: &PgPool, decision: &Decision) {
+    if environment::is_canary(decision.resource_id) {
+        return;
+    }
+
+    if let Err(e) = append(pool, decision).await {
+        warn!(
+            "⚠️ Failed to audit {} decision for {}: {}",
+            decision.kind.name(),
+            decision.resource_id,
+            e
+        );
+    }
+}
+
+/// Every decision recorded for the tender, oldest first, including those data_archive has
+/// moved to `decision_audit_archive`
+pub async fn trail(pool: &PgPool, resource_id: i64) -> Result<Vec<AuditEntry>> {
+    let archived: bool =
+        sqlx::query_scalar("SELECT to_regclass('decision_audit_archive') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+    let archive = if archived {
+        format!(
+            "SELECT {} FROM decision_audit_archive WHERE resource_id = $1 UNION ALL ",
+            COLUMNS
+        )
+    } else {
+        String::new()
+    };
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT * FROM (
+            {archive}SELECT {columns} FROM decision_audit WHERE resource_id = $1
+        ) entries
+        ORDER BY decided_at, id
+        "#,
+        archive = archive,
+        columns = COLUMNS
+    ))
+    .bind(resource_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| AuditEntry {
+            id: row.get("id"),
+            resource_id: row.get("resource_id"),
+            kind: row.get("kind"),
+            component: row.get("component"),
+            outcome: row.get("outcome"),
+            inputs_hash: row.get("inputs_hash"),
+            versions: row.get("versions"),
+            detail: row.get("detail"),
+            decided_at: row.get("decided_at"),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_names_round_trip() {
+        for kind in Kind::ALL {
+            assert_eq!(Kind::parse(kind.name()), Some(kind));
+        }
+        assert_eq!(Kind::parse("routing_error"), None);
+    }
+
+    #[test]
+    fn test_inputs_hash_separates_parts() {
+        assert_eq!(hash_inputs(&["ab", "c"]), hash_inputs(&["ab", "c"]));
+        assert_ne!(hash_inputs(&["ab", "c"]), hash_inputs(&["a", "bc"]));
+        assert_eq!(hash_inputs(&[]).len(), 64);
+
+        let decision = Decision::new(1, Kind::Ml, "ml_bid_predictor", "bid")
+            .inputs(&["title", "text"])
+            .version("model", "v1");
+        assert_eq!(decision.inputs_hash, Some(hash_inputs(&["title", "text"])));
+        assert_eq!(decision.versions["model"], "v1");
+    }
+}
@@ -0,0 +1,382 @@
+//! eTenders alert emails read into the scraper's tender records.
+//!
+//! The portal's saved-search alerts list each new opportunity as a few "Label: value"
+//! lines (or table rows in the HTML version) and a link to it, which carries the resource
+//! id. A record starts at each title, or at a link to a tender not seen yet, and takes
+//! the labelled fields that follow. Records without a resource id are dropped: everything
+//! downstream is keyed on it.
+//!
+//! Alerts carry less than the search results page, so info, cycle and the award date are
+//! left empty and the status is Open unless the alert says otherwise. A date without a
+//! time is taken as midnight.
+
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDate, NaiveDateTime};
+use pipeline_contract::tender_status::{self, TenderStatus};
+use regex::Regex;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+use crate::mime::Bodies;
+
+const PORTAL: &str = "https://www.etenders.gov.ie";
+
+static RESOURCE_ID: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)(?:resourceId|opportunityId)=(\d+)").unwrap());
+static URL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"https?://\S+").unwrap());
+static LABELLED: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*([A-Za-z][A-Za-z /]*?)\s*[:\t][\s:]*(.*?)\s*$").unwrap());
+static DATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\d{1,2}/\d{1,2}/\d{4}(?:\s+\d{1,2}:\d{2}(?::\d{2})?)?").unwrap());
+static AMOUNT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d[\d,]*(?:\.\d+)?").unwrap());
+
+/// The scraper's queue message, which postgres_dataload reads
+pub use tender_core::TenderRecord;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Title,
+    ResourceId,
+    ContractingAuthority,
+    Published,
+    Deadline,
+    Procedure,
+    Status,
+    Value,
+}
+
+impl Field {
+    fn from_label(label: &str) -> Option<Self> {
+        Some(match label.to_lowercase().as_str() {
+            "title" | "tender title" | "opportunity title" | "contract title" => Field::Title,
+            "resource id" | "tender id" | "opportunity id" => Field::ResourceId,
+            "contracting authority" | "authority" | "buyer" | "ca" => Field::ContractingAuthority,
+            "published" | "date published" | "publication date" => Field::Published,
+            "deadline"
+            | "tender deadline"
+            | "response deadline"
+            | "submission deadline"
+            | "tender submission deadline"
+            | "closing date" => Field::Deadline,
+            "procedure" | "procedure type" => Field::Procedure,
+            "status" => Field::Status,
+            "value" | "estimated value" | "estimated contract value" => Field::Value,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Fields {
+    title: String,
+    resource_id: Option<i64>,
+    contracting_authority: String,
+    published: Option<NaiveDateTime>,
+    deadline: Option<NaiveDateTime>,
+    procedure: String,
+    status: String,
+    value: Option<BigDecimal>,
+}
+
+impl Fields {
+    fn set(&mut self, field: Field, value: &str) {
+        match field {
+            Field::Title => self.title = value.to_string(),
+            Field::ResourceId => {
+                if let Ok(id) = value.trim().parse() {
+                    self.resource_id = Some(id);
+                }
+            }
+            Field::ContractingAuthority => self.contracting_authority = value.to_string(),
+            Field::Published => self.published = parse_datetime(value),
+            Field::Deadline => self.deadline = parse_datetime(value),
+            Field::Procedure => self.procedure = value.to_string(),
+            Field::Status => self.status = value.to_string(),
+            Field::Value => self.value = parse_value(value),
+        }
+    }
+
+    fn into_record(self) -> Option<TenderRecord> {
+        let resource_id = self.resource_id?;
+        let status = if self.status.is_empty() {
+            "Open"
+        } else {
+            &self.status
+        };
+        Some(TenderRecord {
+            title: self.title,
+            resource_id,
+            contracting_authority: self.contracting_authority,
+            published: self.published,
+            deadline: self.deadline,
+            procedure: self.procedure,
+            status: normalize_status(status),
+            pdf_url: notice_url(resource_id),
+            value: self.value,
+            ..Default::default()
+        })
+    }
+}
+
+/// Same notice link as the scraper's
+pub fn notice_url(resource_id: i64) -> String {
+    format!(
+        "{}/epps/cft/downloadNoticeForAdvSearch.do?resourceId={}",
+        PORTAL, resource_id
+    )
+}
+
+/// Whether the From header names one of the allowed senders: an address, or a domain
+/// and its subdomains
+pub fn is_allowed_sender(from: &str, allowed: &[String]) -> bool {
+    let address = match (from.rfind('<'), from.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &from[start + 1..end],
+        _ => from,
+    }
+    .trim()
+    .to_lowercase();
+    let Some((_, domain)) = address.rsplit_once('@') else {
+        return false;
+    };
+
+    allowed.iter().any(|sender| {
+        let sender = sender.trim().to_lowercase();
+        if sender.contains('@') {
+            address == sender
+        } else {
+            domain == sender || domain.ends_with(&format!(".{}", sender))
+        }
+    })
+}
+
+/// The alert's text: the plain-text body, else the HTML body as text
+pub fn alert_text(bodies: Bodies) -> Option<String> {
+    bodies
+        .text
+        .or_else(|| bodies.html.as_deref().map(html_to_text))
+}
+
+/// HTML as lines of text; table cells are tab-separated and links keep their URL after
+/// the link text
+pub fn html_to_text(html: &str) -> String {
+    static HIDDEN: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"(?is)<style[^>]*>.*?</style>|<script[^>]*>.*?</script>").unwrap()
+    });
+    static ANCHOR: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r#"(?is)<a\s[^>]*?href\s*=\s*["']([^"']*)["'][^>]*>(.*?)</a>"#).unwrap()
+    });
+    static BREAK: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"(?i)<br\s*/?>|</(?:p|div|tr|li|h[1-6]|table)>").unwrap());
+    static CELL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)</t[dh]>").unwrap());
+    static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+
+    let text = HIDDEN.replace_all(html, "");
+    let text = ANCHOR.replace_all(&text, "$2 $1");
+    let text = BREAK.replace_all(&text, "\n");
+    let text = CELL.replace_all(&text, "\t");
+    let text = TAG.replace_all(&text, "");
+    text.replace("&nbsp;", " ")
+        .replace("&euro;", "€")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .lines()
+        .map(|line| {
+            line.split('\t')
+                .map(|cell| cell.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|cell| !cell.is_empty())
+                .collect::<Vec<_>>()
+                .join("\t")
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The tenders listed in an alert, each once
+pub fn parse(text: &str) -> Vec<TenderRecord> {
+    let mut listed: Vec<Fields> = Vec::new();
+    for line in text.lines() {
+        let labelled = LABELLED.captures(line).and_then(|captures| {
+            let field = Field::from_label(&captures[1])?;
+            let value = captures[2].to_string();
+            (!value.is_empty()).then_some((field, value))
+        });
+
+        match labelled {
+            Some((Field::Title, title)) => {
+                let mut fields = Fields::default();
+                fields.set(Field::Title, URL.replace_all(&title, "").trim());
+                fields.resource_id = resource_id_in(line);
+                listed.push(fields);
+            }
+            Some((field, value)) => {
+                if let Some(fields) = listed.last_mut() {
+                    fields.set(field, &value);
+                }
+            }
+            None => {
+                let Some(resource_id) = resource_id_in(line) else {
+                    continue;
+                };
+                match listed.last_mut() {
+                    Some(fields) if fields.resource_id.is_none() => {
+                        fields.resource_id = Some(resource_id)
+                    }
+                    Some(fields) if fields.resource_id == Some(resource_id) => {}
+                    _ => {
+                        // A bare link to another tender, titled by its link text
+                        let title = URL.replace_all(line, "").trim().to_string();
+                        listed.push(Fields {
+                            title,
+                            resource_id: Some(resource_id),
+                            ..Fields::default()
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    let mut records: Vec<TenderRecord> = Vec::new();
+    for record in listed.into_iter().filter_map(Fields::into_record) {
+        if !records
+            .iter()
+            .any(|seen| seen.resource_id == record.resource_id)
+        {
+            records.push(record);
+        }
+    }
+    records
+}
+
+fn resource_id_in(line: &str) -> Option<i64> {
+    RESOURCE_ID
+        .captures(line)
+        .and_then(|captures| captures[1].parse().ok())
+}
+
+fn parse_datetime(text: &str) -> Option<NaiveDateTime> {
+    let found = DATE.find(text)?.as_str();
+    ["%d/%m/%Y %H:%M:%S", "%d/%m/%Y %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(found, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(found, "%d/%m/%Y")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+fn parse_value(text: &str) -> Option<BigDecimal> {
+    let amount = AMOUNT.find(text)?.as_str().replace(',', "");
+    BigDecimal::from_str(&amount).ok()
+}
+
+/// As the scraper normalises the portal's status
+fn normalize_status(status: &str) -> String {
+    TenderStatus::normalize(status)
+        .label()
+        .map(str::to_string)
+        .unwrap_or_else(|| tender_status::clean(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plain_alert() {
+        let text = "Dear user,\n\
+The following opportunities match your alert \"IT services\":\n\
+\n\
+Title: Provision of Cloud Hosting Services\n\
+Contracting Authority: Health Service Executive\n\
+Procedure: Open procedure\n\
+Tender Deadline: 15/11/2026 12:00\n\
+Estimated Value: €250,000\n\
+View: https://www.etenders.gov.ie/epps/cft/prepareViewCfTWS.do?resourceId=6123456\n\
+\n\
+Title: Payroll System Support\n\
+Resource ID: 6123999\n\
+Closing Date: 01/12/2026\n\
+\n\
+Title: A tender with no link\n\
+\n\
+Title: Provision of Cloud Hosting Services\n\
+View: https://www.etenders.gov.ie/epps/cft/prepareViewCfTWS.do?resourceId=6123456\n";
+
+        let records = parse(text);
+        assert_eq!(records.len(), 2);
+
+        let first = &records[0];
+        assert_eq!(first.resource_id, 6123456);
+        assert_eq!(first.title, "Provision of Cloud Hosting Services");
+        assert_eq!(first.contracting_authority, "Health Service Executive");
+        assert_eq!(first.procedure, "Open procedure");
+        assert_eq!(first.status, "Open");
+        assert_eq!(
+            first.deadline,
+            NaiveDate::from_ymd_opt(2026, 11, 15)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+        );
+        assert_eq!(first.value, Some(BigDecimal::from(250000)));
+        assert_eq!(
+            first.pdf_url,
+            "https://www.etenders.gov.ie/epps/cft/downloadNoticeForAdvSearch.do?resourceId=6123456"
+        );
+
+        assert_eq!(records[1].resource_id, 6123999);
+        assert_eq!(
+            records[1].deadline,
+            NaiveDate::from_ymd_opt(2026, 12, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_parse_html_alert() {
+        let html = r#"<html><style>td { color: red; }</style><body>
+<p>New opportunities:</p>
+<table>
+<tr><td>Title:</td><td><a href="https://www.etenders.gov.ie/epps/opportunity/opportunityDetailAction.do?opportunityId=7001&amp;x=1">Managed Print&nbsp;Services</a></td></tr>
+<tr><td>Buyer</td><td>Dublin City Council</td></tr>
+<tr><td>Status</td><td>CLOSED</td></tr>
+</table>
+<p><a href="https://www.etenders.gov.ie/epps/cft/prepareViewCfTWS.do?resourceId=7002">Network Refresh</a></p>
+</body></html>"#;
+
+        let text = html_to_text(html);
+        assert!(!text.contains("color"));
+        let records = parse(&text);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].resource_id, 7001);
+        assert_eq!(records[0].title, "Managed Print Services");
+        assert_eq!(records[0].contracting_authority, "Dublin City Council");
+        assert_eq!(records[0].status, "Closed");
+        assert_eq!(records[1].resource_id, 7002);
+        assert_eq!(records[1].title, "Network Refresh");
+        assert_eq!(records[1].status, "Open");
+    }
+
+    #[test]
+    fn test_allowed_senders() {
+        let allowed = vec![
+            "etenders.gov.ie".to_string(),
+            "alerts@example.com".to_string(),
+        ];
+        assert!(is_allowed_sender(
+            "eTenders <noreply@etenders.gov.ie>",
+            &allowed
+        ));
+        assert!(is_allowed_sender("noreply@mail.etenders.gov.ie", &allowed));
+        assert!(is_allowed_sender("Alerts <ALERTS@example.com>", &allowed));
+        assert!(!is_allowed_sender("someone@notetenders.gov.ie", &allowed));
+        assert!(!is_allowed_sender("other@example.com", &allowed));
+        assert!(!is_allowed_sender("undisclosed", &allowed));
+    }
+}
@@ -0,0 +1,200 @@
+//! Alert emails from the eTenders portal as a second way in for new tenders.
+//!
+//! An SES receipt rule stores each email sent to the alert address in S3, and the bucket's
+//! ObjectCreated notification invokes this lambda with the object. The alert is parsed
+//! into the same TenderRecords the scraper queues (see `alert`) and sent to the tender
+//! processing queue, so postgres_dataload loads them as usual. When the scraper breaks
+//! (portal markup changes, blocked requests) alerts keep new tenders coming; when both
+//! work, dataload skips the resource ids it has already loaded.
+//!
+//! Only mail from INGEST_ALLOWED_SENDERS (comma-separated addresses or domains, default
+//! `etenders.gov.ie`) is read, so anyone who learns the address can't inject tenders.
+
+use anyhow::{Context, Result};
+use aws_lambda_events::event::s3::S3Event;
+use aws_sdk_s3::Client as S3Client;
+use aws_sdk_sqs::Client as SqsClient;
+use environment::Environment;
+use lambda_runtime::{service_fn, Error, LambdaEvent};
+use resource_discovery::{Resource, ResourceDiscovery};
+use retry::Policy;
+use tracing::{error, info, warn, Instrument};
+
+mod alert;
+mod mime;
+
+use alert::TenderRecord;
+use mime::Part;
+
+const DEFAULT_ALLOWED_SENDERS: &str = "etenders.gov.ie";
+
+fn allowed_senders() -> Vec<String> {
+    std::env::var("INGEST_ALLOWED_SENDERS")
+        .ok()
+        .filter(|senders| !senders.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_ALLOWED_SENDERS.to_string())
+        .split(',')
+        .map(|sender| sender.trim().to_string())
+        .filter(|sender| !sender.is_empty())
+        .collect()
+}
+
+async fn function_handler(event: LambdaEvent<S3Event>) -> Result<(), Error> {
+    let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        .load()
+        .await;
+    let s3_client = S3Client::new(&aws_config);
+    let sqs_client = SqsClient::new(&aws_config);
+    let queue_url = ResourceDiscovery::new(&aws_config)
+        .resolve(Resource::TenderProcessingQueue)
+        .await
+        .map_err(|e| Error::from(format!("Tender processing queue not found: {}", e).as_str()))?;
+    let allowed = allowed_senders();
+
+    let mut failed = 0;
+    for record in event.payload.records {
+        let (Some(bucket), Some(key)) = (record.s3.bucket.name, record.s3.object.key) else {
+            warn!("S3 record without a bucket or key, skipping");
+            continue;
+        };
+        let key = object_key(&key);
+
+        let raw = fetch(&s3_client, &bucket, &key).await?;
+        let records = read_alert(&raw, &allowed, &key);
+        if records.is_empty() {
+            continue;
+        }
+
+        let queued = queue_records(&sqs_client, &queue_url, &records).await;
+        info!(
+            "📬 Queued {} of {} tenders from s3://{}/{}",
+            queued,
+            records.len(),
+            bucket,
+            key
+        );
+        failed += records.len() - queued;
+    }
+
+    // S3 retries the invocation; dataload skips the tenders that were queued already
+    if failed > 0 {
+        return Err(Error::from(
+            format!("{} tenders from alert emails could not be queued", failed).as_str(),
+        ));
+    }
+    Ok(())
+}
+
+/// S3 event keys are URL-encoded, with `+` for a space
+fn object_key(key: &str) -> String {
+    let bytes = key.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match key
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+async fn fetch(s3_client: &S3Client, bucket: &str, key: &str) -> Result<String> {
+    let response = Policy::aws("s3_get")
+        .run(|| s3_client.get_object().bucket(bucket).key(key).send())
+        .await
+        .with_context(|| format!("Failed to read s3://{}/{}", bucket, key))?;
+    let body = response.body.collect().await?;
+    Ok(String::from_utf8_lossy(&body.into_bytes()).into_owned())
+}
+
+/// The tenders in one stored email; none when it isn't an alert from an allowed sender
+fn read_alert(raw: &str, allowed: &[String], key: &str) -> Vec<TenderRecord> {
+    let message = Part::parse(raw);
+    let from = message.header("from").unwrap_or_default();
+    if !alert::is_allowed_sender(from, allowed) {
+        warn!(
+            "Ignoring email {} from '{}': not an allowed sender",
+            key, from
+        );
+        return Vec::new();
+    }
+
+    let subject = message.header("subject").unwrap_or_default();
+    let Some(text) = alert::alert_text(message.bodies()) else {
+        warn!("Email {} ('{}') has no text body", key, subject);
+        return Vec::new();
+    };
+
+    let records = alert::parse(&text);
+    if records.is_empty() {
+        // Either an alert with nothing new or a format the parser doesn't know
+        warn!("No tenders found in email {} ('{}')", key, subject);
+    }
+    records
+}
+
+async fn queue_records(sqs_client: &SqsClient, queue_url: &str, records: &[TenderRecord]) -> usize {
+    let mut queued_count = 0;
+
+    for record in records {
+        let message_body = match serde_json::to_string(record) {
+            Ok(body) => body,
+            Err(e) => {
+                error!("Failed to serialize tender {}: {}", record.resource_id, e);
+                continue;
+            }
+        };
+
+        let sent = Policy::aws("sqs_send")
+            .run(|| {
+                sqs_client
+                    .send_message()
+                    .queue_url(queue_url)
+                    .message_body(message_body.clone())
+                    .send()
+            })
+            .await;
+        match sent {
+            Ok(resp) => {
+                info!(
+                    "Queued tender {} (message ID: {})",
+                    record.resource_id,
+                    resp.message_id().unwrap_or_default()
+                );
+                queued_count += 1;
+            }
+            Err(e) => {
+                error!("Failed to queue tender {}: {}", record.resource_id, e);
+            }
+        }
+    }
+
+    queued_count
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    let environment = Environment::from_env();
+    lambda_runtime::run(service_fn(move |event| {
+        function_handler(event).instrument(environment.span())
+    }))
+    .await
+}
@@ -0,0 +1,230 @@
+//! Just enough MIME to read an alert email as SES stores it: headers, nested multipart
+//! bodies, and base64 or quoted-printable parts in UTF-8 or Latin-1.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// A message or one part of a multipart body
+pub struct Part<'a> {
+    headers: Vec<(String, String)>,
+    body: &'a str,
+}
+
+/// The text bodies found in a message
+#[derive(Debug, Default, PartialEq)]
+pub struct Bodies {
+    pub text: Option<String>,
+    pub html: Option<String>,
+}
+
+impl<'a> Part<'a> {
+    pub fn parse(raw: &'a str) -> Self {
+        let (head, body) = match (raw.find("\r\n\r\n"), raw.find("\n\n")) {
+            (Some(crlf), Some(lf)) if lf < crlf => (&raw[..lf], &raw[lf + 2..]),
+            (Some(crlf), _) => (&raw[..crlf], &raw[crlf + 4..]),
+            (None, Some(lf)) => (&raw[..lf], &raw[lf + 2..]),
+            (None, None) => (raw, ""),
+        };
+
+        // Folded header lines continue with leading whitespace
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in head.lines() {
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+            } else if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+            }
+        }
+
+        Self { headers, body }
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.headers
+            .iter()
+            .find(|(header, _)| *header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The media type, lowercased, without parameters; text/plain when there is none
+    fn content_type(&self) -> String {
+        self.header("content-type")
+            .and_then(|value| value.split(';').next())
+            .map(|media| media.trim().to_lowercase())
+            .unwrap_or_else(|| "text/plain".to_string())
+    }
+
+    fn parameter(&self, name: &str) -> Option<String> {
+        self.header("content-type")?
+            .split(';')
+            .skip(1)
+            .filter_map(|parameter| parameter.split_once('='))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim().trim_matches('"').to_string())
+    }
+
+    /// The first text/plain and text/html bodies, decoded, in any nesting of multiparts
+    pub fn bodies(&self) -> Bodies {
+        let mut bodies = Bodies::default();
+        self.collect(&mut bodies);
+        bodies
+    }
+
+    fn collect(&self, bodies: &mut Bodies) {
+        let content_type = self.content_type();
+        if content_type.starts_with("multipart/") {
+            if let Some(boundary) = self.parameter("boundary") {
+                for part in split_multipart(self.body, &boundary) {
+                    Part::parse(part).collect(bodies);
+                }
+            }
+            return;
+        }
+        if self
+            .header("content-disposition")
+            .is_some_and(|disposition| disposition.to_lowercase().starts_with("attachment"))
+        {
+            return;
+        }
+
+        let slot = match content_type.as_str() {
+            "text/plain" => &mut bodies.text,
+            "text/html" => &mut bodies.html,
+            _ => return,
+        };
+        if slot.is_none() {
+            *slot = Some(self.decoded());
+        }
+    }
+
+    fn decoded(&self) -> String {
+        let encoding = self
+            .header("content-transfer-encoding")
+            .unwrap_or_default()
+            .to_lowercase();
+        let bytes = match encoding.as_str() {
+            "base64" => {
+                let compact: String = self.body.chars().filter(|c| !c.is_whitespace()).collect();
+                match STANDARD.decode(compact) {
+                    Ok(bytes) => bytes,
+                    Err(_) => return self.body.to_string(),
+                }
+            }
+            "quoted-printable" => decode_quoted_printable(self.body),
+            _ => return self.body.to_string(),
+        };
+
+        let charset = self.parameter("charset").unwrap_or_default().to_lowercase();
+        if charset == "iso-8859-1" || charset == "latin1" || charset == "windows-1252" {
+            bytes.iter().map(|&b| b as char).collect()
+        } else {
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+    }
+}
+
+/// The parts between `--boundary` lines, up to the closing `--boundary--`
+fn split_multipart<'a>(body: &'a str, boundary: &str) -> Vec<&'a str> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == delimiter || trimmed == format!("{}--", delimiter) {
+            if let Some(start) = start {
+                parts.push(body[start..offset].trim_end_matches(['\r', '\n']));
+            }
+            if trimmed != delimiter {
+                break;
+            }
+            start = Some(offset + line.len());
+        }
+        offset += line.len();
+    }
+    parts
+}
+
+fn decode_quoted_printable(body: &str) -> Vec<u8> {
+    let bytes = body.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'=' {
+            decoded.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        // Soft line break
+        if bytes[i + 1..].starts_with(b"\r\n") {
+            i += 3;
+        } else if bytes[i + 1..].starts_with(b"\n") {
+            i += 2;
+        } else if let Some(byte) = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(b'=');
+            i += 1;
+        }
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multipart_bodies_are_decoded() {
+        let raw = "From: eTenders <noreply@etenders.gov.ie>\r\n\
+Subject: Alert\r\n\
+Content-Type: multipart/alternative;\r\n boundary=\"b1\"\r\n\
+\r\n\
+--b1\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+Value: =E2=82=AC250,000 for a long line that=\r\n continues\r\n\
+--b1\r\n\
+Content-Type: text/html; charset=utf-8\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+PHA+SGVsbG88L3A+\r\n\
+--b1--\r\n";
+
+        let message = Part::parse(raw);
+        assert_eq!(
+            message.header("FROM"),
+            Some("eTenders <noreply@etenders.gov.ie>")
+        );
+        assert_eq!(
+            message.bodies(),
+            Bodies {
+                text: Some("Value: €250,000 for a long line that continues".to_string()),
+                html: Some("<p>Hello</p>".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_single_part_and_latin1() {
+        let raw = "Content-Type: text/plain; charset=ISO-8859-1\n\
+Content-Transfer-Encoding: quoted-printable\n\
+\n\
+Caf=E9 =3D ok";
+        assert_eq!(Part::parse(raw).bodies().text.as_deref(), Some("Café = ok"));
+
+        let attachment = "Content-Type: multipart/mixed; boundary=x\n\n--x\n\
+Content-Type: text/plain\nContent-Disposition: attachment; filename=a.txt\n\nignored\n--x--\n";
+        assert_eq!(Part::parse(attachment).bodies(), Bodies::default());
+    }
+}
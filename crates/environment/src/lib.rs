@@ -0,0 +1,185 @@
+//! Deployment environment (dev/staging/prod) shared by every lambda.
+//!
+//! Set `ENVIRONMENT` to `dev`, `staging` or `prod` (default `prod`, which keeps the
+//! original un-prefixed resource names and the `public` schema). Non-prod environments:
+//!
+//! - resolve queues/buckets with a `<env>-` prefix (see `resource_discovery`)
+//! - keep their tables in a Postgres schema named after the environment
+//! - record the environment on every row via the `environment` column default
+//! - tag every log line with the environment span
+//!
+//! It also holds `is_canary`, the one other check every stage shares.
+
+use anyhow::Result;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use tracing::{info_span, warn, Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Environment {
+    Dev,
+    Staging,
+    Prod,
+}
+
+impl Environment {
+    /// Read `ENVIRONMENT`; unset means prod so existing deployments are unchanged
+    pub fn from_env() -> Self {
+        match std::env::var("ENVIRONMENT") {
+            Ok(value) => Self::parse(&value).unwrap_or_else(|| {
+                warn!("⚠️ Unknown ENVIRONMENT '{}', defaulting to prod", value);
+                Environment::Prod
+            }),
+            Err(_) => Environment::Prod,
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "dev" | "development" => Some(Environment::Dev),
+            "staging" | "stage" => Some(Environment::Staging),
+            "prod" | "production" | "" => Some(Environment::Prod),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Environment::Dev => "dev",
+            Environment::Staging => "staging",
+            Environment::Prod => "prod",
+        }
+    }
+
+    pub fn is_production(&self) -> bool {
+        *self == Environment::Prod
+    }
+
+    /// Prefix applied to queue/bucket names, e.g. "staging-"
+    pub fn resource_prefix(&self) -> String {
+        if self.is_production() {
+            String::new()
+        } else {
+            format!("{}-", self.name())
+        }
+    }
+
+    /// Postgres schema holding this environment's tables
+    pub fn db_schema(&self) -> &'static str {
+        match self {
+            Environment::Dev => "dev",
+            Environment::Staging => "staging",
+            Environment::Prod => "public",
+        }
+    }
+
+    /// Pool options that pin every connection to the environment's schema (prod stays on `public`)
+    ///
+    /// The search_path deliberately excludes `public` for non-prod so a missing dev
+    /// table fails loudly instead of silently reading production data.
+    pub fn pool_options(&self) -> PgPoolOptions {
+        Self::with_setup(self.session_setup(false))
+    }
+
+    /// Like `pool_options`, but every transaction is read-only, so the pool also works
+    /// against a hot-standby replica (the schema is never created from here)
+    pub fn read_only_pool_options(&self) -> PgPoolOptions {
+        Self::with_setup(self.session_setup(true))
+    }
+
+    /// SQL run on each new connection
+    pub fn session_setup(&self, read_only: bool) -> String {
+        let mut setup = String::new();
+        if !self.is_production() {
+            if !read_only {
+                setup.push_str(&format!(
+                    "CREATE SCHEMA IF NOT EXISTS {}; ",
+                    self.db_schema()
+                ));
+            }
+            setup.push_str(&format!("SET search_path TO {}; ", self.db_schema()));
+        }
+        setup.push_str(&format!("SET app.environment TO '{}';", self.name()));
+        if read_only {
+            setup.push_str(" SET default_transaction_read_only TO on;");
+        }
+        setup
+    }
+
+    fn with_setup(setup: String) -> PgPoolOptions {
+        PgPoolOptions::new().after_connect(move |conn, _meta| {
+            let setup = setup.clone();
+            Box::pin(async move {
+                sqlx::Executor::execute(&mut *conn, setup.as_str()).await?;
+                Ok(())
+            })
+        })
+    }
+
+    /// Add an `environment` column that defaults to the connection's environment
+    pub async fn ensure_environment_column(pool: &PgPool, table: &str) -> Result<()> {
+        let query = format!(
+            "ALTER TABLE {} ADD COLUMN IF NOT EXISTS environment TEXT \
+             DEFAULT current_setting('app.environment', true)",
+            table
+        );
+        sqlx::query(&query).execute(pool).await?;
+        Ok(())
+    }
+
+    /// Span carrying the environment; instrument the handler with it so every log line includes it
+    pub fn span(&self) -> Span {
+        info_span!("env", environment = self.name())
+    }
+}
+
+/// Whether a tender is a synthetic pipeline_canary tender rather than a real one.
+///
+/// Canary tenders use negative resource ids, which the portal never issues. Every
+/// stage processes them normally but skips outward side effects (emails, tickets, webhooks).
+pub fn is_canary(resource_id: i64) -> bool {
+    resource_id < 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_aliases() {
+        assert_eq!(Environment::parse("Development"), Some(Environment::Dev));
+        assert_eq!(Environment::parse("stage"), Some(Environment::Staging));
+        assert_eq!(Environment::parse("production"), Some(Environment::Prod));
+        assert_eq!(Environment::parse("qa"), None);
+    }
+
+    #[test]
+    fn test_canary_ids_are_negative() {
+        assert!(is_canary(-1_700_000_000));
+        assert!(!is_canary(5850990));
+    }
+
+    #[test]
+    fn test_prod_keeps_original_names() {
+        assert_eq!(Environment::Prod.resource_prefix(), "");
+        assert_eq!(Environment::Prod.db_schema(), "public");
+        assert_eq!(Environment::Staging.resource_prefix(), "staging-");
+        assert_eq!(Environment::Dev.db_schema(), "dev");
+    }
+
+    #[test]
+    fn test_read_only_session_never_creates_the_schema() {
+        assert_eq!(
+            Environment::Prod.session_setup(false),
+            "SET app.environment TO 'prod';"
+        );
+        assert_eq!(
+            Environment::Staging.session_setup(false),
+            "CREATE SCHEMA IF NOT EXISTS staging; SET search_path TO staging; SET app.environment TO 'staging';"
+        );
+        assert_eq!(
+            Environment::Staging.session_setup(true),
+            "SET search_path TO staging; SET app.environment TO 'staging'; SET default_transaction_read_only TO on;"
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Backfill progress in the lambda bucket, one object per environment.
+//!
+//! A backfill saves the page it has reached after every page it queues, so a run cut
+//! short - the invocation limit, a failed reinvoke, a retried error - resumes from there on
+//! the next backfill request instead of page 1. The object is removed once the whole range
+//! is done.
+
+use anyhow::{Context, Result};
+use aws_sdk_s3::Client as S3Client;
+use environment::Environment;
+use resource_discovery::{Resource, ResourceDiscovery};
+
+use crate::types::Checkpoint;
+
+pub struct Checkpoints {
+    s3: S3Client,
+    bucket: String,
+    key: String,
+}
+
+impl Checkpoints {
+    pub async fn new(aws_config: &aws_config::SdkConfig) -> Result<Self> {
+        let bucket = ResourceDiscovery::new(aws_config)
+            .resolve(Resource::LambdaBucket)
+            .await
+            .context("Failed to find the lambda bucket")?;
+        Ok(Self {
+            s3: S3Client::new(aws_config),
+            bucket,
+            key: format!(
+                "etenders_scraper/{}/backfill_checkpoint.json",
+                Environment::from_env().name()
+            ),
+        })
+    }
+
+    /// The saved checkpoint, None when no backfill is in progress
+    pub async fn load(&self) -> Result<Option<Checkpoint>> {
+        let object = match self
+            .s3
+            .get_object()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .send()
+            .await
+        {
+            Ok(object) => object,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read s3://{}/{}", self.bucket, self.key))
+            }
+        };
+        let body = object
+            .body
+            .collect()
+            .await
+            .with_context(|| format!("Failed to read s3://{}/{}", self.bucket, self.key))?;
+        Ok(Some(serde_json::from_slice(&body.into_bytes())?))
+    }
+
+    pub async fn save(&self, checkpoint: &Checkpoint) -> Result<()> {
+        self.s3
+            .put_object()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .body(serde_json::to_vec(checkpoint)?.into())
+            .send()
+            .await
+            .with_context(|| format!("Failed to write s3://{}/{}", self.bucket, self.key))?;
+        Ok(())
+    }
+
+    pub async fn clear(&self) -> Result<()> {
+        self.s3
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .send()
+            .await
+            .with_context(|| format!("Failed to delete s3://{}/{}", self.bucket, self.key))?;
+        Ok(())
+    }
+}
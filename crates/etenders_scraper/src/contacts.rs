@@ -0,0 +1,149 @@
+//! The buyer's contact point from a tender's notice page.
+//!
+//! The results table has no contact details; the notice page lists the contracting
+//! authority's contact point as label/value rows ("Contact person", "E-mail",
+//! "Telephone"), in a table or a definition list depending on the notice form. Bid
+//! writers want the clarification contact without opening the PDF, so the scraper reads
+//! it here and postgres_dataload stores it on the tender.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use reqwest::Client;
+use scraper::{ElementRef, Html, Selector};
+use std::sync::LazyLock;
+use tender_core::BuyerContact;
+
+use crate::documents::PORTAL;
+
+static EMAIL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\w.+-]+@[\w-]+(?:\.[\w-]+)+").expect("valid email pattern"));
+
+fn notice_page_url(resource_id: i64) -> String {
+    format!(
+        "{}/epps/cft/prepareViewCfTWS.do?resourceId={}",
+        PORTAL, resource_id
+    )
+}
+
+/// The tender's contact point, None when the notice page gives none
+pub async fn fetch_contact(client: &Client, resource_id: i64) -> Result<Option<BuyerContact>> {
+    let body = client
+        .get(notice_page_url(resource_id))
+        .send()
+        .await
+        .context(format!("Failed to fetch notice page for {}", resource_id))?
+        .error_for_status()?
+        .text()
+        .await
+        .context(format!("Failed to read notice page for {}", resource_id))?;
+    Ok(parse_contact(&body))
+}
+
+/// Contact name, email and phone from the notice page's label/value rows. The first
+/// `mailto:` link stands in for an email with no label
+pub fn parse_contact(body: &str) -> Option<BuyerContact> {
+    let doc = Html::parse_document(body);
+    let row_sel = Selector::parse("tr, dl").unwrap();
+    let cell_sel = Selector::parse("th, td, dt, dd").unwrap();
+    let mailto_sel = Selector::parse(r#"a[href^="mailto:"]"#).unwrap();
+
+    let (mut name, mut email, mut phone) = (None, None, None);
+    for row in doc.select(&row_sel) {
+        let cells: Vec<String> = row.select(&cell_sel).map(|cell| text(&cell)).collect();
+        for pair in cells.chunks(2) {
+            let [label, value] = pair else { continue };
+            let label = label.trim_end_matches(':').to_lowercase();
+            match label.as_str() {
+                "contact person" | "contact name" | "contact point" | "contact" | "attention" => {
+                    name = name.or_else(|| clean_name(value))
+                }
+                "e-mail" | "email" | "e-mail address" | "email address" => {
+                    email = email.or_else(|| clean_email(value))
+                }
+                "telephone" | "phone" | "tel" | "tel." | "telephone number" => {
+                    phone = phone.or_else(|| clean_phone(value))
+                }
+                _ => {}
+            }
+        }
+    }
+    if email.is_none() {
+        email = doc
+            .select(&mailto_sel)
+            .filter_map(|link| link.value().attr("href"))
+            .find_map(|href| clean_email(href.trim_start_matches("mailto:")));
+    }
+    BuyerContact::new(name, email, phone)
+}
+
+pub(crate) fn text(cell: &ElementRef) -> String {
+    cell.text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A name, not an address or placeholder that ended up in the contact field
+fn clean_name(value: &str) -> Option<String> {
+    let value = value.trim();
+    let placeholder = matches!(value.to_lowercase().as_str(), "" | "-" | "n/a" | "none");
+    (!placeholder && !value.contains('@') && value.chars().any(char::is_alphabetic))
+        .then(|| value.to_string())
+}
+
+fn clean_email(value: &str) -> Option<String> {
+    EMAIL.find(value).map(|m| m.as_str().to_lowercase())
+}
+
+/// The number as written, if it has enough digits to be a phone number
+fn clean_phone(value: &str) -> Option<String> {
+    let value = value.trim();
+    let digits = value.chars().filter(char::is_ascii_digit).count();
+    (7..=15).contains(&digits).then(|| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_contact_from_table() {
+        let body = r#"<html><body><table>
+            <tr><th>Contracting authority:</th><td>Kerry County Council</td></tr>
+            <tr><th>Contact person:</th><td> Máire  Ní Bhriain </td></tr>
+            <tr><th>E-mail:</th><td><a href="mailto:Procurement@KerryCoCo.ie">Procurement@KerryCoCo.ie</a></td></tr>
+            <tr><th>Telephone:</th><td>+353 66 718 3500</td></tr>
+        </table></body></html>"#;
+
+        assert_eq!(
+            parse_contact(body),
+            Some(BuyerContact {
+                name: Some("Máire Ní Bhriain".to_string()),
+                email: Some("procurement@kerrycoco.ie".to_string()),
+                phone: Some("+353 66 718 3500".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_contact_from_definition_list_and_mailto() {
+        let body = r#"<dl>
+            <dt>Contact point</dt><dd>N/A</dd>
+            <dt>Tel.</dt><dd>12</dd>
+        </dl>
+        <p>Questions to <a href="mailto:tenders@opw.ie?subject=RFT">the OPW</a></p>"#;
+
+        assert_eq!(
+            parse_contact(body),
+            Some(BuyerContact {
+                name: None,
+                email: Some("tenders@opw.ie".to_string()),
+                phone: None,
+            })
+        );
+        assert_eq!(
+            parse_contact("<html><body>Session expired</body></html>"),
+            None
+        );
+    }
+}
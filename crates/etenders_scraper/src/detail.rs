@@ -0,0 +1,250 @@
+//! Description, CPV and NUTS codes and lots from a tender's detail page.
+//!
+//! The results table only gives the title, contracting authority, dates and value. The
+//! detail page (`opportunityDetailAction.do`, the page the notification emails link to)
+//! has the rest as label/value rows, in a table or a definition list like the notice page:
+//! the description, the main and additional CPV codes, the NUTS codes of the place of
+//! performance, and the lots - as "Lot 1" rows, or a table with lot number and title
+//! columns. Its contact point rows are read with `contacts::parse_contact`, so the notice
+//! page is only fetched for tenders whose detail page has none.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use reqwest::Client;
+use scraper::{Html, Selector};
+use std::sync::LazyLock;
+use tender_core::{BuyerContact, TenderLot, TenderRecord};
+
+use crate::contacts::{self, text};
+use crate::documents::PORTAL;
+
+/// "72000000-5", or without the check digit
+static CPV: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b\d{8}(?:-\d)?\b").expect("valid CPV pattern"));
+
+/// Country prefix and one to three region levels, e.g. "IE0", "IE061"
+static NUTS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[A-Z]{2}[0-9][0-9A-Z]{0,2}\b").expect("valid NUTS pattern"));
+
+static LOT_LABEL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^lot\s*(?:no\.?|number)?\s*(\d+)$").expect("valid lot label pattern")
+});
+
+/// What the detail page adds to the results table's fields
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TenderDetail {
+    pub description: Option<String>,
+    pub cpv_codes: Vec<String>,
+    pub nuts_codes: Vec<String>,
+    pub lots: Vec<TenderLot>,
+    pub contact: Option<BuyerContact>,
+}
+
+impl TenderDetail {
+    /// Fill in the record's fields the page gave, leaving the rest as they are
+    pub fn apply(self, record: &mut TenderRecord) {
+        if let Some(description) = self.description {
+            record.info = description;
+        }
+        record.cpv_codes = self.cpv_codes;
+        record.nuts_codes = self.nuts_codes;
+        record.lots = self.lots;
+        if self.contact.is_some() {
+            record.buyer_contact = self.contact;
+        }
+    }
+}
+
+fn detail_page_url(resource_id: i64) -> String {
+    format!(
+        "{}/epps/opportunity/opportunityDetailAction.do?opportunityId={}",
+        PORTAL, resource_id
+    )
+}
+
+/// The tender's detail page, parsed
+pub async fn scrape_tender_detail(client: &Client, resource_id: i64) -> Result<TenderDetail> {
+    let body = client
+        .get(detail_page_url(resource_id))
+        .send()
+        .await
+        .context(format!("Failed to fetch detail page for {}", resource_id))?
+        .error_for_status()?
+        .text()
+        .await
+        .context(format!("Failed to read detail page for {}", resource_id))?;
+    Ok(parse_detail(&body))
+}
+
+/// The detail page's label/value rows and lot table. A code listed twice (main and
+/// additional) is kept once, in the order the page gives them
+pub fn parse_detail(body: &str) -> TenderDetail {
+    let doc = Html::parse_document(body);
+    let row_sel = Selector::parse("tr, dl").unwrap();
+    let cell_sel = Selector::parse("th, td, dt, dd").unwrap();
+
+    let mut detail = TenderDetail::default();
+    for row in doc.select(&row_sel) {
+        let cells: Vec<String> = row.select(&cell_sel).map(|cell| text(&cell)).collect();
+        for pair in cells.chunks(2) {
+            let [label, value] = pair else { continue };
+            let label = label.trim_end_matches(':').trim().to_lowercase();
+            match label.as_str() {
+                "description"
+                | "short description"
+                | "tender description"
+                | "description of the procurement" => {
+                    if detail.description.is_none() && !value.is_empty() {
+                        detail.description = Some(value.clone());
+                    }
+                }
+                label if label.contains("cpv") => push_codes(&mut detail.cpv_codes, &CPV, value),
+                label if label.contains("nuts") || label == "place of performance" => {
+                    push_codes(&mut detail.nuts_codes, &NUTS, value)
+                }
+                label => {
+                    if let Some(number) = LOT_LABEL
+                        .captures(label)
+                        .and_then(|caps| caps[1].parse().ok())
+                    {
+                        push_lot(&mut detail.lots, number, value);
+                    }
+                }
+            }
+        }
+    }
+    for (number, title) in lot_table_rows(&doc) {
+        push_lot(&mut detail.lots, number, &title);
+    }
+    detail.lots.sort_by_key(|lot| lot.number);
+    detail.contact = contacts::parse_contact(body);
+    detail
+}
+
+fn push_codes(codes: &mut Vec<String>, pattern: &Regex, value: &str) {
+    for code in pattern.find_iter(value) {
+        if !codes.iter().any(|c| c == code.as_str()) {
+            codes.push(code.as_str().to_string());
+        }
+    }
+}
+
+fn push_lot(lots: &mut Vec<TenderLot>, number: u32, title: &str) {
+    let title = title.trim();
+    if !title.is_empty() && !lots.iter().any(|lot| lot.number == number) {
+        lots.push(TenderLot {
+            number,
+            title: title.to_string(),
+        });
+    }
+}
+
+/// (number, title) from tables whose header has a lot column and a title or name column
+fn lot_table_rows(doc: &Html) -> Vec<(u32, String)> {
+    let table_sel = Selector::parse("table").unwrap();
+    let header_sel = Selector::parse("th").unwrap();
+    let row_sel = Selector::parse("tr").unwrap();
+    let cell_sel = Selector::parse("td").unwrap();
+
+    let mut rows = Vec::new();
+    for table in doc.select(&table_sel) {
+        let headers: Vec<String> = table
+            .select(&header_sel)
+            .map(|th| text(&th).to_lowercase())
+            .collect();
+        let number_col = headers.iter().position(|h| h.starts_with("lot"));
+        let title_col = headers
+            .iter()
+            .position(|h| h.contains("title") || h.contains("name"));
+        let (Some(number_col), Some(title_col)) = (number_col, title_col) else {
+            continue;
+        };
+        for row in table.select(&row_sel) {
+            let cells: Vec<String> = row.select(&cell_sel).map(|td| text(&td)).collect();
+            let number = cells.get(number_col).and_then(|cell| {
+                cell.chars()
+                    .filter(char::is_ascii_digit)
+                    .collect::<String>()
+                    .parse()
+                    .ok()
+            });
+            if let (Some(number), Some(title)) = (number, cells.get(title_col)) {
+                rows.push((number, title.clone()));
+            }
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_detail_from_tables() {
+        let body = r#"<html><body>
+        <table>
+            <tr><th>Title:</th><td>Managed network services</td></tr>
+            <tr><th>Description:</th><td>Provision of managed LAN and
+                WAN services to Kerry County Council.</td></tr>
+            <tr><th>Main CPV code:</th><td>72700000-7 Computer network services</td></tr>
+            <tr><th>Additional CPV codes:</th><td>72700000-7, 32420000-3 Network equipment</td></tr>
+            <tr><th>NUTS codes:</th><td>IE053 Mid-West, IE052 South-West</td></tr>
+            <tr><th>Contact person:</th><td>Seán Ó Sé</td></tr>
+        </table>
+        <table>
+            <tr><th>Lot number</th><th>Lot title</th><th>Estimated value</th></tr>
+            <tr><td>Lot 2</td><td>WAN services</td><td>€200,000</td></tr>
+            <tr><td>Lot 1</td><td>LAN services</td><td>€150,000</td></tr>
+        </table>
+        </body></html>"#;
+
+        let detail = parse_detail(body);
+        assert_eq!(
+            detail.description.as_deref(),
+            Some("Provision of managed LAN and WAN services to Kerry County Council.")
+        );
+        assert_eq!(detail.cpv_codes, vec!["72700000-7", "32420000-3"]);
+        assert_eq!(detail.nuts_codes, vec!["IE053", "IE052"]);
+        assert_eq!(
+            detail.lots,
+            vec![
+                TenderLot {
+                    number: 1,
+                    title: "LAN services".to_string()
+                },
+                TenderLot {
+                    number: 2,
+                    title: "WAN services".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            detail.contact.and_then(|c| c.name).as_deref(),
+            Some("Seán Ó Sé")
+        );
+    }
+
+    #[test]
+    fn test_parse_detail_from_definition_list() {
+        let body = r#"<dl>
+            <dt>Short description</dt><dd>Supply of laptops</dd>
+            <dt>CPV</dt><dd>30213100</dd>
+            <dt>Place of performance</dt><dd>Ireland</dd>
+            <dt>Lot No. 3:</dt><dd>Docking stations</dd>
+        </dl>"#;
+
+        let mut record = TenderRecord::default();
+        parse_detail(body).apply(&mut record);
+        assert_eq!(record.info, "Supply of laptops");
+        assert_eq!(record.cpv_codes, vec!["30213100"]);
+        assert!(record.nuts_codes.is_empty());
+        assert_eq!(record.lots[0].number, 3);
+        assert_eq!(record.buyer_contact, None);
+
+        assert_eq!(
+            parse_detail("<html><body>Session expired</body></html>"),
+            TenderDetail::default()
+        );
+    }
+}
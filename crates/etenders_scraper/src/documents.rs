@@ -0,0 +1,156 @@
+//! Document inventory for a tender from the portal's document list endpoint.
+//!
+//! The results table only links the tender notice, but most tenders carry several
+//! documents (specification, pricing schedule, forms). The portal's tender page loads
+//! them from a JSON endpoint, which gives each document's name, type, size and download
+//! link. When that endpoint fails or lists nothing, the inventory falls back to the
+//! notice alone, which is what the scraper queued before.
+
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde_json::Value;
+use tender_core::TenderDocument;
+
+pub(crate) const PORTAL: &str = "https://www.etenders.gov.ie";
+
+pub fn notice_url(resource_id: &str) -> String {
+    format!(
+        "{}/epps/cft/downloadNoticeForAdvSearch.do?resourceId={}",
+        PORTAL, resource_id
+    )
+}
+
+fn document_list_url(resource_id: i64) -> String {
+    format!(
+        "{}/epps/cft/listContractDocumentsJson.do?resourceId={}",
+        PORTAL, resource_id
+    )
+}
+
+/// The inventory used when the document list is unavailable: just the tender notice
+pub fn notice_only(resource_id: i64) -> Vec<TenderDocument> {
+    vec![TenderDocument {
+        name: "Tender notice".to_string(),
+        file_type: "pdf".to_string(),
+        size_bytes: None,
+        url: notice_url(&resource_id.to_string()),
+    }]
+}
+
+/// The tender's documents as listed by the portal
+pub async fn fetch_documents(client: &Client, resource_id: i64) -> Result<Vec<TenderDocument>> {
+    let body = client
+        .get(document_list_url(resource_id))
+        .header("Accept", "application/json")
+        .header("X-Requested-With", "XMLHttpRequest")
+        .send()
+        .await
+        .context(format!("Failed to fetch document list for {}", resource_id))?
+        .error_for_status()?
+        .text()
+        .await
+        .context(format!("Failed to read document list for {}", resource_id))?;
+    parse_document_list(&body)
+}
+
+/// Documents in a document list response: a bare array, or one under
+/// `documents`/`data`/`rows`. Entries without a name or a way to download them are skipped
+pub fn parse_document_list(body: &str) -> Result<Vec<TenderDocument>> {
+    let json: Value = serde_json::from_str(body).context("Document list is not JSON")?;
+    let entries = match &json {
+        Value::Array(entries) => entries,
+        _ => ["documents", "data", "rows"]
+            .iter()
+            .find_map(|key| json[key].as_array())
+            .context("Document list has no documents array")?,
+    };
+    Ok(entries.iter().filter_map(parse_document).collect())
+}
+
+fn parse_document(entry: &Value) -> Option<TenderDocument> {
+    let text = |keys: &[&str]| {
+        keys.iter().find_map(|key| match &entry[key] {
+            Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+    };
+
+    let name = text(&["name", "fileName", "title"])?;
+    let url = match text(&["url", "downloadUrl", "href"]) {
+        Some(url) if url.starts_with("http") => url,
+        Some(path) => format!("{}/{}", PORTAL, path.trim_start_matches('/')),
+        None => format!(
+            "{}/epps/cft/downloadContractDocument.do?documentId={}",
+            PORTAL,
+            text(&["documentId", "id"])?
+        ),
+    };
+    let file_type = text(&["type", "fileType", "documentType"])
+        .or_else(|| name.rsplit_once('.').map(|(_, ext)| ext.to_string()))
+        .unwrap_or_default()
+        .to_lowercase();
+    let size_bytes = text(&["size", "fileSize"]).and_then(|size| parse_size(&size));
+
+    Some(TenderDocument {
+        name,
+        file_type,
+        size_bytes,
+        url,
+    })
+}
+
+/// "48213", "512 KB" or "1.2 MB"
+fn parse_size(size: &str) -> Option<i64> {
+    let size = size.trim().to_uppercase();
+    let (number, unit) = match size.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(at) => (size[..at].trim(), size[at..].trim()),
+        None => (size.as_str(), "B"),
+    };
+    let multiplier = match unit {
+        "B" | "BYTES" => 1.0,
+        "KB" => 1024.0,
+        "MB" => 1024.0 * 1024.0,
+        "GB" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    let number: f64 = number.replace(',', "").parse().ok()?;
+    Some((number * multiplier).round() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_document_list() {
+        let body = r#"{"documents": [
+            {"fileName": "Specification.pdf", "fileSize": "1.5 MB", "documentId": 901},
+            {"name": "Pricing Schedule", "type": "XLSX", "size": 20480, "url": "/epps/cft/downloadContractDocument.do?documentId=902"},
+            {"name": "", "documentId": 903},
+            {"name": "No link"}
+        ]}"#;
+        let documents = parse_document_list(body).unwrap();
+
+        assert_eq!(documents.len(), 2);
+        assert_eq!(
+            documents[0],
+            TenderDocument {
+                name: "Specification.pdf".to_string(),
+                file_type: "pdf".to_string(),
+                size_bytes: Some(1_572_864),
+                url: "https://www.etenders.gov.ie/epps/cft/downloadContractDocument.do?documentId=901"
+                    .to_string(),
+            }
+        );
+        assert_eq!(documents[1].file_type, "xlsx");
+        assert_eq!(documents[1].size_bytes, Some(20480));
+        assert!(documents[1]
+            .url
+            .starts_with("https://www.etenders.gov.ie/epps/"));
+
+        assert_eq!(parse_document_list("[]").unwrap(), vec![]);
+        assert!(parse_document_list("<html>Session expired</html>").is_err());
+        assert!(parse_document_list(r#"{"error": "not found"}"#).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+//! Targeted scrapes: only tenders matching the request's filters are queued.
+//!
+//! A run for one buyer, one kind of work or one value band would otherwise send every tender
+//! on the pages it walks through the whole pipeline. The filters are set in the request
+//! (`cpv_prefixes`, `contracting_authorities`, `statuses`, `min_value`, `max_value`), carried
+//! between invocations in the continuation token, and a tender has to match all of the ones
+//! set:
+//!
+//! - `statuses`: `TenderStatus` names (open, closed, cancelled, awarded, unknown)
+//! - `contracting_authorities`: any of them appears in the authority's name, ignoring case
+//! - `min_value`/`max_value`: inclusive; a tender without a stated value doesn't match
+//! - `cpv_prefixes`: one of the detail page's CPV codes starts with one of them, e.g. "72"
+//!   for IT services. This needs the detail page, which is then read even with
+//!   SCRAPER_FETCH_DETAILS=false; a tender whose page gives no codes doesn't match
+//!
+//! The listing filters run before any per-tender request, the CPV filter once the detail
+//! page is read. Filtered-out tenders are counted by the first filter they failed.
+
+use bigdecimal::BigDecimal;
+use pipeline_contract::tender_status::TenderStatus;
+use serde::{Deserialize, Serialize};
+use tender_core::TenderRecord;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Filters {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpv_prefixes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contracting_authorities: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statuses: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_value: Option<BigDecimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<BigDecimal>,
+}
+
+/// Tenders left out, by the filter they failed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilteredOut {
+    pub status: usize,
+    pub contracting_authority: usize,
+    pub value: usize,
+    pub cpv: usize,
+}
+
+impl FilteredOut {
+    pub fn total(&self) -> usize {
+        self.status + self.contracting_authority + self.value + self.cpv
+    }
+
+    pub fn add(&mut self, other: &FilteredOut) {
+        self.status += other.status;
+        self.contracting_authority += other.contracting_authority;
+        self.value += other.value;
+        self.cpv += other.cpv;
+    }
+}
+
+impl Filters {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The CPV filter is set, so the detail page has to be read
+    pub fn needs_details(&self) -> bool {
+        !self.cpv_prefixes.is_empty()
+    }
+
+    /// Keep the records whose status, authority and value match, counting the rest
+    pub fn retain_listed(&self, records: &mut Vec<TenderRecord>, filtered: &mut FilteredOut) {
+        records.retain(|record| {
+            if !self.status_matches(record) {
+                filtered.status += 1;
+            } else if !self.authority_matches(record) {
+                filtered.contracting_authority += 1;
+            } else if !self.value_matches(record) {
+                filtered.value += 1;
+            } else {
+                return true;
+            }
+            false
+        });
+    }
+
+    /// Keep the records with a CPV code under one of the prefixes, counting the rest
+    pub fn retain_cpv(&self, records: &mut Vec<TenderRecord>, filtered: &mut FilteredOut) {
+        records.retain(|record| {
+            let matches = self.cpv_matches(record);
+            if !matches {
+                filtered.cpv += 1;
+            }
+            matches
+        });
+    }
+
+    fn status_matches(&self, record: &TenderRecord) -> bool {
+        let status = TenderStatus::normalize(&record.status);
+        self.statuses.is_empty()
+            || self
+                .statuses
+                .iter()
+                .any(|name| TenderStatus::parse(name) == Some(status))
+    }
+
+    fn authority_matches(&self, record: &TenderRecord) -> bool {
+        let authority = record.contracting_authority.to_lowercase();
+        self.contracting_authorities.is_empty()
+            || self
+                .contracting_authorities
+                .iter()
+                .any(|name| authority.contains(&name.trim().to_lowercase()))
+    }
+
+    fn value_matches(&self, record: &TenderRecord) -> bool {
+        if self.min_value.is_none() && self.max_value.is_none() {
+            return true;
+        }
+        record.value.as_ref().is_some_and(|value| {
+            self.min_value.as_ref().is_none_or(|min| value >= min)
+                && self.max_value.as_ref().is_none_or(|max| value <= max)
+        })
+    }
+
+    fn cpv_matches(&self, record: &TenderRecord) -> bool {
+        self.cpv_prefixes.is_empty()
+            || record.cpv_codes.iter().any(|code| {
+                self.cpv_prefixes
+                    .iter()
+                    .any(|prefix| code.starts_with(prefix.trim()))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tender(resource_id: i64, ca: &str, status: &str, value: Option<&str>) -> TenderRecord {
+        TenderRecord {
+            resource_id,
+            contracting_authority: ca.to_string(),
+            status: status.to_string(),
+            value: value.map(|v| v.parse().unwrap()),
+            ..Default::default()
+        }
+    }
+
+    fn ids(records: &[TenderRecord]) -> Vec<i64> {
+        records.iter().map(|r| r.resource_id).collect()
+    }
+
+    #[test]
+    fn test_listing_filters_count_the_first_mismatch() {
+        let filters: Filters = serde_json::from_value(serde_json::json!({
+            "contracting_authorities": ["county council"],
+            "statuses": ["open"],
+            "min_value": 50000,
+            "max_value": "500000"
+        }))
+        .unwrap();
+        let mut records = vec![
+            tender(1, "Kerry County Council", "Open", Some("120000")),
+            tender(2, "Kerry County Council", "Closed", Some("120000")),
+            tender(3, "HSE", "Open", Some("120000")),
+            tender(4, "Cork County Council", "Open", Some("20000")),
+            tender(5, "Cork County Council", "Open", None),
+            tender(6, "Cork County Council", "Open", Some("500000")),
+        ];
+        let mut filtered = FilteredOut::default();
+
+        filters.retain_listed(&mut records, &mut filtered);
+        assert_eq!(ids(&records), vec![1, 6]);
+        assert_eq!(
+            filtered,
+            FilteredOut {
+                status: 1,
+                contracting_authority: 1,
+                value: 2,
+                cpv: 0,
+            }
+        );
+        assert!(!filters.needs_details());
+
+        let mut all = vec![tender(7, "OPW", "", None)];
+        Filters::default().retain_listed(&mut all, &mut filtered);
+        assert_eq!(all.len(), 1);
+        assert!(Filters::default().is_empty());
+    }
+
+    #[test]
+    fn test_cpv_prefixes_need_a_matching_code() {
+        let filters = Filters {
+            cpv_prefixes: vec!["72".to_string(), "302".to_string()],
+            ..Default::default()
+        };
+        let mut it = tender(1, "HSE", "Open", None);
+        it.cpv_codes = vec!["45000000-7".to_string(), "72260000-5".to_string()];
+        let mut laptops = tender(2, "HSE", "Open", None);
+        laptops.cpv_codes = vec!["30213100-6".to_string()];
+        let mut works = tender(3, "HSE", "Open", None);
+        works.cpv_codes = vec!["45210000-2".to_string()];
+        let unread = tender(4, "HSE", "Open", None);
+
+        let mut records = vec![it, laptops, works, unread];
+        let mut filtered = FilteredOut::default();
+        filters.retain_cpv(&mut records, &mut filtered);
+        assert_eq!(ids(&records), vec![1, 2]);
+        assert_eq!(filtered.cpv, 2);
+        assert_eq!(filtered.total(), 2);
+        assert!(filters.needs_details());
+    }
+}
@@ -0,0 +1,88 @@
+//! Tenders already in `tender_records`, for incremental runs.
+//!
+//! A scheduled run only needs what has appeared since the last one, but the results list
+//! keeps every open tender. An incremental run looks each page's resource_ids up as it
+//! goes, queues only the ones postgres_dataload hasn't stored, and stops paging after a
+//! streak of known tenders - the results are newest first, so past that point it is all
+//! tenders an earlier run sent. Known tenders aren't refreshed; a full run still picks up
+//! their changes.
+
+use anyhow::Result;
+use sqlx::{PgPool, Row};
+use std::collections::HashSet;
+use tender_core::TenderRecord;
+
+/// Of `resource_ids`, those already stored
+pub async fn known_ids(pool: &PgPool, resource_ids: &[i64]) -> Result<HashSet<i64>> {
+    let rows = sqlx::query("SELECT resource_id FROM tender_records WHERE resource_id = ANY($1)")
+        .bind(resource_ids)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.iter().map(|row| row.get("resource_id")).collect())
+}
+
+/// One page of an incremental run
+#[derive(Debug)]
+pub struct Split {
+    /// Tenders to queue
+    pub new: Vec<TenderRecord>,
+    pub skipped: usize,
+    /// `stop_after` known tenders in a row were seen
+    pub stop: bool,
+}
+
+/// Drop the known tenders from a page, in order, carrying the run of consecutive known
+/// tenders in `streak` from one page to the next
+pub fn split(
+    records: Vec<TenderRecord>,
+    known: &HashSet<i64>,
+    streak: &mut u32,
+    stop_after: u32,
+) -> Split {
+    let mut split = Split {
+        new: Vec::new(),
+        skipped: 0,
+        stop: false,
+    };
+    for record in records {
+        if known.contains(&record.resource_id) {
+            split.skipped += 1;
+            *streak += 1;
+            split.stop |= *streak >= stop_after;
+        } else {
+            *streak = 0;
+            split.new.push(record);
+        }
+    }
+    split
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(ids: &[i64]) -> Vec<TenderRecord> {
+        ids.iter()
+            .map(|&resource_id| TenderRecord {
+                resource_id,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_stops_after_a_streak_of_known_tenders() {
+        let known: HashSet<i64> = [2, 3, 5, 6, 7].into_iter().collect();
+        let mut streak = 0;
+
+        let first = split(records(&[1, 2, 3, 4, 5]), &known, &mut streak, 3);
+        let ids: Vec<i64> = first.new.iter().map(|r| r.resource_id).collect();
+        assert_eq!((ids, first.skipped, first.stop), (vec![1, 4], 3, false));
+        assert_eq!(streak, 1);
+
+        let second = split(records(&[6, 7, 8]), &known, &mut streak, 3);
+        let ids: Vec<i64> = second.new.iter().map(|r| r.resource_id).collect();
+        assert_eq!((ids, second.skipped, second.stop), (vec![8], 2, true));
+        assert_eq!(streak, 0);
+    }
+}
@@ -1,33 +1,39 @@
 use anyhow::{Context, Result};
-use aws_config;
+use aws_sdk_lambda::primitives::Blob;
+use aws_sdk_lambda::types::InvocationType;
+use aws_sdk_lambda::Client as LambdaClient;
 use aws_sdk_sqs::Client as SqsClient;
 use bigdecimal::BigDecimal;
-use chrono::{NaiveDate, NaiveDateTime};
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use environment::Environment;
 use lambda_runtime::{service_fn, Error, LambdaEvent};
+use pipeline_contract::tender_status::{self, TenderStatus};
+use queue::batch::{self, Entry};
 use regex::Regex;
 use reqwest::Client;
+use resource_discovery::{Resource, ResourceDiscovery};
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
-use std::env;
 use std::str::FromStr;
-use tracing::{error, info, warn};
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct TenderRecord {
-    title: String,
-    resource_id: i64,
-    contracting_authority: String,
-    info: String,
-    published: Option<NaiveDateTime>,
-    deadline: Option<NaiveDateTime>,
-    procedure: String,
-    status: String,
-    pdf_url: String,
-    awarddate: Option<NaiveDate>,
-    value: Option<BigDecimal>,
-    cycle: String,
-    bid: Option<i32>,
-}
+use std::time::SystemTime;
+use tender_core::{SNSMessage, TenderRecord, RUN_SUMMARY_MESSAGE_TYPE};
+use tracing::{error, info, warn, Instrument};
+
+mod checkpoint;
+mod contacts;
+mod detail;
+mod documents;
+mod filters;
+mod known;
+mod pagination;
+mod types;
+
+use checkpoint::Checkpoints;
+use types::{
+    backfill_delay, database_url, fetch_contacts, fetch_details, fetch_document_lists,
+    known_streak, max_invocations, pages_per_invocation, time_margin, Checkpoint, Continuation,
+    Request, Response, RunStats,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct TenderRecordRaw {
@@ -45,162 +51,595 @@ struct TenderRecordRaw {
     cycle: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Request {
-    max_pages: Option<u32>,
-    test_mode: Option<bool>,
-    start_page: Option<u32>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct Response {
-    records_count: usize,
-    success: bool,
-    message: String,
-    queued_to_sqs: usize,
-}
-
 async fn function_handler(event: LambdaEvent<Request>) -> Result<Response, Error> {
     info!("=== ETENDERS SCRAPER STARTED ===");
 
-    let test_mode = event.payload.test_mode.unwrap_or(false);
-    let start_page = event.payload.start_page.unwrap_or(1);
-    let max_pages = if test_mode {
-        1
+    let mut continuation = Continuation::from_request(&event.payload);
+    let self_chain = event.payload.self_chain.unwrap_or(true);
+    let test_mode = continuation.test_mode;
+
+    let client = Client::new();
+    let base_url = "https://www.etenders.gov.ie/epps/quickSearchAction.do";
+    let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        .load()
+        .await;
+
+    let checkpoints = if continuation.backfill {
+        let checkpoints = Checkpoints::new(&aws_config)
+            .await
+            .map_err(|e| Error::from(format!("Backfill checkpoint unavailable: {}", e).as_str()))?;
+        // An explicit start page restarts the backfill there
+        if event.payload.continuation.is_some() || event.payload.start_page.is_none() {
+            match checkpoints.load().await {
+                Ok(Some(checkpoint)) => {
+                    info!(
+                        "📌 Backfill checkpoint from {} at page {}",
+                        checkpoint.saved_at, checkpoint.next_page
+                    );
+                    continuation.resume(&checkpoint);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("⚠️ Backfill checkpoint unreadable, not resuming: {}", e),
+            }
+        }
+        Some(checkpoints)
+    } else {
+        None
+    };
+    let known_pool = if continuation.incremental {
+        let database_url = database_url()
+            .ok_or_else(|| Error::from("DATABASE_URL must be set for an incremental run"))?;
+        let pool = db::connect(&database_url, 1)
+            .await
+            .map_err(|e| Error::from(format!("Failed to connect to database: {}", e).as_str()))?;
+        Some(pool)
     } else {
-        event.payload.max_pages.unwrap_or(10)
+        None
     };
+    if !continuation.filters.is_empty() {
+        info!("Queueing only tenders matching {:?}", continuation.filters);
+    }
+    let stop_after = known_streak();
+    let pages_per_invocation = pages_per_invocation();
+    let mut batch_end = continuation.batch_end(pages_per_invocation);
 
     info!(
-        "Configuration: test_mode={}, start_page={}, max_pages={}",
-        test_mode, start_page, max_pages
+        "Configuration: test_mode={}, pages {}-{}{}, invocation {}, self_chain={}, backfill={}, incremental={}",
+        test_mode,
+        continuation.next_page,
+        continuation.end_page - 1,
+        if continuation.discover_pages {
+            " (until the last page)"
+        } else {
+            ""
+        },
+        continuation.invocation,
+        self_chain,
+        continuation.backfill,
+        continuation.incremental
     );
 
-    let client = Client::new();
-    let base_url = "https://www.etenders.gov.ie/epps/quickSearchAction.do";
+    let queue = if !test_mode {
+        // Get the processing queue URL
+        let processing_queue_url = ResourceDiscovery::new(&aws_config)
+            .resolve(Resource::TenderProcessingQueue)
+            .await
+            .map_err(|e| {
+                Error::from(format!("Tender processing queue not found: {}", e).as_str())
+            })?;
+        info!("Sending records to SQS queue: {}", processing_queue_url);
+        Some((SqsClient::new(&aws_config), processing_queue_url))
+    } else {
+        info!("Test mode: skipping SQS queue");
+        None
+    };
 
     info!(
-        "Scraping pages {}-{}",
-        start_page,
-        start_page + max_pages - 1
+        "Scraping pages {}-{} in this invocation",
+        continuation.next_page,
+        batch_end - 1
     );
-    let records = scrape_tenders(&client, base_url, start_page, start_page + max_pages)
-        .await
-        .map_err(|e| Error::from(format!("Failed to scrape tenders: {}", e).as_str()))?;
-
-    info!("Successfully scraped {} tender records", records.len());
 
+    let deadline = event.context.deadline();
+    let margin = time_margin();
+    let mut records_count = 0;
     let mut queued_count = 0;
+    let mut new_count = 0;
+    let mut stats = RunStats::default();
+    let new_cutoff = Utc::now().naive_utc() - Duration::hours(24);
+
+    let mut page = continuation.next_page;
+    while page < batch_end {
+        // Always make progress on the first page so a chain of invocations can't loop
+        let remaining = deadline
+            .duration_since(SystemTime::now())
+            .unwrap_or_default();
+        if page > continuation.next_page && remaining < margin {
+            warn!(
+                "⏱️ {}s left before timeout, checkpointing at page {}",
+                remaining.as_secs(),
+                page
+            );
+            break;
+        }
+        if continuation.backfill && page > continuation.next_page {
+            tokio::time::sleep(backfill_delay()).await;
+        }
 
-    if !test_mode {
-        // Initialize AWS SQS client
-        let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
-            .load()
-            .await;
-        let sqs_client = SqsClient::new(&aws_config);
+        info!("Fetching page {}/{}", page, continuation.end_page - 1);
+        let (mut records, parse_failures, last_page) =
+            match scrape_page(&client, base_url, page, !continuation.backfill).await {
+                Ok(scraped) => scraped,
+                Err(e) => {
+                    let error = format!("Failed to scrape tenders: {}", e);
+                    return Err(fail_run(
+                        &aws_config,
+                        &continuation,
+                        &stats,
+                        page,
+                        test_mode,
+                        error,
+                    )
+                    .await);
+                }
+            };
+        if let Some(last_page) = last_page {
+            continuation.discovered(last_page);
+            batch_end = continuation.batch_end(pages_per_invocation);
+        } else if continuation.discover_pages {
+            warn!(
+                "No paginator on page {}, keeping the range ending at page {}",
+                page,
+                continuation.end_page - 1
+            );
+        }
+        records_count += records.len();
+        stats.pages_crawled += 1;
+        stats.tenders_found += records.len();
+        stats.parse_failures += parse_failures;
+        stats.new_tenders += records
+            .iter()
+            .filter(|r| r.published.is_some_and(|p| p >= new_cutoff))
+            .count();
+
+        let mut reached_known = false;
+        if let Some(pool) = &known_pool {
+            let resource_ids: Vec<i64> = records.iter().map(|r| r.resource_id).collect();
+            match known::known_ids(pool, &resource_ids).await {
+                Ok(known) => {
+                    let split =
+                        known::split(records, &known, &mut continuation.known_streak, stop_after);
+                    records = split.new;
+                    stats.skipped_known += split.skipped;
+                    reached_known = split.stop;
+                }
+                Err(e) => warn!(
+                    "⚠️ Couldn't check page {} against stored tenders, queueing all of it: {}",
+                    page, e
+                ),
+            }
+        }
+        new_count += records.len();
 
-        // Get the processing queue URL
-        let processing_queue_url = env::var("TENDER_PROCESSING_QUEUE_URL")
-            .map_err(|_| Error::from("TENDER_PROCESSING_QUEUE_URL not set"))?;
-
-        info!(
-            "Sending {} records to SQS queue: {}",
-            records.len(),
-            processing_queue_url
-        );
-
-        // Send each record to SQS
-        for record in records.iter() {
-            let message_body = serde_json::to_string(record)
-                .map_err(|e| Error::from(format!("Failed to serialize record: {}", e).as_str()))?;
-
-            match sqs_client
-                .send_message()
-                .queue_url(&processing_queue_url)
-                .message_body(message_body)
-                .send()
-                .await
+        // The listing filters first, so filtered-out tenders cost no further requests
+        let filters = &continuation.filters;
+        filters.retain_listed(&mut records, &mut stats.filtered_out);
+        if fetch_details() || filters.needs_details() {
+            attach_details(&client, &mut records).await;
+        }
+        filters.retain_cpv(&mut records, &mut stats.filtered_out);
+
+        if fetch_document_lists() {
+            stats.document_list_fallbacks += attach_documents(&client, &mut records).await;
+        }
+        if fetch_contacts() {
+            attach_contacts(&client, &mut records).await;
+        }
+
+        // Queue each page as soon as it is scraped so a timeout only loses the page in flight
+        if let Some((sqs_client, queue_url)) = &queue {
+            let queued = match queue_records(sqs_client, queue_url, &records).await {
+                Ok(queued) => queued,
+                Err(e) => {
+                    let error = format!("Failed to queue page {}: {}", page, e);
+                    return Err(fail_run(
+                        &aws_config,
+                        &continuation,
+                        &stats,
+                        page,
+                        test_mode,
+                        error,
+                    )
+                    .await);
+                }
+            };
+            queued_count += queued;
+            stats.queue_failures += records.len() - queued;
+        }
+
+        page += 1;
+        if let Some(checkpoints) = &checkpoints {
+            let checkpoint = Checkpoint {
+                next_page: page,
+                end_page: continuation.end_page,
+                saved_at: Utc::now(),
+            };
+            if let Err(e) = checkpoints.save(&checkpoint).await {
+                warn!(
+                    "⚠️ Failed to save backfill checkpoint at page {}: {}",
+                    page, e
+                );
+            }
+        }
+
+        if reached_known {
+            info!(
+                "⏹️ {} stored tenders in a row by page {}, stopping the incremental run",
+                continuation.known_streak,
+                page - 1
+            );
+            continuation.stop_before(page);
+            break;
+        }
+    }
+    let next_page = page;
+
+    info!("Successfully scraped {} tender records", records_count);
+    if queue.is_some() {
+        info!("Successfully queued {} records to SQS", queued_count);
+    }
+
+    let mut success = true;
+    let mut message = format!(
+        "Scraped {} tenders, queued {} to SQS",
+        records_count, queued_count
+    );
+    if continuation.incremental {
+        message.push_str(&format!(
+            "; {} new, {} already stored",
+            new_count, stats.skipped_known
+        ));
+    }
+    if !continuation.filters.is_empty() {
+        message.push_str(&format!("; {} filtered out", stats.filtered_out.total()));
+    }
+
+    let next = continuation.advance(next_page, &stats);
+    // Pages left undone when the run stops early
+    let mut unscraped = None;
+    match &next {
+        None => {
+            info!("✅ Page range complete");
+            if let Some(checkpoints) = &checkpoints {
+                if let Err(e) = checkpoints.clear().await {
+                    warn!("⚠️ Failed to clear backfill checkpoint: {}", e);
+                }
+            }
+        }
+        Some(next) if !self_chain => {
+            info!(
+                "Returning continuation for pages {}-{}",
+                next.next_page,
+                next.end_page - 1
+            );
+            message.push_str(&format!("; continue from page {}", next.next_page));
+        }
+        Some(next) if next.invocation > max_invocations() => {
+            error!(
+                "Invocation limit reached, pages {}-{} were not scraped",
+                next.next_page,
+                next.end_page - 1
+            );
+            success = false;
+            unscraped = Some((next.next_page, next.end_page - 1));
+            message.push_str(&format!(
+                "; invocation limit reached, pages {}-{} were not scraped",
+                next.next_page,
+                next.end_page - 1
+            ));
+            if next.backfill {
+                message.push_str("; the checkpoint is kept, rerun the backfill to resume");
+            }
+        }
+        Some(next) => {
+            let request = Request {
+                continuation: Some(next.clone()),
+                self_chain: Some(true),
+                ..Default::default()
+            };
+            match reinvoke(
+                &aws_config,
+                &event.context.env_config.function_name,
+                &request,
+            )
+            .await
             {
-                Ok(resp) => {
+                Ok(()) => {
                     info!(
-                        "Queued tender {} (message ID: {})",
-                        record.resource_id,
-                        resp.message_id().unwrap_or_default()
+                        "🔁 Continuing pages {}-{} in invocation {}",
+                        next.next_page,
+                        next.end_page - 1,
+                        next.invocation
                     );
-                    queued_count += 1;
+                    message.push_str(&format!("; continuing from page {}", next.next_page));
                 }
                 Err(e) => {
-                    error!("Failed to queue tender {}: {}", record.resource_id, e);
+                    // Don't fail the invocation: a retry would re-queue the pages already sent
+                    error!(
+                        "Failed to reinvoke scraper, pages {}-{} were not scraped: {}",
+                        next.next_page,
+                        next.end_page - 1,
+                        e
+                    );
+                    success = false;
+                    unscraped = Some((next.next_page, next.end_page - 1));
+                    message.push_str(&format!(
+                        "; failed to continue, pages {}-{} were not scraped",
+                        next.next_page,
+                        next.end_page - 1
+                    ));
                 }
             }
         }
+    }
 
-        info!("Successfully queued {} records to SQS", queued_count);
-    } else {
-        info!("Test mode: skipping SQS queue");
+    // The last invocation of a run (or one that cut it short) reports the whole run to ops
+    let run_stats = continuation.run_stats(&stats);
+    if (next.is_none() || unscraped.is_some()) && !test_mode {
+        if let Err(e) = send_run_summary(&aws_config, &run_stats, unscraped, None).await {
+            error!("Failed to send scraper run summary: {}", e);
+        }
     }
 
     info!("=== ETENDERS SCRAPER COMPLETED ===");
 
     Ok(Response {
-        records_count: records.len(),
-        success: true,
-        message: format!(
-            "Scraped {} tenders, queued {} to SQS",
-            records.len(),
-            queued_count
-        ),
+        records_count,
+        success,
+        message,
         queued_to_sqs: queued_count,
+        new_records: new_count,
+        skipped_known: stats.skipped_known,
+        filtered_out: stats.filtered_out.clone(),
+        done: next.is_none(),
+        continuation: next,
+        run_stats,
     })
 }
 
-async fn scrape_tenders(
+/// Report a run that stops on `error` at `page`, returning the error for the invocation to fail with.
+/// The end-of-handler summary is never reached on this path, so ops would otherwise hear nothing
+async fn fail_run(
+    aws_config: &aws_config::SdkConfig,
+    continuation: &Continuation,
+    stats: &RunStats,
+    page: u32,
+    test_mode: bool,
+    error: String,
+) -> Error {
+    error!("{}", error);
+    if !test_mode {
+        let run_stats = continuation.run_stats(stats);
+        let unscraped = Some((page, continuation.end_page - 1));
+        if let Err(e) = send_run_summary(aws_config, &run_stats, unscraped, Some(&error)).await {
+            error!("Failed to send scraper run summary: {}", e);
+        }
+    }
+    Error::from(error.as_str())
+}
+
+/// Queue the end-of-run summary for sns_notification, which emails it to the ops recipients.
+/// `error` is what stopped the run, for a run that failed part way
+async fn send_run_summary(
+    aws_config: &aws_config::SdkConfig,
+    stats: &RunStats,
+    unscraped: Option<(u32, u32)>,
+    error: Option<&str>,
+) -> Result<()> {
+    let problems = stats.problems(unscraped);
+    let title = if problems.is_empty() {
+        format!(
+            "Scraper run complete: {} tenders from {} pages",
+            stats.tenders_found, stats.pages_crawled
+        )
+    } else {
+        format!("Scraper run needs attention: {}", problems.join("; "))
+    };
+    let message = SNSMessage {
+        message_type: RUN_SUMMARY_MESSAGE_TYPE.to_string(),
+        resource_id: "0".to_string(),
+        title: title.clone(),
+        priority: if problems.is_empty() {
+            "NORMAL"
+        } else {
+            "HIGH"
+        }
+        .to_string(),
+        summary: match error {
+            Some(error) => format!("{}\n\nRun failed: {}", stats.summary(unscraped), error),
+            None => stats.summary(unscraped),
+        },
+        action_required: if problems.is_empty() {
+            "None"
+        } else {
+            "Check the scraper logs for this run"
+        }
+        .to_string(),
+        timestamp: Utc::now(),
+        metadata: serde_json::to_value(stats)?,
+    };
+
+    let queue_url = ResourceDiscovery::new(aws_config)
+        .resolve(Resource::NotificationQueue)
+        .await?;
+    SqsClient::new(aws_config)
+        .send_message()
+        .queue_url(&queue_url)
+        .message_body(serde_json::to_string(&message)?)
+        .send()
+        .await?;
+
+    info!("📨 Queued scraper run summary: {}", title);
+    Ok(())
+}
+
+/// Send the records to the tender processing queue in batches, returning how many were queued
+async fn queue_records(
+    sqs_client: &SqsClient,
+    queue_url: &str,
+    records: &[TenderRecord],
+) -> Result<usize, Error> {
+    let entries = records
+        .iter()
+        .map(|record| serde_json::to_string(record).map(Entry::new))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Error::from(format!("Failed to serialize record: {}", e).as_str()))?;
+
+    let outcomes = batch::send_batch(sqs_client, queue_url, &entries).await;
+    let mut queued_count = 0;
+    for (record, sent) in records.iter().zip(outcomes) {
+        match sent {
+            Ok(message_id) => {
+                info!(
+                    "Queued tender {} (message ID: {})",
+                    record.resource_id, message_id
+                );
+                queued_count += 1;
+            }
+            Err(e) => {
+                error!("Failed to queue tender {}: {}", record.resource_id, e);
+            }
+        }
+    }
+
+    Ok(queued_count)
+}
+
+/// Hand the rest of the page range to a fresh asynchronous invocation of this function
+async fn reinvoke(
+    aws_config: &aws_config::SdkConfig,
+    function_name: &str,
+    request: &Request,
+) -> Result<()> {
+    LambdaClient::new(aws_config)
+        .invoke()
+        .function_name(function_name)
+        .invocation_type(InvocationType::Event)
+        .payload(Blob::new(serde_json::to_vec(request)?))
+        .send()
+        .await?;
+    Ok(())
+}
+
+/// Fill in each record's document inventory, falling back to the notice alone when the
+/// portal's document list can't be had. Returns how many records fell back
+async fn attach_documents(client: &Client, records: &mut [TenderRecord]) -> usize {
+    let mut fallbacks = 0;
+    for record in records.iter_mut() {
+        record.documents = match documents::fetch_documents(client, record.resource_id).await {
+            Ok(documents) if !documents.is_empty() => {
+                info!(
+                    "📎 {} documents listed for tender {}",
+                    documents.len(),
+                    record.resource_id
+                );
+                documents
+            }
+            Ok(_) => {
+                warn!("No documents listed for tender {}", record.resource_id);
+                fallbacks += 1;
+                documents::notice_only(record.resource_id)
+            }
+            Err(e) => {
+                warn!(
+                    "Document list unavailable for tender {}, using the notice only: {}",
+                    record.resource_id, e
+                );
+                fallbacks += 1;
+                documents::notice_only(record.resource_id)
+            }
+        };
+    }
+    fallbacks
+}
+
+/// Fill in each record's description, CPV and NUTS codes, lots and (when given) buyer
+/// contact from its detail page; a page that can't be read leaves them empty
+async fn attach_details(client: &Client, records: &mut [TenderRecord]) {
+    for record in records.iter_mut() {
+        match detail::scrape_tender_detail(client, record.resource_id).await {
+            Ok(detail) => detail.apply(record),
+            Err(e) => warn!(
+                "Detail page unavailable for tender {}, no description or codes: {}",
+                record.resource_id, e
+            ),
+        }
+    }
+}
+
+/// Fill in the buyer contact from the notice page for records whose detail page gave none;
+/// a page that can't be read leaves the contact empty
+async fn attach_contacts(client: &Client, records: &mut [TenderRecord]) {
+    for record in records.iter_mut().filter(|r| r.buyer_contact.is_none()) {
+        match contacts::fetch_contact(client, record.resource_id).await {
+            Ok(contact) => record.buyer_contact = contact,
+            Err(e) => warn!(
+                "Notice page unavailable for tender {}, no buyer contact: {}",
+                record.resource_id, e
+            ),
+        }
+    }
+}
+
+/// Tenders on one results page, the number of rows that couldn't be parsed, and the last
+/// page the paginator shows. Without `latest` the search covers every notice on the portal,
+/// not only the current ones
+async fn scrape_page(
     client: &Client,
     base_url: &str,
-    start_page: u32,
-    end_page: u32,
-) -> Result<Vec<TenderRecord>> {
-    let mut all_records = Vec::new();
-
-    for page in start_page..end_page {
-        info!("Fetching page {}/{}", page, end_page - 1);
-
-        let url = format!(
-            "{}?d-3680175-p={}&searchType=cftFTS&latest=true",
-            base_url, page
-        );
-
-        let response = client
-            .get(&url)
-            .send()
-            .await
-            .context(format!("Failed to fetch page {}", page))?;
+    page: u32,
+    latest: bool,
+) -> Result<(Vec<TenderRecord>, usize, Option<u32>)> {
+    let mut url = format!(
+        "{}?{}={}&searchType=cftFTS",
+        base_url,
+        pagination::PAGE_PARAM,
+        page
+    );
+    if latest {
+        url.push_str("&latest=true");
+    }
 
-        let body = response
-            .text()
-            .await
-            .context(format!("Failed to read response body for page {}", page))?;
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .context(format!("Failed to fetch page {}", page))?;
 
-        let doc = Html::parse_document(&body);
-        let row_sel = Selector::parse("tbody tr").unwrap();
+    let body = response
+        .text()
+        .await
+        .context(format!("Failed to read response body for page {}", page))?;
 
-        let mut page_records = Vec::new();
+    let doc = Html::parse_document(&body);
+    let row_sel = Selector::parse("tbody tr").unwrap();
 
-        for row in doc.select(&row_sel) {
-            match parse_tender_row(&row) {
-                Ok(record) => page_records.push(record),
-                Err(e) => {
-                    warn!("Failed to parse tender row: {}", e);
-                    continue;
-                }
+    let mut page_records = Vec::new();
+    let mut parse_failures = 0;
+
+    for row in doc.select(&row_sel) {
+        match parse_tender_row(&row) {
+            Ok(record) => page_records.push(record),
+            Err(e) => {
+                warn!("Failed to parse tender row: {}", e);
+                parse_failures += 1;
+                continue;
             }
         }
-
-        info!("Parsed {} records from page {}", page_records.len(), page);
-        all_records.extend(page_records);
     }
 
-    Ok(all_records)
+    info!("Parsed {} records from page {}", page_records.len(), page);
+    Ok((page_records, parse_failures, pagination::last_page(&doc)))
 }
 
 fn parse_tender_row(row: &scraper::ElementRef) -> Result<TenderRecord> {
@@ -247,6 +686,10 @@ fn parse_tender_row(row: &scraper::ElementRef) -> Result<TenderRecord> {
 
     // Extract resource_id from column 3 (plain text number)
     let resource_id = col3_content.clone();
+    if resource_id.parse::<i64>().is_err() {
+        // Usually a sign the portal's table layout changed
+        anyhow::bail!("no resource id in column 3: '{}'", resource_id);
+    }
 
     // Extract title from column 2's anchor tag text
     let title = col2_text.clone();
@@ -255,10 +698,7 @@ fn parse_tender_row(row: &scraper::ElementRef) -> Result<TenderRecord> {
     info!("Extracted title: {}", title);
 
     let pdf_url = if !resource_id.is_empty() {
-        format!(
-            "https://www.etenders.gov.ie/epps/cft/downloadNoticeForAdvSearch.do?resourceId={}",
-            resource_id
-        )
+        documents::notice_url(&resource_id)
     } else {
         String::new()
     };
@@ -319,16 +759,25 @@ impl From<TenderRecordRaw> for TenderRecord {
             published: parse_irish_datetime(&raw.published),
             deadline: parse_irish_datetime(&raw.deadline),
             procedure: raw.procedure,
-            status: raw.status,
+            status: normalize_status(&raw.status),
             pdf_url: raw.pdf_url,
             awarddate: parse_irish_date(&raw.awarddate),
             value: parse_tender_value(&raw.value),
             cycle: raw.cycle,
             bid: None,
+            ..Default::default()
         }
     }
 }
 
+/// The status's normalised label, or the portal's text tidied up when it isn't recognised
+fn normalize_status(status: &str) -> String {
+    TenderStatus::normalize(status)
+        .label()
+        .map(str::to_string)
+        .unwrap_or_else(|| tender_status::clean(status))
+}
+
 fn parse_irish_date(date_str: &str) -> Option<NaiveDate> {
     if date_str.is_empty() {
         return None;
@@ -387,5 +836,9 @@ async fn main() -> Result<(), Error> {
         .without_time()
         .init();
 
-    lambda_runtime::run(service_fn(function_handler)).await
+    let environment = Environment::from_env();
+    lambda_runtime::run(service_fn(move |event| {
+        function_handler(event).instrument(environment.span())
+    }))
+    .await
 }
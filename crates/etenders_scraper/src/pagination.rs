@@ -0,0 +1,81 @@
+//! How many results pages the search has, read from the results page itself.
+//!
+//! The results table is a displaytag table: a banner ("1,234 items found, displaying 1 to
+//! 20.") above it and page links (`?d-3680175-p=N`) around it. The paginator only links a
+//! window of pages plus "Last", so the highest page linked is the last page; the banner is
+//! the fallback when the links are missing (a single page, or a layout change).
+
+use regex::Regex;
+use scraper::{Html, Selector};
+use std::sync::LazyLock;
+
+/// Query parameter displaytag uses for the page number of the results table
+pub const PAGE_PARAM: &str = "d-3680175-p";
+
+static PAGE_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(r"[?&;]{}=(\d+)", PAGE_PARAM)).expect("valid page link pattern")
+});
+
+static BANNER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)([\d,]+)\s+items?\s+found(?:,\s*displaying\s+([\d,]+)\s+to\s+([\d,]+))?")
+        .expect("valid page banner pattern")
+});
+
+fn number(value: &str) -> Option<u32> {
+    value.replace(',', "").parse().ok()
+}
+
+/// The last results page, None when the page says nothing about pagination
+pub fn last_page(doc: &Html) -> Option<u32> {
+    let link_sel = Selector::parse("a[href]").unwrap();
+    let linked = doc
+        .select(&link_sel)
+        .filter_map(|link| link.value().attr("href"))
+        .filter_map(|href| PAGE_LINK.captures(href))
+        .filter_map(|caps| number(&caps[1]))
+        .max();
+    linked.or_else(|| banner_last_page(&doc.root_element().text().collect::<String>()))
+}
+
+/// Total results over the rows shown per page, from "N items found, displaying A to B"
+fn banner_last_page(text: &str) -> Option<u32> {
+    let caps = BANNER.captures(text)?;
+    let total = number(&caps[1])?;
+    let shown = match (caps.get(2), caps.get(3)) {
+        (Some(from), Some(to)) => number(to.as_str())?.checked_sub(number(from.as_str())?)? + 1,
+        _ => total,
+    };
+    if total == 0 {
+        return Some(1);
+    }
+    Some(total.div_ceil(shown.max(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_page_from_paginator_links() {
+        let body = r#"<span class="pagebanner">4,321 items found, displaying 21 to 40.</span>
+            <span class="pagelinks">[<a href="/epps/quickSearchAction.do?d-3680175-p=1&amp;searchType=cftFTS">First</a>]
+            <a href="/epps/quickSearchAction.do?searchType=cftFTS&amp;d-3680175-p=3">3</a>
+            [<a href="/epps/quickSearchAction.do?searchType=cftFTS&amp;d-3680175-p=217">Last</a>]</span>"#;
+
+        assert_eq!(last_page(&Html::parse_document(body)), Some(217));
+    }
+
+    #[test]
+    fn test_last_page_from_banner() {
+        let page = |body: &str| last_page(&Html::parse_document(body));
+
+        assert_eq!(
+            page("<span>1,234 items found, displaying 1 to 20.</span>"),
+            Some(62)
+        );
+        assert_eq!(page("<span>One item found.</span>"), None);
+        assert_eq!(page("<span>7 items found.</span>"), Some(1));
+        assert_eq!(page("<span>0 items found.</span>"), Some(1));
+        assert_eq!(page("<html><body>Session expired</body></html>"), None);
+    }
+}
@@ -0,0 +1,524 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::filters::{FilteredOut, Filters};
+
+/// Stop starting new pages once less than this is left before the lambda deadline
+const DEFAULT_TIME_MARGIN_SECS: u64 = 60;
+/// Pages scraped by a single invocation before handing the rest of the range on
+const DEFAULT_PAGES_PER_INVOCATION: u32 = 10;
+/// Upper bound on invocations in one chain, so a bad range can't reinvoke forever
+const DEFAULT_MAX_INVOCATIONS: u32 = 100;
+/// Look up each tender's document list; one extra request per tender
+const DEFAULT_FETCH_DOCUMENTS: bool = true;
+/// Read each tender's detail page for its description, codes and lots; one extra request per tender
+const DEFAULT_FETCH_DETAILS: bool = true;
+/// Read the notice page for the buyer contact when the detail page has none
+const DEFAULT_FETCH_CONTACTS: bool = true;
+/// Pause between results pages in a backfill, which walks thousands of them
+const DEFAULT_BACKFILL_DELAY_MS: u64 = 2000;
+/// Known tenders in a row after which an incremental run stops paging
+const DEFAULT_KNOWN_STREAK: u32 = 25;
+
+/// Scraper input.
+///
+/// A fresh run sets `start_page`/`max_pages`; a follow-up invocation passes the
+/// `continuation` from the previous response instead (the other fields are then ignored).
+/// `all_pages` replaces `max_pages` with the last page the results paginator shows, and
+/// `backfill` does the same over the portal's whole history rather than the latest notices,
+/// pausing between pages and checkpointing so a rerun resumes where it stopped (from
+/// `start_page` instead when one is given). `incremental` skips tenders already stored and
+/// stops paging at a streak of them (see `known`); without `max_pages` it runs up to the
+/// last page. `cpv_prefixes`, `contracting_authorities`, `statuses`, `min_value` and
+/// `max_value` narrow what is queued to the matching tenders (see `filters`).
+/// With `self_chain` (the default) the scraper reinvokes itself until the range is done;
+/// an external orchestrator such as Step Functions sets it to false and loops on the
+/// response's `continuation` until `done`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Request {
+    pub max_pages: Option<u32>,
+    pub test_mode: Option<bool>,
+    pub start_page: Option<u32>,
+    pub continuation: Option<Continuation>,
+    pub self_chain: Option<bool>,
+    pub all_pages: Option<bool>,
+    pub backfill: Option<bool>,
+    pub incremental: Option<bool>,
+    #[serde(default)]
+    pub cpv_prefixes: Vec<String>,
+    #[serde(default)]
+    pub contracting_authorities: Vec<String>,
+    #[serde(default)]
+    pub statuses: Vec<String>,
+    pub min_value: Option<BigDecimal>,
+    pub max_value: Option<BigDecimal>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Response {
+    pub records_count: usize,
+    pub success: bool,
+    pub message: String,
+    pub queued_to_sqs: usize,
+    /// Tenders not yet stored, in an incremental run (all of them otherwise)
+    pub new_records: usize,
+    /// Tenders an incremental run left out as already stored
+    pub skipped_known: usize,
+    /// Tenders the request's filters left out
+    pub filtered_out: FilteredOut,
+    /// Where the next invocation picks up; None once the whole range is scraped
+    pub continuation: Option<Continuation>,
+    pub done: bool,
+    /// Totals for the run so far, including this invocation
+    pub run_stats: RunStats,
+}
+
+/// Counts for a whole run, carried between invocations in the continuation token
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunStats {
+    pub pages_crawled: u32,
+    pub tenders_found: usize,
+    /// Tenders published in the 24 hours before they were scraped
+    pub new_tenders: usize,
+    /// Result rows that couldn't be parsed into a tender
+    pub parse_failures: usize,
+    /// Tenders scraped but not sent to the processing queue
+    pub queue_failures: usize,
+    /// Tenders queued with only their notice because the document list was unavailable
+    #[serde(default)]
+    pub document_list_fallbacks: usize,
+    /// Tenders an incremental run left out as already stored
+    #[serde(default)]
+    pub skipped_known: usize,
+    /// Tenders the request's filters left out
+    #[serde(default)]
+    pub filtered_out: FilteredOut,
+}
+
+impl RunStats {
+    pub fn add(&mut self, other: &RunStats) {
+        self.pages_crawled += other.pages_crawled;
+        self.tenders_found += other.tenders_found;
+        self.new_tenders += other.new_tenders;
+        self.parse_failures += other.parse_failures;
+        self.queue_failures += other.queue_failures;
+        self.document_list_fallbacks += other.document_list_fallbacks;
+        self.skipped_known += other.skipped_known;
+        self.filtered_out.add(&other.filtered_out);
+    }
+
+    /// Reasons the run needs a look, empty for a healthy run.
+    /// `unscraped` is the page range left undone when the run was cut short
+    pub fn problems(&self, unscraped: Option<(u32, u32)>) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Some((from, to)) = unscraped {
+            problems.push(format!("pages {}-{} were not scraped", from, to));
+        }
+        if self.pages_crawled > 0 && self.tenders_found == 0 {
+            problems.push("no tenders found - the portal layout may have changed".to_string());
+        }
+        if self.parse_failures > 0 {
+            problems.push(format!("{} rows could not be parsed", self.parse_failures));
+        }
+        if self.queue_failures > 0 {
+            problems.push(format!(
+                "{} tenders could not be queued",
+                self.queue_failures
+            ));
+        }
+        problems
+    }
+
+    /// Plain-text body of the ops summary
+    pub fn summary(&self, unscraped: Option<(u32, u32)>) -> String {
+        let mut lines = vec![
+            format!("Pages crawled: {}", self.pages_crawled),
+            format!("Tenders found: {}", self.tenders_found),
+            format!(
+                "New tenders (published in the last 24h): {}",
+                self.new_tenders
+            ),
+            format!("Parse failures: {}", self.parse_failures),
+            format!("Queue send failures: {}", self.queue_failures),
+            format!("Document list fallbacks: {}", self.document_list_fallbacks),
+        ];
+        if self.skipped_known > 0 {
+            lines.push(format!("Already stored (skipped): {}", self.skipped_known));
+        }
+        let filtered = &self.filtered_out;
+        if filtered.total() > 0 {
+            lines.push(format!(
+                "Filtered out: {} (status {}, authority {}, value {}, CPV {})",
+                filtered.total(),
+                filtered.status,
+                filtered.contracting_authority,
+                filtered.value,
+                filtered.cpv
+            ));
+        }
+        let problems = self.problems(unscraped);
+        if !problems.is_empty() {
+            lines.push(String::new());
+            lines.push("Needs attention:".to_string());
+            lines.extend(problems.iter().map(|p| format!("- {}", p)));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Progress through a page range that spans several invocations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Continuation {
+    pub next_page: u32,
+    /// Exclusive
+    pub end_page: u32,
+    pub test_mode: bool,
+    /// 1-based position of the invocation that will consume this token
+    pub invocation: u32,
+    /// Totals from the earlier invocations of the run
+    #[serde(default)]
+    pub stats: RunStats,
+    /// `end_page` follows the paginator's last page as pages are scraped
+    #[serde(default)]
+    pub discover_pages: bool,
+    /// Walking the whole portal history, checkpointed as it goes
+    #[serde(default)]
+    pub backfill: bool,
+    /// Skipping stored tenders, and stopping at a streak of them
+    #[serde(default)]
+    pub incremental: bool,
+    /// Stored tenders seen in a row so far, across invocations
+    #[serde(default)]
+    pub known_streak: u32,
+    /// Only tenders matching these are queued
+    #[serde(default)]
+    pub filters: Filters,
+}
+
+impl Continuation {
+    /// The token for the first invocation of a run, or the one carried by the request
+    pub fn from_request(request: &Request) -> Self {
+        if let Some(continuation) = &request.continuation {
+            return continuation.clone();
+        }
+
+        let test_mode = request.test_mode.unwrap_or(false);
+        let backfill = !test_mode && request.backfill.unwrap_or(false);
+        let incremental = !test_mode && request.incremental.unwrap_or(false);
+        let discover_pages = backfill
+            || (!test_mode && request.all_pages.unwrap_or(false))
+            || (incremental && request.max_pages.is_none());
+        let start_page = request.start_page.unwrap_or(1);
+        // A discovered range starts at one page until that page's paginator is read
+        let max_pages = if test_mode || discover_pages {
+            1
+        } else {
+            request.max_pages.unwrap_or(10)
+        };
+
+        Self {
+            next_page: start_page,
+            end_page: start_page + max_pages,
+            test_mode,
+            invocation: 1,
+            stats: RunStats::default(),
+            discover_pages,
+            backfill,
+            incremental,
+            known_streak: 0,
+            filters: Filters {
+                cpv_prefixes: request.cpv_prefixes.clone(),
+                contracting_authorities: request.contracting_authorities.clone(),
+                statuses: request.statuses.clone(),
+                min_value: request.min_value.clone(),
+                max_value: request.max_value.clone(),
+            },
+        }
+    }
+
+    /// End the range at `last_page` when the range is discovered. The portal gains and
+    /// loses pages during a long backfill, so every page read moves it
+    pub fn discovered(&mut self, last_page: u32) {
+        if self.discover_pages {
+            self.end_page = last_page.saturating_add(1);
+        }
+    }
+
+    /// End the range before `page`; nothing after it needs scraping
+    pub fn stop_before(&mut self, page: u32) {
+        self.end_page = self.end_page.min(page);
+    }
+
+    /// Skip ahead to a saved checkpoint, scraping at least its next page to rediscover the range
+    pub fn resume(&mut self, checkpoint: &Checkpoint) {
+        if checkpoint.next_page > self.next_page {
+            self.next_page = checkpoint.next_page;
+            self.end_page = self
+                .end_page
+                .max(checkpoint.end_page)
+                .max(self.next_page + 1);
+        }
+    }
+
+    /// Exclusive end of the pages this invocation should attempt
+    pub fn batch_end(&self, pages_per_invocation: u32) -> u32 {
+        self.next_page
+            .saturating_add(pages_per_invocation.max(1))
+            .min(self.end_page)
+    }
+
+    /// Token for the next invocation once pages before `next_page` are done,
+    /// carrying the run's totals including this invocation's `stats`
+    pub fn advance(&self, next_page: u32, stats: &RunStats) -> Option<Self> {
+        (next_page < self.end_page).then(|| Self {
+            next_page,
+            end_page: self.end_page,
+            test_mode: self.test_mode,
+            invocation: self.invocation + 1,
+            stats: self.run_stats(stats),
+            discover_pages: self.discover_pages,
+            backfill: self.backfill,
+            incremental: self.incremental,
+            known_streak: self.known_streak,
+            filters: self.filters.clone(),
+        })
+    }
+
+    /// The run's totals so far: earlier invocations plus this one's `stats`
+    pub fn run_stats(&self, stats: &RunStats) -> RunStats {
+        let mut total = self.stats.clone();
+        total.add(stats);
+        total
+    }
+}
+
+/// How far a backfill has got, saved after every page (see `checkpoint`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub next_page: u32,
+    /// Exclusive end of the range when it was saved
+    pub end_page: u32,
+    pub saved_at: DateTime<Utc>,
+}
+
+fn env_or<T: std::str::FromStr>(var: &str, default: T) -> T {
+    std::env::var(var)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+pub fn time_margin() -> Duration {
+    Duration::from_secs(env_or("SCRAPER_TIME_MARGIN_SECS", DEFAULT_TIME_MARGIN_SECS))
+}
+
+pub fn pages_per_invocation() -> u32 {
+    env_or("SCRAPER_PAGES_PER_INVOCATION", DEFAULT_PAGES_PER_INVOCATION)
+}
+
+pub fn max_invocations() -> u32 {
+    env_or("SCRAPER_MAX_INVOCATIONS", DEFAULT_MAX_INVOCATIONS)
+}
+
+pub fn fetch_document_lists() -> bool {
+    env_or("SCRAPER_FETCH_DOCUMENTS", DEFAULT_FETCH_DOCUMENTS)
+}
+
+pub fn fetch_details() -> bool {
+    env_or("SCRAPER_FETCH_DETAILS", DEFAULT_FETCH_DETAILS)
+}
+
+pub fn fetch_contacts() -> bool {
+    env_or("SCRAPER_FETCH_CONTACTS", DEFAULT_FETCH_CONTACTS)
+}
+
+pub fn known_streak() -> u32 {
+    env_or("SCRAPER_KNOWN_STREAK", DEFAULT_KNOWN_STREAK).max(1)
+}
+
+/// Database checked for stored tenders in an incremental run
+pub fn database_url() -> Option<String> {
+    std::env::var("DATABASE_URL")
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+}
+
+pub fn backfill_delay() -> Duration {
+    Duration::from_millis(env_or(
+        "SCRAPER_BACKFILL_DELAY_MS",
+        DEFAULT_BACKFILL_DELAY_MS,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fresh_request_starts_a_chain() {
+        let request = Request {
+            start_page: Some(5),
+            max_pages: Some(25),
+            ..Default::default()
+        };
+
+        let continuation = Continuation::from_request(&request);
+        assert_eq!(continuation.next_page, 5);
+        assert_eq!(continuation.end_page, 30);
+        assert_eq!(continuation.invocation, 1);
+        assert_eq!(continuation.batch_end(10), 15);
+    }
+
+    #[test]
+    fn test_chain_walks_range_in_bounded_batches() {
+        let mut continuation = Continuation::from_request(&Request {
+            max_pages: Some(25),
+            ..Default::default()
+        });
+        let mut batches = Vec::new();
+
+        loop {
+            let end = continuation.batch_end(10);
+            batches.push((continuation.next_page, end));
+            match continuation.advance(end, &RunStats::default()) {
+                Some(next) => continuation = next,
+                None => break,
+            }
+        }
+
+        assert_eq!(batches, vec![(1, 11), (11, 21), (21, 26)]);
+        assert_eq!(continuation.invocation, 3);
+    }
+
+    #[test]
+    fn test_continuation_round_trips_through_request() {
+        let token = Continuation {
+            next_page: 12,
+            end_page: 40,
+            test_mode: false,
+            invocation: 2,
+            stats: RunStats {
+                pages_crawled: 11,
+                tenders_found: 200,
+                ..Default::default()
+            },
+            discover_pages: true,
+            backfill: true,
+            incremental: false,
+            known_streak: 0,
+            filters: Filters::default(),
+        };
+        let payload = serde_json::json!({ "continuation": token, "start_page": 1 });
+        let request: Request = serde_json::from_value(payload).unwrap();
+
+        assert_eq!(Continuation::from_request(&request), token);
+    }
+
+    #[test]
+    fn test_filters_are_carried_through_the_chain() {
+        let payload = serde_json::json!({
+            "max_pages": 20,
+            "cpv_prefixes": ["72"],
+            "min_value": 50000
+        });
+        let first = Continuation::from_request(&serde_json::from_value(payload).unwrap());
+        assert_eq!(first.filters.cpv_prefixes, vec!["72"]);
+        assert_eq!(first.filters.min_value, Some(BigDecimal::from(50000)));
+
+        let next = first.advance(11, &RunStats::default()).unwrap();
+        let token = serde_json::json!({ "continuation": next });
+        let request: Request = serde_json::from_value(token).unwrap();
+        assert_eq!(Continuation::from_request(&request).filters, first.filters);
+        assert!(Continuation::from_request(&Request::default())
+            .filters
+            .is_empty());
+    }
+
+    #[test]
+    fn test_stats_accumulate_across_invocations() {
+        let first = Continuation::from_request(&Request {
+            max_pages: Some(20),
+            ..Default::default()
+        });
+        let page_stats = RunStats {
+            pages_crawled: 10,
+            tenders_found: 200,
+            new_tenders: 12,
+            parse_failures: 1,
+            queue_failures: 0,
+            document_list_fallbacks: 3,
+            skipped_known: 0,
+            filtered_out: FilteredOut::default(),
+        };
+
+        let second = first.advance(11, &page_stats).unwrap();
+        let total = second.run_stats(&page_stats);
+
+        assert_eq!(total.pages_crawled, 20);
+        assert_eq!(total.tenders_found, 400);
+        assert_eq!(total.parse_failures, 2);
+        assert_eq!(total.document_list_fallbacks, 6);
+        assert_eq!(total.problems(None), vec!["2 rows could not be parsed"]);
+    }
+
+    #[test]
+    fn test_backfill_discovers_range_and_resumes() {
+        let mut continuation = Continuation::from_request(&Request {
+            backfill: Some(true),
+            max_pages: Some(5),
+            ..Default::default()
+        });
+        assert_eq!((continuation.next_page, continuation.end_page), (1, 2));
+
+        continuation.resume(&Checkpoint {
+            next_page: 41,
+            end_page: 180,
+            saved_at: Utc::now(),
+        });
+        assert_eq!((continuation.next_page, continuation.end_page), (41, 180));
+        assert_eq!(continuation.batch_end(10), 51);
+
+        continuation.discovered(184);
+        let next = continuation.advance(51, &RunStats::default()).unwrap();
+        assert_eq!((next.next_page, next.end_page), (51, 185));
+        assert!(next.backfill && next.discover_pages);
+
+        let mut latest = Continuation::from_request(&Request {
+            max_pages: Some(5),
+            ..Default::default()
+        });
+        latest.discovered(184);
+        assert_eq!(latest.end_page, 6);
+    }
+
+    #[test]
+    fn test_incremental_run_stops_at_known_tenders() {
+        let mut continuation = Continuation::from_request(&Request {
+            incremental: Some(true),
+            ..Default::default()
+        });
+        assert!(continuation.discover_pages);
+        continuation.discovered(40);
+        continuation.known_streak = 12;
+
+        let next = continuation.advance(11, &RunStats::default()).unwrap();
+        assert_eq!(next.known_streak, 12);
+        assert!(next.incremental);
+
+        continuation.stop_before(4);
+        assert_eq!(continuation.advance(4, &RunStats::default()), None);
+    }
+
+    #[test]
+    fn test_empty_pages_are_a_problem() {
+        let stats = RunStats {
+            pages_crawled: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(stats.problems(Some((4, 10))).len(), 2);
+        assert!(RunStats::default().problems(None).is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+//! Random failures in the calls a stage depends on, for testing the retry, dead-letter and
+//! idempotency handling against something that actually fails.
+//!
+//! Call sites ask `check(Fault::...)` just before the real call and, when it returns an
+//! error, fail the way that call would: a Claude request error inside the `claude` retry
+//! policy, an SDK timeout inside `Policy::aws`, a database error where the row is written.
+//! Everything downstream - retries, `StageError` codes, batch item failures, redelivery and
+//! the dead-letter queue - is the production path.
+//!
+//! Faults are compiled in only with the `enabled` feature, which the stages expose as their
+//! own `fault_injection` feature for the LocalStack integration build; release builds get
+//! a `check` that always passes. When compiled in, each fault fires at the rate in its
+//! `FAULT_*_RATE` variable (0 to 1, unset is 0), and never in prod.
+
+use std::fmt;
+
+/// A kind of call that can be made to fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// A Claude request fails, as an overloaded or unreachable API does
+    Claude,
+    /// An S3 request times out
+    S3Timeout,
+    /// A database write fails before reaching the table
+    DbWrite,
+}
+
+impl Fault {
+    pub const ALL: [Fault; 3] = [Fault::Claude, Fault::S3Timeout, Fault::DbWrite];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Fault::Claude => "claude",
+            Fault::S3Timeout => "s3_timeout",
+            Fault::DbWrite => "db_write",
+        }
+    }
+
+    pub fn env_var(self) -> &'static str {
+        match self {
+            Fault::Claude => "FAULT_CLAUDE_RATE",
+            Fault::S3Timeout => "FAULT_S3_TIMEOUT_RATE",
+            Fault::DbWrite => "FAULT_DB_WRITE_RATE",
+        }
+    }
+
+    /// Share of calls to fail, from the fault's variable; anything unparseable is 0
+    pub fn rate_from_env(self) -> f64 {
+        parse_rate(std::env::var(self.env_var()).ok().as_deref())
+    }
+}
+
+/// The error an injected fault returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Injected {
+    pub fault: Fault,
+}
+
+impl fmt::Display for Injected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let call = match self.fault {
+            Fault::Claude => "Claude request failed",
+            Fault::S3Timeout => "S3 request timed out",
+            Fault::DbWrite => "database write failed",
+        };
+        write!(f, "{} (injected, {})", call, self.fault.env_var())
+    }
+}
+
+impl std::error::Error for Injected {}
+
+fn parse_rate(value: Option<&str>) -> f64 {
+    value
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|rate| rate.is_finite())
+        .map(|rate| rate.clamp(0.0, 1.0))
+        .unwrap_or(0.0)
+}
+
+/// Whether a call fails at `rate`, with `random` in [0, 1) as the roll
+pub fn fires(rate: f64, random: f64) -> bool {
+    random < rate
+}
+
+/// Fail this call at the fault's configured rate
+#[cfg(feature = "enabled")]
+pub fn check(fault: Fault) -> Result<(), Injected> {
+    if environment::Environment::from_env().is_production() {
+        return Ok(());
+    }
+    if !fires(fault.rate_from_env(), fastrand::f64()) {
+        return Ok(());
+    }
+    tracing::warn!("💥 Injecting fault: {}", fault.name());
+    Err(Injected { fault })
+}
+
+/// Faults aren't compiled in; every call goes ahead
+#[cfg(not(feature = "enabled"))]
+#[inline(always)]
+pub fn check(_fault: Fault) -> Result<(), Injected> {
+    Ok(())
+}
+
+/// The rates in effect, for config reports, e.g. "claude 0.2, s3_timeout 0, db_write 0.05"
+pub fn describe() -> String {
+    if !cfg!(feature = "enabled") {
+        return "not built".to_string();
+    }
+    if environment::Environment::from_env().is_production() {
+        return "off in prod".to_string();
+    }
+    Fault::ALL
+        .iter()
+        .map(|&fault| format!("{} {}", fault.name(), fault.rate_from_env()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rates_are_clamped_and_default_to_zero() {
+        assert_eq!(parse_rate(None), 0.0);
+        assert_eq!(parse_rate(Some(" 0.25 ")), 0.25);
+        assert_eq!(parse_rate(Some("2")), 1.0);
+        assert_eq!(parse_rate(Some("-1")), 0.0);
+        assert_eq!(parse_rate(Some("NaN")), 0.0);
+        assert_eq!(parse_rate(Some("often")), 0.0);
+
+        assert!(fires(0.25, 0.1));
+        assert!(!fires(0.25, 0.25));
+        assert!(!fires(0.0, 0.0));
+        assert!(fires(1.0, 0.999));
+    }
+
+    #[test]
+    fn test_injected_error_names_its_variable() {
+        let error = Injected {
+            fault: Fault::DbWrite,
+        };
+        assert_eq!(
+            error.to_string(),
+            "database write failed (injected, FAULT_DB_WRITE_RATE)"
+        );
+    }
+}
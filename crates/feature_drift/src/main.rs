@@ -0,0 +1,247 @@
+use anyhow::Result;
+use aws_config::BehaviorVersion;
+use aws_sdk_sns::Client as SnsClient;
+use environment::Environment;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use ml_bid_predictor::drift::{self, DriftReport, Snapshot};
+use ml_bid_predictor::features::FeatureExtractor;
+use ml_bid_predictor::ml_predictor::MODEL_VERSION;
+use ml_bid_predictor::types::{FeatureVector, TenderRecord};
+use serde_json::Value;
+use sqlx::{PgPool, Row};
+use tracing::{error, info, warn, Instrument};
+
+mod types;
+
+use types::{alert_lines, metric_line, Config, Report};
+
+/// Labelled tenders read for the training snapshot, enough for stable means
+const TRAINING_SAMPLE_LIMIT: i64 = 5000;
+
+/// Triggered on a schedule (EventBridge, daily); the event body is not used
+async fn function_handler(_event: LambdaEvent<Value>) -> Result<Value, Error> {
+    info!("=== FEATURE DRIFT CHECK STARTED ===");
+
+    let config = Config::from_env().map_err(|e| {
+        error!("Failed to load configuration: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+
+    let pool = db::connect(&config.database_url, 2)
+        .await
+        .map_err(|e| Error::from(format!("Failed to connect to database: {}", e).as_str()))?;
+    ensure_table(&pool).await.map_err(|e| {
+        Error::from(format!("Failed to create ml_feature_snapshots table: {}", e).as_str())
+    })?;
+
+    let extractor = FeatureExtractor::new();
+    let mut report = Report {
+        model_version: MODEL_VERSION,
+        snapshot_captured: false,
+        drift: None,
+    };
+
+    let training = match load_snapshot(&pool).await {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => {
+            let snapshot = capture_snapshot(&pool, &extractor)
+                .await
+                .map_err(|e| Error::from(format!("Failed to capture snapshot: {}", e).as_str()))?;
+            let Some(snapshot) = snapshot else {
+                warn!("⚠️ No labelled tenders with PDF content to take a training snapshot from");
+                return Ok(serde_json::to_value(&report)?);
+            };
+            info!(
+                "📸 Training snapshot for model {} taken from {} labelled tenders",
+                MODEL_VERSION, snapshot.samples
+            );
+            report.snapshot_captured = true;
+            snapshot
+        }
+        Err(e) => {
+            return Err(Error::from(
+                format!("Failed to load training snapshot: {}", e).as_str(),
+            ))
+        }
+    };
+
+    let recent = recent_vectors(&pool, &extractor, config.window_days)
+        .await
+        .map_err(|e| Error::from(format!("Failed to load recent tenders: {}", e).as_str()))?;
+    if recent.len() < config.min_samples {
+        info!(
+            "🔍 Only {} tenders with PDF content in the last {} days (need {}); skipping",
+            recent.len(),
+            config.window_days,
+            config.min_samples
+        );
+        return Ok(serde_json::to_value(&report)?);
+    }
+    let Some(recent) = Snapshot::from_vectors(&recent) else {
+        return Ok(serde_json::to_value(&report)?);
+    };
+
+    let drift = drift::compare(&training, &recent, config.threshold);
+    let environment = Environment::from_env();
+    // Not through tracing: CloudWatch only parses the metric from a bare JSON line
+    println!("{}", metric_line(environment.name(), MODEL_VERSION, &drift));
+
+    if drift.drifted.is_empty() {
+        info!(
+            "✅ No drift over {} recent tenders (largest shift {:.2} std devs)",
+            drift.recent_samples, drift.max_shift
+        );
+    } else {
+        error!(
+            "🚨 Feature drift for model {}: {} shifted more than {} std devs (largest {:.2})",
+            MODEL_VERSION,
+            drift.drifted.join(", "),
+            config.threshold,
+            drift.max_shift
+        );
+        alert(&config, &environment, &drift).await;
+    }
+
+    report.drift = Some(drift);
+    info!("=== FEATURE DRIFT CHECK COMPLETED ===");
+    Ok(serde_json::to_value(&report)?)
+}
+
+async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "ml_feature_snapshots", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS ml_feature_snapshots (
+                model_version TEXT PRIMARY KEY,
+                snapshot JSONB NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "ml_feature_snapshots").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+async fn load_snapshot(pool: &PgPool) -> Result<Option<Snapshot>> {
+    let snapshot: Option<Value> =
+        sqlx::query_scalar("SELECT snapshot FROM ml_feature_snapshots WHERE model_version = $1")
+            .bind(MODEL_VERSION)
+            .fetch_optional(pool)
+            .await?;
+    Ok(snapshot.map(serde_json::from_value).transpose()?)
+}
+
+/// Snapshot the labelled tenders with PDF content - the data the current weights were
+/// tuned on - and store it against MODEL_VERSION
+async fn capture_snapshot(pool: &PgPool, extractor: &FeatureExtractor) -> Result<Option<Snapshot>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT t.resource_id, t.title, t.ca, p.pdf_text, p.codes_count
+        FROM tender_records t
+        JOIN pdf_content p ON p.resource_id = t.resource_id
+        WHERE t.bid IS NOT NULL AND t.resource_id > 0 AND p.pdf_text <> ''
+        ORDER BY t.resource_id
+        LIMIT $1
+        "#,
+    )
+    .bind(TRAINING_SAMPLE_LIMIT)
+    .fetch_all(pool)
+    .await?;
+
+    let Some(snapshot) = Snapshot::from_vectors(&vectors(&rows, extractor)) else {
+        return Ok(None);
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO ml_feature_snapshots (model_version, snapshot)
+        VALUES ($1, $2)
+        ON CONFLICT (model_version) DO NOTHING
+        "#,
+    )
+    .bind(MODEL_VERSION)
+    .bind(serde_json::to_value(&snapshot)?)
+    .execute(pool)
+    .await?;
+    Ok(Some(snapshot))
+}
+
+/// Features of the tenders with PDF content loaded in the last `days` days
+async fn recent_vectors(
+    pool: &PgPool,
+    extractor: &FeatureExtractor,
+    days: i64,
+) -> Result<Vec<FeatureVector>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT t.resource_id, t.title, t.ca, p.pdf_text, p.codes_count
+        FROM tender_records t
+        JOIN pdf_content p ON p.resource_id = t.resource_id
+        WHERE t.created_at > NOW() - make_interval(days => $1::INT)
+          AND t.resource_id > 0 AND p.pdf_text <> ''
+        "#,
+    )
+    .bind(days)
+    .fetch_all(pool)
+    .await?;
+    Ok(vectors(&rows, extractor))
+}
+
+/// Same features the predictor computes; a tender that fails extraction is left out
+fn vectors(rows: &[sqlx::postgres::PgRow], extractor: &FeatureExtractor) -> Vec<FeatureVector> {
+    rows.iter()
+        .filter_map(|row| {
+            let tender = TenderRecord {
+                resource_id: row.get("resource_id"),
+                title: row.get("title"),
+                contracting_authority: row.get("ca"),
+                pdf_content: Some(row.get("pdf_text")),
+                codes_count: row.get("codes_count"),
+                ..Default::default()
+            };
+            extractor.extract_features(&tender).ok()
+        })
+        .collect()
+}
+
+/// Publish the drift warning to the alert topic when configured
+async fn alert(config: &Config, environment: &Environment, report: &DriftReport) {
+    let Some(topic_arn) = &config.alert_topic_arn else {
+        return;
+    };
+
+    let aws_config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+    if let Err(e) = SnsClient::new(&aws_config)
+        .publish()
+        .topic_arn(topic_arn)
+        .subject(format!(
+            "[{}] Bid model input drift in {} features",
+            environment.name().to_uppercase(),
+            report.drifted.len()
+        ))
+        .message(alert_lines(report).join("\n"))
+        .send()
+        .await
+    {
+        error!("Failed to publish drift warning: {}", e);
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    let environment = Environment::from_env();
+    run(service_fn(move |event| {
+        function_handler(event).instrument(environment.span())
+    }))
+    .await
+}
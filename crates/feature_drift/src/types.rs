@@ -0,0 +1,160 @@
+use ml_bid_predictor::drift::{self, DriftReport};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+pub const DEFAULT_WINDOW_DAYS: i64 = 7;
+pub const DEFAULT_MIN_SAMPLES: usize = 30;
+/// CloudWatch namespace for the embedded metric
+pub const METRIC_NAMESPACE: &str = "TenderPipeline";
+pub const METRIC_NAME: &str = "FeatureDrift";
+
+/// Configuration from environment
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database_url: String,
+    /// Tenders loaded in the last this many days are compared with the snapshot
+    pub window_days: i64,
+    /// Fewer recent tenders than this (with PDF content) say nothing reliable
+    pub min_samples: usize,
+    pub threshold: f64,
+    /// SNS topic for the drift warning; drift is always logged at error level
+    pub alert_topic_arn: Option<String>,
+}
+
+impl Config {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let database_url = std::env::var("DATABASE_URL")
+            .map_err(|_| anyhow::anyhow!("DATABASE_URL environment variable not set"))?;
+
+        let window_days = std::env::var("DRIFT_WINDOW_DAYS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_WINDOW_DAYS);
+
+        let min_samples = std::env::var("DRIFT_MIN_SAMPLES")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_MIN_SAMPLES);
+
+        let threshold = std::env::var("DRIFT_THRESHOLD")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(drift::DEFAULT_THRESHOLD);
+
+        let alert_topic_arn = std::env::var("DRIFT_ALERT_TOPIC_ARN")
+            .ok()
+            .filter(|arn| !arn.trim().is_empty());
+
+        Ok(Self {
+            database_url,
+            window_days,
+            min_samples,
+            threshold,
+            alert_topic_arn,
+        })
+    }
+}
+
+/// Returned by the lambda so a manual invocation shows what happened
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub model_version: &'static str,
+    /// The training snapshot was taken on this run
+    pub snapshot_captured: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drift: Option<DriftReport>,
+}
+
+/// CloudWatch embedded metric format: printed on its own line, CloudWatch Logs turns
+/// it into the FeatureDrift metric (largest shift) without a PutMetricData call
+pub fn metric_line(environment: &str, model_version: &str, report: &DriftReport) -> Value {
+    let mut line = json!({
+        "_aws": {
+            "Timestamp": chrono::Utc::now().timestamp_millis(),
+            "CloudWatchMetrics": [{
+                "Namespace": METRIC_NAMESPACE,
+                "Dimensions": [["Environment"]],
+                "Metrics": [{ "Name": METRIC_NAME, "Unit": "None" }]
+            }]
+        },
+        "Environment": environment,
+        "ModelVersion": model_version,
+        METRIC_NAME: report.max_shift,
+        "RecentSamples": report.recent_samples,
+    });
+    // Per-feature shifts ride along as properties, searchable in Logs Insights
+    for feature in &report.features {
+        line[format!("shift_{}", feature.feature)] = json!(feature.shift);
+    }
+    line
+}
+
+/// Body of the ops warning, one line per drifted feature
+pub fn alert_lines(report: &DriftReport) -> Vec<String> {
+    let mut lines = vec![format!(
+        "Feature distributions of the last {} tenders have shifted from the {} training tenders.",
+        report.recent_samples, report.training_samples
+    )];
+    lines.extend(
+        report
+            .features
+            .iter()
+            .filter(|f| report.drifted.contains(&f.feature))
+            .map(|f| {
+                format!(
+                    "{}: mean {:.3} now vs {:.3} in training ({:.1} std devs)",
+                    f.feature, f.recent_mean, f.training_mean, f.shift
+                )
+            }),
+    );
+    lines.push(
+        "Check for a change in the portal's notices or in PDF extraction before trusting the bid model."
+            .to_string(),
+    );
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ml_bid_predictor::drift::FeatureDrift;
+
+    #[test]
+    fn test_metric_line_and_alert() {
+        let report = DriftReport {
+            training_samples: 400,
+            recent_samples: 52,
+            features: vec![
+                FeatureDrift {
+                    feature: "codes_count".to_string(),
+                    training_mean: 1.8,
+                    recent_mean: 0.2,
+                    shift: 1.5,
+                },
+                FeatureDrift {
+                    feature: "tfidf_software".to_string(),
+                    training_mean: 0.3,
+                    recent_mean: 0.31,
+                    shift: 0.05,
+                },
+            ],
+            max_shift: 1.5,
+            drifted: vec!["codes_count".to_string()],
+        };
+
+        let line = metric_line("prod", "v1", &report);
+        assert_eq!(line["FeatureDrift"], json!(1.5));
+        assert_eq!(line["shift_tfidf_software"], json!(0.05));
+        assert_eq!(
+            line["_aws"]["CloudWatchMetrics"][0]["Metrics"][0]["Name"],
+            "FeatureDrift"
+        );
+
+        let lines = alert_lines(&report);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            "codes_count: mean 0.200 now vs 1.800 in training (1.5 std devs)"
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! Recipient feedback on notification emails, kept in `tender_feedback`.
+//!
+//! sns_notification puts signed "This was not relevant" / "Good call" links in each
+//! email; tender_api verifies the signature and records the verdict. A "not relevant"
+//! verdict from the default tenant also labels an unlabelled tender as a no-bid, so it
+//! feeds the training data.
+//! weekly_report turns repeated negatives into exclusion-term suggestions.
+//!
+//! Links are signed with HMAC-SHA256 over the tender, tenant and verdict, so a link
+//! can't be edited into feedback on another tender (or the opposite verdict).
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use environment::Environment;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::{PgPool, Row};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// ai_summary's `tenants::DEFAULT_TENANT`, the company `tender_records.bid` labels are for
+const DEFAULT_TENANT: &str = "default";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    NotRelevant,
+    GoodCall,
+}
+
+impl Verdict {
+    /// Value stored in `tender_feedback.verdict` and used in links
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verdict::NotRelevant => "not_relevant",
+            Verdict::GoodCall => "good_call",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "not_relevant" => Some(Verdict::NotRelevant),
+            "good_call" => Some(Verdict::GoodCall),
+            _ => None,
+        }
+    }
+
+    /// Wording used in the email and on the confirmation page
+    pub fn label(&self) -> &'static str {
+        match self {
+            Verdict::NotRelevant => "This was not relevant",
+            Verdict::GoodCall => "Good call",
+        }
+    }
+}
+
+fn mac(key: &str, resource_id: i64, tenant_id: &str, verdict: Verdict) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}:{}:{}", resource_id, tenant_id, verdict.as_str()).as_bytes());
+    mac
+}
+
+/// Hex-encoded signature for a feedback link
+pub fn sign(key: &str, resource_id: i64, tenant_id: &str, verdict: Verdict) -> String {
+    hex::encode(
+        mac(key, resource_id, tenant_id, verdict)
+            .finalize()
+            .into_bytes(),
+    )
+}
+
+/// Constant-time check of a link's signature
+pub fn verify(
+    key: &str,
+    resource_id: i64,
+    tenant_id: &str,
+    verdict: Verdict,
+    signature: &str,
+) -> bool {
+    match hex::decode(signature) {
+        Ok(bytes) => mac(key, resource_id, tenant_id, verdict)
+            .verify_slice(&bytes)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Signed `GET {base_url}/feedback` link for the email
+pub fn link(
+    base_url: &str,
+    key: &str,
+    resource_id: i64,
+    tenant_id: &str,
+    verdict: Verdict,
+) -> String {
+    format!(
+        "{}/feedback?resource_id={}&tenant_id={}&verdict={}&sig={}",
+        base_url.trim_end_matches('/'),
+        resource_id,
+        encode(tenant_id),
+        verdict.as_str(),
+        sign(key, resource_id, tenant_id, verdict)
+    )
+}
+
+/// Percent-encode everything but unreserved characters
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+pub async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "tender_feedback", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS tender_feedback (
+                resource_id BIGINT NOT NULL,
+                tenant_id TEXT NOT NULL,
+                verdict TEXT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (resource_id, tenant_id)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "tender_feedback").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Whether the verdict is a no-bid label. Only the default tenant's count: another
+/// tenant's "not relevant" says nothing about whether the default company should bid
+fn labels_no_bid(tenant_id: &str, verdict: Verdict) -> bool {
+    tenant_id == DEFAULT_TENANT && verdict == Verdict::NotRelevant
+}
+
+/// Record a verdict; clicking the other link later replaces it
+pub async fn record(
+    pool: &PgPool,
+    resource_id: i64,
+    tenant_id: &str,
+    verdict: Verdict,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+        INSERT INTO tender_feedback (resource_id, tenant_id, verdict)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (resource_id, tenant_id) DO UPDATE SET
+            verdict = EXCLUDED.verdict,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(resource_id)
+    .bind(tenant_id)
+    .bind(verdict.as_str())
+    .execute(&mut *tx)
+    .await?;
+
+    // Never overwrite a label someone already set
+    if labels_no_bid(tenant_id, verdict) {
+        sqlx::query("UPDATE tender_records SET bid = 0 WHERE resource_id = $1 AND bid IS NULL")
+            .bind(resource_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// A recorded verdict with the tender fields the weekly report needs
+#[derive(Debug, Clone)]
+pub struct FeedbackEntry {
+    pub resource_id: i64,
+    pub tenant_id: String,
+    pub verdict: Verdict,
+    pub title: String,
+    pub contracting_authority: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Verdicts recorded (or changed) since the given time
+pub async fn since(pool: &PgPool, since: DateTime<Utc>) -> Result<Vec<FeedbackEntry>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT f.resource_id, f.tenant_id, f.verdict, f.updated_at, t.title, t.ca
+        FROM tender_feedback f
+        JOIN tender_records t ON t.resource_id = f.resource_id
+        WHERE f.updated_at >= $1
+        ORDER BY f.updated_at
+        "#,
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(FeedbackEntry {
+                resource_id: row.get("resource_id"),
+                tenant_id: row.get("tenant_id"),
+                verdict: Verdict::parse(row.get("verdict"))?,
+                title: row.get("title"),
+                contracting_authority: row.get("ca"),
+                updated_at: row.get("updated_at"),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_covers_tender_tenant_and_verdict() {
+        let signature = sign("key", 42, "default", Verdict::NotRelevant);
+        let valid = |key, resource_id, tenant_id, verdict| {
+            verify(key, resource_id, tenant_id, verdict, &signature)
+        };
+
+        assert!(valid("key", 42, "default", Verdict::NotRelevant));
+        assert!(!valid("key", 43, "default", Verdict::NotRelevant));
+        assert!(!valid("key", 42, "other", Verdict::NotRelevant));
+        assert!(!valid("key", 42, "default", Verdict::GoodCall));
+        assert!(!valid("other", 42, "default", Verdict::NotRelevant));
+        assert!(!verify("key", 42, "default", Verdict::NotRelevant, "zz"));
+    }
+
+    #[test]
+    fn test_link_encodes_tenant() {
+        let url = link(
+            "https://api.example.com/",
+            "key",
+            7,
+            "sister co",
+            Verdict::GoodCall,
+        );
+
+        assert!(url.starts_with(
+            "https://api.example.com/feedback?resource_id=7&tenant_id=sister%20co&verdict=good_call&sig="
+        ));
+    }
+
+    #[test]
+    fn test_only_the_default_tenant_labels_no_bid() {
+        assert!(labels_no_bid(DEFAULT_TENANT, Verdict::NotRelevant));
+        assert!(!labels_no_bid(DEFAULT_TENANT, Verdict::GoodCall));
+        assert!(!labels_no_bid("sister co", Verdict::NotRelevant));
+    }
+}
@@ -1,17 +1,24 @@
-use aws_config;
 use aws_sdk_s3::Client as S3Client;
 use bigdecimal::BigDecimal;
 use chrono::{NaiveDate, NaiveDateTime};
 use lambda_runtime::{Error, LambdaEvent, service_fn};
+use outbound_http::HttpClient;
 use regex::Regex;
 use reqwest::Client;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Postgres, postgres::PgPoolOptions};
+use std::collections::HashMap;
 use std::env;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::task::JoinSet;
 
-use pdf_processing::{extract_codes, extract_text_from_pdf};
+use pdf_processing::{
+    CodeMatcher, ExtractionBudget, clarification, codes, extract_text_streaming,
+    pdf_content_migration, quality,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct TenderRecord {
@@ -52,8 +59,12 @@ struct Request {
     test_mode: Option<bool>,
     start_page: Option<u32>,
     offset: Option<u32>,
+    pdf_concurrency: Option<usize>,
 }
 
+/// PDFs downloaded/extracted at once unless overridden by the request or PDF_CONCURRENCY
+const DEFAULT_PDF_CONCURRENCY: usize = 5;
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Response {
     records_count: usize,
@@ -61,6 +72,33 @@ struct Response {
     message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     sample_records: Option<Vec<TenderRecord>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pdf_stats: Option<PdfStats>,
+}
+
+/// Aggregated outcome of the PDF fan-out
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct PdfStats {
+    attempted: usize,
+    succeeded: usize,
+    failed: usize,
+    total_chars: usize,
+    total_codes: usize,
+    concurrency: usize,
+    elapsed_ms: u128,
+    failures: Vec<PdfFailure>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PdfFailure {
+    resource_id: i64,
+    error: String,
+}
+
+/// Result of processing a single PDF
+struct PdfOutcome {
+    chars: usize,
+    codes: usize,
 }
 
 #[derive(Debug)]
@@ -95,17 +133,51 @@ async fn read_codes_from_storage(
     };
 
     // Parse codes using the same approach as pdf_processing
-    let codes: Vec<String> = content
-        .lines()
-        .filter_map(|line| line.split(',').next()) // Take everything before first comma
-        .map(|code| code.trim().to_string())
-        .filter(|code| !code.is_empty())
+    let codes: Vec<String> = codes::parse_codes_file(&content)
+        .into_iter()
+        .map(|c| c.code)
         .collect();
 
     println!("Loaded {} codes from {}", codes.len(), filename);
     Ok(codes)
 }
 
+/// Active codes from the detection_codes table, falling back to codes.txt in S3 until the table is seeded
+async fn load_code_matcher(pool: &Pool<Postgres>) -> Result<Arc<CodeMatcher>, Error> {
+    if let Some(matcher) = codes::cached_matcher(pool).await? {
+        println!("Using {} detection codes from database", matcher.len());
+        return Ok(matcher);
+    }
+
+    println!(
+        "WARNING: detection_codes table has no active codes - falling back to codes.txt in S3"
+    );
+    let bucket_name = env::var("LAMBDA_BUCKET_NAME")
+        .map_err(|_| "LAMBDA_BUCKET_NAME environment variable not set")?;
+
+    // Initialize AWS S3 client
+    let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        .load()
+        .await;
+    let s3_client = S3Client::new(&aws_config);
+    let storage = StorageBackend::S3 {
+        client: s3_client,
+        bucket: bucket_name,
+    };
+
+    // Read codes from S3
+    let codes = read_codes_from_storage(&storage, "codes.txt")
+        .await
+        .map_err(|e| format!("Failed to read codes from S3: {}", e))?;
+
+    if !codes.is_empty() {
+        println!("First 5 codes: {:?}", &codes[..codes.len().min(5)]);
+    } else {
+        println!("WARNING: No codes loaded from S3!");
+    }
+    Ok(Arc::new(CodeMatcher::new(&codes)))
+}
+
 async fn function_handler(event: LambdaEvent<Request>) -> Result<Response, Error> {
     println!("Starting get_data Lambda...");
 
@@ -139,8 +211,8 @@ async fn function_handler(event: LambdaEvent<Request>) -> Result<Response, Error
             .max_connections(5)
             .connect(&db_url)
             .await?;
-        ensure_tender_table_exists(&pool).await?;
-        ensure_pdf_table_exists(&pool).await?;
+        db::tables::ensure_tender_records(&pool).await?;
+        db::tables::ensure_pdf_content(&pool).await?;
         Some(pool)
     } else {
         None
@@ -177,123 +249,42 @@ async fn function_handler(event: LambdaEvent<Request>) -> Result<Response, Error
         }
     }
 
-    // Load detection codes from S3
-    let bucket_name = env::var("LAMBDA_BUCKET_NAME")
-        .map_err(|_| "LAMBDA_BUCKET_NAME environment variable not set")?;
-
-    // Initialize AWS S3 client
-    let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
-        .load()
-        .await;
-    let s3_client = S3Client::new(&aws_config);
-    let storage = StorageBackend::S3 {
-        client: s3_client,
-        bucket: bucket_name,
-    };
-
-    // Read codes from S3
-    let codes = read_codes_from_storage(&storage, "codes.txt")
-        .await
-        .map_err(|e| format!("Failed to read codes from S3: {}", e))?;
-
-    if codes.len() > 0 {
-        println!("First 5 codes: {:?}", &codes[..codes.len().min(5)]);
-    } else {
-        println!("WARNING: No codes loaded from S3!");
-    }
-
     // Process PDFs
-    if let Some(pool_ref) = &pool {
-        for record in records.iter().filter(|r| !r.pdf_url.is_empty()) {
-            if let Err(e) = process_pdf(&client, pool_ref, record, &codes).await {
-                println!("Error processing {}: {}", record.resource_id, e);
-            }
+    let concurrency = event
+        .payload
+        .pdf_concurrency
+        .or_else(|| env::var("PDF_CONCURRENCY").ok()?.parse().ok())
+        .unwrap_or(DEFAULT_PDF_CONCURRENCY)
+        .max(1);
+    let pdf_stats = match &pool {
+        Some(pool_ref) => {
+            let matcher = load_code_matcher(pool_ref).await?;
+            // pdf_url comes from the scraped page; only fetch from OUTBOUND_ALLOWED_HOSTS
+            let pdf_client = HttpClient::new(None)?;
+            Some(process_pdfs(&pdf_client, pool_ref, &records, matcher, concurrency).await)
         }
-    }
+        None => None,
+    };
 
     Ok(Response {
         records_count: records.len(),
         success: true,
-        message: format!("Processed {} tender records", records.len()),
+        message: match &pdf_stats {
+            Some(stats) => format!(
+                "Processed {} tender records ({} PDFs ok, {} failed)",
+                records.len(),
+                stats.succeeded,
+                stats.failed
+            ),
+            None => format!("Processed {} tender records", records.len()),
+        },
         sample_records: if test_mode { Some(records) } else { None },
+        pdf_stats,
     })
 }
 
 // ================= DB UTILITIES =================
 
-async fn ensure_tender_table_exists(pool: &Pool<Postgres>) -> Result<(), Error> {
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS tender_records (
-            id SERIAL PRIMARY KEY,
-            title TEXT NOT NULL,
-            resource_id TEXT NOT NULL UNIQUE,
-            ca TEXT NOT NULL,
-            info TEXT NOT NULL,
-            published TIMESTAMP WITHOUT TIME ZONE,
-            deadline TIMESTAMP WITHOUT TIME ZONE,
-            procedure TEXT NOT NULL,
-            status TEXT NOT NULL,
-            pdf_url TEXT NOT NULL,
-            awarddate DATE,
-            value DECIMAL(15,2),
-            cycle TEXT NOT NULL,
-            bid INTEGER DEFAULT NULL,
-            notification_sent BOOLEAN DEFAULT FALSE,
-            notification_sent_at TIMESTAMP WITH TIME ZONE DEFAULT NULL,
-            created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    // Add notification columns if they don't exist (for existing tables)
-    sqlx::query(
-        r#"
-        DO $$
-        BEGIN
-            IF NOT EXISTS (
-                SELECT 1 FROM information_schema.columns
-                WHERE table_name='tender_records' AND column_name='notification_sent'
-            ) THEN
-                ALTER TABLE tender_records ADD COLUMN notification_sent BOOLEAN DEFAULT FALSE;
-            END IF;
-
-            IF NOT EXISTS (
-                SELECT 1 FROM information_schema.columns
-                WHERE table_name='tender_records' AND column_name='notification_sent_at'
-            ) THEN
-                ALTER TABLE tender_records ADD COLUMN notification_sent_at TIMESTAMP WITH TIME ZONE DEFAULT NULL;
-            END IF;
-        END $$;
-        "#
-    )
-    .execute(pool)
-    .await?;
-
-    Ok(())
-}
-
-async fn ensure_pdf_table_exists(pool: &Pool<Postgres>) -> Result<(), Error> {
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS pdf_content (
-            resource_id BIGINT PRIMARY KEY,
-            pdf_text TEXT NOT NULL,
-            extraction_timestamp TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
-            processing_status TEXT NOT NULL,
-            metadata JSONB DEFAULT '{}'::JSONB,
-            detected_codes TEXT[],
-            codes_count INTEGER DEFAULT 0
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-    Ok(())
-}
-
 async fn filter_new_records(
     pool: &Pool<Postgres>,
     records: &[TenderRecord],
@@ -342,15 +333,15 @@ async fn save_records(pool: &Pool<Postgres>, records: &[TenderRecord]) -> Result
         .bind(rec.resource_id)
         .bind(&rec.ca)
         .bind(&rec.info)
-        .bind(&rec.published)
-        .bind(&rec.deadline)
+        .bind(rec.published)
+        .bind(rec.deadline)
         .bind(&rec.procedure)
         .bind(&rec.status)
         .bind(&rec.pdf_url)
-        .bind(&rec.awarddate)
+        .bind(rec.awarddate)
         .bind(&rec.value)
         .bind(&rec.cycle)
-        .bind(&rec.bid)
+        .bind(rec.bid)
         .execute(pool)
         .await?;
     }
@@ -362,53 +353,170 @@ async fn store_pdf_content_with_codes(
     resource_id: i64,
     pdf_text: &str,
     detected_codes: &[String],
+    page_offsets: &[usize],
+    clarification_deadline: Option<NaiveDateTime>,
 ) -> Result<(), Error> {
+    let page_offsets: Vec<i32> = page_offsets.iter().map(|&offset| offset as i32).collect();
+    // No OCR fallback here, so the text layer is always what gets stored
+    let extraction_quality = quality::score(pdf_text).score;
     sqlx::query(
         r#"
-        INSERT INTO pdf_content (resource_id, pdf_text, extraction_timestamp, processing_status, detected_codes, codes_count)
-        VALUES ($1,$2,CURRENT_TIMESTAMP,'COMPLETED',$3,$4)
+        INSERT INTO pdf_content (resource_id, pdf_text, extraction_timestamp, processing_status, detected_codes, codes_count, page_offsets, clarification_deadline, extraction_quality, extraction_method)
+        VALUES ($1,$2,CURRENT_TIMESTAMP,'COMPLETED',$3,$4,$5,$6,$7,'text')
         ON CONFLICT (resource_id) DO UPDATE SET
             pdf_text = EXCLUDED.pdf_text,
             extraction_timestamp = EXCLUDED.extraction_timestamp,
             processing_status = EXCLUDED.processing_status,
             detected_codes = EXCLUDED.detected_codes,
-            codes_count = EXCLUDED.codes_count
+            codes_count = EXCLUDED.codes_count,
+            page_offsets = EXCLUDED.page_offsets,
+            clarification_deadline = EXCLUDED.clarification_deadline,
+            extraction_quality = EXCLUDED.extraction_quality,
+            extraction_method = EXCLUDED.extraction_method
         "#
     )
     .bind(resource_id)
     .bind(pdf_text)
     .bind(detected_codes)
     .bind(detected_codes.len() as i32)
+    .bind(&page_offsets)
+    .bind(clarification_deadline)
+    .bind(extraction_quality)
     .execute(pool)
     .await?;
+
+    pdf_content_migration::mirror(
+        pool,
+        &pdf_content_migration::PdfContentRow {
+            resource_id,
+            pdf_text,
+            detected_codes,
+            page_offsets: &page_offsets,
+            clarification_deadline,
+            codes_source: None,
+            extraction_quality: Some(extraction_quality),
+            extraction_method: Some("text"),
+            document_metadata: None,
+        },
+    )
+    .await;
     Ok(())
 }
 
 // ================= PDF PROCESSING =================
 
+/// Download and extract every record's PDF, at most `concurrency` at a time.
+/// A failing PDF is recorded in the stats and never stops the others.
+async fn process_pdfs(
+    client: &HttpClient,
+    pool: &Pool<Postgres>,
+    records: &[TenderRecord],
+    matcher: Arc<CodeMatcher>,
+    concurrency: usize,
+) -> PdfStats {
+    let started = Instant::now();
+    let mut stats = PdfStats {
+        concurrency,
+        ..Default::default()
+    };
+    let mut tasks = JoinSet::new();
+    let mut in_flight = HashMap::new();
+    let mut pending = records.iter().filter(|r| !r.pdf_url.is_empty());
+
+    println!("Processing PDFs with concurrency {}", concurrency);
+    loop {
+        // Top up to the concurrency cap
+        while tasks.len() < concurrency {
+            let Some(record) = pending.next() else { break };
+            let (client, pool, matcher, record) = (
+                client.clone(),
+                pool.clone(),
+                matcher.clone(),
+                record.clone(),
+            );
+            let resource_id = record.resource_id;
+            let handle =
+                tasks.spawn(async move { process_pdf(&client, &pool, &record, matcher).await });
+            in_flight.insert(handle.id(), resource_id);
+            stats.attempted += 1;
+        }
+
+        let Some(joined) = tasks.join_next_with_id().await else {
+            break;
+        };
+        let (resource_id, result) = match joined {
+            Ok((id, result)) => (in_flight.remove(&id).unwrap_or_default(), result),
+            // A panic in one PDF (e.g. inside the extractor) only loses that PDF
+            Err(e) => (
+                in_flight.remove(&e.id()).unwrap_or_default(),
+                Err(format!("PDF task failed: {}", e).into()),
+            ),
+        };
+
+        match result {
+            Ok(outcome) => {
+                stats.succeeded += 1;
+                stats.total_chars += outcome.chars;
+                stats.total_codes += outcome.codes;
+            }
+            Err(e) => {
+                println!("Error processing {}: {}", resource_id, e);
+                stats.failed += 1;
+                stats.failures.push(PdfFailure {
+                    resource_id,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    stats.elapsed_ms = started.elapsed().as_millis();
+    println!(
+        "PDF processing finished in {}ms: {} ok, {} failed, {} chars, {} codes",
+        stats.elapsed_ms, stats.succeeded, stats.failed, stats.total_chars, stats.total_codes
+    );
+    stats
+}
+
 async fn process_pdf(
-    client: &Client,
+    client: &HttpClient,
     pool: &Pool<Postgres>,
     record: &TenderRecord,
-    codes: &[String],
-) -> Result<(), Error> {
+    matcher: Arc<CodeMatcher>,
+) -> Result<PdfOutcome, Error> {
     println!("Downloading PDF for {}", record.resource_id);
-    let response = client.get(&record.pdf_url).send().await?;
+    let response = client
+        .get_with_retry(&record.pdf_url, "pdf_download")
+        .await?;
     let response = response.error_for_status()?;
     let pdf_bytes = response.bytes().await?;
 
-    let pdf_text = extract_text_from_pdf(&pdf_bytes).map_err(|e| {
+    // Extraction is CPU-bound; keep it off the async workers so downloads keep flowing.
+    // Pages are streamed within the budget so very large PDFs can't exhaust memory
+    let extraction = tokio::task::spawn_blocking(move || {
+        extract_text_streaming(&pdf_bytes, &matcher, &ExtractionBudget::from_env())
+            .map_err(|e| e.to_string())
+    })
+    .await?
+    .map_err(|e| {
         let err: Error = format!("Text extraction failed: {}", e).into();
         err
     })?;
+    let pdf_text = extraction.text;
+    let detected_codes = extraction.detected_codes;
+    let page_offsets = extraction.page_offsets;
 
     println!(
-        "Extracted {} characters from PDF {}",
+        "Extracted {} characters from {}/{} pages of PDF {}",
         pdf_text.len(),
+        extraction.pages_processed,
+        extraction.total_pages,
         record.resource_id
     );
+    if let Some(reason) = extraction.stop_reason {
+        println!("Stopped early on PDF {}: {:?}", record.resource_id, reason);
+    }
 
-    let detected_codes = extract_codes(&pdf_text, codes);
     println!(
         "Detected {} codes in PDF {}: {:?}",
         detected_codes.len(),
@@ -424,8 +532,19 @@ async fn process_pdf(
         println!("Full PDF text: '{}'", pdf_text);
     }
 
-    store_pdf_content_with_codes(pool, record.resource_id, &pdf_text, &detected_codes).await?;
-    Ok(())
+    store_pdf_content_with_codes(
+        pool,
+        record.resource_id,
+        &pdf_text,
+        &detected_codes,
+        &page_offsets,
+        clarification::clarification_deadline(&pdf_text),
+    )
+    .await?;
+    Ok(PdfOutcome {
+        chars: pdf_text.len(),
+        codes: detected_codes.len(),
+    })
 }
 
 // ================= SCRAPER =================
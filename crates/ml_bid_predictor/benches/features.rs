@@ -0,0 +1,66 @@
+use bigdecimal::BigDecimal;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ml_bid_predictor::features::FeatureExtractor;
+use ml_bid_predictor::ml_predictor::OptimizedBidPredictor;
+use ml_bid_predictor::types::TenderRecord;
+use std::str::FromStr;
+
+/// Representative PDF text; repeated to reach the sizes seen in production
+const PDF_PARAGRAPH: &str = "The contracting authority requires software development and \
+technical support services, including systems integration, cloud hosting and cybersecurity. \
+Tenderers must provide evidence of ISO 27001 certification and experience delivering \
+digital transformation programmes for public sector bodies. ";
+
+fn tender_with_pdf(pdf_chars: usize) -> TenderRecord {
+    let pdf_content = PDF_PARAGRAPH.repeat(pdf_chars / PDF_PARAGRAPH.len() + 1);
+
+    TenderRecord {
+        resource_id: 1,
+        title: "Provision of Software Development and Technical Support Services".to_string(),
+        contracting_authority: "Health Service Executive".to_string(),
+        procedure: "Open".to_string(),
+        status: "Open".to_string(),
+        value: Some(BigDecimal::from_str("250000").unwrap()),
+        pdf_content: Some(pdf_content[..pdf_chars].to_string()),
+        detected_codes: Some(vec!["72000000".to_string(), "72200000".to_string()]),
+        codes_count: Some(2),
+        ..Default::default()
+    }
+}
+
+fn bench_extract_features(c: &mut Criterion) {
+    let extractor = FeatureExtractor::new();
+    let mut group = c.benchmark_group("extract_features");
+
+    for pdf_chars in [0, 10_000, 100_000] {
+        let tender = tender_with_pdf(pdf_chars);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(pdf_chars),
+            &tender,
+            |b, tender| b.iter(|| extractor.extract_features(black_box(tender)).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+/// Constructing an extractor is now free; before patterns were precompiled this rebuilt ~100 regexes
+fn bench_extractor_new(c: &mut Criterion) {
+    c.bench_function("feature_extractor_new", |b| b.iter(FeatureExtractor::new));
+}
+
+fn bench_predict(c: &mut Criterion) {
+    let predictor = OptimizedBidPredictor::new();
+    let tender = tender_with_pdf(10_000);
+
+    c.bench_function("predict/10000", |b| {
+        b.iter(|| predictor.predict(black_box(&tender)).unwrap())
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_extract_features,
+    bench_extractor_new,
+    bench_predict
+);
+criterion_main!(benches);
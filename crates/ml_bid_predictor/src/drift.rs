@@ -0,0 +1,208 @@
+//! Feature distribution drift: recent tenders compared with the training snapshot.
+//!
+//! A snapshot holds the mean and spread of each tracked feature over the tenders the
+//! model was tuned on. It is kept per `MODEL_VERSION`. When recent means move several
+//! standard deviations away from the snapshot, the portal's content or the PDF
+//! extraction has probably changed, and the fixed weights no longer fit the input.
+
+use crate::types::FeatureVector;
+use serde::{Deserialize, Serialize};
+
+/// Shift (in training standard deviations) above which a feature has drifted
+pub const DEFAULT_THRESHOLD: f64 = 0.5;
+/// Floor for the training standard deviation, so a feature that was constant in training
+/// doesn't turn a tiny change into a huge shift
+const MIN_STD_DEV: f64 = 0.01;
+
+/// Features compared: the codes count, the exclusion score and the TF-IDF terms
+pub const TRACKED_FEATURES: [&str; 12] = [
+    "codes_count",
+    "exclusion_score",
+    "tfidf_software",
+    "tfidf_support",
+    "tfidf_provision",
+    "tfidf_computer",
+    "tfidf_services",
+    "tfidf_systems",
+    "tfidf_management",
+    "tfidf_works",
+    "tfidf_package",
+    "tfidf_technical",
+];
+
+fn tracked(features: &FeatureVector) -> [f64; 12] {
+    [
+        features.codes_count,
+        features.exclusion_score,
+        features.tfidf_software,
+        features.tfidf_support,
+        features.tfidf_provision,
+        features.tfidf_computer,
+        features.tfidf_services,
+        features.tfidf_systems,
+        features.tfidf_management,
+        features.tfidf_works,
+        features.tfidf_package,
+        features.tfidf_technical,
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureStats {
+    pub feature: String,
+    pub mean: f64,
+    pub std_dev: f64,
+}
+
+/// Distribution summary of the tracked features over a set of tenders
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub samples: usize,
+    pub features: Vec<FeatureStats>,
+}
+
+impl Snapshot {
+    /// `None` for an empty set
+    pub fn from_vectors(vectors: &[FeatureVector]) -> Option<Self> {
+        if vectors.is_empty() {
+            return None;
+        }
+
+        let rows: Vec<[f64; 12]> = vectors.iter().map(tracked).collect();
+        let n = rows.len() as f64;
+        let features = TRACKED_FEATURES
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let mean = rows.iter().map(|row| row[i]).sum::<f64>() / n;
+                let variance = rows.iter().map(|row| (row[i] - mean).powi(2)).sum::<f64>() / n;
+                FeatureStats {
+                    feature: name.to_string(),
+                    mean,
+                    std_dev: variance.sqrt(),
+                }
+            })
+            .collect();
+
+        Some(Self {
+            samples: rows.len(),
+            features,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureDrift {
+    pub feature: String,
+    pub training_mean: f64,
+    pub recent_mean: f64,
+    /// |recent mean - training mean| in training standard deviations
+    pub shift: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftReport {
+    pub training_samples: usize,
+    pub recent_samples: usize,
+    pub features: Vec<FeatureDrift>,
+    /// Largest shift of any feature; the drift metric
+    pub max_shift: f64,
+    /// Features whose shift is over the threshold
+    pub drifted: Vec<String>,
+}
+
+/// Compare recent tenders with the training snapshot. Features missing from either
+/// snapshot (e.g. one taken before a feature was tracked) are skipped
+pub fn compare(training: &Snapshot, recent: &Snapshot, threshold: f64) -> DriftReport {
+    let features: Vec<FeatureDrift> = training
+        .features
+        .iter()
+        .filter_map(|trained| {
+            let now = recent
+                .features
+                .iter()
+                .find(|stats| stats.feature == trained.feature)?;
+            Some(FeatureDrift {
+                feature: trained.feature.clone(),
+                training_mean: trained.mean,
+                recent_mean: now.mean,
+                shift: (now.mean - trained.mean).abs() / trained.std_dev.max(MIN_STD_DEV),
+            })
+        })
+        .collect();
+
+    DriftReport {
+        training_samples: training.samples,
+        recent_samples: recent.samples,
+        max_shift: features.iter().map(|f| f.shift).fold(0.0, f64::max),
+        drifted: features
+            .iter()
+            .filter(|f| f.shift > threshold)
+            .map(|f| f.feature.clone())
+            .collect(),
+        features,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vector(codes_count: f64, exclusion_score: f64, tfidf_software: f64) -> FeatureVector {
+        FeatureVector {
+            codes_count,
+            has_codes: if codes_count > 0.0 { 1.0 } else { 0.0 },
+            title_length: 40.0,
+            ca_encoded: 0.0,
+            exclusion_score,
+            tfidf_software,
+            tfidf_support: 0.1,
+            tfidf_provision: 0.0,
+            tfidf_computer: 0.0,
+            tfidf_services: 0.2,
+            tfidf_systems: 0.0,
+            tfidf_management: 0.0,
+            tfidf_works: 0.0,
+            tfidf_package: 0.0,
+            tfidf_technical: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_stats() {
+        let snapshot =
+            Snapshot::from_vectors(&[vector(1.0, 0.0, 0.2), vector(3.0, 2.0, 0.4)]).unwrap();
+
+        assert_eq!(snapshot.samples, 2);
+        assert_eq!(snapshot.features.len(), TRACKED_FEATURES.len());
+        let codes = &snapshot.features[0];
+        assert_eq!(codes.feature, "codes_count");
+        assert!((codes.mean - 2.0).abs() < 1e-9);
+        assert!((codes.std_dev - 1.0).abs() < 1e-9);
+        assert!(Snapshot::from_vectors(&[]).is_none());
+    }
+
+    #[test]
+    fn test_shifted_features_drift() {
+        let training =
+            Snapshot::from_vectors(&[vector(1.0, 0.0, 0.2), vector(3.0, 2.0, 0.4)]).unwrap();
+        // Extraction stopped finding codes; the text itself looks the same
+        let recent =
+            Snapshot::from_vectors(&[vector(0.0, 0.0, 0.2), vector(0.0, 2.0, 0.4)]).unwrap();
+
+        let report = compare(&training, &recent, DEFAULT_THRESHOLD);
+
+        assert_eq!(report.drifted, vec!["codes_count"]);
+        assert!((report.max_shift - 2.0).abs() < 1e-9);
+        // Constant in training and unchanged: no shift despite the zero spread
+        let services = report
+            .features
+            .iter()
+            .find(|f| f.feature == "tfidf_services")
+            .unwrap();
+        assert_eq!(services.shift, 0.0);
+        assert!(compare(&training, &training, DEFAULT_THRESHOLD)
+            .drifted
+            .is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::types::{TenderRecord, FeatureVector};
 use anyhow::Result;
-use regex::Regex;
+use aho_corasick::AhoCorasick;
 use std::collections::HashMap;
 use once_cell::sync::Lazy;
 
@@ -12,10 +12,12 @@ use once_cell::sync::Lazy;
 /// 3. title_length - Text complexity
 /// 4. ca_encoded - Contracting authority
 /// 5. exclusion_score - Non-IT sector filtering (NEW)
-/// 6-15. TF-IDF features for key terms
+/// 6. to 15. TF-IDF features for key terms
 pub struct FeatureExtractor {
-    term_patterns: Vec<Regex>,
-    exclusion_patterns: Vec<Regex>,
+    term_counter: &'static TermCounter,
+    exclusion_counter: &'static TermCounter,
+    high_weight_counter: &'static TermCounter,
+    phrase_counter: &'static TermCounter,
 }
 
 /// Static key terms identified as most predictive for bids
@@ -71,6 +73,70 @@ static EXCLUSION_TERMS: &[&str] = &[
     "waste management", "recycling", "sustainability",
 ];
 
+/// High-weight exclusion indicators (double scoring)
+static HIGH_WEIGHT_TERMS: &[&str] = &[
+    "construction", "building", "road", "bridge", "civil engineering",
+    "mechanical", "electrical", "plumbing", "hvac", "infrastructure",
+    "excavation", "concrete", "steel", "demolition", "refurbishment"
+];
+
+/// Specific problematic phrases (1.5x weight, matched anywhere - not just on word boundaries)
+static EXCLUSION_PHRASES: &[&str] = &[
+    "ground investigation", "site investigation", "civil works",
+    "building works", "construction works", "mechanical works",
+    "electrical works", "infrastructure works", "road works",
+    "maintenance works", "repair works", "cleaning services",
+    "security services", "catering services", "transport services", 
+    "school meals", "meal service", "food service", "breakfast provision",
+    "lunch provision", "dinner provision", "catering service", "food provision"
+];
+
+/// Counts every term of a list in a single pass over the text (case-insensitive)
+struct TermCounter {
+    automaton: AhoCorasick,
+    /// Only count matches on word boundaries, like a regex `\bterm\b`
+    whole_words: bool,
+}
+
+impl TermCounter {
+    /// The term lists are static so failure is a programming error
+    fn new(terms: &[&str], whole_words: bool) -> Self {
+        let automaton = AhoCorasick::builder()
+            .ascii_case_insensitive(true)
+            .build(terms)
+            .expect("Failed to build term matcher");
+        Self { automaton, whole_words }
+    }
+
+    /// Occurrences of each term, in term-list order
+    fn counts(&self, text: &str) -> Vec<usize> {
+        let mut counts = vec![0; self.automaton.patterns_len()];
+        // Overlapping so "meals" inside "school meals" still counts, as the per-term regexes did
+        for m in self.automaton.find_overlapping_iter(text) {
+            if !self.whole_words || is_whole_word(text, m.start(), m.end()) {
+                counts[m.pattern().as_usize()] += 1;
+            }
+        }
+        counts
+    }
+
+    fn total(&self, text: &str) -> usize {
+        self.counts(text).iter().sum()
+    }
+}
+
+fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    !text[..start].chars().next_back().is_some_and(is_word_char)
+        && !text[end..].chars().next().is_some_and(is_word_char)
+}
+
+// Built once per lambda container rather than per extractor or per call
+static TERM_COUNTER: Lazy<TermCounter> = Lazy::new(|| TermCounter::new(KEY_TERMS, true));
+static EXCLUSION_COUNTER: Lazy<TermCounter> = Lazy::new(|| TermCounter::new(EXCLUSION_TERMS, true));
+static HIGH_WEIGHT_COUNTER: Lazy<TermCounter> = Lazy::new(|| TermCounter::new(HIGH_WEIGHT_TERMS, true));
+static PHRASE_COUNTER: Lazy<TermCounter> = Lazy::new(|| TermCounter::new(EXCLUSION_PHRASES, false));
+
 /// Common contracting authorities mapping for encoding
 static CA_MAPPING: Lazy<HashMap<&'static str, u32>> = Lazy::new(|| {
     let mut map = HashMap::new();
@@ -91,22 +157,11 @@ static CA_MAPPING: Lazy<HashMap<&'static str, u32>> = Lazy::new(|| {
 impl FeatureExtractor {
     /// Create new feature extractor
     pub fn new() -> Self {
-        // Pre-compile regex patterns for efficiency
-        let term_patterns = KEY_TERMS
-            .iter()
-            .map(|term| Regex::new(&format!(r"(?i)\b{}\b", regex::escape(term))))
-            .collect::<Result<Vec<_>, _>>()
-            .expect("Failed to compile regex patterns");
-
-        let exclusion_patterns = EXCLUSION_TERMS
-            .iter()
-            .map(|term| Regex::new(&format!(r"(?i)\b{}\b", regex::escape(term))))
-            .collect::<Result<Vec<_>, _>>()
-            .expect("Failed to compile exclusion regex patterns");
-
         Self {
-            term_patterns,
-            exclusion_patterns,
+            term_counter: &TERM_COUNTER,
+            exclusion_counter: &EXCLUSION_COUNTER,
+            high_weight_counter: &HIGH_WEIGHT_COUNTER,
+            phrase_counter: &PHRASE_COUNTER,
         }
     }
     
@@ -193,38 +248,13 @@ impl FeatureExtractor {
         let mut exclusion_score = 0.0;
         
         // High-weight exclusion indicators (double scoring)
-        let high_weight_terms = [
-            "construction", "building", "road", "bridge", "civil engineering",
-            "mechanical", "electrical", "plumbing", "hvac", "infrastructure",
-            "excavation", "concrete", "steel", "demolition", "refurbishment"
-        ];
-        
-        for term in &high_weight_terms {
-            let pattern = Regex::new(&format!(r"(?i)\b{}\b", regex::escape(term)))?;
-            let matches = pattern.find_iter(text).count() as f64;
-            exclusion_score += matches * 2.0; // Double weight for high-risk terms
-        }
+        exclusion_score += self.high_weight_counter.total(text) as f64 * 2.0; // Double weight for high-risk terms
         
         // Standard exclusion terms (normal weight)
-        for pattern in &self.exclusion_patterns {
-            exclusion_score += pattern.find_iter(text).count() as f64;
-        }
+        exclusion_score += self.exclusion_counter.total(text) as f64;
         
         // Check for specific problematic phrases
-        let exclusion_phrases = [
-            "ground investigation", "site investigation", "civil works",
-            "building works", "construction works", "mechanical works",
-            "electrical works", "infrastructure works", "road works",
-            "maintenance works", "repair works", "cleaning services",
-            "security services", "catering services", "transport services", 
-            "school meals", "meal service", "food service", "breakfast provision",
-            "lunch provision", "dinner provision", "catering service", "food provision"
-        ];
-        
-        for phrase in &exclusion_phrases {
-            let pattern = Regex::new(&format!(r"(?i){}", regex::escape(phrase)))?;
-            exclusion_score += pattern.find_iter(text).count() as f64 * 1.5; // 1.5x weight for phrases
-        }
+        exclusion_score += self.phrase_counter.total(text) as f64 * 1.5; // 1.5x weight for phrases
         
         // Calculate exclusion density (matches per 50 words, not 100)
         let exclusion_density = (exclusion_score / word_count) * 50.0;
@@ -243,16 +273,17 @@ impl FeatureExtractor {
             return Ok(vec![0.0; KEY_TERMS.len()]);
         }
         
-        for pattern in &self.term_patterns {
+        let term_counts = self.term_counter.counts(text);
+        for (term, &count) in KEY_TERMS.iter().zip(&term_counts) {
             // Count occurrences of the term
-            let matches = pattern.find_iter(text).count() as f64;
+            let matches = count as f64;
             
             // Calculate TF (term frequency)
             let tf = matches / word_count;
             
             // Simplified IDF calculation (in production, this would use corpus statistics)
             // For now, we use a simplified approach based on term importance
-            let idf = self.get_term_idf_weight(&pattern.as_str());
+            let idf = self.get_term_idf_weight(term);
             
             // TF-IDF score
             let tfidf = tf * idf;
@@ -263,16 +294,16 @@ impl FeatureExtractor {
     }
     
     /// Get IDF weight for term (simplified - in production would be calculated from corpus)
-    fn get_term_idf_weight(&self, _term_pattern: &str) -> f64 {
+    fn get_term_idf_weight(&self, term: &str) -> f64 {
         // Simplified IDF weights based on analysis results
         // Higher weights for terms that are more discriminative for bids
-        match _term_pattern {
-            pattern if pattern.contains("software") => 2.5,
-            pattern if pattern.contains("support") => 2.0,
-            pattern if pattern.contains("computer") => 1.8,
-            pattern if pattern.contains("technical") => 1.5,
-            pattern if pattern.contains("services") => 1.3,
-            pattern if pattern.contains("systems") => 1.2,
+        match term {
+            "software" => 2.5,
+            "support" => 2.0,
+            "computer" => 1.8,
+            "technical" => 1.5,
+            "services" => 1.3,
+            "systems" => 1.2,
             _ => 1.0, // Default weight for other terms
         }
     }   
@@ -316,6 +347,7 @@ mod tests {
             ml_bid: None,
             ml_confidence: None,
             ml_reasoning: None,
+            ..Default::default()
         }
     }
 
@@ -347,7 +379,7 @@ mod tests {
         
         // Unknown CA should get hash-based code
         let unknown_code = extractor.encode_contracting_authority("Unknown Authority");
-        assert!(unknown_code >= 11.0 && unknown_code <= 100.0);
+        assert!((11.0..=100.0).contains(&unknown_code));
     }
 
     #[test]
@@ -365,6 +397,28 @@ mod tests {
         assert!(features[9] > 0.0); // technical
     }
 
+    #[test]
+    fn test_exclusion_score_weights() {
+        let extractor = FeatureExtractor::new();
+        // "road": high-weight (2.0) + standard term (1.0); "road works": phrase (1.5) - over 20 words
+        let text = "road works the quick brown fox jumps over the lazy dog and then returns home for a nice long rest";
+        
+        let score = extractor.calculate_exclusion_score(text).unwrap();
+        
+        assert_eq!(score, 4.5 / 20.0 * 50.0);
+    }
+
+    #[test]
+    fn test_terms_only_count_whole_words() {
+        let extractor = FeatureExtractor::new();
+        // "roadside" and "supportive" must not count as "road" or "support"
+        let features = extractor.calculate_tfidf_features("supportive roadside Support").unwrap();
+        assert_eq!(features[1], 1.0 / 3.0 * 2.0);
+
+        let score = extractor.calculate_exclusion_score("roadside cafe").unwrap();
+        assert_eq!(score, 0.0);
+    }
+
     #[test]
     fn test_empty_text_handling() {
         let extractor = FeatureExtractor::new();
@@ -0,0 +1,5 @@
+//! Pure prediction logic, shared by the lambda binary, the benchmarks and feature_drift
+pub mod drift;
+pub mod features;
+pub mod ml_predictor;
+pub mod types;
@@ -1,59 +1,87 @@
-use aws_lambda_events::event::sqs::SqsEvent;
-use lambda_runtime::{run, service_fn, tracing, Error, LambdaEvent};
+use decision_audit::{Decision, Kind};
+use lambda_runtime::tracing;
+use pipeline_contract::diagnostics::{self, ConfigReport};
+use pipeline_contract::{Completed, ErrorCode, Handoff, StageError, StageEvent, StageResults};
+use pipeline_status::lifecycle::{self, State};
+use pipeline_status::{Claim, Stage};
+use resource_discovery::Resource;
 use serde_json::Value;
+use stage_runtime::Error;
+use std::time::SystemTime;
 use tracing::info;
 
 mod database;
-mod features;
-mod ml_predictor;
 mod queue_handler;
-mod types;
 
 use database::Database;
-use ml_predictor::OptimizedBidPredictor;
+use ml_bid_predictor::{ml_predictor, types};
+use ml_predictor::{OptimizedBidPredictor, TagWeights};
 use queue_handler::QueueHandler;
 use types::TenderRecord;
 
-/// Main lambda handler for ML bid prediction
-async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<Value, Error> {
-    let (event, _context) = event.into_parts();
+/// Name recorded against this lambda's lifecycle transitions
+const ACTOR: &str = "ml_bid_predictor";
 
-    info!("Processing {} SQS records", event.records.len());
+/// Effective configuration, returned for `{"payload": {"diagnostic": "config"}}`
+fn config_report() -> ConfigReport {
+    ConfigReport::new("ml_bid_predictor", env!("CARGO_PKG_VERSION"))
+        .envs(&[
+            "AWS_REGION",
+            "ML_TAG_WEIGHTS",
+            "PIPELINE_TOPIC_ARN",
+            "STAGE_RUNTIME",
+        ])
+        .build("model_version", ml_predictor::MODEL_VERSION)
+        .build("threshold", OptimizedBidPredictor::new().get_threshold())
+        .effective("tag_weights", format!("{:?}", TagWeights::from_env()))
+}
+
+/// Runs under Lambda or as a service polling the ML prediction queue (see `stage_runtime`)
+struct MlBidPredictor;
+
+impl stage_runtime::Stage for MlBidPredictor {
+    fn queue(&self) -> Resource {
+        Resource::MlPredictionQueue
+    }
+
+    async fn handle(&self, event: StageEvent, _deadline: SystemTime) -> Result<Value, Error> {
+        function_handler(event).await
+    }
+}
+
+/// Main handler for ML bid prediction
+async fn function_handler(event: StageEvent) -> Result<Value, Error> {
+    if let Some(diagnostic) = event.diagnostic() {
+        return diagnostics::respond(diagnostic, config_report)
+            .map_err(|e| Error::from(e.as_str()));
+    }
+    let mut results = StageResults::new(&event);
+    let messages = event.into_messages();
+
+    info!("Processing {} messages", messages.len());
 
     // Initialize predictor, queue handler, and database
-    let predictor = OptimizedBidPredictor::new();
+    let predictor = OptimizedBidPredictor::new().with_tag_weights(TagWeights::from_env());
     let queue_handler = QueueHandler::new().await?;
     let database = Database::new().await?;
 
-    let mut processed_count = 0;
-    let mut error_count = 0;
-
-    for record in &event.records {
-        match process_tender_record(&predictor, &queue_handler, &database, record).await {
-            Ok(_) => {
-                processed_count += 1;
-                info!("Successfully processed record {}", processed_count);
-            }
-            Err(e) => {
-                error_count += 1;
-                tracing::error!("Error processing record: {}", e);
-            }
-        }
+    for message in &messages {
+        let handoff = Handoff::new(message);
+        let result = process_tender_record(
+            &predictor,
+            &queue_handler,
+            &database,
+            &message.body,
+            &handoff,
+        )
+        .await;
+        results.record(message, &handoff, result).await;
     }
 
-    info!(
-        "Batch complete: {} processed, {} errors",
-        processed_count, error_count
-    );
-
-    Ok(serde_json::json!({
-        "statusCode": 200,
-        "body": {
-            "processed": processed_count,
-            "errors": error_count,
-            "message": "ML bid prediction batch completed"
-        }
-    }))
+    // Retryable failures go back to SQS (or fail the Step Functions task); permanent ones are acknowledged
+    results
+        .into_response()
+        .map_err(|e| Error::from(e.to_string().as_str()))
 }
 
 /// Process individual tender record
@@ -61,15 +89,78 @@ async fn process_tender_record(
     predictor: &OptimizedBidPredictor,
     queue_handler: &QueueHandler,
     database: &Database,
-    record: &impl serde::ser::Serialize,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // Parse tender record from SQS message body
-    let record_json = serde_json::to_value(record)?;
-    let body_str = record_json
-        .get("body")
-        .and_then(|v| v.as_str())
-        .ok_or("SQS record missing body field")?;
-    let tender_record: TenderRecord = serde_json::from_str(body_str)?;
+    body_str: &str,
+    handoff: &Handoff,
+) -> Result<Completed, StageError> {
+    let tender_record: TenderRecord = serde_json::from_str(body_str).map_err(|e| {
+        StageError::new(
+            ErrorCode::InvalidMessage,
+            format!("Failed to parse tender record: {}", e),
+        )
+    })?;
+    let resource_id = tender_record.resource_id;
+
+    if !lifecycle::may_enter(database.pool(), resource_id, State::Predicted, &[], ACTOR).await {
+        return Err(StageError::new(
+            ErrorCode::InvalidState,
+            "Tender isn't ready for prediction (see tender_lifecycle_transitions)",
+        )
+        .for_tender(resource_id));
+    }
+
+    // Failed rather than dropped, so the message comes back if the other invocation died
+    if let Claim::Held { until } =
+        pipeline_status::claim(database.pool(), resource_id, Stage::MlPrediction, body_str).await
+    {
+        info!(
+            "⏭️ Tender {} is already being predicted by another invocation - retrying after {}",
+            resource_id, until
+        );
+        return Err(StageError::new(
+            ErrorCode::Claimed,
+            format!("Already being processed, claimed until {}", until),
+        )
+        .for_tender(resource_id));
+    }
+    let result = predict_and_forward(predictor, queue_handler, database, tender_record, handoff)
+        .await
+        .map_err(|e| e.for_tender(resource_id));
+    match &result {
+        Ok(()) => {
+            pipeline_status::completed(database.pool(), resource_id, Stage::MlPrediction).await
+        }
+        // Permanent failures are recorded as rejected so pipeline_watchdog leaves them alone
+        Err(e) if e.is_retryable() => {
+            pipeline_status::failed(
+                database.pool(),
+                resource_id,
+                Stage::MlPrediction,
+                &e.to_string(),
+            )
+            .await
+        }
+        Err(e) => {
+            pipeline_status::rejected(
+                database.pool(),
+                resource_id,
+                Stage::MlPrediction,
+                &e.to_string(),
+            )
+            .await
+        }
+    }
+    result.map(|()| Completed::new(resource_id, "ML prediction completed"))
+}
+
+/// Predict, store the prediction and hand the tender on to ai_summary
+async fn predict_and_forward(
+    predictor: &OptimizedBidPredictor,
+    queue_handler: &QueueHandler,
+    database: &Database,
+    tender_record: TenderRecord,
+    handoff: &Handoff,
+) -> Result<(), StageError> {
+    let db_error = |e: anyhow::Error| StageError::new(ErrorCode::Database, e.to_string());
 
     info!(
         "Processing tender: {} (ID: {})",
@@ -97,13 +188,28 @@ async fn process_tender_record(
                 &error_msg,
                 "routing_error",
             )
-            .await?;
+            .await
+            .map_err(db_error)?;
 
-        return Err(error_msg.into());
+        return Err(StageError::new(ErrorCode::InvalidState, error_msg));
     }
 
+    // Manual tags only count when ML_TAG_WEIGHTS is set; a failed lookup just predicts without them
+    let tags = if predictor.uses_tags() {
+        tender_tags::for_tender(database.pool(), tender_record.resource_id)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("⚠️ Could not load tags, predicting without them: {}", e);
+                Vec::new()
+            })
+    } else {
+        Vec::new()
+    };
+
     // Run ML prediction with optimized threshold (0.054)
-    let prediction = predictor.predict(&tender_record)?;
+    let prediction = predictor
+        .predict_with_tags(&tender_record, &tags)
+        .map_err(|e| StageError::new(ErrorCode::InvalidState, e.to_string()))?;
 
     // Always send ALL predictions to AI queue for Claude analysis (eliminate blind spots)
     info!(
@@ -125,14 +231,54 @@ async fn process_tender_record(
                 "no-bid"
             },
         )
-        .await?;
+        .await
+        .map_err(db_error)?;
+
+    let outcome = if prediction.should_bid {
+        "bid"
+    } else {
+        "no-bid"
+    };
+    let codes_count = tender_record.codes_count.unwrap_or(0).to_string();
+    let decision = Decision::new(
+        tender_record.resource_id,
+        Kind::Ml,
+        "ml_bid_predictor",
+        outcome,
+    )
+    .inputs(&[
+        &tender_record.title,
+        tender_record.pdf_content.as_deref().unwrap_or_default(),
+        &codes_count,
+        &tags.join(","),
+    ])
+    .version("model", ml_predictor::MODEL_VERSION)
+    .version(
+        "tag_weights",
+        std::env::var("ML_TAG_WEIGHTS").unwrap_or_default(),
+    )
+    .detail(serde_json::json!({
+        "confidence": prediction.confidence,
+        "reasoning": prediction.reasoning,
+    }));
+    decision_audit::record(database.pool(), &decision).await;
+
+    // Recorded before the hand-off so ai_summary never sees the tender a step behind
+    lifecycle::advance(
+        database.pool(),
+        tender_record.resource_id,
+        State::Predicted,
+        ACTOR,
+    )
+    .await;
 
     // Send ALL predictions to AI queue - Claude will make the final decision
     // This eliminates blind spots where ML might miss good opportunities
     info!("🧠 Sending to Claude for expert analysis (ML is just initial filter)");
     queue_handler
-        .send_to_ai_summary_queue(&tender_record, &prediction)
-        .await?;
+        .send_to_ai_summary_queue(&tender_record, &prediction, handoff)
+        .await
+        .map_err(|e| StageError::new(ErrorCode::ForwardFailed, e.to_string()))?;
 
     Ok(())
 }
@@ -144,6 +290,6 @@ async fn main() -> Result<(), Error> {
 
     info!("🚀 Starting ML Bid Predictor Lambda (optimized threshold: 0.054)");
 
-    // Run the lambda
-    run(service_fn(function_handler)).await
+    // Under Lambda or as a service (STAGE_RUNTIME), tagging every log line with the deployment environment
+    stage_runtime::run(MlBidPredictor).await
 }
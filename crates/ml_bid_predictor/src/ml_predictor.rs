@@ -1,7 +1,50 @@
 use crate::types::{TenderRecord, MLPredictionResult, FeatureVector, FeatureScores};
 use crate::features::FeatureExtractor;
 use anyhow::Result;
-use tracing::{info, debug};
+use std::collections::HashMap;
+use tracing::{info, debug, warn};
+
+/// Optional score adjustments for manually tagged tenders, from ML_TAG_WEIGHTS
+/// (e.g. "cloud=0.3,staff aug=0.2,catering=-0.5"). Added to the weighted feature sum
+/// before the sigmoid; tags without a weight are ignored
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagWeights {
+    weights: HashMap<String, f64>,
+}
+
+impl TagWeights {
+    pub fn parse(spec: &str) -> Self {
+        let mut weights = HashMap::new();
+        for entry in spec.split(',').filter(|e| !e.trim().is_empty()) {
+            match entry.rsplit_once('=').map(|(tag, w)| (tag, w.trim().parse::<f64>())) {
+                Some((tag, Ok(weight))) if !tag.trim().is_empty() => {
+                    // Same normalization as tender_tags, so "Staff  Aug" matches "staff aug"
+                    let tag = tag.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+                    weights.insert(tag, weight);
+                }
+                _ => warn!("⚠️ Ignoring invalid ML_TAG_WEIGHTS entry '{}'", entry.trim()),
+            }
+        }
+        Self { weights }
+    }
+
+    pub fn from_env() -> Self {
+        std::env::var("ML_TAG_WEIGHTS").map(|spec| Self::parse(&spec)).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    /// Summed weight of the tender's weighted tags
+    pub fn score(&self, tags: &[String]) -> f64 {
+        tags.iter().filter_map(|tag| self.weights.get(tag)).sum()
+    }
+}
+
+/// Identifies the weights, threshold and feature set below. Bump it whenever any of them
+/// change, so feature_drift captures a fresh training snapshot for the new model
+pub const MODEL_VERSION: &str = "tfidf-svm-2025.1";
 
 /// Optimized Bid Predictor using threshold 0.054 based on TF-IDF Linear SVM analysis
 /// 
@@ -17,6 +60,7 @@ pub struct OptimizedBidPredictor {
     // Enhanced feature weights based on TF-IDF + Linear SVM analysis
     // More conservative to reduce false positives while maintaining recall
     feature_weights: [f64; 15],  // Updated for 15 features
+    tag_weights: TagWeights,
 }
 
 impl OptimizedBidPredictor {
@@ -47,11 +91,21 @@ impl OptimizedBidPredictor {
                 0.003, // tfidf_package (reduced from 0.005)
                 0.003, // tfidf_technical (reduced from 0.005)
             ],
+            tag_weights: TagWeights::default(),
         }
     }
+
+    /// Use manual tags as an extra feature (disabled while the weights are empty)
+    pub fn with_tag_weights(mut self, tag_weights: TagWeights) -> Self {
+        self.tag_weights = tag_weights;
+        self
+    }
+
+    pub fn uses_tags(&self) -> bool {
+        !self.tag_weights.is_empty()
+    }
     
     /// Get the current threshold value
-    #[cfg(test)]
     pub fn get_threshold(&self) -> f64 {
         self.threshold
     }
@@ -63,6 +117,12 @@ impl OptimizedBidPredictor {
     /// 
     /// Returns prediction result with confidence score and reasoning
     pub fn predict(&self, tender: &TenderRecord) -> Result<MLPredictionResult> {
+        self.predict_with_tags(tender, &[])
+    }
+
+    /// `predict`, with the tender's manual tags weighed in when tag weights are configured.
+    /// The exclusion rules still apply first, so a tag can't rescue a construction tender
+    pub fn predict_with_tags(&self, tender: &TenderRecord, tags: &[String]) -> Result<MLPredictionResult> {
         debug!("🤖 Starting ML prediction for: {}", tender.resource_id);
         
         // Validate that we have PDF content - this is a hard requirement
@@ -109,7 +169,8 @@ impl OptimizedBidPredictor {
         }
         
         // Level 3: Regular ML prediction with conservative approach
-        let prediction_score = self.calculate_prediction_score(&features)?;
+        let tag_score = self.tag_weights.score(tags);
+        let prediction_score = self.calculate_prediction_score(&features, tag_score)?;
         
         // Apply more conservative threshold adjustment based on exclusion score
         let adjusted_threshold = if features.exclusion_score > 1.0 {
@@ -122,7 +183,7 @@ impl OptimizedBidPredictor {
         let should_bid = prediction_score >= adjusted_threshold;
         
         // Generate reasoning based on feature contributions
-        let reasoning = self.generate_reasoning(&features, prediction_score, should_bid, adjusted_threshold);
+        let reasoning = self.generate_reasoning(&features, tag_score, prediction_score, should_bid, adjusted_threshold);
         
         // Calculate feature scores for transparency
         let feature_scores = self.calculate_feature_scores(&features);
@@ -148,14 +209,14 @@ impl OptimizedBidPredictor {
     }
     
     /// Calculate prediction score using weighted feature importance
-    fn calculate_prediction_score(&self, features: &FeatureVector) -> Result<f64> {
+    fn calculate_prediction_score(&self, features: &FeatureVector, tag_score: f64) -> Result<f64> {
         let feature_array = features.to_array();
         
         // Normalize features to 0-1 range for consistent scoring
         let normalized_features = self.normalize_features(&feature_array);
         
         // Calculate weighted sum
-        let mut score = 0.0;
+        let mut score = tag_score;
         for (i, &weight) in self.feature_weights.iter().enumerate() {
             score += normalized_features[i] * weight;
         }
@@ -188,7 +249,7 @@ impl OptimizedBidPredictor {
     }
     
     /// Generate human-readable reasoning for the prediction
-    fn generate_reasoning(&self, features: &FeatureVector, score: f64, should_bid: bool, threshold: f64) -> String {
+    fn generate_reasoning(&self, features: &FeatureVector, tag_score: f64, score: f64, should_bid: bool, threshold: f64) -> String {
         let mut reasons = Vec::new();
         
         // Check exclusion indicators first (most important for filtering)
@@ -215,6 +276,10 @@ impl OptimizedBidPredictor {
             reasons.push("✅ Support service terms found".to_string());
         }
         
+        if tag_score != 0.0 {
+            reasons.push(format!("🏷️ Manual tags weighted {:+.2}", tag_score));
+        }
+
         // PDF content quality - check title length as proxy
         if features.title_length > 100.0 {
             reasons.push("✅ Detailed title indicates complex requirements".to_string());
@@ -302,6 +367,7 @@ mod tests {
             ml_bid: None,
             ml_confidence: None,
             ml_reasoning: None,
+            ..Default::default()
         }
     }
 
@@ -323,6 +389,23 @@ mod tests {
         assert!(result.reasoning.contains("software") || result.reasoning.contains("codes"));
     }
 
+    #[test]
+    fn test_tag_weights() {
+        let weights = TagWeights::parse("cloud=0.3, Staff  Aug=0.2,catering=-0.5,bogus,=1");
+        let tags = |tags: &[&str]| tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(weights.score(&tags(&["cloud", "staff aug"])), 0.5);
+        assert_eq!(weights.score(&tags(&["catering", "unweighted"])), -0.5);
+        assert!(TagWeights::parse("").is_empty());
+
+        let tender = create_test_tender();
+        let predictor = OptimizedBidPredictor::new().with_tag_weights(weights);
+        let untagged = predictor.predict(&tender).unwrap();
+        let tagged = predictor.predict_with_tags(&tender, &tags(&["cloud"])).unwrap();
+        assert!(tagged.confidence > untagged.confidence);
+        assert!(tagged.reasoning.contains("Manual tags weighted +0.30"));
+    }
+
     #[test]
     fn test_feature_normalization() {
         let predictor = OptimizedBidPredictor::new();
@@ -1,37 +1,31 @@
-use crate::types::{TenderRecord, MLPredictionResult, AISummaryMessage, SNSMessage, Config};
-use aws_sdk_sqs::Client as SqsClient;
-use aws_sdk_sns::{Client as SnsClient};
+use crate::types::{TenderRecord, MLPredictionResult, AISummaryMessage, Config};
 use aws_config::BehaviorVersion;
+use queue::Publisher;
+use resource_discovery::Resource;
+use pipeline_contract::Handoff;
+use pipeline_status::Stage;
 use anyhow::Result;
-use tracing::{info, debug};
+use tracing::info;
 use chrono::Utc;
-use serde_json;
 
-/// Queue handler for SQS and SNS operations
+/// Queue handler for sends to the next stage, over SQS or the pipeline topic
 pub struct QueueHandler {
-    sqs_client: SqsClient,
-    sns_client: SnsClient,
-    config: Config,
+    publisher: Publisher,
 }
 
 impl QueueHandler {
     /// Create new queue handler
     pub async fn new() -> Result<Self> {
-        let config = Config::from_env()?;
+        let config = Config::from_env();
         
         let aws_config = aws_config::defaults(BehaviorVersion::latest())
             .region(aws_config::Region::new(config.aws_region.clone()))
             .load()
             .await;
         
-        let sqs_client = SqsClient::new(&aws_config);
-        let sns_client = SnsClient::new(&aws_config);
-        
         info!("✅ Queue handler initialized");
         Ok(Self {
-            sqs_client,
-            sns_client,
-            config,
+            publisher: Publisher::new(&aws_config),
         })
     }
     
@@ -40,6 +34,7 @@ impl QueueHandler {
         &self,
         tender: &TenderRecord,
         prediction: &MLPredictionResult,
+        handoff: &Handoff,
     ) -> Result<()> {
         info!("📨 Sending to AI summary queue: {}", tender.resource_id);
         
@@ -58,15 +53,19 @@ impl QueueHandler {
             pdf_content: tender.pdf_content.clone().unwrap_or_default(),
             priority: priority.to_string(),
             timestamp: Utc::now(),
+            ..Default::default()
         };
         
+        if handoff.capture(Stage::AiSummary, &ai_message) {
+            info!("🧭 Orchestrated by Step Functions - returning AI summary payload for {}", tender.resource_id);
+            return Ok(());
+        }
+        
         let message_body = serde_json::to_string(&ai_message)?;
+        let routing = handoff.routing(Stage::AiSummary.name()).priority(priority);
         
-        self.sqs_client
-            .send_message()
-            .queue_url(&self.config.ai_summary_queue_url)
-            .message_body(message_body)
-            .send()
+        self.publisher
+            .send(Resource::AiSummaryQueue, &message_body, &routing)
             .await?;
         
         info!("✅ Sent to AI summary queue: {}", tender.resource_id);
@@ -77,63 +76,12 @@ impl QueueHandler {
         
         Ok(())
     }
-    
-    /// Send SNS notification for predicted bid opportunity
-    async fn send_bid_prediction_alert(
-        &self,
-        tender: &TenderRecord,
-        prediction: &MLPredictionResult,
-    ) -> Result<()> {
-        info!("🎯 Sending bid prediction alert for: {}", tender.resource_id);
-        
-        let sns_message = SNSMessage {
-            message_type: "ML_BID_PREDICTION".to_string(),
-            resource_id: tender.resource_id.to_string().clone(),
-            title: tender.title.clone(),
-            priority: "URGENT".to_string(),
-            summary: format!(
-                "ML predicts BID opportunity with {:.1}% confidence: {}",
-                prediction.confidence * 100.0,
-                prediction.reasoning
-            ),
-            action_required: "Review tender details and initiate bid process if appropriate".to_string(),
-            timestamp: Utc::now(),
-            metadata: serde_json::json!({
-                "ca": tender.contracting_authority,
-                "value": tender.value,
-                "deadline": tender.deadline,
-                "ml_confidence": prediction.confidence,
-                "ml_reasoning": prediction.reasoning,
-                "feature_scores": prediction.feature_scores
-            }),
-        };
-        
-        self.send_sns_notification(&sns_message, "BID_OPPORTUNITY").await?;
-        Ok(())
-    }
-    
-    /// Send SNS notification
-    async fn send_sns_notification(&self, message: &SNSMessage, subject_prefix: &str) -> Result<()> {
-        let subject = format!("[{}] {}", subject_prefix, message.title);
-        let message_body = serde_json::to_string_pretty(message)?;
-        
-        self.sns_client
-            .publish()
-            .topic_arn(&self.config.sns_topic_arn)
-            .subject(subject)
-            .message(message_body)
-            .send()
-            .await?;
-        
-        debug!("✅ SNS notification sent for: {}", message.resource_id);
-        Ok(())
-    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{FeatureScores, TenderRecord};
+    use crate::types::{FeatureScores, SNSMessage, TenderRecord};
     use chrono::Utc;
 
     fn create_test_tender() -> TenderRecord {
@@ -162,6 +110,7 @@ mod tests {
             ml_bid: None,
             ml_confidence: None,
             ml_reasoning: None,
+            ..Default::default()
         }
     }
 
@@ -212,6 +161,7 @@ mod tests {
             pdf_content: tender.pdf_content.clone().unwrap_or_default(),
             priority: "URGENT".to_string(),
             timestamp: Utc::now(),
+            ..Default::default()
         };
         
         let serialized = serde_json::to_string(&ai_message);
@@ -0,0 +1,689 @@
+//! Disaster recovery and environment cloning: every pipeline table to JSONL in S3 and back.
+//!
+//! `export` reads all tables in the environment's schema inside one repeatable-read
+//! transaction, so the dump is a consistent snapshot, and writes each table as JSONL parts
+//! under `backups/<environment>/<timestamp>/` in the lambda bucket. `manifest.json` is
+//! written last and records each table's columns, constraints and row count; an export
+//! that died half way has no manifest and can't be imported.
+//!
+//! `import` loads an export into a database whose tables are missing or empty (it
+//! refuses to merge into live data), all in one transaction. Missing tables are created
+//! from the manifest, with foreign keys added after the rows are in and sequences moved
+//! past the restored ids. Indexes are not recreated here: `schema_versions` is left out of
+//! the export, so each lambda reruns its `ensure_schema` DDL against the restored tables
+//! on its next start. Rows take the target environment's `environment` value unless
+//! `--keep-environment` is given, so `ENVIRONMENT=staging ops_cli backup import` clones
+//! prod into staging. Analytics views are rebuilt with `ops_cli refresh-analytics`.
+//!
+//! Partitioned tables are exported through their parent and restored as plain tables;
+//! data_archive keeps moving rows into a restored archive without partitions.
+//!
+//! An export keeps what `ops_cli purge` later removes; restoring one taken before a purge
+//! brings the tender's PDF text and summaries back, so purge it again afterwards (the
+//! purge receipts in `decision_audit` list the tenders).
+//!
+//! PDFs aren't archived in S3, only their thumbnails; `--include-s3-manifest` lists those
+//! objects alongside the tables so a DR test can check they are all still there.
+
+use anyhow::{Context, Result, bail};
+use aws_sdk_s3::Client as S3Client;
+use clap::{Args, Subcommand};
+use environment::Environment;
+use resource_discovery::{Resource, ResourceDiscovery};
+use serde_json::{Value, json};
+use sqlx::{PgConnection, PgPool, Row};
+
+const FORMAT_VERSION: i64 = 1;
+const MANIFEST: &str = "manifest.json";
+/// `db::ensure_schema` bookkeeping; see the module docs
+const SKIPPED_TABLES: &[&str] = &["schema_versions"];
+/// Where pdf_processing stores thumbnails (see `pdf_processing::thumbnail`)
+const S3_ARCHIVE_PREFIX: &str = "thumbnails/";
+/// Rows per INSERT on import
+const INSERT_BATCH: usize = 500;
+
+#[derive(Subcommand)]
+pub enum BackupCommand {
+    /// Dump every pipeline table to JSONL in S3
+    Export(ExportArgs),
+    /// Load an export into a database whose pipeline tables are missing or empty
+    Import(ImportArgs),
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// Defaults to the lambda bucket
+    #[arg(long)]
+    bucket: Option<String>,
+    /// Defaults to backups/<environment>/<timestamp>
+    #[arg(long)]
+    prefix: Option<String>,
+    /// Rows per JSONL part
+    #[arg(long, default_value_t = 1000)]
+    part_rows: usize,
+    /// Also list the S3 thumbnail archive in the export
+    #[arg(long)]
+    include_s3_manifest: bool,
+}
+
+#[derive(Args)]
+pub struct ImportArgs {
+    /// Prefix the export was written to, as printed by `backup export`
+    prefix: String,
+    /// Defaults to the lambda bucket
+    #[arg(long)]
+    bucket: Option<String>,
+    /// Only import these tables; repeat for several
+    #[arg(long = "table")]
+    tables: Vec<String>,
+    /// Keep the exported `environment` values instead of the target environment's
+    #[arg(long)]
+    keep_environment: bool,
+    /// Load everything, then roll back
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Column {
+    name: String,
+    data_type: String,
+    not_null: bool,
+    default: Option<String>,
+}
+
+impl Column {
+    fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "type": self.data_type,
+            "not_null": self.not_null,
+            "default": self.default,
+        })
+    }
+
+    fn from_json(value: &Value) -> Result<Self> {
+        Ok(Self {
+            name: str_field(value, "name")?,
+            data_type: str_field(value, "type")?,
+            not_null: value["not_null"].as_bool().unwrap_or(false),
+            default: value["default"].as_str().map(str::to_string),
+        })
+    }
+
+    /// Filled from a sequence, which needs moving past the restored values
+    fn is_serial(&self) -> bool {
+        self.default
+            .as_deref()
+            .is_some_and(|default| default.starts_with("nextval("))
+    }
+
+    /// Column definition for CREATE TABLE. Sequence defaults name a sequence that doesn't
+    /// exist in a fresh database, so those columns become SERIAL/BIGSERIAL instead
+    fn definition(&self) -> String {
+        let mut definition = match (self.is_serial(), self.data_type.as_str()) {
+            (true, "smallint") => format!("{} SMALLSERIAL", ident(&self.name)),
+            (true, "integer") => format!("{} SERIAL", ident(&self.name)),
+            (true, "bigint") => format!("{} BIGSERIAL", ident(&self.name)),
+            _ => format!("{} {}", ident(&self.name), self.data_type),
+        };
+        if self.not_null {
+            definition.push_str(" NOT NULL");
+        }
+        if let Some(default) = self.default.as_deref().filter(|_| !self.is_serial()) {
+            definition.push_str(&format!(" DEFAULT {}", default));
+        }
+        definition
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Constraint {
+    name: String,
+    /// pg_constraint.contype: p(rimary key), u(nique), c(heck), f(oreign key), x (exclusion)
+    kind: String,
+    definition: String,
+}
+
+impl Constraint {
+    fn to_json(&self) -> Value {
+        json!({ "name": self.name, "kind": self.kind, "definition": self.definition })
+    }
+
+    fn from_json(value: &Value) -> Result<Self> {
+        Ok(Self {
+            name: str_field(value, "name")?,
+            kind: str_field(value, "kind")?,
+            definition: str_field(value, "definition")?,
+        })
+    }
+
+    /// Foreign keys wait until every table is loaded
+    fn is_foreign_key(&self) -> bool {
+        self.kind == "f"
+    }
+
+    fn clause(&self) -> String {
+        format!("CONSTRAINT {} {}", ident(&self.name), self.definition)
+    }
+}
+
+fn str_field(value: &Value, field: &str) -> Result<String> {
+    value[field]
+        .as_str()
+        .map(str::to_string)
+        .with_context(|| format!("Manifest entry is missing '{}'", field))
+}
+
+/// Quote an identifier for SQL
+fn ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn part_key(prefix: &str, table: &str, part: usize) -> String {
+    format!("{}/{}/part-{:05}.jsonl", prefix, table, part)
+}
+
+fn create_table_sql(table: &str, columns: &[Column], constraints: &[Constraint]) -> String {
+    let definitions: Vec<String> = columns
+        .iter()
+        .map(Column::definition)
+        .chain(
+            constraints
+                .iter()
+                .filter(|c| !c.is_foreign_key())
+                .map(Constraint::clause),
+        )
+        .collect();
+    format!(
+        "CREATE TABLE {} (\n    {}\n)",
+        ident(table),
+        definitions.join(",\n    ")
+    )
+}
+
+/// Insert a batch of exported rows (JSON objects, one per line) into `columns`
+fn insert_sql(table: &str, columns: &[String]) -> String {
+    let columns = columns
+        .iter()
+        .map(|c| ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "INSERT INTO {table} ({columns}) SELECT {columns} FROM jsonb_populate_recordset(NULL::{table}, $1::JSONB)",
+        table = ident(table),
+        columns = columns
+    )
+}
+
+async fn read_columns(conn: &mut PgConnection, table: &str) -> Result<Vec<Column>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT a.attname::TEXT AS name,
+               format_type(a.atttypid, a.atttypmod) AS data_type,
+               a.attnotnull AS not_null,
+               pg_get_expr(d.adbin, d.adrelid) AS "default"
+        FROM pg_attribute a
+        LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+        WHERE a.attrelid = $1::TEXT::REGCLASS AND a.attnum > 0 AND NOT a.attisdropped
+        ORDER BY a.attnum
+        "#,
+    )
+    .bind(ident(table))
+    .fetch_all(&mut *conn)
+    .await
+    .with_context(|| format!("Failed to read the columns of {}", table))?;
+
+    Ok(rows
+        .iter()
+        .map(|row| Column {
+            name: row.get("name"),
+            data_type: row.get("data_type"),
+            not_null: row.get("not_null"),
+            default: row.get("default"),
+        })
+        .collect())
+}
+
+async fn read_constraints(conn: &mut PgConnection, table: &str) -> Result<Vec<Constraint>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT conname::TEXT AS name, contype::TEXT AS kind, pg_get_constraintdef(oid) AS definition
+        FROM pg_constraint
+        WHERE conrelid = $1::TEXT::REGCLASS AND contype IN ('p', 'u', 'c', 'f', 'x')
+        ORDER BY conname
+        "#,
+    )
+    .bind(ident(table))
+    .fetch_all(&mut *conn)
+    .await
+    .with_context(|| format!("Failed to read the constraints of {}", table))?;
+
+    Ok(rows
+        .iter()
+        .map(|row| Constraint {
+            name: row.get("name"),
+            kind: row.get("kind"),
+            definition: row.get("definition"),
+        })
+        .collect())
+}
+
+async fn table_exists(conn: &mut PgConnection, table: &str) -> Result<bool> {
+    Ok(sqlx::query("SELECT to_regclass($1) IS NOT NULL AS exists")
+        .bind(ident(table))
+        .fetch_one(&mut *conn)
+        .await?
+        .get("exists"))
+}
+
+async fn s3_client_and_bucket(bucket: Option<String>) -> Result<(S3Client, String)> {
+    let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        .load()
+        .await;
+    let bucket = match bucket {
+        Some(bucket) => bucket,
+        None => ResourceDiscovery::new(&aws_config)
+            .resolve(Resource::LambdaBucket)
+            .await
+            .context("Failed to find the lambda bucket")?,
+    };
+    Ok((S3Client::new(&aws_config), bucket))
+}
+
+async fn put(s3: &S3Client, bucket: &str, key: &str, body: Vec<u8>) -> Result<()> {
+    s3.put_object()
+        .bucket(bucket)
+        .key(key)
+        .body(body.into())
+        .send()
+        .await
+        .with_context(|| format!("Failed to write s3://{}/{}", bucket, key))?;
+    Ok(())
+}
+
+async fn get(s3: &S3Client, bucket: &str, key: &str) -> Result<Vec<u8>> {
+    let object = s3
+        .get_object()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .with_context(|| format!("Failed to read s3://{}/{}", bucket, key))?;
+    let body = object
+        .body
+        .collect()
+        .await
+        .with_context(|| format!("Failed to read s3://{}/{}", bucket, key))?;
+    Ok(body.into_bytes().to_vec())
+}
+
+/// One JSON line per object under the thumbnail prefix
+async fn s3_archive_listing(s3: &S3Client, bucket: &str) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    let mut token = None;
+    loop {
+        let page = s3
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(S3_ARCHIVE_PREFIX)
+            .set_continuation_token(token)
+            .send()
+            .await
+            .with_context(|| format!("Failed to list s3://{}/{}", bucket, S3_ARCHIVE_PREFIX))?;
+        for object in page.contents() {
+            lines.push(
+                json!({
+                    "key": object.key(),
+                    "size": object.size(),
+                    "etag": object.e_tag(),
+                    "last_modified": object.last_modified().map(|t| t.to_string()),
+                })
+                .to_string(),
+            );
+        }
+        token = page.next_continuation_token().map(str::to_string);
+        if token.is_none() {
+            return Ok(lines);
+        }
+    }
+}
+
+async fn export(pool: &PgPool, args: ExportArgs) -> Result<()> {
+    if args.part_rows == 0 {
+        bail!("--part-rows must be at least 1");
+    }
+    let (s3, bucket) = s3_client_and_bucket(args.bucket).await?;
+    let environment = Environment::from_env();
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+    let (created_at, stamp): (String, String) = sqlx::query_as(
+        r#"SELECT NOW()::TEXT, to_char(NOW() AT TIME ZONE 'UTC', 'YYYYMMDD"T"HH24MISS"Z"')"#,
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let prefix = args
+        .prefix
+        .map(|prefix| prefix.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| format!("backups/{}/{}", environment.name(), stamp));
+
+    // A partition's rows are read through its parent (data_archive's monthly archives)
+    let tables: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT c.relname::TEXT
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = current_schema() AND c.relkind IN ('r', 'p') AND NOT c.relispartition
+        ORDER BY c.relname
+        "#,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut manifest_tables = Vec::new();
+    for table in tables
+        .iter()
+        .filter(|table| !SKIPPED_TABLES.contains(&table.as_str()))
+    {
+        let columns = read_columns(&mut tx, table).await?;
+        let constraints = read_constraints(&mut tx, table).await?;
+
+        sqlx::query(&format!(
+            "DECLARE backup_rows NO SCROLL CURSOR FOR SELECT row_to_json(t)::TEXT FROM {} t",
+            ident(table)
+        ))
+        .execute(&mut *tx)
+        .await
+        .with_context(|| format!("Failed to read {}", table))?;
+
+        let (mut rows, mut parts) = (0usize, Vec::new());
+        loop {
+            let lines: Vec<String> =
+                sqlx::query_scalar(&format!("FETCH {} FROM backup_rows", args.part_rows))
+                    .fetch_all(&mut *tx)
+                    .await
+                    .with_context(|| format!("Failed to read {}", table))?;
+            if lines.is_empty() {
+                break;
+            }
+            rows += lines.len();
+            let key = part_key(&prefix, table, parts.len());
+            let mut body = lines.join("\n");
+            body.push('\n');
+            put(&s3, &bucket, &key, body.into_bytes()).await?;
+            parts.push(key);
+        }
+        sqlx::query("CLOSE backup_rows").execute(&mut *tx).await?;
+
+        println!("{}\t{} rows\t{} parts", table, rows, parts.len());
+        manifest_tables.push(json!({
+            "name": table,
+            "rows": rows,
+            "parts": parts,
+            "columns": columns.iter().map(Column::to_json).collect::<Vec<_>>(),
+            "constraints": constraints.iter().map(Constraint::to_json).collect::<Vec<_>>(),
+        }));
+    }
+    tx.commit().await?;
+
+    let mut manifest = json!({
+        "format_version": FORMAT_VERSION,
+        "environment": environment.name(),
+        "created_at": created_at,
+        "tables": manifest_tables,
+    });
+    if args.include_s3_manifest {
+        let lines = s3_archive_listing(&s3, &bucket).await?;
+        let key = format!("{}/s3_objects.jsonl", prefix);
+        let count = lines.len();
+        put(&s3, &bucket, &key, lines.join("\n").into_bytes()).await?;
+        println!("S3 archive\t{} objects", count);
+        manifest["s3_objects"] = json!({ "bucket": bucket, "key": key, "count": count });
+    }
+
+    let key = format!("{}/{}", prefix, MANIFEST);
+    put(&s3, &bucket, &key, serde_json::to_vec_pretty(&manifest)?).await?;
+    println!(
+        "✅ Exported {} tables to s3://{}/{}",
+        manifest_tables.len(),
+        bucket,
+        prefix
+    );
+    Ok(())
+}
+
+async fn import(pool: &PgPool, args: ImportArgs) -> Result<()> {
+    let (s3, bucket) = s3_client_and_bucket(args.bucket).await?;
+    let prefix = args.prefix.trim_end_matches('/');
+
+    let manifest: Value = serde_json::from_slice(
+        &get(&s3, &bucket, &format!("{}/{}", prefix, MANIFEST))
+            .await
+            .context("No manifest; the export may not have finished")?,
+    )?;
+    if manifest["format_version"].as_i64() != Some(FORMAT_VERSION) {
+        bail!(
+            "Unsupported export format {}; this ops_cli reads version {}",
+            manifest["format_version"],
+            FORMAT_VERSION
+        );
+    }
+
+    let tables: Vec<&Value> = manifest["tables"]
+        .as_array()
+        .context("Manifest has no tables")?
+        .iter()
+        .filter(|table| {
+            args.tables.is_empty()
+                || args
+                    .tables
+                    .iter()
+                    .any(|name| table["name"].as_str() == Some(name.as_str()))
+        })
+        .collect();
+    if tables.is_empty() {
+        bail!("None of the requested tables are in the export");
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // Refuse before writing anything, so a half-restored database never happens
+    for table in &tables {
+        let name = str_field(table, "name")?;
+        if table_exists(&mut tx, &name).await? {
+            let has_rows: bool =
+                sqlx::query_scalar(&format!("SELECT EXISTS (SELECT 1 FROM {})", ident(&name)))
+                    .fetch_one(&mut *tx)
+                    .await?;
+            if has_rows {
+                bail!(
+                    "{} already has rows; import only into an empty database",
+                    name
+                );
+            }
+        }
+    }
+
+    let mut foreign_keys = Vec::new();
+    for table in &tables {
+        let name = str_field(table, "name")?;
+        let columns = table["columns"]
+            .as_array()
+            .context("Manifest table has no columns")?
+            .iter()
+            .map(Column::from_json)
+            .collect::<Result<Vec<_>>>()?;
+        let constraints = table["constraints"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(Constraint::from_json)
+            .collect::<Result<Vec<_>>>()?;
+
+        if !table_exists(&mut tx, &name).await? {
+            sqlx::query(&create_table_sql(&name, &columns, &constraints))
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("Failed to create {}", name))?;
+            foreign_keys.extend(
+                constraints
+                    .into_iter()
+                    .filter(Constraint::is_foreign_key)
+                    .map(|constraint| (name.clone(), constraint)),
+            );
+        }
+
+        // Only columns both sides have; newer target columns take their defaults
+        let target = read_columns(&mut tx, &name).await?;
+        let insert_columns: Vec<String> = columns
+            .iter()
+            .map(|column| column.name.clone())
+            .filter(|column| target.iter().any(|t| &t.name == column))
+            .filter(|column| args.keep_environment || column != "environment")
+            .collect();
+        let insert = insert_sql(&name, &insert_columns);
+
+        let mut rows = 0usize;
+        for part in table["parts"].as_array().into_iter().flatten() {
+            let key = part.as_str().context("Manifest part is not a key")?;
+            let body = String::from_utf8(get(&s3, &bucket, key).await?)
+                .with_context(|| format!("{} is not UTF-8", key))?;
+            let lines: Vec<&str> = body.lines().filter(|line| !line.is_empty()).collect();
+            for batch in lines.chunks(INSERT_BATCH) {
+                rows += sqlx::query(&insert)
+                    .bind(format!("[{}]", batch.join(",")))
+                    .execute(&mut *tx)
+                    .await
+                    .with_context(|| format!("Failed to load {} into {}", key, name))?
+                    .rows_affected() as usize;
+            }
+        }
+
+        let expected = table["rows"].as_u64().unwrap_or_default() as usize;
+        if rows != expected {
+            bail!(
+                "{}: loaded {} rows but the export has {}",
+                name,
+                rows,
+                expected
+            );
+        }
+
+        for column in target.iter().filter(|column| column.is_serial()) {
+            sqlx::query(&format!(
+                "SELECT setval(pg_get_serial_sequence($1, $2), COALESCE((SELECT MAX({}) FROM {}), 0) + 1, false)",
+                ident(&column.name),
+                ident(&name)
+            ))
+            .bind(ident(&name))
+            .bind(&column.name)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to reset the sequence on {}.{}", name, column.name))?;
+        }
+        println!("{}\t{} rows", name, rows);
+    }
+
+    for (table, constraint) in &foreign_keys {
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD {}",
+            ident(table),
+            constraint.clause()
+        ))
+        .execute(&mut *tx)
+        .await
+        .with_context(|| format!("Failed to add {} to {}", constraint.name, table))?;
+    }
+
+    if args.dry_run {
+        tx.rollback().await?;
+        println!("[dry run] {} tables loaded and rolled back", tables.len());
+    } else {
+        tx.commit().await?;
+        println!(
+            "✅ Imported {} tables from s3://{}/{} (exported from {} at {})",
+            tables.len(),
+            bucket,
+            prefix,
+            manifest["environment"].as_str().unwrap_or("unknown"),
+            manifest["created_at"].as_str().unwrap_or("unknown")
+        );
+    }
+    Ok(())
+}
+
+pub async fn run(pool: &PgPool, command: BackupCommand) -> Result<()> {
+    match command {
+        BackupCommand::Export(args) => export(pool, args).await,
+        BackupCommand::Import(args) => import(pool, args).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, data_type: &str, default: Option<&str>) -> Column {
+        Column {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            not_null: true,
+            default: default.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_serial_columns_drop_their_sequence() {
+        let id = column(
+            "id",
+            "bigint",
+            Some("nextval('reminders_id_seq'::regclass)"),
+        );
+        assert!(id.is_serial());
+        assert_eq!(id.definition(), "\"id\" BIGSERIAL NOT NULL");
+
+        let created = column("created_at", "timestamp with time zone", Some("now()"));
+        assert!(!created.is_serial());
+        assert_eq!(
+            created.definition(),
+            "\"created_at\" timestamp with time zone NOT NULL DEFAULT now()"
+        );
+    }
+
+    #[test]
+    fn test_create_table_leaves_foreign_keys_for_later() {
+        let constraints = vec![
+            Constraint {
+                name: "tenant_rules_pkey".to_string(),
+                kind: "p".to_string(),
+                definition: "PRIMARY KEY (id)".to_string(),
+            },
+            Constraint {
+                name: "tenant_rules_tenant_id_fkey".to_string(),
+                kind: "f".to_string(),
+                definition: "FOREIGN KEY (tenant_id) REFERENCES tenants(tenant_id)".to_string(),
+            },
+        ];
+        let sql = create_table_sql(
+            "tenant_rules",
+            &[column("id", "integer", Some("nextval('x'::regclass)"))],
+            &constraints,
+        );
+        assert!(sql.contains("CONSTRAINT \"tenant_rules_pkey\" PRIMARY KEY (id)"));
+        assert!(!sql.contains("FOREIGN KEY"));
+
+        let manifest: Vec<Value> = constraints.iter().map(Constraint::to_json).collect();
+        assert_eq!(Constraint::from_json(&manifest[1]).unwrap(), constraints[1]);
+    }
+
+    #[test]
+    fn test_part_keys_sort_in_order() {
+        assert_eq!(
+            part_key("backups/prod/20261016T120000Z", "tender_records", 3),
+            "backups/prod/20261016T120000Z/tender_records/part-00003.jsonl"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+//! Writes the database schema documentation: every table in the environment's schema with
+//! its columns, keys and meanings from `db::catalog`, and a mermaid ER diagram.
+//!
+//! `schema_docs --output mdbook/src/schema.md` writes a page for the mdbook; `--check` exits
+//! non-zero while a table or column in the database is missing from the registry.
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use db::catalog;
+
+#[derive(Parser)]
+#[command(
+    name = "schema_docs",
+    about = "Document the pipeline's database tables"
+)]
+struct Args {
+    #[arg(long, env = "DATABASE_URL", hide_env_values = true)]
+    database_url: String,
+
+    /// Write the Markdown here instead of stdout
+    #[arg(long)]
+    output: Option<std::path::PathBuf>,
+
+    /// Fail if any table or column is not described in `db::catalog`
+    #[arg(long)]
+    check: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let pool = db::connect_read_only(&args.database_url, 1)
+        .await
+        .context("Failed to connect to database")?;
+
+    let schema: String = sqlx::query_scalar("SELECT current_schema()::TEXT")
+        .fetch_one(&pool)
+        .await?;
+    let tables = catalog::introspect(&pool).await?;
+    let markdown = catalog::markdown(&schema, &tables);
+
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, &markdown)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            eprintln!(
+                "📝 Documented {} tables in {}",
+                tables.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", markdown),
+    }
+
+    if args.check {
+        let missing = catalog::undocumented(&tables);
+        if !missing.is_empty() {
+            for name in &missing {
+                eprintln!("❌ Not in db::catalog: {}", name);
+            }
+            bail!("{} tables or columns are undocumented", missing.len());
+        }
+    }
+    Ok(())
+}
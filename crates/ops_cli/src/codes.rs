@@ -0,0 +1,101 @@
+use anyhow::{Context, Result, bail};
+use clap::Subcommand;
+use pdf_processing::codes::{self, DetectionCode};
+use sqlx::PgPool;
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum CodesCommand {
+    /// List active codes
+    List {
+        /// Include deactivated codes
+        #[arg(long)]
+        all: bool,
+    },
+    /// Add a code, or update the description/category of an existing one (re-activates it)
+    Add {
+        code: String,
+        #[arg(long)]
+        description: Option<String>,
+        #[arg(long)]
+        category: Option<String>,
+    },
+    /// Stop matching a code without losing its history
+    Deactivate { code: String },
+    /// Resume matching a deactivated code
+    Activate { code: String },
+    /// Seed the table from a legacy codes.txt (one `code,description` per line)
+    Import {
+        file: PathBuf,
+        /// Category applied to every imported code
+        #[arg(long)]
+        category: Option<String>,
+    },
+}
+
+pub async fn run(pool: &PgPool, command: CodesCommand) -> Result<()> {
+    codes::ensure_codes_table(pool).await?;
+
+    match command {
+        CodesCommand::List { all } => {
+            let codes = codes::list_codes(pool, all).await?;
+            for code in &codes {
+                println!(
+                    "{}\t{}\t{}{}",
+                    code.code,
+                    code.category.as_deref().unwrap_or("-"),
+                    code.description.as_deref().unwrap_or(""),
+                    if code.active { "" } else { "\t(inactive)" }
+                );
+            }
+            println!("{} codes", codes.len());
+        }
+        CodesCommand::Add {
+            code,
+            description,
+            category,
+        } => {
+            let code = DetectionCode {
+                code: code.trim().to_string(),
+                description,
+                category,
+                active: true,
+            };
+            if code.code.is_empty() {
+                bail!("Code must not be empty");
+            }
+            codes::upsert_code(pool, &code).await?;
+            println!("✅ Saved code {}", code.code);
+        }
+        CodesCommand::Deactivate { code } => set_active(pool, &code, false).await?,
+        CodesCommand::Activate { code } => set_active(pool, &code, true).await?,
+        CodesCommand::Import { file, category } => {
+            let content = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            let mut imported = codes::parse_codes_file(&content);
+            for code in &mut imported {
+                code.category = category.clone();
+                codes::upsert_code(pool, code).await?;
+            }
+            println!(
+                "✅ Imported {} codes from {}",
+                imported.len(),
+                file.display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn set_active(pool: &PgPool, code: &str, active: bool) -> Result<()> {
+    if !codes::set_active(pool, code, active).await? {
+        bail!("Unknown code {}", code);
+    }
+    println!(
+        "✅ {} code {}",
+        if active { "Activated" } else { "Deactivated" },
+        code
+    );
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! The effective configuration of the stage lambdas, as they report it.
+//!
+//! Each lambda is invoked with `{"payload": {"diagnostic": "config"}}` and answers with
+//! its `ConfigReport` (see `pipeline_contract::diagnostics`) instead of processing a
+//! tender: its environment variables with secrets redacted, the model and prompt versions
+//! it was built with, and its thresholds and policies after defaults. Function names get
+//! the environment's resource prefix, as queues and buckets do.
+
+use anyhow::{Context, Result, bail};
+use aws_sdk_lambda::Client as LambdaClient;
+use aws_sdk_lambda::primitives::Blob;
+use clap::Subcommand;
+use environment::Environment;
+use pipeline_contract::diagnostics::{self, ConfigReport, Source};
+use serde_json::json;
+
+/// Lambdas that answer the config diagnostic
+const LAMBDAS: &[&str] = &[
+    "pdf_processing",
+    "ml_bid_predictor",
+    "ai_summary",
+    "sns_notification",
+];
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the stage lambdas' effective configuration, secrets redacted
+    Show {
+        /// Only this lambda (pdf_processing, ml_bid_predictor, ai_summary or sns_notification)
+        lambda: Option<String>,
+        /// Print the reports as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+pub async fn run(command: ConfigCommand) -> Result<()> {
+    match command {
+        ConfigCommand::Show { lambda, json } => {
+            let lambdas: Vec<&str> = match &lambda {
+                Some(name) if LAMBDAS.contains(&name.as_str()) => vec![name.as_str()],
+                Some(name) => bail!(
+                    "Unknown lambda '{}' (expected one of {})",
+                    name,
+                    LAMBDAS.join(", ")
+                ),
+                None => LAMBDAS.to_vec(),
+            };
+
+            let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+                .load()
+                .await;
+            let client = LambdaClient::new(&aws_config);
+            let prefix = Environment::from_env().resource_prefix();
+
+            let mut reports = Vec::new();
+            for lambda in lambdas {
+                let function = format!("{}{}", prefix, lambda);
+                reports.push(fetch(&client, &function).await?);
+            }
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&reports)?);
+            } else {
+                for report in &reports {
+                    print_report(report);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn fetch(client: &LambdaClient, function: &str) -> Result<ConfigReport> {
+    let request = json!({ "payload": { "diagnostic": diagnostics::CONFIG } });
+    let response = client
+        .invoke()
+        .function_name(function)
+        .payload(Blob::new(serde_json::to_vec(&request)?))
+        .send()
+        .await
+        .with_context(|| format!("Failed to invoke {}", function))?;
+
+    let payload = response
+        .payload()
+        .map(|payload| payload.as_ref())
+        .unwrap_or_default();
+    if let Some(error) = response.function_error() {
+        bail!(
+            "{} failed ({}): {}",
+            function,
+            error,
+            String::from_utf8_lossy(payload)
+        );
+    }
+    serde_json::from_slice(payload)
+        .with_context(|| format!("{} didn't return a config report", function))
+}
+
+fn print_report(report: &ConfigReport) {
+    println!("== {} {}", report.component, report.version);
+    for entry in &report.entries {
+        let source = match entry.source {
+            Source::Env => "env",
+            Source::Effective => "effective",
+            Source::Build => "build",
+        };
+        println!(
+            "{:<30} {:<10} {}",
+            entry.name,
+            source,
+            entry.value.as_deref().unwrap_or("(unset)")
+        );
+    }
+    println!();
+}
@@ -0,0 +1,76 @@
+//! Evaluate a tender PDF that isn't on eTenders, as tender_api's `POST /evaluate` does.
+
+use anyhow::{Context, Result};
+use clap::Args;
+use sqlx::PgPool;
+use std::path::PathBuf;
+use tender_evaluation::{Evaluator, Upload};
+
+#[derive(Args)]
+pub struct EvaluateArgs {
+    /// The tender PDF
+    path: PathBuf,
+    #[arg(long)]
+    title: String,
+    /// Contracting authority, as the portal would name it
+    #[arg(long, default_value = "Unknown")]
+    authority: String,
+    #[arg(long, env = "ANTHROPIC_API_KEY", hide_env_values = true)]
+    anthropic_api_key: String,
+    /// Print the verdict as JSON, as the API returns it
+    #[arg(long)]
+    json: bool,
+}
+
+pub async fn run(pool: &PgPool, args: EvaluateArgs) -> Result<()> {
+    let pdf = std::fs::read(&args.path)
+        .with_context(|| format!("Failed to read {}", args.path.display()))?;
+    let evaluator = Evaluator::new(pool.clone(), args.anthropic_api_key).await?;
+    let verdict = evaluator
+        .evaluate(Upload {
+            title: args.title,
+            contracting_authority: args.authority,
+            pdf,
+        })
+        .await?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&verdict)?);
+        return Ok(());
+    }
+
+    println!(
+        "{} ({}): {} pages, {} characters",
+        verdict.title, verdict.contracting_authority, verdict.pages, verdict.pdf_chars
+    );
+    println!("Codes:\t{}", verdict.detected_codes.join(", "));
+    println!(
+        "Model:\t{} ({:.0}% confidence) - {}",
+        if verdict.ml_prediction.should_bid {
+            "BID"
+        } else {
+            "NO BID"
+        },
+        verdict.ml_prediction.confidence * 100.0,
+        verdict.ml_prediction.reasoning
+    );
+    match (&verdict.ai_summary, &verdict.ai_error) {
+        (Some(summary), _) => {
+            println!("Claude:\t{}", summary.recommendation);
+            println!("\n{}", summary.ai_summary);
+            for point in &summary.key_points {
+                println!("  - {}", point);
+            }
+            println!();
+        }
+        (None, Some(e)) => println!("Claude:\tunavailable ({})", e),
+        (None, None) => {}
+    }
+    println!(
+        "{} {} (decided by {})",
+        if verdict.bid { "✅" } else { "❌" },
+        if verdict.bid { "BID" } else { "NO BID" },
+        verdict.decided_by
+    );
+    Ok(())
+}
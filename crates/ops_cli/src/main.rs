@@ -0,0 +1,97 @@
+//! Operator command line for the tender pipeline.
+//!
+//! Connects with `DATABASE_URL` and honours `ENVIRONMENT` the same way the lambdas do,
+//! so `ENVIRONMENT=staging ops_cli ...` works against the staging schema.
+
+mod backup;
+mod codes;
+mod config;
+mod evaluate;
+mod notify;
+mod outcomes;
+mod overrides;
+mod purge;
+mod simulate;
+mod tags;
+mod tail;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use sqlx::PgPool;
+
+#[derive(Parser)]
+#[command(name = "ops_cli", about = "Operator tooling for the tender pipeline")]
+struct Cli {
+    #[arg(long, env = "DATABASE_URL", hide_env_values = true)]
+    database_url: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage the detection codes matched in tender PDFs
+    #[command(subcommand)]
+    Codes(codes::CodesCommand),
+    /// Evaluate a tender PDF that isn't on eTenders (e.g. one received by email)
+    Evaluate(evaluate::EvaluateArgs),
+    /// Tag tenders with manual categories
+    #[command(subcommand)]
+    Tags(tags::TagsCommand),
+    /// Record bid outcomes for the win-rate analytics
+    #[command(subcommand)]
+    Outcomes(outcomes::OutcomesCommand),
+    /// Force a tender's recommendation to BID or NO BID, or snooze it
+    #[command(subcommand)]
+    Override(overrides::OverrideCommand),
+    /// Remove a tender's stored PDF text, summaries and notification logs (GDPR requests)
+    Purge(purge::PurgeArgs),
+    /// Follow a tender's stage updates, decisions and deliveries as they happen
+    Tail(tail::TailArgs),
+    /// Export every pipeline table to S3, or restore an export into an empty database
+    #[command(subcommand)]
+    Backup(backup::BackupCommand),
+    /// Re-send a tender's notification email
+    #[command(subcommand)]
+    Notify(notify::NotifyCommand),
+    /// Show what the deployed lambdas are actually configured with
+    #[command(subcommand)]
+    Config(config::ConfigCommand),
+    /// Replay recent tenders through new routing, threshold or notification settings
+    Simulate(simulate::SimulateArgs),
+    /// Refresh the analytics views now instead of waiting for analytics_refresh
+    RefreshAnalytics,
+}
+
+async fn connect(database_url: &str) -> Result<PgPool> {
+    db::connect(database_url, 1)
+        .await
+        .context("Failed to connect to database")
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let pool = connect(&cli.database_url).await?;
+
+    match cli.command {
+        Command::Codes(command) => codes::run(&pool, command).await,
+        Command::Evaluate(args) => evaluate::run(&pool, args).await,
+        Command::Tags(command) => tags::run(&pool, command).await,
+        Command::Outcomes(command) => outcomes::run(&pool, command).await,
+        Command::Override(command) => overrides::run(&pool, command).await,
+        Command::Purge(args) => purge::run(&pool, args).await,
+        Command::Tail(args) => tail::run(&pool, args).await,
+        Command::Backup(command) => backup::run(&pool, command).await,
+        Command::Notify(command) => notify::run(&pool, command).await,
+        Command::Config(command) => config::run(command).await,
+        Command::Simulate(args) => simulate::run(&pool, args).await,
+        Command::RefreshAnalytics => {
+            analytics::ensure_views(&pool).await?;
+            analytics::refresh(&pool).await?;
+            println!("✅ Analytics views refreshed");
+            Ok(())
+        }
+    }
+}
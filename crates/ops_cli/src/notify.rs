@@ -0,0 +1,260 @@
+//! Re-send a tender's notification email, e.g. to someone who asks for a forward.
+//!
+//! The message is rebuilt from what's stored - the tender record, the tenant's
+//! `ai_summaries` row, the PDF's codes and eligibility lines - and queued for
+//! sns_notification as a `NOTIFICATION_RESEND`, so it goes out through the same templates
+//! and threading as the original. sns_notification only emails it: the tender's
+//! notified flag and lifecycle are left alone, and the resend is recorded in
+//! `decision_audit`. What isn't stored (the ML feature scores, win estimate and ticket)
+//! is left out of the email.
+
+use ai_summary::decision::{self, Indicators, WATCH_RULE_NOTE};
+use ai_summary::prompt_context::extract_eligibility;
+use ai_summary::tenants::DEFAULT_TENANT;
+use anyhow::{Result, bail};
+use chrono::Utc;
+use clap::Subcommand;
+use pipeline_contract::{Procedure, Routing};
+use pipeline_status::Stage;
+use queue::Publisher;
+use resource_discovery::Resource;
+use serde_json::{Value, json};
+use sqlx::{PgPool, Row};
+use tender_core::RESEND_MESSAGE_TYPE;
+
+#[derive(Subcommand)]
+pub enum NotifyCommand {
+    /// Email a tender's notification again, rebuilt from its stored summary
+    Resend {
+        resource_id: i64,
+        /// Send only to this address instead of the usual recipients; repeat for several
+        #[arg(long = "to")]
+        to: Vec<String>,
+        /// Whose summary and recipients to use
+        #[arg(long, default_value = DEFAULT_TENANT)]
+        tenant: String,
+    },
+}
+
+pub async fn run(pool: &PgPool, command: NotifyCommand) -> Result<()> {
+    match command {
+        NotifyCommand::Resend {
+            resource_id,
+            to,
+            tenant,
+        } => {
+            let message = build_message(pool, resource_id, &tenant, &to).await?;
+            let publisher = Publisher::from_env().await;
+            let message_id = publisher
+                .send(
+                    Resource::NotificationQueue,
+                    &message.to_string(),
+                    &Routing::to_stage(Stage::Notification.name()),
+                )
+                .await?;
+            let recipients = if to.is_empty() {
+                "the usual recipients".to_string()
+            } else {
+                to.join(", ")
+            };
+            println!(
+                "✅ Queued tender {}'s notification for {} (MessageId: {})",
+                resource_id, recipients, message_id
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The notification ai_summary would have sent, from the stored tender and summary
+async fn build_message(
+    pool: &PgPool,
+    resource_id: i64,
+    tenant: &str,
+    to: &[String],
+) -> Result<Value> {
+    let Some(row) = sqlx::query(
+        r#"
+        SELECT t.title,
+               t.ca AS contracting_authority,
+               t.value::TEXT AS value,
+               t.deadline::TEXT AS deadline,
+               t.procedure,
+               t.status,
+               t.pdf_url,
+               t.ml_bid,
+               t.ml_confidence::FLOAT8 AS ml_confidence,
+               t.ml_reasoning,
+               s.summary_type,
+               s.ai_summary,
+               s.key_points,
+               s.recommendation,
+               s.confidence_assessment,
+               s.processing_notes
+        FROM tender_records t
+        JOIN ai_summaries s ON s.resource_id = t.resource_id AND s.tenant_id = $2
+        WHERE t.resource_id = $1
+        "#,
+    )
+    .bind(resource_id)
+    .bind(tenant)
+    .fetch_optional(pool)
+    .await?
+    else {
+        bail!(
+            "Tender {} has no stored summary for tenant '{}'",
+            resource_id,
+            tenant
+        );
+    };
+
+    let summary_type: String = row.get("summary_type");
+    let ai_summary: String = row.get("ai_summary");
+    let notes: Vec<String> =
+        serde_json::from_value(row.get("processing_notes")).unwrap_or_default();
+    let ml_bid = row.get::<Option<bool>, _>("ml_bid").unwrap_or(false);
+    let indicators = Indicators::from_notes(&notes, WATCH_RULE_NOTE);
+    let watchlist_matches: Vec<&str> = notes
+        .iter()
+        .filter_map(|note| note.strip_prefix("👀 WATCHLIST MATCH: "))
+        .collect();
+    let watch_rule = notes
+        .iter()
+        .find_map(|note| note.strip_prefix(WATCH_RULE_NOTE));
+
+    let procedure_text: Option<String> = row.get("procedure");
+    let procedure = Procedure::normalize(procedure_text.as_deref().unwrap_or_default());
+    let template = if summary_type == decision::EARLY_INTEREST {
+        "early_interest"
+    } else {
+        "opportunity"
+    };
+
+    let (detected_codes, eligibility, clarification_deadline) =
+        pdf_details(pool, resource_id).await?;
+    let (tenant_name, tenant_emails) = tenant_profile(pool, tenant).await?;
+    let recipients = if to.is_empty() {
+        tenant_emails
+    } else {
+        to.to_vec()
+    };
+
+    Ok(json!({
+        "message_type": RESEND_MESSAGE_TYPE,
+        "resource_id": resource_id.to_string(),
+        "title": row.get::<String, _>("title"),
+        "priority": decision::priority(indicators, ml_bid, &summary_type),
+        "summary": ai_summary,
+        "action_required": decision::action_required(indicators, ml_bid, &summary_type),
+        "timestamp": Utc::now().to_rfc3339(),
+        "metadata": {
+            "resource_id": resource_id,
+            "tenant_id": tenant,
+            "tenant_name": tenant_name,
+            "notification_emails": recipients,
+            "watchlist_matches": watchlist_matches,
+            "watch_rule": watch_rule,
+            "contracting_authority": row.get::<Option<String>, _>("contracting_authority"),
+            "estimated_value": row.get::<Option<String>, _>("value"),
+            "deadline": row.get::<Option<String>, _>("deadline"),
+            "clarification_deadline": clarification_deadline,
+            "summary_type": summary_type,
+            "processing_notes": notes,
+            "ml_prediction": {
+                "should_bid": ml_bid,
+                "confidence": row.get::<Option<f64>, _>("ml_confidence").unwrap_or(0.0),
+                "reasoning": row.get::<Option<String>, _>("ml_reasoning")
+            },
+            "detected_codes": detected_codes,
+            "eligibility": eligibility,
+            "ai_summary": ai_summary,
+            "key_points": row.get::<Value, _>("key_points"),
+            "recommendation": row.get::<String, _>("recommendation"),
+            "confidence_assessment": row.get::<String, _>("confidence_assessment"),
+            "pdf_url": row.get::<Option<String>, _>("pdf_url"),
+            "status": row.get::<Option<String>, _>("status"),
+            "procedure": procedure_text,
+            "procedure_type": procedure.name(),
+            "procedure_label": procedure.label(),
+            "template": template,
+            "portal_link": format!("https://etenders.gov.ie/epps/opportunity/opportunityDetailAction.do?opportunityId={}", resource_id)
+        }
+    }))
+}
+
+/// Codes (with catalogue descriptions), eligibility lines and clarification deadline from
+/// the tender's PDF, if it had one
+async fn pdf_details(
+    pool: &PgPool,
+    resource_id: i64,
+) -> Result<(Vec<Value>, Vec<String>, Option<String>)> {
+    if !table_exists(pool, "pdf_content").await? {
+        return Ok((Vec::new(), Vec::new(), None));
+    }
+    let Some(row) = sqlx::query(
+        r#"
+        SELECT pdf_text, detected_codes, clarification_deadline::TEXT AS clarification_deadline
+        FROM pdf_content
+        WHERE resource_id = $1
+        "#,
+    )
+    .bind(resource_id)
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok((Vec::new(), Vec::new(), None));
+    };
+
+    let codes: Vec<String> = row
+        .get::<Option<Vec<String>>, _>("detected_codes")
+        .unwrap_or_default();
+    let descriptions = if codes.is_empty() || !table_exists(pool, "detection_codes").await? {
+        Vec::new()
+    } else {
+        sqlx::query("SELECT code, description FROM detection_codes WHERE code = ANY($1)")
+            .bind(&codes)
+            .fetch_all(pool)
+            .await?
+    };
+    let detected_codes = codes
+        .iter()
+        .map(|code| {
+            let description = descriptions
+                .iter()
+                .find(|row| row.get::<String, _>("code") == *code)
+                .and_then(|row| row.get::<Option<String>, _>("description"));
+            json!({ "code": code, "description": description })
+        })
+        .collect();
+
+    let pdf_text: String = row.get("pdf_text");
+    Ok((
+        detected_codes,
+        extract_eligibility(&pdf_text),
+        row.get("clarification_deadline"),
+    ))
+}
+
+/// The tenant's name and own recipients, when it has a profile
+async fn tenant_profile(pool: &PgPool, tenant: &str) -> Result<(Option<String>, Vec<String>)> {
+    if !table_exists(pool, "tenants").await? {
+        return Ok((None, Vec::new()));
+    }
+    let row = sqlx::query("SELECT name, notification_emails FROM tenants WHERE tenant_id = $1")
+        .bind(tenant)
+        .fetch_optional(pool)
+        .await?;
+    Ok(match row {
+        Some(row) => (row.get("name"), row.get("notification_emails")),
+        None => (None, Vec::new()),
+    })
+}
+
+async fn table_exists(pool: &PgPool, table: &str) -> Result<bool> {
+    Ok(sqlx::query("SELECT to_regclass($1) IS NOT NULL AS exists")
+        .bind(table)
+        .fetch_one(pool)
+        .await?
+        .get("exists"))
+}
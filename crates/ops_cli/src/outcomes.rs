@@ -0,0 +1,68 @@
+use analytics::outcomes::{self, Outcome};
+use anyhow::{Result, bail};
+use clap::Subcommand;
+use sqlx::PgPool;
+
+#[derive(Subcommand)]
+pub enum OutcomesCommand {
+    /// Most recently recorded outcomes
+    List {
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+    },
+    /// Record what happened to a tender we bid on (won, lost or withdrawn)
+    Record {
+        resource_id: i64,
+        outcome: String,
+        /// Recorded as the outcome's author
+        #[arg(long, env = "USER", default_value = "ops_cli")]
+        by: String,
+    },
+    /// Remove a tender's outcome, e.g. one recorded against the wrong tender
+    Remove { resource_id: i64 },
+}
+
+pub async fn run(pool: &PgPool, command: OutcomesCommand) -> Result<()> {
+    outcomes::ensure_table(pool).await?;
+
+    match command {
+        OutcomesCommand::List { limit } => {
+            let recorded = outcomes::recent(pool, limit).await?;
+            for outcome in &recorded {
+                println!(
+                    "{}\t{}\t{}\t{}\t{} ({})",
+                    outcome.resource_id,
+                    outcome.outcome.as_str(),
+                    outcome.recorded_at.format("%Y-%m-%d"),
+                    outcome.recorded_by,
+                    outcome.title,
+                    outcome.contracting_authority
+                );
+            }
+            println!("{} outcomes", recorded.len());
+        }
+        OutcomesCommand::Record {
+            resource_id,
+            outcome,
+            by,
+        } => {
+            let Some(outcome) = Outcome::parse(&outcome) else {
+                bail!(
+                    "Unknown outcome '{}' (expected won, lost or withdrawn)",
+                    outcome
+                );
+            };
+            outcomes::record(pool, resource_id, outcome, &by).await?;
+            println!("✅ Recorded tender {} as {}", resource_id, outcome.as_str());
+        }
+        OutcomesCommand::Remove { resource_id } => {
+            if outcomes::remove(pool, resource_id).await? {
+                println!("✅ Removed the outcome of tender {}", resource_id);
+            } else {
+                println!("Tender {} had no outcome", resource_id);
+            }
+        }
+    }
+
+    Ok(())
+}
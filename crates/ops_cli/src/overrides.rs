@@ -0,0 +1,177 @@
+use anyhow::Result;
+use chrono::Utc;
+use clap::{Args, Subcommand};
+use sqlx::PgPool;
+use tender_overrides::{Change, Forced, TenderOverride};
+
+#[derive(Subcommand)]
+pub enum OverrideCommand {
+    /// Every override of the tenant's, or one tender's
+    List {
+        #[arg(long)]
+        resource_id: Option<i64>,
+        #[arg(long, default_value = "default")]
+        tenant: String,
+    },
+    /// Force the tender's recommendation to BID, whatever Claude said
+    Bid {
+        resource_id: i64,
+        #[command(flatten)]
+        change: ChangeArgs,
+    },
+    /// Force the tender's recommendation to NO BID; its emails and reminders stop
+    NoBid {
+        resource_id: i64,
+        #[command(flatten)]
+        change: ChangeArgs,
+    },
+    /// Go back to Claude's recommendation
+    Clear {
+        resource_id: i64,
+        #[command(flatten)]
+        change: ChangeArgs,
+    },
+    /// Hold back the tender's emails and reminders for a number of days
+    Snooze {
+        resource_id: i64,
+        #[arg(long)]
+        days: i64,
+        #[command(flatten)]
+        change: ChangeArgs,
+    },
+    /// End a snooze early
+    Wake {
+        resource_id: i64,
+        #[command(flatten)]
+        change: ChangeArgs,
+    },
+}
+
+#[derive(Args)]
+pub struct ChangeArgs {
+    #[arg(long, default_value = "default")]
+    tenant: String,
+    /// Recorded with the override and in the audit trail
+    #[arg(long)]
+    reason: Option<String>,
+    /// Recorded as the override's author
+    #[arg(long, env = "USER", default_value = "ops_cli")]
+    by: String,
+}
+
+impl ChangeArgs {
+    fn change(&self) -> Change<'_> {
+        Change {
+            tenant_id: &self.tenant,
+            set_by: &self.by,
+            reason: self.reason.as_deref(),
+            component: "ops_cli",
+        }
+    }
+}
+
+fn describe(tender_override: &TenderOverride) -> String {
+    let mut parts = Vec::new();
+    if let Some(forced) = tender_override.recommendation {
+        parts.push(format!("forced {}", forced.recommendation()));
+    }
+    if let Some(until) = tender_override.snoozed_until {
+        parts.push(if until > Utc::now() {
+            format!("snoozed until {}", until.format("%Y-%m-%d %H:%M"))
+        } else {
+            format!("snooze ended {}", until.format("%Y-%m-%d %H:%M"))
+        });
+    }
+    format!(
+        "{}\t{}\t{} {}{}",
+        tender_override.resource_id,
+        parts.join(", "),
+        tender_override.set_by,
+        tender_override.set_at.format("%Y-%m-%d"),
+        tender_override
+            .reason
+            .as_deref()
+            .map(|reason| format!("\t{}", reason))
+            .unwrap_or_default()
+    )
+}
+
+fn report(resource_id: i64, current: Option<TenderOverride>) {
+    match current {
+        Some(current) => println!("✅ {}", describe(&current)),
+        None => println!("✅ Tender {} has no override", resource_id),
+    }
+}
+
+pub async fn run(pool: &PgPool, command: OverrideCommand) -> Result<()> {
+    tender_overrides::ensure_table(pool).await?;
+    decision_audit::ensure_table(pool).await?;
+
+    match command {
+        OverrideCommand::List {
+            resource_id: Some(resource_id),
+            tenant,
+        } => match tender_overrides::get(pool, resource_id, &tenant).await? {
+            Some(current) => println!("{}", describe(&current)),
+            None => println!(
+                "Tender {} has no override for tenant {}",
+                resource_id, tenant
+            ),
+        },
+        OverrideCommand::List {
+            resource_id: None,
+            tenant,
+        } => {
+            let overrides = tender_overrides::list(pool, &tenant).await?;
+            for tender_override in &overrides {
+                println!("{}", describe(tender_override));
+            }
+            println!("{} overrides for tenant {}", overrides.len(), tenant);
+        }
+        OverrideCommand::Bid {
+            resource_id,
+            change,
+        } => {
+            let current =
+                tender_overrides::force(pool, resource_id, Some(Forced::Bid), change.change())
+                    .await?;
+            report(resource_id, current);
+        }
+        OverrideCommand::NoBid {
+            resource_id,
+            change,
+        } => {
+            let current =
+                tender_overrides::force(pool, resource_id, Some(Forced::NoBid), change.change())
+                    .await?;
+            report(resource_id, current);
+        }
+        OverrideCommand::Clear {
+            resource_id,
+            change,
+        } => {
+            let current = tender_overrides::force(pool, resource_id, None, change.change()).await?;
+            report(resource_id, current);
+        }
+        OverrideCommand::Snooze {
+            resource_id,
+            days,
+            change,
+        } => {
+            let until = tender_overrides::snooze_until(days, Utc::now())?;
+            let current =
+                tender_overrides::snooze(pool, resource_id, Some(until), change.change()).await?;
+            report(resource_id, current);
+        }
+        OverrideCommand::Wake {
+            resource_id,
+            change,
+        } => {
+            let current =
+                tender_overrides::snooze(pool, resource_id, None, change.change()).await?;
+            report(resource_id, current);
+        }
+    }
+
+    Ok(())
+}
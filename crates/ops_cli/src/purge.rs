@@ -0,0 +1,347 @@
+//! Data subject deletion: removes what the pipeline derived from a tender's documents.
+//!
+//! The PDF text, Claude's summaries (cached ones and long PDFs' chunk notes too) and
+//! response skeletons, queued messages carrying the text, notification and webhook
+//! delivery logs, and the PDF's thumbnail in S3 are all removed.
+//! The tender's portal metadata (title, authority, dates) stays, without the buyer's
+//! contact details, as do labels and outcomes. Each purged tender gets a receipt in
+//! `decision_audit` listing what went.
+//! Tickets and CRM deals live in other systems and are not touched, and neither are
+//! `ops_cli backup` exports: one taken before the purge still holds the PDF text and
+//! summaries, so restoring it brings them back. The receipt says so (`backups`); purge the
+//! tender again after such a restore.
+
+use anyhow::{Context, Result, bail};
+use aws_sdk_s3::Client as S3Client;
+use clap::{ArgGroup, Args};
+use decision_audit::{Decision, Kind};
+use pdf_processing::thumbnail::thumbnail_key;
+use resource_discovery::{Resource, ResourceDiscovery};
+use serde_json::{Map, Value, json};
+use sqlx::{PgPool, Row};
+
+/// On every receipt: what the purge leaves behind in `ops_cli backup` exports
+const BACKUPS_NOTE: &str = "Backups exported before this purge (backups/<environment>/ in the \
+     lambda bucket) still hold the removed rows; purge the tender again after restoring one";
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("selector").required(true).args(["resource_ids", "authority"])))]
+pub struct PurgeArgs {
+    /// Tender to purge; repeat for several
+    #[arg(long = "resource-id")]
+    resource_ids: Vec<i64>,
+    /// Purge every tender from this contracting authority (case and spacing are ignored)
+    #[arg(long)]
+    authority: Option<String>,
+    /// Request reference for the receipt, e.g. the ticket number
+    #[arg(long)]
+    reference: Option<String>,
+    /// Recorded on the receipt
+    #[arg(long, env = "USER", default_value = "ops_cli")]
+    by: String,
+    /// Report what would be removed without removing it
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// Per table, the statement removing a tender's data; tables that don't exist yet are skipped
+const STEPS: &[(&str, &str)] = &[
+    // The tender itself stays; the buyer's contact person is personal data
+    (
+        "tender_records",
+        "UPDATE tender_records SET buyer_contact_name = NULL, buyer_contact_email = NULL, buyer_contact_phone = NULL WHERE resource_id = $1 AND (buyer_contact_name IS NOT NULL OR buyer_contact_email IS NOT NULL OR buyer_contact_phone IS NOT NULL)",
+    ),
+    (
+        "pdf_content",
+        "DELETE FROM pdf_content WHERE resource_id = $1",
+    ),
+    (
+        "pdf_content_next",
+        "DELETE FROM pdf_content_next WHERE resource_id = $1",
+    ),
+    (
+        "ai_summaries",
+        "DELETE FROM ai_summaries WHERE resource_id = $1",
+    ),
+    (
+        "ai_summary_cache",
+        "DELETE FROM ai_summary_cache WHERE (result->>'resource_id')::BIGINT = $1",
+    ),
+    (
+        "ai_summary_chunks",
+        "DELETE FROM ai_summary_chunks WHERE resource_id = $1",
+    ),
+    (
+        "response_skeletons",
+        "DELETE FROM response_skeletons WHERE resource_id = $1",
+    ),
+    (
+        "ai_summary_batch",
+        "DELETE FROM ai_summary_batch WHERE resource_id = $1",
+    ),
+    // Stored stage messages carry the PDF text; the stage status itself stays
+    (
+        "pipeline_status",
+        "UPDATE pipeline_status SET message = NULL WHERE resource_id = $1 AND message IS NOT NULL",
+    ),
+    (
+        "notification_log",
+        "DELETE FROM notification_log WHERE resource_id = $1",
+    ),
+    (
+        "notification_log_archive",
+        "DELETE FROM notification_log_archive WHERE resource_id = $1",
+    ),
+    (
+        "webhook_deliveries",
+        "DELETE FROM webhook_deliveries WHERE resource_id = $1",
+    ),
+    // Moved there by data_archive once old enough
+    (
+        "webhook_deliveries_archive",
+        "DELETE FROM webhook_deliveries_archive WHERE resource_id = $1",
+    ),
+    ("reminders", "DELETE FROM reminders WHERE resource_id = $1"),
+    (
+        "saved_search_matches",
+        "DELETE FROM saved_search_matches WHERE resource_id = $1",
+    ),
+];
+
+/// Authority names are typed inconsistently on the portal; match the SQL in `select`
+fn normalize(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+async fn select(pool: &PgPool, args: &PurgeArgs) -> Result<Vec<i64>> {
+    let Some(authority) = &args.authority else {
+        return Ok(args.resource_ids.clone());
+    };
+
+    let ids = sqlx::query_scalar(
+        r#"
+        SELECT resource_id FROM tender_records
+        WHERE lower(regexp_replace(trim(ca), '\s+', ' ', 'g')) = $1
+        ORDER BY resource_id
+        "#,
+    )
+    .bind(normalize(authority))
+    .fetch_all(pool)
+    .await?;
+    Ok(ids)
+}
+
+async fn existing_tables(pool: &PgPool) -> Result<Vec<&'static str>> {
+    let mut tables = Vec::new();
+    for (table, _) in STEPS {
+        let exists: bool = sqlx::query("SELECT to_regclass($1) IS NOT NULL AS exists")
+            .bind(*table)
+            .fetch_one(pool)
+            .await?
+            .get("exists");
+        if exists {
+            tables.push(*table);
+        }
+    }
+    Ok(tables)
+}
+
+/// Rows removed per table, in one transaction (rolled back for a dry run)
+async fn purge_tables(
+    pool: &PgPool,
+    tables: &[&str],
+    resource_id: i64,
+    dry_run: bool,
+) -> Result<Map<String, Value>> {
+    let mut tx = pool.begin().await?;
+    let mut removed = Map::new();
+    for (table, statement) in STEPS.iter().filter(|(table, _)| tables.contains(table)) {
+        let rows = sqlx::query(statement)
+            .bind(resource_id)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to purge {}", table))?
+            .rows_affected();
+        if rows > 0 {
+            removed.insert(table.to_string(), json!(rows));
+        }
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+    Ok(removed)
+}
+
+/// The S3 objects found for the tender (deleted unless it's a dry run)
+async fn purge_s3(
+    s3: &S3Client,
+    bucket: &str,
+    resource_id: i64,
+    dry_run: bool,
+) -> Result<Vec<String>> {
+    let key = thumbnail_key(resource_id);
+    match s3.head_object().bucket(bucket).key(&key).send().await {
+        Ok(_) => {}
+        Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to check s3://{}/{}", bucket, key));
+        }
+    }
+
+    if !dry_run {
+        s3.delete_object()
+            .bucket(bucket)
+            .key(&key)
+            .send()
+            .await
+            .with_context(|| format!("Failed to delete s3://{}/{}", bucket, key))?;
+    }
+    Ok(vec![format!("s3://{}/{}", bucket, key)])
+}
+
+pub async fn run(pool: &PgPool, args: PurgeArgs) -> Result<()> {
+    let resource_ids = select(pool, &args).await?;
+    if resource_ids.is_empty() {
+        bail!("No tenders match");
+    }
+
+    let tables = existing_tables(pool).await?;
+    let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        .load()
+        .await;
+    let bucket = ResourceDiscovery::new(&aws_config)
+        .resolve(Resource::LambdaBucket)
+        .await
+        .context("Failed to find the lambda bucket")?;
+    let s3 = S3Client::new(&aws_config);
+
+    let mut failed = 0;
+    for resource_id in &resource_ids {
+        let resource_id = *resource_id;
+        let removed = purge_tables(pool, &tables, resource_id, args.dry_run).await?;
+        // The database part is done by now, so an S3 failure still gets a receipt
+        let (objects, s3_error) = match purge_s3(&s3, &bucket, resource_id, args.dry_run).await {
+            Ok(objects) => (objects, None),
+            Err(e) => {
+                failed += 1;
+                (Vec::new(), Some(format!("{:#}", e)))
+            }
+        };
+
+        println!(
+            "{}{}\t{}\t{}{}",
+            if args.dry_run { "[dry run] " } else { "" },
+            resource_id,
+            if removed.is_empty() {
+                "no rows".to_string()
+            } else {
+                removed
+                    .iter()
+                    .map(|(table, rows)| format!("{} {}", table, rows))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            },
+            if objects.is_empty() {
+                "no S3 objects".to_string()
+            } else {
+                objects.join(", ")
+            },
+            s3_error
+                .as_deref()
+                .map(|e| format!("\tS3 FAILED: {}", e))
+                .unwrap_or_default()
+        );
+        if args.dry_run {
+            continue;
+        }
+
+        let receipt = Decision::new(
+            resource_id,
+            Kind::Purge,
+            "ops_cli",
+            if s3_error.is_some() {
+                "partial"
+            } else {
+                "purged"
+            },
+        )
+        .detail(json!({
+            "rows_removed": removed,
+            "s3_objects_removed": objects,
+            "s3_error": s3_error,
+            "selector": match &args.authority {
+                Some(authority) => json!({ "authority": authority }),
+                None => json!({ "resource_id": resource_id }),
+            },
+            "reference": args.reference,
+            "by": args.by,
+            "backups": BACKUPS_NOTE,
+        }));
+        decision_audit::append(pool, &receipt)
+            .await
+            .with_context(|| format!("Purged {} but failed to write its receipt", resource_id))?;
+    }
+
+    if failed > 0 {
+        bail!(
+            "{} of {} tenders still have S3 objects; rerun to retry them",
+            failed,
+            resource_ids.len()
+        );
+    }
+    println!(
+        "{} {} tenders",
+        if args.dry_run {
+            "Would purge"
+        } else {
+            "✅ Purged"
+        },
+        resource_ids.len()
+    );
+    println!("Note: {}", BACKUPS_NOTE);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authority_normalization() {
+        assert_eq!(
+            normalize("  Kerry   County Council "),
+            "kerry county council"
+        );
+    }
+
+    #[test]
+    fn test_every_contact_column_is_cleared() {
+        let personal: Vec<(&str, &str)> = db::catalog::TABLES
+            .iter()
+            .flat_map(|doc| {
+                doc.columns
+                    .iter()
+                    .filter(|(column, _)| column.starts_with("buyer_contact"))
+                    .map(move |(column, _)| (doc.name, *column))
+            })
+            .collect();
+        assert_eq!(personal.len(), 3);
+
+        for (table, column) in personal {
+            assert!(
+                STEPS.iter().any(|(step_table, statement)| {
+                    *step_table == table && statement.contains(&format!("{} = NULL", column))
+                }),
+                "{}.{} is not cleared by the purge",
+                table,
+                column
+            );
+        }
+    }
+}
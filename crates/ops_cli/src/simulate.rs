@@ -0,0 +1,694 @@
+//! Dry run of a routing, threshold or notification policy change before it goes live.
+//!
+//! Replays the tenders loaded in the last `--days` days through the pipeline's decisions
+//! twice: once with the live settings (or `--baseline`) and once with `--config`, and
+//! reports the difference in tenders filtered out, Claude calls, notifications, repeat
+//! emails and labelled bids that would have been missed. Nothing is written, queued or
+//! sent, and Claude isn't called: a tender's verdict is the summary already stored in
+//! `ai_summaries`, put through the same `ai_summary::decision` rules with the simulated ML
+//! prediction. A tender the new config sends to Claude that was never summarised has no
+//! verdict to replay, so it counts as Claude calls but its notification is reported as
+//! unknown rather than guessed.
+//!
+//! The config is JSON and every field is optional, defaulting to the live value:
+//!
+//! ```json
+//! {
+//!   "pipeline_statuses": ["open", "unknown"],
+//!   "min_value": 25000,
+//!   "min_days_to_deadline": 3,
+//!   "ml_threshold": 0.054,
+//!   "claude_for_ml_no_bid": true,
+//!   "suppression_hours": 72
+//! }
+//! ```
+//!
+//! Repeat emails are replayed from `notification_log`, which only holds emails that were
+//! sent, so a shorter suppression window than the live one can't show what the live one
+//! suppressed.
+
+use ai_summary::decision::{self, Indicators, OVERRIDE_NOTE, WATCH_RULE_NOTE};
+use ai_summary::types::{AISummaryResult, FeatureScores, MLPredictionResult};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use clap::Args;
+use pipeline_contract::tender_status::{DEFAULT_PIPELINE_STATUSES, TenderStatus};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tender_overrides::Forced;
+
+/// How many resource ids to list per line of the report
+const LISTED_IDS: usize = 20;
+
+#[derive(Args)]
+pub struct SimulateArgs {
+    /// JSON file with the settings to try
+    #[arg(long)]
+    config: PathBuf,
+    /// Replay tenders loaded in this many days
+    #[arg(long, default_value_t = 30)]
+    days: i32,
+    /// JSON file with the settings to compare against, instead of the live defaults
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+}
+
+/// The settings a simulation varies, named after the env vars they stand in for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimConfig {
+    /// PIPELINE_STATUSES
+    pub pipeline_statuses: Vec<String>,
+    /// DATALOAD_MIN_VALUE; 0 turns the check off
+    pub min_value: i64,
+    /// DATALOAD_MIN_DAYS_TO_DEADLINE; 0 turns the check off
+    pub min_days_to_deadline: i64,
+    /// ml_bid_predictor's bid threshold
+    pub ml_threshold: f64,
+    /// Whether tenders ML scores as no-bid still go to Claude (they all do today)
+    pub claude_for_ml_no_bid: bool,
+    /// NOTIFICATION_SUPPRESSION_HOURS; 0 turns suppression off
+    pub suppression_hours: i64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            pipeline_statuses: DEFAULT_PIPELINE_STATUSES
+                .iter()
+                .map(|status| status.name().to_string())
+                .collect(),
+            min_value: 25_000,
+            min_days_to_deadline: 3,
+            ml_threshold: 0.054,
+            claude_for_ml_no_bid: true,
+            suppression_hours: 72,
+        }
+    }
+}
+
+impl SimConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config: Self = serde_json::from_str(&text)
+            .with_context(|| format!("{} is not a valid simulation config", path.display()))?;
+        config.statuses()?;
+        Ok(config)
+    }
+
+    /// Unlike PIPELINE_STATUSES, an unknown name is an error: a typo would skew the replay
+    fn statuses(&self) -> Result<Vec<TenderStatus>> {
+        self.pipeline_statuses
+            .iter()
+            .map(|name| match TenderStatus::parse(name) {
+                Some(status) => Ok(status),
+                None => bail!("Unknown tender status '{}' in pipeline_statuses", name),
+            })
+            .collect()
+    }
+
+    fn suppression_window(&self) -> Option<Duration> {
+        (self.suppression_hours > 0).then(|| Duration::hours(self.suppression_hours))
+    }
+
+    /// `field: old -> new` for each setting that differs from `baseline`
+    pub fn changes_from(&self, baseline: &SimConfig) -> Vec<String> {
+        let (serde_json::Value::Object(old), serde_json::Value::Object(new)) = (
+            serde_json::to_value(baseline).unwrap_or_default(),
+            serde_json::to_value(self).unwrap_or_default(),
+        ) else {
+            return Vec::new();
+        };
+        new.iter()
+            .filter(|(field, value)| old.get(*field) != Some(value))
+            .map(|(field, value)| {
+                let before = old.get(field).cloned().unwrap_or_default();
+                format!("{}: {} -> {}", field, before, value)
+            })
+            .collect()
+    }
+}
+
+/// A tender as the pipeline saw it when it was loaded
+#[derive(Debug, Clone)]
+pub struct Tender {
+    pub resource_id: i64,
+    pub status: String,
+    pub value: Option<f64>,
+    pub deadline: Option<NaiveDateTime>,
+    pub loaded_at: NaiveDateTime,
+    pub ml_confidence: Option<f64>,
+    /// The bid label: whether we actually bid
+    pub bid: Option<bool>,
+    /// One per tenant that got a verdict
+    pub summaries: Vec<AISummaryResult>,
+}
+
+/// What happens to one tender under a config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    FilteredOut,
+    /// ML scored it no-bid and the config doesn't send those to Claude
+    SkippedByMl,
+    Summarised {
+        claude_calls: usize,
+        /// None when there's no stored verdict to replay
+        notified: Option<bool>,
+    },
+}
+
+impl Outcome {
+    fn notified(&self) -> Option<bool> {
+        match self {
+            Outcome::Summarised { notified, .. } => *notified,
+            _ => Some(false),
+        }
+    }
+}
+
+/// One email from `notification_log`
+#[derive(Debug, Clone)]
+pub struct Sent {
+    pub resource_id: i64,
+    pub tenant_id: String,
+    pub sent_at: DateTime<Utc>,
+    pub deadline: Option<NaiveDateTime>,
+    pub document_hash: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Totals {
+    pub filtered_out: usize,
+    pub skipped_by_ml: usize,
+    pub claude_calls: usize,
+    pub notified: usize,
+    /// Sent to Claude under this config but never summarised, so the verdict is unknown
+    pub unknown: Vec<i64>,
+    pub repeat_emails: usize,
+    /// Labelled as bid on, but not notified
+    pub missed_bids: Vec<i64>,
+}
+
+/// Mirrors postgres_dataload's prefilter, judged at the time the tender was loaded
+fn filtered_out(tender: &Tender, config: &SimConfig, statuses: &[TenderStatus]) -> bool {
+    if !statuses.contains(&TenderStatus::normalize(&tender.status)) {
+        return true;
+    }
+    if config.min_value > 0
+        && let Some(value) = tender.value
+        && value < config.min_value as f64
+    {
+        return true;
+    }
+    config.min_days_to_deadline > 0
+        && tender.deadline.is_some_and(|deadline| {
+            deadline - tender.loaded_at < Duration::days(config.min_days_to_deadline)
+        })
+}
+
+/// Route one tender through the config; `tenants` is how many verdicts a new summary costs
+pub fn outcome(
+    tender: &Tender,
+    config: &SimConfig,
+    statuses: &[TenderStatus],
+    tenants: usize,
+) -> Outcome {
+    if filtered_out(tender, config, statuses) {
+        return Outcome::FilteredOut;
+    }
+    let ml_bid = tender
+        .ml_confidence
+        .map(|confidence| confidence >= config.ml_threshold);
+    if !config.claude_for_ml_no_bid && ml_bid == Some(false) {
+        return Outcome::SkippedByMl;
+    }
+    if tender.summaries.is_empty() {
+        return Outcome::Summarised {
+            claude_calls: tenants,
+            notified: None,
+        };
+    }
+
+    let ml_prediction = MLPredictionResult {
+        should_bid: ml_bid.unwrap_or(false),
+        confidence: tender.ml_confidence.unwrap_or_default(),
+        reasoning: String::new(),
+        feature_scores: FeatureScores::default(),
+    };
+    let notified = tender.summaries.iter().any(|summary| {
+        let watch_rule =
+            Indicators::from_notes(&summary.processing_notes, WATCH_RULE_NOTE).watch_rule;
+        // As overridden when it was summarised
+        let forced = summary
+            .processing_notes
+            .iter()
+            .find_map(|note| note.strip_prefix(OVERRIDE_NOTE))
+            .and_then(Forced::parse);
+        decision::notification_reason(summary, &ml_prediction, watch_rule, forced).notify()
+    });
+    Outcome::Summarised {
+        claude_calls: tender.summaries.len(),
+        notified: Some(notified),
+    }
+}
+
+/// Emails after the first per tender and tenant that the suppression window lets through,
+/// with sns_notification's rule; `log` is ordered by tender, tenant and time
+pub fn repeat_emails(log: &[Sent], window: Option<Duration>) -> usize {
+    let mut repeats = 0;
+    let mut last: Option<&Sent> = None;
+    for sent in log {
+        let Some(previous) = last.filter(|previous| {
+            previous.resource_id == sent.resource_id && previous.tenant_id == sent.tenant_id
+        }) else {
+            last = Some(sent);
+            continue;
+        };
+        let emailed = match window {
+            None => true,
+            Some(window) => {
+                sent.sent_at >= previous.sent_at + window
+                    || sent.deadline != previous.deadline
+                    || (sent.document_hash.is_some()
+                        && sent.document_hash != previous.document_hash)
+            }
+        };
+        if emailed {
+            repeats += 1;
+            last = Some(sent);
+        }
+    }
+    repeats
+}
+
+pub fn tally(
+    tenders: &[Tender],
+    log: &[Sent],
+    config: &SimConfig,
+    tenants: usize,
+) -> Result<Totals> {
+    let statuses = config.statuses()?;
+    let mut totals = Totals {
+        repeat_emails: repeat_emails(log, config.suppression_window()),
+        ..Totals::default()
+    };
+    for tender in tenders {
+        let outcome = outcome(tender, config, &statuses, tenants);
+        match outcome {
+            Outcome::FilteredOut => totals.filtered_out += 1,
+            Outcome::SkippedByMl => totals.skipped_by_ml += 1,
+            Outcome::Summarised {
+                claude_calls,
+                notified,
+            } => {
+                totals.claude_calls += claude_calls;
+                match notified {
+                    Some(true) => totals.notified += 1,
+                    Some(false) => {}
+                    None => totals.unknown.push(tender.resource_id),
+                }
+            }
+        }
+        if tender.bid == Some(true) && outcome.notified() == Some(false) {
+            totals.missed_bids.push(tender.resource_id);
+        }
+    }
+    Ok(totals)
+}
+
+pub async fn run(pool: &PgPool, args: SimulateArgs) -> Result<()> {
+    if args.days <= 0 {
+        bail!("--days must be at least 1");
+    }
+    let new = SimConfig::load(&args.config)?;
+    let baseline = match &args.baseline {
+        Some(path) => SimConfig::load(path)?,
+        None => SimConfig::default(),
+    };
+
+    let tenders = load_tenders(pool, args.days).await?;
+    let log = load_log(pool, args.days).await?;
+    let tenants = active_tenants(pool).await?;
+
+    let before = tally(&tenders, &log, &baseline, tenants)?;
+    let after = tally(&tenders, &log, &new, tenants)?;
+
+    println!(
+        "Replaying {} tenders loaded in the last {} days ({} notification_log emails); nothing is written or sent",
+        tenders.len(),
+        args.days,
+        log.len()
+    );
+    match &args.baseline {
+        Some(path) => println!("Baseline: {}", path.display()),
+        None => println!("Baseline: live defaults"),
+    }
+    let changes = new.changes_from(&baseline);
+    if changes.is_empty() {
+        println!("⚠️ {} changes nothing", args.config.display());
+    }
+    for change in &changes {
+        println!("  {}", change);
+    }
+    println!();
+    print!("{}", report(&before, &after));
+    Ok(())
+}
+
+pub fn report(before: &Totals, after: &Totals) -> String {
+    let mut out = format!(
+        "{:<30} {:>10} {:>10} {:>10}\n",
+        "", "baseline", "new", "delta"
+    );
+    let rows = [
+        (
+            "Filtered out at dataload",
+            before.filtered_out,
+            after.filtered_out,
+        ),
+        ("Skipped by ML", before.skipped_by_ml, after.skipped_by_ml),
+        ("Claude calls", before.claude_calls, after.claude_calls),
+        ("Notified tenders", before.notified, after.notified),
+        ("Repeat emails", before.repeat_emails, after.repeat_emails),
+        (
+            "Labelled bids missed",
+            before.missed_bids.len(),
+            after.missed_bids.len(),
+        ),
+    ];
+    for (label, before, after) in rows {
+        out.push_str(&format!(
+            "{:<30} {:>10} {:>10} {:>+10}\n",
+            label,
+            before,
+            after,
+            after as i64 - before as i64
+        ));
+    }
+
+    if !after.unknown.is_empty() {
+        out.push_str(&format!(
+            "\n❓ {} tenders would reach Claude but were never summarised, so whether they'd be notified is unknown: {}\n",
+            after.unknown.len(),
+            ids(&after.unknown)
+        ));
+    }
+    let newly_missed = difference(&after.missed_bids, &before.missed_bids);
+    if !newly_missed.is_empty() {
+        out.push_str(&format!(
+            "\n🚨 Bids that would no longer be notified: {}\n",
+            ids(&newly_missed)
+        ));
+    }
+    let recovered = difference(&before.missed_bids, &after.missed_bids);
+    if !recovered.is_empty() {
+        out.push_str(&format!(
+            "\n✅ Bids that would now be notified: {}\n",
+            ids(&recovered)
+        ));
+    }
+    out
+}
+
+fn difference(of: &[i64], without: &[i64]) -> Vec<i64> {
+    let without: HashSet<_> = without.iter().collect();
+    of.iter()
+        .filter(|id| !without.contains(id))
+        .copied()
+        .collect()
+}
+
+fn ids(ids: &[i64]) -> String {
+    let mut listed: Vec<String> = ids.iter().take(LISTED_IDS).map(i64::to_string).collect();
+    if ids.len() > LISTED_IDS {
+        listed.push(format!("and {} more", ids.len() - LISTED_IDS));
+    }
+    listed.join(", ")
+}
+
+async fn load_tenders(pool: &PgPool, days: i32) -> Result<Vec<Tender>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT resource_id,
+               status,
+               value::FLOAT8 AS value,
+               deadline,
+               (created_at AT TIME ZONE 'UTC') AS loaded_at,
+               ml_confidence::FLOAT8 AS ml_confidence,
+               bid
+        FROM tender_records
+        WHERE created_at >= NOW() - make_interval(days => $1)
+        ORDER BY resource_id
+        "#,
+    )
+    .bind(days)
+    .fetch_all(pool)
+    .await
+    .context("Failed to load tenders")?;
+
+    let mut tenders: Vec<Tender> = rows
+        .iter()
+        .map(|row| Tender {
+            resource_id: row.get("resource_id"),
+            status: row.get::<Option<String>, _>("status").unwrap_or_default(),
+            value: row.get("value"),
+            deadline: row.get("deadline"),
+            loaded_at: row.get("loaded_at"),
+            ml_confidence: row.get("ml_confidence"),
+            bid: row.get::<Option<i32>, _>("bid").map(|bid| bid == 1),
+            summaries: Vec::new(),
+        })
+        .collect();
+
+    let resource_ids: Vec<i64> = tenders.iter().map(|tender| tender.resource_id).collect();
+    let mut summaries = load_summaries(pool, &resource_ids).await?;
+    for tender in &mut tenders {
+        tender.summaries = summaries.remove(&tender.resource_id).unwrap_or_default();
+    }
+    Ok(tenders)
+}
+
+async fn load_summaries(
+    pool: &PgPool,
+    resource_ids: &[i64],
+) -> Result<HashMap<i64, Vec<AISummaryResult>>> {
+    let mut summaries: HashMap<i64, Vec<AISummaryResult>> = HashMap::new();
+    if !table_exists(pool, "ai_summaries").await? {
+        return Ok(summaries);
+    }
+    let rows = sqlx::query(
+        r#"
+        SELECT resource_id, tenant_id, summary_type, recommendation, processing_notes, created_at
+        FROM ai_summaries
+        WHERE resource_id = ANY($1)
+        "#,
+    )
+    .bind(resource_ids)
+    .fetch_all(pool)
+    .await
+    .context("Failed to load summaries")?;
+
+    for row in rows {
+        let summary = AISummaryResult {
+            resource_id: row.get("resource_id"),
+            tenant_id: row.get("tenant_id"),
+            summary_type: row.get("summary_type"),
+            ai_summary: String::new(),
+            one_liner: None,
+            key_points: Vec::new(),
+            recommendation: row.get("recommendation"),
+            confidence_assessment: String::new(),
+            processing_notes: serde_json::from_value(row.get("processing_notes"))
+                .unwrap_or_default(),
+            created_at: row.get("created_at"),
+            quality_score: None,
+        };
+        summaries
+            .entry(summary.resource_id)
+            .or_default()
+            .push(summary);
+    }
+    Ok(summaries)
+}
+
+async fn load_log(pool: &PgPool, days: i32) -> Result<Vec<Sent>> {
+    if !table_exists(pool, "notification_log").await? {
+        return Ok(Vec::new());
+    }
+    let rows = sqlx::query(
+        r#"
+        SELECT resource_id, tenant_id, sent_at, deadline, document_hash
+        FROM notification_log
+        WHERE sent_at >= NOW() - make_interval(days => $1)
+        ORDER BY resource_id, tenant_id, sent_at
+        "#,
+    )
+    .bind(days)
+    .fetch_all(pool)
+    .await
+    .context("Failed to load notification_log")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| Sent {
+            resource_id: row.get("resource_id"),
+            tenant_id: row.get("tenant_id"),
+            sent_at: row.get("sent_at"),
+            deadline: row.get("deadline"),
+            document_hash: row.get("document_hash"),
+        })
+        .collect())
+}
+
+/// Verdicts a newly summarised tender costs: one per active tenant, or the default tenant
+async fn active_tenants(pool: &PgPool) -> Result<usize> {
+    if !table_exists(pool, "tenants").await? {
+        return Ok(1);
+    }
+    let count: i64 = sqlx::query("SELECT COUNT(*) AS count FROM tenants WHERE active")
+        .fetch_one(pool)
+        .await?
+        .get("count");
+    Ok((count as usize).max(1))
+}
+
+async fn table_exists(pool: &PgPool, table: &str) -> Result<bool> {
+    Ok(sqlx::query("SELECT to_regclass($1) IS NOT NULL AS exists")
+        .bind(table)
+        .fetch_one(pool)
+        .await?
+        .get("exists"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone};
+
+    fn at(day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+    }
+
+    fn summary(resource_id: i64, recommendation: &str) -> AISummaryResult {
+        AISummaryResult {
+            resource_id,
+            tenant_id: "default".to_string(),
+            summary_type: "FULL_PDF".to_string(),
+            ai_summary: String::new(),
+            one_liner: None,
+            key_points: Vec::new(),
+            recommendation: recommendation.to_string(),
+            confidence_assessment: String::new(),
+            processing_notes: Vec::new(),
+            created_at: Utc.with_ymd_and_hms(2026, 10, 1, 9, 0, 0).unwrap(),
+            quality_score: None,
+        }
+    }
+
+    fn tender(resource_id: i64, ml_confidence: f64, recommendation: Option<&str>) -> Tender {
+        Tender {
+            resource_id,
+            status: "Open".to_string(),
+            value: Some(100_000.0),
+            deadline: Some(at(30)),
+            loaded_at: at(1),
+            ml_confidence: Some(ml_confidence),
+            bid: None,
+            summaries: recommendation
+                .map(|recommendation| vec![summary(resource_id, recommendation)])
+                .unwrap_or_default(),
+        }
+    }
+
+    #[test]
+    fn test_config_changes_routing_and_missed_bids() {
+        let tenders = vec![
+            tender(1, 0.30, Some("BID")),
+            Tender {
+                bid: Some(true),
+                ..tender(2, 0.02, Some("BID - strong fit"))
+            },
+            tender(3, 0.01, Some("NO BID")),
+            Tender {
+                value: Some(40_000.0),
+                ..tender(4, 0.40, None)
+            },
+            Tender {
+                deadline: Some(at(3)),
+                ..tender(5, 0.40, Some("BID"))
+            },
+        ];
+        let live = SimConfig::default();
+        let before = tally(&tenders, &[], &live, 2).unwrap();
+        assert_eq!(before.filtered_out, 1);
+        assert_eq!(before.claude_calls, 3 + 2);
+        assert_eq!(before.notified, 2);
+        assert_eq!(before.unknown, vec![4]);
+        assert!(before.missed_bids.is_empty());
+
+        let stricter = SimConfig {
+            min_value: 50_000,
+            min_days_to_deadline: 0,
+            claude_for_ml_no_bid: false,
+            ..SimConfig::default()
+        };
+        let after = tally(&tenders, &[], &stricter, 2).unwrap();
+        assert_eq!(after.filtered_out, 1);
+        assert_eq!(after.skipped_by_ml, 2);
+        assert_eq!(after.claude_calls, 2);
+        assert_eq!(after.notified, 2);
+        assert!(after.unknown.is_empty());
+        assert_eq!(after.missed_bids, vec![2]);
+
+        let report = report(&before, &after);
+        assert!(report.contains("Bids that would no longer be notified: 2"));
+        assert_eq!(
+            stricter.changes_from(&live),
+            vec![
+                "claude_for_ml_no_bid: true -> false",
+                "min_days_to_deadline: 3 -> 0",
+                "min_value: 25000 -> 50000",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repeat_emails_follow_window() {
+        let sent = |resource_id: i64, hours: i64, document: &str| Sent {
+            resource_id,
+            tenant_id: "default".to_string(),
+            sent_at: Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap() + Duration::hours(hours),
+            deadline: Some(at(30)),
+            document_hash: Some(document.to_string()),
+        };
+        let log = vec![
+            sent(1, 0, "a"),
+            sent(1, 24, "a"),
+            sent(1, 48, "b"),
+            sent(1, 100, "b"),
+            sent(2, 10, "a"),
+        ];
+        assert_eq!(repeat_emails(&log, None), 3);
+        assert_eq!(repeat_emails(&log, Some(Duration::hours(72))), 1);
+        assert_eq!(repeat_emails(&log, Some(Duration::hours(12))), 3);
+        assert_eq!(repeat_emails(&log, Some(Duration::hours(200))), 1);
+    }
+
+    #[test]
+    fn test_config_rejects_unknown_fields_and_statuses() {
+        let config: SimConfig = serde_json::from_str(r#"{"ml_threshold": 0.1}"#).unwrap();
+        assert_eq!(config.min_value, 25_000);
+        assert!(serde_json::from_str::<SimConfig>(r#"{"ml_treshold": 0.1}"#).is_err());
+        let typo = SimConfig {
+            pipeline_statuses: vec!["opne".to_string()],
+            ..SimConfig::default()
+        };
+        assert!(typo.statuses().is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use clap::Subcommand;
+use sqlx::PgPool;
+
+#[derive(Subcommand)]
+pub enum TagsCommand {
+    /// Every tag in use with its tender count, or one tender's tags
+    List {
+        #[arg(long)]
+        resource_id: Option<i64>,
+    },
+    /// Tag a tender (tags are lowercased; quote multi-word tags)
+    Add {
+        resource_id: i64,
+        #[arg(required = true)]
+        tags: Vec<String>,
+        /// Recorded as the tag's author
+        #[arg(long, env = "USER", default_value = "ops_cli")]
+        by: String,
+    },
+    /// Remove tags from a tender
+    Remove {
+        resource_id: i64,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Print the controlled vocabulary
+    Vocabulary,
+}
+
+pub async fn run(pool: &PgPool, command: TagsCommand) -> Result<()> {
+    tender_tags::ensure_table(pool).await?;
+
+    match command {
+        TagsCommand::List {
+            resource_id: Some(resource_id),
+        } => {
+            let tags = tender_tags::for_tender(pool, resource_id).await?;
+            for tag in &tags {
+                println!("{}", tag);
+            }
+            println!("{} tags on tender {}", tags.len(), resource_id);
+        }
+        TagsCommand::List { resource_id: None } => {
+            let counts = tender_tags::counts(pool).await?;
+            for (tag, tenders) in &counts {
+                println!(
+                    "{}\t{}{}",
+                    tag,
+                    tenders,
+                    if tender_tags::is_controlled(tag) {
+                        ""
+                    } else {
+                        "\t(free-form)"
+                    }
+                );
+            }
+            println!("{} tags", counts.len());
+        }
+        TagsCommand::Add {
+            resource_id,
+            tags,
+            by,
+        } => {
+            for tag in &tags {
+                let tag = tender_tags::normalize(tag)?;
+                if tender_tags::add(pool, resource_id, &tag, &by).await? {
+                    println!("✅ Tagged tender {} '{}'", resource_id, tag);
+                } else {
+                    println!("Tender {} already tagged '{}'", resource_id, tag);
+                }
+            }
+        }
+        TagsCommand::Remove { resource_id, tags } => {
+            for tag in &tags {
+                let tag = tender_tags::normalize(tag)?;
+                if tender_tags::remove(pool, resource_id, &tag).await? {
+                    println!("✅ Removed '{}' from tender {}", tag, resource_id);
+                } else {
+                    println!("Tender {} was not tagged '{}'", resource_id, tag);
+                }
+            }
+        }
+        TagsCommand::Vocabulary => {
+            for tag in tender_tags::CONTROLLED_TAGS {
+                println!("{}", tag);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,229 @@
+//! Follow one tender through the pipeline as it happens.
+//!
+//! Prints the tender's stage status changes (`pipeline_status`), decisions
+//! (`decision_audit` and its archive, which is where a notification that was or wasn't
+//! sent shows up), lifecycle transitions and webhook deliveries as one timeline, oldest
+//! first, then polls for new rows. Polling rather than LISTEN/NOTIFY keeps the lambdas
+//! unchanged and works against a read replica. `pipeline_status` rows are updated in place,
+//! so only the latest state of each stage is seen, not every intermediate one between polls.
+
+use anyhow::{Result, bail};
+use clap::Args;
+use sqlx::{PgPool, Row};
+use std::collections::HashSet;
+use std::time::Duration;
+
+#[derive(Args)]
+pub struct TailArgs {
+    resource_id: i64,
+    /// Seconds between polls
+    #[arg(long, default_value_t = 2)]
+    interval: u64,
+    /// Print the history and exit instead of following
+    #[arg(long)]
+    once: bool,
+}
+
+/// Per table, the tender's rows at or after `$2` (epoch seconds) as (epoch, at, key, line);
+/// tables that don't exist yet are skipped
+const SOURCES: &[(&str, &str)] = &[
+    (
+        "pipeline_status",
+        r#"
+        SELECT EXTRACT(EPOCH FROM updated_at)::FLOAT8 AS epoch,
+               to_char(updated_at AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') AS at,
+               stage || '/' || status || '/' || attempts || '/' || requeues AS key,
+               format('%s %s (attempt %s%s)%s', stage, status, attempts,
+                      CASE WHEN requeues > 0 THEN format(', requeued %s', requeues) ELSE '' END,
+                      COALESCE(': ' || last_error, '')) AS line
+        FROM pipeline_status
+        WHERE resource_id = $1 AND updated_at >= to_timestamp($2)
+        "#,
+    ),
+    (
+        "decision_audit",
+        r#"
+        SELECT EXTRACT(EPOCH FROM decided_at)::FLOAT8 AS epoch,
+               to_char(decided_at AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') AS at,
+               id::TEXT AS key,
+               format('%s decision by %s: %s%s', kind, component, outcome,
+                      CASE WHEN detail = 'null'::JSONB THEN '' ELSE ' ' || detail::TEXT END) AS line
+        FROM decision_audit
+        WHERE resource_id = $1 AND decided_at >= to_timestamp($2)
+        "#,
+    ),
+    (
+        // Entries data_archive moved out of decision_audit, for a tender followed from its start
+        "decision_audit_archive",
+        r#"
+        SELECT EXTRACT(EPOCH FROM decided_at)::FLOAT8 AS epoch,
+               to_char(decided_at AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') AS at,
+               id::TEXT AS key,
+               format('%s decision by %s: %s%s', kind, component, outcome,
+                      CASE WHEN detail = 'null'::JSONB THEN '' ELSE ' ' || detail::TEXT END) AS line
+        FROM decision_audit_archive
+        WHERE resource_id = $1 AND decided_at >= to_timestamp($2)
+        "#,
+    ),
+    (
+        "tender_lifecycle_transitions",
+        r#"
+        SELECT EXTRACT(EPOCH FROM recorded_at)::FLOAT8 AS epoch,
+               to_char(recorded_at AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') AS at,
+               id::TEXT AS key,
+               format('lifecycle %s -> %s by %s (%s)', COALESCE(from_state, '-'), to_state,
+                      actor, outcome) AS line
+        FROM tender_lifecycle_transitions
+        WHERE resource_id = $1 AND recorded_at >= to_timestamp($2)
+        "#,
+    ),
+    (
+        "webhook_deliveries",
+        r#"
+        SELECT EXTRACT(EPOCH FROM delivered_at)::FLOAT8 AS epoch,
+               to_char(delivered_at AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') AS at,
+               id::TEXT AS key,
+               format('webhook %s to #%s attempt %s: %s', event_type, webhook_id, attempt,
+                      CASE WHEN success THEN format('delivered (%s)', status_code)
+                           ELSE format('failed (%s)', COALESCE(error, status_code::TEXT, 'no response')) END) AS line
+        FROM webhook_deliveries
+        WHERE resource_id = $1 AND delivered_at >= to_timestamp($2)
+        "#,
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+struct Event {
+    epoch: f64,
+    /// UTC, for display
+    at: String,
+    source: &'static str,
+    key: String,
+    line: String,
+}
+
+/// Events not printed yet, oldest first. Each poll starts at (not after) the newest time
+/// seen so rows sharing that timestamp aren't missed; `seen` drops the repeats
+fn unseen(seen: &mut HashSet<(&'static str, String)>, mut events: Vec<Event>) -> Vec<Event> {
+    events.retain(|event| seen.insert((event.source, event.key.clone())));
+    events.sort_by(|a, b| a.epoch.total_cmp(&b.epoch));
+    events
+}
+
+async fn existing_sources(pool: &PgPool) -> Result<Vec<(&'static str, &'static str)>> {
+    let mut sources = Vec::new();
+    for (table, query) in SOURCES {
+        let exists: bool = sqlx::query("SELECT to_regclass($1) IS NOT NULL AS exists")
+            .bind(*table)
+            .fetch_one(pool)
+            .await?
+            .get("exists");
+        if exists {
+            sources.push((*table, *query));
+        }
+    }
+    Ok(sources)
+}
+
+async fn poll(
+    pool: &PgPool,
+    sources: &[(&'static str, &'static str)],
+    resource_id: i64,
+    since: f64,
+) -> Result<Vec<Event>> {
+    let mut events = Vec::new();
+    for (table, query) in sources {
+        let rows = sqlx::query(query)
+            .bind(resource_id)
+            .bind(since)
+            .fetch_all(pool)
+            .await?;
+        events.extend(rows.iter().map(|row| Event {
+            epoch: row.get("epoch"),
+            at: row.get("at"),
+            source: table,
+            key: row.get("key"),
+            line: row.get("line"),
+        }));
+    }
+    Ok(events)
+}
+
+pub async fn run(pool: &PgPool, args: TailArgs) -> Result<()> {
+    let tender = sqlx::query(
+        "SELECT title, ca, deadline::TEXT AS deadline FROM tender_records WHERE resource_id = $1",
+    )
+    .bind(args.resource_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some(tender) = tender else {
+        bail!("Tender {} not found", args.resource_id);
+    };
+    println!(
+        "{} {} ({}), deadline {}",
+        args.resource_id,
+        tender.get::<String, _>("title"),
+        tender.get::<String, _>("ca"),
+        tender
+            .get::<Option<String>, _>("deadline")
+            .unwrap_or_else(|| "unknown".to_string())
+    );
+
+    let sources = existing_sources(pool).await?;
+    let mut seen = HashSet::new();
+    let mut since = 0.0;
+    loop {
+        let events = unseen(
+            &mut seen,
+            poll(pool, &sources, args.resource_id, since).await?,
+        );
+        for event in &events {
+            println!("{}\t{}", event.at, event.line);
+        }
+        since = events.iter().map(|e| e.epoch).fold(since, f64::max);
+
+        if args.once {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(args.interval.max(1))).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(epoch: f64, source: &'static str, key: &str) -> Event {
+        Event {
+            epoch,
+            at: String::new(),
+            source,
+            key: key.to_string(),
+            line: format!("{} {}", source, key),
+        }
+    }
+
+    #[test]
+    fn test_unseen_orders_and_drops_repeats() {
+        let mut seen = HashSet::new();
+        let first = unseen(
+            &mut seen,
+            vec![
+                event(20.0, "decision_audit", "7"),
+                event(10.0, "pipeline_status", "ml_prediction/completed/1/0"),
+            ],
+        );
+        assert_eq!(first[0].source, "pipeline_status");
+        assert_eq!(first[1].source, "decision_audit");
+
+        // The next poll starts at the newest time seen, so it returns id 7 again
+        let second = unseen(
+            &mut seen,
+            vec![
+                event(20.0, "decision_audit", "7"),
+                event(20.0, "decision_audit", "8"),
+            ],
+        );
+        assert_eq!(second, vec![event(20.0, "decision_audit", "8")]);
+    }
+}
@@ -0,0 +1,393 @@
+//! Outbound HTTP for lambdas that fetch URLs they didn't choose.
+//!
+//! `pdf_url` arrives in a queue message, so a bad record could point a lambda at an
+//! internal address. `HttpClient` wraps reqwest and only fetches from the hosts in
+//! `OUTBOUND_ALLOWED_HOSTS` (comma separated, default `etenders.gov.ie`). An entry
+//! allows the host and its subdomains; IP addresses are only allowed when listed as is.
+//! Redirects are checked the same way, hop by hop.
+//!
+//! `get_with_retry` retries timeouts, connection failures, 429s and 5xx responses
+//! through a `retry::Policy`; a refused URL is never retried.
+//!
+//! Every request prints a CloudWatch embedded metric format line with its method, host,
+//! status and duration, which CloudWatch Logs turns into the `OutboundRequestDuration`,
+//! `OutboundRequestErrors` and `OutboundRequestBlocked` metrics per host.
+
+use reqwest::{redirect, Method, Response, StatusCode, Url};
+use retry::Policy;
+use serde_json::{json, Value};
+use std::fmt;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+pub const ALLOWED_HOSTS_VAR: &str = "OUTBOUND_ALLOWED_HOSTS";
+pub const DEFAULT_ALLOWED_HOSTS: &[&str] = &["etenders.gov.ie"];
+
+const METRIC_NAMESPACE: &str = "TenderPipeline";
+const MAX_REDIRECTS: usize = 10;
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidUrl(String),
+    /// The URL, or a redirect it led to, is not on the allowlist
+    NotAllowed(String),
+    Request(reqwest::Error),
+    /// A 429 or 5xx response that was still failing when `get_with_retry` gave up
+    Status(StatusCode),
+}
+
+impl Error {
+    /// The request was refused before (or while) being made; retrying won't help
+    pub fn is_blocked(&self) -> bool {
+        matches!(self, Error::InvalidUrl(_) | Error::NotAllowed(_))
+    }
+
+    /// Worth another try: a timeout, a failed connection or a transient status
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Request(e) => e.is_timeout() || e.is_connect(),
+            Error::Status(_) => true,
+            Error::InvalidUrl(_) | Error::NotAllowed(_) => false,
+        }
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidUrl(url) => write!(f, "invalid URL '{}'", url),
+            Error::NotAllowed(host) => {
+                write!(f, "host '{}' is not on the outbound allowlist", host)
+            }
+            Error::Request(e) => write!(f, "{}", e),
+            Error::Status(status) => write!(f, "HTTP {}", status),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Request(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Allowlist {
+    hosts: Vec<String>,
+}
+
+impl Allowlist {
+    pub fn new<S: AsRef<str>>(hosts: &[S]) -> Self {
+        Self {
+            hosts: hosts
+                .iter()
+                .map(|host| host.as_ref().trim().trim_start_matches('.').to_lowercase())
+                .filter(|host| !host.is_empty())
+                .collect(),
+        }
+    }
+
+    /// `OUTBOUND_ALLOWED_HOSTS`, or `DEFAULT_ALLOWED_HOSTS` when unset or empty
+    pub fn from_env() -> Self {
+        let configured: Vec<String> = std::env::var(ALLOWED_HOSTS_VAR)
+            .unwrap_or_default()
+            .split(',')
+            .map(|host| host.trim().to_string())
+            .filter(|host| !host.is_empty())
+            .collect();
+        if configured.is_empty() {
+            Self::new(DEFAULT_ALLOWED_HOSTS)
+        } else {
+            Self::new(&configured)
+        }
+    }
+
+    /// An http(s) URL whose host is listed, or is a subdomain of a listed domain
+    pub fn allows(&self, url: &Url) -> bool {
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_lowercase();
+        if host.parse::<IpAddr>().is_ok() {
+            return self.hosts.contains(&host);
+        }
+        self.hosts
+            .iter()
+            .any(|allowed| host == *allowed || host.ends_with(&format!(".{}", allowed)))
+    }
+
+    pub fn check(&self, url: &str) -> Result<Url, Error> {
+        let parsed = Url::parse(url).map_err(|_| Error::InvalidUrl(url.to_string()))?;
+        if self.allows(&parsed) {
+            Ok(parsed)
+        } else {
+            Err(Error::NotAllowed(host_of(&parsed)))
+        }
+    }
+}
+
+fn host_of(url: &Url) -> String {
+    url.host_str().unwrap_or("<none>").to_string()
+}
+
+/// CloudWatch embedded metric format line for one request. `status` is None when no
+/// response came back; a blocked request has neither status nor duration
+pub fn metric_line(
+    environment: &str,
+    method: &str,
+    host: &str,
+    status: Option<u16>,
+    duration: Option<Duration>,
+) -> Value {
+    let blocked = duration.is_none();
+    let failed = !blocked && status.is_none_or(|status| status >= 400);
+    let mut metrics = vec![
+        json!({ "Name": "OutboundRequestErrors", "Unit": "Count" }),
+        json!({ "Name": "OutboundRequestBlocked", "Unit": "Count" }),
+    ];
+    let mut line = json!({
+        "Environment": environment,
+        "Host": host,
+        "Method": method,
+        "Status": status,
+        "OutboundRequestErrors": failed as u8,
+        "OutboundRequestBlocked": blocked as u8,
+    });
+    if let Some(duration) = duration {
+        metrics.push(json!({ "Name": "OutboundRequestDuration", "Unit": "Milliseconds" }));
+        line["OutboundRequestDuration"] = json!(duration.as_millis() as u64);
+    }
+    line["_aws"] = json!({
+        "Timestamp": chrono::Utc::now().timestamp_millis(),
+        "CloudWatchMetrics": [{
+            "Namespace": METRIC_NAMESPACE,
+            "Dimensions": [["Environment", "Host"]],
+            "Metrics": metrics
+        }]
+    });
+    line
+}
+
+fn emit(method: &Method, host: &str, status: Option<u16>, duration: Option<Duration>) {
+    let environment = environment::Environment::from_env();
+    println!(
+        "{}",
+        metric_line(environment.name(), method.as_str(), host, status, duration)
+    );
+}
+
+/// The host of a redirect the policy refused, found in the error's source chain
+fn refused_redirect(e: &reqwest::Error) -> Option<String> {
+    let mut source = std::error::Error::source(e);
+    while let Some(error) = source {
+        if let Some(Error::NotAllowed(host)) = error.downcast_ref::<Error>() {
+            return Some(host.clone());
+        }
+        source = error.source();
+    }
+    None
+}
+
+/// reqwest client that only talks to allowlisted hosts and reports every request
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    allowlist: Allowlist,
+}
+
+impl HttpClient {
+    /// Client for `Allowlist::from_env`, with an optional overall request timeout
+    pub fn new(timeout: Option<Duration>) -> Result<Self, Error> {
+        Self::with_allowlist(Allowlist::from_env(), timeout)
+    }
+
+    pub fn with_allowlist(allowlist: Allowlist, timeout: Option<Duration>) -> Result<Self, Error> {
+        let redirects = allowlist.clone();
+        let policy = redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if redirects.allows(attempt.url()) {
+                attempt.follow()
+            } else {
+                let error = Error::NotAllowed(host_of(attempt.url()));
+                attempt.error(error)
+            }
+        });
+
+        let mut builder = reqwest::Client::builder().redirect(policy);
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        Ok(Self {
+            client: builder.build()?,
+            allowlist,
+        })
+    }
+
+    pub fn allowlist(&self) -> &Allowlist {
+        &self.allowlist
+    }
+
+    pub async fn get(&self, url: &str) -> Result<Response, Error> {
+        self.send(Method::GET, url, &[]).await
+    }
+
+    /// GET with up to 3 tries for transient failures; `operation` names it in the retry
+    /// metrics. Other error statuses come back as responses, as with `get`
+    pub async fn get_with_retry(
+        &self,
+        url: &str,
+        operation: &'static str,
+    ) -> Result<Response, Error> {
+        Policy::new(operation)
+            .base_delay(Duration::from_millis(500))
+            .retry_if(Error::is_transient)
+            .run(|| async {
+                let response = self.get(url).await?;
+                if is_transient_status(response.status()) {
+                    return Err(Error::Status(response.status()));
+                }
+                Ok(response)
+            })
+            .await
+    }
+
+    /// Send a request with the given headers, if the URL is allowed
+    pub async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<Response, Error> {
+        let url = match self.allowlist.check(url) {
+            Ok(url) => url,
+            Err(e) => {
+                warn!("🚫 Refused outbound {} {}: {}", method, url, e);
+                if let Error::NotAllowed(host) = &e {
+                    emit(&method, host, None, None);
+                }
+                return Err(e);
+            }
+        };
+        let host = host_of(&url);
+
+        let mut request = self.client.request(method.clone(), url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        let started = Instant::now();
+        let result = request.send().await;
+        let elapsed = started.elapsed();
+        match result {
+            Ok(response) => {
+                emit(
+                    &method,
+                    &host,
+                    Some(response.status().as_u16()),
+                    Some(elapsed),
+                );
+                Ok(response)
+            }
+            Err(e) if refused_redirect(&e).is_some() => {
+                let target = refused_redirect(&e).unwrap_or_default();
+                warn!(
+                    "🚫 Refused outbound {} redirect from {} to {}",
+                    method, host, target
+                );
+                emit(&method, &target, None, None);
+                Err(Error::NotAllowed(target))
+            }
+            Err(e) => {
+                emit(&method, &host, None, Some(elapsed));
+                Err(Error::Request(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let allowlist = Allowlist::new(&["etenders.gov.ie", ".example.com", "10.0.0.5"]);
+
+        assert!(allowlist.allows(&url(
+            "https://www.etenders.gov.ie/epps/cft/downloadNoticeForAdvSearch.do?resourceId=1"
+        )));
+        assert!(allowlist.allows(&url("https://ETENDERS.gov.ie/")));
+        assert!(allowlist.allows(&url("http://files.example.com/a.pdf")));
+        assert!(allowlist.allows(&url("http://10.0.0.5/")));
+
+        // Look-alike hosts, other addresses and other schemes
+        assert!(!allowlist.allows(&url("https://etenders.gov.ie.attacker.net/")));
+        assert!(!allowlist.allows(&url("https://notetenders.gov.ie/")));
+        assert!(!allowlist.allows(&url("http://169.254.169.254/latest/meta-data/")));
+        assert!(!allowlist.allows(&url("http://[::1]/")));
+        assert!(!allowlist.allows(&url("file:///etc/passwd")));
+
+        assert!(matches!(
+            allowlist.check("http://localhost:8080/"),
+            Err(Error::NotAllowed(host)) if host == "localhost"
+        ));
+        assert!(matches!(
+            allowlist.check("not a url"),
+            Err(Error::InvalidUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_transient_statuses() {
+        assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(!is_transient_status(StatusCode::NOT_FOUND));
+        assert!(Error::Status(StatusCode::SERVICE_UNAVAILABLE).is_transient());
+        assert!(!Error::NotAllowed("localhost".to_string()).is_transient());
+    }
+
+    #[test]
+    fn test_metric_line() {
+        let line = metric_line(
+            "prod",
+            "GET",
+            "www.etenders.gov.ie",
+            Some(503),
+            Some(Duration::from_millis(1250)),
+        );
+        assert_eq!(line["OutboundRequestDuration"], 1250);
+        assert_eq!(line["OutboundRequestErrors"], 1);
+        assert_eq!(line["OutboundRequestBlocked"], 0);
+        assert_eq!(line["Status"], 503);
+        assert_eq!(
+            line["_aws"]["CloudWatchMetrics"][0]["Metrics"]
+                .as_array()
+                .unwrap()
+                .len(),
+            3
+        );
+
+        let blocked = metric_line("prod", "GET", "169.254.169.254", None, None);
+        assert_eq!(blocked["OutboundRequestBlocked"], 1);
+        assert_eq!(blocked["OutboundRequestErrors"], 0);
+        assert!(blocked.get("OutboundRequestDuration").is_none());
+    }
+}
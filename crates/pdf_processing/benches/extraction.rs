@@ -0,0 +1,153 @@
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use lopdf::content::{Content, Operation};
+use lopdf::{Document, Object, Stream, dictionary};
+use pdf_processing::{
+    CodeMatcher, ExtractionBudget, extract_codes, extract_text_from_pdf, extract_text_streaming,
+};
+
+const LINES_PER_PAGE: usize = 50;
+
+/// Build an n-page text PDF in memory so fixture sizes don't depend on files in the repo
+fn generate_pdf(pages: usize) -> Vec<u8> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Courier",
+    });
+    let resources_id = doc.add_object(dictionary! {
+        "Font" => dictionary! { "F1" => font_id },
+    });
+
+    let mut kids = Vec::with_capacity(pages);
+    for page in 0..pages {
+        let mut operations = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 9.into()]),
+            Operation::new("Td", vec![40.into(), 800.into()]),
+        ];
+        for line in 0..LINES_PER_PAGE {
+            operations.push(Operation::new(
+                "Tj",
+                vec![Object::string_literal(format!(
+                    "Page {} line {}: software development services 72000000 and support 7226{:04}",
+                    page, line, line
+                ))],
+            ));
+            operations.push(Operation::new("Td", vec![0.into(), (-14).into()]));
+        }
+        operations.push(Operation::new("ET", vec![]));
+
+        let content = Content { operations };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        });
+        kids.push(page_id.into());
+    }
+
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => pages as i64,
+            "Resources" => resources_id,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+
+    let mut buffer = Vec::new();
+    doc.save_to(&mut buffer).unwrap();
+    buffer
+}
+
+/// 10k distinct 8-digit CPV-style codes, a few of which appear in the generated text
+fn code_list() -> Vec<String> {
+    (0..10_000).map(|i| format!("7{:07}", i * 97)).collect()
+}
+
+fn bench_extract_text(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extract_text_from_pdf");
+    group.sample_size(10);
+
+    for pages in [1, 10, 100] {
+        let pdf = generate_pdf(pages);
+        group.throughput(Throughput::Bytes(pdf.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(pages), &pdf, |b, pdf| {
+            b.iter(|| extract_text_from_pdf(black_box(pdf)).unwrap())
+        });
+    }
+
+    // The real tender notice checked into the crate (present after the extraction test has run)
+    if let Ok(pdf) = std::fs::read("test.pdf") {
+        group.throughput(Throughput::Bytes(pdf.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter("test.pdf"), &pdf, |b, pdf| {
+            b.iter(|| extract_text_from_pdf(black_box(pdf)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_extract_text_streaming(c: &mut Criterion) {
+    let matcher = CodeMatcher::new(&code_list());
+    let budget = ExtractionBudget::default();
+    let mut group = c.benchmark_group("extract_text_streaming");
+    group.sample_size(10);
+
+    for pages in [1, 10, 100] {
+        let pdf = generate_pdf(pages);
+        group.throughput(Throughput::Bytes(pdf.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(pages), &pdf, |b, pdf| {
+            b.iter(|| extract_text_streaming(black_box(pdf), &matcher, &budget).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_extract_codes(c: &mut Criterion) {
+    let codes = code_list();
+    let matcher = CodeMatcher::new(&codes);
+    let mut group = c.benchmark_group("extract_codes/10k_codes");
+
+    for pages in [1, 10, 100] {
+        let text = extract_text_from_pdf(&generate_pdf(pages)).unwrap();
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        // Includes building the automaton, as one-off callers pay for it
+        group.bench_with_input(
+            BenchmarkId::new("extract_codes", pages),
+            &text,
+            |b, text| b.iter(|| extract_codes(black_box(text), &codes)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("prebuilt_matcher", pages),
+            &text,
+            |b, text| b.iter(|| matcher.find_codes(black_box(text))),
+        );
+    }
+    group.finish();
+}
+
+fn bench_code_matcher_build(c: &mut Criterion) {
+    let codes = code_list();
+    c.bench_function("code_matcher_new/10k_codes", |b| {
+        b.iter(|| CodeMatcher::new(black_box(&codes)))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_extract_text,
+    bench_extract_text_streaming,
+    bench_extract_codes,
+    bench_code_matcher_build
+);
+criterion_main!(benches);
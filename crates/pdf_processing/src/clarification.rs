@@ -0,0 +1,211 @@
+//! The deadline for clarification questions stated in a tender document.
+//!
+//! Tenders usually stop taking clarification questions some days before submissions
+//! close. The deadline is the first date that follows a mention of clarifications (or
+//! questions/queries with a deadline cue such as "no later than") within a short window.
+//! Dates are read day first, as Irish documents write them: "14/03/2025", "14.03.2025",
+//! "14th March 2025" or "14 Mar 2025", optionally followed by a time ("12:00", "3pm",
+//! "12 noon"). Like portal deadlines, the result is Irish local time; without a stated
+//! time it is 12:00 on the day.
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+/// Bytes after a trigger phrase searched for the date
+const WINDOW: usize = 250;
+/// Bytes after the date searched for its time
+const TIME_WINDOW: usize = 40;
+
+/// Always about clarifications
+const TRIGGERS: &[&str] = &["clarification"];
+/// About clarifications only when a deadline cue comes before the date
+const CUED_TRIGGERS: &[&str] = &["questions", "queries"];
+const CUES: &[&str] = &[
+    "deadline",
+    "no later than",
+    "not later than",
+    "latest",
+    "closing",
+    "submitted by",
+    "received by",
+];
+
+const MONTHS: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// The clarification question deadline, if the text states one
+pub fn clarification_deadline(text: &str) -> Option<NaiveDateTime> {
+    // ASCII lowercasing keeps byte offsets, so windows can be taken from either string
+    let lower = text.to_ascii_lowercase();
+    let mut mentions: Vec<(usize, bool)> = TRIGGERS
+        .iter()
+        .map(|trigger| (trigger, false))
+        .chain(CUED_TRIGGERS.iter().map(|trigger| (trigger, true)))
+        .flat_map(|(trigger, cued)| {
+            lower
+                .match_indices(trigger)
+                .map(move |(at, _)| (at + trigger.len(), cued))
+        })
+        .collect();
+    mentions.sort();
+
+    mentions.into_iter().find_map(|(start, cued)| {
+        let after = window(&lower, start, WINDOW);
+        let (date_at, date_end, date) = find_date(after)?;
+        if cued && !CUES.iter().any(|cue| after[..date_at].contains(cue)) {
+            return None;
+        }
+        let time = find_time(window(after, date_end, TIME_WINDOW))
+            .unwrap_or(NaiveTime::from_hms_opt(12, 0, 0)?);
+        Some(date.and_time(time))
+    })
+}
+
+/// Up to `len` bytes of `text` from `start`, cut back to a character boundary
+fn window(text: &str, start: usize, len: usize) -> &str {
+    let mut end = (start + len).min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[start..end]
+}
+
+/// Words of the text with their byte ranges, split on whitespace and commas
+fn words(text: &str) -> Vec<(usize, usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        let separator = c.is_whitespace() || c == ',';
+        match (start, separator) {
+            (None, false) => start = Some(i),
+            (Some(s), true) => {
+                words.push((s, i, &text[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, text.len(), &text[s..]));
+    }
+    words
+}
+
+/// The first date in the text, with where it starts and ends
+fn find_date(text: &str) -> Option<(usize, usize, NaiveDate)> {
+    let words = words(text);
+    for (i, &(start, end, word)) in words.iter().enumerate() {
+        if let Some(date) = numeric_date(word.trim_matches(['(', ')', '.', ';'])) {
+            return Some((start, end, date));
+        }
+
+        // "14th March 2025", "14 Mar 2025", "14th of March 2025"
+        let Some(day) = day_number(word.trim_start_matches('(')) else {
+            continue;
+        };
+        let mut rest = words[i + 1..].iter();
+        let mut month_word = rest.next()?;
+        if month_word.2 == "of" {
+            month_word = rest.next()?;
+        }
+        let Some(month) = month_number(month_word.2) else {
+            continue;
+        };
+        let Some(&(_, year_end, year_word)) = rest.next() else {
+            continue;
+        };
+        let Ok(year) = year_word.trim_end_matches(['.', ')', ';']).parse::<i32>() else {
+            continue;
+        };
+        if let Some(date) = plausible(year, month, day) {
+            return Some((start, year_end, date));
+        }
+    }
+    None
+}
+
+/// "14/03/2025", "14-03-2025" or "14.03.2025"
+fn numeric_date(word: &str) -> Option<NaiveDate> {
+    let parts: Vec<&str> = word.split(['/', '-', '.']).collect();
+    let [day, month, year] = parts.as_slice() else {
+        return None;
+    };
+    if year.len() != 4 {
+        return None;
+    }
+    plausible(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?)
+}
+
+/// "14", "14th", "1st"
+fn day_number(word: &str) -> Option<u32> {
+    let digits = word
+        .strip_suffix("st")
+        .or_else(|| word.strip_suffix("nd"))
+        .or_else(|| word.strip_suffix("rd"))
+        .or_else(|| word.strip_suffix("th"))
+        .unwrap_or(word);
+    if digits.is_empty() || digits.len() > 2 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// "march", "mar" or "mar."
+fn month_number(word: &str) -> Option<u32> {
+    let word = word.trim_end_matches('.');
+    if word.len() < 3 {
+        return None;
+    }
+    let position = MONTHS.iter().position(|month| word.starts_with(month))?;
+    Some(position as u32 + 1)
+}
+
+fn plausible(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    if !(2000..2100).contains(&year) {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+/// A time of day near the start of the text: "12:00", "15.30", "3pm", "3:30 p.m." or "noon"
+fn find_time(text: &str) -> Option<NaiveTime> {
+    let words = words(text);
+    for (i, &(_, _, word)) in words.iter().enumerate() {
+        if word.trim_matches(['(', ')', '.']) == "noon" {
+            return NaiveTime::from_hms_opt(12, 0, 0);
+        }
+        let word = word
+            .trim_start_matches(['(', '@'])
+            .trim_end_matches([')', ';']);
+        let (clock, suffix) = match word.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(at) => (&word[..at], &word[at..]),
+            None => (word, words.get(i + 1).map_or("", |next| next.2)),
+        };
+        let pm = matches!(suffix.trim_end_matches('.'), "pm" | "p.m");
+        let am = matches!(suffix.trim_end_matches('.'), "am" | "a.m");
+
+        let mut parts = clock.trim_end_matches('.').splitn(2, [':', '.']);
+        let Ok(hour) = parts.next().unwrap_or_default().parse::<u32>() else {
+            continue;
+        };
+        let minute = match parts.next() {
+            Some(minute) if minute.len() == 2 => minute.parse::<u32>().ok(),
+            Some(_) => None,
+            // A bare number is only a time with am/pm after it
+            None if am || pm => Some(0),
+            None => None,
+        };
+        let Some(minute) = minute else {
+            continue;
+        };
+        let hour = match (am, pm) {
+            (_, true) if hour < 12 => hour + 12,
+            (true, _) if hour == 12 => 0,
+            _ => hour,
+        };
+        if let Some(time) = NaiveTime::from_hms_opt(hour, minute, 0) {
+            return Some(time);
+        }
+    }
+    None
+}
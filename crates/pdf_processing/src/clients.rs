@@ -0,0 +1,114 @@
+//! Clients shared by every invocation a warm container serves.
+//!
+//! Connecting to Postgres, building the HTTP client and loading the AWS config took a few
+//! hundred milliseconds per message when each invocation made its own. They are now set up
+//! by the first invocation that needs them and kept until the container is recycled.
+//!
+//! The AWS clients and the HTTP client hold no state that goes bad, so they're built once.
+//! The database pool can: the database may restart, or drop connections while the container
+//! is frozen between invocations. `db_pool` checks it with a quick query at most once per
+//! `HEALTH_CHECK_INTERVAL` and replaces a pool that fails the check.
+
+use aws_sdk_s3::Client as S3Client;
+use aws_sdk_sqs::Client as SqsClient;
+use db::PoolSettings;
+use outbound_http::HttpClient;
+use queue::Publisher;
+use resource_discovery::ResourceDiscovery;
+use sqlx::PgPool;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, OnceCell};
+
+/// Whole-request limit for PDF downloads
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+/// A pool used more recently than this is trusted without a check
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// How long the check query may take before the pool is written off
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The clients built from the container's AWS config
+pub struct Aws {
+    pub sqs: SqsClient,
+    pub s3: S3Client,
+    /// Caches resolved queue URLs and bucket names for the life of the container
+    pub discovery: ResourceDiscovery,
+    pub publisher: Publisher,
+}
+
+struct CheckedPool {
+    pool: PgPool,
+    last_used: Instant,
+}
+
+static AWS: OnceCell<Aws> = OnceCell::const_new();
+static HTTP: OnceCell<HttpClient> = OnceCell::const_new();
+static POOL: Mutex<Option<CheckedPool>> = Mutex::const_new(None);
+
+/// The container's AWS clients, loading the config on first use
+pub async fn aws() -> &'static Aws {
+    AWS.get_or_init(|| async {
+        println!("Loading AWS config (once per container)");
+        let config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .load()
+            .await;
+        Aws {
+            sqs: SqsClient::new(&config),
+            s3: S3Client::new(&config),
+            discovery: ResourceDiscovery::new(&config),
+            publisher: Publisher::new(&config),
+        }
+    })
+    .await
+}
+
+/// The container's HTTP client; it only fetches from OUTBOUND_ALLOWED_HOSTS
+pub async fn http() -> Result<&'static HttpClient, outbound_http::Error> {
+    HTTP.get_or_try_init(|| async {
+        println!("Creating HTTP client (once per container)");
+        HttpClient::new(Some(HTTP_TIMEOUT))
+    })
+    .await
+}
+
+/// A pool that has recently answered a query, connecting (or reconnecting) when needed
+pub async fn db_pool(database_url: &str, settings: PoolSettings) -> anyhow::Result<PgPool> {
+    let mut slot = POOL.lock().await;
+    if let Some(checked) = slot.as_mut() {
+        if checked.last_used.elapsed() < HEALTH_CHECK_INTERVAL {
+            checked.last_used = Instant::now();
+            return Ok(checked.pool.clone());
+        }
+        match health_check(&checked.pool).await {
+            Ok(()) => {
+                checked.last_used = Instant::now();
+                return Ok(checked.pool.clone());
+            }
+            Err(e) => {
+                println!(
+                    "WARNING: Shared database pool failed its health check ({}) - reconnecting",
+                    e
+                );
+                checked.pool.close().await;
+                *slot = None;
+            }
+        }
+    }
+
+    println!("Creating database pool (reused by warm invocations)");
+    let pool = db::connect_with(database_url, settings).await?;
+    *slot = Some(CheckedPool {
+        pool: pool.clone(),
+        last_used: Instant::now(),
+    });
+    Ok(pool)
+}
+
+async fn health_check(pool: &PgPool) -> anyhow::Result<()> {
+    if pool.is_closed() {
+        anyhow::bail!("pool is closed");
+    }
+    tokio::time::timeout(HEALTH_CHECK_TIMEOUT, sqlx::query("SELECT 1").execute(pool))
+        .await
+        .map_err(|_| anyhow::anyhow!("no answer within {:?}", HEALTH_CHECK_TIMEOUT))??;
+    Ok(())
+}
@@ -0,0 +1,208 @@
+//! Detection codes (CPV codes and the like) stored in the `detection_codes` table.
+//!
+//! The table replaced the flat `codes.txt` in S3; `ops_cli codes import` seeds it from
+//! that file. Lambdas read the active codes through `cached_matcher`, which rebuilds
+//! the matcher at most once per `CODES_CACHE_TTL_SECS` (default 300) per container.
+//! When neither the table nor `codes.txt` can be had, `fallback_matcher` covers the core
+//! IT services codes so a tender is still processed, with its codes marked as "fallback".
+
+use crate::CodeMatcher;
+use sqlx::{PgPool, Row};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+const DEFAULT_CACHE_TTL_SECS: u64 = 300;
+
+/// Where a tender's detected codes came from, as recorded in `pdf_content.metadata`
+pub const SOURCE_CONFIGURED: &str = "configured";
+pub const SOURCE_FALLBACK: &str = "fallback";
+
+/// CPV division 72 (IT services) down to group level; a subset of the bundled `codes.txt`
+pub const FALLBACK_CODES: &[&str] = &[
+    "72000000", "72100000", "72200000", "72210000", "72220000", "72300000", "72310000", "72320000",
+    "72400000", "72500000", "72600000", "72610000", "72700000", "72800000",
+];
+
+const METRIC_NAMESPACE: &str = "TenderPipeline";
+const FALLBACK_METRIC: &str = "DetectionCodesFallback";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectionCode {
+    pub code: String,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub active: bool,
+}
+
+pub async fn ensure_codes_table(pool: &PgPool) -> anyhow::Result<()> {
+    db::ensure_schema(pool, "detection_codes", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS detection_codes (
+                code TEXT PRIMARY KEY,
+                description TEXT,
+                category TEXT,
+                active BOOLEAN NOT NULL DEFAULT TRUE,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// All codes ordered by code, optionally including deactivated ones
+pub async fn list_codes(
+    pool: &PgPool,
+    include_inactive: bool,
+) -> Result<Vec<DetectionCode>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT code, description, category, active
+        FROM detection_codes
+        WHERE active OR $1
+        ORDER BY code
+        "#,
+    )
+    .bind(include_inactive)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| DetectionCode {
+            code: row.get("code"),
+            description: row.get("description"),
+            category: row.get("category"),
+            active: row.get("active"),
+        })
+        .collect())
+}
+
+/// Insert or update a code; a missing description/category keeps the stored one
+pub async fn upsert_code(pool: &PgPool, code: &DetectionCode) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO detection_codes (code, description, category, active)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (code) DO UPDATE SET
+            description = COALESCE(EXCLUDED.description, detection_codes.description),
+            category = COALESCE(EXCLUDED.category, detection_codes.category),
+            active = EXCLUDED.active,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(&code.code)
+    .bind(&code.description)
+    .bind(&code.category)
+    .bind(code.active)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Activate or deactivate a code; returns false if the code doesn't exist
+pub async fn set_active(pool: &PgPool, code: &str, active: bool) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("UPDATE detection_codes SET active = $2, updated_at = NOW() WHERE code = $1")
+            .bind(code)
+            .bind(active)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Parse the legacy `codes.txt` format: one `code,description` per line
+pub fn parse_codes_file(content: &str) -> Vec<DetectionCode> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (code, description) = match line.split_once(',') {
+                Some((code, description)) => (code, Some(description.trim())),
+                None => (line, None),
+            };
+            let code = code.trim();
+            (!code.is_empty()).then(|| DetectionCode {
+                code: code.to_string(),
+                description: description.filter(|d| !d.is_empty()).map(str::to_string),
+                category: None,
+                active: true,
+            })
+        })
+        .collect()
+}
+
+/// Matcher over `FALLBACK_CODES`, for when no configured codes can be loaded
+pub fn fallback_matcher() -> Arc<CodeMatcher> {
+    let codes: Vec<String> = FALLBACK_CODES.iter().map(|c| c.to_string()).collect();
+    Arc::new(CodeMatcher::new(&codes))
+}
+
+/// CloudWatch embedded metric format line counting one tender processed with the
+/// fallback codes; printed on its own line, CloudWatch Logs turns it into the metric
+pub fn fallback_metric_line(environment: &str, resource_id: i64) -> serde_json::Value {
+    serde_json::json!({
+        "_aws": {
+            "Timestamp": chrono::Utc::now().timestamp_millis(),
+            "CloudWatchMetrics": [{
+                "Namespace": METRIC_NAMESPACE,
+                "Dimensions": [["Environment"]],
+                "Metrics": [{ "Name": FALLBACK_METRIC, "Unit": "Count" }]
+            }]
+        },
+        "Environment": environment,
+        "ResourceId": resource_id,
+        FALLBACK_METRIC: 1,
+    })
+}
+
+struct CachedMatcher {
+    loaded_at: Instant,
+    matcher: Arc<CodeMatcher>,
+}
+
+// Per container; warm invocations reuse the automaton instead of rebuilding it
+static CACHE: LazyLock<Mutex<Option<CachedMatcher>>> = LazyLock::new(|| Mutex::new(None));
+
+fn cache_ttl() -> Duration {
+    let secs = std::env::var("CODES_CACHE_TTL_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_CACHE_TTL_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Matcher over the active codes, or None if the table has no active codes yet
+pub async fn cached_matcher(pool: &PgPool) -> anyhow::Result<Option<Arc<CodeMatcher>>> {
+    let ttl = cache_ttl();
+    let cached = CACHE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .filter(|cached| cached.loaded_at.elapsed() < ttl)
+        .map(|cached| cached.matcher.clone());
+    if cached.is_some() {
+        return Ok(cached);
+    }
+
+    ensure_codes_table(pool).await?;
+    let codes = list_codes(pool, false).await?;
+    if codes.is_empty() {
+        return Ok(None);
+    }
+
+    println!(
+        "Loaded {} active detection codes from database",
+        codes.len()
+    );
+    let matcher = Arc::new(CodeMatcher::with_descriptions(&codes));
+    *CACHE.lock().unwrap() = Some(CachedMatcher {
+        loaded_at: Instant::now(),
+        matcher: matcher.clone(),
+    });
+    Ok(Some(matcher))
+}
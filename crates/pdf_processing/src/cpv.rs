@@ -0,0 +1,156 @@
+//! CPV codes declared in a tender notice, and the retro-labelling rule built on them.
+//!
+//! Notices list their CPV codes as eight digits plus a check digit, e.g. "45000000-7".
+//! `detection_codes` only holds the IT codes we look for, but this reads every declared
+//! code, so a notice can be recognised as clearly outside IT. `mentions` is also what
+//! `CodeMatcher` uses to find detection codes as whole codes rather than substrings.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// CPV divisions (the first two digits) that are never IT work for us.
+///
+/// Divisions with IT inside them are left out on purpose. Examples are 30 (computer
+/// equipment), 32 (telecoms), 48 (software), 50 (repair, which includes IT support),
+/// 72 (IT services), 79 (business services) and 80 (training).
+pub const NON_IT_DIVISIONS: &[(&str, &str)] = &[
+    ("03", "agricultural and forestry products"),
+    ("09", "fuels and energy"),
+    ("14", "mining products"),
+    ("15", "food and beverages"),
+    ("16", "agricultural machinery"),
+    ("18", "clothing and footwear"),
+    ("19", "leather and textiles"),
+    ("24", "chemical products"),
+    ("33", "medical equipment and pharmaceuticals"),
+    ("34", "transport equipment"),
+    ("37", "musical instruments and sports goods"),
+    ("39", "furniture and furnishings"),
+    ("41", "water"),
+    ("43", "mining and construction machinery"),
+    ("44", "construction materials"),
+    ("45", "construction work"),
+    ("55", "hotel, restaurant and catering services"),
+    ("60", "transport services"),
+    ("65", "public utilities"),
+    ("66", "financial and insurance services"),
+    ("70", "real estate services"),
+    ("77", "agricultural, forestry and horticultural services"),
+    ("85", "health and social work services"),
+    ("90", "sewage, refuse, cleaning and environmental services"),
+    ("92", "recreational, cultural and sporting services"),
+];
+
+/// An eight-digit code on its own, optionally followed by its check digit. The word
+/// boundaries keep a code inside a longer number (a reference, a phone number) from matching
+static CPV_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d{8})(?:-(\d))?\b").expect("valid CPV pattern"));
+
+/// One CPV code as written in a text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mention {
+    /// The eight digits, without the check digit
+    pub code: String,
+    pub check_digit: Option<u8>,
+    /// Byte offset of the code in the text
+    pub position: usize,
+}
+
+/// A detection code found in a text, with every place it occurs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedCode {
+    pub code: String,
+    pub description: Option<String>,
+    pub count: usize,
+    /// Byte offsets of each occurrence, in order
+    pub positions: Vec<usize>,
+}
+
+/// Every CPV-shaped code in the text, in order
+pub fn mentions(text: &str) -> Vec<Mention> {
+    CPV_PATTERN
+        .captures_iter(text)
+        .filter_map(|captures| {
+            let code = captures.get(1).expect("the code group always matches");
+            let check_digit = captures.get(2);
+            // "72000000-55" backtracks to a bare code; the regex crate has no lookahead
+            let rest = &text.as_bytes()[code.end()..];
+            if check_digit.is_none()
+                && rest.len() > 1
+                && rest[0] == b'-'
+                && rest[1].is_ascii_digit()
+            {
+                return None;
+            }
+            Some(Mention {
+                code: code.as_str().to_string(),
+                check_digit: check_digit.map(|digit| digit.as_str().as_bytes()[0] - b'0'),
+                position: code.start(),
+            })
+        })
+        .collect()
+}
+
+/// The eight-digit form of a code written as "72000000" or "72000000-5"; None for anything else
+pub fn normalize(code: &str) -> Option<String> {
+    let code = code.trim();
+    let digits = match code.split_once('-') {
+        Some((digits, check)) if check.len() == 1 && check.as_bytes()[0].is_ascii_digit() => {
+            digits.trim_end()
+        }
+        Some(_) => return None,
+        None => code,
+    };
+    (digits.len() == 8 && digits.bytes().all(|b| b.is_ascii_digit())).then(|| digits.to_string())
+}
+
+/// Eight-digit CPV codes declared in the text ("45000000-7" gives "45000000"), in order of
+/// first appearance. Only codes written with their check digit count as declared
+pub fn declared_codes(text: &str) -> Vec<String> {
+    let mut codes: Vec<String> = Vec::new();
+    for mention in mentions(text) {
+        if mention.check_digit.is_some() && !codes.contains(&mention.code) {
+            codes.push(mention.code);
+        }
+    }
+    codes
+}
+
+/// Description of the code's division when it is a non-IT one
+pub fn non_it_division(code: &str) -> Option<&'static str> {
+    let division = code.get(..2)?;
+    NON_IT_DIVISIONS
+        .iter()
+        .find(|(prefix, _)| *prefix == division)
+        .map(|(_, description)| *description)
+}
+
+/// A proposed `bid = 0` label, with the reason shown to the labeller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proposal {
+    pub reason: String,
+}
+
+/// Propose a no-bid label when the notice declares CPV codes, every one of them is in a
+/// non-IT division, and none of the IT detection codes matched (`detected_count`)
+pub fn propose_no_bid(declared: &[String], detected_count: usize) -> Option<Proposal> {
+    if declared.is_empty() || detected_count > 0 {
+        return None;
+    }
+
+    let mut divisions: Vec<&str> = Vec::new();
+    for code in declared {
+        let division = non_it_division(code)?;
+        if !divisions.contains(&division) {
+            divisions.push(division);
+        }
+    }
+
+    Some(Proposal {
+        reason: format!(
+            "declares only non-IT CPV codes ({}: {})",
+            declared.join(", "),
+            divisions.join("; ")
+        ),
+    })
+}
@@ -0,0 +1,245 @@
+//! A PDF as pages rather than one flat string, with its language and embedded metadata.
+//!
+//! `PdfDocument` keeps the extracted text whole (it is what `pdf_content.pdf_text` stores)
+//! plus where each page sits in it, so a page's text is a slice rather than a copy.
+//! `metadata` is the JSON pdf_processing merges into `pdf_content.metadata`: the page count,
+//! detected language, the PDF's own title, author and creation date, and the page map
+//! (each page's number, start offset in `pdf_text` and length), which lets prompts and
+//! answers cite "page 12" of the document.
+
+use crate::{CodeMatcher, ExtractionBudget, StreamedExtraction, extract_text_streaming};
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone};
+use pdf_extract::{Document, Object};
+use serde_json::{Value, json};
+
+/// Fewer marker words than this across the sample and the language is left undetected
+const MIN_LANGUAGE_HITS: usize = 10;
+/// Only the start of the text is sampled; tenders don't switch language halfway
+const LANGUAGE_SAMPLE_CHARS: usize = 20_000;
+
+/// Frequent words that are distinctive for each language (ISO 639-1 code first). Words
+/// shared with another listed language, such as Irish "an", "is" and "le", are left out
+const LANGUAGE_MARKERS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "of", "to", "is", "for", "with", "that", "this", "be", "will", "shall",
+            "are", "which", "by",
+        ],
+    ),
+    (
+        "ga",
+        &[
+            "agus", "na", "ar", "leis", "go", "ag", "atá", "bhfuil", "seo", "sin", "faoi", "chun",
+            "ó", "nó", "mar", "níl", "ní",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "des", "et", "du", "est", "pour", "dans", "une", "qui", "sur",
+            "avec", "sont",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "und", "das", "ist", "mit", "für", "von", "den", "nicht", "eine",
+            "werden", "sind", "auf",
+        ],
+    ),
+];
+
+/// The document information dictionary; a field the PDF leaves out or garbles is None
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocumentInfo {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub created: Option<DateTime<FixedOffset>>,
+}
+
+impl DocumentInfo {
+    pub fn read(doc: &Document) -> Self {
+        let Some(info) = doc
+            .trailer
+            .get(b"Info")
+            .ok()
+            .and_then(|info| doc.dereference(info).ok())
+            .and_then(|(_, info)| info.as_dict().ok())
+        else {
+            return Self::default();
+        };
+        let text = |key: &[u8]| {
+            info.get_deref(key, doc)
+                .ok()
+                .and_then(|value| pdf_extract::decode_text_string(value).ok())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            title: text(b"Title"),
+            author: text(b"Author"),
+            created: info
+                .get_deref(b"CreationDate", doc)
+                .ok()
+                .and_then(|value| match value {
+                    Object::String(bytes, _) => std::str::from_utf8(bytes).ok(),
+                    _ => None,
+                })
+                .and_then(parse_pdf_date),
+        }
+    }
+}
+
+/// Where one page sits in `PdfDocument::text`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    /// 1-based, in document order
+    pub number: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct PdfDocument {
+    /// Every extracted page's text, in order
+    pub text: String,
+    /// One per page read; a page that failed to parse is empty
+    pub pages: Vec<Page>,
+    /// Pages in the file, which is more than `pages` when the extraction budget ran out
+    pub page_count: usize,
+    /// ISO 639-1 code, when the text is clearly one of the languages we recognise
+    pub language: Option<&'static str>,
+    pub info: DocumentInfo,
+}
+
+impl PdfDocument {
+    /// Split a streamed extraction into pages (its detected codes are dropped)
+    pub fn from_extraction(extraction: StreamedExtraction) -> Self {
+        let ends = extraction
+            .page_offsets
+            .iter()
+            .skip(1)
+            .copied()
+            .chain(std::iter::once(extraction.text.len()));
+        let pages = extraction
+            .page_offsets
+            .iter()
+            .zip(ends)
+            .enumerate()
+            .map(|(index, (&start, end))| Page {
+                number: index + 1,
+                start,
+                end,
+            })
+            .collect();
+        Self {
+            language: detect_language(&extraction.text),
+            text: extraction.text,
+            pages,
+            page_count: extraction.total_pages,
+            info: extraction.info,
+        }
+    }
+
+    /// A page's text by its 1-based number
+    pub fn page_text(&self, number: usize) -> Option<&str> {
+        let page = self.pages.get(number.checked_sub(1)?)?;
+        Some(&self.text[page.start..page.end])
+    }
+
+    /// Where each page starts in `text`, as stored in `pdf_content.page_offsets`
+    pub fn page_offsets(&self) -> Vec<usize> {
+        self.pages.iter().map(|page| page.start).collect()
+    }
+
+    /// Merged into `pdf_content.metadata`; offsets and lengths are in bytes of `pdf_text`
+    pub fn metadata(&self) -> Value {
+        let pages: Vec<Value> = self
+            .pages
+            .iter()
+            .map(|page| json!({ "page": page.number, "start": page.start, "length": page.end - page.start }))
+            .collect();
+        json!({
+            "page_count": self.page_count,
+            "language": self.language,
+            "title": self.info.title,
+            "author": self.info.author,
+            "created": self.info.created.map(|created| created.to_rfc3339()),
+            "pages": pages,
+        })
+    }
+}
+
+/// Extract a PDF page by page within the default `ExtractionBudget`
+pub fn extract_document(pdf_bytes: &[u8]) -> Result<PdfDocument, Box<dyn std::error::Error>> {
+    let extraction = extract_text_streaming(
+        pdf_bytes,
+        &CodeMatcher::new(&[]),
+        &ExtractionBudget::default(),
+    )?;
+    Ok(PdfDocument::from_extraction(extraction))
+}
+
+/// The language whose marker words are most frequent, if there are enough of them to tell
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let sample = &text[..crate::floor_char_boundary(text, LANGUAGE_SAMPLE_CHARS)];
+    let words: Vec<String> = sample
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let mut hits: Vec<(&'static str, usize)> = LANGUAGE_MARKERS
+        .iter()
+        .map(|(code, markers)| {
+            let count = words
+                .iter()
+                .filter(|word| markers.contains(&word.as_str()))
+                .count();
+            (*code, count)
+        })
+        .collect();
+    hits.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    match hits.as_slice() {
+        [(code, best), (_, runner_up), ..] if *best >= MIN_LANGUAGE_HITS && best > runner_up => {
+            Some(*code)
+        }
+        _ => None,
+    }
+}
+
+/// Parse a PDF date string, `D:YYYYMMDDHHmmSSOHH'mm'`, where everything after the year is
+/// optional and a missing offset is taken as UTC
+pub fn parse_pdf_date(value: &str) -> Option<DateTime<FixedOffset>> {
+    let value = value.trim();
+    let value = value.strip_prefix("D:").unwrap_or(value);
+    let digits = value.bytes().take_while(u8::is_ascii_digit).count();
+    if digits < 4 {
+        return None;
+    }
+    let (stamp, zone) = value.split_at(digits);
+    let field = |from: usize, default: u32| -> Option<u32> {
+        match stamp.get(from..from + 2) {
+            Some(text) => text.parse().ok(),
+            None => Some(default),
+        }
+    };
+    let year: i32 = stamp[..4].parse().ok()?;
+    let date = NaiveDate::from_ymd_opt(year, field(4, 1)?, field(6, 1)?)?;
+    let local = date.and_hms_opt(field(8, 0)?, field(10, 0)?, field(12, 0)?)?;
+
+    let offset_seconds = match zone.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let numbers: String = zone[1..].chars().filter(char::is_ascii_digit).collect();
+            let hours: i32 = numbers.get(..2)?.parse().ok()?;
+            let minutes: i32 = numbers.get(2..4).map_or(Some(0), |m| m.parse().ok())?;
+            let seconds = hours * 3600 + minutes * 60;
+            if sign == '-' { -seconds } else { seconds }
+        }
+        _ => 0,
+    };
+    FixedOffset::east_opt(offset_seconds)?
+        .from_local_datetime(&local)
+        .single()
+}
@@ -1,12 +1,304 @@
+pub mod clarification;
+pub mod clients;
+pub mod codes;
+pub mod cpv;
+pub mod document;
+#[cfg(feature = "ocr")]
+pub mod ocr;
+pub mod pdf_content_migration;
+pub mod quality;
+#[cfg(feature = "thumbnail")]
+pub mod thumbnail;
+pub mod unknown_codes;
+
+use aho_corasick::AhoCorasick;
+use codes::DetectionCode;
+use cpv::DetectedCode;
+pub use document::{DocumentInfo, PdfDocument, extract_document};
+use pdf_extract::{Document, PlainTextOutput, output_doc_page};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
 pub fn extract_text_from_pdf(pdf_bytes: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
     let text = pdf_extract::extract_text_from_mem(pdf_bytes)?;
     Ok(text)
 }
 
+/// Convenience for one-off checks; build a `CodeMatcher` once when scanning many texts
 pub fn extract_codes(text: &str, codes: &[String]) -> Vec<String> {
-    codes
-        .iter()
-        .filter(|code| text.contains(&code[..]))
-        .cloned()
-        .collect()
-}
\ No newline at end of file
+    CodeMatcher::new(codes).find_codes(text)
+}
+
+/// Detects which codes occur in a text in a single pass, however long the code list.
+///
+/// Eight-digit codes are matched as whole CPV codes (`cpv::mentions`): "72000000-5" counts
+/// as 72000000, and 72000000 inside "172000000" doesn't count. Codes of any other shape
+/// are matched as substrings.
+pub struct CodeMatcher {
+    codes: Vec<String>,
+    descriptions: Vec<Option<String>>,
+    /// Index in `codes` of each eight-digit code
+    cpv: HashMap<String, usize>,
+    /// Index in `codes` of each automaton pattern
+    others: Vec<usize>,
+    automaton: AhoCorasick,
+}
+
+impl CodeMatcher {
+    pub fn new(codes: &[String]) -> Self {
+        Self::build(codes.iter().map(|code| (code.as_str(), None)))
+    }
+
+    /// A matcher that reports each code's description with it
+    pub fn with_descriptions(codes: &[DetectionCode]) -> Self {
+        Self::build(
+            codes
+                .iter()
+                .map(|code| (code.code.as_str(), code.description.as_deref())),
+        )
+    }
+
+    fn build<'a>(entries: impl Iterator<Item = (&'a str, Option<&'a str>)>) -> Self {
+        let mut codes = Vec::new();
+        let mut descriptions = Vec::new();
+        let mut cpv = HashMap::new();
+        let mut others = Vec::new();
+        for (code, description) in entries {
+            let index = codes.len();
+            match cpv::normalize(code) {
+                Some(normalized) => {
+                    if cpv.contains_key(&normalized) {
+                        continue;
+                    }
+                    cpv.insert(normalized.clone(), index);
+                    codes.push(normalized);
+                }
+                None => {
+                    others.push(index);
+                    codes.push(code.to_string());
+                }
+            }
+            descriptions.push(description.map(str::to_string));
+        }
+        // Only fails past aho-corasick's size limits, far beyond any code list
+        let automaton = AhoCorasick::new(others.iter().map(|&index| &codes[index]))
+            .expect("Failed to build code matcher");
+        Self {
+            codes,
+            descriptions,
+            cpv,
+            others,
+            automaton,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    /// Codes found anywhere in the text, in code-list order
+    pub fn find_codes(&self, text: &str) -> Vec<String> {
+        self.detect(text)
+            .into_iter()
+            .map(|detected| detected.code)
+            .collect()
+    }
+
+    /// Codes found in the text with their descriptions and where they occur, in code-list order
+    pub fn detect(&self, text: &str) -> Vec<DetectedCode> {
+        let mut positions: Vec<Vec<usize>> = vec![Vec::new(); self.codes.len()];
+        for mention in cpv::mentions(text) {
+            if let Some(&index) = self.cpv.get(&mention.code) {
+                positions[index].push(mention.position);
+            }
+        }
+        // Overlapping search so codes nested inside other codes are still reported
+        for m in self.automaton.find_overlapping_iter(text) {
+            positions[self.others[m.pattern().as_usize()]].push(m.start());
+        }
+
+        positions
+            .into_iter()
+            .enumerate()
+            .filter(|(_, positions)| !positions.is_empty())
+            .map(|(index, mut positions)| {
+                positions.sort_unstable();
+                DetectedCode {
+                    code: self.codes[index].clone(),
+                    description: self.descriptions[index].clone(),
+                    count: positions.len(),
+                    positions,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Limits for `extract_text_streaming`; `None` means unlimited
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractionBudget {
+    pub max_pages: Option<usize>,
+    pub max_chars: Option<usize>,
+    /// Stop once this many distinct codes have been detected
+    pub stop_after_codes: Option<usize>,
+}
+
+impl Default for ExtractionBudget {
+    fn default() -> Self {
+        Self {
+            max_pages: Some(300),
+            max_chars: Some(1_000_000),
+            stop_after_codes: None,
+        }
+    }
+}
+
+impl ExtractionBudget {
+    /// Defaults overridden by PDF_MAX_PAGES, PDF_MAX_CHARS and PDF_STOP_AFTER_CODES (0 = unlimited)
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |var: &str, default: Option<usize>| match std::env::var(var)
+            .ok()
+            .and_then(|v| v.trim().parse::<usize>().ok())
+        {
+            Some(0) => None,
+            Some(value) => Some(value),
+            None => default,
+        };
+
+        Self {
+            max_pages: read("PDF_MAX_PAGES", defaults.max_pages),
+            max_chars: read("PDF_MAX_CHARS", defaults.max_chars),
+            stop_after_codes: read("PDF_STOP_AFTER_CODES", defaults.stop_after_codes),
+        }
+    }
+}
+
+/// Why streaming extraction stopped before the last page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    MaxPages,
+    MaxChars,
+    EnoughCodes,
+    /// The caller gave up waiting (see `extract_text_cancellable`)
+    Cancelled,
+}
+
+#[derive(Debug, Clone)]
+pub struct StreamedExtraction {
+    pub text: String,
+    /// Byte offset in `text` where each page starts, in page order; a page that failed to
+    /// parse starts where the next one does. Stored in `pdf_content.page_offsets`
+    pub page_offsets: Vec<usize>,
+    pub detected_codes: Vec<String>,
+    pub pages_processed: usize,
+    pub pages_failed: usize,
+    pub total_pages: usize,
+    pub stop_reason: Option<StopReason>,
+    /// The PDF's title, author and creation date
+    pub info: DocumentInfo,
+}
+
+/// Extract text page by page, detecting codes as each page arrives.
+///
+/// Only the accumulated text (bounded by `max_chars`) is kept, rather than the whole
+/// document's text plus its intermediate copies, and a page that fails to parse is
+/// skipped instead of failing the document.
+pub fn extract_text_streaming(
+    pdf_bytes: &[u8],
+    matcher: &CodeMatcher,
+    budget: &ExtractionBudget,
+) -> Result<StreamedExtraction, Box<dyn std::error::Error>> {
+    extract_text_cancellable(pdf_bytes, matcher, budget, &AtomicBool::new(false))
+}
+
+/// `extract_text_streaming`, stopping before the next page once `cancelled` is set
+pub fn extract_text_cancellable(
+    pdf_bytes: &[u8],
+    matcher: &CodeMatcher,
+    budget: &ExtractionBudget,
+    cancelled: &AtomicBool,
+) -> Result<StreamedExtraction, Box<dyn std::error::Error>> {
+    let mut doc = Document::load_mem(pdf_bytes)?;
+    if doc.is_encrypted() {
+        doc.decrypt("")?;
+    }
+
+    let pages = doc.get_pages();
+    let mut extraction = StreamedExtraction {
+        text: String::new(),
+        page_offsets: Vec::new(),
+        detected_codes: Vec::new(),
+        pages_processed: 0,
+        pages_failed: 0,
+        total_pages: pages.len(),
+        stop_reason: None,
+        info: DocumentInfo::read(&doc),
+    };
+
+    for &page_num in pages.keys() {
+        if cancelled.load(Ordering::Relaxed) {
+            extraction.stop_reason = Some(StopReason::Cancelled);
+            break;
+        }
+        if budget
+            .max_pages
+            .is_some_and(|max| extraction.pages_processed >= max)
+        {
+            extraction.stop_reason = Some(StopReason::MaxPages);
+            break;
+        }
+        extraction.page_offsets.push(extraction.text.len());
+
+        let mut page_text = String::new();
+        {
+            let mut output = PlainTextOutput::new(&mut page_text);
+            if let Err(e) = output_doc_page(&doc, &mut output, page_num) {
+                println!("Skipping page {}: {}", page_num, e);
+                extraction.pages_failed += 1;
+                continue;
+            }
+        }
+        extraction.pages_processed += 1;
+
+        if let Some(max) = budget.max_chars {
+            let remaining = max.saturating_sub(extraction.text.len());
+            if page_text.len() > remaining {
+                page_text.truncate(floor_char_boundary(&page_text, remaining));
+                extraction.stop_reason = Some(StopReason::MaxChars);
+            }
+        }
+
+        for code in matcher.find_codes(&page_text) {
+            if !extraction.detected_codes.contains(&code) {
+                extraction.detected_codes.push(code);
+            }
+        }
+        extraction.text.push_str(&page_text);
+
+        if extraction.stop_reason.is_some() {
+            break;
+        }
+        if budget
+            .stop_after_codes
+            .is_some_and(|enough| extraction.detected_codes.len() >= enough)
+        {
+            extraction.stop_reason = Some(StopReason::EnoughCodes);
+            break;
+        }
+    }
+
+    Ok(extraction)
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut end = index.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    end
+}
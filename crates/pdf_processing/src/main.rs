@@ -1,92 +1,124 @@
-use lambda_runtime::{service_fn, LambdaEvent, Error, run};
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+use aws_sdk_s3::error::SdkError;
+use fault_injection::Fault;
+use retry::Policy;
+use sqlx::{Pool, Postgres};
 use std::env;
-use std::time::Duration;
-use aws_lambda_events::event::sqs::SqsEvent;
-use serde_json;
-use aws_config;
-use aws_sdk_sqs::Client as SqsClient;
-use aws_sdk_s3::Client as S3Client;
-use chrono::{NaiveDate, NaiveDateTime};
-use bigdecimal::BigDecimal;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use chrono::NaiveDateTime;
+use tender_core::{AISummaryMessage, MLPredictionResult, TenderRecord};
 
 // Import the function from the lib.rs file
-use pdf_processing::{extract_codes, extract_text_from_pdf};
+use pdf_processing::{clarification, clients, codes, extract_text_cancellable, pdf_content_migration, quality, unknown_codes, CodeMatcher, ExtractionBudget, PdfDocument, StreamedExtraction};
+use resource_discovery::Resource;
+use environment::Environment;
+use db::PoolSettings;
+use pipeline_contract::{Completed, ErrorCode, Handoff, StageError, StageEvent, StageMessage, StageResults};
+use pipeline_contract::diagnostics::{self, ConfigReport};
+use pipeline_contract::timeouts::{Call, Timeouts};
+use pipeline_status::Stage;
+use pipeline_status::lifecycle::{self, State};
+use decision_audit::{Decision, Kind};
+use stage_runtime::Error;
 
 // Track if this container has been used
 // Removed: Unused after redesign
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct TenderRecord {
-    title: String,
-    resource_id: i64, // Back to i64 for consistency
-    contracting_authority: String,
-    info: String,
-    published: Option<NaiveDateTime>,
-    deadline: Option<NaiveDateTime>,
-    procedure: String,
-    status: String,
-    pdf_url: String,
-    awarddate: Option<NaiveDate>,
-    value: Option<BigDecimal>,
-    cycle: String,
-    bid: Option<i32>, // 1 = bid, 0 = no bid, NULL = unlabeled
-    // This will be added during PDF processing
-    pdf_content: Option<String>,
-    detected_codes: Option<Vec<String>>, // Added by pdf_processing - actual codes found
-    codes_count: Option<i32>, // Added by pdf_processing - count of detected codes
-    processing_stage: Option<String>, // e.g. "ml_prediction"
+/// Effective configuration, returned for `{"payload": {"diagnostic": "config"}}`
+fn config_report() -> ConfigReport {
+    ConfigReport::new("pdf_processing", env!("CARGO_PKG_VERSION"))
+        .envs(&[
+            "EXTRACTION_MIN_QUALITY", "CODES_CACHE_TTL_SECS", "PDF_MAX_PAGES", "PDF_MAX_CHARS",
+            "PDF_STOP_AFTER_CODES", outbound_http::ALLOWED_HOSTS_VAR, "GHOSTSCRIPT_PATH", "THUMBNAIL_DPI",
+            "TESSERACT_PATH", "OCR_LANGUAGE", "OCR_DPI", "OCR_MAX_PAGES", "PIPELINE_TOPIC_ARN",
+            "DOWNLOAD_TIMEOUT_SECS", "EXTRACTION_TIMEOUT_SECS", "DB_QUERY_TIMEOUT_SECS", "PDF_BATCH_TIME_MARGIN_SECS",
+            "STAGE_RUNTIME", "FAULT_S3_TIMEOUT_RATE", "FAULT_DB_WRITE_RATE",
+        ])
+        .build("ocr", cfg!(feature = "ocr"))
+        .effective("min_quality", quality::min_quality_from_env())
+        .effective("extraction_budget", format!("{:?}", ExtractionBudget::from_env()))
+        .effective("outbound_allowlist", format!("{:?}", outbound_http::Allowlist::from_env()))
+        .effective("timeouts", Timeouts::from_env().describe())
+        .effective("fault_injection", fault_injection::describe())
+        .effective("batch_time_margin_secs", batch_time_margin(&Timeouts::from_env()).as_secs())
+        .effective("thumbnails", thumbnail_options())
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Response {
-    resource_id: String,
-    success: bool,
-    message: String,
-    text_length: Option<usize>,
+#[cfg(feature = "thumbnail")]
+fn thumbnail_options() -> String {
+    format!("{:?}", pdf_processing::thumbnail::ThumbnailOptions::from_env())
 }
 
-async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<Response, Error> {
-    println!("=== FUNCTION HANDLER STARTED ===");
-    println!("Event received, processing SQS records...");
-    
-    // Check if this container has been used before
-    // Removed: Unused after redesign
-    
-    // Expect exactly one record per invocation (batch_size = 1)
-    let sqs_records = &event.payload.records;
-    println!("Number of SQS records: {}", sqs_records.len());
-    
-    if sqs_records.is_empty() {
-        println!("No SQS records found in event");
-        return Ok(Response {
-            resource_id: String::new(),
-            success: false,
-            message: "No SQS records received".to_string(),
-            text_length: None,
-        });
+#[cfg(not(feature = "thumbnail"))]
+fn thumbnail_options() -> String {
+    "not built (thumbnail feature off)".to_string()
+}
+
+/// Runs under Lambda or as a service polling the PDF processing queue (see `stage_runtime`)
+struct PdfProcessing;
+
+impl stage_runtime::Stage for PdfProcessing {
+    fn queue(&self) -> Resource {
+        Resource::PdfProcessingQueue
     }
 
-    let sqs_message = &sqs_records[0];
-    println!("Processing SQS message, checking body...");
-    let body_str = match &sqs_message.body {
-        Some(b) => {
-            println!("SQS message body found, length: {}", b.len());
-            println!("Message body preview: {}", &b[..b.len().min(100)]);
-            b
-        },
-        None => {
-            println!("ERROR: SQS message body is None");
-            return Ok(Response {
-                resource_id: String::new(),
-                success: false,
-                message: "SQS message body missing".to_string(),
-                text_length: None,
-            });
+    async fn handle(&self, event: StageEvent, deadline: SystemTime) -> Result<serde_json::Value, Error> {
+        function_handler(event, deadline).await
+    }
+}
+
+async fn function_handler(event: StageEvent, deadline: SystemTime) -> Result<serde_json::Value, Error> {
+    println!("=== FUNCTION HANDLER STARTED ===");
+    if let Some(diagnostic) = event.diagnostic() {
+        return diagnostics::respond(diagnostic, config_report).map_err(|e| Error::from(e.as_str()));
+    }
+    println!("Event received, processing messages...");
+    
+    // Any number of SQS records (each one's outcome reported separately), or one Step Functions task
+    let timeouts = Timeouts::from_env();
+    let margin = batch_time_margin(&timeouts);
+    let mut results = StageResults::new(&event);
+    let messages = event.into_messages();
+    println!("Number of messages: {}", messages.len());
+    
+    for (index, message) in messages.iter().enumerate() {
+        // Records after the first are only started with time to finish before the lambda timeout;
+        // the rest go back to the queue untouched instead of being redelivered with the whole batch
+        let left = deadline.duration_since(SystemTime::now()).unwrap_or_default();
+        if index > 0 && left < margin {
+            println!("{:?} left before the timeout (margin {:?}) - returning {} unstarted messages to the queue", left, margin, messages.len() - index);
+            for unstarted in &messages[index..] {
+                results.record_unstarted(unstarted);
+            }
+            break;
         }
-    };
+        
+        let handoff = Handoff::new(message);
+        let result = process_message(message, &handoff, &timeouts).await;
+        results.record(message, &handoff, result).await;
+    }
+    println!("Batch finished: {}", results.summary());
+    
+    // Retryable failures go back to SQS as batch item failures (or fail the Step Functions task); permanent ones are acknowledged
+    results.into_response().map_err(|e| Error::from(e.to_string().as_str()))
+}
+
+/// Time that must be left before the lambda timeout to start another record of a batch:
+/// PDF_BATCH_TIME_MARGIN_SECS, else one record's worst case - the download and extraction limits plus a few queries
+fn batch_time_margin(timeouts: &Timeouts) -> Duration {
+    if let Some(secs) = env::var("PDF_BATCH_TIME_MARGIN_SECS").ok().and_then(|v| v.trim().parse().ok()) {
+        return Duration::from_secs(secs);
+    }
+    [Call::Download, Call::Extraction, Call::Database, Call::Database, Call::Database]
+        .iter()
+        .filter_map(|&call| timeouts.limit(call))
+        .sum()
+}
+
+async fn process_message(message: &StageMessage, handoff: &Handoff, timeouts: &Timeouts) -> Result<Completed, StageError> {
+    let body_str = &message.body;
+    println!("Message body length: {}", body_str.len());
+    println!("Message body preview: {}", &body_str[..body_str.len().min(100)]);
 
     println!("Attempting to parse JSON from SQS message body...");
     // Deserialize the message body into our TenderRecord struct
@@ -99,429 +131,581 @@ async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<Response, Erro
         Err(e) => {
             println!("ERROR: Failed to parse TenderRecord JSON: {:?}", e);
             println!("Raw message body: {}", body_str);
-            return Ok(Response {
-                resource_id: String::new(),
-                success: false,
-                message: format!("Failed to parse SQS message JSON: {}", e),
-                text_length: None,
-            });
+            return Err(StageError::new(ErrorCode::InvalidMessage, format!("Failed to parse SQS message JSON: {}", e)));
         }
     };
     
     let resource_id = tender_record.resource_id;
-    let pdf_url = tender_record.pdf_url.clone();
     
-    println!("Fresh container processing PDF for resource_id: {}", resource_id);
+    println!("Processing PDF for resource_id: {} (correlation id {:?})", resource_id, message.routing.correlation_id);
 
-    if pdf_url.is_empty() {
+    let db_url = match env::var("DATABASE_URL") {
+        Ok(url) => {
+            println!("DATABASE_URL found, length: {}", url.len());
+            url
+        },
+        Err(e) => {
+            println!("ERROR: DATABASE_URL not found: {:?}", e);
+            return Err(StageError::new(ErrorCode::Configuration, format!("DATABASE_URL environment variable not set: {:?}", e)).for_tender(resource_id));
+        }
+    };
+    // The container's shared pool, reconnected if it went bad between invocations (the title-only route needs it too, for the audit trail)
+    let db_pool = clients::db_pool(&db_url, PoolSettings::lambda(1).acquire_timeout(Duration::from_secs(5)))
+        .await
+        .map_err(|e| StageError::new(ErrorCode::Database, format!("Failed to connect to database: {}", e)).for_tender(resource_id))?;
+
+    // Reject messages for tenders that aren't (or are no longer) waiting for this stage
+    if !lifecycle::may_enter(&db_pool, resource_id, State::PdfProcessed, &[State::TitleOnly], "pdf_processing").await {
+        return Err(StageError::new(ErrorCode::InvalidState, "Tender isn't ready for PDF processing (see tender_lifecycle_transitions)").for_tender(resource_id));
+    }
+
+    if tender_record.pdf_url.is_empty() {
         println!("No PDF URL provided - routing to AI Summary for title-only analysis");
         
         // Route to AI Summary for title-only analysis
         tender_record.pdf_content = Some(String::new()); // Empty PDF content
         tender_record.detected_codes = Some(vec![]); // No codes
         tender_record.codes_count = Some(0); // Zero codes
-        tender_record.processing_stage = Some("ai_summary_title_only".to_string());
         
-        if let Err(e) = forward_to_ai_summary(&tender_record).await {
+        // Recorded before the hand-off so the next stage never sees the tender a step behind
+        lifecycle::advance(&db_pool, resource_id, State::TitleOnly, "pdf_processing").await;
+        let forwarded = forward_to_ai_summary(&tender_record, handoff).await;
+        if forwarded.is_ok() {
+            let decision = Decision::new(resource_id, Kind::Routing, "pdf_processing", "ai_summary_title_only")
+                .inputs(&[&tender_record.title, &tender_record.pdf_url])
+                .detail(serde_json::json!({ "reason": "no PDF URL" }));
+            decision_audit::record(&db_pool, &decision).await;
+        }
+        if let Err(e) = forwarded {
             println!("WARNING: Failed to forward to AI Summary queue: {}", e);
-            return Ok(Response {
-                resource_id: resource_id.to_string(),
-                success: false,
-                message: format!("No PDF URL and failed to forward to AI Summary: {}", e),
-                text_length: None,
-            });
+            return Err(StageError::new(ErrorCode::ForwardFailed, format!("No PDF URL and failed to forward to AI Summary: {}", e)).for_tender(resource_id));
         }
         
-        return Ok(Response {
-            resource_id: resource_id.to_string(),
-            success: true,
-            message: "No PDF URL - routed to AI Summary for title-only analysis".to_string(),
-            text_length: Some(0),
-        });
+        return Ok(Completed::new(resource_id, "No PDF URL - routed to AI Summary for title-only analysis"));
     }
 
-    // Create fresh HTTP client for each invocation
-    println!("Creating HTTP client");
-    let http_client = Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    pipeline_status::started(&db_pool, resource_id, Stage::PdfProcessing, body_str).await;
 
-    // Create fresh database pool for each invocation
-    println!("Creating database connection");
-    let db_url = match env::var("DATABASE_URL") {
-        Ok(url) => {
-            println!("DATABASE_URL found, length: {}", url.len());
-            url
-        },
-        Err(e) => {
-            println!("ERROR: DATABASE_URL not found: {:?}", e);
-            return Ok(Response {
-                resource_id: resource_id.to_string(),
-                success: false,
-                message: format!("DATABASE_URL environment variable not set: {:?}", e),
-                text_length: None,
-            });
-        }
-    };
-    let db_pool = PgPoolOptions::new()
-        .max_connections(1)
-        .acquire_timeout(Duration::from_secs(5))
-        .connect(&db_url)
-        .await
-        .map_err(|e| format!("Failed to connect to database: {}", e))?;
+    let result = process_pdf(&db_pool, tender_record, handoff, timeouts).await
+        .map_err(|e| e.for_tender(resource_id));
+    
+    // Permanent failures are recorded as rejected so pipeline_watchdog leaves them alone
+    match &result {
+        Err(e) if e.code == ErrorCode::Timeout => pipeline_status::timed_out(&db_pool, resource_id, Stage::PdfProcessing, &e.to_string()).await,
+        Err(e) if e.is_retryable() => pipeline_status::failed(&db_pool, resource_id, Stage::PdfProcessing, &e.to_string()).await,
+        Err(e) => pipeline_status::rejected(&db_pool, resource_id, Stage::PdfProcessing, &e.to_string()).await,
+        Ok(_) => {}
+    }
+    result
+}
+
+/// Download, extract and store the PDF, then hand the tender to the next stage. The download,
+/// extraction and queries each fail with `ErrorCode::Timeout` past their limit in `timeouts`
+async fn process_pdf(
+    db_pool: &Pool<Postgres>,
+    mut tender_record: TenderRecord,
+    handoff: &Handoff,
+    timeouts: &Timeouts,
+) -> Result<Completed, StageError> {
+    let resource_id = tender_record.resource_id;
+    let pdf_url = tender_record.pdf_url.clone();
+    
+    // The container's HTTP client; it only fetches from OUTBOUND_ALLOWED_HOSTS
+    let http_client = clients::http().await
+        .map_err(|e| StageError::new(ErrorCode::Configuration, format!("Failed to create HTTP client: {}", e)))?;
 
-    // Download PDF using the fresh client
+    // Download PDF; timeouts, 429s and 5xx responses are retried, all
+    // within the overall download limit so a host that keeps hanging can't stall the lambda
     println!("Downloading PDF from: {}", pdf_url);
-    let pdf_bytes = match http_client.get(&pdf_url).send().await {
+    let download = async { Ok(match http_client.get_with_retry(&pdf_url, "pdf_download").await {
         Ok(response) => match response.error_for_status() {
             Ok(resp) => {
                 println!("PDF download successful, getting bytes");
-                resp.bytes().await.map_err(|e| format!("Failed to get PDF bytes: {}", e))?
+                resp.bytes().await.map_err(|e| StageError::new(ErrorCode::DownloadFailed, format!("Failed to get PDF bytes: {}", e)))?
             },
             Err(e) => {
-                let _ = db_pool.close().await;
-                return Ok(Response {
-                    resource_id: resource_id.to_string(),
-                    success: false,
-                    message: format!("Failed to download PDF: HTTP {}", e.status().unwrap_or_default()),
-                    text_length: None,
-                });
+                let status = e.status().unwrap_or_default();
+                // A missing notice won't appear on retry; server errors might clear
+                let code = if status.is_client_error() { ErrorCode::UnusablePdf } else { ErrorCode::DownloadFailed };
+                return Err(StageError::new(code, format!("Failed to download PDF: HTTP {}", status)));
             }
         },
+        // A URL off the allowlist never becomes fetchable on retry
+        Err(e) if e.is_blocked() => {
+            return Err(StageError::new(ErrorCode::UnusablePdf, format!("Refused to download PDF: {}", e)));
+        }
         Err(e) => {
-            let _ = db_pool.close().await;
-            return Ok(Response {
-                resource_id: resource_id.to_string(),
-                success: false,
-                message: format!("Failed to send request: {}", e),
-                text_length: None,
-            });
+            return Err(StageError::new(ErrorCode::DownloadFailed, format!("Failed to send request: {}", e)));
         }
-    };
+    }) };
+    let pdf_bytes = timeouts.run(Call::Download, download).await??;
     
-    // Extract text from PDF
+    // Preview for the notification email; a failed render never fails the tender
+    #[cfg(feature = "thumbnail")]
+    store_thumbnail(resource_id, &pdf_bytes).await;
+
+    // Load codes first so they can be detected page by page during extraction
+    let (matcher, codes_source) = timeouts.run(Call::Database, load_code_matcher(db_pool)).await?
+        .map_err(|e| StageError::new(ErrorCode::Database, format!("Failed to load detection codes: {}", e)))?;
+    if codes_source == codes::SOURCE_FALLBACK {
+        println!("{}", codes::fallback_metric_line(Environment::from_env().name(), resource_id));
+    }
+    
+    // Extract text page by page within the memory budget (very large PDFs would otherwise exhaust the lambda),
+    // off the async runtime so the extraction limit can give up on it
     println!("Extracting text from PDF ({} bytes)", pdf_bytes.len());
-    let pdf_text = match extract_text_from_pdf(&pdf_bytes) {
-        Ok(text) => {
-            println!("Text extraction successful, {} characters", text.len());
-            text
-        },
-        Err(e) => {
-            let _ = db_pool.close().await;
-            return Ok(Response {
-                resource_id: resource_id.to_string(),
-                success: false,
-                message: format!("Failed to extract text from PDF: {}", e),
-                text_length: None,
-            });
-        }
+    let budget = ExtractionBudget::from_env();
+    let extracting = {
+        let (pdf_bytes, matcher, budget) = (pdf_bytes.clone(), Arc::clone(&matcher), budget.clone());
+        timeouts.run_blocking(Call::Extraction, move |cancelled| {
+            extract_text_cancellable(&pdf_bytes, &matcher, &budget, cancelled).map_err(|e| e.to_string())
+        })
     };
-    
-    // Load codes from embedded content (instead of file system)
-    println!("Loading codes from S3");
-    let codes = match load_codes_from_s3().await {
-        Ok(codes) => {
-            println!("Loaded {} codes from S3", codes.len());
-            codes
+    let extraction = match extracting.await? {
+        Ok(extraction) => {
+            println!(
+                "Text extraction successful, {} characters from {}/{} pages ({} failed)",
+                extraction.text.len(),
+                extraction.pages_processed,
+                extraction.total_pages,
+                extraction.pages_failed
+            );
+            if let Some(reason) = extraction.stop_reason {
+                println!("Stopped early: {:?} (budget: {:?})", reason, budget);
+            }
+            extraction
         },
         Err(e) => {
-            let _ = db_pool.close().await;
-            return Ok(Response {
-                resource_id: resource_id.to_string(),
-                success: false,
-                message: format!("Failed to load codes from S3: {}", e),
-                text_length: Some(pdf_text.len()),
-            });
+            return Err(StageError::new(ErrorCode::UnusablePdf, format!("Failed to extract text from PDF: {}", e)));
         }
     };
     
-    // Detect codes in the PDF text
-    let detected_codes = extract_codes(&pdf_text, &codes);
+    let (mut extraction, extracted) = check_extraction_quality(&pdf_bytes, &matcher, extraction);
+    drop(pdf_bytes);
+    let detected_codes = std::mem::take(&mut extraction.detected_codes);
     let codes_count = detected_codes.len();
     
     println!("Detected {} codes in PDF", codes_count);
     
+    // Page map, language and the PDF's own title/author/date, for pdf_content.metadata
+    let document = PdfDocument::from_extraction(extraction);
+    let mut document_metadata = document.metadata();
+    // Each detected code's description, count and byte offsets in pdf_text
+    let code_details: Vec<serde_json::Value> = matcher.detect(&document.text).into_iter()
+        .map(|detected| serde_json::json!({
+            "code": detected.code,
+            "description": detected.description,
+            "count": detected.count,
+            "positions": detected.positions,
+        }))
+        .collect();
+    document_metadata["codes"] = code_details.into();
+    println!("Document language: {}", document.language.unwrap_or("undetected"));
+    let page_offsets = document.page_offsets();
+    let pdf_text = document.text;
+    
+    let clarification_deadline = clarification::clarification_deadline(&pdf_text);
+    if let Some(deadline) = clarification_deadline {
+        println!("Clarification questions close {}", deadline);
+    }
+    
     // Ensure table exists
     println!("Ensuring table exists");
-    if let Err(e) = ensure_table_exists(&db_pool).await {
-        let _ = db_pool.close().await;
-        return Ok(Response {
-            resource_id: resource_id.to_string(),
-            success: false,
-            message: format!("Failed to ensure table exists: {}", e),
-            text_length: Some(pdf_text.len()),
-        });
-    }
+    timeouts.run(Call::Database, ensure_table_exists(db_pool)).await?
+        .map_err(|e| StageError::new(ErrorCode::Database, format!("Failed to ensure table exists: {}", e)))?;
     
     // Store in pdf_content table
     println!("Storing PDF content in database");
-    match store_pdf_content_with_codes(&db_pool, resource_id, &pdf_text, &detected_codes).await {
-        Ok(_) => {
-            println!("Successfully stored PDF content for resource_id: {}", resource_id);
-            let _ = db_pool.close().await;
-
-            // Only delete SQS message AFTER successful database storage
-            println!("Deleting SQS message after successful database storage");
-            if let Some(receipt_handle) = &sqs_message.receipt_handle {
-                // build a fresh SQS client using the same config so we don't re-use across threads
-                let sqs_client = SqsClient::new(&aws_config::defaults(aws_config::BehaviorVersion::latest()).load().await);
-                if let Ok(queue_url) = env::var("PDF_PROCESSING_QUEUE_URL") {
-                    match sqs_client
-                        .delete_message()
-                        .queue_url(queue_url)
-                        .receipt_handle(receipt_handle)
-                        .send()
-                        .await
-                    {
-                        Ok(_) => println!("SQS message deleted successfully"),
-                        Err(e) => println!("WARNING: Failed to delete SQS message: {}", e),
-                    }
-                }
-            }
+    let stored = store_pdf_content_with_codes(db_pool, resource_id, &pdf_text, &detected_codes, codes_source, &page_offsets, clarification_deadline, &extracted, &document_metadata);
+    if let Err(e) = timeouts.run(Call::Database, stored).await? {
+        println!("CRITICAL ERROR: Failed to store PDF content for resource_id {}: {}", resource_id, e);
+        
+        // Retryable, so the record is reported in batchItemFailures and comes back
+        return Err(StageError::new(ErrorCode::Database, format!("Failed to store PDF content: {}", e)));
+    }
+    println!("Successfully stored PDF content for resource_id: {}", resource_id);
+    
+    // Declared CPV codes the catalogue lacks, for the weekly report; never fails the tender, even when it times out
+    let _ = timeouts.run(Call::Database, record_unknown_codes(db_pool, resource_id, &pdf_text)).await;
 
-            // Update tender record with PDF processing results
-            tender_record.pdf_content = Some(pdf_text.clone());
-            tender_record.detected_codes = Some(detected_codes.clone());
-            tender_record.codes_count = Some(codes_count as i32);
-            
-            // INTELLIGENT ROUTING: Check PDF content quality to decide next step
-            let pdf_content_length = pdf_text.trim().len();
-            let min_pdf_threshold = 100; // Minimum characters for meaningful ML analysis
-            
-            if pdf_content_length < min_pdf_threshold {
-                // Route directly to AI Summary for title-only analysis
-                println!("PDF content too minimal ({} chars < {} threshold) - routing to AI Summary for title-only analysis", 
-                         pdf_content_length, min_pdf_threshold);
-                
-                tender_record.processing_stage = Some("ai_summary_title_only".to_string());
-                if let Err(e) = forward_to_ai_summary(&tender_record).await {
-                    println!("WARNING: Failed to forward to AI Summary queue: {}", e);
-                    // Don't fail the whole process if queue forwarding fails
-                }
-            } else {
-                // Route to ML prediction first (has substantial PDF content)
-                println!("PDF content substantial ({} chars >= {} threshold) - routing to ML prediction first", 
-                         pdf_content_length, min_pdf_threshold);
-                
-                tender_record.processing_stage = Some("ml_prediction".to_string());
-                if let Err(e) = forward_to_ml_prediction(&tender_record).await {
-                    println!("WARNING: Failed to forward to ML prediction queue: {}", e);
-                    // Don't fail the whole process if queue forwarding fails
-                }
-            }
+    // Update tender record with PDF processing results
+    tender_record.pdf_content = Some(pdf_text.clone());
+    tender_record.detected_codes = Some(detected_codes.clone());
+    tender_record.codes_count = Some(codes_count as i32);
+    tender_record.codes_source = (codes_source == codes::SOURCE_FALLBACK).then(|| codes_source.to_string());
+    
+    // INTELLIGENT ROUTING: Check PDF content quality to decide next step
+    let pdf_content_length = pdf_text.trim().len();
+    let min_pdf_threshold = 100; // Minimum characters for meaningful ML analysis
+    
+    let route = if pdf_content_length < min_pdf_threshold { "ai_summary_title_only" } else { "ml_prediction" };
+    
+    // Recorded before the hand-off so the next stage never sees the tender a step behind
+    let reached = if pdf_content_length < min_pdf_threshold { State::TitleOnly } else { State::PdfProcessed };
+    lifecycle::advance(db_pool, resource_id, reached, "pdf_processing").await;
+    let forwarded = if pdf_content_length < min_pdf_threshold {
+        // Route directly to AI Summary for title-only analysis
+        println!("PDF content too minimal ({} chars < {} threshold) - routing to AI Summary for title-only analysis", 
+                 pdf_content_length, min_pdf_threshold);
+        
+        forward_to_ai_summary(&tender_record, handoff).await
+    } else {
+        // Route to ML prediction first (has substantial PDF content)
+        println!("PDF content substantial ({} chars >= {} threshold) - routing to ML prediction first", 
+                 pdf_content_length, min_pdf_threshold);
+        
+        forward_to_ml_prediction(&tender_record, handoff).await
+    };
 
-            // Build success response
-            let response = Response {
-                resource_id: resource_id.to_string(),
-                success: true,
-                message: "Successfully processed PDF".to_string(),
-                text_length: Some(pdf_text.len()),
-            };
-
-            // Return success normally instead of exiting
-            println!("Lambda completed successfully, returning response");
-            Ok(response)
-        },
+    // Don't fail the whole process if queue forwarding fails - the content is stored and the
+    // stalled pipeline_status row lets pipeline_watchdog requeue it
+    match forwarded {
+        Ok(()) => {
+            let decision = Decision::new(resource_id, Kind::Routing, "pdf_processing", route)
+                .inputs(&[&pdf_text])
+                .version("min_pdf_chars", min_pdf_threshold.to_string())
+                .detail(serde_json::json!({
+                    "pdf_chars": pdf_content_length,
+                    "codes_count": codes_count,
+                    "codes_source": codes_source,
+                    "extraction_quality": extracted.quality,
+                    "extraction_method": extracted.method,
+                }));
+            decision_audit::record(db_pool, &decision).await;
+            pipeline_status::completed(db_pool, resource_id, Stage::PdfProcessing).await
+        }
         Err(e) => {
-            println!("CRITICAL ERROR: Failed to store PDF content for resource_id {}: {}", resource_id, e);
-            let _ = db_pool.close().await;
-            
-            // DO NOT delete SQS message on database failure - let it retry
-            println!("NOT deleting SQS message due to database storage failure - message will retry");
-            
-            Ok(Response {
-                resource_id: resource_id.to_string(),
-                success: false,
-                message: format!("Failed to store PDF content: {}", e),
-                text_length: Some(pdf_text.len()),
-            })
+            println!("WARNING: Failed to forward to next stage: {}", e);
+            pipeline_status::failed(db_pool, resource_id, Stage::PdfProcessing, &format!("Failed to forward: {}", e)).await;
         }
     }
+
+    println!("Lambda completed successfully, returning response");
+    Ok(Completed::new(resource_id, format!("Successfully processed PDF ({} characters)", pdf_text.len())))
 }
 
 async fn ensure_table_exists(pool: &Pool<Postgres>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS pdf_content (
-            resource_id BIGINT PRIMARY KEY,
-            pdf_text TEXT NOT NULL,
-            extraction_timestamp TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
-            processing_status TEXT NOT NULL,
-            metadata JSONB DEFAULT '{}'::JSONB,
-            detected_codes TEXT[],
-            codes_count INTEGER DEFAULT 0
-        )
-        "#
-    )
-    .execute(pool)
-    .await?;
+    db::tables::ensure_pdf_content(pool).await?;
     
     Ok(())
 }
 
+async fn record_unknown_codes(pool: &Pool<Postgres>, resource_id: i64, pdf_text: &str) {
+    let candidates = unknown_codes::candidates(pdf_text);
+    if candidates.is_empty() {
+        return;
+    }
+    if let Err(e) = unknown_codes::ensure_table(pool).await {
+        println!("WARNING: Failed to ensure unknown_codes_seen table: {}", e);
+        return;
+    }
+    match unknown_codes::record(pool, resource_id, &candidates).await {
+        Ok(0) => {}
+        Ok(counted) => println!("Counted {} declared CPV codes not in detection_codes", counted),
+        Err(e) => println!("WARNING: Failed to record unknown CPV codes: {}", e),
+    }
+}
+
+/// How the stored text was obtained
+struct Extracted {
+    quality: f32,
+    /// "text" for the PDF's text layer, "ocr" for the OCR fallback
+    method: &'static str,
+}
+
+/// Score the extracted text and, when it is below EXTRACTION_MIN_QUALITY, try OCR in builds
+/// with the `ocr` feature. Returns whichever extraction is kept
+fn check_extraction_quality(
+    pdf_bytes: &[u8],
+    matcher: &CodeMatcher,
+    extraction: StreamedExtraction,
+) -> (StreamedExtraction, Extracted) {
+    let min_quality = quality::min_quality_from_env();
+    let text_quality = quality::score(&extraction.text);
+    println!(
+        "Extraction quality {:.2} (dictionary words {:.2}, replacement chars {:.3}, average line {:.0} chars)",
+        text_quality.score, text_quality.dictionary_ratio, text_quality.replacement_ratio, text_quality.average_line_length
+    );
+    let text = Extracted { quality: text_quality.score, method: "text" };
+    if !text_quality.is_low(min_quality) {
+        return (extraction, text);
+    }
+    
+    #[cfg(feature = "ocr")]
+    if let Some((ocr, ocr_quality)) = ocr_fallback(pdf_bytes, &text_quality) {
+        let extraction = StreamedExtraction {
+            detected_codes: matcher.find_codes(&ocr.text),
+            text: ocr.text,
+            page_offsets: ocr.page_offsets,
+            ..extraction
+        };
+        return (extraction, Extracted { quality: ocr_quality.score, method: "ocr" });
+    }
+    #[cfg(not(feature = "ocr"))]
+    {
+        let _ = (pdf_bytes, matcher);
+        println!("WARNING: Extraction quality below {:.2} and this build has no OCR fallback", min_quality);
+    }
+    (extraction, text)
+}
+
+/// OCR a PDF whose text layer scored `text_quality`; the OCR text and its quality if it scores better
+#[cfg(feature = "ocr")]
+fn ocr_fallback(
+    pdf_bytes: &[u8],
+    text_quality: &quality::ExtractionQuality,
+) -> Option<(pdf_processing::ocr::OcrText, quality::ExtractionQuality)> {
+    use pdf_processing::ocr::{ocr_pdf, OcrOptions};
+
+    println!("Extraction quality {:.2} is low - trying OCR", text_quality.score);
+    let ocr = match ocr_pdf(pdf_bytes, &OcrOptions::from_env()) {
+        Ok(ocr) => ocr,
+        Err(e) => {
+            println!("WARNING: OCR fallback failed, keeping the text layer: {}", e);
+            return None;
+        }
+    };
+    let ocr_quality = quality::score(&ocr.text);
+    if ocr_quality.score <= text_quality.score {
+        println!("OCR text scored {:.2}, no better than the text layer - keeping the text layer", ocr_quality.score);
+        return None;
+    }
+
+    println!("Using OCR text ({} characters, quality {:.2})", ocr.text.len(), ocr_quality.score);
+    Some((ocr, ocr_quality))
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn store_pdf_content_with_codes(
     pool: &Pool<Postgres>, 
     resource_id: i64, 
     pdf_text: &str,
-    detected_codes: &[String]
+    detected_codes: &[String],
+    codes_source: &str,
+    page_offsets: &[usize],
+    clarification_deadline: Option<NaiveDateTime>,
+    extracted: &Extracted,
+    document_metadata: &serde_json::Value,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    fault_injection::check(Fault::DbWrite)?;
+    let page_offsets: Vec<i32> = page_offsets.iter().map(|&offset| offset as i32).collect();
     sqlx::query(
         r#"
         INSERT INTO pdf_content 
-        (resource_id, pdf_text, extraction_timestamp, processing_status, detected_codes, codes_count)
-        VALUES ($1, $2, CURRENT_TIMESTAMP, 'COMPLETED', $3, $4)
+        (resource_id, pdf_text, extraction_timestamp, processing_status, detected_codes, codes_count, page_offsets, clarification_deadline, metadata, extraction_quality, extraction_method)
+        VALUES ($1, $2, CURRENT_TIMESTAMP, 'COMPLETED', $3, $4, $5, $6, $10::JSONB || jsonb_build_object('codes_source', $7::TEXT), $8, $9)
         ON CONFLICT (resource_id) 
         DO UPDATE SET 
             pdf_text = EXCLUDED.pdf_text,
             extraction_timestamp = EXCLUDED.extraction_timestamp,
             processing_status = EXCLUDED.processing_status,
             detected_codes = EXCLUDED.detected_codes,
-            codes_count = EXCLUDED.codes_count
+            codes_count = EXCLUDED.codes_count,
+            page_offsets = EXCLUDED.page_offsets,
+            clarification_deadline = EXCLUDED.clarification_deadline,
+            metadata = COALESCE(pdf_content.metadata, '{}'::JSONB) || EXCLUDED.metadata,
+            extraction_quality = EXCLUDED.extraction_quality,
+            extraction_method = EXCLUDED.extraction_method
         "#
     )
     .bind(resource_id)
     .bind(pdf_text)
     .bind(detected_codes)
     .bind(detected_codes.len() as i32)
+    .bind(&page_offsets)
+    .bind(clarification_deadline)
+    .bind(codes_source)
+    .bind(extracted.quality)
+    .bind(extracted.method)
+    .bind(document_metadata)
     .execute(pool)
     .await?;
     
+    pdf_content_migration::mirror(pool, &pdf_content_migration::PdfContentRow {
+        resource_id,
+        pdf_text,
+        detected_codes,
+        page_offsets: &page_offsets,
+        clarification_deadline,
+        codes_source: Some(codes_source),
+        extraction_quality: Some(extracted.quality),
+        extraction_method: Some(extracted.method),
+        document_metadata: Some(document_metadata),
+    }).await;
+    
     Ok(())
 }
 
-async fn load_codes_from_s3() -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
-    println!("Initializing AWS config for S3");
-    let config = aws_config::defaults(aws_config::BehaviorVersion::latest()).load().await;
-    let s3_client = S3Client::new(&config);
+/// Render the first page and upload it to the lambda bucket for sns_notification to embed
+#[cfg(feature = "thumbnail")]
+async fn store_thumbnail(resource_id: i64, pdf_bytes: &[u8]) {
+    use pdf_processing::thumbnail::{render_first_page, thumbnail_key, ThumbnailOptions};
+
+    let png = match render_first_page(pdf_bytes, &ThumbnailOptions::from_env()) {
+        Ok(png) => png,
+        Err(e) => {
+            println!("WARNING: Failed to render thumbnail for resource_id {}: {}", resource_id, e);
+            return;
+        }
+    };
+
+    let aws = clients::aws().await;
+    let bucket = match aws.discovery.resolve(Resource::LambdaBucket).await {
+        Ok(bucket) => bucket,
+        Err(e) => {
+            println!("WARNING: Lambda bucket not found, skipping thumbnail: {}", e);
+            return;
+        }
+    };
+
+    let key = thumbnail_key(resource_id);
+    let size = png.len();
+    match aws.s3
+        .put_object()
+        .bucket(&bucket)
+        .key(&key)
+        .content_type("image/png")
+        .body(png.into())
+        .send()
+        .await
+    {
+        Ok(_) => println!("Stored {} byte thumbnail at s3://{}/{}", size, bucket, key),
+        Err(e) => println!("WARNING: Failed to upload thumbnail for resource_id {}: {}", resource_id, e),
+    }
+}
+
+/// Active codes from the detection_codes table, falling back to codes.txt in S3 until the table is seeded,
+/// and to the compiled-in IT codes when codes.txt is missing too. Returns the matcher and where its codes came from
+async fn load_code_matcher(pool: &Pool<Postgres>) -> Result<(Arc<CodeMatcher>, &'static str), Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(matcher) = codes::cached_matcher(pool).await? {
+        println!("Using {} detection codes from database", matcher.len());
+        return Ok((matcher, codes::SOURCE_CONFIGURED));
+    }
+
+    println!("WARNING: detection_codes table has no active codes - falling back to codes.txt in S3");
+    match load_codes_from_s3().await {
+        Ok(codes) if !codes.is_empty() => {
+            println!("Loaded {} codes from S3", codes.len());
+            Ok((Arc::new(CodeMatcher::with_descriptions(&codes)), codes::SOURCE_CONFIGURED))
+        }
+        Ok(_) => {
+            println!("WARNING: codes.txt in S3 is empty - using {} fallback IT codes", codes::FALLBACK_CODES.len());
+            Ok((codes::fallback_matcher(), codes::SOURCE_FALLBACK))
+        }
+        // Retrying can't fix a missing file, so carry on rather than loop the message
+        Err(e) => {
+            println!("WARNING: Failed to load codes.txt from S3 ({}) - using {} fallback IT codes", e, codes::FALLBACK_CODES.len());
+            Ok((codes::fallback_matcher(), codes::SOURCE_FALLBACK))
+        }
+    }
+}
+
+async fn load_codes_from_s3() -> Result<Vec<codes::DetectionCode>, Box<dyn std::error::Error + Send + Sync>> {
+    let aws = clients::aws().await;
     
     // Get S3 bucket and key from environment variables
-    let bucket = match env::var("LAMBDA_BUCKET") {
+    let bucket = match aws.discovery.resolve(Resource::LambdaBucket).await {
         Ok(b) => {
-            println!("LAMBDA_BUCKET found: {}", b);
+            println!("Lambda bucket resolved: {}", b);
             b
         },
         Err(e) => {
-            println!("ERROR: LAMBDA_BUCKET not found: {:?}", e);
-            return Err("LAMBDA_BUCKET environment variable not set".into());
+            println!("ERROR: Lambda bucket not found: {:?}", e);
+            return Err(format!("Lambda bucket could not be resolved: {}", e).into());
         }
     };
     let key = "codes.txt";
     
     println!("Fetching codes from s3://{}/{}", bucket, key);
     
-    let response = s3_client
-        .get_object()
-        .bucket(bucket)
-        .key(key)
-        .send()
+    let response = Policy::aws("s3_get")
+        .run(|| async {
+            if let Err(injected) = fault_injection::check(Fault::S3Timeout) {
+                return Err(SdkError::timeout_error(injected));
+            }
+            aws.s3.get_object().bucket(&bucket).key(key).send().await
+        })
         .await?;
     
     let body = response.body.collect().await?;
     let codes_text = String::from_utf8(body.into_bytes().to_vec())?;
     
-    let codes: Vec<String> = codes_text
-        .lines()
-        .filter_map(|line| line.split(',').next())
-        .map(|code| code.trim().to_string())
-        .filter(|code| !code.is_empty())
-        .collect();
-    
-    Ok(codes)
+    Ok(codes::parse_codes_file(&codes_text))
 }
 
-async fn forward_to_ml_prediction(tender_record: &TenderRecord) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+async fn forward_to_ml_prediction(tender_record: &TenderRecord, handoff: &Handoff) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     println!("Forwarding tender record {} to ML prediction queue", tender_record.resource_id);
     
-    // Get ML prediction queue URL
-    let ml_queue_url = env::var("ML_PREDICTION_QUEUE_URL")
-        .map_err(|_| "ML_PREDICTION_QUEUE_URL environment variable not set")?;
-    
-    // Initialize SQS client
-    let config = aws_config::defaults(aws_config::BehaviorVersion::latest()).load().await;
-    let sqs_client = SqsClient::new(&config);
+    if handoff.capture(Stage::MlPrediction, tender_record) {
+        println!("Orchestrated by Step Functions - returning record {} for ML prediction", tender_record.resource_id);
+        return Ok(());
+    }
     
-    // Add processing stage marker
-    let mut record_with_stage = serde_json::to_value(tender_record)?;
-    record_with_stage["processing_stage"] = serde_json::Value::String("ml_prediction".to_string());
-    let message_body = record_with_stage.to_string();
+    // The body is the record as is; the stage marker goes in the message attributes
+    let message_body = serde_json::to_string(tender_record)?;
+    let routing = handoff.routing(Stage::MlPrediction.name());
     
-    // Send message
-    match sqs_client
-        .send_message()
-        .queue_url(&ml_queue_url)
-        .message_body(message_body)
-        .send()
+    // Send message, to the queue or through the pipeline topic
+    match clients::aws().await.publisher
+        .send(Resource::MlPredictionQueue, &message_body, &routing)
         .await
     {
-        Ok(resp) => {
+        Ok(message_id) => {
             println!("Successfully forwarded record {} to ML prediction queue (message ID: {})", 
                     tender_record.resource_id, 
-                    resp.message_id().unwrap_or_default());
+                    message_id);
             Ok(())
         },
         Err(e) => {
-            println!("Failed to forward record {} to ML prediction queue: {}", 
+            println!("Failed to forward record {} to ML prediction queue: {:#}", 
                     tender_record.resource_id, e);
-            Err(Box::new(e))
+            Err(e.into())
         }
     }
 }
 
-async fn forward_to_ai_summary(tender_record: &TenderRecord) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+async fn forward_to_ai_summary(tender_record: &TenderRecord, handoff: &Handoff) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     println!("Forwarding tender record {} to AI Summary queue for title-only analysis", tender_record.resource_id);
     
-    // Get AI Summary queue URL
-    let ai_queue_url = env::var("AI_SUMMARY_QUEUE_URL")
-        .map_err(|_| "AI_SUMMARY_QUEUE_URL environment variable not set")?;
-    
-    // Initialize SQS client
-    let config = aws_config::defaults(aws_config::BehaviorVersion::latest()).load().await;
-    let sqs_client = SqsClient::new(&config);
-    
-    // Create AI Summary message format
-    // This matches the AISummaryMessage struct expected by ai_summary lambda
-    let ai_message = serde_json::json!({
-        "resource_id": tender_record.resource_id.to_string(),
-        "tender_title": tender_record.title,
-        "ml_prediction": {
-            "should_bid": false, // Default for title-only processing
-            "confidence": 0.0,
-            "reasoning": "Title-only analysis - no PDF content available",
-            "feature_scores": {
-                "codes_count_score": 0.0,
-                "has_codes_score": 0.0,
-                "title_length_score": 0.0,
-                "ca_score": 0.0,
-                "text_features_score": 0.0,
-                "total_score": 0.0
-            }
+    // ML is skipped, so the message carries a neutral prediction
+    let ai_message = AISummaryMessage {
+        resource_id: tender_record.resource_id.to_string(),
+        tender_title: tender_record.title.clone(),
+        ml_prediction: MLPredictionResult {
+            should_bid: false, // Default for title-only processing
+            confidence: 0.0,
+            reasoning: "Title-only analysis - no PDF content available".to_string(),
+            feature_scores: Default::default(),
         },
-        "pdf_content": tender_record.pdf_content.as_ref().unwrap_or(&String::new()).clone(),
-        "priority": "NORMAL", // Title-only gets normal priority
-        "timestamp": chrono::Utc::now().to_rfc3339()
-    });
-    
-    let message_body = ai_message.to_string();
-    
-    // Send message
-    match sqs_client
-        .send_message()
-        .queue_url(&ai_queue_url)
-        .message_body(message_body)
-        .send()
+        pdf_content: tender_record.pdf_content.clone().unwrap_or_default(),
+        priority: "NORMAL".to_string(), // Title-only gets normal priority
+        timestamp: chrono::Utc::now(),
+        ..Default::default()
+    };
+    
+    if handoff.capture(Stage::AiSummary, &ai_message) {
+        println!("Orchestrated by Step Functions - returning record {} for AI Summary", tender_record.resource_id);
+        return Ok(());
+    }
+    
+    let message_body = serde_json::to_string(&ai_message)?;
+    let routing = handoff.routing("ai_summary_title_only").priority("NORMAL");
+    
+    // Send message, to the queue or through the pipeline topic
+    match clients::aws().await.publisher
+        .send(Resource::AiSummaryQueue, &message_body, &routing)
         .await
     {
-        Ok(resp) => {
+        Ok(message_id) => {
             println!("Successfully forwarded record {} to AI Summary queue (message ID: {})", 
                     tender_record.resource_id, 
-                    resp.message_id().unwrap_or_default());
+                    message_id);
             Ok(())
         },
         Err(e) => {
-            println!("Failed to forward record {} to AI Summary queue: {}", 
+            println!("Failed to forward record {} to AI Summary queue: {:#}", 
                     tender_record.resource_id, e);
-            Err(Box::new(e))
+            Err(e.into())
         }
     }
 }
@@ -536,6 +720,6 @@ async fn main() -> Result<(), Error> {
             println!("  {}: {}", key, value);
         }
     }
-    println!("=== Starting lambda runtime ===");
-    run(service_fn(function_handler)).await
+    println!("=== Starting stage runtime ===");
+    stage_runtime::run(PdfProcessing).await
 }
\ No newline at end of file
@@ -0,0 +1,158 @@
+//! OCR fallback for tender PDFs whose text layer is missing or garbled.
+//!
+//! Pages are rendered with Ghostscript, as for thumbnails, and read with Tesseract (a
+//! Lambda layer provides both under `/opt/bin`), so the module is behind the `ocr`
+//! feature. pdf_processing only calls it when `quality::score` rates the text layer
+//! below `EXTRACTION_MIN_QUALITY`, and keeps whichever text scores better.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static TEMP_DIRS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrOptions {
+    pub ghostscript: String,
+    pub tesseract: String,
+    /// Tesseract language(s), e.g. "eng" or "eng+gle"
+    pub language: String,
+    pub dpi: u32,
+    /// Pages read, from the first; OCR takes a few seconds a page
+    pub max_pages: usize,
+}
+
+impl Default for OcrOptions {
+    fn default() -> Self {
+        Self {
+            ghostscript: "gs".to_string(),
+            tesseract: "tesseract".to_string(),
+            language: "eng".to_string(),
+            dpi: 200,
+            max_pages: 20,
+        }
+    }
+}
+
+impl OcrOptions {
+    /// Defaults overridden by GHOSTSCRIPT_PATH, TESSERACT_PATH, OCR_LANGUAGE, OCR_DPI and OCR_MAX_PAGES
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let text = |var: &str, default: String| {
+            std::env::var(var)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+                .unwrap_or(default)
+        };
+        let number = |var: &str| {
+            std::env::var(var)
+                .ok()
+                .and_then(|v| v.trim().parse::<usize>().ok())
+                .filter(|n| *n > 0)
+        };
+        Self {
+            ghostscript: text("GHOSTSCRIPT_PATH", defaults.ghostscript),
+            tesseract: text("TESSERACT_PATH", defaults.tesseract),
+            language: text("OCR_LANGUAGE", defaults.language),
+            dpi: number("OCR_DPI").map_or(defaults.dpi, |dpi| dpi as u32),
+            max_pages: number("OCR_MAX_PAGES").unwrap_or(defaults.max_pages),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OcrText {
+    pub text: String,
+    /// Byte offset in `text` where each page starts, as for `StreamedExtraction`
+    pub page_offsets: Vec<usize>,
+}
+
+fn run(command: &mut Command, program: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(output.stdout)
+}
+
+/// OCR the first `max_pages` pages
+pub fn ocr_pdf(
+    pdf_bytes: &[u8],
+    options: &OcrOptions,
+) -> Result<OcrText, Box<dyn std::error::Error>> {
+    // /tmp is the only writable path on Lambda
+    let dir = std::env::temp_dir().join(format!(
+        "ocr-{}-{}",
+        std::process::id(),
+        TEMP_DIRS.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir)?;
+    let result = ocr_in(&dir, pdf_bytes, options);
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+fn ocr_in(
+    dir: &Path,
+    pdf_bytes: &[u8],
+    options: &OcrOptions,
+) -> Result<OcrText, Box<dyn std::error::Error>> {
+    let pdf = dir.join("input.pdf");
+    std::fs::write(&pdf, pdf_bytes)?;
+
+    run(
+        Command::new(&options.ghostscript)
+            .args([
+                "-q",
+                "-dSAFER",
+                "-dBATCH",
+                "-dNOPAUSE",
+                "-sDEVICE=pnggray",
+                "-dFirstPage=1",
+                &format!("-dLastPage={}", options.max_pages),
+                &format!("-r{}", options.dpi),
+            ])
+            .arg(format!(
+                "-sOutputFile={}",
+                dir.join("page-%04d.png").display()
+            ))
+            .arg(&pdf),
+        &options.ghostscript,
+    )?;
+
+    let mut pages: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+        .collect();
+    pages.sort();
+    if pages.is_empty() {
+        return Err("Ghostscript rendered no pages".into());
+    }
+
+    let mut ocr = OcrText {
+        text: String::new(),
+        page_offsets: Vec::with_capacity(pages.len()),
+    };
+    for page in &pages {
+        ocr.page_offsets.push(ocr.text.len());
+        let text = run(
+            Command::new(&options.tesseract)
+                .arg(page)
+                .arg("stdout")
+                .args(["-l", &options.language]),
+            &options.tesseract,
+        )?;
+        ocr.text.push_str(&String::from_utf8_lossy(&text));
+        ocr.text.push('\n');
+    }
+    Ok(ocr)
+}
@@ -0,0 +1,145 @@
+//! Cutover of `pdf_content` to `pdf_content_next`, keyed by a BIGINT `resource_id`.
+//!
+//! Older deployments created `pdf_content` with a TEXT `resource_id`, and every reader
+//! has to cast around it. pdf_processing and get_data mirror each row they store into
+//! the new table according to `PDF_CONTENT_MIGRATION` (see `db::migration`). Both reads
+//! compare the key as text, so verification works whichever type the legacy column has.
+
+use chrono::NaiveDateTime;
+use db::migration::{self, Mode};
+use sqlx::PgPool;
+
+pub const COMPONENT: &str = "pdf_content";
+
+/// What the storage helpers write, to mirror into the new table
+pub struct PdfContentRow<'a> {
+    pub resource_id: i64,
+    pub pdf_text: &'a str,
+    pub detected_codes: &'a [String],
+    pub page_offsets: &'a [i32],
+    pub clarification_deadline: Option<NaiveDateTime>,
+    /// Recorded in `metadata`; None leaves it empty
+    pub codes_source: Option<&'a str>,
+    /// From `quality::score`, and "text" or "ocr"; None where the writer doesn't score
+    pub extraction_quality: Option<f32>,
+    pub extraction_method: Option<&'a str>,
+    /// `PdfDocument::metadata`, merged into `metadata`; None where the writer doesn't build one
+    pub document_metadata: Option<&'a serde_json::Value>,
+}
+
+const NEW_READ: &str = r#"
+    SELECT jsonb_build_object(
+        'resource_id', resource_id::TEXT,
+        'pdf_text_md5', md5(pdf_text),
+        'processing_status', processing_status,
+        'detected_codes', to_jsonb(detected_codes),
+        'codes_count', codes_count,
+        'page_offsets', to_jsonb(page_offsets),
+        'clarification_deadline', clarification_deadline,
+        'codes_source', metadata->>'codes_source',
+        'extraction_quality', extraction_quality,
+        'extraction_method', extraction_method
+    )
+    FROM pdf_content_next WHERE resource_id::TEXT = $1
+"#;
+
+fn legacy_read() -> String {
+    NEW_READ.replace("FROM pdf_content_next", "FROM pdf_content")
+}
+
+pub async fn ensure_new_table(pool: &PgPool) -> anyhow::Result<()> {
+    db::ensure_schema(pool, "pdf_content_next", 2, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS pdf_content_next (
+                resource_id BIGINT PRIMARY KEY,
+                pdf_text TEXT NOT NULL,
+                extraction_timestamp TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+                processing_status TEXT NOT NULL,
+                metadata JSONB DEFAULT '{}'::JSONB,
+                detected_codes TEXT[],
+                codes_count INTEGER DEFAULT 0,
+                page_offsets INTEGER[],
+                clarification_deadline TIMESTAMP
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            "ALTER TABLE pdf_content_next ADD COLUMN IF NOT EXISTS extraction_quality REAL",
+        )
+        .execute(pool)
+        .await?;
+        sqlx::query("ALTER TABLE pdf_content_next ADD COLUMN IF NOT EXISTS extraction_method TEXT")
+            .execute(pool)
+            .await?;
+
+        environment::Environment::ensure_environment_column(pool, "pdf_content_next").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+async fn write_new(pool: &PgPool, row: &PdfContentRow<'_>) -> anyhow::Result<()> {
+    ensure_new_table(pool).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO pdf_content_next
+        (resource_id, pdf_text, extraction_timestamp, processing_status, detected_codes, codes_count, page_offsets, clarification_deadline, metadata, extraction_quality, extraction_method)
+        VALUES ($1, $2, CURRENT_TIMESTAMP, 'COMPLETED', $3, $4, $5, $6, COALESCE($10::JSONB, '{}'::JSONB) || jsonb_strip_nulls(jsonb_build_object('codes_source', $7::TEXT)), $8, $9)
+        ON CONFLICT (resource_id) DO UPDATE SET
+            pdf_text = EXCLUDED.pdf_text,
+            extraction_timestamp = EXCLUDED.extraction_timestamp,
+            processing_status = EXCLUDED.processing_status,
+            detected_codes = EXCLUDED.detected_codes,
+            codes_count = EXCLUDED.codes_count,
+            page_offsets = EXCLUDED.page_offsets,
+            clarification_deadline = EXCLUDED.clarification_deadline,
+            metadata = COALESCE(pdf_content_next.metadata, '{}'::JSONB) || EXCLUDED.metadata,
+            extraction_quality = EXCLUDED.extraction_quality,
+            extraction_method = EXCLUDED.extraction_method
+        "#,
+    )
+    .bind(row.resource_id)
+    .bind(row.pdf_text)
+    .bind(row.detected_codes)
+    .bind(row.detected_codes.len() as i32)
+    .bind(row.page_offsets)
+    .bind(row.clarification_deadline)
+    .bind(row.codes_source)
+    .bind(row.extraction_quality)
+    .bind(row.extraction_method)
+    .bind(row.document_metadata)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Mirror a row just written to `pdf_content`, and compare the two in verify mode.
+/// Best-effort: a failure is logged and never fails the legacy write
+pub async fn mirror(pool: &PgPool, row: &PdfContentRow<'_>) {
+    let mode = Mode::from_env(COMPONENT);
+    if !mode.writes_new() {
+        return;
+    }
+
+    if let Err(e) = write_new(pool, row).await {
+        println!(
+            "WARNING: Failed to dual-write pdf_content_next for resource_id {}: {}",
+            row.resource_id, e
+        );
+        return;
+    }
+
+    if mode.verifies() {
+        let key = row.resource_id.to_string();
+        if let Err(e) = migration::verify(pool, COMPONENT, &key, &legacy_read(), NEW_READ).await {
+            println!(
+                "WARNING: Failed to verify pdf_content migration for resource_id {}: {}",
+                row.resource_id, e
+            );
+        }
+    }
+}
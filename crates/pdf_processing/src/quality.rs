@@ -0,0 +1,120 @@
+//! How trustworthy an extracted text is, from a few cheap signals.
+//!
+//! Scanned tenders come out empty, and PDFs with broken font maps come out as symbol
+//! soup that still has plenty of characters. The score (0 to 1) combines the share of
+//! words found in a small English/tender vocabulary, the share of replacement and
+//! private-use characters, and the average line length (text split one character per
+//! line is a common failure). It is stored in `pdf_content.extraction_quality`; below
+//! `min_quality_from_env` the OCR fallback is tried and ai_summary warns Claude that the
+//! text may be garbled.
+
+/// Below this a text counts as low quality, unless `EXTRACTION_MIN_QUALITY` says otherwise
+pub const DEFAULT_MIN_QUALITY: f32 = 0.5;
+
+/// A share of vocabulary words at or above this scores full marks; running English
+/// text is usually 35-50% its most common words
+const GOOD_DICTIONARY_RATIO: f32 = 0.25;
+/// A share of replacement characters at or above this scores zero
+const BAD_REPLACEMENT_RATIO: f32 = 0.05;
+/// Average line lengths from here up score full marks
+const GOOD_LINE_LENGTH: f32 = 20.0;
+
+const WEIGHT_DICTIONARY: f32 = 0.6;
+const WEIGHT_REPLACEMENT: f32 = 0.25;
+const WEIGHT_LINE_LENGTH: f32 = 0.15;
+
+/// Common English words plus words every tender uses, whitespace separated
+const VOCABULARY: &str = "\
+    a about after all also an and any are as at be been before but by can contract \
+    contracting date do each for from has have if in into is it its may more must no not \
+    of on one only or other our out per provide provided shall should such than that the \
+    their then there these this those through to under up upon was we were what when where \
+    which who will with within would you your authority award bid criteria deadline \
+    details document documents information lot notice procurement public requirements \
+    response service services submission supplier suppliers tender tenderer tenderers \
+    tenders work";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractionQuality {
+    /// 0 (unusable) to 1 (clean text)
+    pub score: f32,
+    /// Share of words found in the vocabulary
+    pub dictionary_ratio: f32,
+    /// Share of characters that are U+FFFD, private-use or stray control characters
+    pub replacement_ratio: f32,
+    /// Average length in characters of the non-empty lines
+    pub average_line_length: f32,
+}
+
+impl ExtractionQuality {
+    pub fn is_low(&self, min_quality: f32) -> bool {
+        self.score < min_quality
+    }
+}
+
+pub fn min_quality_from_env() -> f32 {
+    std::env::var("EXTRACTION_MIN_QUALITY")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|min: &f32| (0.0..=1.0).contains(min))
+        .unwrap_or(DEFAULT_MIN_QUALITY)
+}
+
+fn is_replacement(c: char) -> bool {
+    c == '\u{FFFD}'
+        || ('\u{E000}'..='\u{F8FF}').contains(&c)
+        || (c.is_control() && !matches!(c, '\n' | '\r' | '\t' | '\u{0C}'))
+}
+
+pub fn score(text: &str) -> ExtractionQuality {
+    let (mut chars, mut replacements) = (0usize, 0usize);
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        chars += 1;
+        if is_replacement(c) {
+            replacements += 1;
+        }
+    }
+    if chars == 0 {
+        return ExtractionQuality {
+            score: 0.0,
+            dictionary_ratio: 0.0,
+            replacement_ratio: 0.0,
+            average_line_length: 0.0,
+        };
+    }
+
+    let (mut words, mut known) = (0usize, 0usize);
+    for word in text.split_whitespace() {
+        let word = word
+            .trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase();
+        if word.is_empty() {
+            continue;
+        }
+        words += 1;
+        if VOCABULARY.split_whitespace().any(|known| known == word) {
+            known += 1;
+        }
+    }
+
+    let lines: Vec<usize> = text
+        .lines()
+        .map(|line| line.trim().chars().count())
+        .filter(|len| *len > 0)
+        .collect();
+
+    let dictionary_ratio = known as f32 / words.max(1) as f32;
+    let replacement_ratio = replacements as f32 / chars as f32;
+    let average_line_length = lines.iter().sum::<usize>() as f32 / lines.len().max(1) as f32;
+
+    let score = WEIGHT_DICTIONARY * (dictionary_ratio / GOOD_DICTIONARY_RATIO).min(1.0)
+        + WEIGHT_REPLACEMENT * (1.0 - (replacement_ratio / BAD_REPLACEMENT_RATIO).min(1.0))
+        + WEIGHT_LINE_LENGTH * (average_line_length / GOOD_LINE_LENGTH).min(1.0);
+
+    ExtractionQuality {
+        score,
+        dictionary_ratio,
+        replacement_ratio,
+        average_line_length,
+    }
+}
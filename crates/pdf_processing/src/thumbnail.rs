@@ -0,0 +1,106 @@
+//! PNG preview of a tender PDF's first page, embedded inline in notification emails.
+//!
+//! Rendering shells out to Ghostscript (a Lambda layer provides `/opt/bin/gs`), so the
+//! module is behind the `thumbnail` feature; builds without it skip previews. The PNG is
+//! stored in the lambda bucket under `thumbnail_key`, where sns_notification looks for it.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Larger renders are dropped rather than bloating every email
+pub const MAX_THUMBNAIL_BYTES: usize = 1024 * 1024;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// S3 key of a tender's thumbnail in the lambda bucket
+pub fn thumbnail_key(resource_id: i64) -> String {
+    format!("thumbnails/{}.png", resource_id)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThumbnailOptions {
+    pub ghostscript: String,
+    /// 40 dpi renders an A4 page at roughly 330 x 470 pixels
+    pub dpi: u32,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        Self {
+            ghostscript: "gs".to_string(),
+            dpi: 40,
+        }
+    }
+}
+
+impl ThumbnailOptions {
+    /// Defaults overridden by GHOSTSCRIPT_PATH and THUMBNAIL_DPI
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            ghostscript: std::env::var("GHOSTSCRIPT_PATH")
+                .ok()
+                .filter(|path| !path.trim().is_empty())
+                .unwrap_or(defaults.ghostscript),
+            dpi: std::env::var("THUMBNAIL_DPI")
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+                .filter(|dpi| *dpi > 0)
+                .unwrap_or(defaults.dpi),
+        }
+    }
+}
+
+/// Render the first page to PNG bytes
+pub fn render_first_page(
+    pdf_bytes: &[u8],
+    options: &ThumbnailOptions,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    // Ghostscript needs a seekable file for PDF input; /tmp is the only writable path on Lambda
+    let path = std::env::temp_dir().join(format!(
+        "thumbnail-{}-{}.pdf",
+        std::process::id(),
+        TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::File::create(&path)?.write_all(pdf_bytes)?;
+
+    let output = Command::new(&options.ghostscript)
+        .args([
+            "-q",
+            "-dSAFER",
+            "-dBATCH",
+            "-dNOPAUSE",
+            "-sDEVICE=png16m",
+            "-dFirstPage=1",
+            "-dLastPage=1",
+            "-dTextAlphaBits=4",
+            "-dGraphicsAlphaBits=4",
+            &format!("-r{}", options.dpi),
+            "-sOutputFile=-",
+        ])
+        .arg(&path)
+        .stdin(Stdio::null())
+        .output();
+    let _ = std::fs::remove_file(&path);
+    let output = output.map_err(|e| format!("Failed to run {}: {}", options.ghostscript, e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Ghostscript exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    if !output.stdout.starts_with(PNG_SIGNATURE) {
+        return Err("Ghostscript did not produce a PNG".into());
+    }
+    if output.stdout.len() > MAX_THUMBNAIL_BYTES {
+        return Err(format!("Thumbnail too large ({} bytes)", output.stdout.len()).into());
+    }
+
+    Ok(output.stdout)
+}
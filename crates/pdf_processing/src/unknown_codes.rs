@@ -0,0 +1,90 @@
+//! CPV codes declared in tender notices that `detection_codes` doesn't know.
+//!
+//! Each processed PDF's declared codes (see `cpv::declared_codes`) that aren't in the
+//! catalogue, active or not, are counted in `unknown_codes_seen`: one per tender, with
+//! when the code was first and last seen. weekly_report lists the ones seen most often as
+//! candidates for `ops_cli codes add`; adding a code stops it being counted.
+
+use crate::cpv;
+use environment::Environment;
+use sqlx::PgPool;
+
+/// CPV divisions run from 03 (agriculture) to 98 (other community services)
+const FIRST_DIVISION: u8 = 3;
+const LAST_DIVISION: u8 = 98;
+
+pub async fn ensure_table(pool: &PgPool) -> anyhow::Result<()> {
+    db::ensure_schema(pool, "unknown_codes_seen", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS unknown_codes_seen (
+                code TEXT PRIMARY KEY,
+                tenders INTEGER NOT NULL DEFAULT 1,
+                first_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                last_resource_id BIGINT
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+        Environment::ensure_environment_column(pool, "unknown_codes_seen").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Declared codes in the text that are CPV-shaped: eight digits with a check digit, in a
+/// CPV division. Dates and reference numbers that happen to look alike are left out
+pub fn candidates(text: &str) -> Vec<String> {
+    cpv::declared_codes(text)
+        .into_iter()
+        .filter(|code| {
+            code.get(..2)
+                .and_then(|division| division.parse::<u8>().ok())
+                .is_some_and(|division| (FIRST_DIVISION..=LAST_DIVISION).contains(&division))
+        })
+        .collect()
+}
+
+/// Count the candidates that aren't in `detection_codes` as seen in this tender; a
+/// retry of the same tender isn't counted twice. Returns how many were counted
+pub async fn record(
+    pool: &PgPool,
+    resource_id: i64,
+    candidates: &[String],
+) -> Result<u64, sqlx::Error> {
+    if candidates.is_empty() {
+        return Ok(0);
+    }
+    let result = sqlx::query(
+        r#"
+        INSERT INTO unknown_codes_seen (code, last_resource_id)
+        SELECT seen.code, $2
+        FROM unnest($1::TEXT[]) AS seen(code)
+        WHERE NOT EXISTS (SELECT 1 FROM detection_codes d WHERE d.code = seen.code)
+        ON CONFLICT (code) DO UPDATE SET
+            tenders = unknown_codes_seen.tenders + 1,
+            last_seen_at = NOW(),
+            last_resource_id = EXCLUDED.last_resource_id
+        WHERE unknown_codes_seen.last_resource_id IS DISTINCT FROM EXCLUDED.last_resource_id
+        "#,
+    )
+    .bind(candidates)
+    .bind(resource_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates_are_cpv_shaped() {
+        let text = "CPV: 72212000-4 Programming services; 48000000-8 Software. \
+                    Ref 00123456-1, order 99000000-2, phone 01234567890-1";
+        assert_eq!(candidates(text), vec!["72212000", "48000000"]);
+    }
+}
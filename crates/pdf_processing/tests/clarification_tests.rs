@@ -0,0 +1,51 @@
+use chrono::NaiveDate;
+use pdf_processing::clarification::clarification_deadline;
+
+fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> chrono::NaiveDateTime {
+    NaiveDate::from_ymd_opt(year, month, day)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+}
+
+#[test]
+fn test_clarification_deadline_formats() {
+    let text = "Tender closing date: 28/03/2025 at 12:00.\n\
+                The deadline for receipt of clarification questions is 14/03/2025 at 15:30.";
+    assert_eq!(clarification_deadline(text), Some(at(2025, 3, 14, 15, 30)));
+
+    let text = "Requests for Clarification must be received no later than \
+                5pm on Friday, 7th of March 2025.";
+    // The time comes before the date here, so the day's default applies
+    assert_eq!(clarification_deadline(text), Some(at(2025, 3, 7, 12, 0)));
+
+    let text = "Clarifications: 3 Apr. 2025 (12 noon)";
+    assert_eq!(clarification_deadline(text), Some(at(2025, 4, 3, 12, 0)));
+
+    let text = "Last date for clarification requests 21.02.2025 3 p.m.";
+    assert_eq!(clarification_deadline(text), Some(at(2025, 2, 21, 15, 0)));
+}
+
+#[test]
+fn test_questions_need_a_deadline_cue() {
+    // A date after "questions" is only taken when it reads as a deadline
+    let text = "Questions about Lot 2 were answered on 10/01/2025.";
+    assert_eq!(clarification_deadline(text), None);
+
+    let text = "All queries must be submitted by 10/01/2025 at 17:00.";
+    assert_eq!(clarification_deadline(text), Some(at(2025, 1, 10, 17, 0)));
+}
+
+#[test]
+fn test_no_clarification_deadline() {
+    assert_eq!(
+        clarification_deadline("Submissions close on 28/03/2025 at 12:00."),
+        None
+    );
+    // Not a real date
+    assert_eq!(
+        clarification_deadline("Clarification deadline: 31/02/2025"),
+        None
+    );
+    assert_eq!(clarification_deadline(""), None);
+}
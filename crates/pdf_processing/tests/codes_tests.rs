@@ -0,0 +1,68 @@
+use pdf_processing::codes::{
+    FALLBACK_CODES, fallback_matcher, fallback_metric_line, parse_codes_file,
+};
+
+#[test]
+fn test_parse_codes_file_keeps_descriptions() {
+    let content = "72000000,IT services - consulting\n\n  48000000 , Software package, and systems \n79000000\n";
+
+    let codes = parse_codes_file(content);
+
+    assert_eq!(codes.len(), 3);
+    assert_eq!(codes[0].code, "72000000");
+    assert_eq!(
+        codes[0].description.as_deref(),
+        Some("IT services - consulting")
+    );
+    // Only the first comma separates code from description
+    assert_eq!(codes[1].code, "48000000");
+    assert_eq!(
+        codes[1].description.as_deref(),
+        Some("Software package, and systems")
+    );
+    assert_eq!(codes[2].description, None);
+    assert!(codes.iter().all(|c| c.active && c.category.is_none()));
+}
+
+#[test]
+fn test_parse_bundled_codes_file() {
+    let content = std::fs::read_to_string("codes.txt").expect("codes.txt next to Cargo.toml");
+
+    let codes = parse_codes_file(&content);
+
+    assert!(!codes.is_empty());
+    assert!(codes.iter().all(|c| !c.code.contains(',')));
+}
+
+#[test]
+fn test_fallback_codes_are_configured_it_codes() {
+    let content = std::fs::read_to_string("codes.txt").expect("codes.txt next to Cargo.toml");
+    let configured: Vec<String> = parse_codes_file(&content)
+        .into_iter()
+        .map(|c| c.code)
+        .collect();
+
+    assert!(
+        FALLBACK_CODES
+            .iter()
+            .all(|code| code.starts_with("72") && configured.iter().any(|c| c == code))
+    );
+
+    let matcher = fallback_matcher();
+    assert_eq!(
+        matcher.find_codes("Services under CPV 72200000 and 72000000"),
+        vec!["72000000", "72200000"]
+    );
+}
+
+#[test]
+fn test_fallback_metric_line() {
+    let line = fallback_metric_line("prod", 5850990);
+
+    assert_eq!(
+        line["_aws"]["CloudWatchMetrics"][0]["Metrics"][0]["Name"],
+        "DetectionCodesFallback"
+    );
+    assert_eq!(line["DetectionCodesFallback"], 1);
+    assert_eq!(line["ResourceId"], 5850990);
+}
@@ -0,0 +1,112 @@
+use pdf_processing::CodeMatcher;
+use pdf_processing::codes::DetectionCode;
+use pdf_processing::cpv::{
+    DetectedCode, Mention, declared_codes, mentions, non_it_division, normalize, propose_no_bid,
+};
+
+#[test]
+fn test_declared_codes() {
+    let text = "Main CPV: 45000000-7 Construction work. Additional: 45210000-2, 45000000-7; \
+                ref 2024000001-1 and phone 01234567 are not codes. Lot 2: 72000000-5";
+
+    assert_eq!(
+        declared_codes(text),
+        vec!["45000000", "45210000", "72000000"]
+    );
+    assert!(declared_codes("No codes here, just 12345678").is_empty());
+}
+
+#[test]
+fn test_non_it_division() {
+    assert_eq!(non_it_division("45210000"), Some("construction work"));
+    assert_eq!(non_it_division("72000000"), None);
+    assert_eq!(non_it_division("4"), None);
+}
+
+#[test]
+fn test_proposes_no_bid_only_for_clearly_non_it_notices() {
+    let codes = |list: &[&str]| list.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+
+    let proposal = propose_no_bid(&codes(&["45000000", "45210000", "55520000"]), 0).unwrap();
+    assert_eq!(
+        proposal.reason,
+        "declares only non-IT CPV codes (45000000, 45210000, 55520000: construction work; hotel, restaurant and catering services)"
+    );
+
+    // One IT code, an IT detection match, or no declared codes at all: leave it to a person
+    assert!(propose_no_bid(&codes(&["45000000", "72000000"]), 0).is_none());
+    assert!(propose_no_bid(&codes(&["45000000"]), 1).is_none());
+    assert!(propose_no_bid(&[], 0).is_none());
+}
+
+#[test]
+fn test_mentions_and_normalize() {
+    let mentions = mentions("CPV 72000000-5, 48000000 and ref 172000000 or 72000000-55");
+    assert_eq!(
+        mentions,
+        vec![
+            Mention {
+                code: "72000000".to_string(),
+                check_digit: Some(5),
+                position: 4,
+            },
+            Mention {
+                code: "48000000".to_string(),
+                check_digit: None,
+                position: 16,
+            },
+        ]
+    );
+
+    assert_eq!(normalize("72000000"), Some("72000000".to_string()));
+    assert_eq!(normalize(" 72000000-5 "), Some("72000000".to_string()));
+    assert_eq!(normalize("7200000"), None);
+    assert_eq!(normalize("72000000-55"), None);
+    assert_eq!(normalize("SaaS"), None);
+}
+
+#[test]
+fn test_matcher_detects_whole_codes_with_descriptions() {
+    let catalogue = vec![
+        DetectionCode {
+            code: "72000000-5".to_string(),
+            description: Some("IT services".to_string()),
+            category: None,
+            active: true,
+        },
+        DetectionCode {
+            code: "72200000".to_string(),
+            description: None,
+            category: None,
+            active: true,
+        },
+        DetectionCode {
+            code: "SaaS".to_string(),
+            description: Some("Software as a service".to_string()),
+            category: None,
+            active: true,
+        },
+    ];
+    let matcher = CodeMatcher::with_descriptions(&catalogue);
+    let text = "Lot 1: 72000000-5 IT services. Ref 1720000001. Also 72000000 and SaaS hosting.";
+
+    assert_eq!(
+        matcher.detect(text),
+        vec![
+            DetectedCode {
+                code: "72000000".to_string(),
+                description: Some("IT services".to_string()),
+                count: 2,
+                positions: vec![7, 52],
+            },
+            DetectedCode {
+                code: "SaaS".to_string(),
+                description: Some("Software as a service".to_string()),
+                count: 1,
+                positions: vec![65],
+            },
+        ]
+    );
+    assert_eq!(matcher.find_codes(text), vec!["72000000", "SaaS"]);
+    assert!(matcher.find_codes("Reference 9720000000").is_empty());
+}
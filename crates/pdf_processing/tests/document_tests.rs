@@ -0,0 +1,168 @@
+use chrono::{FixedOffset, TimeZone};
+use lopdf::content::{Content, Operation};
+use lopdf::{Document, Object, Stream, dictionary, text_string};
+use pdf_processing::document::{detect_language, parse_pdf_date};
+use pdf_processing::{DocumentInfo, extract_document};
+use std::fs;
+
+/// A PDF with one line of text per page and an information dictionary
+fn generate_pdf(pages: &[&str]) -> Vec<u8> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Courier",
+    });
+    let resources_id = doc.add_object(dictionary! {
+        "Font" => dictionary! { "F1" => font_id },
+    });
+
+    let mut kids = Vec::new();
+    for text in pages {
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 9.into()]),
+                Operation::new("Td", vec![40.into(), 800.into()]),
+                Operation::new("Tj", vec![Object::string_literal(*text)]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        kids.push(
+            doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+            })
+            .into(),
+        );
+    }
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => pages.len() as i64,
+            "Resources" => resources_id,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    let info_id = doc.add_object(dictionary! {
+        "Title" => text_string("Request for Tenders: Payroll Services"),
+        "Author" => text_string("Oifig an Phríomh-Aturnae"),
+        "CreationDate" => Object::string_literal("D:20260914103000+01'00'"),
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.trailer.set("Info", info_id);
+
+    let mut buffer = Vec::new();
+    doc.save_to(&mut buffer).unwrap();
+    buffer
+}
+
+#[test]
+fn test_extract_document_pages_and_info() {
+    let pdf_bytes = generate_pdf(&[
+        "Section 1 Instructions to tenderers",
+        "Section 2 Specification of requirements",
+        "Section 3 Award criteria",
+    ]);
+
+    let document = extract_document(&pdf_bytes).unwrap();
+
+    assert_eq!(document.page_count, 3);
+    assert_eq!(document.pages.len(), 3);
+    assert!(
+        document
+            .page_text(1)
+            .unwrap()
+            .contains("Instructions to tenderers")
+    );
+    assert!(document.page_text(2).unwrap().contains("Specification"));
+    assert!(document.page_text(3).unwrap().contains("Award criteria"));
+    assert_eq!(document.page_text(0), None);
+    assert_eq!(document.page_text(4), None);
+    assert_eq!(document.page_offsets()[0], 0);
+
+    assert_eq!(
+        document.info,
+        DocumentInfo {
+            title: Some("Request for Tenders: Payroll Services".to_string()),
+            author: Some("Oifig an Phríomh-Aturnae".to_string()),
+            created: FixedOffset::east_opt(3600)
+                .unwrap()
+                .with_ymd_and_hms(2026, 9, 14, 10, 30, 0)
+                .single(),
+        }
+    );
+
+    let metadata = document.metadata();
+    assert_eq!(metadata["page_count"], 3);
+    assert_eq!(metadata["created"], "2026-09-14T10:30:00+01:00");
+    let pages = metadata["pages"].as_array().unwrap();
+    assert_eq!(pages.len(), 3);
+    assert_eq!(pages[1]["page"], 2);
+    let start = pages[1]["start"].as_u64().unwrap() as usize;
+    let length = pages[1]["length"].as_u64().unwrap() as usize;
+    assert_eq!(
+        &document.text[start..start + length],
+        document.page_text(2).unwrap()
+    );
+}
+
+#[test]
+fn test_extract_document_fixture() {
+    let pdf_bytes = fs::read("test.pdf").expect("Failed to read test.pdf");
+
+    let document = extract_document(&pdf_bytes).unwrap();
+
+    assert_eq!(document.pages.len(), document.page_count);
+    let joined: String = (1..=document.pages.len())
+        .map(|number| document.page_text(number).unwrap())
+        .collect();
+    assert_eq!(joined, document.text);
+    assert_eq!(document.language, Some("en"));
+}
+
+#[test]
+fn test_detect_language() {
+    let english = "The contracting authority will evaluate each tender against the award \
+        criteria and the tenderer with the most economically advantageous tender shall be \
+        awarded the contract for the services described in this document.";
+    let irish = "Tá an t-údarás conarthaí ag lorg tairiscintí chun seirbhísí a sholáthar agus \
+        beidh na tairiscintí go léir le fáil ar an suíomh seo faoi dheireadh na míosa. Níl \
+        aon táille ag baint leis agus ní ghlacfar le hiarratais mhalla ó sholáthraithe nó \
+        ó chomhairleoirí mar atá leagtha amach sa doiciméad seo.";
+
+    assert_eq!(detect_language(english), Some("en"));
+    assert_eq!(detect_language(irish), Some("ga"));
+    assert_eq!(detect_language("Lot 1 - 72000000 - €250,000"), None);
+}
+
+#[test]
+fn test_parse_pdf_date() {
+    let utc = FixedOffset::east_opt(0).unwrap();
+    assert_eq!(
+        parse_pdf_date("D:20260914103000Z"),
+        utc.with_ymd_and_hms(2026, 9, 14, 10, 30, 0).single()
+    );
+    assert_eq!(
+        parse_pdf_date("D:20260914103000-05'30'"),
+        FixedOffset::west_opt(5 * 3600 + 30 * 60)
+            .unwrap()
+            .with_ymd_and_hms(2026, 9, 14, 10, 30, 0)
+            .single()
+    );
+    assert_eq!(
+        parse_pdf_date("D:2026"),
+        utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).single()
+    );
+    assert_eq!(parse_pdf_date("D:20261340"), None);
+    assert_eq!(parse_pdf_date("yesterday"), None);
+}
@@ -1,7 +1,6 @@
 // pub use crate::main::extract_text_from_pdf;
 
 use pdf_processing::extract_text_from_pdf;
-use reqwest;
 use std::fs;
 
 #[tokio::test]
@@ -0,0 +1,47 @@
+use pdf_processing::quality::{DEFAULT_MIN_QUALITY, score};
+
+#[test]
+fn test_clean_tender_text_scores_high() {
+    let text = "The contracting authority invites tenders for the provision of managed IT services.\n\
+        Tenderers must submit their response through the eTenders portal before the deadline.\n\
+        All documents should be provided in English and the contract will be awarded on the\n\
+        basis of the most economically advantageous tender.";
+
+    let quality = score(text);
+
+    assert!(quality.score > 0.9, "score {}", quality.score);
+    assert!(!quality.is_low(DEFAULT_MIN_QUALITY));
+    assert_eq!(quality.replacement_ratio, 0.0);
+}
+
+#[test]
+fn test_broken_font_map_scores_low() {
+    // What a PDF with an unmapped embedded font extracts as: private-use glyphs and
+    // replacement characters, one or two per line
+    let text =
+        "\u{E001}\u{E00F}\n\u{FFFD}\n\u{E022}\u{E013}\n\u{FFFD}\u{E001}\nTq\n\u{E044}\n".repeat(20);
+
+    let quality = score(&text);
+
+    assert!(
+        quality.is_low(DEFAULT_MIN_QUALITY),
+        "score {}",
+        quality.score
+    );
+    assert!(quality.replacement_ratio > 0.5);
+    assert!(quality.average_line_length < 3.0);
+}
+
+#[test]
+fn test_symbol_soup_scores_low() {
+    let text =
+        "Xq7 #vv9 ;;kz Pw0q | rrT 8~j ^^f Lmq3 vvz0 Qk@ ttr 9wwx ^~p z0z0 qPq |k|\n".repeat(10);
+
+    assert!(score(&text).is_low(DEFAULT_MIN_QUALITY));
+}
+
+#[test]
+fn test_empty_text_scores_zero() {
+    assert_eq!(score("").score, 0.0);
+    assert_eq!(score("  \n\n \t").score, 0.0);
+}
@@ -0,0 +1,110 @@
+use pdf_processing::{
+    CodeMatcher, ExtractionBudget, StopReason, extract_codes, extract_text_cancellable,
+    extract_text_streaming,
+};
+use std::fs;
+use std::sync::atomic::AtomicBool;
+
+fn load_fixture() -> (Vec<u8>, Vec<String>) {
+    let pdf_bytes = fs::read("test.pdf").expect("Failed to read test.pdf");
+    let codes: Vec<String> = fs::read_to_string("codes.txt")
+        .expect("Failed to read codes.txt")
+        .lines()
+        .filter_map(|line| line.split(',').next())
+        .map(|code| code.trim().to_string())
+        .filter(|code| !code.is_empty())
+        .collect();
+    (pdf_bytes, codes)
+}
+
+fn unlimited() -> ExtractionBudget {
+    ExtractionBudget {
+        max_pages: None,
+        max_chars: None,
+        stop_after_codes: None,
+    }
+}
+
+#[test]
+fn test_streaming_matches_full_text_code_detection() {
+    let (pdf_bytes, codes) = load_fixture();
+
+    let extraction =
+        extract_text_streaming(&pdf_bytes, &CodeMatcher::new(&codes), &unlimited()).unwrap();
+
+    assert!(extraction.text.len() > 100, "Text should be substantial");
+    assert_eq!(extraction.stop_reason, None);
+    assert_eq!(
+        extraction.pages_processed + extraction.pages_failed,
+        extraction.total_pages
+    );
+
+    assert_eq!(extraction.page_offsets.len(), extraction.total_pages);
+    assert_eq!(extraction.page_offsets.first(), Some(&0));
+    assert!(extraction.page_offsets.windows(2).all(|w| w[0] <= w[1]));
+    assert!(
+        extraction
+            .page_offsets
+            .iter()
+            .all(|&offset| offset <= extraction.text.len())
+    );
+
+    let mut streamed = extraction.detected_codes.clone();
+    let mut full = extract_codes(&extraction.text, &codes);
+    streamed.sort();
+    full.sort();
+    assert_eq!(streamed, full);
+}
+
+#[test]
+fn test_streaming_respects_char_budget() {
+    let (pdf_bytes, codes) = load_fixture();
+    let budget = ExtractionBudget {
+        max_chars: Some(100),
+        ..unlimited()
+    };
+
+    let extraction =
+        extract_text_streaming(&pdf_bytes, &CodeMatcher::new(&codes), &budget).unwrap();
+
+    assert!(extraction.text.len() <= 100);
+    assert_eq!(extraction.stop_reason, Some(StopReason::MaxChars));
+}
+
+#[test]
+fn test_streaming_respects_page_budget() {
+    let (pdf_bytes, codes) = load_fixture();
+    let budget = ExtractionBudget {
+        max_pages: Some(1),
+        ..unlimited()
+    };
+
+    let extraction =
+        extract_text_streaming(&pdf_bytes, &CodeMatcher::new(&codes), &budget).unwrap();
+
+    assert!(extraction.pages_processed <= 1);
+    assert_eq!(
+        extraction.page_offsets.len(),
+        extraction.pages_processed + extraction.pages_failed
+    );
+    if extraction.total_pages > 1 {
+        assert_eq!(extraction.stop_reason, Some(StopReason::MaxPages));
+    }
+}
+
+#[test]
+fn test_cancelled_extraction_stops_before_the_next_page() {
+    let (pdf_bytes, codes) = load_fixture();
+
+    let extraction = extract_text_cancellable(
+        &pdf_bytes,
+        &CodeMatcher::new(&codes),
+        &unlimited(),
+        &AtomicBool::new(true),
+    )
+    .unwrap();
+
+    assert_eq!(extraction.pages_processed, 0);
+    assert!(extraction.text.is_empty());
+    assert_eq!(extraction.stop_reason, Some(StopReason::Cancelled));
+}
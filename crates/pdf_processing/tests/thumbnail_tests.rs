@@ -0,0 +1,30 @@
+#![cfg(feature = "thumbnail")]
+
+use pdf_processing::thumbnail::{ThumbnailOptions, render_first_page, thumbnail_key};
+use std::fs;
+
+#[test]
+fn test_thumbnail_key() {
+    assert_eq!(thumbnail_key(42), "thumbnails/42.png");
+}
+
+#[test]
+fn test_missing_ghostscript_is_an_error() {
+    let options = ThumbnailOptions {
+        ghostscript: "/nonexistent/gs".to_string(),
+        ..ThumbnailOptions::default()
+    };
+
+    let pdf_bytes = fs::read("test.pdf").expect("Failed to read test.pdf");
+    assert!(render_first_page(&pdf_bytes, &options).is_err());
+}
+
+/// Needs Ghostscript on the PATH: `cargo test --features thumbnail -- --ignored`
+#[test]
+#[ignore]
+fn test_renders_first_page_to_png() {
+    let pdf_bytes = fs::read("test.pdf").expect("Failed to read test.pdf");
+
+    let png = render_first_page(&pdf_bytes, &ThumbnailOptions::default()).unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+}
@@ -0,0 +1,167 @@
+use crate::types::{CanaryRun, Config, Stage, StageProgress};
+use anyhow::Result;
+use environment::Environment;
+use sqlx::{Pool, Postgres, Row};
+use tracing::info;
+
+/// Canary bookkeeping plus read access to the pipeline tables it checks
+pub struct Database {
+    pool: Pool<Postgres>,
+}
+
+impl Database {
+    /// Create new database connection
+    pub async fn new(config: &Config) -> Result<Self> {
+        let pool = db::connect(&config.database_url, 2).await?;
+
+        info!("✅ Database connection established");
+        Ok(Self { pool })
+    }
+
+    pub async fn ensure_tables(&self) -> Result<()> {
+        db::ensure_schema(&self.pool, "pipeline_canary", 1, async {
+            sqlx::query(
+                r#"
+                CREATE TABLE IF NOT EXISTS canary_runs (
+                    resource_id BIGINT PRIMARY KEY,
+                    injected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    status TEXT NOT NULL DEFAULT 'pending',
+                    failed_stage TEXT,
+                    resolved_at TIMESTAMPTZ
+                )
+                "#,
+            )
+            .execute(&self.pool)
+            .await?;
+
+            Environment::ensure_environment_column(&self.pool, "canary_runs").await?;
+            anyhow::Ok(())
+        })
+        .await
+    }
+
+    pub async fn record_injection(&self, resource_id: i64) -> Result<()> {
+        sqlx::query("INSERT INTO canary_runs (resource_id) VALUES ($1)")
+            .bind(resource_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn pending_runs(&self) -> Result<Vec<CanaryRun>> {
+        let rows = sqlx::query(
+            "SELECT resource_id, injected_at FROM canary_runs WHERE status = 'pending' ORDER BY injected_at",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| CanaryRun {
+                resource_id: row.get("resource_id"),
+                injected_at: row.get("injected_at"),
+            })
+            .collect())
+    }
+
+    /// What each stage has recorded for the canary tender so far
+    pub async fn progress(&self, resource_id: i64) -> Result<StageProgress> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                EXISTS (SELECT 1 FROM tender_records WHERE resource_id = $1) AS stored,
+                EXISTS (SELECT 1 FROM pdf_content WHERE resource_id = $1) AS pdf_extracted,
+                COALESCE((SELECT ml_processed FROM tender_records WHERE resource_id = $1), FALSE) AS ml_predicted,
+                EXISTS (SELECT 1 FROM ai_summaries WHERE resource_id = $1) AS summarised,
+                COALESCE((SELECT notification_sent FROM tender_records WHERE resource_id = $1), FALSE) AS notified
+            "#,
+        )
+        .bind(resource_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(StageProgress {
+            stored: row.get("stored"),
+            pdf_extracted: row.get("pdf_extracted"),
+            ml_predicted: row.get("ml_predicted"),
+            summarised: row.get("summarised"),
+            notified: row.get("notified"),
+        })
+    }
+
+    pub async fn mark_failed(&self, resource_id: i64, stage: Stage) -> Result<()> {
+        sqlx::query(
+            "UPDATE canary_runs SET status = 'failed', failed_stage = $2, resolved_at = NOW() WHERE resource_id = $1",
+        )
+        .bind(resource_id)
+        .bind(stage.lambda())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Mark the run passed and remove the synthetic tender from every table keyed by
+    /// resource_id, so it never shows up in exports, the dashboard or the stage logs.
+    /// Failed canaries are left in place for debugging.
+    pub async fn mark_passed(&self, resource_id: i64) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for table in tender_tables() {
+            // Tables this deployment hasn't created yet are skipped
+            let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+                .bind(table)
+                .fetch_one(&mut *tx)
+                .await?;
+            if !exists {
+                continue;
+            }
+            sqlx::query(&format!("DELETE FROM {} WHERE resource_id = $1", table))
+                .bind(resource_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query(
+            "UPDATE canary_runs SET status = 'passed', resolved_at = NOW() WHERE resource_id = $1",
+        )
+        .bind(resource_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Tables with a resource_id column, from `db::catalog`; `canary_runs` keeps the run's outcome
+fn tender_tables() -> impl Iterator<Item = &'static str> {
+    db::catalog::TABLES
+        .iter()
+        .filter(|doc| doc.name != "canary_runs")
+        .filter(|doc| {
+            doc.columns
+                .iter()
+                .any(|(column, _)| *column == "resource_id")
+        })
+        .map(|doc| doc.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cleanup_covers_every_stage_table() {
+        let tables: Vec<&str> = tender_tables().collect();
+        for table in [
+            "tender_records",
+            "pdf_content",
+            "ai_summaries",
+            "pipeline_status",
+            "notification_log",
+            "decision_audit",
+            "tender_lifecycle",
+            "tender_lifecycle_transitions",
+        ] {
+            assert!(tables.contains(&table), "{} is not cleaned up", table);
+        }
+        assert!(!tables.contains(&"canary_runs"));
+    }
+}
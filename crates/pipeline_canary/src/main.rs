@@ -0,0 +1,191 @@
+use anyhow::Result;
+use aws_config::{BehaviorVersion, SdkConfig};
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::Client as S3Client;
+use aws_sdk_sns::Client as SnsClient;
+use aws_sdk_sqs::Client as SqsClient;
+use chrono::{Duration, Utc};
+use environment::Environment;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use resource_discovery::{Resource, ResourceDiscovery};
+use serde_json::Value;
+use tender_core::TenderRecord;
+use tracing::{error, info, Instrument};
+
+mod database;
+mod types;
+
+use database::Database;
+use types::{CanaryRun, Config, Stage, CANARY_TITLE};
+
+/// Triggered on a schedule (EventBridge); the event body is not used.
+///
+/// Each run first resolves the canaries injected by earlier runs, then injects a new one,
+/// so the schedule interval should be shorter than CANARY_TIMEOUT_MINUTES.
+async fn function_handler(_event: LambdaEvent<Value>) -> Result<String, Error> {
+    info!("=== PIPELINE CANARY STARTED ===");
+
+    let config = Config::from_env().map_err(|e| {
+        error!("Failed to load configuration: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+
+    let database = Database::new(&config).await.map_err(|e| {
+        error!("Failed to initialize database: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+    database
+        .ensure_tables()
+        .await
+        .map_err(|e| Error::from(format!("Failed to create canary_runs table: {}", e).as_str()))?;
+
+    let aws_config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+
+    let (passed, failed) = check_pending(&database, &config, &aws_config)
+        .await
+        .map_err(|e| Error::from(format!("Failed to check pending canaries: {}", e).as_str()))?;
+
+    let resource_id = inject_canary(&aws_config, &config)
+        .await
+        .map_err(|e| Error::from(format!("Failed to inject canary: {}", e).as_str()))?;
+    database
+        .record_injection(resource_id)
+        .await
+        .map_err(|e| Error::from(format!("Failed to record canary: {}", e).as_str()))?;
+    info!("🐤 Injected canary tender {}", resource_id);
+
+    info!("=== PIPELINE CANARY COMPLETED ===");
+    Ok(format!(
+        "{} canaries passed, {} failed; injected {}",
+        passed, failed, resource_id
+    ))
+}
+
+/// Resolve earlier canaries: passed once every stage has recorded them, failed once past the timeout
+async fn check_pending(
+    database: &Database,
+    config: &Config,
+    aws_config: &SdkConfig,
+) -> Result<(usize, usize)> {
+    let deadline = Utc::now() - Duration::minutes(config.timeout_minutes);
+    let (mut passed, mut failed) = (0, 0);
+
+    for canary in database.pending_runs().await? {
+        let progress = database.progress(canary.resource_id).await?;
+        match progress.first_missing() {
+            None => {
+                database.mark_passed(canary.resource_id).await?;
+                info!(
+                    "✅ Canary {} passed every stage in {} minutes",
+                    canary.resource_id,
+                    (Utc::now() - canary.injected_at).num_minutes()
+                );
+                passed += 1;
+            }
+            Some(stage) if canary.injected_at < deadline => {
+                database.mark_failed(canary.resource_id, stage).await?;
+                alert(aws_config, config, &canary, stage).await;
+                failed += 1;
+            }
+            Some(stage) => {
+                info!(
+                    "⏳ Canary {} still in flight (waiting on {})",
+                    canary.resource_id,
+                    stage.lambda()
+                );
+            }
+        }
+    }
+
+    Ok((passed, failed))
+}
+
+/// Queue a synthetic tender at the head of the pipeline, pointing at the fixture PDF
+async fn inject_canary(aws_config: &SdkConfig, config: &Config) -> Result<i64> {
+    let discovery = ResourceDiscovery::new(aws_config);
+    let bucket = discovery.resolve(Resource::LambdaBucket).await?;
+    let queue_url = discovery.resolve(Resource::TenderProcessingQueue).await?;
+
+    // pdf_processing downloads over HTTP, so hand it a presigned link that outlives the timeout
+    let expires_in = std::time::Duration::from_secs(config.timeout_minutes.max(1) as u64 * 60 * 2);
+    let pdf_url = S3Client::new(aws_config)
+        .get_object()
+        .bucket(&bucket)
+        .key(&config.fixture_pdf_key)
+        .presigned(PresigningConfig::expires_in(expires_in)?)
+        .await?
+        .uri()
+        .to_string();
+
+    // Negative ids mark the tender as a canary for every stage (see environment::is_canary)
+    let resource_id = -Utc::now().timestamp();
+    let now = Utc::now().naive_utc();
+    let tender = TenderRecord {
+        title: CANARY_TITLE.to_string(),
+        resource_id,
+        contracting_authority: "Pipeline Canary".to_string(),
+        info: "Synthetic tender injected by pipeline_canary to verify every stage end to end"
+            .to_string(),
+        published: Some(now),
+        deadline: Some(now + Duration::days(30)),
+        procedure: "Open".to_string(),
+        status: "Open".to_string(),
+        pdf_url,
+        ..Default::default()
+    };
+
+    SqsClient::new(aws_config)
+        .send_message()
+        .queue_url(&queue_url)
+        .message_body(serde_json::to_string(&tender)?)
+        .send()
+        .await?;
+
+    Ok(resource_id)
+}
+
+/// Always log at error level (for log-based alarms); publish to the alert topic when configured
+async fn alert(aws_config: &SdkConfig, config: &Config, canary: &CanaryRun, stage: Stage) {
+    let message = format!(
+        "Canary tender {} injected at {} did not get past {} within {} minutes",
+        canary.resource_id,
+        canary.injected_at.format("%Y-%m-%d %H:%M UTC"),
+        stage.lambda(),
+        config.timeout_minutes
+    );
+    error!("🚨 CANARY FAILED: {}", message);
+
+    let Some(topic_arn) = &config.alert_topic_arn else {
+        return;
+    };
+    let environment = Environment::from_env();
+    if let Err(e) = SnsClient::new(aws_config)
+        .publish()
+        .topic_arn(topic_arn)
+        .subject(format!(
+            "[{}] Pipeline canary failed at {}",
+            environment.name().to_uppercase(),
+            stage.lambda()
+        ))
+        .message(message)
+        .send()
+        .await
+    {
+        error!("Failed to publish canary alert: {}", e);
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    let environment = Environment::from_env();
+    run(service_fn(move |event| {
+        function_handler(event).instrument(environment.span())
+    }))
+    .await
+}
@@ -0,0 +1,134 @@
+use chrono::{DateTime, Utc};
+
+/// Minutes a canary has to reach the end of the pipeline before it counts as dropped
+pub const DEFAULT_TIMEOUT_MINUTES: i64 = 30;
+
+/// Fixture PDF, relative to the lambda bucket
+pub const DEFAULT_FIXTURE_PDF_KEY: &str = "canary/canary_tender.pdf";
+
+pub const CANARY_TITLE: &str = "[CANARY] Pipeline self-test - not a real tender";
+
+/// Pipeline stages in the order a tender passes through them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Dataload,
+    PdfProcessing,
+    MlPrediction,
+    AiSummary,
+    Notification,
+}
+
+impl Stage {
+    /// Lambda responsible for the stage
+    pub fn lambda(&self) -> &'static str {
+        match self {
+            Stage::Dataload => "postgres_dataload",
+            Stage::PdfProcessing => "pdf_processing",
+            Stage::MlPrediction => "ml_bid_predictor",
+            Stage::AiSummary => "ai_summary",
+            Stage::Notification => "sns_notification",
+        }
+    }
+}
+
+/// Which stages have left their mark in the database for a canary tender
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StageProgress {
+    /// tender_records row exists
+    pub stored: bool,
+    /// pdf_content row exists
+    pub pdf_extracted: bool,
+    /// tender_records.ml_processed
+    pub ml_predicted: bool,
+    /// ai_summaries row exists
+    pub summarised: bool,
+    /// tender_records.notification_sent (email itself is suppressed for canaries)
+    pub notified: bool,
+}
+
+impl StageProgress {
+    /// First stage that didn't complete, or None if the canary made it all the way through
+    pub fn first_missing(&self) -> Option<Stage> {
+        [
+            (self.stored, Stage::Dataload),
+            (self.pdf_extracted, Stage::PdfProcessing),
+            (self.ml_predicted, Stage::MlPrediction),
+            (self.summarised, Stage::AiSummary),
+            (self.notified, Stage::Notification),
+        ]
+        .into_iter()
+        .find(|(done, _)| !done)
+        .map(|(_, stage)| stage)
+    }
+}
+
+/// Canary injected earlier and not yet resolved
+#[derive(Debug, Clone)]
+pub struct CanaryRun {
+    pub resource_id: i64,
+    pub injected_at: DateTime<Utc>,
+}
+
+/// Configuration from environment
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database_url: String,
+    pub timeout_minutes: i64,
+    pub fixture_pdf_key: String,
+    /// SNS topic alerted when a canary is dropped; failures are always logged at error level
+    pub alert_topic_arn: Option<String>,
+}
+
+impl Config {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let database_url = std::env::var("DATABASE_URL")
+            .map_err(|_| anyhow::anyhow!("DATABASE_URL environment variable not set"))?;
+
+        let timeout_minutes = std::env::var("CANARY_TIMEOUT_MINUTES")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_TIMEOUT_MINUTES);
+
+        let fixture_pdf_key =
+            std::env::var("CANARY_PDF_KEY").unwrap_or_else(|_| DEFAULT_FIXTURE_PDF_KEY.to_string());
+
+        let alert_topic_arn = std::env::var("CANARY_ALERT_TOPIC_ARN")
+            .ok()
+            .filter(|arn| !arn.trim().is_empty());
+
+        Ok(Self {
+            database_url,
+            timeout_minutes,
+            fixture_pdf_key,
+            alert_topic_arn,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_missing_stage() {
+        let mut progress = StageProgress {
+            stored: true,
+            pdf_extracted: true,
+            ..Default::default()
+        };
+        assert_eq!(progress.first_missing(), Some(Stage::MlPrediction));
+
+        progress.ml_predicted = true;
+        progress.summarised = true;
+        progress.notified = true;
+        assert_eq!(progress.first_missing(), None);
+    }
+
+    #[test]
+    fn test_nothing_stored_blames_dataload() {
+        assert_eq!(
+            StageProgress::default().first_missing(),
+            Some(Stage::Dataload)
+        );
+    }
+}
@@ -0,0 +1,693 @@
+//! Event contracts that let the pipeline stages run under Step Functions as well as SQS chaining.
+//!
+//! Every stage accepts either an SQS batch (the default, each stage forwarding to the next
+//! stage's queue) or a Step Functions task input carrying the stage payload inline. Which one
+//! is used is purely a deployment choice: point a state machine at the lambdas and they stop
+//! forwarding and return a `StageOutput` whose `next_stage` a Choice state routes on.
+//!
+//! States that should keep SQS buffering (e.g. the AI summary queue, so Claude calls stay
+//! rate limited) use `sqs:sendMessage.waitForTaskToken` with a `TaskInput` as the message
+//! body; the stage then reports its `StageOutput` back with `complete_task`. A stage that
+//! finishes the work in a later invocation queues a continuation carrying the same token and
+//! marks the message deferred (`Handoff::defer`), leaving the report to the continuation.
+//!
+//! Failures are typed (`StageError`/`ErrorCode`). Retryable ones are surfaced to the
+//! caller - an SQS batch item failure, a Lambda error or a task failure named after the
+//! code - so SQS redelivery or a Step Functions `Retry` kicks in; permanent ones are
+//! recorded by the stage and acknowledged. `StageResults` applies this for each handler.
+//!
+//! Routing hints travel as SQS message attributes (see `routing`), not in the payload.
+//! `tender_status` normalises the portal's tender status for the scraper and dataload, and
+//! `tender_procedure` its procedure type for ai_summary and sns_notification.
+//! `diagnostics` answers direct invocations asking a stage for its effective configuration.
+//! `timeouts` limits the calls a stage makes (downloads, extraction, queries, Claude), failing
+//! a hung one with `ErrorCode::Timeout`.
+
+use aws_config::SdkConfig;
+use aws_lambda_events::event::sqs::{BatchItemFailure, SqsBatchResponse, SqsEvent};
+use aws_sdk_sfn::Client as SfnClient;
+use pipeline_status::Stage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tracing::{error, info, warn};
+
+pub mod diagnostics;
+pub mod routing;
+pub mod tender_procedure;
+pub mod tender_status;
+pub mod timeouts;
+
+pub use routing::Routing;
+pub use tender_procedure::Procedure;
+pub use tender_status::TenderStatus;
+
+/// Why a stage failed; serialized (and used as the Step Functions error name) in SCREAMING_SNAKE_CASE
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// The message body isn't a payload this stage understands
+    InvalidMessage,
+    /// The tender isn't in the state the stage expects (e.g. reached ML without PDF content)
+    InvalidState,
+    /// The PDF was fetched but can't be used (not found, unparseable)
+    UnusablePdf,
+    /// Missing or invalid lambda configuration
+    Configuration,
+    /// The PDF couldn't be downloaded
+    DownloadFailed,
+    Database,
+    /// Handing the tender to the next stage failed
+    ForwardFailed,
+    /// Claude or another upstream API failed
+    Upstream,
+    /// Email delivery failed
+    DeliveryFailed,
+    /// A download, extraction, query or Claude call ran past its limit (see `timeouts`)
+    Timeout,
+    /// Another invocation holds the tender's claim (see `pipeline_status::claim`); retried
+    /// after it finishes or its lease runs out
+    Claimed,
+}
+
+impl ErrorCode {
+    /// Whether trying the same message again can succeed
+    pub fn is_retryable(self) -> bool {
+        !matches!(
+            self,
+            ErrorCode::InvalidMessage | ErrorCode::InvalidState | ErrorCode::UnusablePdf
+        )
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::InvalidMessage => "INVALID_MESSAGE",
+            ErrorCode::InvalidState => "INVALID_STATE",
+            ErrorCode::UnusablePdf => "UNUSABLE_PDF",
+            ErrorCode::Configuration => "CONFIGURATION",
+            ErrorCode::DownloadFailed => "DOWNLOAD_FAILED",
+            ErrorCode::Database => "DATABASE",
+            ErrorCode::ForwardFailed => "FORWARD_FAILED",
+            ErrorCode::Upstream => "UPSTREAM",
+            ErrorCode::DeliveryFailed => "DELIVERY_FAILED",
+            ErrorCode::Timeout => "TIMEOUT",
+            ErrorCode::Claimed => "CLAIMED",
+        }
+    }
+}
+
+/// A stage failure with its code and a human-readable detail
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageError {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Known once the message has been parsed
+    pub resource_id: Option<i64>,
+}
+
+impl StageError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            resource_id: None,
+        }
+    }
+
+    pub fn for_tender(mut self, resource_id: i64) -> Self {
+        self.resource_id = Some(resource_id);
+        self
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.code.is_retryable()
+    }
+}
+
+impl fmt::Display for StageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code.as_str(), self.message)
+    }
+}
+
+impl std::error::Error for StageError {}
+
+/// A stage invocation: an SQS batch or a direct Step Functions task
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum StageEvent {
+    Sqs(SqsEvent),
+    Task(TaskInput),
+}
+
+/// Step Functions task input, e.g. `{"payload.$": "$.payloads[0]", "task_token.$": "$$.Task.Token"}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskInput {
+    pub payload: Value,
+    #[serde(default)]
+    pub task_token: Option<String>,
+}
+
+/// One message for a stage to process, whichever way it arrived
+#[derive(Debug, Clone)]
+pub struct StageMessage {
+    pub body: String,
+    /// SQS message id, reported back as a batch item failure when retryable
+    pub message_id: Option<String>,
+    pub receipt_handle: Option<String>,
+    pub task_token: Option<String>,
+    /// Invoked directly by Step Functions rather than through SQS
+    pub direct: bool,
+    /// Hints from the SQS message attributes; empty for Step Functions tasks
+    pub routing: Routing,
+}
+
+impl StageMessage {
+    /// Whether a state machine, not the SQS chain, moves the tender to the next stage
+    pub fn orchestrated(&self) -> bool {
+        self.direct || self.task_token.is_some()
+    }
+}
+
+impl StageEvent {
+    pub fn is_direct(&self) -> bool {
+        matches!(self, StageEvent::Task(_))
+    }
+
+    /// The diagnostic asked for by a `{"payload": {"diagnostic": ...}}` invocation (see `diagnostics`)
+    pub fn diagnostic(&self) -> Option<&str> {
+        match self {
+            StageEvent::Task(task) => task.payload.get("diagnostic").and_then(Value::as_str),
+            StageEvent::Sqs(_) => None,
+        }
+    }
+
+    /// The messages to process; SQS bodies that wrap a `TaskInput` are unwrapped
+    pub fn into_messages(self) -> Vec<StageMessage> {
+        match self {
+            StageEvent::Task(task) => vec![StageMessage {
+                body: task.payload.to_string(),
+                message_id: None,
+                receipt_handle: None,
+                task_token: task.task_token,
+                direct: true,
+                routing: Routing::default(),
+            }],
+            StageEvent::Sqs(event) => event
+                .records
+                .into_iter()
+                .filter_map(|record| {
+                    let body = record.body?;
+                    let (body, task_token) = match serde_json::from_str::<TaskInput>(&body) {
+                        Ok(TaskInput {
+                            payload,
+                            task_token: Some(token),
+                        }) => (payload.to_string(), Some(token)),
+                        _ => (body, None),
+                    };
+                    let routing =
+                        Routing::from_sqs(&record.message_attributes, record.message_id.as_deref());
+                    if !routing.is_supported() {
+                        warn!(
+                            "Message {:?} has schema version {:?}, newer than this build's {}",
+                            record.message_id,
+                            routing.schema_version,
+                            routing::SCHEMA_VERSION
+                        );
+                    }
+                    Some(StageMessage {
+                        body,
+                        routing,
+                        message_id: record.message_id,
+                        receipt_handle: record.receipt_handle,
+                        task_token,
+                        direct: false,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// What an orchestrated stage hands back to the state machine
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StageOutput {
+    pub resource_id: Option<i64>,
+    pub success: bool,
+    pub error_code: Option<ErrorCode>,
+    pub message: String,
+    /// Stage the tender should move to, `None` when the pipeline ends here
+    pub next_stage: Option<String>,
+    /// Inputs for the next stage (ai_summary can notify several tenants, hence a list for a Map state)
+    pub payloads: Vec<Value>,
+}
+
+/// Collects the next stage's payloads instead of sending them when a state machine orchestrates
+pub struct Handoff {
+    orchestrated: bool,
+    correlation_id: Option<String>,
+    next: Mutex<(Option<Stage>, Vec<Value>)>,
+    deferred: AtomicBool,
+}
+
+impl Handoff {
+    pub fn new(message: &StageMessage) -> Self {
+        Self {
+            orchestrated: message.orchestrated(),
+            correlation_id: message.routing.correlation_id.clone(),
+            next: Mutex::new((None, Vec::new())),
+            deferred: AtomicBool::new(false),
+        }
+    }
+
+    /// Routing hints for the message forwarded to `processing_stage`, keeping the run's
+    /// correlation id
+    pub fn routing(&self, processing_stage: &str) -> Routing {
+        Routing::to_stage(processing_stage).correlation_id(self.correlation_id.clone())
+    }
+
+    pub fn orchestrated(&self) -> bool {
+        self.orchestrated
+    }
+
+    /// Mark the message as handed to a continuation that carries its task token on, so
+    /// `StageResults` leaves the outcome for that invocation to report
+    pub fn defer(&self) {
+        self.deferred.store(true, Ordering::Relaxed);
+    }
+
+    pub fn deferred(&self) -> bool {
+        self.deferred.load(Ordering::Relaxed)
+    }
+
+    /// Under Step Functions, keep the payload for the output and return true so the caller
+    /// skips its SQS send; returns false (keep nothing) under SQS chaining
+    pub fn capture<T: Serialize>(&self, next_stage: Stage, payload: &T) -> bool {
+        if !self.orchestrated {
+            return false;
+        }
+
+        let payload = match serde_json::to_value(payload) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Failed to serialize {} payload: {}", next_stage.name(), e);
+                return false;
+            }
+        };
+        let mut next = self.next.lock().unwrap();
+        next.0 = Some(next_stage);
+        next.1.push(payload);
+        true
+    }
+
+    pub fn output(&self, result: &Result<Completed, StageError>) -> StageOutput {
+        match result {
+            Ok(completed) => {
+                let next = self.next.lock().unwrap();
+                StageOutput {
+                    resource_id: Some(completed.resource_id),
+                    success: true,
+                    error_code: None,
+                    message: completed.message.clone(),
+                    next_stage: next.0.map(|stage| stage.name().to_string()),
+                    payloads: next.1.clone(),
+                }
+            }
+            // Nothing captured before a failure is handed on
+            Err(e) => StageOutput {
+                resource_id: e.resource_id,
+                success: false,
+                error_code: Some(e.code),
+                message: e.message.clone(),
+                next_stage: None,
+                payloads: Vec::new(),
+            },
+        }
+    }
+}
+
+/// A message the stage finished with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completed {
+    pub resource_id: i64,
+    pub message: String,
+}
+
+impl Completed {
+    pub fn new(resource_id: i64, message: impl Into<String>) -> Self {
+        Self {
+            resource_id,
+            message: message.into(),
+        }
+    }
+}
+
+/// Turns each message's outcome into the handler's return value.
+///
+/// SQS batches return an `SqsBatchResponse` listing only the retryable failures (the event
+/// source mappings use `ReportBatchItemFailures`); a direct Step Functions invocation returns
+/// its `StageOutput`, or an error when the failure is retryable so the state's `Retry` applies.
+pub struct StageResults {
+    direct: bool,
+    aws_config: Option<SdkConfig>,
+    outputs: Vec<StageOutput>,
+    batch_item_failures: Vec<BatchItemFailure>,
+    /// Messages of the batch left for SQS to redeliver without being started
+    unstarted: usize,
+}
+
+impl StageResults {
+    pub fn new(event: &StageEvent) -> Self {
+        Self {
+            direct: event.is_direct(),
+            aws_config: None,
+            outputs: Vec::new(),
+            batch_item_failures: Vec::new(),
+            unstarted: 0,
+        }
+    }
+
+    /// Hand a message the invocation won't get to (e.g. too close to its timeout) back to SQS
+    /// untouched; it is reported as a batch item failure so only it is redelivered
+    pub fn record_unstarted(&mut self, message: &StageMessage) {
+        warn!(
+            "⏭️ Not started, left for redelivery: {:?}",
+            message.message_id
+        );
+        if let Some(message_id) = message.message_id.as_ref().filter(|_| !message.direct) {
+            self.batch_item_failures.push(BatchItemFailure {
+                item_identifier: message_id.clone(),
+            });
+        }
+        self.unstarted += 1;
+    }
+
+    /// Outcome counts for the end-of-batch log line
+    pub fn summary(&self) -> String {
+        let completed = self.outputs.iter().filter(|output| output.success).count();
+        let retrying = self
+            .outputs
+            .iter()
+            .filter(|output| output.error_code.is_some_and(ErrorCode::is_retryable))
+            .count();
+        let rejected = self.outputs.len() - completed - retrying;
+        format!(
+            "{} completed, {} retrying, {} rejected, {} not started",
+            completed, retrying, rejected, self.unstarted
+        )
+    }
+
+    /// Record one message's outcome, reporting it to Step Functions when it carries a task token
+    pub async fn record(
+        &mut self,
+        message: &StageMessage,
+        handoff: &Handoff,
+        result: Result<Completed, StageError>,
+    ) {
+        match &result {
+            Ok(completed) => info!("✅ {}: {}", completed.resource_id, completed.message),
+            Err(e) if e.code == ErrorCode::Timeout => warn!("⏱️ Timed out (retryable): {}", e),
+            Err(e) if e.is_retryable() => warn!("🔁 Retryable failure: {}", e),
+            Err(e) => error!("❌ Permanent failure (acknowledged): {}", e),
+        }
+
+        let output = handoff.output(&result);
+        if handoff.deferred() && result.is_ok() {
+            info!("⏩ Left for the continuation to report");
+        } else if let Some(task_token) = &message.task_token {
+            if self.aws_config.is_none() {
+                self.aws_config = Some(
+                    aws_config::defaults(aws_config::BehaviorVersion::latest())
+                        .load()
+                        .await,
+                );
+            }
+            if let Some(aws_config) = &self.aws_config {
+                complete_task(aws_config, task_token, &output).await;
+            }
+        } else if !message.direct && result.as_ref().is_err_and(StageError::is_retryable) {
+            // Left on the queue for redelivery (and eventually the dead-letter queue)
+            if let Some(message_id) = &message.message_id {
+                self.batch_item_failures.push(BatchItemFailure {
+                    item_identifier: message_id.clone(),
+                });
+            }
+        }
+        self.outputs.push(output);
+    }
+
+    pub fn into_response(mut self) -> Result<Value, StageError> {
+        if self.direct {
+            let output = self.outputs.pop().unwrap_or_default();
+            if let Some(code) = output.error_code.filter(|code| code.is_retryable()) {
+                return Err(StageError {
+                    code,
+                    message: output.message,
+                    resource_id: output.resource_id,
+                });
+            }
+            return Ok(serde_json::to_value(output).unwrap_or_default());
+        }
+
+        Ok(serde_json::to_value(SqsBatchResponse {
+            batch_item_failures: self.batch_item_failures,
+        })
+        .unwrap_or_default())
+    }
+}
+
+/// Report the stage outcome to a `.waitForTaskToken` state; failures only warn since the
+/// state's own timeout covers a lost callback
+pub async fn complete_task(aws_config: &SdkConfig, task_token: &str, output: &StageOutput) {
+    let client = SfnClient::new(aws_config);
+    let result = if output.success {
+        client
+            .send_task_success()
+            .task_token(task_token)
+            .output(serde_json::to_string(output).unwrap_or_default())
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    } else {
+        client
+            .send_task_failure()
+            .task_token(task_token)
+            .error(output.error_code.map_or("STAGE_FAILED", ErrorCode::as_str))
+            .cause(&output.message)
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    };
+
+    match result {
+        Ok(()) => info!("Reported task result to Step Functions"),
+        Err(e) => warn!("Failed to report task result to Step Functions: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqs_and_task_events_are_told_apart() {
+        let sqs: StageEvent = serde_json::from_value(serde_json::json!({
+            "Records": [{ "body": "{\"resource_id\": 1}", "receiptHandle": "abc" }]
+        }))
+        .unwrap();
+        assert!(!sqs.is_direct());
+        let messages = sqs.into_messages();
+        assert_eq!(messages[0].body, "{\"resource_id\": 1}");
+        assert!(!messages[0].orchestrated());
+
+        let task: StageEvent = serde_json::from_value(serde_json::json!({
+            "payload": { "resource_id": 2 }
+        }))
+        .unwrap();
+        assert!(task.is_direct());
+        assert_eq!(task.diagnostic(), None);
+        assert!(task.into_messages()[0].orchestrated());
+
+        let diagnostic: StageEvent = serde_json::from_value(serde_json::json!({
+            "payload": { "diagnostic": "config" }
+        }))
+        .unwrap();
+        assert_eq!(diagnostic.diagnostic(), Some("config"));
+    }
+
+    #[test]
+    fn test_task_token_envelope_in_sqs_body_is_unwrapped() {
+        let body = serde_json::json!({ "payload": { "resource_id": 3 }, "task_token": "tok" });
+        let event: StageEvent = serde_json::from_value(serde_json::json!({
+            "Records": [{ "body": body.to_string() }]
+        }))
+        .unwrap();
+
+        let message = &event.into_messages()[0];
+        assert_eq!(message.body, "{\"resource_id\":3}");
+        assert_eq!(message.task_token.as_deref(), Some("tok"));
+        assert!(message.orchestrated());
+    }
+
+    #[test]
+    fn test_routing_comes_from_message_attributes() {
+        let event: StageEvent = serde_json::from_value(serde_json::json!({
+            "Records": [
+                {
+                    "messageId": "m-2",
+                    "body": "{\"resource_id\": 5}",
+                    "messageAttributes": {
+                        "processing_stage": { "stringValue": "ml_prediction", "dataType": "String" },
+                        "schema_version": { "stringValue": "1", "dataType": "String" },
+                        "correlation_id": { "stringValue": "m-1", "dataType": "String" }
+                    }
+                },
+                { "messageId": "m-3", "body": "{}" }
+            ]
+        }))
+        .unwrap();
+        let messages = event.into_messages();
+
+        // The body is left as sent
+        assert_eq!(messages[0].body, "{\"resource_id\": 5}");
+        let routing = &messages[0].routing;
+        assert_eq!(routing.processing_stage.as_deref(), Some("ml_prediction"));
+        assert_eq!(routing.schema_version, Some(routing::SCHEMA_VERSION));
+        assert!(routing.is_supported());
+
+        // Forwarding keeps the run's correlation id; a message without one starts a run
+        let next = Handoff::new(&messages[0])
+            .routing("ai_summary")
+            .priority("URGENT");
+        assert_eq!(
+            next.attributes(),
+            vec![
+                ("processing_stage", "ai_summary".to_string()),
+                ("priority", "URGENT".to_string()),
+                ("schema_version", routing::SCHEMA_VERSION.to_string()),
+                ("correlation_id", "m-1".to_string()),
+            ]
+        );
+        assert_eq!(messages[1].routing.correlation_id.as_deref(), Some("m-3"));
+        assert_eq!(messages[1].routing.schema_version, None);
+        assert!(messages[1].routing.is_supported());
+    }
+
+    #[test]
+    fn test_handoff_only_captures_when_orchestrated() {
+        let mut message = StageMessage {
+            body: String::new(),
+            message_id: None,
+            receipt_handle: None,
+            task_token: None,
+            direct: false,
+            routing: Routing::default(),
+        };
+        let chained = Handoff::new(&message);
+        assert!(!chained.capture(Stage::AiSummary, &serde_json::json!({})));
+
+        message.direct = true;
+        let handoff = Handoff::new(&message);
+        assert!(handoff.capture(Stage::AiSummary, &serde_json::json!({ "resource_id": 4 })));
+        let output = handoff.output(&Ok(Completed::new(4, "ok")));
+        assert_eq!(output.next_stage.as_deref(), Some("ai_summary"));
+        assert_eq!(output.payloads.len(), 1);
+
+        let failed = handoff.output(&Err(StageError::new(ErrorCode::Database, "down")));
+        assert_eq!(failed.error_code, Some(ErrorCode::Database));
+        assert!(failed.payloads.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_only_retryable_sqs_failures_are_reported() {
+        let event: StageEvent = serde_json::from_value(serde_json::json!({
+            "Records": [
+                { "messageId": "ok", "body": "{}" },
+                { "messageId": "malformed", "body": "{}" },
+                { "messageId": "db-down", "body": "{}" }
+            ]
+        }))
+        .unwrap();
+        let mut results = StageResults::new(&event);
+        let outcomes = [
+            Ok(Completed::new(1, "done")),
+            Err(StageError::new(ErrorCode::InvalidMessage, "bad json")),
+            Err(StageError::new(ErrorCode::Database, "timeout").for_tender(3)),
+        ];
+
+        for (message, result) in event.into_messages().iter().zip(outcomes) {
+            results
+                .record(message, &Handoff::new(message), result)
+                .await;
+        }
+
+        assert_eq!(
+            results.summary(),
+            "1 completed, 1 retrying, 1 rejected, 0 not started"
+        );
+        let response = results.into_response().unwrap();
+        assert_eq!(
+            response,
+            serde_json::json!({ "batchItemFailures": [{ "itemIdentifier": "db-down" }] })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unstarted_messages_are_handed_back_with_the_failures() {
+        let event: StageEvent = serde_json::from_value(serde_json::json!({
+            "Records": [
+                { "messageId": "timed-out", "body": "{}" },
+                { "messageId": "ok", "body": "{}" },
+                { "messageId": "late", "body": "{}" }
+            ]
+        }))
+        .unwrap();
+        let mut results = StageResults::new(&event);
+        let messages = event.into_messages();
+
+        let timed_out = Err(StageError::new(
+            ErrorCode::Timeout,
+            "download timed out after 120s",
+        ));
+        results
+            .record(&messages[0], &Handoff::new(&messages[0]), timed_out)
+            .await;
+        results
+            .record(
+                &messages[1],
+                &Handoff::new(&messages[1]),
+                Ok(Completed::new(2, "done")),
+            )
+            .await;
+        results.record_unstarted(&messages[2]);
+
+        assert_eq!(
+            results.summary(),
+            "1 completed, 1 retrying, 0 rejected, 1 not started"
+        );
+        assert_eq!(
+            results.into_response().unwrap(),
+            serde_json::json!({ "batchItemFailures": [
+                { "itemIdentifier": "timed-out" },
+                { "itemIdentifier": "late" }
+            ] })
+        );
+    }
+
+    #[test]
+    fn test_error_codes_serialize_as_step_functions_error_names() {
+        for code in [
+            ErrorCode::InvalidMessage,
+            ErrorCode::ForwardFailed,
+            ErrorCode::Timeout,
+        ] {
+            assert_eq!(serde_json::to_value(code).unwrap(), code.as_str());
+        }
+        assert!(!ErrorCode::UnusablePdf.is_retryable());
+        assert!(ErrorCode::Upstream.is_retryable());
+    }
+}
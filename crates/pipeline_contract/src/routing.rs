@@ -0,0 +1,121 @@
+//! Routing hints carried as SQS message attributes, so bodies stay the stage payload.
+//!
+//! Forwarders used to patch hints such as `processing_stage` into the JSON body. They now
+//! go in message attributes, which consumers (and anyone looking at a queue or the
+//! dead-letter queue) can read and filter on without parsing the body:
+//!
+//! - `processing_stage`: the stage the message is for, e.g. `ml_prediction`
+//! - `priority`: `URGENT` or `NORMAL` for the AI summary queue
+//! - `schema_version`: version of the body's shape (`SCHEMA_VERSION`)
+//! - `correlation_id`: the SQS message id that started the tender's run, passed along
+//!   every hop so a run can be followed across the stages' logs
+
+use aws_lambda_events::event::sqs::SqsMessageAttribute;
+use aws_sdk_sqs::operation::send_message::builders::SendMessageFluentBuilder;
+use aws_sdk_sqs::types::MessageAttributeValue;
+use std::collections::HashMap;
+
+/// Version of the stage payloads' shape; bump when a body changes incompatibly
+pub const SCHEMA_VERSION: u32 = 1;
+
+const PROCESSING_STAGE: &str = "processing_stage";
+const PRIORITY: &str = "priority";
+const SCHEMA_VERSION_ATTRIBUTE: &str = "schema_version";
+const CORRELATION_ID: &str = "correlation_id";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Routing {
+    pub processing_stage: Option<String>,
+    pub priority: Option<String>,
+    /// None for messages sent before the attributes existed
+    pub schema_version: Option<u32>,
+    pub correlation_id: Option<String>,
+}
+
+impl Routing {
+    /// Hints for a message to `processing_stage`, at the current schema version
+    pub fn to_stage(processing_stage: &str) -> Self {
+        Self {
+            processing_stage: Some(processing_stage.to_string()),
+            priority: None,
+            schema_version: Some(SCHEMA_VERSION),
+            correlation_id: None,
+        }
+    }
+
+    pub fn priority(mut self, priority: &str) -> Self {
+        self.priority = Some(priority.to_string());
+        self
+    }
+
+    pub fn correlation_id(mut self, correlation_id: Option<String>) -> Self {
+        self.correlation_id = correlation_id;
+        self
+    }
+
+    /// Read the hints off a received message; a message without a correlation id starts a
+    /// run, so its own message id becomes one
+    pub fn from_sqs(
+        attributes: &HashMap<String, SqsMessageAttribute>,
+        message_id: Option<&str>,
+    ) -> Self {
+        let value = |name: &str| {
+            attributes
+                .get(name)
+                .and_then(|attribute| attribute.string_value.clone())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            processing_stage: value(PROCESSING_STAGE),
+            priority: value(PRIORITY),
+            schema_version: value(SCHEMA_VERSION_ATTRIBUTE).and_then(|v| v.parse().ok()),
+            correlation_id: value(CORRELATION_ID).or_else(|| message_id.map(str::to_string)),
+        }
+    }
+
+    /// Whether this build understands the body; unversioned (older) messages are assumed to
+    pub fn is_supported(&self) -> bool {
+        self.schema_version
+            .is_none_or(|version| version <= SCHEMA_VERSION)
+    }
+
+    /// The hints as (attribute, value) pairs, in a fixed order
+    pub fn attributes(&self) -> Vec<(&'static str, String)> {
+        [
+            (PROCESSING_STAGE, self.processing_stage.clone()),
+            (PRIORITY, self.priority.clone()),
+            (
+                SCHEMA_VERSION_ATTRIBUTE,
+                self.schema_version.map(|version| version.to_string()),
+            ),
+            (CORRELATION_ID, self.correlation_id.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect()
+    }
+
+    /// Attach the hints to an SQS send
+    pub fn apply(&self, mut request: SendMessageFluentBuilder) -> SendMessageFluentBuilder {
+        for (name, attribute) in self.sqs_attributes() {
+            request = request.message_attributes(name, attribute);
+        }
+        request
+    }
+
+    /// The hints as SQS message attributes, e.g. for a `SendMessageBatch` entry
+    pub fn sqs_attributes(&self) -> HashMap<String, MessageAttributeValue> {
+        self.attributes()
+            .into_iter()
+            .filter_map(|(name, value)| {
+                // Only fails without a data type, which is always set
+                let attribute = MessageAttributeValue::builder()
+                    .data_type("String")
+                    .string_value(value)
+                    .build()
+                    .ok()?;
+                Some((name.to_string(), attribute))
+            })
+            .collect()
+    }
+}
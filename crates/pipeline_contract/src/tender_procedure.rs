@@ -0,0 +1,137 @@
+//! The procurement procedure a notice was published under.
+//!
+//! Most notices are calls for competition (open, restricted, negotiated, competitive
+//! dialogue) and are assessed as bid/no-bid. Prior information notices only announce a
+//! future competition, and a dynamic purchasing system is joined rather than bid for, so
+//! ai_summary gives both an early-interest assessment and sns_notification its own email.
+
+use crate::tender_status::clean;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Procedure {
+    Open,
+    Restricted,
+    /// Negotiated procedures and competitive procedures with negotiation
+    Negotiated,
+    /// Competitive dialogue and innovation partnerships
+    CompetitiveDialogue,
+    /// Prior information notice - no competition yet
+    Pin,
+    /// Dynamic purchasing system - suppliers join, then bid on call-offs
+    Dps,
+    /// Empty, or wording we don't recognise
+    Other,
+}
+
+impl Procedure {
+    pub const ALL: [Procedure; 7] = [
+        Procedure::Open,
+        Procedure::Restricted,
+        Procedure::Negotiated,
+        Procedure::CompetitiveDialogue,
+        Procedure::Pin,
+        Procedure::Dps,
+        Procedure::Other,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Procedure::Open => "open",
+            Procedure::Restricted => "restricted",
+            Procedure::Negotiated => "negotiated",
+            Procedure::CompetitiveDialogue => "competitive_dialogue",
+            Procedure::Pin => "pin",
+            Procedure::Dps => "dps",
+            Procedure::Other => "other",
+        }
+    }
+
+    /// Shown in prompts and emails
+    pub fn label(&self) -> &'static str {
+        match self {
+            Procedure::Open => "Open procedure",
+            Procedure::Restricted => "Restricted procedure",
+            Procedure::Negotiated => "Negotiated procedure",
+            Procedure::CompetitiveDialogue => "Competitive dialogue",
+            Procedure::Pin => "Prior information notice",
+            Procedure::Dps => "Dynamic purchasing system",
+            Procedure::Other => "Other procedure",
+        }
+    }
+
+    /// Notices that call for registering interest rather than a bid decision
+    pub fn is_early_interest(&self) -> bool {
+        matches!(self, Procedure::Pin | Procedure::Dps)
+    }
+
+    /// Classify the portal's procedure text. PIN and DPS are checked first since their
+    /// notices often name the procedure the later competition will use
+    pub fn normalize(text: &str) -> Self {
+        let text = clean(text).to_lowercase();
+        let words: Vec<&str> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        let has = |phrases: &[&str]| phrases.iter().any(|phrase| text.contains(phrase));
+        let has_word = |word: &str| words.contains(&word);
+
+        if has(&["prior information"]) || has_word("pin") {
+            Procedure::Pin
+        } else if has(&["dynamic purchasing"]) || has_word("dps") {
+            Procedure::Dps
+        } else if has(&["competitive dialogue", "innovation partnership"]) {
+            Procedure::CompetitiveDialogue
+        } else if has(&["negotiat"]) {
+            Procedure::Negotiated
+        } else if has(&["restricted"]) {
+            Procedure::Restricted
+        } else if has(&["open"]) {
+            Procedure::Open
+        } else {
+            Procedure::Other
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_portal_text() {
+        assert_eq!(Procedure::normalize("Open procedure"), Procedure::Open);
+        assert_eq!(Procedure::normalize("RESTRICTED"), Procedure::Restricted);
+        assert_eq!(
+            Procedure::normalize("Competitive procedure with negotiation"),
+            Procedure::Negotiated
+        );
+        assert_eq!(
+            Procedure::normalize("Negotiated without prior publication"),
+            Procedure::Negotiated
+        );
+        assert_eq!(
+            Procedure::normalize("Competitive Dialogue"),
+            Procedure::CompetitiveDialogue
+        );
+        assert_eq!(
+            Procedure::normalize("<b>Prior Information Notice</b> (restricted)"),
+            Procedure::Pin
+        );
+        assert_eq!(Procedure::normalize("PIN"), Procedure::Pin);
+        assert_eq!(
+            Procedure::normalize("Dynamic Purchasing System - Open"),
+            Procedure::Dps
+        );
+        assert_eq!(Procedure::normalize("Shipping"), Procedure::Other);
+        assert_eq!(Procedure::normalize(""), Procedure::Other);
+    }
+
+    #[test]
+    fn test_early_interest() {
+        let early: Vec<Procedure> = Procedure::ALL
+            .into_iter()
+            .filter(Procedure::is_early_interest)
+            .collect();
+        assert_eq!(early, vec![Procedure::Pin, Procedure::Dps]);
+    }
+}
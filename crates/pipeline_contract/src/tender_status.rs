@@ -0,0 +1,160 @@
+//! The portal's tender status, normalised so stages can act on it.
+//!
+//! eTenders shows free text ("Open", "Cancelled", "Closed - Awarded", sometimes with
+//! markup or odd spacing). The scraper stores the normalised label in `status`, and
+//! postgres_dataload only queues tenders whose status is in `PIPELINE_STATUSES` (a comma
+//! separated list of `name()`s, default `open,unknown`). Unknown is let through by default
+//! so new portal wording doesn't quietly stop the pipeline.
+
+use tracing::warn;
+
+/// Statuses that go through the pipeline when `PIPELINE_STATUSES` is unset
+pub const DEFAULT_PIPELINE_STATUSES: &[TenderStatus] = &[TenderStatus::Open, TenderStatus::Unknown];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TenderStatus {
+    Open,
+    /// Past its deadline, including under evaluation
+    Closed,
+    Cancelled,
+    Awarded,
+    /// Empty, or wording we don't recognise
+    Unknown,
+}
+
+impl TenderStatus {
+    pub const ALL: [TenderStatus; 5] = [
+        TenderStatus::Open,
+        TenderStatus::Closed,
+        TenderStatus::Cancelled,
+        TenderStatus::Awarded,
+        TenderStatus::Unknown,
+    ];
+
+    /// Name used in `PIPELINE_STATUSES`
+    pub fn name(&self) -> &'static str {
+        match self {
+            TenderStatus::Open => "open",
+            TenderStatus::Closed => "closed",
+            TenderStatus::Cancelled => "cancelled",
+            TenderStatus::Awarded => "awarded",
+            TenderStatus::Unknown => "unknown",
+        }
+    }
+
+    /// Label stored in `tender_records.status`; None keeps the portal's text
+    pub fn label(&self) -> Option<&'static str> {
+        match self {
+            TenderStatus::Open => Some("Open"),
+            TenderStatus::Closed => Some("Closed"),
+            TenderStatus::Cancelled => Some("Cancelled"),
+            TenderStatus::Awarded => Some("Awarded"),
+            TenderStatus::Unknown => None,
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        Self::ALL.into_iter().find(|status| status.name() == name)
+    }
+
+    /// Classify the portal's status text. Cancellation and award win over "closed", which
+    /// the portal often shows alongside them
+    pub fn normalize(text: &str) -> Self {
+        let text = clean(text).to_lowercase();
+        let has = |words: &[&str]| words.iter().any(|word| text.contains(word));
+        if has(&["cancel", "withdrawn"]) {
+            TenderStatus::Cancelled
+        } else if has(&["award"]) {
+            TenderStatus::Awarded
+        } else if has(&["closed", "expired", "evaluation"]) {
+            TenderStatus::Closed
+        } else if has(&["open", "active", "live"]) {
+            TenderStatus::Open
+        } else {
+            TenderStatus::Unknown
+        }
+    }
+}
+
+/// The portal text without markup, entities or repeated whitespace
+pub fn clean(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => stripped.push(c),
+            _ => {}
+        }
+    }
+    stripped
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parse a `PIPELINE_STATUSES` value; unrecognised names are logged and skipped, and an
+/// unset or empty value gives the default
+pub fn parse_allowlist(value: Option<&str>) -> Vec<TenderStatus> {
+    let Some(value) = value.filter(|v| !v.trim().is_empty()) else {
+        return DEFAULT_PIPELINE_STATUSES.to_vec();
+    };
+    let mut statuses = Vec::new();
+    for name in value.split(',').filter(|name| !name.trim().is_empty()) {
+        match TenderStatus::parse(name) {
+            Some(status) if !statuses.contains(&status) => statuses.push(status),
+            Some(_) => {}
+            None => warn!(
+                "⚠️ Ignoring unknown tender status '{}' in PIPELINE_STATUSES",
+                name.trim()
+            ),
+        }
+    }
+    statuses
+}
+
+pub fn allowlist_from_env() -> Vec<TenderStatus> {
+    parse_allowlist(std::env::var("PIPELINE_STATUSES").ok().as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_portal_text() {
+        assert_eq!(TenderStatus::normalize("Open"), TenderStatus::Open);
+        assert_eq!(
+            TenderStatus::normalize(" <span>OPEN</span>&nbsp;"),
+            TenderStatus::Open
+        );
+        assert_eq!(
+            TenderStatus::normalize("Cancelled"),
+            TenderStatus::Cancelled
+        );
+        assert_eq!(
+            TenderStatus::normalize("Closed - Awarded"),
+            TenderStatus::Awarded
+        );
+        assert_eq!(
+            TenderStatus::normalize("Under Evaluation"),
+            TenderStatus::Closed
+        );
+        assert_eq!(TenderStatus::normalize(""), TenderStatus::Unknown);
+        assert_eq!(TenderStatus::normalize("Paused"), TenderStatus::Unknown);
+    }
+
+    #[test]
+    fn test_allowlist() {
+        assert_eq!(parse_allowlist(None), DEFAULT_PIPELINE_STATUSES);
+        assert_eq!(parse_allowlist(Some(" ")), DEFAULT_PIPELINE_STATUSES);
+        assert_eq!(
+            parse_allowlist(Some("Open, closed,open,bogus")),
+            vec![TenderStatus::Open, TenderStatus::Closed]
+        );
+    }
+}
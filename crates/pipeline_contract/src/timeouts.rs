@@ -0,0 +1,281 @@
+//! Time limits on the calls a stage makes to things that can hang.
+//!
+//! Each kind of call - a PDF download, text extraction, a database query, a Claude request -
+//! gets its own limit, so one unresponsive PDF host or a stuck query fails that message
+//! with a `Timeout` error instead of silently running the lambda into its own timeout.
+//! Hitting a limit drops the call's future, which cancels the request in flight; blocking
+//! work run through `Timeouts::run_blocking` is asked to stop at its next checkpoint.
+//!
+//! Limits come from `<CALL>_TIMEOUT_SECS` (see `Call::env_var`); 0 removes the limit.
+
+use crate::{ErrorCode, StageError};
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+/// A kind of external call, each with its own limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Call {
+    /// Fetching the PDF, retries and reading the body included
+    Download,
+    /// Extracting the PDF's text
+    Extraction,
+    /// One database query or a short sequence of them
+    Database,
+    /// A Claude request, retries included
+    Claude,
+}
+
+impl Call {
+    pub const ALL: [Call; 4] = [
+        Call::Download,
+        Call::Extraction,
+        Call::Database,
+        Call::Claude,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Call::Download => "download",
+            Call::Extraction => "extraction",
+            Call::Database => "database",
+            Call::Claude => "claude",
+        }
+    }
+
+    pub fn env_var(self) -> &'static str {
+        match self {
+            Call::Download => "DOWNLOAD_TIMEOUT_SECS",
+            Call::Extraction => "EXTRACTION_TIMEOUT_SECS",
+            Call::Database => "DB_QUERY_TIMEOUT_SECS",
+            Call::Claude => "CLAUDE_TIMEOUT_SECS",
+        }
+    }
+
+    /// Well inside the 5-minute (ai_summary) and 15-minute (pdf_processing) lambda timeouts
+    fn default_limit(self) -> Duration {
+        Duration::from_secs(match self {
+            Call::Download => 120,
+            Call::Extraction => 300,
+            Call::Database => 30,
+            Call::Claude => 120,
+        })
+    }
+}
+
+/// A call that didn't finish within its limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut {
+    pub call: Call,
+    pub limit: Duration,
+}
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} timed out after {}s",
+            self.call.name(),
+            self.limit.as_secs_f32()
+        )
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+impl From<TimedOut> for StageError {
+    fn from(timed_out: TimedOut) -> Self {
+        StageError::new(ErrorCode::Timeout, timed_out.to_string())
+    }
+}
+
+/// Await `future` for at most `limit` (forever when `None`), dropping it once the limit passes
+pub async fn within<F: Future>(
+    call: Call,
+    limit: Option<Duration>,
+    future: F,
+) -> Result<F::Output, TimedOut> {
+    let Some(limit) = limit else {
+        return Ok(future.await);
+    };
+    tokio::time::timeout(limit, future).await.map_err(|_| {
+        warn!("⏱️ {} call timed out after {:?}", call.name(), limit);
+        TimedOut { call, limit }
+    })
+}
+
+/// The limit for each kind of call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeouts {
+    pub download: Option<Duration>,
+    pub extraction: Option<Duration>,
+    pub database: Option<Duration>,
+    pub claude: Option<Duration>,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            download: Some(Call::Download.default_limit()),
+            extraction: Some(Call::Extraction.default_limit()),
+            database: Some(Call::Database.default_limit()),
+            claude: Some(Call::Claude.default_limit()),
+        }
+    }
+}
+
+impl Timeouts {
+    /// Defaults overridden by each call's `<CALL>_TIMEOUT_SECS`; an unparseable value keeps the default
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let mut timeouts = Self::default();
+        for call in Call::ALL {
+            let Some(value) = lookup(call.env_var()) else {
+                continue;
+            };
+            match value.trim().parse::<u64>() {
+                Ok(0) => *timeouts.limit_mut(call) = None,
+                Ok(secs) => *timeouts.limit_mut(call) = Some(Duration::from_secs(secs)),
+                Err(_) => warn!(
+                    "⚠️ Ignoring {}='{}' - not a whole number",
+                    call.env_var(),
+                    value
+                ),
+            }
+        }
+        timeouts
+    }
+
+    pub fn limit(&self, call: Call) -> Option<Duration> {
+        match call {
+            Call::Download => self.download,
+            Call::Extraction => self.extraction,
+            Call::Database => self.database,
+            Call::Claude => self.claude,
+        }
+    }
+
+    fn limit_mut(&mut self, call: Call) -> &mut Option<Duration> {
+        match call {
+            Call::Download => &mut self.download,
+            Call::Extraction => &mut self.extraction,
+            Call::Database => &mut self.database,
+            Call::Claude => &mut self.claude,
+        }
+    }
+
+    /// For the config diagnostic, e.g. "download 120s, extraction 300s, database 30s, claude none"
+    pub fn describe(&self) -> String {
+        Call::ALL
+            .iter()
+            .map(|&call| match self.limit(call) {
+                Some(limit) => format!("{} {}s", call.name(), limit.as_secs()),
+                None => format!("{} none", call.name()),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub async fn run<F: Future>(&self, call: Call, future: F) -> Result<F::Output, TimedOut> {
+        within(call, self.limit(call), future).await
+    }
+
+    /// Run CPU-bound work on the blocking pool within `call`'s limit. Blocking work can't be
+    /// dropped mid-way, so `work` is handed a flag that is set once the limit passes and
+    /// should stop at its next checkpoint; whatever it returns after that is discarded
+    pub async fn run_blocking<T, W>(&self, call: Call, work: W) -> Result<T, TimedOut>
+    where
+        T: Send + 'static,
+        W: FnOnce(&AtomicBool) -> T + Send + 'static,
+    {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancelled);
+        let handle = tokio::task::spawn_blocking(move || work(&flag));
+
+        let result = self.run(call, handle).await;
+        if result.is_err() {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+        match result? {
+            Ok(output) => Ok(output),
+            // The work panicked; carry on as if it had run on this task
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_come_from_env_with_zero_meaning_none() {
+        let timeouts = Timeouts::from_lookup(|name| match name {
+            "DOWNLOAD_TIMEOUT_SECS" => Some("45".to_string()),
+            "CLAUDE_TIMEOUT_SECS" => Some("0".to_string()),
+            "DB_QUERY_TIMEOUT_SECS" => Some("soon".to_string()),
+            _ => None,
+        });
+        assert_eq!(timeouts.download, Some(Duration::from_secs(45)));
+        assert_eq!(timeouts.claude, None);
+        assert_eq!(timeouts.database, Timeouts::default().database);
+        assert_eq!(
+            timeouts.describe(),
+            "download 45s, extraction 300s, database 30s, claude none"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_a_hung_call_times_out_distinct_from_its_own_errors() {
+        let timeouts = Timeouts {
+            download: Some(Duration::from_secs(5)),
+            ..Timeouts::default()
+        };
+
+        let hung = timeouts
+            .run(Call::Download, tokio::time::sleep(Duration::from_secs(60)))
+            .await;
+        let timed_out = hung.unwrap_err();
+        assert_eq!(timed_out.to_string(), "download timed out after 5s");
+
+        let error = StageError::from(timed_out);
+        assert_eq!(error.code, ErrorCode::Timeout);
+        assert!(error.is_retryable());
+
+        let quick = timeouts
+            .run(Call::Download, async { Err::<(), _>("HTTP 500") })
+            .await;
+        assert_eq!(quick, Ok(Err("HTTP 500")));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_blocking_work_is_told_to_stop() {
+        let timeouts = Timeouts {
+            extraction: Some(Duration::from_millis(20)),
+            ..Timeouts::default()
+        };
+        let (stopped_tx, stopped_rx) = std::sync::mpsc::channel();
+
+        let result = timeouts
+            .run_blocking(Call::Extraction, move |cancelled| {
+                while !cancelled.load(Ordering::Relaxed) {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                stopped_tx.send(()).unwrap();
+            })
+            .await;
+        assert_eq!(result.unwrap_err().call, Call::Extraction);
+        stopped_rx
+            .recv_timeout(Duration::from_secs(5))
+            .expect("the blocking work never saw the cancellation");
+
+        let finished = timeouts.run_blocking(Call::Extraction, |_| 42).await;
+        assert_eq!(finished, Ok(42));
+    }
+}
@@ -0,0 +1,537 @@
+//! Per-tender, per-stage progress through the pipeline, kept in `pipeline_status`.
+//!
+//! Each stage lambda calls `started` when it picks up a tender (storing the queue
+//! message it received), then `completed` or `failed`. A stage that never reaches
+//! `completed` - a timeout, a crash, an error swallowed after the message was deleted -
+//! is left behind as a stalled row that `pipeline_watchdog` can requeue from the stored
+//! message.
+//!
+//! A permanent failure (a message that can never succeed, e.g. malformed) is recorded
+//! with `rejected` instead, which the watchdog leaves alone. A call that ran past its limit
+//! (see `pipeline_contract::timeouts`) is recorded with `timed_out`, so a hanging PDF host
+//! or query can be told apart from an error; it is requeued like a failure.
+//!
+//! Stages with expensive work (ml_bid_predictor, ai_summary) call `claim` instead of
+//! `started`, which also turns away a tender another invocation is already processing. One
+//! that hands the rest of its work to a continuation message records `continued`, and one
+//! that only finished part of its work records `failed_with_retry` with a message for the rest.
+//!
+//! Recording is best-effort: `started`/`completed`/`failed` log and carry on rather
+//! than fail the stage they are tracking.
+//!
+//! `lifecycle` tracks the tender itself - which states it has been through - so stages can
+//! reject messages that arrive out of order. `slo` measures scrape-to-notification latency
+//! from the notification stage's completion times.
+
+pub mod lifecycle;
+pub mod slo;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use environment::Environment;
+use sqlx::{PgPool, Row};
+use tracing::warn;
+
+/// How long a `started` row blocks `claim` - the Lambda timeout ceiling, so a live
+/// invocation always holds its claim until it finishes
+pub const CLAIM_LEASE_SECONDS: i64 = 15 * 60;
+
+/// Queue-driven stages, in pipeline order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    PdfProcessing,
+    MlPrediction,
+    AiSummary,
+    Notification,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [
+        Stage::PdfProcessing,
+        Stage::MlPrediction,
+        Stage::AiSummary,
+        Stage::Notification,
+    ];
+
+    /// Value stored in `pipeline_status.stage`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::PdfProcessing => "pdf_processing",
+            Stage::MlPrediction => "ml_prediction",
+            Stage::AiSummary => "ai_summary",
+            Stage::Notification => "notification",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|stage| stage.name() == value)
+    }
+}
+
+/// A stage that started but hasn't completed
+#[derive(Debug, Clone)]
+pub struct StalledStage {
+    pub resource_id: i64,
+    pub stage: Stage,
+    /// started, failed, timed_out or requeued
+    pub status: String,
+    pub attempts: i32,
+    pub requeues: i32,
+    /// Queue message body the stage last received, used to requeue it
+    pub message: Option<String>,
+    pub last_error: Option<String>,
+    pub started_at: DateTime<Utc>,
+}
+
+pub async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "pipeline_status", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS pipeline_status (
+                resource_id BIGINT NOT NULL,
+                stage TEXT NOT NULL,
+                status TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                requeues INTEGER NOT NULL DEFAULT 0,
+                message TEXT,
+                last_error TEXT,
+                started_at TIMESTAMPTZ,
+                completed_at TIMESTAMPTZ,
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (resource_id, stage)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_pipeline_status_open ON pipeline_status (started_at) WHERE status <> 'completed'",
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "pipeline_status").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Record that a stage picked up a tender, keeping the message so it can be requeued
+pub async fn started(pool: &PgPool, resource_id: i64, stage: Stage, message: &str) {
+    let result = async {
+        ensure_table(pool).await?;
+        sqlx::query(
+            r#"
+            INSERT INTO pipeline_status (resource_id, stage, status, attempts, message, started_at)
+            VALUES ($1, $2, 'started', 1, $3, NOW())
+            ON CONFLICT (resource_id, stage) DO UPDATE SET
+                status = 'started',
+                attempts = pipeline_status.attempts + 1,
+                message = EXCLUDED.message,
+                last_error = NULL,
+                started_at = NOW(),
+                completed_at = NULL,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(resource_id)
+        .bind(stage.name())
+        .bind(message)
+        .execute(pool)
+        .await?;
+        anyhow::Ok(())
+    }
+    .await;
+
+    if let Err(e) = result {
+        warn!(
+            "⚠️ Failed to record {} start for {}: {}",
+            stage.name(),
+            resource_id,
+            e
+        );
+    }
+}
+
+/// Outcome of `claim`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Claim {
+    Claimed,
+    /// Another invocation started the stage; its lease runs until then
+    Held {
+        until: DateTime<Utc>,
+    },
+}
+
+impl Claim {
+    /// Whether a stage row with `status`, started at `started_at`, may be claimed at `now`;
+    /// no row means nobody has started the stage
+    fn decide(current: Option<(&str, Option<DateTime<Utc>>)>, now: DateTime<Utc>) -> Claim {
+        match current {
+            Some(("started", Some(started_at))) => {
+                let until = started_at + Duration::seconds(CLAIM_LEASE_SECONDS);
+                if until > now {
+                    Claim::Held { until }
+                } else {
+                    Claim::Claimed
+                }
+            }
+            _ => Claim::Claimed,
+        }
+    }
+}
+
+/// Like `started`, but only when no other invocation is working on the tender: a retry
+/// and a new message for the same tender can otherwise run the stage twice at once.
+///
+/// `Held` means another invocation claimed it less than `CLAIM_LEASE_SECONDS` ago. The
+/// caller should fail the message as retryable rather than drop it, so it comes back once
+/// the lease has run out in case that invocation died. Database errors return `Claimed` -
+/// the claim only saves duplicate work, it must never stop the pipeline
+pub async fn claim(pool: &PgPool, resource_id: i64, stage: Stage, message: &str) -> Claim {
+    let result = async {
+        ensure_table(pool).await?;
+        let mut tx = pool.begin().await?;
+
+        // Only one of two concurrent first claims inserts; the other waits and then sees
+        // its row below
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO pipeline_status (resource_id, stage, status, attempts, message, started_at)
+            VALUES ($1, $2, 'started', 1, $3, NOW())
+            ON CONFLICT (resource_id, stage) DO NOTHING
+            "#,
+        )
+        .bind(resource_id)
+        .bind(stage.name())
+        .bind(message)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if inserted {
+            tx.commit().await?;
+            return anyhow::Ok(Claim::Claimed);
+        }
+
+        // The row lock decides racing claims one after the other
+        let row = sqlx::query(
+            r#"
+            SELECT status, started_at, NOW() AS now FROM pipeline_status
+            WHERE resource_id = $1 AND stage = $2
+            FOR UPDATE
+            "#,
+        )
+        .bind(resource_id)
+        .bind(stage.name())
+        .fetch_one(&mut *tx)
+        .await?;
+        let status: String = row.get("status");
+        let claim = Claim::decide(Some((&status, row.get("started_at"))), row.get("now"));
+
+        if claim == Claim::Claimed {
+            sqlx::query(
+                r#"
+                UPDATE pipeline_status SET
+                    status = 'started',
+                    attempts = attempts + 1,
+                    message = $3,
+                    last_error = NULL,
+                    started_at = NOW(),
+                    completed_at = NULL,
+                    updated_at = NOW()
+                WHERE resource_id = $1 AND stage = $2
+                "#,
+            )
+            .bind(resource_id)
+            .bind(stage.name())
+            .bind(message)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        anyhow::Ok(claim)
+    }
+    .await;
+
+    match result {
+        Ok(claim) => claim,
+        Err(e) => {
+            warn!(
+                "⚠️ Failed to claim {} for {}, processing anyway: {}",
+                stage.name(),
+                resource_id,
+                e
+            );
+            Claim::Claimed
+        }
+    }
+}
+
+pub async fn completed(pool: &PgPool, resource_id: i64, stage: Stage) {
+    let result = sqlx::query(
+        r#"
+        UPDATE pipeline_status
+        SET status = 'completed', completed_at = NOW(), updated_at = NOW()
+        WHERE resource_id = $1 AND stage = $2
+        "#,
+    )
+    .bind(resource_id)
+    .bind(stage.name())
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        warn!(
+            "⚠️ Failed to record {} completion for {}: {}",
+            stage.name(),
+            resource_id,
+            e
+        );
+    }
+}
+
+pub async fn failed(pool: &PgPool, resource_id: i64, stage: Stage, error: &str) {
+    let result = sqlx::query(
+        r#"
+        UPDATE pipeline_status
+        SET status = 'failed', last_error = $3, updated_at = NOW()
+        WHERE resource_id = $1 AND stage = $2
+        "#,
+    )
+    .bind(resource_id)
+    .bind(stage.name())
+    .bind(error)
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        warn!(
+            "⚠️ Failed to record {} failure for {}: {}",
+            stage.name(),
+            resource_id,
+            e
+        );
+    }
+}
+
+/// Record a failure along with the message to requeue instead of the one the stage received,
+/// e.g. one narrowed to the part that failed. With `None` there is nothing to requeue, so
+/// pipeline_watchdog reports the stage rather than redo work that succeeded
+pub async fn failed_with_retry(
+    pool: &PgPool,
+    resource_id: i64,
+    stage: Stage,
+    error: &str,
+    message: Option<&str>,
+) {
+    let result = sqlx::query(
+        r#"
+        UPDATE pipeline_status
+        SET status = 'failed', last_error = $3, message = $4, updated_at = NOW()
+        WHERE resource_id = $1 AND stage = $2
+        "#,
+    )
+    .bind(resource_id)
+    .bind(stage.name())
+    .bind(error)
+    .bind(message)
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        warn!(
+            "⚠️ Failed to record {} failure for {}: {}",
+            stage.name(),
+            resource_id,
+            e
+        );
+    }
+}
+
+/// Record a failure caused by a call running past its time limit rather than erroring
+pub async fn timed_out(pool: &PgPool, resource_id: i64, stage: Stage, error: &str) {
+    let result = sqlx::query(
+        r#"
+        UPDATE pipeline_status
+        SET status = 'timed_out', last_error = $3, updated_at = NOW()
+        WHERE resource_id = $1 AND stage = $2
+        "#,
+    )
+    .bind(resource_id)
+    .bind(stage.name())
+    .bind(error)
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        warn!(
+            "⚠️ Failed to record {} timeout for {}: {}",
+            stage.name(),
+            resource_id,
+            e
+        );
+    }
+}
+
+/// Record that the stage handed the rest of its work to a continuation message. The claim is
+/// released for the continuation, and the stall clock restarts: if the continuation is lost,
+/// pipeline_watchdog requeues the original message, which resumes where the work stopped
+pub async fn continued(pool: &PgPool, resource_id: i64, stage: Stage) {
+    let result = sqlx::query(
+        r#"
+        UPDATE pipeline_status
+        SET status = 'continued', started_at = NOW(), updated_at = NOW()
+        WHERE resource_id = $1 AND stage = $2
+        "#,
+    )
+    .bind(resource_id)
+    .bind(stage.name())
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        warn!(
+            "⚠️ Failed to record {} continuation for {}: {}",
+            stage.name(),
+            resource_id,
+            e
+        );
+    }
+}
+
+/// Record a failure that retrying can't fix, so the watchdog doesn't requeue it
+pub async fn rejected(pool: &PgPool, resource_id: i64, stage: Stage, error: &str) {
+    let result = sqlx::query(
+        r#"
+        UPDATE pipeline_status
+        SET status = 'rejected', last_error = $3, updated_at = NOW()
+        WHERE resource_id = $1 AND stage = $2
+        "#,
+    )
+    .bind(resource_id)
+    .bind(stage.name())
+    .bind(error)
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        warn!(
+            "⚠️ Failed to record {} rejection for {}: {}",
+            stage.name(),
+            resource_id,
+            e
+        );
+    }
+}
+
+/// Stages started more than `older_than` ago that haven't completed (or been rejected), oldest first
+pub async fn find_stalled(
+    pool: &PgPool,
+    older_than: chrono::Duration,
+) -> Result<Vec<StalledStage>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT resource_id, stage, status, attempts, requeues, message, last_error, started_at
+        FROM pipeline_status
+        WHERE status NOT IN ('completed', 'rejected')
+          AND started_at < NOW() - ($1::BIGINT * INTERVAL '1 second')
+        ORDER BY started_at
+        "#,
+    )
+    .bind(older_than.num_seconds())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let stage_name: String = row.get("stage");
+            Some(StalledStage {
+                resource_id: row.get("resource_id"),
+                stage: Stage::parse(&stage_name)?,
+                status: row.get("status"),
+                attempts: row.get("attempts"),
+                requeues: row.get("requeues"),
+                message: row.get("message"),
+                last_error: row.get("last_error"),
+                started_at: row.get("started_at"),
+            })
+        })
+        .collect())
+}
+
+/// Record a requeue; restarts the stall clock so the stage gets a full window to pick it up
+pub async fn record_requeued(pool: &PgPool, resource_id: i64, stage: Stage) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE pipeline_status
+        SET status = 'requeued', requeues = requeues + 1, started_at = NOW(), updated_at = NOW()
+        WHERE resource_id = $1 AND stage = $2
+        "#,
+    )
+    .bind(resource_id)
+    .bind(stage.name())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_names_round_trip() {
+        for stage in Stage::ALL {
+            assert_eq!(Stage::parse(stage.name()), Some(stage));
+        }
+        assert_eq!(Stage::parse("scraping"), None);
+    }
+
+    #[test]
+    fn test_first_claim_is_granted() {
+        let now = Utc::now();
+        assert_eq!(Claim::decide(None, now), Claim::Claimed);
+        assert_eq!(
+            Claim::decide(Some(("completed", Some(now))), now),
+            Claim::Claimed
+        );
+        assert_eq!(
+            Claim::decide(Some(("failed", Some(now))), now),
+            Claim::Claimed
+        );
+    }
+
+    #[test]
+    fn test_duplicate_is_held_while_the_lease_is_live() {
+        let now = Utc::now();
+        let started_at = now - Duration::minutes(5);
+        assert_eq!(
+            Claim::decide(Some(("started", Some(started_at))), now),
+            Claim::Held {
+                until: started_at + Duration::seconds(CLAIM_LEASE_SECONDS)
+            }
+        );
+    }
+
+    #[test]
+    fn test_expired_lease_is_reclaimed() {
+        let now = Utc::now();
+        let started_at = now - Duration::seconds(CLAIM_LEASE_SECONDS);
+        assert_eq!(
+            Claim::decide(Some(("started", Some(started_at))), now),
+            Claim::Claimed
+        );
+        assert_eq!(
+            Claim::decide(
+                Some(("started", Some(started_at + Duration::seconds(1)))),
+                now
+            ),
+            Claim::Held {
+                until: now + Duration::seconds(1)
+            }
+        );
+    }
+}
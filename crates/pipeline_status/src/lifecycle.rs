@@ -0,0 +1,431 @@
+//! Where each tender is in its life, as an explicit state machine kept in `tender_lifecycle`.
+//!
+//! ```text
+//! Scraped -> Loaded -> PdfProcessed -> Predicted -> Summarized -> Notified ---> Closed
+//!              |   \                            /            \             /    ^
+//!              |    -> TitleOnly ---------------              -> Suppressed     |
+//!              |                                                                |
+//!               -> FilteredOut -------------------------------------------------
+//! ```
+//!
+//! `Suppressed` can still become `Notified` (a refreshed summary can change the verdict),
+//! and both close once the tender's deadline has passed, as do tenders postgres_dataload's
+//! prefilter kept out of the pipeline (`FilteredOut`). `pipeline_status` tracks whether
+//! a stage's run finished; this tracks what the tender has been through, so a stage can
+//! tell a message that arrives out of order (an ML prediction for a tender that was never
+//! processed, a redelivered summary for a tender already notified) from a retry.
+//!
+//! Each stage calls `may_enter` when it picks up a tender and rejects the message
+//! (`ErrorCode::InvalidState`) when it returns false. It calls `advance` once its own work
+//! is done but before handing the tender on, so the next stage never finds the tender a
+//! step behind. Every attempt, accepted or not, is kept in `tender_lifecycle_transitions`.
+//!
+//! Tenders loaded before the lifecycle existed have no row and may enter any state, and
+//! canary tenders, which rerun the whole pipeline under the same resource_id, aren't
+//! tracked. Like the rest of the crate, recording is best-effort: a database error logs
+//! and lets the stage carry on.
+
+use anyhow::Result;
+use environment::Environment;
+use sqlx::PgPool;
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum State {
+    Scraped,
+    Loaded,
+    PdfProcessed,
+    /// No usable PDF text; the tender goes to AI summary on its title alone
+    TitleOnly,
+    Predicted,
+    Summarized,
+    Notified,
+    /// Summarized but not worth a notification
+    Suppressed,
+    /// Below the value or too close to the deadline to bid; never queued
+    FilteredOut,
+    Closed,
+}
+
+/// Outcome of checking a move from a tender's current state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    Allowed,
+    /// Already in the state: a retry or redelivery of the stage that put it there
+    Repeat,
+    OutOfOrder,
+}
+
+impl State {
+    pub const ALL: [State; 10] = [
+        State::Scraped,
+        State::Loaded,
+        State::PdfProcessed,
+        State::TitleOnly,
+        State::Predicted,
+        State::Summarized,
+        State::Notified,
+        State::Suppressed,
+        State::FilteredOut,
+        State::Closed,
+    ];
+
+    /// Value stored in `tender_lifecycle.state`
+    pub fn name(&self) -> &'static str {
+        match self {
+            State::Scraped => "scraped",
+            State::Loaded => "loaded",
+            State::PdfProcessed => "pdf_processed",
+            State::TitleOnly => "title_only",
+            State::Predicted => "predicted",
+            State::Summarized => "summarized",
+            State::Notified => "notified",
+            State::Suppressed => "suppressed",
+            State::FilteredOut => "filtered_out",
+            State::Closed => "closed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|state| state.name() == value)
+    }
+
+    /// States reachable in one step
+    pub fn next(&self) -> &'static [State] {
+        match self {
+            State::Scraped => &[State::Loaded],
+            State::Loaded => &[State::PdfProcessed, State::TitleOnly, State::FilteredOut],
+            State::PdfProcessed => &[State::Predicted],
+            State::TitleOnly | State::Predicted => &[State::Summarized],
+            State::Summarized => &[State::Notified, State::Suppressed],
+            State::Suppressed => &[State::Notified, State::Closed],
+            State::Notified | State::FilteredOut => &[State::Closed],
+            State::Closed => &[],
+        }
+    }
+
+    pub fn can_move_to(&self, to: State) -> bool {
+        self.next().contains(&to)
+    }
+}
+
+/// Whether a tender in `current` (None for an untracked tender) may move to `to`
+pub fn check(current: Option<State>, to: State) -> Check {
+    match current {
+        None => Check::Allowed,
+        Some(current) if current == to => Check::Repeat,
+        Some(current) if current.can_move_to(to) => Check::Allowed,
+        Some(_) => Check::OutOfOrder,
+    }
+}
+
+pub async fn ensure_tables(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "tender_lifecycle", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS tender_lifecycle (
+                resource_id BIGINT PRIMARY KEY,
+                state TEXT NOT NULL,
+                updated_by TEXT NOT NULL,
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS tender_lifecycle_transitions (
+                id BIGSERIAL PRIMARY KEY,
+                resource_id BIGINT NOT NULL,
+                from_state TEXT,
+                to_state TEXT NOT NULL,
+                actor TEXT NOT NULL,
+                outcome TEXT NOT NULL,
+                recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_tender_lifecycle_transitions_resource ON tender_lifecycle_transitions (resource_id, recorded_at)",
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "tender_lifecycle").await?;
+        Environment::ensure_environment_column(pool, "tender_lifecycle_transitions").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// The tender's current state; None if it isn't tracked
+pub async fn current(pool: &PgPool, resource_id: i64) -> Result<Option<State>> {
+    ensure_tables(pool).await?;
+    let state: Option<String> =
+        sqlx::query_scalar("SELECT state FROM tender_lifecycle WHERE resource_id = $1")
+            .bind(resource_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(state.as_deref().and_then(State::parse))
+}
+
+async fn record_attempt(
+    executor: impl sqlx::PgExecutor<'_>,
+    resource_id: i64,
+    from: Option<State>,
+    to: State,
+    actor: &str,
+    outcome: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO tender_lifecycle_transitions (resource_id, from_state, to_state, actor, outcome)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(resource_id)
+    .bind(from.map(|state| state.name()))
+    .bind(to.name())
+    .bind(actor)
+    .bind(outcome)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+fn warn_out_of_order(resource_id: i64, from: Option<State>, to: State, actor: &str) {
+    warn!(
+        "🚫 {} can't move tender {} from {} to {}",
+        actor,
+        resource_id,
+        from.map_or("untracked", |state| state.name()),
+        to.name()
+    );
+}
+
+/// Whether `actor` may start work that ends with the tender in `to`, or in any of `also`
+/// (a stage with more than one outcome). False means the message is out of order; the
+/// attempt is recorded and the caller should reject it. Database errors return true, as
+/// for `claim`
+pub async fn may_enter(
+    pool: &PgPool,
+    resource_id: i64,
+    to: State,
+    also: &[State],
+    actor: &str,
+) -> bool {
+    if environment::is_canary(resource_id) {
+        return true;
+    }
+    let result = async {
+        let from = current(pool, resource_id).await?;
+        let in_order = std::iter::once(to)
+            .chain(also.iter().copied())
+            .any(|to| check(from, to) != Check::OutOfOrder);
+        if !in_order {
+            record_attempt(pool, resource_id, from, to, actor, "rejected").await?;
+        }
+        anyhow::Ok((from, in_order))
+    }
+    .await;
+
+    match result {
+        Ok((from, false)) => {
+            warn_out_of_order(resource_id, from, to, actor);
+            false
+        }
+        Ok(_) => true,
+        Err(e) => {
+            warn!(
+                "⚠️ Failed to check lifecycle of {} for {}, processing anyway: {}",
+                resource_id, actor, e
+            );
+            true
+        }
+    }
+}
+
+/// Move the tender to `to` once `actor`'s work is done. Returns false if the move is out of
+/// order (recorded, state unchanged); a repeat is recorded and leaves the state as it is
+pub async fn advance(pool: &PgPool, resource_id: i64, to: State, actor: &str) -> bool {
+    if environment::is_canary(resource_id) {
+        return true;
+    }
+    let result = async {
+        ensure_tables(pool).await?;
+        let mut tx = pool.begin().await?;
+        let from: Option<String> = sqlx::query_scalar(
+            "SELECT state FROM tender_lifecycle WHERE resource_id = $1 FOR UPDATE",
+        )
+        .bind(resource_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let from = from.as_deref().and_then(State::parse);
+
+        let check = check(from, to);
+        if check == Check::Allowed {
+            sqlx::query(
+                r#"
+                INSERT INTO tender_lifecycle (resource_id, state, updated_by, updated_at)
+                VALUES ($1, $2, $3, NOW())
+                ON CONFLICT (resource_id) DO UPDATE SET
+                    state = EXCLUDED.state,
+                    updated_by = EXCLUDED.updated_by,
+                    updated_at = NOW()
+                "#,
+            )
+            .bind(resource_id)
+            .bind(to.name())
+            .bind(actor)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let outcome = match check {
+            Check::Allowed => "accepted",
+            Check::Repeat => "repeated",
+            Check::OutOfOrder => "rejected",
+        };
+        record_attempt(&mut *tx, resource_id, from, to, actor, outcome).await?;
+        tx.commit().await?;
+        anyhow::Ok((from, check))
+    }
+    .await;
+
+    match result {
+        Ok((from, Check::OutOfOrder)) => {
+            warn_out_of_order(resource_id, from, to, actor);
+            false
+        }
+        Ok(_) => true,
+        Err(e) => {
+            warn!(
+                "⚠️ Failed to record {} for {} ({}): {}",
+                to.name(),
+                resource_id,
+                actor,
+                e
+            );
+            true
+        }
+    }
+}
+
+/// Close every notified, suppressed or filtered out tender whose deadline has passed;
+/// returns how many were closed
+pub async fn close_expired(pool: &PgPool, actor: &str) -> Result<u64> {
+    ensure_tables(pool).await?;
+    let closed = sqlx::query(
+        r#"
+        WITH closed AS (
+            UPDATE tender_lifecycle l
+            SET state = 'closed', updated_by = $1, updated_at = NOW()
+            FROM tender_lifecycle previous, tender_records t
+            WHERE previous.resource_id = l.resource_id
+              AND t.resource_id = l.resource_id
+              AND l.state IN ('notified', 'suppressed', 'filtered_out')
+              AND t.deadline < NOW()
+            RETURNING l.resource_id, previous.state AS from_state
+        )
+        INSERT INTO tender_lifecycle_transitions (resource_id, from_state, to_state, actor, outcome)
+        SELECT resource_id, from_state, 'closed', $1, 'accepted' FROM closed
+        "#,
+    )
+    .bind(actor)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    Ok(closed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_names_round_trip() {
+        for state in State::ALL {
+            assert_eq!(State::parse(state.name()), Some(state));
+        }
+        assert_eq!(State::parse("archived"), None);
+    }
+
+    #[test]
+    fn test_happy_paths() {
+        let paths = [
+            vec![
+                State::Scraped,
+                State::Loaded,
+                State::PdfProcessed,
+                State::Predicted,
+                State::Summarized,
+                State::Notified,
+                State::Closed,
+            ],
+            vec![
+                State::Scraped,
+                State::Loaded,
+                State::TitleOnly,
+                State::Summarized,
+                State::Suppressed,
+                State::Notified,
+                State::Closed,
+            ],
+        ];
+        for path in paths {
+            for step in path.windows(2) {
+                assert_eq!(check(Some(step[0]), step[1]), Check::Allowed, "{:?}", step);
+            }
+        }
+    }
+
+    #[test]
+    fn test_out_of_order() {
+        assert_eq!(
+            check(Some(State::Loaded), State::Predicted),
+            Check::OutOfOrder
+        );
+        assert_eq!(
+            check(Some(State::TitleOnly), State::Predicted),
+            Check::OutOfOrder
+        );
+        assert_eq!(
+            check(Some(State::Notified), State::Summarized),
+            Check::OutOfOrder
+        );
+        assert_eq!(
+            check(Some(State::Notified), State::Suppressed),
+            Check::OutOfOrder
+        );
+        assert_eq!(check(Some(State::Closed), State::Loaded), Check::OutOfOrder);
+        assert_eq!(
+            check(Some(State::FilteredOut), State::PdfProcessed),
+            Check::OutOfOrder
+        );
+    }
+
+    #[test]
+    fn test_repeats_and_untracked() {
+        assert_eq!(
+            check(Some(State::Predicted), State::Predicted),
+            Check::Repeat
+        );
+        assert_eq!(check(Some(State::Closed), State::Closed), Check::Repeat);
+        assert_eq!(check(None, State::Summarized), Check::Allowed);
+        // Only a tender that has been through the whole pipeline, or was kept out of it, closes
+        for state in State::ALL {
+            assert_eq!(
+                state.can_move_to(State::Closed),
+                matches!(
+                    state,
+                    State::Notified | State::Suppressed | State::FilteredOut
+                )
+            );
+        }
+    }
+}
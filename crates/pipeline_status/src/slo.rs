@@ -0,0 +1,182 @@
+//! Scrape-to-notification latency against a p95 target.
+//!
+//! A tender's latency runs from when postgres_dataload first stored it
+//! (`tender_records.created_at`) to its `notification` stage completing in
+//! `pipeline_status`. Only tenders stored within `LOOKBACK_DAYS` of that completion count:
+//! a refreshed summary re-notifying an old tender isn't a measure of the pipeline's speed.
+//! Canary tenders (negative resource_ids) are left out.
+//!
+//! pipeline_watchdog checks the trailing 24 hours against `Target` on every run, emitting
+//! the percentiles as metrics and alerting on a breach; weekly_report lists them per day.
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+
+pub const DEFAULT_TARGET_P95_HOURS: f64 = 2.0;
+/// Oldest tender, relative to its notification, that still counts
+pub const LOOKBACK_DAYS: i64 = 7;
+
+const METRIC_NAMESPACE: &str = "TenderPipeline";
+
+/// Latency percentiles of the tenders notified in a period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Latency {
+    pub tenders: i64,
+    pub p50_seconds: f64,
+    pub p95_seconds: f64,
+}
+
+/// Latency of the tenders notified on one UTC day
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailyLatency {
+    pub day: NaiveDate,
+    #[serde(flatten)]
+    pub latency: Latency,
+}
+
+/// The p95 latency the pipeline is held to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Target {
+    pub p95_hours: f64,
+}
+
+impl Target {
+    pub fn from_env() -> Self {
+        let p95_hours = std::env::var("LATENCY_SLO_P95_HOURS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .filter(|hours: &f64| *hours > 0.0)
+            .unwrap_or(DEFAULT_TARGET_P95_HOURS);
+        Self { p95_hours }
+    }
+
+    /// Whether the period's p95 is over target; a period with nothing notified never is
+    pub fn breached(&self, latency: &Latency) -> bool {
+        latency.tenders > 0 && latency.p95_seconds > self.p95_hours * 3600.0
+    }
+}
+
+const LATENCY_COLUMNS: &str = r#"
+    COUNT(*) AS tenders,
+    percentile_cont(0.5) WITHIN GROUP (ORDER BY EXTRACT(EPOCH FROM s.completed_at - t.created_at)::FLOAT8) AS p50_seconds,
+    percentile_cont(0.95) WITHIN GROUP (ORDER BY EXTRACT(EPOCH FROM s.completed_at - t.created_at)::FLOAT8) AS p95_seconds
+    FROM pipeline_status s
+    JOIN tender_records t ON t.resource_id = s.resource_id
+    WHERE s.stage = 'notification' AND s.status = 'completed' AND s.resource_id >= 0
+      AND s.completed_at >= $1 AND s.completed_at < $2
+      AND t.created_at >= s.completed_at - ($3::BIGINT * INTERVAL '1 day')
+"#;
+
+fn latency(row: &sqlx::postgres::PgRow) -> Latency {
+    Latency {
+        tenders: row.get("tenders"),
+        p50_seconds: row.get::<Option<f64>, _>("p50_seconds").unwrap_or_default(),
+        p95_seconds: row.get::<Option<f64>, _>("p95_seconds").unwrap_or_default(),
+    }
+}
+
+/// Latency of the tenders whose notification completed in `[from, to)`
+pub async fn window(pool: &PgPool, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Latency> {
+    let row = sqlx::query(&format!("SELECT {}", LATENCY_COLUMNS))
+        .bind(from)
+        .bind(to)
+        .bind(LOOKBACK_DAYS)
+        .fetch_one(pool)
+        .await?;
+    Ok(latency(&row))
+}
+
+/// Latency per UTC day with notifications in `[from, to)`, oldest first
+pub async fn daily(
+    pool: &PgPool,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<DailyLatency>> {
+    let rows = sqlx::query(&format!(
+        "SELECT (s.completed_at AT TIME ZONE 'UTC')::DATE AS day, {} GROUP BY day ORDER BY day",
+        LATENCY_COLUMNS
+    ))
+    .bind(from)
+    .bind(to)
+    .bind(LOOKBACK_DAYS)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| DailyLatency {
+            day: row.get("day"),
+            latency: latency(row),
+        })
+        .collect())
+}
+
+/// "1h 05m", or "42m" under an hour
+pub fn format_duration(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).round() as i64;
+    if minutes < 60 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+/// CloudWatch embedded metric format line for a latency check: `LatencyP50`/`LatencyP95`
+/// in seconds, `LatencySloBreached` 1 when the p95 is over target
+pub fn metric_line(environment: &str, latency: &Latency, target: &Target) -> Value {
+    json!({
+        "_aws": {
+            "Timestamp": Utc::now().timestamp_millis(),
+            "CloudWatchMetrics": [{
+                "Namespace": METRIC_NAMESPACE,
+                "Dimensions": [["Environment"]],
+                "Metrics": [
+                    { "Name": "LatencyP50", "Unit": "Seconds" },
+                    { "Name": "LatencyP95", "Unit": "Seconds" },
+                    { "Name": "LatencySloBreached", "Unit": "Count" }
+                ]
+            }]
+        },
+        "Environment": environment,
+        "Tenders": latency.tenders,
+        "LatencyP50": latency.p50_seconds,
+        "LatencyP95": latency.p95_seconds,
+        "LatencySloBreached": target.breached(latency) as u8,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(tenders: i64, p95_hours: f64) -> Latency {
+        Latency {
+            tenders,
+            p50_seconds: 1800.0,
+            p95_seconds: p95_hours * 3600.0,
+        }
+    }
+
+    #[test]
+    fn test_breach_needs_notifications_over_target() {
+        let target = Target { p95_hours: 2.0 };
+        assert!(target.breached(&sample(12, 2.5)));
+        assert!(!target.breached(&sample(12, 2.0)));
+        assert!(!target.breached(&sample(0, 0.0)));
+
+        let line = metric_line("prod", &sample(12, 2.5), &target);
+        assert_eq!(line["LatencyP95"], 9000.0);
+        assert_eq!(line["LatencySloBreached"], 1);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0.0), "0m");
+        assert_eq!(format_duration(2519.0), "42m");
+        assert_eq!(format_duration(3900.0), "1h 05m");
+        assert_eq!(format_duration(9000.0), "2h 30m");
+    }
+}
@@ -0,0 +1,238 @@
+use anyhow::Result;
+use aws_config::{BehaviorVersion, SdkConfig};
+use aws_sdk_sns::Client as SnsClient;
+use aws_sdk_sqs::Client as SqsClient;
+use environment::Environment;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use pipeline_status::lifecycle;
+use pipeline_status::slo::{self, Latency};
+use pipeline_status::StalledStage;
+use resource_discovery::ResourceDiscovery;
+use serde_json::Value;
+use sqlx::PgPool;
+use tracing::{error, info, warn, Instrument};
+
+mod types;
+
+use types::{plan, stage_queue, Config, Report, ReportEntry};
+
+/// Triggered on a schedule (EventBridge); the event body is not used
+async fn function_handler(_event: LambdaEvent<Value>) -> Result<Value, Error> {
+    info!("=== PIPELINE WATCHDOG STARTED ===");
+
+    let config = Config::from_env().map_err(|e| {
+        error!("Failed to load configuration: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+
+    let pool = db::connect(&config.database_url, 2)
+        .await
+        .map_err(|e| Error::from(format!("Failed to connect to database: {}", e).as_str()))?;
+    pipeline_status::ensure_table(&pool).await.map_err(|e| {
+        Error::from(format!("Failed to create pipeline_status table: {}", e).as_str())
+    })?;
+
+    let stalled = pipeline_status::find_stalled(&pool, chrono::Duration::hours(config.stall_hours))
+        .await
+        .map_err(|e| Error::from(format!("Failed to find stalled tenders: {}", e).as_str()))?;
+    info!(
+        "🔍 {} stages started over {}h ago without completing",
+        stalled.len(),
+        config.stall_hours
+    );
+
+    let aws_config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+    let plan = plan(&stalled, config.max_requeues);
+    let mut report = Report {
+        stalled: stalled.len(),
+        ..Default::default()
+    };
+
+    let sqs_client = SqsClient::new(&aws_config);
+    let discovery = ResourceDiscovery::new(&aws_config);
+    for stage in plan.requeue {
+        // One bad requeue (e.g. an unresolvable queue) must not stop the rest
+        match requeue(&pool, &sqs_client, &discovery, stage).await {
+            Ok(()) => {
+                info!(
+                    "🔁 Requeued {} at {} (requeue {} of {})",
+                    stage.resource_id,
+                    stage.stage.name(),
+                    stage.requeues + 1,
+                    config.max_requeues
+                );
+                report.requeued.push(ReportEntry::new(stage, None));
+            }
+            Err(e) => {
+                warn!(
+                    "⚠️ Failed to requeue {} at {}: {}",
+                    stage.resource_id,
+                    stage.stage.name(),
+                    e
+                );
+                report.requeue_failed.push(ReportEntry::new(stage, None));
+            }
+        }
+    }
+
+    for (stage, reason) in plan.stragglers {
+        error!(
+            "🚨 Chronic straggler: {} stuck at {} since {} ({} attempts, {} requeues, {:?}): {}",
+            stage.resource_id,
+            stage.stage.name(),
+            stage.started_at.format("%Y-%m-%d %H:%M UTC"),
+            stage.attempts,
+            stage.requeues,
+            reason,
+            stage.last_error.as_deref().unwrap_or("no error recorded")
+        );
+        report
+            .stragglers
+            .push(ReportEntry::new(stage, Some(reason)));
+    }
+
+    if !report.stragglers.is_empty() {
+        alert(&aws_config, &config, &report).await;
+    }
+
+    // A failed latency check shouldn't stop the watchdog from closing expired tenders
+    let now = chrono::Utc::now();
+    match slo::window(&pool, now - chrono::Duration::hours(24), now).await {
+        Ok(latency) => {
+            check_latency(&aws_config, &config, &latency).await;
+            report.latency = Some(latency);
+        }
+        Err(e) => warn!("⚠️ Failed to measure notification latency: {}", e),
+    }
+
+    // Tenders past their deadline leave the lifecycle; a failure here shouldn't fail the run
+    match lifecycle::close_expired(&pool, "pipeline_watchdog").await {
+        Ok(closed) => report.closed = closed,
+        Err(e) => warn!("⚠️ Failed to close expired tenders: {}", e),
+    }
+
+    info!(
+        "=== PIPELINE WATCHDOG COMPLETED: {} requeued, {} stragglers, {} closed ===",
+        report.requeued.len(),
+        report.stragglers.len(),
+        report.closed
+    );
+    Ok(serde_json::to_value(&report)?)
+}
+
+/// Resend the stage's stored message to its queue
+async fn requeue(
+    pool: &PgPool,
+    sqs_client: &SqsClient,
+    discovery: &ResourceDiscovery,
+    stage: &StalledStage,
+) -> Result<()> {
+    let queue_url = discovery.resolve(stage_queue(stage.stage)).await?;
+    let message = stage.message.as_deref().unwrap_or_default();
+
+    sqs_client
+        .send_message()
+        .queue_url(&queue_url)
+        .message_body(message)
+        .send()
+        .await?;
+
+    pipeline_status::record_requeued(pool, stage.resource_id, stage.stage).await
+}
+
+/// Publish the straggler report to the alert topic when configured
+async fn alert(aws_config: &SdkConfig, config: &Config, report: &Report) {
+    let Some(topic_arn) = &config.alert_topic_arn else {
+        return;
+    };
+
+    let lines: Vec<String> = report
+        .stragglers
+        .iter()
+        .map(|s| {
+            format!(
+                "{} stuck at {} since {} ({} requeues): {}",
+                s.resource_id,
+                s.stage,
+                s.started_at.format("%Y-%m-%d %H:%M UTC"),
+                s.requeues,
+                s.last_error.as_deref().unwrap_or("no error recorded")
+            )
+        })
+        .collect();
+
+    let environment = Environment::from_env();
+    if let Err(e) = SnsClient::new(aws_config)
+        .publish()
+        .topic_arn(topic_arn)
+        .subject(format!(
+            "[{}] {} tenders stuck in the pipeline",
+            environment.name().to_uppercase(),
+            report.stragglers.len()
+        ))
+        .message(lines.join("\n"))
+        .send()
+        .await
+    {
+        error!("Failed to publish straggler report: {}", e);
+    }
+}
+
+/// Emit the latency metrics and alert when the p95 is over target
+async fn check_latency(aws_config: &SdkConfig, config: &Config, latency: &Latency) {
+    let environment = Environment::from_env();
+    let target = &config.latency_target;
+    println!("{}", slo::metric_line(environment.name(), latency, target));
+    info!(
+        "⏱️ Scrape-to-notification latency over 24h: p50 {}, p95 {} ({} tenders, target p95 {}h)",
+        slo::format_duration(latency.p50_seconds),
+        slo::format_duration(latency.p95_seconds),
+        latency.tenders,
+        target.p95_hours
+    );
+    if !target.breached(latency) {
+        return;
+    }
+
+    let message = format!(
+        "p95 scrape-to-notification latency over the last 24 hours was {} against a target of {}h \
+         (p50 {}, {} tenders notified)",
+        slo::format_duration(latency.p95_seconds),
+        target.p95_hours,
+        slo::format_duration(latency.p50_seconds),
+        latency.tenders
+    );
+    error!("🚨 Latency SLO breached: {}", message);
+
+    let Some(topic_arn) = &config.alert_topic_arn else {
+        return;
+    };
+    if let Err(e) = SnsClient::new(aws_config)
+        .publish()
+        .topic_arn(topic_arn)
+        .subject(format!(
+            "[{}] Notification latency over target",
+            environment.name().to_uppercase()
+        ))
+        .message(message)
+        .send()
+        .await
+    {
+        error!("Failed to publish latency alert: {}", e);
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    let environment = Environment::from_env();
+    run(service_fn(move |event| {
+        function_handler(event).instrument(environment.span())
+    }))
+    .await
+}
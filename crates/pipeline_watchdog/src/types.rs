@@ -0,0 +1,183 @@
+use chrono::{DateTime, Utc};
+use pipeline_status::slo::{Latency, Target};
+use pipeline_status::{Stage, StalledStage};
+use resource_discovery::Resource;
+use serde::Serialize;
+
+pub const DEFAULT_STALL_HOURS: i64 = 2;
+pub const DEFAULT_MAX_REQUEUES: i32 = 3;
+
+/// Queue feeding each stage; a stalled stage is requeued here with its original message
+pub fn stage_queue(stage: Stage) -> Resource {
+    match stage {
+        Stage::PdfProcessing => Resource::PdfProcessingQueue,
+        Stage::MlPrediction => Resource::MlPredictionQueue,
+        Stage::AiSummary => Resource::AiSummaryQueue,
+        Stage::Notification => Resource::NotificationQueue,
+    }
+}
+
+/// Why a stalled stage is reported instead of requeued
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StragglerReason {
+    /// Already requeued MAX_REQUEUES times
+    RequeueLimit,
+    /// Nothing stored to requeue from
+    NoMessage,
+}
+
+/// Stalled stages split into what to requeue and what needs a human
+#[derive(Debug, Default)]
+pub struct Plan<'a> {
+    pub requeue: Vec<&'a StalledStage>,
+    pub stragglers: Vec<(&'a StalledStage, StragglerReason)>,
+}
+
+/// Decide what to do with each stalled stage. Canary tenders are left alone -
+/// pipeline_canary reports them, and requeueing would hide the failure it is measuring.
+pub fn plan(stalled: &[StalledStage], max_requeues: i32) -> Plan<'_> {
+    let mut plan = Plan::default();
+    for stage in stalled
+        .iter()
+        .filter(|s| !environment::is_canary(s.resource_id))
+    {
+        if stage.requeues >= max_requeues {
+            plan.stragglers.push((stage, StragglerReason::RequeueLimit));
+        } else if stage.message.is_none() {
+            plan.stragglers.push((stage, StragglerReason::NoMessage));
+        } else {
+            plan.requeue.push(stage);
+        }
+    }
+    plan
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportEntry {
+    pub resource_id: i64,
+    pub stage: &'static str,
+    pub status: String,
+    pub attempts: i32,
+    pub requeues: i32,
+    pub started_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<StragglerReason>,
+}
+
+impl ReportEntry {
+    pub fn new(stage: &StalledStage, reason: Option<StragglerReason>) -> Self {
+        Self {
+            resource_id: stage.resource_id,
+            stage: stage.stage.name(),
+            status: stage.status.clone(),
+            attempts: stage.attempts,
+            requeues: stage.requeues,
+            started_at: stage.started_at,
+            last_error: stage.last_error.clone(),
+            reason,
+        }
+    }
+}
+
+/// Returned by the lambda so a manual invocation shows what happened
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub stalled: usize,
+    pub requeued: Vec<ReportEntry>,
+    pub requeue_failed: Vec<ReportEntry>,
+    pub stragglers: Vec<ReportEntry>,
+    /// Notified or suppressed tenders moved to closed in `tender_lifecycle`
+    pub closed: u64,
+    /// Scrape-to-notification latency over the last 24 hours
+    pub latency: Option<Latency>,
+}
+
+/// Configuration from environment
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database_url: String,
+    /// A stage that started this long ago without completing is stalled
+    pub stall_hours: i64,
+    pub max_requeues: i32,
+    /// SNS topic for the chronic straggler report and latency breaches; both are always
+    /// logged at error level
+    pub alert_topic_arn: Option<String>,
+    /// Scrape-to-notification p95 latency target
+    pub latency_target: Target,
+}
+
+impl Config {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let database_url = std::env::var("DATABASE_URL")
+            .map_err(|_| anyhow::anyhow!("DATABASE_URL environment variable not set"))?;
+
+        let stall_hours = std::env::var("STALL_THRESHOLD_HOURS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_STALL_HOURS);
+
+        let max_requeues = std::env::var("MAX_REQUEUES")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_MAX_REQUEUES);
+
+        let alert_topic_arn = std::env::var("WATCHDOG_ALERT_TOPIC_ARN")
+            .ok()
+            .filter(|arn| !arn.trim().is_empty());
+
+        Ok(Self {
+            database_url,
+            stall_hours,
+            max_requeues,
+            alert_topic_arn,
+            latency_target: Target::from_env(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stalled(resource_id: i64, requeues: i32, message: Option<&str>) -> StalledStage {
+        StalledStage {
+            resource_id,
+            stage: Stage::AiSummary,
+            status: "started".to_string(),
+            attempts: 1,
+            requeues,
+            message: message.map(str::to_string),
+            last_error: None,
+            started_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_plan_respects_requeue_limit() {
+        let stages = vec![
+            stalled(1, 0, Some("{}")),
+            stalled(2, 3, Some("{}")),
+            stalled(3, 1, None),
+            stalled(-1_700_000_000, 0, Some("{}")),
+        ];
+
+        let plan = plan(&stages, 3);
+
+        let requeued: Vec<i64> = plan.requeue.iter().map(|s| s.resource_id).collect();
+        assert_eq!(requeued, vec![1]);
+        let stragglers: Vec<(i64, StragglerReason)> = plan
+            .stragglers
+            .iter()
+            .map(|(s, reason)| (s.resource_id, *reason))
+            .collect();
+        assert_eq!(
+            stragglers,
+            vec![
+                (2, StragglerReason::RequeueLimit),
+                (3, StragglerReason::NoMessage)
+            ]
+        );
+    }
+}
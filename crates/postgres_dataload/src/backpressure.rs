@@ -0,0 +1,101 @@
+//! Pacing of sends to the downstream queues.
+//!
+//! Everything postgres_dataload queues ends up at ai_summary, so a big scrape dumped on the
+//! queues at once turns into a burst of Claude calls and rate-limit errors. Below the
+//! threshold records are sent straight away; above it they are spread out with
+//! `DelaySeconds`, and whatever doesn't fit in the 15-minute SQS delay window is deferred
+//! to a follow-up invocation of postgres_dataload.
+
+/// SQS caps `DelaySeconds` at 15 minutes
+pub const MAX_DELAY_SECONDS: i32 = 900;
+const DEFAULT_THRESHOLD: usize = 100;
+const DEFAULT_SENDS_PER_MINUTE: usize = 20;
+/// After this many deferrals a record is sent (at the maximum delay) whatever the backlog,
+/// so a stuck downstream can't hold tenders back forever
+pub const MAX_DEFERRALS: u32 = 8;
+
+/// What to do with one record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dispatch {
+    Now,
+    Delay(i32),
+    /// Hand back to postgres_dataload's own queue for a later invocation
+    Defer,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Backpressure {
+    /// Downstream backlog (messages) below which records are sent immediately
+    pub threshold: usize,
+    /// Pace of delayed sends once the backlog reaches the threshold
+    pub sends_per_minute: usize,
+}
+
+impl Backpressure {
+    pub fn from_env() -> Self {
+        let env_or = |var: &str, default: usize| {
+            std::env::var(var)
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            threshold: env_or("BACKPRESSURE_THRESHOLD", DEFAULT_THRESHOLD),
+            sends_per_minute: env_or("BACKPRESSURE_SENDS_PER_MINUTE", DEFAULT_SENDS_PER_MINUTE)
+                .max(1),
+        }
+    }
+
+    /// Dispatch for each of `count` records bound for a queue with `backlog` messages waiting
+    pub fn plan(&self, count: usize, backlog: usize) -> Vec<Dispatch> {
+        let immediate = self.threshold.saturating_sub(backlog);
+        (0..count)
+            .map(|i| {
+                if i < immediate {
+                    return Dispatch::Now;
+                }
+                let minute = (i - immediate) / self.sends_per_minute + 1;
+                match i32::try_from(minute * 60) {
+                    Ok(delay) if delay <= MAX_DELAY_SECONDS => Dispatch::Delay(delay),
+                    _ => Dispatch::Defer,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backpressure() -> Backpressure {
+        Backpressure {
+            threshold: 10,
+            sends_per_minute: 5,
+        }
+    }
+
+    #[test]
+    fn test_quiet_queue_sends_everything_now() {
+        assert!(
+            backpressure()
+                .plan(10, 0)
+                .iter()
+                .all(|d| *d == Dispatch::Now)
+        );
+    }
+
+    #[test]
+    fn test_backlog_spreads_then_defers() {
+        let plan = backpressure().plan(100, 8);
+
+        assert_eq!(plan[..2], [Dispatch::Now, Dispatch::Now]);
+        assert_eq!(plan[2..7], [Dispatch::Delay(60); 5]);
+        assert_eq!(plan[7], Dispatch::Delay(120));
+        // 15 one-minute slots of 5 records each after the 2 immediate sends
+        assert_eq!(plan[76], Dispatch::Delay(MAX_DELAY_SECONDS));
+        assert_eq!(plan[77], Dispatch::Defer);
+        assert_eq!(plan.iter().filter(|d| **d == Dispatch::Defer).count(), 23);
+    }
+}
@@ -1,5 +1,12 @@
+//! Interactive bid labelling: `label_bids` walks unlabelled tenders in id order.
+//!
+//! `label_bids --retro` walks closed tenders instead (award date set or deadline passed)
+//! and only stops at those whose PDF declares nothing but non-IT CPV codes, proposing
+//! bid = 0. Pressing Enter accepts the proposal.
+
+use pdf_processing::cpv;
 use sqlx::postgres::PgPoolOptions;
-use sqlx::Row;
+use sqlx::{PgPool, Row};
 use std::env;
 use std::io::{self, Write};
 
@@ -18,6 +25,93 @@ fn strip_html(input: &str) -> String {
     out.trim().to_string()
 }
 
+fn read_input() -> String {
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+    input.trim().to_lowercase()
+}
+
+async fn set_bid(pool: &PgPool, id: i32, bid: i32) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE tender_records SET bid = $1 WHERE id = $2")
+        .bind(bid)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Propose bid = 0 for closed tenders that declare only non-IT CPV codes
+async fn retro_label(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let mut last_id = 0;
+    let mut accepted = 0;
+    let mut checked = 0;
+
+    loop {
+        let row = sqlx::query(
+            r#"
+            SELECT t.id, t.title, t.ca, p.pdf_text, COALESCE(p.codes_count, 0) AS codes_count
+            FROM tender_records t
+            JOIN pdf_content p ON p.resource_id = t.resource_id
+            WHERE t.bid IS NULL
+              AND t.id > $1
+              AND (t.awarddate IS NOT NULL OR t.deadline < NOW())
+            ORDER BY t.id
+            LIMIT 1
+            "#
+        )
+        .bind(last_id)
+        .fetch_optional(pool)
+        .await?;
+
+        let Some(r) = row else {
+            println!("\nNo more proposals ({} closed tenders checked, {} labelled bid = 0)", checked, accepted);
+            break;
+        };
+        let id: i32 = r.get("id");
+        last_id = id;
+        checked += 1;
+
+        let pdf_text: String = r.get("pdf_text");
+        let codes_count: i32 = r.get("codes_count");
+        let declared = cpv::declared_codes(&pdf_text);
+        let Some(proposal) = cpv::propose_no_bid(&declared, codes_count.max(0) as usize) else {
+            continue;
+        };
+
+        let title: String = r.get("title");
+        let ca: String = r.get("ca");
+        println!("\nTitle: {}", strip_html(&title));
+        println!("CA: {}", strip_html(&ca));
+        println!("Proposed: no bid - {}", proposal.reason);
+
+        loop {
+            print!("[Enter] accept no bid, y = bid, s = skip, quit: ");
+            io::stdout().flush().unwrap();
+
+            match read_input().as_str() {
+                "" | "n" | "no" => {
+                    set_bid(pool, id, 0).await?;
+                    accepted += 1;
+                    println!("Updated record {} with bid = 0 (no)", id);
+                }
+                "y" | "yes" => {
+                    set_bid(pool, id, 1).await?;
+                    println!("Updated record {} with bid = 1 (yes)", id);
+                }
+                "s" | "skip" => println!("Skipped record {}", id),
+                "quit" => return Ok(()),
+                _ => {
+                    println!("Please press Enter, or type 'y', 's' or 'quit'.");
+                    continue;
+                }
+            }
+            break;
+        }
+    }
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<(), sqlx::Error> {
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
@@ -59,6 +153,10 @@ async fn main() -> Result<(), sqlx::Error> {
     .execute(&pool)
     .await?;
 
+    if env::args().any(|arg| arg == "--retro") {
+        return retro_label(&pool).await;
+    }
+
     loop {
         // Fetch the next unlabeled record in ascending ID order
         let row = sqlx::query(
@@ -1,37 +1,32 @@
-use aws_config;
 use aws_lambda_events::event::sqs::SqsEvent;
 use aws_sdk_sqs::Client as SqsClient;
-use bigdecimal::BigDecimal;
-use chrono::{NaiveDate, NaiveDateTime};
+use aws_sdk_sqs::types::QueueAttributeName;
+use environment::Environment;
 use lambda_runtime::{Error, LambdaEvent, service_fn};
+use pipeline_contract::Routing;
+use pipeline_status::Stage;
+use pipeline_status::lifecycle::{self, State};
+use queue::batch::{self, Entry};
+use resource_discovery::{Resource, ResourceDiscovery};
 use serde::{Deserialize, Serialize};
-use serde_json;
-use sqlx::{Pool, Postgres, postgres::PgPoolOptions};
+use sqlx::{Pool, Postgres};
 use std::env;
-use tracing::{error, info};
+use tender_core::TenderRecord;
+use tracing::{Instrument, error, info, warn};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct TenderRecord {
-    title: String,
-    resource_id: i64,
-    contracting_authority: String,
-    info: String,
-    published: Option<NaiveDateTime>,
-    deadline: Option<NaiveDateTime>,
-    procedure: String,
-    status: String,
-    pdf_url: String,
-    awarddate: Option<NaiveDate>,
-    value: Option<BigDecimal>,
-    cycle: String,
-    bid: Option<i32>,
-}
+mod backpressure;
+mod prefilter;
+
+use backpressure::{Backpressure, Dispatch, MAX_DEFERRALS, MAX_DELAY_SECONDS};
+use prefilter::Prefilter;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct Response {
     records_processed: usize,
     records_saved: usize,
+    records_filtered_out: usize,
     records_queued: usize,
+    records_deferred: usize,
     success: bool,
     message: String,
 }
@@ -44,9 +39,7 @@ async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<Response, Erro
     let db_url = env::var("DATABASE_URL")
         .map_err(|_| Error::from("DATABASE_URL environment variable not set"))?;
 
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&db_url)
+    let pool = db::connect(&db_url, 5)
         .await
         .map_err(|e| Error::from(format!("Failed to connect to database: {}", e).as_str()))?;
 
@@ -57,12 +50,20 @@ async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<Response, Erro
         .await
         .map_err(|e| Error::from(format!("Failed to ensure tables exist: {}", e).as_str()))?;
 
-    // Parse tender records from SQS messages
+    // Parse tender records from SQS messages; deferred ones were saved by an earlier invocation
     let mut tender_records = Vec::new();
+    let mut deferred_records = Vec::new();
 
     for record in event.payload.records {
         if let Some(body) = &record.body {
             match serde_json::from_str::<TenderRecord>(body) {
+                Ok(tender) if tender.deferrals > 0 => {
+                    info!(
+                        "Parsed deferred tender: {} (deferral {})",
+                        tender.resource_id, tender.deferrals
+                    );
+                    deferred_records.push(tender);
+                }
                 Ok(tender) => {
                     info!("Parsed tender: {}", tender.resource_id);
                     tender_records.push(tender);
@@ -98,137 +99,148 @@ async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<Response, Erro
             .await
             .map_err(|e| Error::from(format!("Failed to save records: {}", e).as_str()))?;
         info!("Successfully saved {} records", new_records.len());
+        // The scraper has no database, so both of its steps are recorded here, before the
+        // records are handed to pdf_processing
+        for record in &new_records {
+            lifecycle::advance(
+                &pool,
+                record.resource_id,
+                State::Scraped,
+                "etenders_scraper",
+            )
+            .await;
+            lifecycle::advance(
+                &pool,
+                record.resource_id,
+                State::Loaded,
+                "postgres_dataload",
+            )
+            .await;
+        }
         new_records.len()
     } else {
         info!("No new records to save");
         0
     };
 
-    // Send records to appropriate queues
-    let queued_count = if !new_records.is_empty() {
-        queue_records_for_processing(&new_records)
+    // Tenders we'd never bid on stay saved but aren't queued; canaries always go through
+    let prefilter = Prefilter::from_env();
+    let now = chrono::Utc::now().naive_utc();
+    let mut to_process = Vec::new();
+    let mut filtered_out_count = 0;
+    for record in &new_records {
+        let reason = if environment::is_canary(record.resource_id) {
+            None
+        } else {
+            prefilter.reason(&record.status, record.value.as_ref(), record.deadline, now)
+        };
+        let Some(reason) = reason else {
+            to_process.push(record.clone());
+            continue;
+        };
+
+        info!("Filtered out record {}: {}", record.resource_id, reason);
+        mark_filtered_out(&pool, record.resource_id, &reason)
+            .await
+            .map_err(|e| Error::from(format!("Failed to mark filtered out: {}", e).as_str()))?;
+        lifecycle::advance(
+            &pool,
+            record.resource_id,
+            State::FilteredOut,
+            "postgres_dataload",
+        )
+        .await;
+        filtered_out_count += 1;
+    }
+
+    // Send records to appropriate queues, deferred ones first as they have waited longest
+    let to_forward: Vec<TenderRecord> = deferred_records
+        .iter()
+        .chain(to_process.iter())
+        .cloned()
+        .collect();
+    let (queued_count, deferred_count) = if !to_forward.is_empty() {
+        queue_records_for_processing(&to_forward)
             .await
             .map_err(|e| Error::from(format!("Failed to queue records: {}", e).as_str()))?
     } else {
-        0
+        (0, 0)
     };
 
+    // Notify third-party webhooks about new/updated tenders (best-effort)
+    if !new_records.is_empty()
+        && let Err(e) = publish_tender_updated_events(&new_records).await
+    {
+        error!("Failed to publish webhook events: {}", e);
+    }
+
     info!("=== POSTGRES DATALOAD COMPLETED ===");
 
     Ok(Response {
-        records_processed: tender_records.len(),
+        records_processed: tender_records.len() + deferred_records.len(),
         records_saved: saved_count,
+        records_filtered_out: filtered_out_count,
         records_queued: queued_count,
+        records_deferred: deferred_count,
         success: true,
         message: format!(
-            "Processed {} records, saved {} new, queued {} for processing",
-            tender_records.len(),
+            "Processed {} records, saved {} new, filtered out {}, queued {} for processing, deferred {}",
+            tender_records.len() + deferred_records.len(),
             saved_count,
-            queued_count
+            filtered_out_count,
+            queued_count,
+            deferred_count
         ),
     })
 }
 
 async fn ensure_tables_exist(pool: &Pool<Postgres>) -> Result<(), Error> {
-    // Create tender_records table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS tender_records (
-            id SERIAL PRIMARY KEY,
-            title TEXT NOT NULL,
-            resource_id BIGINT NOT NULL UNIQUE,
-            ca TEXT NOT NULL,
-            info TEXT NOT NULL,
-            published TIMESTAMP WITHOUT TIME ZONE,
-            deadline TIMESTAMP WITHOUT TIME ZONE,
-            procedure TEXT NOT NULL,
-            status TEXT NOT NULL,
-            pdf_url TEXT NOT NULL,
-            awarddate DATE,
-            value DECIMAL(15,2),
-            cycle TEXT NOT NULL,
-            bid INTEGER DEFAULT NULL,
-            notification_sent BOOLEAN DEFAULT FALSE,
-            notification_sent_at TIMESTAMP WITH TIME ZONE DEFAULT NULL,
-            created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
-            updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
-            ml_processed BOOLEAN DEFAULT FALSE,
-            ml_bid BOOLEAN,
-            ml_confidence DECIMAL(5,4),
-            ml_reasoning TEXT,
-            ml_status VARCHAR(20) DEFAULT 'pending'
+    db::tables::ensure_tender_records(pool).await?;
+
+    db::ensure_schema(pool, "tender_documents", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS tender_documents (
+                resource_id BIGINT NOT NULL,
+                position INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                file_type TEXT NOT NULL,
+                size_bytes BIGINT,
+                url TEXT NOT NULL,
+                created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (resource_id, position)
+            )
+            "#,
         )
-        "#,
-    )
-    .execute(pool)
-    .await?;
+        .execute(pool)
+        .await?;
 
-    // Add missing columns if they don't exist (for existing tables that were created before these columns were added)
-    sqlx::query(
-        r#"
-        DO $$
-        BEGIN
-            IF NOT EXISTS (
-                SELECT 1 FROM information_schema.columns
-                WHERE table_name='tender_records' AND column_name='notification_sent'
-            ) THEN
-                ALTER TABLE tender_records ADD COLUMN notification_sent BOOLEAN DEFAULT FALSE;
-            END IF;
-
-            IF NOT EXISTS (
-                SELECT 1 FROM information_schema.columns
-                WHERE table_name='tender_records' AND column_name='notification_sent_at'
-            ) THEN
-                ALTER TABLE tender_records ADD COLUMN notification_sent_at TIMESTAMP WITH TIME ZONE DEFAULT NULL;
-            END IF;
-
-            IF NOT EXISTS (
-                SELECT 1 FROM information_schema.columns
-                WHERE table_name='tender_records' AND column_name='updated_at'
-            ) THEN
-                ALTER TABLE tender_records ADD COLUMN updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP;
-            END IF;
-
-            IF NOT EXISTS (
-                SELECT 1 FROM information_schema.columns
-                WHERE table_name='tender_records' AND column_name='ml_processed'
-            ) THEN
-                ALTER TABLE tender_records ADD COLUMN ml_processed BOOLEAN DEFAULT FALSE;
-            END IF;
-
-            IF NOT EXISTS (
-                SELECT 1 FROM information_schema.columns
-                WHERE table_name='tender_records' AND column_name='ml_bid'
-            ) THEN
-                ALTER TABLE tender_records ADD COLUMN ml_bid BOOLEAN;
-            END IF;
-
-            IF NOT EXISTS (
-                SELECT 1 FROM information_schema.columns
-                WHERE table_name='tender_records' AND column_name='ml_confidence'
-            ) THEN
-                ALTER TABLE tender_records ADD COLUMN ml_confidence DECIMAL(5,4);
-            END IF;
-
-            IF NOT EXISTS (
-                SELECT 1 FROM information_schema.columns
-                WHERE table_name='tender_records' AND column_name='ml_reasoning'
-            ) THEN
-                ALTER TABLE tender_records ADD COLUMN ml_reasoning TEXT;
-            END IF;
-
-            IF NOT EXISTS (
-                SELECT 1 FROM information_schema.columns
-                WHERE table_name='tender_records' AND column_name='ml_status'
-            ) THEN
-                ALTER TABLE tender_records ADD COLUMN ml_status VARCHAR(20) DEFAULT 'pending';
-            END IF;
-        END $$;
-        "#,
-    )
-    .execute(pool)
+        Environment::ensure_environment_column(pool, "tender_documents").await?;
+
+        anyhow::Ok(())
+    })
     .await?;
 
+    db::ensure_schema(pool, "tender_lots", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS tender_lots (
+                resource_id BIGINT NOT NULL,
+                lot_number INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (resource_id, lot_number)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "tender_lots").await?;
+
+        anyhow::Ok(())
+    })
+    .await?;
     Ok(())
 }
 
@@ -256,15 +268,20 @@ async fn filter_new_records(
 
 async fn save_records(pool: &Pool<Postgres>, records: &[TenderRecord]) -> Result<(), Error> {
     for record in records {
+        let contact = record.buyer_contact.as_ref();
         sqlx::query(
             r#"
             INSERT INTO tender_records
-            (title, resource_id, ca, info, published, deadline, procedure, status, pdf_url, awarddate, value, cycle, bid)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            (title, resource_id, ca, info, published, deadline, procedure, status, pdf_url, awarddate, value, cycle, bid,
+             buyer_contact_name, buyer_contact_email, buyer_contact_phone, cpv_codes, nuts_codes)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
             ON CONFLICT (resource_id) DO UPDATE SET
                 title = EXCLUDED.title,
                 ca = EXCLUDED.ca,
-                info = EXCLUDED.info,
+                -- A rescrape that couldn't read the detail page keeps the description and codes
+                info = COALESCE(NULLIF(EXCLUDED.info, ''), tender_records.info),
+                cpv_codes = COALESCE(EXCLUDED.cpv_codes, tender_records.cpv_codes),
+                nuts_codes = COALESCE(EXCLUDED.nuts_codes, tender_records.nuts_codes),
                 published = EXCLUDED.published,
                 deadline = EXCLUDED.deadline,
                 procedure = EXCLUDED.procedure,
@@ -272,7 +289,11 @@ async fn save_records(pool: &Pool<Postgres>, records: &[TenderRecord]) -> Result
                 pdf_url = EXCLUDED.pdf_url,
                 awarddate = EXCLUDED.awarddate,
                 value = EXCLUDED.value,
-                cycle = EXCLUDED.cycle
+                cycle = EXCLUDED.cycle,
+                -- A rescrape that couldn't read the notice page keeps the contact already stored
+                buyer_contact_name = COALESCE(EXCLUDED.buyer_contact_name, tender_records.buyer_contact_name),
+                buyer_contact_email = COALESCE(EXCLUDED.buyer_contact_email, tender_records.buyer_contact_email),
+                buyer_contact_phone = COALESCE(EXCLUDED.buyer_contact_phone, tender_records.buyer_contact_phone)
                 -- Note: We don't update bid column or notification fields to preserve existing data
             "#,
         )
@@ -280,106 +301,353 @@ async fn save_records(pool: &Pool<Postgres>, records: &[TenderRecord]) -> Result
         .bind(record.resource_id)
         .bind(&record.contracting_authority)
         .bind(&record.info)
-        .bind(&record.published)
-        .bind(&record.deadline)
+        .bind(record.published)
+        .bind(record.deadline)
         .bind(&record.procedure)
         .bind(&record.status)
         .bind(&record.pdf_url)
-        .bind(&record.awarddate)
+        .bind(record.awarddate)
         .bind(&record.value)
         .bind(&record.cycle)
-        .bind(&record.bid)
+        .bind(record.bid)
+        .bind(contact.and_then(|c| c.name.as_deref()))
+        .bind(contact.and_then(|c| c.email.as_deref()))
+        .bind(contact.and_then(|c| c.phone.as_deref()))
+        .bind(non_empty(&record.cpv_codes))
+        .bind(non_empty(&record.nuts_codes))
         .execute(pool)
         .await?;
+
+        if !record.documents.is_empty() {
+            save_documents(pool, record).await?;
+        }
+        if !record.lots.is_empty() {
+            save_lots(pool, record).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Record why the prefilter kept a saved tender out of the pipeline
+async fn mark_filtered_out(
+    pool: &Pool<Postgres>,
+    resource_id: i64,
+    reason: &str,
+) -> Result<(), Error> {
+    sqlx::query(
+        "UPDATE tender_records SET filtered_out_reason = $2, updated_at = CURRENT_TIMESTAMP WHERE resource_id = $1",
+    )
+    .bind(resource_id)
+    .bind(reason)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Replace the tender's document inventory with the one just scraped
+async fn save_documents(pool: &Pool<Postgres>, record: &TenderRecord) -> Result<(), Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM tender_documents WHERE resource_id = $1")
+        .bind(record.resource_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for (position, document) in record.documents.iter().enumerate() {
+        sqlx::query(
+            r#"
+            INSERT INTO tender_documents (resource_id, position, name, file_type, size_bytes, url)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(record.resource_id)
+        .bind(position as i32)
+        .bind(&document.name)
+        .bind(&document.file_type)
+        .bind(document.size_bytes)
+        .bind(&document.url)
+        .execute(&mut *tx)
+        .await?;
     }
 
+    tx.commit().await?;
     Ok(())
 }
 
-async fn queue_records_for_processing(records: &[TenderRecord]) -> Result<usize, Error> {
+/// Replace the tender's lots with the ones just scraped
+async fn save_lots(pool: &Pool<Postgres>, record: &TenderRecord) -> Result<(), Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM tender_lots WHERE resource_id = $1")
+        .bind(record.resource_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for lot in &record.lots {
+        sqlx::query("INSERT INTO tender_lots (resource_id, lot_number, title) VALUES ($1, $2, $3)")
+            .bind(record.resource_id)
+            .bind(lot.number as i32)
+            .bind(&lot.title)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// NULL rather than an empty array, so a rescrape without codes keeps the stored ones
+fn non_empty(codes: &[String]) -> Option<&[String]> {
+    (!codes.is_empty()).then_some(codes)
+}
+
+/// Downstream backlog for a queue: its own messages (visible, in flight and delayed) plus the
+/// AI summary queue's, since everything queued here is headed for a Claude call
+async fn queue_backlog(sqs_client: &SqsClient, queue_urls: &[&str]) -> usize {
+    let mut backlog = 0;
+    for queue_url in queue_urls {
+        match sqs_client
+            .get_queue_attributes()
+            .queue_url(*queue_url)
+            .attribute_names(QueueAttributeName::ApproximateNumberOfMessages)
+            .attribute_names(QueueAttributeName::ApproximateNumberOfMessagesNotVisible)
+            .attribute_names(QueueAttributeName::ApproximateNumberOfMessagesDelayed)
+            .send()
+            .await
+        {
+            Ok(output) => {
+                backlog += output
+                    .attributes()
+                    .map(|attributes| {
+                        attributes
+                            .values()
+                            .filter_map(|v| v.parse::<usize>().ok())
+                            .sum::<usize>()
+                    })
+                    .unwrap_or(0);
+            }
+            // Without a depth reading, fall back to sending immediately as before
+            Err(e) => warn!("Failed to read depth of {}: {}", queue_url, e),
+        }
+    }
+    backlog
+}
+
+/// Send `records` to `queue_url`, paced by the downstream backlog. Returns (queued, deferred)
+async fn dispatch_records(
+    sqs_client: &SqsClient,
+    queue_url: &str,
+    ai_summary_queue_url: Option<&str>,
+    self_queue_url: &str,
+    records: &[&TenderRecord],
+    backpressure: &Backpressure,
+) -> Result<(usize, usize), Error> {
+    let mut depth_queues = vec![queue_url];
+    depth_queues.extend(ai_summary_queue_url);
+    let backlog = queue_backlog(sqs_client, &depth_queues).await;
+    let plan = backpressure.plan(records.len(), backlog);
+    info!(
+        "Downstream backlog for {} is {} messages (threshold {})",
+        queue_url, backlog, backpressure.threshold
+    );
+
+    let serialize = |record: &TenderRecord| {
+        serde_json::to_string(record)
+            .map_err(|e| Error::from(format!("Failed to serialize record: {}", e).as_str()))
+    };
+
+    // Records for `queue_url` with their delay, and deferred records with their deferral count
+    let mut forwarded = Vec::new();
+    let mut forwarded_entries = Vec::new();
+    let mut deferred = Vec::new();
+    let mut deferred_entries = Vec::new();
+    for (record, dispatch) in records.iter().zip(plan) {
+        let delay = match dispatch {
+            Dispatch::Now => 0,
+            Dispatch::Delay(delay) => delay,
+            Dispatch::Defer if record.deferrals >= MAX_DEFERRALS => MAX_DELAY_SECONDS,
+            Dispatch::Defer => {
+                let message = TenderRecord {
+                    deferrals: record.deferrals + 1,
+                    ..(*record).clone()
+                };
+                // Deferred records come back to this lambda, which doesn't read the attributes
+                deferred_entries
+                    .push(Entry::new(serialize(&message)?).delay_seconds(MAX_DELAY_SECONDS));
+                deferred.push((*record, message.deferrals));
+                continue;
+            }
+        };
+        let message = TenderRecord {
+            deferrals: 0,
+            ..(*record).clone()
+        };
+        forwarded_entries.push(
+            Entry::new(serialize(&message)?)
+                .routing(Routing::to_stage(Stage::PdfProcessing.name()))
+                .delay_seconds(delay),
+        );
+        forwarded.push((*record, delay));
+    }
+
+    let mut queued_count = 0;
+    let outcomes = batch::send_batch(sqs_client, queue_url, &forwarded_entries).await;
+    for ((record, delay), sent) in forwarded.iter().zip(outcomes) {
+        match sent {
+            Ok(_) => {
+                info!(
+                    "Queued record {} to {} (delay {}s)",
+                    record.resource_id, queue_url, delay
+                );
+                queued_count += 1;
+            }
+            Err(e) => error!("Failed to queue record {}: {}", record.resource_id, e),
+        }
+    }
+
+    let mut deferred_count = 0;
+    let outcomes = batch::send_batch(sqs_client, self_queue_url, &deferred_entries).await;
+    for ((record, deferrals), sent) in deferred.iter().zip(outcomes) {
+        match sent {
+            Ok(_) => {
+                info!(
+                    "Deferred record {} to a follow-up invocation (deferral {})",
+                    record.resource_id, deferrals
+                );
+                deferred_count += 1;
+            }
+            Err(e) => error!("Failed to queue record {}: {}", record.resource_id, e),
+        }
+    }
+
+    Ok((queued_count, deferred_count))
+}
+
+/// Forward records to pdf_processing (with a PDF) or ml_bid_predictor (without).
+/// Returns (queued, deferred)
+async fn queue_records_for_processing(records: &[TenderRecord]) -> Result<(usize, usize), Error> {
     // Initialize AWS SDK
     let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
         .load()
         .await;
     let sqs_client = SqsClient::new(&aws_config);
+    let discovery = ResourceDiscovery::new(&aws_config);
+    let backpressure = Backpressure::from_env();
+
+    let self_queue_url = discovery
+        .resolve(Resource::TenderProcessingQueue)
+        .await
+        .map_err(|e| Error::from(format!("Tender processing queue not found: {}", e).as_str()))?;
+    let ai_summary_queue_url = discovery.resolve_optional(Resource::AiSummaryQueue).await;
 
     // Split records into PDF and non-PDF
     let (pdf_records, non_pdf_records): (Vec<&TenderRecord>, Vec<&TenderRecord>) =
         records.iter().partition(|r| !r.pdf_url.is_empty());
 
     let mut queued_count = 0;
+    let mut deferred_count = 0;
 
     // Send records with PDFs to PDF processing queue
     if !pdf_records.is_empty() {
-        let pdf_queue_url = env::var("PDF_PROCESSING_QUEUE_URL")
-            .map_err(|_| Error::from("PDF_PROCESSING_QUEUE_URL not set"))?;
+        let pdf_queue_url = discovery
+            .resolve(Resource::PdfProcessingQueue)
+            .await
+            .map_err(|e| Error::from(format!("PDF processing queue not found: {}", e).as_str()))?;
 
         info!(
             "Queuing {} records with PDFs to processing queue",
             pdf_records.len()
         );
 
-        for record in pdf_records {
-            let message_body = serde_json::to_string(record)
-                .map_err(|e| Error::from(format!("Failed to serialize record: {}", e).as_str()))?;
-
-            match sqs_client
-                .send_message()
-                .queue_url(&pdf_queue_url)
-                .message_body(message_body)
-                .send()
-                .await
-            {
-                Ok(_) => {
-                    info!("Queued PDF record {} for processing", record.resource_id);
-                    queued_count += 1;
-                }
-                Err(e) => {
-                    error!("Failed to queue PDF record {}: {}", record.resource_id, e);
-                }
-            }
-        }
+        let (queued, deferred) = dispatch_records(
+            &sqs_client,
+            &pdf_queue_url,
+            ai_summary_queue_url.as_deref(),
+            &self_queue_url,
+            &pdf_records,
+            &backpressure,
+        )
+        .await?;
+        queued_count += queued;
+        deferred_count += deferred;
     }
 
     // Send records without PDFs directly to ML prediction queue
     if !non_pdf_records.is_empty() {
-        let ml_queue_url = env::var("ML_PREDICTION_QUEUE_URL")
-            .map_err(|_| Error::from("ML_PREDICTION_QUEUE_URL not set"))?;
+        let ml_queue_url = discovery
+            .resolve(Resource::MlPredictionQueue)
+            .await
+            .map_err(|e| Error::from(format!("ML prediction queue not found: {}", e).as_str()))?;
 
         info!(
             "Queuing {} records without PDFs to ML prediction queue",
             non_pdf_records.len()
         );
 
-        for record in non_pdf_records {
-            let message_body = serde_json::to_string(record)
-                .map_err(|e| Error::from(format!("Failed to serialize record: {}", e).as_str()))?;
-
-            match sqs_client
-                .send_message()
-                .queue_url(&ml_queue_url)
-                .message_body(message_body)
-                .send()
-                .await
-            {
-                Ok(_) => {
-                    info!(
-                        "Queued non-PDF record {} for ML prediction",
-                        record.resource_id
-                    );
-                    queued_count += 1;
-                }
-                Err(e) => {
-                    error!(
-                        "Failed to queue non-PDF record {}: {}",
-                        record.resource_id, e
-                    );
-                }
+        let (queued, deferred) = dispatch_records(
+            &sqs_client,
+            &ml_queue_url,
+            ai_summary_queue_url.as_deref(),
+            &self_queue_url,
+            &non_pdf_records,
+            &backpressure,
+        )
+        .await?;
+        queued_count += queued;
+        deferred_count += deferred;
+    }
+
+    Ok((queued_count, deferred_count))
+}
+
+async fn publish_tender_updated_events(records: &[TenderRecord]) -> Result<usize, Error> {
+    let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        .load()
+        .await;
+
+    // Webhooks are optional - skip silently when the dispatcher isn't deployed
+    let Some(webhook_queue_url) = ResourceDiscovery::new(&aws_config)
+        .resolve_optional(Resource::WebhookQueue)
+        .await
+    else {
+        return Ok(0);
+    };
+
+    let sqs_client = SqsClient::new(&aws_config);
+
+    // Synthetic canary tenders are never announced to third parties
+    let announced: Vec<&TenderRecord> = records
+        .iter()
+        .filter(|r| !environment::is_canary(r.resource_id))
+        .collect();
+    let entries: Vec<Entry> = announced
+        .iter()
+        .map(|record| {
+            let event = serde_json::json!({
+                "event_type": "TENDER_UPDATED",
+                "resource_id": record.resource_id,
+                "occurred_at": chrono::Utc::now(),
+                "payload": record,
+            });
+            Entry::new(event.to_string())
+        })
+        .collect();
+
+    let mut published = 0;
+    let outcomes = batch::send_batch(&sqs_client, &webhook_queue_url, &entries).await;
+    for (record, sent) in announced.iter().zip(outcomes) {
+        match sent {
+            Ok(_) => published += 1,
+            Err(e) => {
+                error!(
+                    "Failed to publish webhook event for {}: {}",
+                    record.resource_id, e
+                );
             }
         }
     }
 
-    Ok(queued_count)
+    info!("Published {} TENDER_UPDATED webhook events", published);
+    Ok(published)
 }
 
 #[tokio::main]
@@ -390,5 +658,9 @@ async fn main() -> Result<(), Error> {
         .without_time()
         .init();
 
-    lambda_runtime::run(service_fn(function_handler)).await
+    let environment = Environment::from_env();
+    lambda_runtime::run(service_fn(move |event| {
+        function_handler(event).instrument(environment.span())
+    }))
+    .await
 }
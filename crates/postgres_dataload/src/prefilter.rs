@@ -0,0 +1,145 @@
+//! Tenders we would never bid on, dropped before any PDF processing or Claude call.
+//!
+//! A tender whose status isn't in `PIPELINE_STATUSES` (by default only open and
+//! unrecognised ones; see `pipeline_contract::tender_status`), whose estimated value is
+//! below `DATALOAD_MIN_VALUE` (default €25,000) or whose deadline is less than
+//! `DATALOAD_MIN_DAYS_TO_DEADLINE` days away (default 3) is still saved, so it stays
+//! queryable, but gets `tender_records.filtered_out_reason` and the `filtered_out`
+//! lifecycle state instead of being queued. A tender with no value or no deadline on the
+//! portal isn't filtered on it. Setting either number to 0 turns that check off.
+
+use bigdecimal::BigDecimal;
+use chrono::{Duration, NaiveDateTime};
+use pipeline_contract::tender_status::{self, TenderStatus};
+
+const DEFAULT_MIN_VALUE: i64 = 25_000;
+const DEFAULT_MIN_DAYS_TO_DEADLINE: i64 = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prefilter {
+    /// Normalised statuses that go through the pipeline
+    pub statuses: Vec<TenderStatus>,
+    /// Tenders valued below this are filtered out; None disables the check
+    pub min_value: Option<BigDecimal>,
+    /// Tenders closing sooner than this are filtered out; None disables the check
+    pub min_time_to_deadline: Option<Duration>,
+}
+
+impl Prefilter {
+    pub fn from_env() -> Self {
+        let env_or = |var: &str, default: i64| {
+            std::env::var(var)
+                .ok()
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .filter(|v| *v >= 0)
+                .unwrap_or(default)
+        };
+        let min_value = env_or("DATALOAD_MIN_VALUE", DEFAULT_MIN_VALUE);
+        let min_days = env_or(
+            "DATALOAD_MIN_DAYS_TO_DEADLINE",
+            DEFAULT_MIN_DAYS_TO_DEADLINE,
+        );
+        Self {
+            statuses: tender_status::allowlist_from_env(),
+            min_value: (min_value > 0).then(|| BigDecimal::from(min_value)),
+            min_time_to_deadline: (min_days > 0).then(|| Duration::days(min_days)),
+        }
+    }
+
+    /// Why a tender should be filtered out, or None to process it
+    pub fn reason(
+        &self,
+        status: &str,
+        value: Option<&BigDecimal>,
+        deadline: Option<NaiveDateTime>,
+        now: NaiveDateTime,
+    ) -> Option<String> {
+        let normalized = TenderStatus::normalize(status);
+        if !self.statuses.contains(&normalized) {
+            return Some(format!("status '{}' is not in PIPELINE_STATUSES", status));
+        }
+        if let (Some(min_value), Some(value)) = (&self.min_value, value)
+            && value < min_value
+        {
+            return Some(format!("value {} is below {}", value, min_value));
+        }
+        if let (Some(min_time), Some(deadline)) = (self.min_time_to_deadline, deadline)
+            && deadline - now < min_time
+        {
+            return Some(format!(
+                "deadline {} is less than {} days away",
+                deadline.format("%Y-%m-%d %H:%M"),
+                min_time.num_days()
+            ));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn prefilter() -> Prefilter {
+        Prefilter {
+            statuses: vec![TenderStatus::Open, TenderStatus::Unknown],
+            min_value: Some(BigDecimal::from(25_000)),
+            min_time_to_deadline: Some(Duration::days(3)),
+        }
+    }
+
+    fn at(day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_value_and_deadline_filters() {
+        let now = at(1);
+        let small = BigDecimal::from(24_999);
+        let large = BigDecimal::from(25_000);
+
+        assert_eq!(
+            prefilter().reason("Open", Some(&small), Some(at(20)), now),
+            Some("value 24999 is below 25000".to_string())
+        );
+        assert_eq!(
+            prefilter().reason("Open", Some(&large), Some(at(3)), now),
+            Some("deadline 2026-10-03 12:00 is less than 3 days away".to_string())
+        );
+        assert_eq!(
+            prefilter().reason("Open", Some(&large), Some(at(4)), now),
+            None
+        );
+    }
+
+    #[test]
+    fn test_status_allowlist() {
+        let now = at(1);
+        assert_eq!(
+            prefilter().reason("Cancelled", None, None, now),
+            Some("status 'Cancelled' is not in PIPELINE_STATUSES".to_string())
+        );
+        assert_eq!(prefilter().reason("", None, None, now), None);
+    }
+
+    #[test]
+    fn test_missing_values_and_disabled_checks_pass() {
+        let now = at(1);
+        assert_eq!(prefilter().reason("Open", None, None, now), None);
+
+        let disabled = Prefilter {
+            statuses: vec![TenderStatus::Open],
+            min_value: None,
+            min_time_to_deadline: None,
+        };
+        let small = BigDecimal::from(100);
+        assert_eq!(
+            disabled.reason("Open", Some(&small), Some(at(1)), now),
+            None
+        );
+    }
+}
@@ -0,0 +1,309 @@
+//! Sending many messages to one queue with `SendMessageBatch`.
+//!
+//! etenders_scraper and postgres_dataload forward a page or a load's worth of records at a
+//! time; one request per 10 messages instead of one each cuts the time and the request
+//! count. SQS accepts or rejects each entry of a batch separately, so `send_batch` reports
+//! an outcome per message. Entries SQS failed on its side (`SenderFault` false: throttling,
+//! internal errors) and whole requests that failed are retried with the `sqs_send_batch`
+//! policy; entries SQS rejected as malformed or too large fail without another try.
+//!
+//! Batches hold at most 10 entries and 256 KiB of bodies and attributes, SQS's limits.
+
+use aws_sdk_sqs::operation::send_message_batch::SendMessageBatchOutput;
+use aws_sdk_sqs::types::SendMessageBatchRequestEntry;
+use aws_sdk_sqs::Client as SqsClient;
+use pipeline_contract::Routing;
+use retry::Policy;
+use std::future::Future;
+use std::ops::Range;
+use std::sync::Mutex;
+
+/// Most entries SQS takes in one batch
+pub const MAX_ENTRIES: usize = 10;
+/// Most bytes (bodies plus attributes) SQS takes in one batch
+pub const MAX_BATCH_BYTES: usize = 256 * 1024;
+
+/// One message of a batch
+#[derive(Debug, Clone, Default)]
+pub struct Entry {
+    pub body: String,
+    pub routing: Routing,
+    pub delay_seconds: i32,
+}
+
+impl Entry {
+    pub fn new(body: String) -> Self {
+        Self {
+            body,
+            ..Default::default()
+        }
+    }
+
+    pub fn routing(mut self, routing: Routing) -> Self {
+        self.routing = routing;
+        self
+    }
+
+    pub fn delay_seconds(mut self, delay_seconds: i32) -> Self {
+        self.delay_seconds = delay_seconds;
+        self
+    }
+
+    /// Bytes the entry counts for against the batch size limit
+    fn size(&self) -> usize {
+        self.body.len()
+            + self
+                .routing
+                .attributes()
+                .iter()
+                .map(|(name, value)| name.len() + value.len() + "String".len())
+                .sum::<usize>()
+    }
+
+    fn request_entry(&self, id: usize) -> Option<SendMessageBatchRequestEntry> {
+        SendMessageBatchRequestEntry::builder()
+            .id(id.to_string())
+            .message_body(&self.body)
+            .delay_seconds(self.delay_seconds)
+            .set_message_attributes(Some(self.routing.sqs_attributes()))
+            .build()
+            .ok()
+    }
+}
+
+/// What happened to one entry: its message id, or why it wasn't sent
+pub type Sent = Result<String, String>;
+
+/// Send `entries` to `queue_url` in as few batches as the limits allow. The outcomes are
+/// in the same order as `entries`
+pub async fn send_batch(sqs: &SqsClient, queue_url: &str, entries: &[Entry]) -> Vec<Sent> {
+    send_with(entries, |request_entries| async move {
+        sqs.send_message_batch()
+            .queue_url(queue_url)
+            .set_entries(Some(request_entries))
+            .send()
+            .await
+            .map_err(|e| e.to_string())
+    })
+    .await
+}
+
+/// `send_batch` with each SendMessageBatch request made by `send`, so tests can stand in
+/// for SQS
+async fn send_with<F, Fut>(entries: &[Entry], send: F) -> Vec<Sent>
+where
+    F: Fn(Vec<SendMessageBatchRequestEntry>) -> Fut,
+    Fut: Future<Output = Result<SendMessageBatchOutput, String>>,
+{
+    let outcomes: Mutex<Vec<Sent>> = Mutex::new(vec![Err("not sent".to_string()); entries.len()]);
+    for batch in batches(entries) {
+        let pending = Mutex::new(batch.collect::<Vec<usize>>());
+        // The policy only sees whether retryable entries are left; outcomes are kept per entry
+        let _ = Policy::aws("sqs_send_batch")
+            .max_tries(3)
+            .run(|| send_pending(&send, entries, &pending, &outcomes))
+            .await;
+    }
+    outcomes.into_inner().unwrap_or_else(|e| e.into_inner())
+}
+
+/// One try at the entries still pending, leaving the retryable failures pending
+async fn send_pending<F, Fut>(
+    send: &F,
+    entries: &[Entry],
+    pending: &Mutex<Vec<usize>>,
+    outcomes: &Mutex<Vec<Sent>>,
+) -> Result<(), String>
+where
+    F: Fn(Vec<SendMessageBatchRequestEntry>) -> Fut,
+    Fut: Future<Output = Result<SendMessageBatchOutput, String>>,
+{
+    let ids = pending.lock().map_err(|e| e.to_string())?.clone();
+    let request_entries = ids
+        .iter()
+        .filter_map(|&id| entries[id].request_entry(id))
+        .collect();
+
+    let output = match send(request_entries).await {
+        Ok(output) => output,
+        Err(e) => {
+            let error = format!("SendMessageBatch failed: {}", e);
+            let mut outcomes = outcomes.lock().map_err(|e| e.to_string())?;
+            for &id in &ids {
+                outcomes[id] = Err(error.clone());
+            }
+            return Err(error);
+        }
+    };
+
+    let mut outcomes = outcomes.lock().map_err(|e| e.to_string())?;
+    for sent in &output.successful {
+        if let Some(id) = entry_id(&sent.id, entries.len()) {
+            outcomes[id] = Ok(sent.message_id.clone());
+        }
+    }
+    let mut retry = Vec::new();
+    for failed in &output.failed {
+        let Some(id) = entry_id(&failed.id, entries.len()) else {
+            continue;
+        };
+        outcomes[id] = Err(format!(
+            "{}: {}",
+            failed.code,
+            failed.message.as_deref().unwrap_or("no message")
+        ));
+        if !failed.sender_fault {
+            retry.push(id);
+        }
+    }
+
+    let failed = retry.len();
+    *pending.lock().map_err(|e| e.to_string())? = retry;
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(format!("{} of {} entries failed", failed, ids.len()))
+    }
+}
+
+fn entry_id(id: &str, entries: usize) -> Option<usize> {
+    id.parse().ok().filter(|&id| id < entries)
+}
+
+/// Consecutive runs of entries within the entry and size limits. An entry over the size
+/// limit on its own gets a batch to itself, for SQS to reject
+fn batches(entries: &[Entry]) -> Vec<Range<usize>> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut bytes = 0;
+    for (index, entry) in entries.iter().enumerate() {
+        let size = entry.size();
+        if index > start && (index - start == MAX_ENTRIES || bytes + size > MAX_BATCH_BYTES) {
+            batches.push(start..index);
+            start = index;
+            bytes = 0;
+        }
+        bytes += size;
+    }
+    if start < entries.len() {
+        batches.push(start..entries.len());
+    }
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_sqs::types::{BatchResultErrorEntry, SendMessageBatchResultEntry};
+    use std::cell::RefCell;
+
+    fn entries(sizes: &[usize]) -> Vec<Entry> {
+        sizes
+            .iter()
+            .map(|&size| Entry::new("x".repeat(size)))
+            .collect()
+    }
+
+    #[test]
+    fn test_batches_respect_entry_and_size_limits() {
+        assert!(batches(&[]).is_empty());
+        assert_eq!(batches(&entries(&[10; 23])), vec![0..10, 10..20, 20..23]);
+
+        let half = MAX_BATCH_BYTES / 2;
+        assert_eq!(
+            batches(&entries(&[half, half, 1, MAX_BATCH_BYTES + 1, 5])),
+            vec![0..2, 2..3, 3..4, 4..5]
+        );
+    }
+
+    #[test]
+    fn test_entry_size_counts_attributes() {
+        let entry = Entry::new("{}".to_string()).routing(Routing::to_stage("pdf_processing"));
+        // The body, then processing_stage and schema_version with their "String" data types
+        assert_eq!(entry.size(), 2 + (16 + 14 + 6) + (14 + 1 + 6));
+        assert_eq!(entry.request_entry(3).unwrap().id, "3");
+    }
+
+    fn sent(id: &str) -> SendMessageBatchResultEntry {
+        SendMessageBatchResultEntry::builder()
+            .id(id)
+            .message_id(format!("message-{}", id))
+            .md5_of_message_body("")
+            .build()
+            .unwrap()
+    }
+
+    fn failed(id: &str, code: &str, sender_fault: bool) -> BatchResultErrorEntry {
+        BatchResultErrorEntry::builder()
+            .id(id)
+            .code(code)
+            .sender_fault(sender_fault)
+            .build()
+            .unwrap()
+    }
+
+    /// Stands in for SQS: records the entry ids of each request and replies with `reply`
+    async fn send_stubbed(
+        entries: &[Entry],
+        reply: impl Fn(usize, &str) -> Result<SendMessageBatchResultEntry, BatchResultErrorEntry>,
+    ) -> (Vec<Sent>, Vec<Vec<String>>) {
+        let requests = RefCell::new(Vec::new());
+        let outcomes = send_with(entries, |request_entries| {
+            let mut requests = requests.borrow_mut();
+            let call = requests.len();
+            requests.push(
+                request_entries
+                    .iter()
+                    .map(|entry| entry.id.clone())
+                    .collect::<Vec<_>>(),
+            );
+            let (successful, failed): (Vec<_>, Vec<_>) = request_entries
+                .iter()
+                .map(|entry| reply(call, &entry.id))
+                .partition(Result::is_ok);
+            let output = SendMessageBatchOutput::builder()
+                .set_successful(Some(
+                    successful.into_iter().filter_map(Result::ok).collect(),
+                ))
+                .set_failed(Some(failed.into_iter().filter_map(Result::err).collect()))
+                .build()
+                .map_err(|e| e.to_string());
+            async move { output }
+        })
+        .await;
+        (outcomes, requests.into_inner())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_only_sqs_side_failures_are_resent() {
+        let entries = entries(&[10, 10, 10]);
+        let (outcomes, requests) = send_stubbed(&entries, |call, id| match (call, id) {
+            (0, "1") => Err(failed(id, "ThrottlingException", false)),
+            (_, "2") => Err(failed(id, "InvalidMessageContents", true)),
+            _ => Ok(sent(id)),
+        })
+        .await;
+
+        assert_eq!(requests, vec![vec!["0", "1", "2"], vec!["1"]]);
+        assert_eq!(outcomes[0], Ok("message-0".to_string()));
+        assert_eq!(outcomes[1], Ok("message-1".to_string()));
+        assert_eq!(
+            outcomes[2],
+            Err("InvalidMessageContents: no message".to_string())
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_gives_up_after_three_tries() {
+        let entries = entries(&[10, 10]);
+        let (outcomes, requests) = send_stubbed(&entries, |_, id| match id {
+            "0" => Err(failed(id, "InternalError", false)),
+            _ => Ok(sent(id)),
+        })
+        .await;
+
+        assert_eq!(requests, vec![vec!["0", "1"], vec!["0"], vec!["0"]]);
+        assert_eq!(outcomes[0], Err("InternalError: no message".to_string()));
+        assert_eq!(outcomes[1], Ok("message-1".to_string()));
+    }
+}
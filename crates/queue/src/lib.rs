@@ -0,0 +1,204 @@
+//! Sends stage messages either straight to the consuming queue or through one SNS topic.
+//!
+//! Producers name the queue a message is for (`Resource::MlPredictionQueue` and so on)
+//! and `Publisher` picks the transport from `PIPELINE_TOPIC_ARN`, which Terraform sets per
+//! environment:
+//!
+//! - unset or empty: the queue URL is resolved through `resource_discovery` and the
+//!   message sent to it, as before
+//! - a topic ARN: the message is published once to the topic with a `destination`
+//!   attribute (the queue's `Resource::name()`). The PDF, ML, AI summary and notification
+//!   queues subscribe with raw delivery and a filter policy on `destination`, so adding
+//!   another consumer (an audit queue, a second environment's canary) is a subscription
+//!   rather than a producer change
+//!
+//! Either way the routing hints go in message attributes; with raw delivery SNS passes
+//! them on as SQS message attributes, so consumers read them with `Routing::from_sqs`
+//! whichever transport sent the message. SNS can't delay delivery, so postgres_dataload,
+//! which spaces its sends out with SQS delays, still sends to the PDF queue directly.
+//!
+//! `batch` sends many messages to one queue with `SendMessageBatch`, for the scraper and
+//! postgres_dataload.
+
+use anyhow::{Context, Result};
+use aws_config::SdkConfig;
+use aws_sdk_sns::types::MessageAttributeValue as SnsAttribute;
+use aws_sdk_sns::Client as SnsClient;
+use aws_sdk_sqs::Client as SqsClient;
+use pipeline_contract::Routing;
+use resource_discovery::{Resource, ResourceDiscovery};
+use retry::Policy;
+use tracing::info;
+
+pub mod batch;
+
+/// Message attribute the topic's subscription filter policies match on
+pub const DESTINATION: &str = "destination";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transport {
+    /// Straight to the destination queue
+    Sqs,
+    /// Published to the pipeline topic, which fans out to the subscribed queues
+    Sns { topic_arn: String },
+}
+
+impl Transport {
+    pub fn parse(topic_arn: Option<&str>) -> Self {
+        match topic_arn.map(str::trim).filter(|arn| !arn.is_empty()) {
+            Some(topic_arn) => Transport::Sns {
+                topic_arn: topic_arn.to_string(),
+            },
+            None => Transport::Sqs,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::parse(std::env::var("PIPELINE_TOPIC_ARN").ok().as_deref())
+    }
+}
+
+/// Message attributes for a message to `destination`: the routing hints, plus the
+/// destination itself when publishing to the topic
+pub fn attributes(
+    transport: &Transport,
+    destination: Resource,
+    routing: &Routing,
+) -> Vec<(&'static str, String)> {
+    let mut attributes = routing.attributes();
+    if let Transport::Sns { .. } = transport {
+        attributes.push((DESTINATION, destination.name().to_string()));
+    }
+    attributes
+}
+
+pub struct Publisher {
+    transport: Transport,
+    sqs_client: SqsClient,
+    sns_client: SnsClient,
+    discovery: ResourceDiscovery,
+}
+
+impl Publisher {
+    pub fn new(aws_config: &SdkConfig) -> Self {
+        let transport = Transport::from_env();
+        if let Transport::Sns { topic_arn } = &transport {
+            info!("📣 Publishing stage messages to {}", topic_arn);
+        }
+        Self {
+            transport,
+            sqs_client: SqsClient::new(aws_config),
+            sns_client: SnsClient::new(aws_config),
+            discovery: ResourceDiscovery::new(aws_config),
+        }
+    }
+
+    /// Convenience constructor that loads the default AWS config
+    pub async fn from_env() -> Self {
+        let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .load()
+            .await;
+        Self::new(&aws_config)
+    }
+
+    pub fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    /// Send `body` to `destination` with the routing hints; returns the message id
+    pub async fn send(
+        &self,
+        destination: Resource,
+        body: &str,
+        routing: &Routing,
+    ) -> Result<String> {
+        match &self.transport {
+            Transport::Sqs => self.send_to_queue(destination, body, routing).await,
+            Transport::Sns { topic_arn } => {
+                self.publish(topic_arn, destination, body, routing).await
+            }
+        }
+    }
+
+    async fn send_to_queue(
+        &self,
+        destination: Resource,
+        body: &str,
+        routing: &Routing,
+    ) -> Result<String> {
+        let queue_url = self
+            .discovery
+            .resolve(destination)
+            .await
+            .with_context(|| format!("{} could not be resolved", destination.name()))?;
+
+        let response = Policy::aws("sqs_send")
+            .run(|| {
+                routing
+                    .apply(
+                        self.sqs_client
+                            .send_message()
+                            .queue_url(&queue_url)
+                            .message_body(body),
+                    )
+                    .send()
+            })
+            .await
+            .with_context(|| format!("Failed to send to {}", destination.name()))?;
+
+        Ok(response.message_id().unwrap_or_default().to_string())
+    }
+
+    async fn publish(
+        &self,
+        topic_arn: &str,
+        destination: Resource,
+        body: &str,
+        routing: &Routing,
+    ) -> Result<String> {
+        let mut request = self.sns_client.publish().topic_arn(topic_arn).message(body);
+        for (name, value) in attributes(&self.transport, destination, routing) {
+            let attribute = SnsAttribute::builder()
+                .data_type("String")
+                .string_value(value)
+                .build()?;
+            request = request.message_attributes(name, attribute);
+        }
+
+        let response = Policy::aws("sns_publish")
+            .run(|| request.clone().send())
+            .await
+            .with_context(|| format!("Failed to publish for {}", destination.name()))?;
+
+        Ok(response.message_id().unwrap_or_default().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transport_parse() {
+        assert_eq!(Transport::parse(None), Transport::Sqs);
+        assert_eq!(Transport::parse(Some("  ")), Transport::Sqs);
+        assert_eq!(
+            Transport::parse(Some("arn:aws:sns:eu-west-2:123456789012:pipeline-events")),
+            Transport::Sns {
+                topic_arn: "arn:aws:sns:eu-west-2:123456789012:pipeline-events".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_attributes() {
+        let routing = Routing::to_stage("ml_prediction");
+        let sqs = attributes(&Transport::Sqs, Resource::MlPredictionQueue, &routing);
+        assert!(sqs.iter().all(|(name, _)| *name != DESTINATION));
+
+        let sns = Transport::parse(Some("arn:aws:sns:eu-west-2:123456789012:pipeline-events"));
+        let published = attributes(&sns, Resource::MlPredictionQueue, &routing);
+        assert_eq!(published.len(), sqs.len() + 1);
+        assert!(published.contains(&(DESTINATION, "ml-prediction-queue".to_string())));
+    }
+}
@@ -0,0 +1,220 @@
+//! Raw MIME messages for SES `SendRawEmail`.
+//!
+//! `SendEmail` only takes HTML and text bodies, so an email that embeds an image (the PDF
+//! preview, referenced from the HTML as `cid:`) or needs headers of its own (threading,
+//! see `thread`) is built here and sent raw. Bodies are multipart/alternative when there
+//! is HTML, wrapped in multipart/related when there is also an inline image.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+pub mod thread;
+
+pub use thread::Thread;
+
+/// An image the HTML body references as `<img src="cid:{content_id}">`
+pub struct InlineImage<'a> {
+    pub content_id: &'a str,
+    pub filename: &'a str,
+    pub content_type: &'a str,
+    pub data: &'a [u8],
+}
+
+pub struct RawEmail<'a> {
+    pub from: &'a str,
+    pub to: &'a [String],
+    pub subject: &'a str,
+    pub text_body: &'a str,
+    /// None for a plain-text email
+    pub html_body: Option<&'a str>,
+    /// Only sent alongside an HTML body
+    pub image: Option<&'a InlineImage<'a>>,
+    pub thread: Option<&'a Thread>,
+}
+
+impl RawEmail<'_> {
+    pub fn build(&self) -> String {
+        let mut message = String::new();
+        message.push_str(&format!("From: {}\r\n", self.from));
+        message.push_str(&format!("To: {}\r\n", self.to.join(", ")));
+        message.push_str(&format!("Subject: {}\r\n", encode_header(self.subject)));
+        if let Some(thread) = self.thread {
+            message.push_str(&thread.headers());
+        }
+        message.push_str("MIME-Version: 1.0\r\n");
+
+        let Some(html_body) = self.html_body else {
+            message.push_str(&text_part("text/plain", self.text_body));
+            return message;
+        };
+        let Some(image) = self.image else {
+            message.push_str(&alternative(self.text_body, html_body));
+            return message;
+        };
+
+        let related = boundary("related");
+        message.push_str(&format!(
+            "Content-Type: multipart/related; type=\"multipart/alternative\"; boundary=\"{}\"\r\n\r\n",
+            related
+        ));
+        message.push_str(&format!("--{}\r\n", related));
+        message.push_str(&alternative(self.text_body, html_body));
+
+        message.push_str(&format!("--{}\r\n", related));
+        message.push_str(&format!(
+            "Content-Type: {}; name=\"{}\"\r\n",
+            image.content_type, image.filename
+        ));
+        message.push_str("Content-Transfer-Encoding: base64\r\n");
+        message.push_str(&format!("Content-ID: <{}>\r\n", image.content_id));
+        message.push_str(&format!(
+            "Content-Disposition: inline; filename=\"{}\"\r\n\r\n",
+            image.filename
+        ));
+        message.push_str(&encode_body(image.data));
+        message.push_str(&format!("--{}--\r\n", related));
+
+        message
+    }
+}
+
+/// A multipart/alternative part (headers and body) with the text and HTML bodies
+fn alternative(text_body: &str, html_body: &str) -> String {
+    let alternative = boundary("alternative");
+    let mut part = format!(
+        "Content-Type: multipart/alternative; boundary=\"{}\"\r\n\r\n",
+        alternative
+    );
+    for (content_type, body) in [("text/plain", text_body), ("text/html", html_body)] {
+        part.push_str(&format!("--{}\r\n", alternative));
+        part.push_str(&text_part(content_type, body));
+    }
+    part.push_str(&format!("--{}--\r\n", alternative));
+    part
+}
+
+fn text_part(content_type: &str, body: &str) -> String {
+    format!(
+        "Content-Type: {}; charset=UTF-8\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+        content_type,
+        encode_body(body.as_bytes())
+    )
+}
+
+fn boundary(part: &str) -> String {
+    format!("=_{}_{}", part, uuid::Uuid::new_v4().simple())
+}
+
+/// RFC 2047 encoded words, split so each stays within the 75 character limit
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        return value.to_string();
+    }
+
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+    for c in value.chars() {
+        if chunk.len() + c.len_utf8() > 45 {
+            words.push(format!("=?UTF-8?B?{}?=", STANDARD.encode(&chunk)));
+            chunk.clear();
+        }
+        chunk.push(c);
+    }
+    if !chunk.is_empty() {
+        words.push(format!("=?UTF-8?B?{}?=", STANDARD.encode(&chunk)));
+    }
+    words.join("\r\n ")
+}
+
+/// Base64 in 76 character lines
+fn encode_body(data: &[u8]) -> String {
+    let encoded = STANDARD.encode(data);
+    let mut body = String::with_capacity(encoded.len() + encoded.len() / 38 + 2);
+    for line in encoded.as_bytes().chunks(76) {
+        body.push_str(std::str::from_utf8(line).expect("base64 is ASCII"));
+        body.push_str("\r\n");
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_message_references_inline_image() {
+        let image = InlineImage {
+            content_id: "pdf-preview",
+            filename: "preview.png",
+            content_type: "image/png",
+            data: &[0x89, b'P', b'N', b'G'],
+        };
+        let to = ["a@example.com".to_string(), "b@example.com".to_string()];
+        let message = RawEmail {
+            from: "from@example.com",
+            to: &to,
+            subject: "Tender Opportunity",
+            text_body: "text",
+            html_body: Some("<img src=\"cid:pdf-preview\">"),
+            image: Some(&image),
+            thread: None,
+        }
+        .build();
+
+        assert!(message.contains("To: a@example.com, b@example.com\r\n"));
+        assert!(message.contains("Subject: Tender Opportunity\r\n"));
+        assert!(message.contains("Content-ID: <pdf-preview>\r\n"));
+        assert!(message.contains(&STANDARD.encode("<img src=\"cid:pdf-preview\">")));
+        assert!(message.contains(&STANDARD.encode(image.data)));
+        assert!(!message.contains("References:"));
+    }
+
+    #[test]
+    fn test_threaded_plain_text_message() {
+        let thread = Thread::for_tender("42", "default", "prod", "from@example.com");
+        let to = ["a@example.com".to_string()];
+        let message = RawEmail {
+            from: "from@example.com",
+            to: &to,
+            subject: "Clarification deadline",
+            text_body: "Questions close on Friday",
+            html_body: None,
+            image: None,
+            thread: Some(&thread),
+        }
+        .build();
+
+        let (headers, body) = message.split_once("\r\n\r\n").unwrap();
+        assert!(headers.contains("\r\nReferences: <tender-42.default.prod@example.com>\r\n"));
+        assert!(headers.contains("\r\nIn-Reply-To: <tender-42.default.prod@example.com>\r\n"));
+        assert!(headers.ends_with(
+            "Content-Type: text/plain; charset=UTF-8\r\nContent-Transfer-Encoding: base64"
+        ));
+        assert_eq!(body, encode_body(b"Questions close on Friday"));
+    }
+
+    #[test]
+    fn test_encode_header() {
+        assert_eq!(encode_header("Plain"), "Plain");
+
+        let subject = "Tender Opportunity – Cúram Sláinte ".repeat(3);
+        let encoded = encode_header(&subject);
+        assert!(encoded.lines().all(|line| line.trim().len() <= 75));
+
+        let decoded: Vec<u8> = encoded
+            .split_whitespace()
+            .flat_map(|word| {
+                let payload = word.trim_start_matches("=?UTF-8?B?").trim_end_matches("?=");
+                STANDARD.decode(payload).unwrap()
+            })
+            .collect();
+        assert_eq!(String::from_utf8(decoded).unwrap(), subject);
+    }
+
+    #[test]
+    fn test_encode_body_wraps_lines() {
+        let body = encode_body(&[0u8; 200]);
+        assert!(body.lines().all(|line| line.len() <= 76));
+        assert!(body.ends_with("\r\n"));
+    }
+}
@@ -0,0 +1,118 @@
+//! Threading headers so every email about a tender lands in one conversation.
+//!
+//! The initial notification, later updates and reminders for a tender are separate SES
+//! sends, so without help each shows up as its own thread. Every one of them now carries
+//! `In-Reply-To` and `References` naming the same root Message-ID, derived from the
+//! tender, tenant and environment, which mail clients thread on. No email is sent with
+//! the root ID itself: SES may replace a Message-ID it is given, and a re-sent
+//! notification reusing one would be dropped as a duplicate by some clients.
+
+/// Headers for one email in a tender's thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thread {
+    root: String,
+    message_id: String,
+}
+
+impl Thread {
+    /// The thread for a tender's emails to one tenant. `from` supplies the domain, so the
+    /// IDs are ours; `environment` keeps test sends out of production threads
+    pub fn for_tender(resource_id: &str, tenant_id: &str, environment: &str, from: &str) -> Self {
+        let domain = domain(from);
+        let tender = format!(
+            "tender-{}.{}.{}",
+            id_part(resource_id),
+            id_part(tenant_id),
+            id_part(environment)
+        );
+        Self {
+            root: format!("<{}@{}>", tender, domain),
+            message_id: format!("<{}.{}@{}>", tender, uuid::Uuid::new_v4().simple(), domain),
+        }
+    }
+
+    /// Message-ID every email in the thread refers to
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// This email's own Message-ID
+    pub fn message_id(&self) -> &str {
+        &self.message_id
+    }
+
+    /// The header lines, CRLF terminated
+    pub fn headers(&self) -> String {
+        format!(
+            "Message-ID: {}\r\nIn-Reply-To: {}\r\nReferences: {}\r\n",
+            self.message_id, self.root, self.root
+        )
+    }
+}
+
+/// The sender's domain, or "localhost" for an address without one
+fn domain(from: &str) -> String {
+    let address = from
+        .rsplit_once('<')
+        .map_or(from, |(_, address)| address.trim_end_matches('>'));
+    address
+        .rsplit_once('@')
+        .map(|(_, domain)| id_part(domain))
+        .filter(|domain| !domain.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// Characters that are safe in a Message-ID
+fn id_part(value: &str) -> String {
+    value
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_tender_shares_root() {
+        let first = Thread::for_tender("12345", "default", "prod", "noreply@example.com");
+        let second =
+            Thread::for_tender("12345", "default", "prod", "Tenders <noreply@example.com>");
+
+        assert_eq!(first.root(), "<tender-12345.default.prod@example.com>");
+        assert_eq!(first.root(), second.root());
+        assert_ne!(first.message_id(), second.message_id());
+        assert!(first
+            .message_id()
+            .starts_with("<tender-12345.default.prod."));
+        assert!(first.message_id().ends_with("@example.com>"));
+
+        let other_tenant = Thread::for_tender("12345", "acme co", "prod", "noreply@example.com");
+        assert_eq!(
+            other_tenant.root(),
+            "<tender-12345.acme_co.prod@example.com>"
+        );
+        let staging = Thread::for_tender("12345", "default", "staging", "noreply@example.com");
+        assert_ne!(staging.root(), first.root());
+    }
+
+    #[test]
+    fn test_headers() {
+        let thread = Thread::for_tender("7", "default", "prod", "not-an-address");
+        assert_eq!(
+            thread.headers(),
+            format!(
+                "Message-ID: {}\r\nIn-Reply-To: <tender-7.default.prod@localhost>\r\nReferences: <tender-7.default.prod@localhost>\r\n",
+                thread.message_id()
+            )
+        );
+    }
+}
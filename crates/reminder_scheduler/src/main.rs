@@ -0,0 +1,243 @@
+use anyhow::Result;
+use aws_config::BehaviorVersion;
+use aws_sdk_ses::primitives::Blob;
+use aws_sdk_ses::types::RawMessage;
+use aws_sdk_ses::Client as SesClient;
+use chrono::Utc;
+use environment::Environment;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use raw_email::{RawEmail, Thread};
+use reminders::Reminder;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tender_overrides::Hold;
+use tracing::{error, info, warn, Instrument};
+
+/// Reminders sent per run; the rest go out on the next one
+const MAX_PER_RUN: i64 = 100;
+
+struct Config {
+    database_url: String,
+    from_email: String,
+    /// For reminders whose tenant has no recipients of its own
+    default_recipients: Vec<String>,
+    /// Dev only: every email is redirected here (and dropped without it)
+    test_inbox: Option<String>,
+}
+
+impl Config {
+    fn from_env() -> Result<Self> {
+        let database_url = std::env::var("DATABASE_URL")
+            .map_err(|_| anyhow::anyhow!("DATABASE_URL environment variable not set"))?;
+
+        let from_email = std::env::var("FROM_EMAIL")
+            .unwrap_or_else(|_| "etenders-noreply@robertsweetman.com".to_string());
+
+        // The same recipients sns_notification sends tender notifications to
+        let default_recipients = std::env::var("NOTIFICATION_EMAILS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|email| email.trim().to_string())
+            .filter(|email| !email.is_empty())
+            .collect();
+
+        let test_inbox = std::env::var("TEST_INBOX")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+
+        Ok(Self {
+            database_url,
+            from_email,
+            default_recipients,
+            test_inbox,
+        })
+    }
+}
+
+/// The manual override holding the reminder's tender back for its tenant, if any. An
+/// override that can't be read holds nothing back
+async fn held(pool: &PgPool, reminder: &Reminder) -> Option<Hold> {
+    match tender_overrides::get(pool, reminder.resource_id, &reminder.tenant_id).await {
+        Ok(tender_override) => tender_override.and_then(|o| o.hold(Utc::now())),
+        Err(e) => {
+            warn!(
+                "⚠️ Failed to read the override for {} (tenant {}), sending as usual: {}",
+                reminder.resource_id, reminder.tenant_id, e
+            );
+            None
+        }
+    }
+}
+
+/// Send every reminder that has fallen due.
+///
+/// A reminder is only marked sent once its email has gone out, so a failed send is
+/// retried on the next run. A snoozed tender's reminder is put off until the snooze ends,
+/// or cancelled when the date comes first; a tender forced to NO BID's is cancelled
+async fn send_due(pool: &PgPool, config: &Config) -> Result<Value> {
+    reminders::ensure_table(pool).await?;
+    tender_overrides::ensure_table(pool).await?;
+    let due = reminders::due(pool, Utc::now(), MAX_PER_RUN).await?;
+
+    let (mut sent, mut failed, mut skipped) = (0, 0, 0);
+    let (mut postponed, mut cancelled) = (0, 0);
+    for scheduled in &due {
+        let reminder = &scheduled.reminder;
+        if let Some(hold) = held(pool, reminder).await {
+            match hold.reminder_due(reminder.event_at.and_utc()) {
+                Some(due_at) => {
+                    reminders::postpone(pool, scheduled.id, due_at).await?;
+                    info!(
+                        "💤 {} reminder for {} put off until {} ({})",
+                        reminder.kind.name(),
+                        reminder.resource_id,
+                        due_at,
+                        hold.name()
+                    );
+                    postponed += 1;
+                }
+                None => {
+                    reminders::cancel(pool, scheduled.id).await?;
+                    info!(
+                        "✋ {} reminder for {} cancelled ({})",
+                        reminder.kind.name(),
+                        reminder.resource_id,
+                        hold.name()
+                    );
+                    cancelled += 1;
+                }
+            }
+            continue;
+        }
+
+        let recipients = if reminder.recipients.is_empty() {
+            &config.default_recipients
+        } else {
+            &reminder.recipients
+        };
+        if recipients.is_empty() {
+            warn!(
+                "📭 No recipients for {} reminder on {} - NOTIFICATION_EMAILS not set",
+                reminder.kind.name(),
+                reminder.resource_id
+            );
+            skipped += 1;
+            continue;
+        }
+
+        if let Err(e) = send(config, reminder, recipients).await {
+            error!(
+                "❌ Failed to send {} reminder for {}: {}",
+                reminder.kind.name(),
+                reminder.resource_id,
+                e
+            );
+            failed += 1;
+            continue;
+        }
+        reminders::mark_sent(pool, scheduled.id).await?;
+        info!(
+            "⏰ Sent {} reminder for {} (tenant {})",
+            reminder.kind.name(),
+            reminder.resource_id,
+            reminder.tenant_id
+        );
+        sent += 1;
+    }
+
+    Ok(json!({
+        "due": due.len(),
+        "sent": sent,
+        "failed": failed,
+        "skipped": skipped,
+        "postponed": postponed,
+        "cancelled": cancelled,
+    }))
+}
+
+async fn send(config: &Config, reminder: &Reminder, recipients: &[String]) -> Result<()> {
+    let environment = Environment::from_env();
+    let recipients = match environment {
+        Environment::Dev => match &config.test_inbox {
+            Some(inbox) => vec![inbox.clone()],
+            None => {
+                warn!(
+                    "Dev environment without TEST_INBOX - suppressing reminder to {}",
+                    recipients.join(", ")
+                );
+                return Ok(());
+            }
+        },
+        _ => recipients.to_vec(),
+    };
+
+    let mut subject = reminder.subject.clone();
+    if !environment.is_production() {
+        subject = format!("[{}] {}", environment.name().to_uppercase(), subject);
+    }
+
+    // Sent raw so the reminder threads with the tender's notification emails
+    let thread = Thread::for_tender(
+        &reminder.resource_id.to_string(),
+        &reminder.tenant_id,
+        environment.name(),
+        &config.from_email,
+    );
+    let raw = RawEmail {
+        from: &config.from_email,
+        to: &recipients,
+        subject: &subject,
+        text_body: &reminder.body,
+        html_body: None,
+        image: None,
+        thread: Some(&thread),
+    }
+    .build();
+
+    let aws_config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+    SesClient::new(&aws_config)
+        .send_raw_email()
+        .source(&config.from_email)
+        .set_destinations(Some(recipients))
+        .raw_message(RawMessage::builder().data(Blob::new(raw)).build()?)
+        .send()
+        .await?;
+    Ok(())
+}
+
+/// Triggered on a schedule (EventBridge), e.g. hourly; the event body is not used
+async fn function_handler(_event: LambdaEvent<Value>) -> Result<Value, Error> {
+    info!("=== REMINDER SCHEDULER STARTED ===");
+
+    let config = Config::from_env().map_err(|e| {
+        error!("Failed to load configuration: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+
+    let pool = db::connect(&config.database_url, 2)
+        .await
+        .map_err(|e| Error::from(format!("Failed to connect to database: {}", e).as_str()))?;
+
+    let summary = send_due(&pool, &config)
+        .await
+        .map_err(|e| Error::from(format!("Failed to send reminders: {}", e).as_str()))?;
+
+    info!("=== REMINDER SCHEDULER COMPLETED === {}", summary);
+    Ok(summary)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    let environment = Environment::from_env();
+    run(service_fn(move |event| {
+        function_handler(event).instrument(environment.span())
+    }))
+    .await
+}
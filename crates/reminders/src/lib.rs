@@ -0,0 +1,254 @@
+//! Reminders about upcoming tender dates, kept in `reminders`.
+//!
+//! A stage schedules a reminder when it learns of a date worth acting on: ai_summary
+//! does for the clarification question deadline of every tender it notifies.
+//! reminder_scheduler then emails each reminder once it falls due. Reminders are keyed
+//! by tender, tenant and kind, so scheduling one again (e.g. for a re-run tender) moves
+//! it rather than adding another, and one that has gone out is never sent twice.
+//!
+//! reminder_scheduler puts a snoozed tender's reminders off until the snooze ends and
+//! cancels those of tenders forced to NO BID (see `tender_overrides`); scheduling a
+//! cancelled reminder again brings it back.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use environment::Environment;
+use sqlx::{PgPool, Row};
+
+/// Days before the date a reminder goes out, unless `REMINDER_LEAD_DAYS` says otherwise
+pub const DEFAULT_LEAD_DAYS: i64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Last day to put clarification questions to the contracting authority
+    ClarificationDeadline,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 1] = [Kind::ClarificationDeadline];
+
+    /// Value stored in `reminders.kind`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::ClarificationDeadline => "clarification_deadline",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == value)
+    }
+}
+
+pub fn lead_days_from_env() -> i64 {
+    std::env::var("REMINDER_LEAD_DAYS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|days| *days >= 0)
+        .unwrap_or(DEFAULT_LEAD_DAYS)
+}
+
+/// When to remind about a date `lead_days` ahead: then, or straight away when that is
+/// already past. `None` once the date itself has passed.
+///
+/// Tender dates are Irish local time but compared as UTC; an hour either way doesn't
+/// matter days ahead.
+pub fn due_at(
+    event_at: NaiveDateTime,
+    lead_days: i64,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let event_at = event_at.and_utc();
+    if event_at <= now {
+        return None;
+    }
+    Some((event_at - Duration::days(lead_days)).max(now))
+}
+
+#[derive(Debug, Clone)]
+pub struct Reminder {
+    pub resource_id: i64,
+    pub tenant_id: String,
+    pub kind: Kind,
+    /// The date reminded about, as the tender gives it
+    pub event_at: NaiveDateTime,
+    pub due_at: DateTime<Utc>,
+    pub subject: String,
+    /// Plain-text email body
+    pub body: String,
+    /// The tenant's recipients; empty for reminder_scheduler's default ones
+    pub recipients: Vec<String>,
+}
+
+/// A reminder waiting to go out
+#[derive(Debug, Clone)]
+pub struct Scheduled {
+    pub id: i64,
+    pub reminder: Reminder,
+}
+
+pub async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "reminders", 2, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS reminders (
+                id BIGSERIAL PRIMARY KEY,
+                resource_id BIGINT NOT NULL,
+                tenant_id TEXT NOT NULL DEFAULT 'default',
+                kind TEXT NOT NULL,
+                event_at TIMESTAMP NOT NULL,
+                due_at TIMESTAMPTZ NOT NULL,
+                subject TEXT NOT NULL,
+                body TEXT NOT NULL,
+                recipients TEXT[] NOT NULL DEFAULT '{}',
+                sent_at TIMESTAMPTZ,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                UNIQUE (resource_id, tenant_id, kind)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query("ALTER TABLE reminders ADD COLUMN IF NOT EXISTS cancelled_at TIMESTAMPTZ")
+            .execute(pool)
+            .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_reminders_due ON reminders (due_at) WHERE sent_at IS NULL",
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "reminders").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Schedule the reminder, moving an unsent one for the same tender, tenant and kind
+pub async fn schedule(pool: &PgPool, reminder: &Reminder) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO reminders
+            (resource_id, tenant_id, kind, event_at, due_at, subject, body, recipients)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (resource_id, tenant_id, kind) DO UPDATE SET
+            event_at = EXCLUDED.event_at,
+            due_at = EXCLUDED.due_at,
+            subject = EXCLUDED.subject,
+            body = EXCLUDED.body,
+            recipients = EXCLUDED.recipients,
+            cancelled_at = NULL
+        WHERE reminders.sent_at IS NULL
+        "#,
+    )
+    .bind(reminder.resource_id)
+    .bind(&reminder.tenant_id)
+    .bind(reminder.kind.name())
+    .bind(reminder.event_at)
+    .bind(reminder.due_at)
+    .bind(&reminder.subject)
+    .bind(&reminder.body)
+    .bind(&reminder.recipients)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Unsent, uncancelled reminders due by `now`, oldest first
+pub async fn due(pool: &PgPool, now: DateTime<Utc>, limit: i64) -> Result<Vec<Scheduled>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, resource_id, tenant_id, kind, event_at, due_at, subject, body, recipients
+        FROM reminders
+        WHERE sent_at IS NULL AND cancelled_at IS NULL AND due_at <= $1
+        ORDER BY due_at, id
+        LIMIT $2
+        "#,
+    )
+    .bind(now)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(Scheduled {
+                id: row.get("id"),
+                reminder: Reminder {
+                    resource_id: row.get("resource_id"),
+                    tenant_id: row.get("tenant_id"),
+                    // Kinds from a newer release are left for it to send
+                    kind: Kind::parse(row.get("kind"))?,
+                    event_at: row.get("event_at"),
+                    due_at: row.get("due_at"),
+                    subject: row.get("subject"),
+                    body: row.get("body"),
+                    recipients: row.get("recipients"),
+                },
+            })
+        })
+        .collect())
+}
+
+pub async fn mark_sent(pool: &PgPool, id: i64) -> Result<()> {
+    sqlx::query("UPDATE reminders SET sent_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Send the reminder at `due_at` instead
+pub async fn postpone(pool: &PgPool, id: i64, due_at: DateTime<Utc>) -> Result<()> {
+    sqlx::query("UPDATE reminders SET due_at = $2 WHERE id = $1")
+        .bind(id)
+        .bind(due_at)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Never send the reminder (unless it's scheduled again)
+pub async fn cancel(pool: &PgPool, id: i64) -> Result<()> {
+    sqlx::query("UPDATE reminders SET cancelled_at = NOW() WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_due_at() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 1, 9, 0, 0).unwrap();
+        let event = |day| {
+            chrono::NaiveDate::from_ymd_opt(2025, 3, day)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap()
+        };
+
+        assert_eq!(
+            due_at(event(14), 3, now),
+            Some(Utc.with_ymd_and_hms(2025, 3, 11, 12, 0, 0).unwrap())
+        );
+        // Too close for the full lead time: remind straight away
+        assert_eq!(due_at(event(2), 3, now), Some(now));
+        // Already past
+        assert_eq!(due_at(event(1) - Duration::hours(4), 3, now), None);
+    }
+
+    #[test]
+    fn test_kind_names_round_trip() {
+        for kind in Kind::ALL {
+            assert_eq!(Kind::parse(kind.name()), Some(kind));
+        }
+        assert_eq!(Kind::parse("submission_deadline"), None);
+    }
+}
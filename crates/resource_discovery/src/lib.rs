@@ -0,0 +1,289 @@
+//! Resolves the queues and buckets the pipeline lambdas talk to.
+//!
+//! Every resource is looked up in the same order:
+//!
+//! 1. An explicit env var (e.g. `PDF_PROCESSING_QUEUE_URL`) - always wins, so existing
+//!    deployments keep working unchanged
+//! 2. A resource tagged `etenders:resource = <name>` (and `etenders:environment = <env>`
+//!    when `RESOURCE_ENVIRONMENT` is set), via the Resource Groups Tagging API
+//! 3. The Terraform naming convention, `<RESOURCE_PREFIX><name>`
+//!
+//! `RESOURCE_PREFIX` and `RESOURCE_ENVIRONMENT` default from `ENVIRONMENT`, so a
+//! staging lambda finds `staging-ai-summary-queue` without extra configuration.
+
+use anyhow::{Context, Result};
+use aws_config::SdkConfig;
+use aws_sdk_resourcegroupstagging::types::TagFilter;
+use aws_sdk_resourcegroupstagging::Client as TaggingClient;
+use aws_sdk_sqs::Client as SqsClient;
+use environment::Environment;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Tag identifying which pipeline resource an AWS resource is
+pub const RESOURCE_TAG: &str = "etenders:resource";
+
+/// Tag identifying the deployment environment of an AWS resource
+pub const ENVIRONMENT_TAG: &str = "etenders:environment";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    Queue,
+    Bucket,
+}
+
+/// Pipeline resources that can be discovered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Resource {
+    TenderProcessingQueue,
+    PdfProcessingQueue,
+    MlPredictionQueue,
+    AiSummaryQueue,
+    NotificationQueue,
+    WebhookQueue,
+    LambdaBucket,
+}
+
+impl Resource {
+    /// Base name used in Terraform and in the `etenders:resource` tag
+    pub fn name(&self) -> &'static str {
+        match self {
+            Resource::TenderProcessingQueue => "tender-processing-queue",
+            Resource::PdfProcessingQueue => "pdf-processing-queue",
+            Resource::MlPredictionQueue => "ml-prediction-queue",
+            Resource::AiSummaryQueue => "ai-summary-queue",
+            Resource::NotificationQueue => "sns-notification-queue",
+            Resource::WebhookQueue => "webhook-queue",
+            Resource::LambdaBucket => "module2-lambda-deployments",
+        }
+    }
+
+    /// Env vars that override discovery, in order of precedence
+    pub fn env_vars(&self) -> &'static [&'static str] {
+        match self {
+            Resource::TenderProcessingQueue => &["TENDER_PROCESSING_QUEUE_URL"],
+            Resource::PdfProcessingQueue => &["PDF_PROCESSING_QUEUE_URL"],
+            Resource::MlPredictionQueue => &["ML_PREDICTION_QUEUE_URL"],
+            Resource::AiSummaryQueue => &["AI_SUMMARY_QUEUE_URL"],
+            Resource::NotificationQueue => &["SNS_QUEUE_URL"],
+            Resource::WebhookQueue => &["WEBHOOK_QUEUE_URL"],
+            Resource::LambdaBucket => &["LAMBDA_BUCKET", "LAMBDA_BUCKET_NAME"],
+        }
+    }
+
+    pub fn kind(&self) -> ResourceKind {
+        match self {
+            Resource::LambdaBucket => ResourceKind::Bucket,
+            _ => ResourceKind::Queue,
+        }
+    }
+}
+
+/// Resolves and caches resource locations (queue URLs, bucket names)
+pub struct ResourceDiscovery {
+    sqs_client: SqsClient,
+    tagging_client: TaggingClient,
+    prefix: String,
+    environment: Option<String>,
+    cache: Mutex<HashMap<Resource, String>>,
+}
+
+impl ResourceDiscovery {
+    pub fn new(aws_config: &SdkConfig) -> Self {
+        let environment = Environment::from_env();
+        Self {
+            sqs_client: SqsClient::new(aws_config),
+            tagging_client: TaggingClient::new(aws_config),
+            prefix: std::env::var("RESOURCE_PREFIX")
+                .unwrap_or_else(|_| environment.resource_prefix()),
+            // Prod resources predate the environment tag, so prod doesn't require it
+            environment: std::env::var("RESOURCE_ENVIRONMENT")
+                .ok()
+                .filter(|e| !e.trim().is_empty())
+                .or_else(|| (!environment.is_production()).then(|| environment.name().to_string())),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Convenience constructor that loads the default AWS config
+    pub async fn from_env() -> Self {
+        let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .load()
+            .await;
+        Self::new(&aws_config)
+    }
+
+    /// Queue URL or bucket name for a resource; errors if it cannot be found
+    pub async fn resolve(&self, resource: Resource) -> Result<String> {
+        if let Some(cached) = self.cache.lock().unwrap().get(&resource) {
+            return Ok(cached.clone());
+        }
+
+        let location = match env_override(resource) {
+            Some(value) => value,
+            None => self.discover(resource).await?,
+        };
+
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(resource, location.clone());
+        Ok(location)
+    }
+
+    /// Like `resolve`, for resources that are optional in some deployments
+    pub async fn resolve_optional(&self, resource: Resource) -> Option<String> {
+        match self.resolve(resource).await {
+            Ok(location) => Some(location),
+            Err(e) => {
+                info!("{} not available: {}", resource.name(), e);
+                None
+            }
+        }
+    }
+
+    async fn discover(&self, resource: Resource) -> Result<String> {
+        match self.find_by_tag(resource).await {
+            Ok(Some(location)) => {
+                info!("🔎 Resolved {} by tag: {}", resource.name(), location);
+                return Ok(location);
+            }
+            Ok(None) => {}
+            // Missing tag:GetResources permission shouldn't stop the naming convention working
+            Err(e) => warn!("⚠️ Tag lookup failed for {}: {}", resource.name(), e),
+        }
+
+        let name = format!("{}{}", self.prefix, resource.name());
+        let location = match resource.kind() {
+            ResourceKind::Queue => self
+                .sqs_client
+                .get_queue_url()
+                .queue_name(&name)
+                .send()
+                .await
+                .with_context(|| format!("Queue '{}' not found", name))?
+                .queue_url()
+                .map(|url| url.to_string())
+                .with_context(|| format!("No URL returned for queue '{}'", name))?,
+            ResourceKind::Bucket => name,
+        };
+
+        info!(
+            "🔎 Resolved {} by naming convention: {}",
+            resource.name(),
+            location
+        );
+        Ok(location)
+    }
+
+    async fn find_by_tag(&self, resource: Resource) -> Result<Option<String>> {
+        let mut request = self
+            .tagging_client
+            .get_resources()
+            .resource_type_filters(match resource.kind() {
+                ResourceKind::Queue => "sqs",
+                ResourceKind::Bucket => "s3",
+            })
+            .tag_filters(
+                TagFilter::builder()
+                    .key(RESOURCE_TAG)
+                    .values(resource.name())
+                    .build(),
+            );
+        if let Some(environment) = &self.environment {
+            request = request.tag_filters(
+                TagFilter::builder()
+                    .key(ENVIRONMENT_TAG)
+                    .values(environment)
+                    .build(),
+            );
+        }
+
+        let response = request.send().await?;
+        let arns: Vec<&str> = response
+            .resource_tag_mapping_list()
+            .iter()
+            .filter(|mapping| {
+                // Without an environment filter, skip resources that belong to another environment
+                self.environment.is_some()
+                    || mapping
+                        .tags()
+                        .iter()
+                        .find(|tag| tag.key() == ENVIRONMENT_TAG)
+                        .map(|tag| tag.value() == "prod")
+                        .unwrap_or(true)
+            })
+            .filter_map(|mapping| mapping.resource_arn())
+            .collect();
+
+        if arns.len() > 1 {
+            warn!(
+                "⚠️ {} resources tagged {}={}, using the first: {:?}",
+                arns.len(),
+                RESOURCE_TAG,
+                resource.name(),
+                arns
+            );
+        }
+
+        Ok(arns.first().and_then(|arn| match resource.kind() {
+            ResourceKind::Queue => queue_url_from_arn(arn),
+            ResourceKind::Bucket => bucket_from_arn(arn),
+        }))
+    }
+}
+
+fn env_override(resource: Resource) -> Option<String> {
+    resource
+        .env_vars()
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+}
+
+/// `arn:aws:sqs:eu-west-2:123456789012:name` -> `https://sqs.eu-west-2.amazonaws.com/123456789012/name`
+pub fn queue_url_from_arn(arn: &str) -> Option<String> {
+    let parts: Vec<&str> = arn.split(':').collect();
+    match parts.as_slice() {
+        ["arn", _, "sqs", region, account, name] => Some(format!(
+            "https://sqs.{}.amazonaws.com/{}/{}",
+            region, account, name
+        )),
+        _ => None,
+    }
+}
+
+/// `arn:aws:s3:::bucket-name` -> `bucket-name`
+pub fn bucket_from_arn(arn: &str) -> Option<String> {
+    arn.strip_prefix("arn:aws:s3:::")
+        .filter(|bucket| !bucket.is_empty() && !bucket.contains('/'))
+        .map(|bucket| bucket.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_url_from_arn() {
+        assert_eq!(
+            queue_url_from_arn("arn:aws:sqs:eu-west-2:123456789012:ai-summary-queue").as_deref(),
+            Some("https://sqs.eu-west-2.amazonaws.com/123456789012/ai-summary-queue")
+        );
+        assert_eq!(queue_url_from_arn("arn:aws:s3:::bucket"), None);
+    }
+
+    #[test]
+    fn test_bucket_from_arn() {
+        assert_eq!(
+            bucket_from_arn("arn:aws:s3:::module2-lambda-deployments").as_deref(),
+            Some("module2-lambda-deployments")
+        );
+        assert_eq!(bucket_from_arn("arn:aws:s3:::bucket/key"), None);
+        assert_eq!(
+            bucket_from_arn("arn:aws:sqs:eu-west-2:123456789012:queue"),
+            None
+        );
+    }
+}
@@ -0,0 +1,252 @@
+//! Retries with exponential backoff and jitter, for every call that leaves the lambda.
+//!
+//! A `Policy` is built per call site - name, tries, base delay, jitter and which errors
+//! are worth another try - and runs an async operation until it succeeds, fails with an
+//! error the predicate rejects, or runs out of tries:
+//!
+//! ```ignore
+//! let body = Policy::new("pdf_download")
+//!     .max_tries(4)
+//!     .retry_if(|e: &outbound_http::Error| !e.is_blocked())
+//!     .run(|| client.get(url))
+//!     .await?;
+//! ```
+//!
+//! The delay before try `n + 1` is `base_delay * 2^(n - 1)`, capped at `max_delay`, with
+//! up to `jitter` of it taken off at random so retries from concurrent lambdas spread out.
+//!
+//! Every run prints a CloudWatch embedded metric format line with the operation's name,
+//! which CloudWatch Logs turns into the `RetryAttempts` and `RetryFailures` metrics.
+
+use serde_json::{json, Value};
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+const METRIC_NAMESPACE: &str = "TenderPipeline";
+
+#[derive(Clone, Copy)]
+pub struct Policy<E> {
+    operation: &'static str,
+    max_tries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+    retry_if: fn(&E) -> bool,
+}
+
+impl<E> fmt::Debug for Policy<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Policy")
+            .field("operation", &self.operation)
+            .field("max_tries", &self.max_tries)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .finish()
+    }
+}
+
+impl<E: fmt::Display> Policy<E> {
+    /// 3 tries, 200ms base delay, 5s cap, 25% jitter, every error retried.
+    /// `operation` names the call in logs and metrics (e.g. "claude", "sqs_send")
+    pub fn new(operation: &'static str) -> Self {
+        Self {
+            operation,
+            max_tries: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            jitter: 0.25,
+            retry_if: |_| true,
+        }
+    }
+
+    /// For AWS SDK calls. The SDK already retries throttling and 5xx responses itself
+    /// (standard mode, 3 attempts), so this adds a single later try for what it gave up on
+    pub fn aws(operation: &'static str) -> Self {
+        Self::new(operation)
+            .max_tries(2)
+            .base_delay(Duration::from_secs(1))
+    }
+
+    /// Total tries, including the first (at least 1)
+    pub fn max_tries(mut self, tries: u32) -> Self {
+        self.max_tries = tries.max(1);
+        self
+    }
+
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Share of each delay (0 to 1) that may be taken off at random
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Only errors for which `retryable` is true are tried again
+    pub fn retry_if(mut self, retryable: fn(&E) -> bool) -> Self {
+        self.retry_if = retryable;
+        self
+    }
+
+    pub fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    /// Delay after failed try `tries` (from 1), with `random` in [0, 1) picking the jitter
+    pub fn delay(&self, tries: u32, random: f64) -> Duration {
+        let exponent = tries.saturating_sub(1).min(31);
+        let backoff = self
+            .base_delay
+            .saturating_mul(1u32 << exponent)
+            .min(self.max_delay);
+        backoff.mul_f64(1.0 - self.jitter * random)
+    }
+
+    /// Run `operation` until it succeeds or the policy gives up; the last error is returned
+    pub async fn run<T, F, Fut>(&self, mut operation: F) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut tries = 0;
+        loop {
+            tries += 1;
+            let error = match operation().await {
+                Ok(value) => {
+                    self.emit(tries, true);
+                    return Ok(value);
+                }
+                Err(e) => e,
+            };
+
+            if tries >= self.max_tries || !(self.retry_if)(&error) {
+                self.emit(tries, false);
+                return Err(error);
+            }
+
+            let delay = self.delay(tries, fastrand::f64());
+            warn!(
+                "🔁 {} failed (try {}/{}), retrying in {}ms: {}",
+                self.operation,
+                tries,
+                self.max_tries,
+                delay.as_millis(),
+                error
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    fn emit(&self, tries: u32, succeeded: bool) {
+        let environment = environment::Environment::from_env();
+        println!(
+            "{}",
+            metric_line(environment.name(), self.operation, tries, succeeded)
+        );
+    }
+}
+
+/// CloudWatch embedded metric format line for one run: `RetryAttempts` is the number of
+/// tries beyond the first, `RetryFailures` 1 when the run ended in an error
+pub fn metric_line(environment: &str, operation: &str, tries: u32, succeeded: bool) -> Value {
+    json!({
+        "_aws": {
+            "Timestamp": chrono::Utc::now().timestamp_millis(),
+            "CloudWatchMetrics": [{
+                "Namespace": METRIC_NAMESPACE,
+                "Dimensions": [["Environment", "Operation"]],
+                "Metrics": [
+                    { "Name": "RetryAttempts", "Unit": "Count" },
+                    { "Name": "RetryFailures", "Unit": "Count" }
+                ]
+            }]
+        },
+        "Environment": environment,
+        "Operation": operation,
+        "Tries": tries,
+        "RetryAttempts": tries.saturating_sub(1),
+        "RetryFailures": (!succeeded) as u8,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_delay_backs_off_within_bounds() {
+        let policy = Policy::<String>::new("test")
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(1000))
+            .jitter(0.5);
+
+        assert_eq!(policy.delay(1, 0.0), Duration::from_millis(100));
+        assert_eq!(policy.delay(2, 0.0), Duration::from_millis(200));
+        assert_eq!(policy.delay(3, 0.0), Duration::from_millis(400));
+        // Capped, then jittered down by at most half
+        assert_eq!(policy.delay(10, 0.0), Duration::from_millis(1000));
+        assert_eq!(policy.delay(10, 0.5), Duration::from_millis(750));
+        assert_eq!(policy.delay(u32::MAX, 0.999).as_millis(), 500);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_stops_on_success_or_unretryable_error() {
+        let calls = Cell::new(0);
+        let result: Result<u32, String> = Policy::new("test")
+            .max_tries(5)
+            .run(|| {
+                calls.set(calls.get() + 1);
+                let call = calls.get();
+                async move {
+                    if call < 3 {
+                        Err(format!("timeout {}", call))
+                    } else {
+                        Ok(call)
+                    }
+                }
+            })
+            .await;
+        assert_eq!(result, Ok(3));
+
+        calls.set(0);
+        let result: Result<u32, String> = Policy::new("test")
+            .max_tries(5)
+            .retry_if(|e: &String| e.starts_with("timeout"))
+            .run(|| {
+                calls.set(calls.get() + 1);
+                async { Err("not found".to_string()) }
+            })
+            .await;
+        assert_eq!(result, Err("not found".to_string()));
+        assert_eq!(calls.get(), 1);
+
+        calls.set(0);
+        let result: Result<u32, String> = Policy::new("test")
+            .run(|| {
+                calls.set(calls.get() + 1);
+                async { Err("timeout".to_string()) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn test_metric_line() {
+        let line = metric_line("prod", "claude", 3, false);
+        assert_eq!(line["RetryAttempts"], 2);
+        assert_eq!(line["RetryFailures"], 1);
+        assert_eq!(line["Operation"], "claude");
+    }
+}
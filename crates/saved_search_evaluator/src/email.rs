@@ -0,0 +1,88 @@
+use chrono::NaiveDateTime;
+use saved_searches::{Match, SavedSearch};
+
+/// Plain-text email listing each search's new matches
+pub fn render(results: &[(&SavedSearch, Vec<Match>)], max_per_search: i64) -> String {
+    let mut body = String::from("New tenders matching your saved searches:\n");
+
+    for (search, matches) in results {
+        let heading = format!("{} ({} new)", search.name, matches.len());
+        body.push_str(&format!(
+            "\n{}\n{}\n",
+            heading,
+            "-".repeat(heading.chars().count())
+        ));
+
+        for tender in matches {
+            body.push_str(&format!(
+                "- {}\n  {} | deadline {} | value {}\n  {}\n",
+                tender.title,
+                tender.contracting_authority,
+                format_deadline(tender.deadline),
+                format_value(tender.value),
+                portal_link(tender.resource_id)
+            ));
+        }
+        if matches.len() as i64 >= max_per_search {
+            body.push_str(&format!(
+                "  (showing the first {}; narrow the search to see fewer)\n",
+                max_per_search
+            ));
+        }
+    }
+
+    body.push_str(
+        "\nThese matches come from your saved criteria only, not the bid model or AI review.\n",
+    );
+    body
+}
+
+fn format_deadline(deadline: Option<NaiveDateTime>) -> String {
+    deadline
+        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "not set".to_string())
+}
+
+fn format_value(value: Option<f64>) -> String {
+    value
+        .map(|v| format!("€{:.0}", v))
+        .unwrap_or_else(|| "not stated".to_string())
+}
+
+fn portal_link(resource_id: i64) -> String {
+    format!(
+        "https://etenders.gov.ie/epps/opportunity/opportunityDetailAction.do?opportunityId={}",
+        resource_id
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use saved_searches::SearchCriteria;
+
+    #[test]
+    fn test_render_lists_matches_per_search() {
+        let search = SavedSearch {
+            id: 1,
+            owner_email: "jo@example.com".to_string(),
+            name: "Cloud in Cork".to_string(),
+            criteria: SearchCriteria::default(),
+            last_evaluated_at: None,
+            created_at: chrono::Utc::now(),
+        };
+        let matches = vec![Match {
+            resource_id: 42,
+            title: "Cloud hosting".to_string(),
+            contracting_authority: "Cork County Council".to_string(),
+            deadline: None,
+            value: Some(120_000.0),
+        }];
+
+        let body = render(&[(&search, matches)], 50);
+        assert!(body.contains("Cloud in Cork (1 new)"));
+        assert!(body.contains("Cork County Council | deadline not set | value €120000"));
+        assert!(body.contains("opportunityId=42"));
+        assert!(!body.contains("showing the first"));
+    }
+}
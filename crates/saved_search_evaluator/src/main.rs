@@ -0,0 +1,195 @@
+use anyhow::Result;
+use aws_config::BehaviorVersion;
+use aws_sdk_ses::types::{Body, Content, Destination, Message};
+use aws_sdk_ses::Client as SesClient;
+use chrono::Utc;
+use environment::Environment;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use saved_searches::SavedSearch;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use tracing::{error, info, warn, Instrument};
+
+mod email;
+
+/// Matches listed per search in one email
+const DEFAULT_MAX_MATCHES: i64 = 50;
+
+struct Config {
+    database_url: String,
+    from_email: String,
+    max_matches: i64,
+    /// Dev only: every email is redirected here (and dropped without it)
+    test_inbox: Option<String>,
+}
+
+impl Config {
+    fn from_env() -> Result<Self> {
+        let database_url = std::env::var("DATABASE_URL")
+            .map_err(|_| anyhow::anyhow!("DATABASE_URL environment variable not set"))?;
+
+        let from_email = std::env::var("FROM_EMAIL")
+            .unwrap_or_else(|_| "etenders-noreply@robertsweetman.com".to_string());
+
+        let max_matches = std::env::var("SAVED_SEARCH_MAX_MATCHES")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .filter(|max| *max > 0)
+            .unwrap_or(DEFAULT_MAX_MATCHES);
+
+        let test_inbox = std::env::var("TEST_INBOX")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+
+        Ok(Self {
+            database_url,
+            from_email,
+            max_matches,
+            test_inbox,
+        })
+    }
+}
+
+/// Run every active search and email each owner their new matches.
+///
+/// A search's window only moves on once its owner's email has gone out, so a failed
+/// send is retried with the same tenders on the next run
+async fn evaluate(pool: &PgPool, config: &Config) -> Result<Value> {
+    saved_searches::ensure_tables(pool).await?;
+    let evaluated_at = Utc::now();
+    let searches = saved_searches::list_active(pool).await?;
+
+    let mut by_owner: BTreeMap<&str, Vec<&SavedSearch>> = BTreeMap::new();
+    for search in &searches {
+        by_owner
+            .entry(&search.owner_email)
+            .or_default()
+            .push(search);
+    }
+
+    let (mut emails_sent, mut tenders_matched, mut failed_owners) = (0, 0, 0);
+    for (owner, searches) in by_owner {
+        let mut results = Vec::new();
+        for search in searches {
+            let matches =
+                saved_searches::new_matches(pool, search, evaluated_at, config.max_matches).await?;
+            results.push((search, matches));
+        }
+
+        let found: usize = results.iter().map(|(_, matches)| matches.len()).sum();
+        if found > 0 {
+            let with_matches: Vec<_> = results
+                .iter()
+                .filter(|(_, matches)| !matches.is_empty())
+                .map(|(search, matches)| (*search, matches.clone()))
+                .collect();
+            let body = email::render(&with_matches, config.max_matches);
+
+            if let Err(e) = send(config, owner, found, &body).await {
+                error!(
+                    "❌ Failed to email {} their saved search matches: {}",
+                    owner, e
+                );
+                failed_owners += 1;
+                continue;
+            }
+            info!("📧 Sent {} saved search matches to {}", found, owner);
+            emails_sent += 1;
+            tenders_matched += found;
+        }
+
+        for (search, matches) in &results {
+            saved_searches::record_run(pool, search.id, matches, evaluated_at).await?;
+        }
+    }
+
+    Ok(json!({
+        "searches": searches.len(),
+        "emails_sent": emails_sent,
+        "tenders_matched": tenders_matched,
+        "failed_owners": failed_owners,
+    }))
+}
+
+async fn send(config: &Config, owner: &str, found: usize, body: &str) -> Result<()> {
+    let environment = Environment::from_env();
+    let recipient = match environment {
+        Environment::Dev => match &config.test_inbox {
+            Some(inbox) => inbox.clone(),
+            None => {
+                warn!(
+                    "Dev environment without TEST_INBOX - suppressing email to {}",
+                    owner
+                );
+                return Ok(());
+            }
+        },
+        _ => owner.to_string(),
+    };
+
+    let mut subject = format!(
+        "{} new tender{} for your saved searches",
+        found,
+        if found == 1 { "" } else { "s" }
+    );
+    if !environment.is_production() {
+        subject = format!("[{}] {}", environment.name().to_uppercase(), subject);
+    }
+
+    let aws_config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+    SesClient::new(&aws_config)
+        .send_email()
+        .source(&config.from_email)
+        .destination(Destination::builder().to_addresses(recipient).build())
+        .message(
+            Message::builder()
+                .subject(Content::builder().data(subject).charset("UTF-8").build()?)
+                .body(
+                    Body::builder()
+                        .text(Content::builder().data(body).charset("UTF-8").build()?)
+                        .build(),
+                )
+                .build(),
+        )
+        .send()
+        .await?;
+    Ok(())
+}
+
+/// Triggered on a schedule (EventBridge), after the scraper's runs; the event body is not used
+async fn function_handler(_event: LambdaEvent<Value>) -> Result<Value, Error> {
+    info!("=== SAVED SEARCH EVALUATION STARTED ===");
+
+    let config = Config::from_env().map_err(|e| {
+        error!("Failed to load configuration: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+
+    let pool = db::connect(&config.database_url, 2)
+        .await
+        .map_err(|e| Error::from(format!("Failed to connect to database: {}", e).as_str()))?;
+
+    let summary = evaluate(&pool, &config)
+        .await
+        .map_err(|e| Error::from(format!("Failed to evaluate saved searches: {}", e).as_str()))?;
+
+    info!("=== SAVED SEARCH EVALUATION COMPLETED === {}", summary);
+    Ok(summary)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    let environment = Environment::from_env();
+    run(service_fn(move |event| {
+        function_handler(event).instrument(environment.span())
+    }))
+    .await
+}
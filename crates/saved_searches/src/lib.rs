@@ -0,0 +1,390 @@
+//! Saved tender searches, kept in `saved_searches`.
+//!
+//! Users save filters through tender_api (`/saved-searches`); saved_search_evaluator runs
+//! them on a schedule against tenders loaded since the previous run and emails each owner
+//! their new matches. This is independent of the ML/AI path: a tender matches on its
+//! portal fields alone, whatever the model or Claude made of it.
+//!
+//! Within a criterion any value matches (keyword A or keyword B); every criterion that is
+//! set must match. `saved_search_matches` records what was sent, so a tender is only ever
+//! reported once per search.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use environment::Environment;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
+
+/// Saved searches one owner can have active
+pub const MAX_SEARCHES_PER_OWNER: i64 = 20;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchCriteria {
+    /// Matched against the title and description
+    pub keywords: Vec<String>,
+    /// Matched against the contracting authority name
+    pub contracting_authorities: Vec<String>,
+    pub min_value: Option<f64>,
+    pub max_value: Option<f64>,
+    /// Place names (e.g. "Cork"); the portal has no region field, so these are matched
+    /// against the contracting authority and description
+    pub regions: Vec<String>,
+}
+
+impl SearchCriteria {
+    /// Trim the terms and drop empty ones, rejecting a search that would match everything
+    pub fn normalized(self) -> Result<Self> {
+        let clean = |terms: Vec<String>| -> Vec<String> {
+            terms
+                .into_iter()
+                .map(|term| term.trim().to_string())
+                .filter(|term| !term.is_empty())
+                .collect()
+        };
+        let criteria = Self {
+            keywords: clean(self.keywords),
+            contracting_authorities: clean(self.contracting_authorities),
+            regions: clean(self.regions),
+            ..self
+        };
+
+        if let (Some(min), Some(max)) = (criteria.min_value, criteria.max_value) {
+            if min > max {
+                bail!("min_value {} is greater than max_value {}", min, max);
+            }
+        }
+        if criteria == Self::default() {
+            bail!("A saved search needs at least one criterion");
+        }
+        Ok(criteria)
+    }
+
+    /// AND conditions on `tender_records t` for every criterion that is set
+    pub fn push_conditions(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        push_any(query, &["t.title", "t.info"], &self.keywords);
+        push_any(query, &["t.ca"], &self.contracting_authorities);
+        push_any(query, &["t.ca", "t.info"], &self.regions);
+        if let Some(min) = self.min_value {
+            query.push(" AND t.value >= ").push_bind(min);
+        }
+        if let Some(max) = self.max_value {
+            query.push(" AND t.value <= ").push_bind(max);
+        }
+    }
+}
+
+/// `AND (col1 ILIKE term1 OR col2 ILIKE term1 OR col1 ILIKE term2 ...)`
+fn push_any(query: &mut QueryBuilder<'_, Postgres>, columns: &[&str], terms: &[String]) {
+    if terms.is_empty() {
+        return;
+    }
+    query.push(" AND (");
+    let mut first = true;
+    for term in terms {
+        for column in columns {
+            if !first {
+                query.push(" OR ");
+            }
+            first = false;
+            query
+                .push(*column)
+                .push(" ILIKE ")
+                .push_bind(format!("%{}%", term));
+        }
+    }
+    query.push(")");
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedSearch {
+    pub id: i64,
+    pub owner_email: String,
+    pub name: String,
+    pub criteria: SearchCriteria,
+    pub last_evaluated_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SavedSearch {
+    fn from_row(row: &PgRow) -> Result<Self> {
+        Ok(Self {
+            id: row.get("id"),
+            owner_email: row.get("owner_email"),
+            name: row.get("name"),
+            criteria: serde_json::from_value(row.get("criteria"))?,
+            last_evaluated_at: row.get("last_evaluated_at"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+/// A tender a search matched, with what the owner's email shows
+#[derive(Debug, Clone, Serialize)]
+pub struct Match {
+    pub resource_id: i64,
+    pub title: String,
+    pub contracting_authority: String,
+    pub deadline: Option<chrono::NaiveDateTime>,
+    pub value: Option<f64>,
+}
+
+pub async fn ensure_tables(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "saved_searches", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS saved_searches (
+                id BIGSERIAL PRIMARY KEY,
+                owner_email TEXT NOT NULL,
+                name TEXT NOT NULL,
+                criteria JSONB NOT NULL,
+                active BOOLEAN NOT NULL DEFAULT TRUE,
+                last_evaluated_at TIMESTAMPTZ,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS saved_search_matches (
+                search_id BIGINT NOT NULL REFERENCES saved_searches (id) ON DELETE CASCADE,
+                resource_id BIGINT NOT NULL,
+                notified_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (search_id, resource_id)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "saved_searches").await?;
+        Environment::ensure_environment_column(pool, "saved_search_matches").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Save a search; it only sees tenders loaded from now on
+pub async fn create(
+    pool: &PgPool,
+    owner_email: &str,
+    name: &str,
+    criteria: SearchCriteria,
+) -> Result<SavedSearch> {
+    let owner_email = owner_email.trim().to_lowercase();
+    if !owner_email.contains('@') {
+        bail!("Invalid owner email '{}'", owner_email);
+    }
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("A saved search needs a name");
+    }
+    let criteria = criteria.normalized()?;
+
+    let existing: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM saved_searches WHERE owner_email = $1 AND active")
+            .bind(&owner_email)
+            .fetch_one(pool)
+            .await?;
+    if existing >= MAX_SEARCHES_PER_OWNER {
+        bail!(
+            "{} already has {} saved searches",
+            owner_email,
+            MAX_SEARCHES_PER_OWNER
+        );
+    }
+
+    let row = sqlx::query(
+        r#"
+        INSERT INTO saved_searches (owner_email, name, criteria)
+        VALUES ($1, $2, $3)
+        RETURNING id, owner_email, name, criteria, last_evaluated_at, created_at
+        "#,
+    )
+    .bind(&owner_email)
+    .bind(name)
+    .bind(serde_json::to_value(&criteria)?)
+    .fetch_one(pool)
+    .await?;
+
+    SavedSearch::from_row(&row)
+}
+
+/// An owner's active searches, oldest first
+pub async fn list_for_owner(pool: &PgPool, owner_email: &str) -> Result<Vec<SavedSearch>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, owner_email, name, criteria, last_evaluated_at, created_at
+        FROM saved_searches
+        WHERE owner_email = $1 AND active
+        ORDER BY id
+        "#,
+    )
+    .bind(owner_email.trim().to_lowercase())
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(SavedSearch::from_row).collect()
+}
+
+/// Stop evaluating a search; false if the owner has no such active search
+pub async fn deactivate(pool: &PgPool, id: i64, owner_email: &str) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE saved_searches SET active = FALSE WHERE id = $1 AND owner_email = $2 AND active",
+    )
+    .bind(id)
+    .bind(owner_email.trim().to_lowercase())
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Every active search, for the evaluator
+pub async fn list_active(pool: &PgPool) -> Result<Vec<SavedSearch>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, owner_email, name, criteria, last_evaluated_at, created_at
+        FROM saved_searches
+        WHERE active
+        ORDER BY owner_email, id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter().map(SavedSearch::from_row).collect()
+}
+
+/// Tenders loaded after the search's last run (up to `until`) that match it and haven't
+/// been reported for it. Canary tenders are never matched
+pub async fn new_matches(
+    pool: &PgPool,
+    search: &SavedSearch,
+    until: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<Match>> {
+    let mut query = QueryBuilder::<Postgres>::new(
+        r#"SELECT t.resource_id, t.title, t.ca, t.deadline, t.value::FLOAT8 AS value
+        FROM tender_records t
+        WHERE t.resource_id > 0 AND t.created_at > "#,
+    );
+    query
+        .push_bind(search.last_evaluated_at.unwrap_or(search.created_at))
+        .push(" AND t.created_at <= ")
+        .push_bind(until)
+        .push(" AND NOT EXISTS (SELECT 1 FROM saved_search_matches m WHERE m.search_id = ")
+        .push_bind(search.id)
+        .push(" AND m.resource_id = t.resource_id)");
+    search.criteria.push_conditions(&mut query);
+    query
+        .push(" ORDER BY t.deadline ASC NULLS LAST, t.resource_id LIMIT ")
+        .push_bind(limit);
+
+    let rows = query.build().fetch_all(pool).await?;
+    Ok(rows
+        .iter()
+        .map(|row| Match {
+            resource_id: row.get("resource_id"),
+            title: row.get("title"),
+            contracting_authority: row.get("ca"),
+            deadline: row.get("deadline"),
+            value: row.get("value"),
+        })
+        .collect())
+}
+
+/// Record the matches sent for a search and move its window up to `evaluated_at`
+pub async fn record_run(
+    pool: &PgPool,
+    search_id: i64,
+    matches: &[Match],
+    evaluated_at: DateTime<Utc>,
+) -> Result<()> {
+    let resource_ids: Vec<i64> = matches.iter().map(|m| m.resource_id).collect();
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO saved_search_matches (search_id, resource_id)
+        SELECT $1, UNNEST($2::BIGINT[])
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(search_id)
+    .bind(&resource_ids)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE saved_searches SET last_evaluated_at = $2 WHERE id = $1")
+        .bind(search_id)
+        .bind(evaluated_at)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalized_rejects_empty_and_inverted_searches() {
+        let criteria = SearchCriteria {
+            keywords: vec!["  cloud ".to_string(), " ".to_string()],
+            ..Default::default()
+        }
+        .normalized()
+        .unwrap();
+        assert_eq!(criteria.keywords, vec!["cloud"]);
+
+        let blank = SearchCriteria {
+            regions: vec!["".to_string()],
+            ..Default::default()
+        };
+        assert!(blank.normalized().is_err());
+
+        let inverted = SearchCriteria {
+            min_value: Some(500_000.0),
+            max_value: Some(100_000.0),
+            ..Default::default()
+        };
+        assert!(inverted.normalized().is_err());
+    }
+
+    #[test]
+    fn test_conditions_or_within_and_across_criteria() {
+        let criteria = SearchCriteria {
+            keywords: vec!["cloud".to_string(), "hosting".to_string()],
+            regions: vec!["Cork".to_string()],
+            min_value: Some(100_000.0),
+            ..Default::default()
+        };
+        let mut query = QueryBuilder::<Postgres>::new("SELECT 1 FROM tender_records t WHERE TRUE");
+        criteria.push_conditions(&mut query);
+
+        assert_eq!(
+            query.sql(),
+            "SELECT 1 FROM tender_records t WHERE TRUE \
+             AND (t.title ILIKE $1 OR t.info ILIKE $2 OR t.title ILIKE $3 OR t.info ILIKE $4) \
+             AND (t.ca ILIKE $5 OR t.info ILIKE $6) \
+             AND t.value >= $7"
+        );
+    }
+
+    #[test]
+    fn test_criteria_json_defaults_missing_fields() {
+        let criteria: SearchCriteria =
+            serde_json::from_str(r#"{"keywords": ["software"], "max_value": 250000}"#).unwrap();
+
+        assert_eq!(criteria.keywords, vec!["software"]);
+        assert!(criteria.regions.is_empty());
+        assert_eq!(criteria.max_value, Some(250_000.0));
+    }
+}
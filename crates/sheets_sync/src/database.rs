@@ -0,0 +1,56 @@
+use crate::types::{Config, Opportunity};
+use anyhow::Result;
+use sqlx::{Pool, Postgres, Row};
+use tracing::info;
+
+/// Read-only database access for the sheet export
+pub struct Database {
+    pool: Pool<Postgres>,
+}
+
+impl Database {
+    /// Create new database connection
+    pub async fn new(config: &Config) -> Result<Self> {
+        let pool = db::connect_read_only(&config.database_url, 2).await?;
+
+        info!("✅ Database connection established");
+        Ok(Self { pool })
+    }
+
+    /// The tenant's BID-recommended tenders whose deadline has not passed (same rule as the notification decision)
+    pub async fn open_bid_opportunities(&self, tenant_id: &str) -> Result<Vec<Opportunity>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                t.resource_id,
+                t.title,
+                t.ca,
+                t.value::TEXT AS value,
+                t.deadline,
+                s.recommendation
+            FROM tender_records t
+            JOIN ai_summaries s ON s.resource_id = t.resource_id AND s.tenant_id = $1
+            WHERE s.recommendation ILIKE '%bid%'
+              AND s.recommendation NOT ILIKE '%no bid%'
+              AND (t.deadline IS NULL OR t.deadline > NOW())
+              AND t.resource_id > 0 -- pipeline_canary tenders use negative ids
+            ORDER BY t.deadline ASC NULLS LAST, t.resource_id
+            "#,
+        )
+        .bind(tenant_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Opportunity {
+                resource_id: row.get("resource_id"),
+                title: row.get("title"),
+                contracting_authority: row.get("ca"),
+                value: row.get("value"),
+                deadline: row.get("deadline"),
+                recommendation: row.get("recommendation"),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,83 @@
+use environment::Environment;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use serde_json::Value;
+use tracing::{error, info, Instrument};
+
+mod database;
+mod sheets;
+mod types;
+
+use database::Database;
+use sheets::{plan_sync, SheetsClient};
+use types::Config;
+
+/// Triggered on a schedule (EventBridge); the event body is not used
+async fn function_handler(_event: LambdaEvent<Value>) -> Result<String, Error> {
+    info!("=== SHEETS SYNC STARTED ===");
+
+    let config = Config::from_env().map_err(|e| {
+        error!("Failed to load configuration: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+
+    let database = Database::new(&config).await.map_err(|e| {
+        error!("Failed to initialize database: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+
+    let opportunities = database
+        .open_bid_opportunities(&config.tenant_id)
+        .await
+        .map_err(|e| Error::from(format!("Failed to load opportunities: {}", e).as_str()))?;
+    info!("📋 {} open BID-recommended tenders", opportunities.len());
+
+    let sheets = SheetsClient::connect(
+        &config.service_account,
+        &config.spreadsheet_id,
+        &config.sheet_name,
+    )
+    .await
+    .map_err(|e| Error::from(format!("Failed to authenticate with Google: {}", e).as_str()))?;
+
+    let existing_ids = sheets
+        .existing_ids()
+        .await
+        .map_err(|e| Error::from(format!("Failed to read sheet: {}", e).as_str()))?;
+
+    let synced_at = chrono::Utc::now().format("%Y-%m-%d %H:%M UTC").to_string();
+    let plan = plan_sync(&existing_ids, &opportunities, &synced_at);
+    info!(
+        "📝 Sheet plan: {} updated, {} new, {} closed",
+        plan.updates.len(),
+        plan.appends.len(),
+        plan.closed.len()
+    );
+
+    sheets
+        .apply(&plan)
+        .await
+        .map_err(|e| Error::from(format!("Failed to update sheet: {}", e).as_str()))?;
+
+    info!("=== SHEETS SYNC COMPLETED ===");
+    Ok(format!(
+        "Synced {} opportunities ({} new, {} closed)",
+        opportunities.len(),
+        plan.appends.len(),
+        plan.closed.len()
+    ))
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    let environment = Environment::from_env();
+    run(service_fn(move |event| {
+        function_handler(event).instrument(environment.span())
+    }))
+    .await
+}
@@ -0,0 +1,285 @@
+use crate::types::{Opportunity, ServiceAccountKey, SHEET_HEADERS};
+use anyhow::{Context, Result};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use reqwest::{Client, Url};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tracing::info;
+
+const SHEETS_API: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+const SHEETS_SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets";
+
+/// Last column written by the sync (column I for the nine headers)
+const LAST_COLUMN: char = (b'A' + SHEET_HEADERS.len() as u8 - 1) as char;
+
+/// Column letter of "Status" in `SHEET_HEADERS`
+const STATUS_COLUMN: char = 'F';
+
+#[derive(Debug, Serialize)]
+struct JwtClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+/// Changes needed to bring the sheet in line with the database
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncPlan {
+    /// (sheet row number, cells) for tenders already in the sheet
+    pub updates: Vec<(usize, Vec<String>)>,
+    /// Rows for tenders not yet in the sheet
+    pub appends: Vec<Vec<String>>,
+    /// Sheet row numbers whose tender is no longer open
+    pub closed: Vec<usize>,
+}
+
+/// Work out which rows to update, append or mark closed
+///
+/// `existing_ids` is column A from row 2 downwards (row 1 is the header), so the
+/// sheet row number of `existing_ids[i]` is `i + 2`. Rows the sync did not write
+/// (blank or non-numeric ids) are left alone.
+pub fn plan_sync(
+    existing_ids: &[String],
+    opportunities: &[Opportunity],
+    synced_at: &str,
+) -> SyncPlan {
+    let existing_rows: HashMap<&str, usize> = existing_ids
+        .iter()
+        .enumerate()
+        .filter(|(_, id)| !id.trim().is_empty())
+        .map(|(i, id)| (id.trim(), i + 2))
+        .collect();
+
+    let mut plan = SyncPlan::default();
+    let mut live_ids = HashSet::new();
+
+    for opportunity in opportunities {
+        let id = opportunity.resource_id.to_string();
+        let row = opportunity.to_row(synced_at);
+        match existing_rows.get(id.as_str()) {
+            Some(&row_number) => plan.updates.push((row_number, row)),
+            None => plan.appends.push(row),
+        }
+        live_ids.insert(id);
+    }
+
+    for (i, id) in existing_ids.iter().enumerate() {
+        let id = id.trim();
+        if id.parse::<i64>().is_ok() && !live_ids.contains(id) {
+            plan.closed.push(i + 2);
+        }
+    }
+
+    plan
+}
+
+/// Minimal Google Sheets v4 client authenticated with a service account
+pub struct SheetsClient {
+    http_client: Client,
+    access_token: String,
+    spreadsheet_id: String,
+    sheet_name: String,
+}
+
+impl SheetsClient {
+    /// Exchange a signed service-account JWT for an access token
+    pub async fn connect(
+        service_account: &ServiceAccountKey,
+        spreadsheet_id: &str,
+        sheet_name: &str,
+    ) -> Result<Self> {
+        let http_client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+
+        let now = chrono::Utc::now().timestamp();
+        let claims = JwtClaims {
+            iss: &service_account.client_email,
+            scope: SHEETS_SCOPE,
+            aud: &service_account.token_uri,
+            iat: now,
+            exp: now + 3600,
+        };
+        let key = EncodingKey::from_rsa_pem(service_account.private_key.as_bytes())
+            .context("Service account private_key is not a valid RSA PEM")?;
+        let assertion = encode(&Header::new(Algorithm::RS256), &claims, &key)?;
+
+        let response: Value = http_client
+            .post(&service_account.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let access_token = response
+            .get("access_token")
+            .and_then(|v| v.as_str())
+            .context("Token response missing access_token")?
+            .to_string();
+
+        info!(
+            "🔑 Authenticated with Google as {}",
+            service_account.client_email
+        );
+
+        Ok(Self {
+            http_client,
+            access_token,
+            spreadsheet_id: spreadsheet_id.to_string(),
+            sheet_name: sheet_name.to_string(),
+        })
+    }
+
+    /// Sheet range in A1 notation, quoting the sheet name
+    fn range(&self, cells: &str) -> String {
+        format!("'{}'!{}", self.sheet_name.replace('\'', "''"), cells)
+    }
+
+    /// `{SHEETS_API}/{spreadsheet_id}/values/{range}{suffix}` with the range percent-encoded
+    fn values_url(&self, range: &str, suffix: &str) -> Result<Url> {
+        let mut url = Url::parse(SHEETS_API)?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Invalid Sheets API base URL"))?
+            .push(&self.spreadsheet_id)
+            .push("values")
+            .push(&format!("{}{}", range, suffix));
+        Ok(url)
+    }
+
+    /// Resource ids currently in column A (excluding the header row)
+    pub async fn existing_ids(&self) -> Result<Vec<String>> {
+        let url = self.values_url(&self.range("A2:A"), "")?;
+        let response: Value = self
+            .http_client
+            .get(url)
+            .bearer_auth(&self.access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(response
+            .get("values")
+            .and_then(|v| v.as_array())
+            .map(|rows| {
+                rows.iter()
+                    .map(|row| {
+                        row.get(0)
+                            .and_then(|cell| cell.as_str())
+                            .unwrap_or("")
+                            .to_string()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Apply the header row, in-place updates and closed markers in one batch, then append new rows
+    ///
+    /// Values are written RAW so tender titles starting with "=" are never evaluated as formulas.
+    pub async fn apply(&self, plan: &SyncPlan) -> Result<()> {
+        let mut data = vec![json!({
+            "range": self.range(&format!("A1:{}1", LAST_COLUMN)),
+            "values": [SHEET_HEADERS],
+        })];
+        for (row_number, cells) in &plan.updates {
+            data.push(json!({
+                "range": self.range(&format!("A{0}:{1}{0}", row_number, LAST_COLUMN)),
+                "values": [cells],
+            }));
+        }
+        for row_number in &plan.closed {
+            data.push(json!({
+                "range": self.range(&format!("{0}{1}:{0}{1}", STATUS_COLUMN, row_number)),
+                "values": [["Closed"]],
+            }));
+        }
+
+        let mut batch_url = Url::parse(SHEETS_API)?;
+        batch_url
+            .path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Invalid Sheets API base URL"))?
+            .push(&self.spreadsheet_id)
+            .push("values:batchUpdate");
+
+        self.http_client
+            .post(batch_url)
+            .bearer_auth(&self.access_token)
+            .json(&json!({ "valueInputOption": "RAW", "data": data }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        if !plan.appends.is_empty() {
+            let mut url = self.values_url(&self.range("A:A"), ":append")?;
+            url.query_pairs_mut()
+                .append_pair("valueInputOption", "RAW")
+                .append_pair("insertDataOption", "INSERT_ROWS");
+
+            self.http_client
+                .post(url)
+                .bearer_auth(&self.access_token)
+                .json(&json!({ "values": plan.appends }))
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opportunity(resource_id: i64) -> Opportunity {
+        Opportunity {
+            resource_id,
+            title: format!("Tender {}", resource_id),
+            contracting_authority: "HSE".to_string(),
+            value: Some("100000.00".to_string()),
+            deadline: None,
+            recommendation: "BID".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_plan_updates_existing_and_appends_new() {
+        let existing = vec!["100".to_string(), "200".to_string()];
+        let plan = plan_sync(&existing, &[opportunity(200), opportunity(300)], "now");
+
+        assert_eq!(plan.updates.len(), 1);
+        assert_eq!(plan.updates[0].0, 3);
+        assert_eq!(plan.appends.len(), 1);
+        assert_eq!(plan.appends[0][0], "300");
+        assert_eq!(plan.closed, vec![2]);
+    }
+
+    #[test]
+    fn test_plan_ignores_rows_not_written_by_sync() {
+        let existing = vec!["".to_string(), "Notes".to_string(), "100".to_string()];
+        let plan = plan_sync(&existing, &[opportunity(100)], "now");
+
+        assert_eq!(plan.updates, vec![(4, opportunity(100).to_row("now"))]);
+        assert!(plan.appends.is_empty());
+        assert!(plan.closed.is_empty());
+    }
+
+    #[test]
+    fn test_last_column_matches_headers() {
+        assert_eq!(LAST_COLUMN, 'I');
+        assert_eq!(
+            SHEET_HEADERS[(STATUS_COLUMN as u8 - b'A') as usize],
+            "Status"
+        );
+    }
+}
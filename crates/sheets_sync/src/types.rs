@@ -0,0 +1,105 @@
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+
+/// Header row written to the sheet; sync only touches these columns so the sales
+/// team can keep their own notes in the columns to the right
+pub const SHEET_HEADERS: [&str; 9] = [
+    "Resource ID",
+    "Title",
+    "Contracting Authority",
+    "Estimated Value",
+    "Deadline",
+    "Status",
+    "Recommendation",
+    "Portal Link",
+    "Last Synced",
+];
+
+/// Open BID-recommended tender as loaded from the database
+#[derive(Debug, Clone)]
+pub struct Opportunity {
+    pub resource_id: i64,
+    pub title: String,
+    pub contracting_authority: String,
+    pub value: Option<String>,
+    pub deadline: Option<NaiveDateTime>,
+    pub recommendation: String,
+}
+
+impl Opportunity {
+    /// Cell values in `SHEET_HEADERS` order
+    pub fn to_row(&self, synced_at: &str) -> Vec<String> {
+        vec![
+            self.resource_id.to_string(),
+            self.title.clone(),
+            self.contracting_authority.clone(),
+            self.value.clone().unwrap_or_default(),
+            self.deadline
+                .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            "Open".to_string(),
+            self.recommendation.clone(),
+            format!(
+                "https://etenders.gov.ie/epps/opportunity/opportunityDetailAction.do?opportunityId={}",
+                self.resource_id
+            ),
+            synced_at.to_string(),
+        ]
+    }
+}
+
+/// Google service-account key file (the JSON downloaded from the Cloud console)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServiceAccountKey {
+    pub client_email: String,
+    pub private_key: String,
+    #[serde(default = "default_token_uri")]
+    pub token_uri: String,
+}
+
+fn default_token_uri() -> String {
+    "https://oauth2.googleapis.com/token".to_string()
+}
+
+/// Configuration from environment
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database_url: String,
+    pub service_account: ServiceAccountKey,
+    pub spreadsheet_id: String,
+    pub sheet_name: String,
+    pub tenant_id: String,
+}
+
+impl Config {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let database_url = std::env::var("DATABASE_URL")
+            .map_err(|_| anyhow::anyhow!("DATABASE_URL environment variable not set"))?;
+
+        let service_account_json = std::env::var("GOOGLE_SERVICE_ACCOUNT_JSON").map_err(|_| {
+            anyhow::anyhow!("GOOGLE_SERVICE_ACCOUNT_JSON environment variable not set")
+        })?;
+        let service_account: ServiceAccountKey = serde_json::from_str(&service_account_json)
+            .map_err(|e| anyhow::anyhow!("Invalid GOOGLE_SERVICE_ACCOUNT_JSON: {}", e))?;
+
+        let spreadsheet_id = std::env::var("SHEETS_SPREADSHEET_ID")
+            .map_err(|_| anyhow::anyhow!("SHEETS_SPREADSHEET_ID environment variable not set"))?;
+
+        let sheet_name =
+            std::env::var("SHEETS_SHEET_NAME").unwrap_or_else(|_| "Opportunities".to_string());
+
+        // Each tenant syncs to its own spreadsheet/sheet
+        let tenant_id = std::env::var("TENANT_ID")
+            .ok()
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or_else(|| "default".to_string());
+
+        Ok(Self {
+            database_url,
+            service_account,
+            spreadsheet_id,
+            sheet_name,
+            tenant_id,
+        })
+    }
+}
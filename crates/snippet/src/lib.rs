@@ -0,0 +1,426 @@
+//! Short snippets of an AI summary: the email preview text (the line inboxes show after
+//! the subject) and the title of chat cards built from webhook events.
+//!
+//! A `Summarizer` picks the text a snippet starts from - the first sentences of the
+//! summary, or the one-line summary Claude is asked for alongside it - and `truncate` cuts
+//! that down to the space available. SNIPPET_SUMMARIZER chooses the summarizer:
+//! `one_liner` (the default) uses Claude's one-liner and falls back to the first
+//! sentences for summaries without one (fallback summaries, older rows); `sentences`
+//! always takes the first SNIPPET_SENTENCES (default 2) sentences.
+//!
+//! Lengths are counted in characters, not bytes, and a cut never separates a character
+//! from the accents, joiners or modifiers that follow it, so Irish fadas written as
+//! combining marks, emoji sequences and flags survive truncation intact.
+
+use tracing::warn;
+
+/// Inbox clients show roughly this much preview text
+pub const PREVIEW_CHARS: usize = 140;
+/// Chat card titles wrap past this
+pub const TITLE_CHARS: usize = 80;
+
+const DEFAULT_SENTENCES: usize = 2;
+const ELLIPSIS: char = '…';
+
+/// Abbreviations whose full stop doesn't end a sentence (compared lowercased)
+const ABBREVIATIONS: &[&str] = &[
+    "e.g", "i.e", "etc", "approx", "incl", "excl", "no", "nos", "ltd", "co", "dr", "mr", "mrs",
+    "ms", "st", "vs", "ref", "fig",
+];
+
+/// Picks the text a snippet is cut from
+pub trait Summarizer: Send + Sync {
+    /// `one_liner` is the model's one-line summary, when it gave one
+    fn summarize(&self, summary: &str, one_liner: Option<&str>) -> String;
+
+    /// For the config diagnostic
+    fn describe(&self) -> String;
+}
+
+/// The first `n` sentences of the summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FirstSentences(pub usize);
+
+impl Summarizer for FirstSentences {
+    fn summarize(&self, summary: &str, _one_liner: Option<&str>) -> String {
+        let summary = collapse_whitespace(summary);
+        sentences(&summary)
+            .into_iter()
+            .take(self.0.max(1))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn describe(&self) -> String {
+        format!("first {} sentence(s)", self.0.max(1))
+    }
+}
+
+/// The model's one-liner, or `fallback` when there isn't one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OneLiner {
+    pub fallback: FirstSentences,
+}
+
+impl Summarizer for OneLiner {
+    fn summarize(&self, summary: &str, one_liner: Option<&str>) -> String {
+        match one_liner.map(collapse_whitespace) {
+            Some(one_liner) if !one_liner.is_empty() => one_liner,
+            _ => self.fallback.summarize(summary, None),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("model one-liner, else {}", self.fallback.describe())
+    }
+}
+
+/// Makes preview and title snippets with the configured summarizer
+pub struct Snippets {
+    summarizer: Box<dyn Summarizer>,
+}
+
+impl Snippets {
+    pub fn new(summarizer: impl Summarizer + 'static) -> Self {
+        Self {
+            summarizer: Box::new(summarizer),
+        }
+    }
+
+    /// SNIPPET_SUMMARIZER (`one_liner` or `sentences`) and SNIPPET_SENTENCES
+    pub fn from_env() -> Self {
+        let count = match std::env::var("SNIPPET_SENTENCES") {
+            Ok(value) => match value.trim().parse() {
+                Ok(count) if count > 0 => count,
+                _ => {
+                    warn!(
+                        "⚠️ Ignoring SNIPPET_SENTENCES='{}' - not a positive whole number",
+                        value
+                    );
+                    DEFAULT_SENTENCES
+                }
+            },
+            Err(_) => DEFAULT_SENTENCES,
+        };
+        let sentences = FirstSentences(count);
+        match std::env::var("SNIPPET_SUMMARIZER")
+            .as_deref()
+            .map(str::trim)
+        {
+            Ok("sentences") => Self::new(sentences),
+            Ok("one_liner") | Ok("") | Err(_) => Self::new(OneLiner {
+                fallback: sentences,
+            }),
+            Ok(other) => {
+                warn!("⚠️ Unknown SNIPPET_SUMMARIZER '{}', using one_liner", other);
+                Self::new(OneLiner {
+                    fallback: sentences,
+                })
+            }
+        }
+    }
+
+    /// Email preview text, at most `PREVIEW_CHARS` characters
+    pub fn preview(&self, summary: &str, one_liner: Option<&str>) -> String {
+        truncate(
+            &self.summarizer.summarize(summary, one_liner),
+            PREVIEW_CHARS,
+        )
+    }
+
+    /// Chat card title, at most `TITLE_CHARS` characters
+    pub fn title(&self, summary: &str, one_liner: Option<&str>) -> String {
+        truncate(&self.summarizer.summarize(summary, one_liner), TITLE_CHARS)
+    }
+
+    /// For the config diagnostic
+    pub fn describe(&self) -> String {
+        self.summarizer.describe()
+    }
+}
+
+impl Default for Snippets {
+    fn default() -> Self {
+        Self::new(OneLiner {
+            fallback: FirstSentences(DEFAULT_SENTENCES),
+        })
+    }
+}
+
+/// Split text into sentences. A sentence ends at `.`, `!`, `?` or `…` (plus any closing
+/// quotes or brackets) followed by a space and a word that doesn't start lowercase, or at
+/// CJK full-width punctuation; a full stop after a known abbreviation or a single-letter
+/// initial doesn't end one. Decimal points and URLs have no space after them.
+pub fn sentences(text: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        let full_width = matches!(c, '。' | '！' | '？');
+        if !full_width && !matches!(c, '.' | '!' | '?' | '…') {
+            continue;
+        }
+        let mut end = index + c.len_utf8();
+        while let Some(&(next_index, next)) = chars.peek() {
+            if !matches!(
+                next,
+                '.' | '!' | '?' | '…' | '"' | '\'' | ')' | ']' | '”' | '’' | '»'
+            ) {
+                break;
+            }
+            end = next_index + next.len_utf8();
+            chars.next();
+        }
+
+        let rest = &text[end..];
+        let boundary = full_width
+            || match rest.chars().next() {
+                None => true,
+                Some(next) if next.is_whitespace() => {
+                    let continues = rest.trim_start().starts_with(char::is_lowercase);
+                    !(continues || c == '.' && is_abbreviation(&text[start..index]))
+                }
+                Some(_) => false,
+            };
+        if boundary {
+            let sentence = text[start..end].trim();
+            if !sentence.is_empty() {
+                found.push(sentence);
+            }
+            start = end;
+        }
+    }
+
+    let tail = text[start..].trim();
+    if !tail.is_empty() {
+        found.push(tail);
+    }
+    found
+}
+
+/// Whether the word a full stop follows is an abbreviation or an initial
+fn is_abbreviation(before: &str) -> bool {
+    let word = before
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or("")
+        .trim_start_matches(|c: char| !c.is_alphanumeric());
+    let mut letters = word.chars();
+    if let (Some(first), None) = (letters.next(), letters.next()) {
+        return first.is_uppercase();
+    }
+    ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+}
+
+/// Cut text to at most `max_chars` characters, ending with "…" when anything was dropped.
+/// The cut goes back to the last word break when there is one in the second half of what
+/// fits, and never splits a character from the marks that combine with it.
+pub fn truncate(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    if max_chars == 0 {
+        return String::new();
+    }
+
+    let keep = max_chars - 1;
+    let mut cut = text
+        .char_indices()
+        .nth(keep)
+        .map_or(text.len(), |(index, _)| index);
+    while cut > 0 && continues_cluster(text, cut) {
+        cut = text[..cut]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(index, _)| index);
+    }
+
+    let at_break = text[cut..].starts_with(char::is_whitespace);
+    if !at_break {
+        if let Some(space) = text[..cut].rfind(char::is_whitespace) {
+            if text[..space].chars().count() >= keep / 2 {
+                cut = space;
+            }
+        }
+    }
+
+    let kept = text[..cut]
+        .trim_end()
+        .trim_end_matches([',', ';', ':', '-', '–', '—', '('])
+        .trim_end();
+    format!("{}{}", kept, ELLIPSIS)
+}
+
+/// Whether the character at `index` belongs with the one before it
+fn continues_cluster(text: &str, index: usize) -> bool {
+    let Some(next) = text[index..].chars().next() else {
+        return false;
+    };
+    let previous = text[..index].chars().next_back();
+    if previous == Some('\u{200D}') || is_extending(next) {
+        return true;
+    }
+    // Flags are pairs of regional indicators; an odd run before the cut is half a flag
+    is_regional_indicator(next)
+        && text[..index]
+            .chars()
+            .rev()
+            .take_while(|c| is_regional_indicator(*c))
+            .count()
+            % 2
+            == 1
+}
+
+/// Combining marks, joiners, variation selectors and emoji modifiers
+fn is_extending(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'
+        | '\u{0483}'..='\u{0489}'
+        | '\u{0591}'..='\u{05BD}'
+        | '\u{0610}'..='\u{061A}'
+        | '\u{064B}'..='\u{065F}'
+        | '\u{0900}'..='\u{0903}'
+        | '\u{093A}'..='\u{094F}'
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{200C}'..='\u{200D}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE00}'..='\u{FE0F}'
+        | '\u{FE20}'..='\u{FE2F}'
+        | '\u{1F3FB}'..='\u{1F3FF}'
+        | '\u{E0020}'..='\u{E007F}'
+        | '\u{E0100}'..='\u{E01EF}')
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentences() {
+        assert_eq!(
+            sentences(
+                "Supply of laptops to the HSE. Lot 1 is €1.5m, e.g. 500 units! Is it IT? Yes."
+            ),
+            vec![
+                "Supply of laptops to the HSE.",
+                "Lot 1 is €1.5m, e.g. 500 units!",
+                "Is it IT?",
+                "Yes.",
+            ]
+        );
+        assert_eq!(
+            sentences("Contact J. Murphy at Dublin Co. Council (ref. 12). Closing soon"),
+            vec![
+                "Contact J. Murphy at Dublin Co. Council (ref. 12).",
+                "Closing soon"
+            ]
+        );
+        assert_eq!(
+            sentences("Tender for \"cloud hosting.\" Then the rest... and more"),
+            vec!["Tender for \"cloud hosting.\"", "Then the rest... and more"]
+        );
+        assert_eq!(
+            sentences("云服务采购。预算五十万欧元！"),
+            vec!["云服务采购。", "预算五十万欧元！"]
+        );
+        assert!(sentences("  ").is_empty());
+    }
+
+    #[test]
+    fn test_summarizers() {
+        let summary = "Managed payroll service for\n  the OPW.  Three year term. Includes support.";
+        assert_eq!(
+            FirstSentences(2).summarize(summary, None),
+            "Managed payroll service for the OPW. Three year term."
+        );
+        let one_liner = OneLiner {
+            fallback: FirstSentences(1),
+        };
+        assert_eq!(
+            one_liner.summarize(summary, Some("Payroll outsourcing for the OPW")),
+            "Payroll outsourcing for the OPW"
+        );
+        assert_eq!(
+            one_liner.summarize(summary, Some("  ")),
+            "Managed payroll service for the OPW."
+        );
+        assert_eq!(
+            one_liner.summarize(summary, None),
+            "Managed payroll service for the OPW."
+        );
+    }
+
+    #[test]
+    fn test_truncate_at_word_break() {
+        assert_eq!(truncate("Short enough", 20), "Short enough");
+        assert_eq!(
+            truncate("Supply and installation of network switches", 24),
+            "Supply and installation…"
+        );
+        assert_eq!(
+            truncate("Cloud hosting, support and training", 16),
+            "Cloud hosting…"
+        );
+        assert_eq!(truncate("Anything", 0), "");
+        assert_eq!(truncate("Supercalifragilistic", 6), "Super…");
+    }
+
+    #[test]
+    fn test_truncate_unicode() {
+        // Counted in characters: each of these is over 20 bytes but 20 characters or fewer
+        let irish = "Seirbhísí TFC don Roinn Oideachais";
+        assert_eq!(truncate(irish, 20), "Seirbhísí TFC don…");
+        assert_eq!(truncate("Ceangal ó Éirinn", 16), "Ceangal ó Éirinn");
+
+        // A fada written as a combining mark stays on its letter
+        let decomposed = "Aistriu\u{301}cha\u{301}n";
+        assert_eq!(truncate(decomposed, 8), "Aistri…");
+        assert_eq!(truncate("Cafe\u{301}s", 5), "Caf…");
+
+        // Emoji sequences and flags are kept whole or dropped whole
+        assert_eq!(truncate("Team 👩\u{200D}💻 wanted", 7), "Team…");
+        assert_eq!(truncate("ab👩\u{200D}💻cd", 4), "ab…");
+        assert_eq!(truncate("🇮🇪🇪🇺 tender", 2), "…");
+        assert_eq!(truncate("🇮🇪🇪🇺 tender", 4), "🇮🇪…");
+        assert_eq!(truncate("👍🏽👍🏽👍🏽", 4), "👍🏽…");
+
+        // No word breaks: cut at the character limit
+        assert_eq!(truncate("云服务采购预算五十万欧元", 6), "云服务采购…");
+
+        for text in [irish, decomposed, "🇮🇪🇪🇺 tender", "云服务采购预算五十万欧元"]
+        {
+            for max in 0..25 {
+                assert!(truncate(text, max).chars().count() <= max);
+            }
+        }
+    }
+
+    #[test]
+    fn test_snippets() {
+        let snippets = Snippets::new(FirstSentences(1));
+        let summary = "The Office of Public Works seeks a provider for the maintenance, hosting \
+            and ongoing development of its heritage website portfolio. Value €400k.";
+        let preview = snippets.preview(summary, Some("ignored"));
+        assert!(preview.starts_with("The Office of Public Works"));
+        assert!(preview.chars().count() <= PREVIEW_CHARS);
+        let title = snippets.title(summary, None);
+        assert!(title.ends_with('…'));
+        assert!(title.chars().count() <= TITLE_CHARS);
+        assert_eq!(snippets.describe(), "first 1 sentence(s)");
+        assert_eq!(
+            Snippets::default().describe(),
+            "model one-liner, else first 2 sentence(s)"
+        );
+    }
+}
@@ -1,15 +1,35 @@
 use anyhow::Result;
 use aws_config::BehaviorVersion;
-use aws_sdk_ses::{Client as SesClient, types::Content, types::Body, types::Message, types::Destination};
-use handlebars::Handlebars;
+use environment::Environment;
+use aws_sdk_s3::Client as S3Client;
+use aws_sdk_s3::error::SdkError;
+use aws_sdk_ses::{Client as SesClient, primitives::Blob, types::Content, types::Body, types::Message, types::Destination, types::RawMessage};
+use fault_injection::Fault;
+use feedback::Verdict;
 use tracing::{info, error, warn};
 
-use crate::types::{Config, SNSMessage, EmailData, NotificationPriority};
+use crate::localization::Localizer;
+use crate::templates::Templates;
+use raw_email::{InlineImage, RawEmail, Thread};
+use resource_discovery::{Resource, ResourceDiscovery};
+use snippet::Snippets;
+use crate::types::{Config, SNSMessage, EmailData, NotificationPriority, OpsEmailData};
+
+/// Content-ID the email template references the PDF preview by
+const THUMBNAIL_CID: &str = "pdf-preview";
+
+/// Previews larger than pdf_processing's own cap aren't embedded
+const MAX_THUMBNAIL_BYTES: usize = 1024 * 1024;
 
 pub struct EmailService {
     ses_client: SesClient,
-    handlebars: Handlebars<'static>,
+    s3_client: S3Client,
+    thumbnail_bucket: Option<String>, // Lambda bucket holding pdf_processing's first-page previews
+    templates: Templates,
     config: Config,
+    environment: Environment,
+    localizer: Localizer,
+    snippets: Snippets,
 }
 
 impl EmailService {
@@ -19,37 +39,97 @@ impl EmailService {
             .await;
        
         let ses_client = SesClient::new(&aws_config);
-        let mut handlebars = Handlebars::new();
-        
-        // Register email templates
-        handlebars.register_template_string("email_html", include_str!("../templates/email.hbs"))?;
-        handlebars.register_template_string("email_text", include_str!("../templates/email.txt"))?;
+        let thumbnail_bucket = if config.pdf_thumbnails {
+            ResourceDiscovery::new(&aws_config).resolve_optional(Resource::LambdaBucket).await
+        } else {
+            None
+        };
         
         Ok(EmailService {
             ses_client,
-            handlebars,
+            s3_client: S3Client::new(&aws_config),
+            thumbnail_bucket,
+            templates: Templates::new()?,
             config: config.clone(),
+            environment: Environment::from_env(),
+            localizer: Localizer::from_env(),
+            snippets: Snippets::from_env(),
         })
     }
 
     pub async fn send_notification(&self, sns_message: &SNSMessage) -> Result<()> {
-        if self.config.notification_emails.is_empty() {
+        let mut email_data = EmailData::from_sns_message(sns_message).map_err(|e| anyhow::anyhow!(e))?;
+        self.localizer.localize(&mut email_data);
+        email_data.timestamp = self.localizer.format_timestamp(sns_message.timestamp);
+        email_data.preview = self.snippets.preview(&email_data.ai_summary, email_data.one_liner.as_deref());
+        let priority = NotificationPriority::from(sns_message.priority.as_str());
+
+        if self.config.notification_emails.is_empty() && email_data.tenant_recipients.is_empty() {
             warn!("No notification emails configured, skipping email send");
             return Ok(());
         }
 
-        let email_data = EmailData::from_sns_message(sns_message).map_err(|e| anyhow::anyhow!(e))?;
-        let priority = NotificationPriority::from(sns_message.priority.as_str());
+        if let (Some(base_url), Some(key), Ok(resource_id)) = (
+            &self.config.feedback_base_url,
+            &self.config.feedback_signing_key,
+            email_data.resource_id.parse::<i64>(),
+        ) {
+            let link = |verdict| feedback::link(base_url, key, resource_id, &email_data.tenant_id, verdict);
+            email_data.feedback_not_relevant_url = Some(link(Verdict::NotRelevant));
+            email_data.feedback_good_call_url = Some(link(Verdict::GoodCall));
+        }
+
+        if let Some(tenant_name) = &email_data.tenant_name {
+            email_data.subject = format!("{} - {}", email_data.subject, tenant_name);
+        }
+
+        if !self.environment.is_production() {
+            let env_name = self.environment.name().to_uppercase();
+            email_data.subject = format!("[{}] {}", env_name, email_data.subject);
+            email_data.environment_banner = Some(format!(
+                "{} ENVIRONMENT - test notification, not a live opportunity alert",
+                env_name
+            ));
+        }
 
         info!("Sending {} priority notification for tender: {}", 
               sns_message.priority, email_data.resource_id);
 
-        // Generate email content
-        let html_body = self.handlebars.render("email_html", &email_data)?;
-        let text_body = self.handlebars.render("email_text", &email_data)?;
+        let thumbnail = self.fetch_thumbnail(&email_data.resource_id).await;
+        if thumbnail.is_some() {
+            email_data.thumbnail_cid = Some(THUMBNAIL_CID.to_string());
+        }
+
+        // Generate email content - PINs and DPS notices get their own early-interest templates
+        let template = if email_data.early_interest { "early_interest" } else { "email" };
+        let html_body = self.templates.html(template, &email_data)?;
+        let text_body = self.templates.text(template, &email_data)?;
 
         // Determine recipients based on priority
-        let recipients = self.get_recipients_for_priority(&priority);
+        let recipients = match self.environment {
+            // Dev never emails real recipients - redirect to the test inbox (or drop)
+            Environment::Dev => match &self.config.test_inbox {
+                Some(inbox) => {
+                    info!("Dev environment: redirecting notification to test inbox {}", inbox);
+                    vec![inbox.clone()]
+                }
+                None => {
+                    warn!("Dev environment without TEST_INBOX - suppressing email");
+                    return Ok(());
+                }
+            },
+            // Tenants with their own recipients are routed to them instead of the shared list
+            _ if !email_data.tenant_recipients.is_empty() => email_data.tenant_recipients.clone(),
+            _ => self.get_recipients_for_priority(&priority),
+        };
+
+        // Every email about the tender (to this tenant) threads together, reminders included
+        let thread = Thread::for_tender(
+            &email_data.resource_id,
+            &email_data.tenant_id,
+            self.environment.name(),
+            &self.config.from_email,
+        );
 
         // Send email using AWS SES
         self.send_ses_email(
@@ -57,12 +137,89 @@ impl EmailService {
             &html_body,
             &text_body,
             &recipients,
+            thumbnail.as_deref(),
+            Some(&thread),
         ).await?;
 
         info!("Email notification sent successfully to {} recipients", recipients.len());
         Ok(())
     }
 
+    /// Email an ops summary (e.g. the scraper's end-of-run report) to the ops recipients
+    pub async fn send_ops_summary(&self, sns_message: &SNSMessage) -> Result<()> {
+        let mut data = OpsEmailData {
+            subject: format!("[OPS] {}", sns_message.title),
+            title: sns_message.title.clone(),
+            summary: sns_message.summary.clone(),
+            action_required: sns_message.action_required.clone(),
+            timestamp: self.localizer.format_timestamp(sns_message.timestamp),
+            environment_banner: None,
+            lang: self.localizer.tag().to_string(),
+        };
+
+        if !self.environment.is_production() {
+            let env_name = self.environment.name().to_uppercase();
+            data.subject = format!("[{}] {}", env_name, data.subject);
+            data.environment_banner = Some(format!("{} ENVIRONMENT", env_name));
+        }
+
+        let recipients = match self.environment {
+            Environment::Dev => match &self.config.test_inbox {
+                Some(inbox) => vec![inbox.clone()],
+                None => {
+                    warn!("Dev environment without TEST_INBOX - suppressing ops summary");
+                    return Ok(());
+                }
+            },
+            _ if !self.config.ops_emails.is_empty() => self.config.ops_emails.clone(),
+            _ => self.config.notification_emails.clone(),
+        };
+
+        let html_body = self.templates.html("ops", &data)?;
+        let text_body = format!(
+            "{}\n\n{}\n\nAction required: {}\n",
+            data.title, data.summary, data.action_required
+        );
+
+        self.send_ses_email(&data.subject, &html_body, &text_body, &recipients, None, None).await?;
+        info!("Ops summary '{}' sent to {} recipients", sns_message.message_type, recipients.len());
+        Ok(())
+    }
+
+    /// First-page PNG that pdf_processing stored for the tender (built with its `thumbnail` feature)
+    async fn fetch_thumbnail(&self, resource_id: &str) -> Option<Vec<u8>> {
+        let bucket = self.thumbnail_bucket.as_ref()?;
+        let key = format!("thumbnails/{}.png", resource_id);
+
+        let fetched = match fault_injection::check(Fault::S3Timeout) {
+            Ok(()) => self.s3_client.get_object().bucket(bucket).key(&key).send().await,
+            Err(injected) => Err(SdkError::timeout_error(injected)),
+        };
+        let object = match fetched {
+            Ok(object) => object,
+            Err(e) => {
+                // Most tenders have no preview (no PDF, or rendering disabled)
+                if !e.as_service_error().is_some_and(|e| e.is_no_such_key()) {
+                    warn!("Failed to fetch thumbnail s3://{}/{}: {}", bucket, key, e);
+                }
+                return None;
+            }
+        };
+
+        let png = match object.body.collect().await {
+            Ok(data) => data.into_bytes().to_vec(),
+            Err(e) => {
+                warn!("Failed to read thumbnail s3://{}/{}: {}", bucket, key, e);
+                return None;
+            }
+        };
+        if png.len() > MAX_THUMBNAIL_BYTES {
+            warn!("Thumbnail s3://{}/{} too large ({} bytes), not embedding", bucket, key, png.len());
+            return None;
+        }
+        Some(png)
+    }
+
     fn get_recipients_for_priority(&self, priority: &NotificationPriority) -> Vec<String> {
         match priority {
             NotificationPriority::Urgent => {
@@ -86,6 +243,8 @@ impl EmailService {
         html_body: &str,
         text_body: &str,
         recipients: &[String],
+        inline_thumbnail: Option<&[u8]>,
+        thread: Option<&Thread>,
     ) -> Result<()> {
         if recipients.is_empty() {
             warn!("No recipients specified for email");
@@ -110,46 +269,83 @@ impl EmailService {
             }
         }
 
-        let destination = Destination::builder()
-            .set_to_addresses(Some(recipients.to_vec()))
-            .build();
-
-        let subject_content = Content::builder()
-            .data(subject)
-            .charset("UTF-8")
-            .build()?;
-
-        let html_content = Content::builder()
-            .data(html_body)
-            .charset("UTF-8")
-            .build()?;
-
-        let text_content = Content::builder()
-            .data(text_body)
-            .charset("UTF-8")
-            .build()?;
-
-        let body = Body::builder()
-            .html(html_content)
-            .text(text_content)
-            .build();
-
-        let message = Message::builder()
-            .subject(subject_content)
-            .body(body)
-            .build();
-
-        let send_email_result = self.ses_client
-            .send_email()
-            .source(&self.config.from_email)
-            .destination(destination)
-            .message(message)
-            .send()
-            .await;
+        let send_email_result = match (inline_thumbnail, thread) {
+            // SendEmail can't carry the inline image or threading headers; with either, the whole MIME message is built
+            (None, None) => {
+                let destination = Destination::builder()
+                    .set_to_addresses(Some(recipients.to_vec()))
+                    .build();
+
+                let subject_content = Content::builder()
+                    .data(subject)
+                    .charset("UTF-8")
+                    .build()?;
+
+                let html_content = Content::builder()
+                    .data(html_body)
+                    .charset("UTF-8")
+                    .build()?;
+
+                let text_content = Content::builder()
+                    .data(text_body)
+                    .charset("UTF-8")
+                    .build()?;
+
+                let body = Body::builder()
+                    .html(html_content)
+                    .text(text_content)
+                    .build();
+
+                let message = Message::builder()
+                    .subject(subject_content)
+                    .body(body)
+                    .build();
+
+                self.ses_client
+                    .send_email()
+                    .source(&self.config.from_email)
+                    .destination(destination)
+                    .message(message)
+                    .send()
+                    .await
+                    .map(|output| output.message_id().to_string())
+                    .map_err(anyhow::Error::from)
+            },
+            _ => {
+                let image = inline_thumbnail.map(|png| InlineImage {
+                    content_id: THUMBNAIL_CID,
+                    filename: "preview.png",
+                    content_type: "image/png",
+                    data: png,
+                });
+                let raw = RawEmail {
+                    from: &self.config.from_email,
+                    to: recipients,
+                    subject,
+                    text_body,
+                    html_body: Some(html_body),
+                    image: image.as_ref(),
+                    thread,
+                }.build();
+                if let Some(thread) = thread {
+                    info!("  Thread: {}", thread.root());
+                }
+
+                self.ses_client
+                    .send_raw_email()
+                    .source(&self.config.from_email)
+                    .set_destinations(Some(recipients.to_vec()))
+                    .raw_message(RawMessage::builder().data(Blob::new(raw)).build()?)
+                    .send()
+                    .await
+                    .map(|output| output.message_id().to_string())
+                    .map_err(anyhow::Error::from)
+            },
+        };
 
         match send_email_result {
-            Ok(output) => {
-                info!("Email sent successfully. Message ID: {:?}", output.message_id());
+            Ok(message_id) => {
+                info!("Email sent successfully. Message ID: {}", message_id);
                 Ok(())
             },
             Err(e) => {
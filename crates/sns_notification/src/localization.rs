@@ -0,0 +1,207 @@
+//! Locale-aware formatting of the values and dates shown in notification emails.
+//!
+//! `EMAIL_LOCALE` picks the locale (default `en-IE`). Tender values are always in euro
+//! and portal deadlines are Irish local time, so the locale only changes presentation:
+//! "1000000" becomes "€1,000,000" and "2025-07-04T12:00:00" "Fri 4 Jul 2025, 12:00 IST".
+//! Anything that doesn't parse is shown as it came.
+
+use crate::types::EmailData;
+use chrono::{DateTime, Locale, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Europe::Dublin;
+use tracing::warn;
+
+pub const DEFAULT_LOCALE: &str = "en-IE";
+
+const DATE_FORMAT: &str = "%a %-d %b %Y, %H:%M %Z";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Localizer {
+    tag: &'static str,
+    locale: Locale,
+    thousands: &'static str,
+    decimal: &'static str,
+    symbol_first: bool,
+}
+
+const LOCALES: &[Localizer] = &[
+    Localizer {
+        tag: "en-IE",
+        locale: Locale::en_IE,
+        thousands: ",",
+        decimal: ".",
+        symbol_first: true,
+    },
+    Localizer {
+        tag: "ga-IE",
+        locale: Locale::ga_IE,
+        thousands: ",",
+        decimal: ".",
+        symbol_first: true,
+    },
+    Localizer {
+        tag: "en-GB",
+        locale: Locale::en_GB,
+        thousands: ",",
+        decimal: ".",
+        symbol_first: true,
+    },
+    Localizer {
+        tag: "fr-FR",
+        locale: Locale::fr_FR,
+        thousands: "\u{202f}",
+        decimal: ",",
+        symbol_first: false,
+    },
+    Localizer {
+        tag: "de-DE",
+        locale: Locale::de_DE,
+        thousands: ".",
+        decimal: ",",
+        symbol_first: false,
+    },
+];
+
+impl Default for Localizer {
+    fn default() -> Self {
+        LOCALES[0]
+    }
+}
+
+impl Localizer {
+    /// "en-IE", "en_ie" etc.; None for a locale we have no formats for
+    pub fn new(tag: &str) -> Option<Self> {
+        let tag = tag.trim().replace('_', "-");
+        LOCALES
+            .iter()
+            .find(|l| l.tag.eq_ignore_ascii_case(&tag))
+            .copied()
+    }
+
+    pub fn from_env() -> Self {
+        match std::env::var("EMAIL_LOCALE") {
+            Ok(tag) if !tag.trim().is_empty() => Self::new(&tag).unwrap_or_else(|| {
+                warn!(
+                    "⚠️ Unknown EMAIL_LOCALE '{}', using {}",
+                    tag, DEFAULT_LOCALE
+                );
+                Self::default()
+            }),
+            _ => Self::default(),
+        }
+    }
+
+    /// BCP 47 tag, for the HTML `lang` attribute
+    pub fn tag(&self) -> &'static str {
+        self.tag
+    }
+
+    /// A euro amount: cents only when there are any
+    pub fn format_value(&self, raw: &str) -> Option<String> {
+        let value: f64 = raw.trim().parse().ok()?;
+        if !value.is_finite() {
+            return None;
+        }
+        let cents = (value.abs() * 100.0).round() as u64;
+        let digits = (cents / 100).to_string();
+
+        let mut number = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                number.push_str(self.thousands);
+            }
+            number.push(digit);
+        }
+        if !cents.is_multiple_of(100) {
+            number.push_str(&format!("{}{:02}", self.decimal, cents % 100));
+        }
+
+        let sign = if value < 0.0 { "-" } else { "" };
+        Some(if self.symbol_first {
+            format!("{}€{}", sign, number)
+        } else {
+            format!("{}{}\u{a0}€", sign, number)
+        })
+    }
+
+    /// A portal deadline (Irish local time) with its day, month and time zone
+    pub fn format_deadline(&self, raw: &str) -> Option<String> {
+        let raw = raw.trim();
+        let local = match DateTime::parse_from_rfc3339(raw) {
+            Ok(at) => at.with_timezone(&Dublin),
+            Err(_) => {
+                let naive = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+                    .iter()
+                    .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())?;
+                Dublin.from_local_datetime(&naive).earliest()?
+            }
+        };
+        Some(local.format_localized(DATE_FORMAT, self.locale).to_string())
+    }
+
+    pub fn format_timestamp(&self, at: DateTime<Utc>) -> String {
+        at.with_timezone(&Dublin)
+            .format_localized(DATE_FORMAT, self.locale)
+            .to_string()
+    }
+
+    /// Format the email's value and dates in place
+    pub fn localize(&self, data: &mut EmailData) {
+        data.lang = self.tag.to_string();
+        if let Some(value) = data.estimated_value.as_deref() {
+            data.estimated_value = self.format_value(value).or(data.estimated_value.take());
+        }
+        if let Some(deadline) = data.deadline.as_deref() {
+            data.deadline = self.format_deadline(deadline).or(data.deadline.take());
+        }
+        if let Some(deadline) = data.clarification_deadline.as_deref() {
+            data.clarification_deadline = self
+                .format_deadline(deadline)
+                .or(data.clarification_deadline.take());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_value() {
+        let en = Localizer::default();
+        assert_eq!(en.format_value("1000000").as_deref(), Some("€1,000,000"));
+        assert_eq!(en.format_value("950.5").as_deref(), Some("€950.50"));
+        assert_eq!(en.format_value("1000000.00").as_deref(), Some("€1,000,000"));
+        assert_eq!(en.format_value("TBC"), None);
+
+        let de = Localizer::new("de_de").unwrap();
+        assert_eq!(
+            de.format_value("1234567.8").as_deref(),
+            Some("1.234.567,80\u{a0}€")
+        );
+    }
+
+    #[test]
+    fn test_format_deadline_uses_irish_time() {
+        let en = Localizer::default();
+        // Irish Standard Time (summer) and GMT (winter)
+        assert_eq!(
+            en.format_deadline("2025-07-04T12:00:00").as_deref(),
+            Some("Fri 4 Jul 2025, 12:00 IST")
+        );
+        assert_eq!(
+            en.format_deadline("2025-03-14 12:00:00").as_deref(),
+            Some("Fri 14 Mar 2025, 12:00 GMT")
+        );
+        assert_eq!(
+            en.format_deadline("2025-07-04T11:00:00Z").as_deref(),
+            Some("Fri 4 Jul 2025, 12:00 IST")
+        );
+        assert_eq!(en.format_deadline("next Friday"), None);
+    }
+
+    #[test]
+    fn test_unknown_locale() {
+        assert_eq!(Localizer::new("xx-XX"), None);
+        assert_eq!(Localizer::new("en-ie").unwrap().tag(), "en-IE");
+    }
+}
@@ -1,17 +1,35 @@
 // crates/sns_notification/src/main.rs
 use anyhow::Result;
-use aws_lambda_events::event::sqs::SqsEvent;
-use lambda_runtime::{run, service_fn, Error, LambdaEvent};
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use chrono::Utc;
+use decision_audit::{Decision, Kind};
+use pipeline_contract::diagnostics::{self, ConfigReport};
+use pipeline_contract::{Completed, ErrorCode, Handoff, StageError, StageEvent, StageResults};
+use pipeline_status::lifecycle::{self, State};
+use pipeline_status::Stage;
+use resource_discovery::Resource;
+use serde_json::Value;
+use snippet::Snippets;
+use sqlx::PgPool;
+use stage_runtime::Error;
 use std::env;
-use tracing::{error, info};
+use std::time::SystemTime;
+use tender_overrides::Hold;
+use tracing::{error, info, warn};
 
 mod email_service;
+mod localization;
+mod policy;
+mod templates;
 mod types;
 
 use email_service::EmailService;
+use localization::Localizer;
+use policy::{Fingerprint, Policy, Verdict};
 use types::{Config, SNSMessage};
 
+/// Name recorded against this lambda's lifecycle transitions
+const ACTOR: &str = "sns_notification";
+
 async fn mark_tender_as_notified(pool: &PgPool, resource_id: i64) -> Result<()> {
     sqlx::query(
         r#"
@@ -29,12 +47,66 @@ async fn mark_tender_as_notified(pool: &PgPool, resource_id: i64) -> Result<()>
     Ok(())
 }
 
-async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<String, Error> {
+/// Effective configuration, returned for `{"payload": {"diagnostic": "config"}}`
+fn config_report() -> ConfigReport {
+    let report = ConfigReport::new("sns_notification", env!("CARGO_PKG_VERSION"))
+        .envs(&[
+            "NOTIFICATION_EMAILS",
+            "OPS_NOTIFICATION_EMAILS",
+            "FROM_EMAIL",
+            "AWS_REGION",
+            "TEST_INBOX",
+            "FEEDBACK_BASE_URL",
+            "FEEDBACK_SIGNING_KEY",
+            "EMAIL_PDF_THUMBNAILS",
+            "EMAIL_LOCALE",
+            "NOTIFICATION_SUPPRESSION_HOURS",
+            "SNIPPET_SUMMARIZER",
+            "SNIPPET_SENTENCES",
+            "STAGE_RUNTIME",
+            "FAULT_S3_TIMEOUT_RATE",
+        ])
+        .effective("email_locale", Localizer::from_env().tag())
+        .effective("suppression_window", Policy::from_env().describe())
+        .effective("email_preview", Snippets::from_env().describe())
+        .effective("fault_injection", fault_injection::describe());
+    match Config::from_env() {
+        Ok(config) => report
+            .effective("from_email", &config.from_email)
+            .effective("notification_emails", config.notification_emails.join(", "))
+            .effective("ops_emails", config.ops_emails.join(", "))
+            .effective(
+                "feedback_links",
+                config.feedback_base_url.is_some() && config.feedback_signing_key.is_some(),
+            )
+            .effective("pdf_thumbnails", config.pdf_thumbnails),
+        Err(e) => report.effective("config", e),
+    }
+}
+
+/// Runs under Lambda or as a service polling the notification queue (see `stage_runtime`)
+struct SnsNotification;
+
+impl stage_runtime::Stage for SnsNotification {
+    fn queue(&self) -> Resource {
+        Resource::NotificationQueue
+    }
+
+    async fn handle(&self, event: StageEvent, _deadline: SystemTime) -> Result<Value, Error> {
+        function_handler(event).await
+    }
+}
+
+async fn function_handler(event: StageEvent) -> Result<Value, Error> {
     info!("=== SNS NOTIFICATION LAMBDA STARTED ===");
-    info!(
-        "Received SQS event with {} records",
-        event.payload.records.len()
-    );
+    if let Some(diagnostic) = event.diagnostic() {
+        return diagnostics::respond(diagnostic, config_report)
+            .map_err(|e| Error::from(e.as_str()));
+    }
+
+    let mut results = StageResults::new(&event);
+    let messages = event.into_messages();
+    info!("Received event with {} messages", messages.len());
 
     let config = Config::from_env().map_err(|e| {
         error!("Failed to load configuration: {}", e);
@@ -53,66 +125,254 @@ async fn function_handler(event: LambdaEvent<SqsEvent>) -> Result<String, Error>
     // Connect to database to track notifications
     let database_url = env::var("DATABASE_URL")
         .map_err(|_| Error::from("DATABASE_URL environment variable not set"))?;
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
+    let pool = db::connect(&database_url, 5)
         .await
         .map_err(|e| Error::from(format!("Failed to connect to database: {}", e).as_str()))?;
     info!("Connected to database");
-
-    let mut processed_count = 0;
+    // Without the log every notification is treated as the first for its tender
+    if let Err(e) = policy::ensure_table(&pool).await {
+        warn!("⚠️ Failed to ensure notification_log table: {}", e);
+    }
+    if let Err(e) = tender_overrides::ensure_table(&pool).await {
+        warn!("⚠️ Failed to ensure tender_overrides table: {}", e);
+    }
+    let policy = Policy::from_env();
 
     // Process each SQS record (containing our notification messages)
-    for record in event.payload.records {
-        if let Some(body) = &record.body {
-            info!("Processing SQS message: {}", body);
-
-            // Parse the message directly (our SNSMessage structure)
-            let sns_message: SNSMessage = serde_json::from_str(body).map_err(|e| {
-                error!("Failed to parse SQS message body: {}", e);
-                Error::from(format!("Failed to parse message: {}", e).as_str())
+    for message in &messages {
+        let handoff = Handoff::new(message);
+        let result = process_notification(&message.body, &email_service, &pool, &policy).await;
+        results.record(message, &handoff, result).await;
+    }
+
+    info!("=== SNS NOTIFICATION LAMBDA COMPLETED ===");
+    // Retryable failures (e.g. SES errors) go back to SQS; permanent ones are acknowledged
+    results
+        .into_response()
+        .map_err(|e| Error::from(e.to_string().as_str()))
+}
+
+/// Email one notification (unless `policy` suppresses it as a repeat) and mark the tender
+/// notified. Notification is the last stage, so an orchestrated run has nothing to hand on
+async fn process_notification(
+    body: &str,
+    email_service: &EmailService,
+    pool: &PgPool,
+    policy: &Policy,
+) -> Result<Completed, StageError> {
+    info!("Processing SQS message: {}", body);
+
+    // Parse the message directly (our SNSMessage structure)
+    let sns_message: SNSMessage = serde_json::from_str(body).map_err(|e| {
+        StageError::new(
+            ErrorCode::InvalidMessage,
+            format!("Failed to parse message: {}", e),
+        )
+    })?;
+
+    info!(
+        "Parsed notification message - Type: {}, Priority: {}, Tender: {}",
+        sns_message.message_type, sns_message.priority, sns_message.resource_id
+    );
+
+    // Ops summaries aren't about a tender, so there is no pipeline status to track
+    if sns_message.is_ops_summary() {
+        email_service
+            .send_ops_summary(&sns_message)
+            .await
+            .map_err(|e| {
+                StageError::new(
+                    ErrorCode::DeliveryFailed,
+                    format!("Failed to send ops summary: {}", e),
+                )
             })?;
+        return Ok(Completed::new(0, "Ops summary sent"));
+    }
 
-            info!(
-                "Parsed notification message - Type: {}, Priority: {}, Tender: {}",
-                sns_message.message_type, sns_message.priority, sns_message.resource_id
-            );
+    // Parse resource_id from String to i64
+    let resource_id = sns_message.resource_id.parse::<i64>().map_err(|e| {
+        StageError::new(
+            ErrorCode::InvalidMessage,
+            format!("Invalid resource_id format: {}", e),
+        )
+    })?;
 
-            // Send email notification
-            email_service
-                .send_notification(&sns_message)
-                .await
-                .map_err(|e| {
-                    error!("Failed to send email notification: {}", e);
-                    Error::from(format!("Failed to send email: {}", e).as_str())
-                })?;
-
-            // Mark tender as notified in database
-            // Parse resource_id from String to i64
-            let resource_id = sns_message.resource_id.parse::<i64>().map_err(|e| {
-                error!("Failed to parse resource_id: {}", e);
-                Error::from(format!("Invalid resource_id format: {}", e).as_str())
+    // A resend is emailed again, but the tender's status and lifecycle already record the original
+    if sns_message.is_resend() {
+        email_service
+            .send_notification(&sns_message)
+            .await
+            .map_err(|e| {
+                StageError::new(
+                    ErrorCode::DeliveryFailed,
+                    format!("Failed to resend email: {}", e),
+                )
+                .for_tender(resource_id)
             })?;
+        let decision = Decision::new(
+            resource_id,
+            Kind::Notification,
+            "sns_notification",
+            "resent",
+        )
+        .inputs(&[body])
+        .detail(serde_json::json!({
+            "priority": sns_message.priority,
+            "notification_emails": sns_message.metadata.get("notification_emails"),
+        }));
+        decision_audit::record(pool, &decision).await;
+        return Ok(Completed::new(resource_id, "Notification resent"));
+    }
 
-            mark_tender_as_notified(&pool, resource_id)
-                .await
-                .map_err(|e| {
-                    error!("Failed to mark tender as notified: {}", e);
-                    Error::from(format!("Failed to update notification status: {}", e).as_str())
-                })?;
+    if !lifecycle::may_enter(pool, resource_id, State::Notified, &[], ACTOR).await {
+        return Err(StageError::new(
+            ErrorCode::InvalidState,
+            "Tender isn't ready for a notification (see tender_lifecycle_transitions)",
+        )
+        .for_tender(resource_id));
+    }
 
-            processed_count += 1;
-        } else {
-            error!("SQS record has no body - skipping");
+    pipeline_status::started(pool, resource_id, Stage::Notification, body).await;
+
+    // Canary tenders are marked notified (which is what pipeline_canary checks) but never emailed
+    let fingerprint = Fingerprint::of(&sns_message);
+    let reason = if environment::is_canary(resource_id) {
+        info!("🐤 Canary tender {} - suppressing email", resource_id);
+        None
+    } else {
+        if let Some(hold) =
+            policy::held(pool, resource_id, &fingerprint.tenant_id, Utc::now()).await
+        {
+            return Ok(hold_back(pool, resource_id, body, &fingerprint, hold).await);
         }
-    }
+        let last = policy::last_sent(pool, resource_id, &fingerprint.tenant_id)
+            .await
+            .unwrap_or_else(|e| {
+                warn!(
+                    "⚠️ Failed to read notification_log for {}, treating as first notification: {}",
+                    resource_id, e
+                );
+                None
+            });
+        let reason = match policy.decide(last.as_ref(), &fingerprint, Utc::now()) {
+            Verdict::Send(reason) => reason,
+            Verdict::Suppress { last_sent, until } => {
+                return Ok(suppress(pool, resource_id, body, &fingerprint, last_sent, until).await);
+            }
+        };
 
-    info!("=== SNS NOTIFICATION LAMBDA COMPLETED ===");
-    info!("Successfully processed {} notifications", processed_count);
-    Ok(format!(
-        "Successfully processed {} notifications",
-        processed_count
-    ))
+        if let Err(e) = email_service.send_notification(&sns_message).await {
+            pipeline_status::failed(pool, resource_id, Stage::Notification, &e.to_string()).await;
+            return Err(StageError::new(
+                ErrorCode::DeliveryFailed,
+                format!("Failed to send email: {}", e),
+            )
+            .for_tender(resource_id));
+        }
+        if let Err(e) =
+            policy::record_sent(pool, resource_id, &sns_message, &fingerprint, reason).await
+        {
+            warn!(
+                "⚠️ Failed to log notification for {} in notification_log: {}",
+                resource_id, e
+            );
+        }
+        Some(reason)
+    };
+
+    // Mark tender as notified in database
+    mark_tender_as_notified(pool, resource_id)
+        .await
+        .map_err(|e| {
+            StageError::new(
+                ErrorCode::Database,
+                format!("Failed to update notification status: {}", e),
+            )
+            .for_tender(resource_id)
+        })?;
+    pipeline_status::completed(pool, resource_id, Stage::Notification).await;
+    lifecycle::advance(pool, resource_id, State::Notified, ACTOR).await;
+
+    let decision = Decision::new(
+        resource_id,
+        Kind::Notification,
+        "sns_notification",
+        "emailed",
+    )
+    .inputs(&[body])
+    .detail(serde_json::json!({
+        "message_type": sns_message.message_type,
+        "priority": sns_message.priority,
+        "reason": reason.map(|r| r.name()),
+    }));
+    decision_audit::record(pool, &decision).await;
+
+    Ok(Completed::new(resource_id, "Notification processed"))
+}
+
+/// Acknowledge a repeat notification without emailing it; the tender is already notified
+async fn suppress(
+    pool: &PgPool,
+    resource_id: i64,
+    body: &str,
+    fingerprint: &Fingerprint,
+    last_sent: chrono::DateTime<Utc>,
+    until: chrono::DateTime<Utc>,
+) -> Completed {
+    info!(
+        "🔕 Tender {} (tenant {}) was notified at {} and nothing material changed - suppressing until {}",
+        resource_id, fingerprint.tenant_id, last_sent, until
+    );
+    pipeline_status::completed(pool, resource_id, Stage::Notification).await;
+
+    let decision = Decision::new(
+        resource_id,
+        Kind::Notification,
+        "sns_notification",
+        "suppressed",
+    )
+    .inputs(&[body])
+    .detail(serde_json::json!({
+        "tenant_id": fingerprint.tenant_id,
+        "last_sent": last_sent,
+        "suppressed_until": until,
+    }));
+    decision_audit::record(pool, &decision).await;
+
+    Completed::new(resource_id, "Notification suppressed as a repeat")
+}
+
+/// Acknowledge a notification for a snoozed or NO BID tender without emailing it
+async fn hold_back(
+    pool: &PgPool,
+    resource_id: i64,
+    body: &str,
+    fingerprint: &Fingerprint,
+    hold: Hold,
+) -> Completed {
+    info!(
+        "✋ Tender {} (tenant {}) is held back by a manual override ({}) - not emailing",
+        resource_id,
+        fingerprint.tenant_id,
+        hold.name()
+    );
+    pipeline_status::completed(pool, resource_id, Stage::Notification).await;
+    lifecycle::advance(pool, resource_id, State::Suppressed, ACTOR).await;
+
+    let snoozed_until = match hold {
+        Hold::Snoozed { until } => Some(until),
+        Hold::NoBid => None,
+    };
+    let decision = Decision::new(resource_id, Kind::Notification, "sns_notification", "held")
+        .inputs(&[body])
+        .detail(serde_json::json!({
+            "tenant_id": fingerprint.tenant_id,
+            "hold": hold.name(),
+            "snoozed_until": snoozed_until,
+        }));
+    decision_audit::record(pool, &decision).await;
+
+    Completed::new(resource_id, "Notification held back by a manual override")
 }
 
 #[tokio::main]
@@ -123,5 +383,6 @@ async fn main() -> Result<(), Error> {
         .without_time()
         .init();
 
-    run(service_fn(function_handler)).await
+    // Under Lambda or as a service (STAGE_RUNTIME), tagging every log line with the deployment environment
+    stage_runtime::run(SnsNotification).await
 }
@@ -0,0 +1,365 @@
+//! Notification policy: whether a tender notification is emailed or suppressed.
+//!
+//! Every email sent for a tender is logged in `notification_log` with the deadline and a
+//! hash of the document it described. Another notification for the same tender and tenant
+//! within NOTIFICATION_SUPPRESSION_HOURS (default 72; 0 turns suppression off) of the last
+//! one is suppressed, unless something material changed since: the deadline moved or the
+//! document is different. Refreshed summaries and redeliveries therefore don't email the
+//! same tender twice, while an amended tender is sent again straight away.
+//!
+//! A tender snoozed for the tenant, or forced to NO BID (`tender_overrides`), isn't
+//! emailed at all while the override stands.
+//!
+//! Resends (`ops_cli notify resend`) and ops summaries never reach the policy.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use environment::Environment;
+use sqlx::PgPool;
+use tender_overrides::Hold;
+use tracing::warn;
+
+use crate::types::SNSMessage;
+
+const DEFAULT_WINDOW_HOURS: i64 = 72;
+
+/// What a notification said that matters if it changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub tenant_id: String,
+    pub deadline: Option<NaiveDateTime>,
+    /// SHA-256 of the PDF text (from ai_summary); None for title-only notifications
+    pub document_hash: Option<String>,
+}
+
+impl Fingerprint {
+    pub fn of(message: &SNSMessage) -> Self {
+        let metadata = &message.metadata;
+        Self {
+            tenant_id: metadata
+                .get("tenant_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or("default")
+                .to_string(),
+            deadline: metadata
+                .get("deadline")
+                .and_then(|v| serde_json::from_value(v.clone()).ok()),
+            document_hash: metadata
+                .get("document_hash")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+        }
+    }
+}
+
+/// The last notification sent for a tender and tenant
+#[derive(Debug, Clone)]
+pub struct LastSent {
+    pub sent_at: DateTime<Utc>,
+    pub deadline: Option<NaiveDateTime>,
+    pub document_hash: Option<String>,
+}
+
+/// Why a notification goes out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    First,
+    WindowPassed,
+    DeadlineMoved,
+    DocumentChanged,
+    /// Suppression is turned off
+    NoWindow,
+}
+
+impl Reason {
+    /// Value stored in `notification_log.reason` and the audit trail
+    pub fn name(&self) -> &'static str {
+        match self {
+            Reason::First => "first",
+            Reason::WindowPassed => "window_passed",
+            Reason::DeadlineMoved => "deadline_moved",
+            Reason::DocumentChanged => "document_changed",
+            Reason::NoWindow => "no_window",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Send(Reason),
+    Suppress {
+        last_sent: DateTime<Utc>,
+        until: DateTime<Utc>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Policy {
+    /// None sends every notification
+    window: Option<Duration>,
+}
+
+impl Policy {
+    pub fn new(window: Option<Duration>) -> Self {
+        Self { window }
+    }
+
+    pub fn from_env() -> Self {
+        let hours = match std::env::var("NOTIFICATION_SUPPRESSION_HOURS") {
+            Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+                warn!(
+                    "⚠️ Ignoring NOTIFICATION_SUPPRESSION_HOURS='{}' - not a whole number",
+                    value
+                );
+                DEFAULT_WINDOW_HOURS
+            }),
+            Err(_) => DEFAULT_WINDOW_HOURS,
+        };
+        Self::new((hours > 0).then(|| Duration::hours(hours)))
+    }
+
+    /// For the config diagnostic
+    pub fn describe(&self) -> String {
+        match self.window {
+            Some(window) => format!(
+                "{}h unless the deadline or document changes",
+                window.num_hours()
+            ),
+            None => "off".to_string(),
+        }
+    }
+
+    pub fn decide(
+        &self,
+        last: Option<&LastSent>,
+        current: &Fingerprint,
+        now: DateTime<Utc>,
+    ) -> Verdict {
+        let Some(window) = self.window else {
+            return Verdict::Send(Reason::NoWindow);
+        };
+        let Some(last) = last else {
+            return Verdict::Send(Reason::First);
+        };
+        let until = last.sent_at + window;
+        if now >= until {
+            Verdict::Send(Reason::WindowPassed)
+        } else if current.deadline != last.deadline {
+            Verdict::Send(Reason::DeadlineMoved)
+        } else if current.document_hash.is_some() && current.document_hash != last.document_hash {
+            // A title-only rerun of a tender already notified with its document isn't new
+            Verdict::Send(Reason::DocumentChanged)
+        } else {
+            Verdict::Suppress {
+                last_sent: last.sent_at,
+                until,
+            }
+        }
+    }
+}
+
+pub async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "notification_log", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS notification_log (
+                id BIGSERIAL PRIMARY KEY,
+                resource_id BIGINT NOT NULL,
+                tenant_id TEXT NOT NULL DEFAULT 'default',
+                message_type TEXT NOT NULL,
+                deadline TIMESTAMP,
+                document_hash TEXT,
+                reason TEXT NOT NULL,
+                sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_notification_log_tender ON notification_log (resource_id, tenant_id, sent_at DESC)",
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "notification_log").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Whether a manual override holds the tender's notification back. An override that
+/// can't be read holds nothing back
+pub async fn held(
+    pool: &PgPool,
+    resource_id: i64,
+    tenant_id: &str,
+    now: DateTime<Utc>,
+) -> Option<Hold> {
+    match tender_overrides::get(pool, resource_id, tenant_id).await {
+        Ok(tender_override) => tender_override.and_then(|o| o.hold(now)),
+        Err(e) => {
+            warn!(
+                "⚠️ Failed to read the override for {} (tenant {}), sending as usual: {}",
+                resource_id, tenant_id, e
+            );
+            None
+        }
+    }
+}
+
+pub async fn last_sent(
+    pool: &PgPool,
+    resource_id: i64,
+    tenant_id: &str,
+) -> Result<Option<LastSent>> {
+    let row: Option<(DateTime<Utc>, Option<NaiveDateTime>, Option<String>)> = sqlx::query_as(
+        r#"
+        SELECT sent_at, deadline, document_hash
+        FROM notification_log
+        WHERE resource_id = $1 AND tenant_id = $2
+        ORDER BY sent_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(resource_id)
+    .bind(tenant_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(sent_at, deadline, document_hash)| LastSent {
+        sent_at,
+        deadline,
+        document_hash,
+    }))
+}
+
+pub async fn record_sent(
+    pool: &PgPool,
+    resource_id: i64,
+    message: &SNSMessage,
+    fingerprint: &Fingerprint,
+    reason: Reason,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO notification_log
+            (resource_id, tenant_id, message_type, deadline, document_hash, reason)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(resource_id)
+    .bind(&fingerprint.tenant_id)
+    .bind(&message.message_type)
+    .bind(fingerprint.deadline)
+    .bind(&fingerprint.document_hash)
+    .bind(reason.name())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone};
+
+    fn deadline(day: u32) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(2026, 11, day)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+    }
+
+    fn fingerprint(day: u32, document: Option<&str>) -> Fingerprint {
+        Fingerprint {
+            tenant_id: "default".to_string(),
+            deadline: deadline(day),
+            document_hash: document.map(str::to_string),
+        }
+    }
+
+    fn last_sent(day: u32, document: Option<&str>) -> LastSent {
+        LastSent {
+            sent_at: Utc.with_ymd_and_hms(2026, 10, 16, 9, 0, 0).unwrap(),
+            deadline: deadline(day),
+            document_hash: document.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_repeat_within_window_suppressed_unless_material() {
+        let policy = Policy::new(Some(Duration::hours(72)));
+        let last = last_sent(20, Some("abc"));
+        let next_day = Utc.with_ymd_and_hms(2026, 10, 17, 9, 0, 0).unwrap();
+
+        assert_eq!(
+            policy.decide(None, &fingerprint(20, Some("abc")), next_day),
+            Verdict::Send(Reason::First)
+        );
+        assert_eq!(
+            policy.decide(Some(&last), &fingerprint(20, Some("abc")), next_day),
+            Verdict::Suppress {
+                last_sent: last.sent_at,
+                until: Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap(),
+            }
+        );
+        assert_eq!(
+            policy.decide(Some(&last), &fingerprint(20, None), next_day),
+            Verdict::Suppress {
+                last_sent: last.sent_at,
+                until: Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap(),
+            }
+        );
+        assert_eq!(
+            policy.decide(Some(&last), &fingerprint(27, Some("abc")), next_day),
+            Verdict::Send(Reason::DeadlineMoved)
+        );
+        assert_eq!(
+            policy.decide(Some(&last), &fingerprint(20, Some("def")), next_day),
+            Verdict::Send(Reason::DocumentChanged)
+        );
+
+        let later = Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+        assert_eq!(
+            policy.decide(Some(&last), &fingerprint(20, Some("abc")), later),
+            Verdict::Send(Reason::WindowPassed)
+        );
+        assert_eq!(
+            Policy::new(None).decide(Some(&last), &fingerprint(20, Some("abc")), next_day),
+            Verdict::Send(Reason::NoWindow)
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_from_metadata() {
+        let message = SNSMessage {
+            message_type: "AI_SUMMARY_COMPLETE".to_string(),
+            resource_id: "12345".to_string(),
+            title: "Payroll platform migration".to_string(),
+            priority: "HIGH".to_string(),
+            summary: "Summary".to_string(),
+            action_required: "Review".to_string(),
+            timestamp: Utc::now(),
+            metadata: serde_json::json!({
+                "tenant_id": "acme",
+                "deadline": "2026-11-20T12:00:00",
+                "document_hash": "abc",
+            }),
+        };
+        assert_eq!(
+            Fingerprint::of(&message),
+            Fingerprint {
+                tenant_id: "acme".to_string(),
+                ..fingerprint(20, Some("abc"))
+            }
+        );
+
+        let bare = SNSMessage {
+            metadata: serde_json::json!({ "deadline": null }),
+            ..message
+        };
+        assert_eq!(Fingerprint::of(&bare).tenant_id, "default");
+        assert_eq!(Fingerprint::of(&bare).deadline, None);
+    }
+}
@@ -0,0 +1,189 @@
+//! The email templates, compiled into the binary.
+//!
+//! HTML and plain-text bodies are rendered by separate registries: the HTML one escapes
+//! values, the text one must not, or an authority like "Health & Safety" would arrive as
+//! "Health &amp; Safety" in the text part.
+
+use anyhow::Result;
+use handlebars::Handlebars;
+use serde::Serialize;
+
+pub struct Templates {
+    html: Handlebars<'static>,
+    text: Handlebars<'static>,
+}
+
+impl Templates {
+    pub fn new() -> Result<Self> {
+        let mut html = Handlebars::new();
+        html.register_template_string("email", include_str!("../templates/email.hbs"))?;
+        html.register_template_string(
+            "early_interest",
+            include_str!("../templates/early_interest.hbs"),
+        )?;
+        html.register_template_string("ops", include_str!("../templates/ops.hbs"))?;
+
+        let mut text = Handlebars::new();
+        text.register_escape_fn(handlebars::no_escape);
+        text.register_template_string("email", include_str!("../templates/email.txt"))?;
+        text.register_template_string(
+            "early_interest",
+            include_str!("../templates/early_interest.txt"),
+        )?;
+
+        Ok(Self { html, text })
+    }
+
+    pub fn html<T: Serialize>(&self, name: &str, data: &T) -> Result<String> {
+        Ok(self.html.render(name, data)?)
+    }
+
+    pub fn text<T: Serialize>(&self, name: &str, data: &T) -> Result<String> {
+        Ok(self.text.render(name, data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EmailData, SNSMessage};
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    fn message(metadata: serde_json::Value) -> SNSMessage {
+        SNSMessage {
+            message_type: "AI_SUMMARY_COMPLETE".to_string(),
+            resource_id: "12345".to_string(),
+            title: "Payroll platform migration".to_string(),
+            priority: "HIGH".to_string(),
+            summary: "Summary".to_string(),
+            action_required: "Review".to_string(),
+            timestamp: Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap(),
+            metadata,
+        }
+    }
+
+    fn full_metadata() -> serde_json::Value {
+        json!({
+            "tenant_id": "default",
+            "contracting_authority": "Health & Safety Authority",
+            "estimated_value": "250000",
+            "ai_summary": "Migrate the payroll platform to a managed cloud service.",
+            "key_points": ["Cloud migration", "Three year term"],
+            "recommendation": "BID",
+            "confidence_assessment": "High",
+            "ml_prediction": {
+                "should_bid": true,
+                "confidence": 0.82,
+                "reasoning": "Strong code match",
+                "top_features": [
+                    { "name": "Procurement code count", "score": 0.35 },
+                    { "name": "Contracting authority history", "score": -0.08 }
+                ]
+            },
+            "detected_codes": [
+                { "code": "72000000", "description": "IT services" },
+                { "code": "99999999", "description": null }
+            ],
+            "eligibility": ["Minimum turnover of €500,000 in each of the last three years"],
+            "response_skeleton": null
+        })
+    }
+
+    #[test]
+    fn test_plain_text_sections() {
+        let data = EmailData::from_sns_message(&message(full_metadata())).unwrap();
+        let text = Templates::new().unwrap().text("email", &data).unwrap();
+
+        assert!(text.contains("Contracting Authority: Health & Safety Authority\n"));
+        assert!(text.contains("KEY POINTS\n----------\n• Cloud migration\n• Three year term\n"));
+        assert!(text.contains("- Procurement code count: +0.35\n"));
+        assert!(text.contains("- Contracting authority history: -0.08\n"));
+        assert!(text.contains(
+            "PROCUREMENT CODES\n-----------------\n72000000: IT services\n99999999: (not in the code catalogue)\n"
+        ));
+        assert!(text.contains(
+            "ELIGIBILITY REQUIREMENTS\n------------------------\nLines from the tender document - check them against the full text.\n- Minimum turnover"
+        ));
+        assert!(text.contains(
+            "FULL AI SUMMARY\n---------------\nMigrate the payroll platform to a managed cloud service.\n"
+        ));
+        assert!(!text.contains("DRAFT RESPONSE SKELETON"));
+    }
+
+    #[test]
+    fn test_plain_text_without_optional_sections() {
+        let data = EmailData::from_sns_message(&message(json!({}))).unwrap();
+        let text = Templates::new().unwrap().text("email", &data).unwrap();
+
+        assert!(text.contains("Contracting Authority: Unknown Authority\n"));
+        assert!(text.contains("FULL AI SUMMARY\n---------------\nSummary\n"));
+        assert!(!text.contains("PROCUREMENT CODES"));
+        assert!(!text.contains("ELIGIBILITY REQUIREMENTS"));
+        assert!(!text.contains("ML ANALYSIS"));
+    }
+
+    #[test]
+    fn test_html_escapes_and_collapses_summary() {
+        let data = EmailData::from_sns_message(&message(full_metadata())).unwrap();
+        let html = Templates::new().unwrap().html("email", &data).unwrap();
+
+        assert!(html.contains("Health &amp; Safety Authority"));
+        assert!(html.contains("<summary>📄 Full AI summary</summary>"));
+        assert!(html.contains("<span class=\"detail-label code\">72000000</span>"));
+    }
+
+    #[test]
+    fn test_buyer_contact_is_shown_unredacted() {
+        let mut metadata = full_metadata();
+        metadata["buyer_contact"] = json!({
+            "name": "Máire Ní Bhriain",
+            "email": "procurement@kerrycoco.ie",
+            "phone": "+353 66 718 3500"
+        });
+        let data = EmailData::from_sns_message(&message(metadata)).unwrap();
+        let templates = Templates::new().unwrap();
+
+        let text = templates.text("email", &data).unwrap();
+        assert!(text.contains(
+            "Buyer Contact: Máire Ní Bhriain <procurement@kerrycoco.ie> +353 66 718 3500\n"
+        ));
+        let html = templates.html("early_interest", &data).unwrap();
+        assert!(html.contains("<a href=\"mailto:procurement@kerrycoco.ie\">"));
+
+        let data = EmailData::from_sns_message(&message(full_metadata())).unwrap();
+        assert!(!templates
+            .text("email", &data)
+            .unwrap()
+            .contains("Buyer Contact"));
+    }
+
+    #[test]
+    fn test_preview_preheader() {
+        let mut data = EmailData::from_sns_message(&message(full_metadata())).unwrap();
+        let templates = Templates::new().unwrap();
+        assert!(!templates.html("email", &data).unwrap().contains("mso-hide"));
+
+        data.preview = "Payroll & HR cloud migration…".to_string();
+        for template in ["email", "early_interest"] {
+            let html = templates.html(template, &data).unwrap();
+            assert!(html.contains("mso-hide: all;\">Payroll &amp; HR cloud migration…</div>"));
+        }
+    }
+
+    #[test]
+    fn test_metadata_is_typed() {
+        // Values sent as numbers and metadata sent as a JSON string both still parse
+        let data =
+            EmailData::from_sns_message(&message(json!({ "estimated_value": 250000 }))).unwrap();
+        assert_eq!(data.estimated_value.as_deref(), Some("250000"));
+
+        let data =
+            EmailData::from_sns_message(&message(json!(full_metadata().to_string()))).unwrap();
+        assert_eq!(data.detected_codes.len(), 2);
+        assert_eq!(data.prediction_confidence, Some(82.0));
+
+        let malformed = message(json!({ "detected_codes": "72000000" }));
+        assert!(EmailData::from_sns_message(&malformed).is_err());
+    }
+}
@@ -1,13 +1,17 @@
-use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 use anyhow::Result;
 use std::env;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
     pub notification_emails: Vec<String>,
+    pub ops_emails: Vec<String>, // Ops summaries (e.g. scraper runs); falls back to notification_emails
     pub from_email: String,
     pub aws_region: String,
+    pub test_inbox: Option<String>, // Dev only: every email is redirected here
+    pub feedback_base_url: Option<String>, // tender_api base URL; feedback links are added when this and the key are set
+    pub feedback_signing_key: Option<String>,
+    pub pdf_thumbnails: bool, // Embed pdf_processing's first-page preview when one was rendered
 }
 
 impl Config {
@@ -35,12 +39,36 @@ impl Config {
                 .collect()
         };
 
+        let ops_emails: Vec<String> = env::var("OPS_NOTIFICATION_EMAILS")
+            .map(|s| s.split(',')
+                .map(|e| e.trim().to_string())
+                .filter(|e| !e.is_empty())
+                .collect())
+            .unwrap_or_default();
+
         let from_email = env::var("FROM_EMAIL")
             .unwrap_or_else(|_| "etenders-noreply@robertsweetman.com".to_string());
 
         let aws_region = env::var("AWS_REGION")
             .unwrap_or_else(|_| "eu-west-1".to_string());
 
+        let test_inbox = env::var("TEST_INBOX")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+
+        let feedback_base_url = env::var("FEEDBACK_BASE_URL")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        let feedback_signing_key = env::var("FEEDBACK_SIGNING_KEY")
+            .ok()
+            .filter(|s| !s.trim().is_empty());
+
+        let pdf_thumbnails = env::var("EMAIL_PDF_THUMBNAILS")
+            .map(|v| !matches!(v.trim().to_lowercase().as_str(), "false" | "0" | "no"))
+            .unwrap_or(true);
+
         // Log the email configuration for debugging
         eprintln!("Email configuration:");
         eprintln!("  From email: {}", from_email);
@@ -49,22 +77,40 @@ impl Config {
 
         Ok(Config {
             notification_emails,
+            ops_emails,
             from_email,
             aws_region,
+            test_inbox,
+            feedback_base_url,
+            feedback_signing_key,
+            pdf_thumbnails,
         })
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct SNSMessage {
-    pub message_type: String,
-    pub resource_id: String,
+pub use tender_core::{BuyerContact, SNSMessage};
+
+/// Template data for ops summaries
+#[derive(Debug, Serialize, Clone)]
+pub struct OpsEmailData {
+    pub subject: String,
     pub title: String,
-    pub priority: String,
     pub summary: String,
     pub action_required: String,
-    pub timestamp: DateTime<Utc>,
-    pub metadata: serde_json::Value,
+    pub timestamp: String,
+    pub environment_banner: Option<String>,
+    pub lang: String,
+}
+
+/// Draft response skeleton ai_summary generates for BID recommendations, when enabled
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ResponseSkeleton {
+    #[serde(default)]
+    pub compliance_checklist: Vec<String>,
+    #[serde(default)]
+    pub section_headings: Vec<String>,
+    #[serde(default)]
+    pub clarification_questions: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -77,100 +123,217 @@ pub struct EmailData {
     pub priority: String,
     pub prediction_confidence: Option<f64>,
     pub deadline: Option<String>,
+    pub clarification_deadline: Option<String>, // Last date for clarification questions, when the PDF gives one
+    pub buyer_contact: Option<BuyerContact>, // Who to send clarification questions to, from the notice page
     pub estimated_value: Option<String>,
     pub timestamp: String,
     pub portal_link: String,
     pub ai_summary: String,
+    pub preview: String, // Inbox preview text, rendered as the hidden preheader; set by EmailService
     pub key_points: Vec<String>,
     pub recommendation: String,
     pub confidence_assessment: String,
     pub pdf_url: Option<String>,
     pub ml_reasoning: Option<String>,
+    pub top_features: Vec<FeatureLine>, // ML features that moved the score most
+    pub detected_codes: Vec<DetectedCode>,
+    pub eligibility: Vec<String>,
+    pub win_probability: Option<f64>, // Chance of winning if we bid (percent), separate from the match confidence
+    pub win_reasons: Vec<String>,
+    pub ticket_key: Option<String>,
+    pub ticket_url: Option<String>,
+    pub response_skeleton: Option<ResponseSkeleton>,
+    pub environment_banner: Option<String>, // Set for non-prod so test emails are obvious
+    pub tenant_name: Option<String>, // Set when the tender was evaluated for a non-default tenant
+    pub watch_rule: Option<String>, // Provenance when an always-notify watch rule forced the email
+    pub feedback_not_relevant_url: Option<String>, // Signed feedback links, set when feedback is configured
+    pub feedback_good_call_url: Option<String>,
+    pub thumbnail_cid: Option<String>, // Content-ID of the inline first-page preview, when attached
+    pub lang: String, // BCP 47 tag of the locale the values and dates were formatted for
+    pub procedure_label: Option<String>, // e.g. "Prior information notice"; set by ai_summary
+    #[serde(skip)]
+    pub one_liner: Option<String>, // Claude's one-sentence summary, which the preview prefers
+    #[serde(skip)]
+    pub early_interest: bool, // PIN or DPS notice - rendered with the early-interest templates
+    #[serde(skip)]
+    pub tenant_id: String,
+    #[serde(skip)]
+    pub tenant_recipients: Vec<String>, // Tenant's own recipients; overrides NOTIFICATION_EMAILS when non-empty
 }
 
 impl EmailData {
     pub fn from_sns_message(msg: &SNSMessage) -> Result<Self, String> {
-        // Parse metadata JSON string to serde_json::Value first
-        let metadata: serde_json::Value = if let serde_json::Value::String(metadata_str) = &msg.metadata {
-            serde_json::from_str(metadata_str)
-                .map_err(|e| format!("Failed to parse metadata string: {}", e))?
-        } else {
-            // If it's already a JSON value, use it directly
-            msg.metadata.clone()
-        };
+        let metadata = NotificationMetadata::from_value(&msg.metadata)?;
 
         // Debug logging to see what we're working with
         eprintln!("🔍 SNS Message Debug:");
         eprintln!("   Summary: '{}'", msg.summary);
-        eprintln!("   Metadata keys: {:?}", metadata.as_object().map(|obj| obj.keys().collect::<Vec<_>>()));
-        eprintln!("   AI Summary from metadata: {:?}", metadata.get("ai_summary"));
-        eprintln!("   Recommendation from metadata: {:?}", metadata.get("recommendation"));
-        eprintln!("   Key points from metadata: {:?}", metadata.get("key_points"));
+        eprintln!("   AI Summary from metadata: {:?}", metadata.ai_summary);
+        eprintln!("   Recommendation from metadata: {:?}", metadata.recommendation);
+        eprintln!("   Key points from metadata: {:?}", metadata.key_points);
+
+        let early_interest = metadata.template.as_deref() == Some("early_interest");
+        let ml_prediction = metadata.ml_prediction.unwrap_or_default();
+        let win_estimate = metadata.win_estimate.unwrap_or_default();
+        let tenant_id = metadata.tenant_id.unwrap_or_else(|| "default".to_string());
 
         Ok(EmailData {
-            subject: "Tender Opportunity".to_string(), // Fixed header as requested
+            subject: if early_interest { "Early Market Notice" } else { "Tender Opportunity" }.to_string(), // Fixed headers as requested
             resource_id: msg.resource_id.clone(),
             tender_title: msg.title.clone(),
-            contracting_authority: metadata.get("contracting_authority")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unknown Authority")
-                .to_string(),
+            contracting_authority: metadata.contracting_authority
+                .unwrap_or_else(|| "Unknown Authority".to_string()),
             summary: msg.summary.clone(), // This should be the simple text summary
             priority: msg.priority.clone(),
-            prediction_confidence: metadata.get("ml_prediction")
-                .and_then(|ml| ml.get("confidence"))
-                .and_then(|v| v.as_f64())
+            prediction_confidence: ml_prediction.confidence
                 .map(|v| (v * 100.0).round()), // Convert to percentage and round to nearest whole number
-            deadline: metadata.get("deadline")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            estimated_value: metadata.get("estimated_value")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
+            deadline: metadata.deadline,
+            clarification_deadline: metadata.clarification_deadline,
+            buyer_contact: metadata.buyer_contact,
+            estimated_value: metadata.estimated_value,
             timestamp: msg.timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
-            portal_link: metadata.get("portal_link")
-                .and_then(|v| v.as_str())
-                .unwrap_or(&format!("https://etenders.gov.ie/epps/opportunity/opportunityDetailAction.do?opportunityId={}", msg.resource_id))
-                .to_string(),
-            ai_summary: metadata.get("ai_summary")
-                .and_then(|v| v.as_str())
-                .unwrap_or(&msg.summary) // Fallback to message summary
-                .to_string(),
-            key_points: metadata.get("key_points")
-                .and_then(|v| v.as_array())
-                .map(|arr| arr.iter()
-                    .filter_map(|item| item.as_str())
-                    .map(|s| s.to_string())
-                    .collect())
+            portal_link: metadata.portal_link
+                .unwrap_or_else(|| format!("https://etenders.gov.ie/epps/opportunity/opportunityDetailAction.do?opportunityId={}", msg.resource_id)),
+            ai_summary: metadata.ai_summary
+                .unwrap_or_else(|| msg.summary.clone()), // Fallback to message summary
+            preview: String::new(),
+            key_points: metadata.key_points
                 .unwrap_or_else(|| {
                     eprintln!("⚠️ No key_points found in metadata, using default");
                     vec!["See summary for details".to_string()]
                 }),
-            recommendation: metadata.get("recommendation")
-                .and_then(|v| v.as_str())
+            recommendation: metadata.recommendation
                 .unwrap_or_else(|| {
                     eprintln!("⚠️ No recommendation found in metadata");
-                    "See summary"
-                })
-                .to_string(),
-            confidence_assessment: metadata.get("confidence_assessment")
-                .and_then(|v| v.as_str())
+                    "See summary".to_string()
+                }),
+            confidence_assessment: metadata.confidence_assessment
                 .unwrap_or_else(|| {
                     eprintln!("⚠️ No confidence_assessment found in metadata");
-                    "Assessment pending"
-                })
-                .to_string(),
-            pdf_url: metadata.get("pdf_url")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            ml_reasoning: metadata.get("ml_prediction")
-                .and_then(|ml| ml.get("reasoning"))
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
+                    "Assessment pending".to_string()
+                }),
+            pdf_url: metadata.pdf_url,
+            ml_reasoning: ml_prediction.reasoning,
+            top_features: ml_prediction.top_features.unwrap_or_default()
+                .into_iter()
+                .map(|f| FeatureLine { name: f.name, score: format!("{:+.2}", f.score) })
+                .collect(),
+            detected_codes: metadata.detected_codes.unwrap_or_default(),
+            eligibility: metadata.eligibility.unwrap_or_default(),
+            win_probability: win_estimate.probability
+                .map(|v| (v * 100.0).round()),
+            win_reasons: win_estimate.reasons.unwrap_or_default(),
+            ticket_key: metadata.ticket_key,
+            ticket_url: metadata.ticket_url,
+            response_skeleton: metadata.response_skeleton,
+            environment_banner: None,
+            feedback_not_relevant_url: None,
+            feedback_good_call_url: None,
+            thumbnail_cid: None,
+            lang: crate::localization::DEFAULT_LOCALE.to_string(),
+            procedure_label: metadata.procedure_label,
+            one_liner: metadata.one_liner,
+            early_interest,
+            watch_rule: metadata.watch_rule,
+            tenant_name: Some(&tenant_id)
+                .filter(|id| *id != "default")
+                .map(|id| metadata.tenant_name.unwrap_or_else(|| id.clone())),
+            tenant_id,
+            tenant_recipients: metadata.notification_emails.unwrap_or_default()
+                .into_iter()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
         })
     }
 }
 
+/// The metadata ai_summary sends with AI_SUMMARY_COMPLETE. Everything is optional so a
+/// message from an older ai_summary (or a hand-queued one) still renders
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct NotificationMetadata {
+    pub tenant_id: Option<String>,
+    pub tenant_name: Option<String>,
+    pub notification_emails: Option<Vec<String>>,
+    pub watch_rule: Option<String>,
+    pub contracting_authority: Option<String>,
+    #[serde(deserialize_with = "string_or_number")]
+    pub estimated_value: Option<String>, // A decimal string from ai_summary; plain numbers are accepted too
+    pub deadline: Option<String>,
+    pub clarification_deadline: Option<String>,
+    pub buyer_contact: Option<BuyerContact>,
+    pub ml_prediction: Option<MlPrediction>,
+    pub win_estimate: Option<WinEstimate>,
+    pub detected_codes: Option<Vec<DetectedCode>>,
+    pub eligibility: Option<Vec<String>>, // Eligibility requirement lines found in the PDF
+    pub ai_summary: Option<String>,
+    pub one_liner: Option<String>,
+    pub key_points: Option<Vec<String>>,
+    pub recommendation: Option<String>,
+    pub confidence_assessment: Option<String>,
+    pub pdf_url: Option<String>,
+    pub portal_link: Option<String>,
+    pub ticket_key: Option<String>,
+    pub ticket_url: Option<String>,
+    pub response_skeleton: Option<ResponseSkeleton>,
+    pub template: Option<String>, // "early_interest" for PINs and DPS notices
+    pub procedure_label: Option<String>,
+}
+
+impl NotificationMetadata {
+    /// Older senders put the metadata in as a JSON string rather than an object
+    pub fn from_value(value: &serde_json::Value) -> Result<Self, String> {
+        match value {
+            serde_json::Value::String(metadata_str) => serde_json::from_str(metadata_str),
+            value => serde_json::from_value(value.clone()),
+        }
+        .map_err(|e| format!("Failed to parse metadata: {}", e))
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct MlPrediction {
+    pub confidence: Option<f64>,
+    pub reasoning: Option<String>,
+    pub top_features: Option<Vec<FeatureScore>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeatureScore {
+    pub name: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct WinEstimate {
+    pub probability: Option<f64>,
+    pub reasons: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DetectedCode {
+    pub code: String,
+    pub description: Option<String>, // From the code catalogue; None for codes it doesn't know
+}
+
+/// An ML feature as shown in the email, score signed to two decimals
+#[derive(Debug, Serialize, Clone)]
+pub struct FeatureLine {
+    pub name: String,
+    pub score: String,
+}
+
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(match Option::<serde_json::Value>::deserialize(deserializer)? {
+        Some(serde_json::Value::String(s)) => Some(s),
+        Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
 #[derive(Debug)]
 pub enum NotificationPriority {
     Urgent,
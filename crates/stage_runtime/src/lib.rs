@@ -0,0 +1,97 @@
+//! Runs a queue-driven stage as a Lambda function or as a long-lived service.
+//!
+//! pdf_processing, ml_bid_predictor, ai_summary and sns_notification implement `Stage`
+//! and hand it to `run`, which picks the runtime from STAGE_RUNTIME:
+//!
+//! - unset or `lambda`: the Lambda runtime, invoked by the SQS event source mapping or
+//!   Step Functions as before
+//! - `service`: `service::run` polls the stage's queue itself, for ECS/EC2 tasks doing
+//!   heavy backfills where the 15 minute limit and cold starts get in the way
+//!
+//! Either way the stage sees the same `StageEvent` and returns the same response (an
+//! `SqsBatchResponse` for a batch), so its batch item failure and Step Functions handling
+//! don't change. The deadline it is given is the Lambda deadline, or the end of the
+//! service's visibility timeout - past which SQS hands the messages to someone else.
+
+use pipeline_contract::StageEvent;
+use resource_discovery::Resource;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tracing::Instrument;
+
+pub mod service;
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// One queue-driven pipeline stage
+pub trait Stage: 'static {
+    /// The queue the stage consumes, polled when it runs as a service
+    fn queue(&self) -> Resource;
+
+    /// Process one invocation (an SQS batch or a Step Functions task), starting no work
+    /// that can't finish by `deadline`
+    fn handle(
+        &self,
+        event: StageEvent,
+        deadline: SystemTime,
+    ) -> impl Future<Output = Result<Value, Error>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Runtime {
+    Lambda,
+    Service,
+}
+
+impl Runtime {
+    pub fn parse(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim().to_lowercase()).as_deref() {
+            Some("service") => Runtime::Service,
+            _ => Runtime::Lambda,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::parse(std::env::var("STAGE_RUNTIME").ok().as_deref())
+    }
+}
+
+/// Run `stage` until the Lambda runtime or the service stops
+pub async fn run<S: Stage>(stage: S) -> Result<(), Error> {
+    match Runtime::from_env() {
+        Runtime::Lambda => run_lambda(stage).await,
+        Runtime::Service => service::run(stage).await,
+    }
+}
+
+async fn run_lambda<S: Stage>(stage: S) -> Result<(), Error> {
+    let stage = Arc::new(stage);
+    let environment = environment::Environment::from_env();
+    lambda_runtime::run(lambda_runtime::service_fn(
+        move |event: lambda_runtime::LambdaEvent<StageEvent>| {
+            let stage = stage.clone();
+            let span = environment.span();
+            async move {
+                stage
+                    .handle(event.payload, event.context.deadline())
+                    .instrument(span)
+                    .await
+            }
+        },
+    ))
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_defaults_to_lambda() {
+        assert_eq!(Runtime::parse(None), Runtime::Lambda);
+        assert_eq!(Runtime::parse(Some("lambda")), Runtime::Lambda);
+        assert_eq!(Runtime::parse(Some(" Service ")), Runtime::Service);
+    }
+}
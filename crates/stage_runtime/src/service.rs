@@ -0,0 +1,265 @@
+//! The SQS polling loop behind STAGE_RUNTIME=service.
+//!
+//! It does for a long-lived task what the event source mapping does for the function:
+//! receives up to STAGE_SERVICE_BATCH_SIZE messages (default 10) with long polling, hands
+//! them to the stage as one SQS batch, and deletes the ones not reported back as batch item
+//! failures. A batch that fails outright is left alone, so like a failed invocation it
+//! comes back after the visibility timeout (STAGE_SERVICE_VISIBILITY_SECS, default 900) and
+//! ends up in the dead-letter queue after the queue's maxReceiveCount.
+//!
+//! The queue is the stage's own (`Stage::queue`, overridable with its usual `*_QUEUE_URL`
+//! variable). SIGTERM - an ECS task being stopped - lets the batch in hand finish first.
+
+use aws_lambda_events::event::sqs::{SqsBatchResponse, SqsEvent, SqsMessage, SqsMessageAttribute};
+use aws_sdk_sqs::types::{DeleteMessageBatchRequestEntry, Message, MessageSystemAttributeName};
+use aws_sdk_sqs::Client as SqsClient;
+use pipeline_contract::StageEvent;
+use resource_discovery::ResourceDiscovery;
+use serde_json::Value;
+use std::time::{Duration, SystemTime};
+use tokio::signal::unix::{signal, SignalKind};
+use tracing::{error, info, warn, Instrument};
+
+use crate::{Error, Stage};
+
+const DEFAULT_BATCH_SIZE: i32 = 10;
+const DEFAULT_VISIBILITY_SECS: u64 = 900;
+/// Longest long poll SQS allows
+const WAIT_SECONDS: i32 = 20;
+/// Pause after a failed receive before polling again
+const RECEIVE_BACKOFF: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceConfig {
+    /// Messages per batch, 1 to 10
+    pub batch_size: i32,
+    /// How long a received batch is hidden from other consumers; also the stage's deadline
+    pub visibility_timeout: Duration,
+}
+
+impl ServiceConfig {
+    pub fn from_env() -> Self {
+        let batch_size = std::env::var("STAGE_SERVICE_BATCH_SIZE")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_BATCH_SIZE)
+            .clamp(1, 10);
+        let visibility_secs = std::env::var("STAGE_SERVICE_VISIBILITY_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(DEFAULT_VISIBILITY_SECS);
+        Self {
+            batch_size,
+            visibility_timeout: Duration::from_secs(visibility_secs),
+        }
+    }
+}
+
+/// Poll the stage's queue until SIGTERM
+pub async fn run<S: Stage>(stage: S) -> Result<(), Error> {
+    let config = ServiceConfig::from_env();
+    let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        .load()
+        .await;
+    let queue_url = ResourceDiscovery::new(&aws_config)
+        .resolve(stage.queue())
+        .await
+        .map_err(|e| Error::from(format!("Stage queue not found: {}", e)))?;
+    let sqs = SqsClient::new(&aws_config);
+    let environment = environment::Environment::from_env();
+    let mut terminate = signal(SignalKind::terminate())?;
+    info!("🛠️ Running as a service on {} ({:?})", queue_url, config);
+
+    loop {
+        let receive = sqs
+            .receive_message()
+            .queue_url(&queue_url)
+            .max_number_of_messages(config.batch_size)
+            .wait_time_seconds(WAIT_SECONDS)
+            .visibility_timeout(config.visibility_timeout.as_secs() as i32)
+            .message_attribute_names("All")
+            .message_system_attribute_names(MessageSystemAttributeName::All)
+            .send();
+        let received = tokio::select! {
+            _ = terminate.recv() => {
+                info!("SIGTERM received, stopping");
+                return Ok(());
+            }
+            received = receive => received,
+        };
+        let messages = match received {
+            Ok(output) => output.messages.unwrap_or_default(),
+            Err(e) => {
+                warn!("⚠️ Failed to receive from {}: {}", queue_url, e);
+                tokio::time::sleep(RECEIVE_BACKOFF).await;
+                continue;
+            }
+        };
+        if messages.is_empty() {
+            continue;
+        }
+
+        let deadline = SystemTime::now() + config.visibility_timeout;
+        let event = StageEvent::Sqs(sqs_event(&messages));
+        match stage
+            .handle(event, deadline)
+            .instrument(environment.span())
+            .await
+        {
+            Ok(response) => delete(&sqs, &queue_url, &acknowledged(&messages, &response)).await,
+            Err(e) => error!(
+                "❌ Batch of {} failed, left for redelivery: {}",
+                messages.len(),
+                e
+            ),
+        }
+    }
+}
+
+/// The batch as the event source mapping would deliver it
+fn sqs_event(messages: &[Message]) -> SqsEvent {
+    let records = messages
+        .iter()
+        .map(|message| {
+            let mut record = SqsMessage {
+                message_id: message.message_id.clone(),
+                receipt_handle: message.receipt_handle.clone(),
+                body: message.body.clone(),
+                md5_of_body: message.md5_of_body.clone(),
+                md5_of_message_attributes: message.md5_of_message_attributes.clone(),
+                event_source: Some("aws:sqs".to_string()),
+                ..Default::default()
+            };
+            for (name, value) in message.attributes.iter().flatten() {
+                record
+                    .attributes
+                    .insert(name.as_str().to_string(), value.clone());
+            }
+            for (name, value) in message.message_attributes.iter().flatten() {
+                let attribute = SqsMessageAttribute {
+                    string_value: value.string_value.clone(),
+                    data_type: Some(value.data_type.clone()),
+                    ..Default::default()
+                };
+                record.message_attributes.insert(name.clone(), attribute);
+            }
+            record
+        })
+        .collect();
+    SqsEvent { records }
+}
+
+/// Messages the stage finished with: all but its batch item failures. A response that
+/// isn't a batch response acknowledges the whole batch, as Lambda does
+fn acknowledged<'a>(messages: &'a [Message], response: &Value) -> Vec<&'a Message> {
+    let failures: Vec<String> = serde_json::from_value::<SqsBatchResponse>(response.clone())
+        .map(|response| {
+            response
+                .batch_item_failures
+                .into_iter()
+                .map(|failure| failure.item_identifier)
+                .collect()
+        })
+        .unwrap_or_default();
+    messages
+        .iter()
+        .filter(|message| {
+            !message
+                .message_id
+                .as_ref()
+                .is_some_and(|id| failures.contains(id))
+        })
+        .collect()
+}
+
+async fn delete(sqs: &SqsClient, queue_url: &str, messages: &[&Message]) {
+    let entries: Vec<DeleteMessageBatchRequestEntry> = messages
+        .iter()
+        .enumerate()
+        .filter_map(|(index, message)| {
+            DeleteMessageBatchRequestEntry::builder()
+                .id(index.to_string())
+                .receipt_handle(message.receipt_handle.clone()?)
+                .build()
+                .ok()
+        })
+        .collect();
+    if entries.is_empty() {
+        return;
+    }
+
+    match sqs
+        .delete_message_batch()
+        .queue_url(queue_url)
+        .set_entries(Some(entries))
+        .send()
+        .await
+    {
+        Ok(output) if !output.failed.is_empty() => warn!(
+            "⚠️ {} processed messages couldn't be deleted and will be redelivered",
+            output.failed.len()
+        ),
+        Ok(_) => {}
+        Err(e) => warn!(
+            "⚠️ Failed to delete {} processed messages, they will be redelivered: {}",
+            messages.len(),
+            e
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_sqs::types::MessageAttributeValue;
+
+    fn message(id: &str) -> Message {
+        Message::builder()
+            .message_id(id)
+            .receipt_handle(format!("receipt-{}", id))
+            .body(format!("{{\"resource_id\": {}}}", id.len()))
+            .message_attributes(
+                "correlation_id",
+                MessageAttributeValue::builder()
+                    .data_type("String")
+                    .string_value("run-1")
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_batch_is_delivered_like_the_event_source_mapping() {
+        let event = sqs_event(&[message("a")]);
+        let record = &event.records[0];
+
+        assert_eq!(record.message_id.as_deref(), Some("a"));
+        assert_eq!(record.receipt_handle.as_deref(), Some("receipt-a"));
+        assert_eq!(
+            record.message_attributes["correlation_id"]
+                .string_value
+                .as_deref(),
+            Some("run-1")
+        );
+        let messages = StageEvent::Sqs(event).into_messages();
+        assert_eq!(messages[0].routing.correlation_id.as_deref(), Some("run-1"));
+    }
+
+    #[test]
+    fn test_batch_item_failures_are_kept() {
+        let messages = [message("a"), message("b"), message("c")];
+        let response = serde_json::json!({ "batchItemFailures": [{ "itemIdentifier": "b" }] });
+
+        let ids: Vec<_> = acknowledged(&messages, &response)
+            .iter()
+            .map(|m| m.message_id.as_deref().unwrap())
+            .collect();
+        assert_eq!(ids, vec!["a", "c"]);
+        assert_eq!(
+            acknowledged(&messages, &serde_json::json!({ "success": true })).len(),
+            3
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! Bid calendar: the open BID-recommended tenders grouped by the week their deadline falls
+//! in, with an estimate of the bid-writing effort each week needs.
+//!
+//! There is no effort estimate per tender, so it comes from the value band (the same bands
+//! as the anonymized export): bigger contracts mean longer responses. A tender without a
+//! value counts as medium. The figures are for spotting crunch weeks, not for planning.
+
+use crate::database::Database;
+use crate::dataset::value_band;
+use crate::feed::escape_xml;
+use crate::types::{
+    Recommendation, SortDirection, Tender, TenderFilter, TenderSort, TenderSortField,
+};
+use anyhow::Result;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+pub const DEFAULT_WEEKS: i64 = 12;
+pub const MAX_WEEKS: i64 = 52;
+const PAGE_SIZE: i64 = 500;
+
+/// Effort band and estimated bid-writing days per value band; unknown values count as medium
+const EFFORT_BANDS: &[(&str, &str, u32)] = &[
+    ("<25k", "small", 2),
+    ("25k-100k", "medium", 5),
+    ("100k-500k", "large", 10),
+    ("500k-1m", "major", 15),
+    (">1m", "major", 15),
+    ("", "medium", 5),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CalendarTender {
+    pub resource_id: i64,
+    pub title: String,
+    pub contracting_authority: String,
+    pub deadline: NaiveDateTime,
+    pub value_band: &'static str,
+    pub effort_band: &'static str,
+    pub effort_days: u32,
+    pub portal_link: String,
+    /// BID was forced by hand rather than recommended by Claude
+    pub forced: bool,
+}
+
+/// The tenders due in one week (Monday to Sunday), soonest deadline first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CalendarWeek {
+    pub week_start: NaiveDate,
+    pub tender_count: usize,
+    pub effort_days: u32,
+    /// Tender count per effort band
+    pub effort_bands: BTreeMap<&'static str, usize>,
+    pub tenders: Vec<CalendarTender>,
+}
+
+/// `weeks` from the query string: how far ahead the calendar reaches
+pub fn weeks_param(params: &HashMap<String, String>) -> Result<i64, String> {
+    match params.get("weeks").map(|w| w.parse::<i64>()) {
+        None => Ok(DEFAULT_WEEKS),
+        Some(Ok(weeks)) if (1..=MAX_WEEKS).contains(&weeks) => Ok(weeks),
+        Some(_) => Err(format!("weeks must be 1-{}", MAX_WEEKS)),
+    }
+}
+
+/// Effort band and days for a value band
+pub fn effort(band: &str) -> (&'static str, u32) {
+    EFFORT_BANDS
+        .iter()
+        .find(|(value_band, _, _)| *value_band == band)
+        .map(|(_, effort_band, days)| (*effort_band, *days))
+        .unwrap_or(("medium", 5))
+}
+
+/// Monday of the deadline's week
+fn week_start(deadline: NaiveDateTime) -> NaiveDate {
+    let date = deadline.date();
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Group tenders with a deadline into weeks, earliest week first
+pub fn group_by_week(tenders: &[Tender]) -> Vec<CalendarWeek> {
+    let mut weeks: BTreeMap<NaiveDate, Vec<CalendarTender>> = BTreeMap::new();
+    for tender in tenders {
+        let Some(deadline) = tender.deadline else {
+            continue;
+        };
+        let value_band = value_band(tender.value.as_deref().and_then(|v| v.parse().ok()));
+        let (effort_band, effort_days) = effort(value_band);
+        weeks
+            .entry(week_start(deadline))
+            .or_default()
+            .push(CalendarTender {
+                resource_id: tender.resource_id,
+                title: tender.title.clone(),
+                contracting_authority: tender.contracting_authority.clone(),
+                deadline,
+                value_band,
+                effort_band,
+                effort_days,
+                portal_link: tender.portal_link.clone(),
+                forced: tender
+                    .manual_override
+                    .as_ref()
+                    .is_some_and(|o| o.recommendation.is_some()),
+            });
+    }
+
+    weeks
+        .into_iter()
+        .map(|(week_start, mut tenders)| {
+            tenders.sort_by_key(|t| (t.deadline, t.resource_id));
+            let mut effort_bands = BTreeMap::new();
+            for tender in &tenders {
+                *effort_bands.entry(tender.effort_band).or_insert(0) += 1;
+            }
+            CalendarWeek {
+                week_start,
+                tender_count: tenders.len(),
+                effort_days: tenders.iter().map(|t| t.effort_days).sum(),
+                effort_bands,
+                tenders,
+            }
+        })
+        .collect()
+}
+
+/// BID-recommended tenders with a deadline between `now` and `weeks` weeks ahead
+pub async fn fetch_open_bids(
+    database: &Database,
+    now: NaiveDateTime,
+    weeks: i64,
+) -> Result<Vec<Tender>> {
+    let filter = TenderFilter {
+        recommendation: Some(Recommendation::Bid),
+        deadline_after: Some(now),
+        deadline_before: Some(now + Duration::weeks(weeks)),
+        ..Default::default()
+    };
+    let sort = TenderSort {
+        field: TenderSortField::Deadline,
+        direction: SortDirection::Asc,
+    };
+
+    let mut tenders = Vec::new();
+    let mut offset = 0;
+    loop {
+        let (page, total) = database
+            .list_tenders(&filter, sort, PAGE_SIZE, offset)
+            .await?;
+        let page_len = page.len() as i64;
+        tenders.extend(page);
+        offset += page_len;
+
+        if page_len == 0 || offset >= total {
+            break;
+        }
+    }
+    Ok(tenders)
+}
+
+/// Dashboard page: one row per week with a bar scaled to the busiest week
+pub fn render_html(weeks: &[CalendarWeek]) -> String {
+    let busiest = weeks
+        .iter()
+        .map(|w| w.effort_days)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let mut rows = String::new();
+    for week in weeks {
+        let bands = week
+            .effort_bands
+            .iter()
+            .map(|(band, count)| format!("{} {}", count, band))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let tenders = week
+            .tenders
+            .iter()
+            .map(|t| {
+                format!(
+                    "<li>{} <a href=\"{}\">{}</a> - {} ({}){}</li>",
+                    t.deadline.format("%a %d %b %H:%M"),
+                    escape_xml(&t.portal_link),
+                    escape_xml(&t.title),
+                    escape_xml(&t.contracting_authority),
+                    t.effort_band,
+                    if t.forced { " - forced BID" } else { "" }
+                )
+            })
+            .collect::<String>();
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td><div style=\"background: #c0392b; height: 12px; width: {}%;\"></div>{} days</td><td><ul>{}</ul></td></tr>\n",
+            week.week_start.format("%d %b %Y"),
+            week.tender_count,
+            bands,
+            week.effort_days * 100 / busiest,
+            week.effort_days,
+            tenders
+        ));
+    }
+
+    let table = if weeks.is_empty() {
+        "<p>No open BID-recommended tenders in this period.</p>".to_string()
+    } else {
+        format!(
+            "<table>\n<tr><th>Week of</th><th>Tenders</th><th>Effort bands</th><th>Estimated effort</th><th>Deadlines</th></tr>\n{}</table>",
+            rows
+        )
+    };
+
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Bid calendar</title></head>\n<body style=\"font-family: sans-serif; margin: 40px;\">\n<h2>Bid calendar</h2>\n<p>Open BID-recommended tenders by deadline week. Effort is estimated from the contract value band.</p>\n{}\n</body></html>",
+        table
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Notification;
+
+    fn tender(resource_id: i64, deadline: &str, value: Option<&str>) -> Tender {
+        Tender {
+            resource_id,
+            title: format!("Tender {}", resource_id),
+            contracting_authority: "HSE".to_string(),
+            info: String::new(),
+            published: None,
+            deadline: NaiveDateTime::parse_from_str(deadline, "%Y-%m-%d %H:%M").ok(),
+            procedure: String::new(),
+            status: String::new(),
+            pdf_url: String::new(),
+            value: value.map(str::to_string),
+            bid: None,
+            portal_link: String::new(),
+            tags: Vec::new(),
+            prediction: None,
+            summary: None,
+            notification: Notification {
+                sent: false,
+                sent_at: None,
+            },
+            manual_override: None,
+        }
+    }
+
+    #[test]
+    fn test_effort_from_value_band() {
+        assert_eq!(effort(value_band(Some(12_000.0))), ("small", 2));
+        assert_eq!(effort(value_band(Some(250_000.0))), ("large", 10));
+        assert_eq!(effort(value_band(Some(4_000_000.0))), ("major", 15));
+        assert_eq!(effort(value_band(None)), ("medium", 5));
+    }
+
+    #[test]
+    fn test_group_by_week() {
+        let tenders = vec![
+            tender(1, "2026-11-06 12:00", Some("250000")), // Friday
+            tender(2, "2026-11-02 09:00", Some("20000")),  // Monday, same week
+            tender(3, "2026-11-09 12:00", None),           // next Monday
+            tender(4, "not a date", Some("20000")),
+        ];
+        let weeks = group_by_week(&tenders);
+
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(
+            weeks[0].week_start,
+            NaiveDate::from_ymd_opt(2026, 11, 2).unwrap()
+        );
+        assert_eq!(
+            weeks[0]
+                .tenders
+                .iter()
+                .map(|t| t.resource_id)
+                .collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+        assert_eq!(weeks[0].effort_days, 12);
+        assert_eq!(
+            weeks[0].effort_bands,
+            BTreeMap::from([("large", 1), ("small", 1)])
+        );
+        assert_eq!(weeks[1].effort_days, 5);
+
+        let html = render_html(&weeks);
+        assert!(html.contains("width: 100%;\"></div>12 days"));
+        assert!(html.contains("width: 41%;\"></div>5 days"));
+    }
+
+    #[test]
+    fn test_weeks_param() {
+        let params = |weeks: &str| HashMap::from([("weeks".to_string(), weeks.to_string())]);
+        assert_eq!(weeks_param(&HashMap::new()), Ok(DEFAULT_WEEKS));
+        assert_eq!(weeks_param(&params("4")), Ok(4));
+        assert!(weeks_param(&params("0")).is_err());
+        assert!(weeks_param(&params("lots")).is_err());
+    }
+}
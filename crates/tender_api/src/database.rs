@@ -0,0 +1,390 @@
+use crate::dataset::DatasetSource;
+use crate::types::{
+    portal_link, Config, ManualOverride, Notification, Prediction, Recommendation, SortDirection,
+    Summary, Tender, TenderFilter, TenderSort, TenderSortField,
+};
+use anyhow::Result;
+use sqlx::postgres::PgRow;
+use sqlx::{Pool, Postgres, QueryBuilder, Row};
+use tender_overrides::Forced;
+use tracing::{debug, info};
+
+/// Columns selected for every tender query (tender + prediction + summary + notification
+/// + override)
+const TENDER_COLUMNS: &str = r#"
+    t.resource_id,
+    t.title,
+    t.ca,
+    t.info,
+    t.published,
+    t.deadline,
+    t.procedure,
+    t.status,
+    t.pdf_url,
+    t.value::TEXT AS value,
+    t.bid,
+    t.ml_bid,
+    t.ml_confidence::FLOAT8 AS ml_confidence,
+    t.ml_reasoning,
+    t.ml_status,
+    COALESCE(t.notification_sent, FALSE) AS notification_sent,
+    t.notification_sent_at,
+    s.summary_type,
+    s.ai_summary,
+    s.key_points,
+    s.recommendation,
+    s.confidence_assessment,
+    s.processing_notes,
+    s.created_at AS summary_created_at,
+    ARRAY(SELECT g.tag FROM tender_tags g WHERE g.resource_id = t.resource_id ORDER BY g.tag) AS tags,
+    o.recommendation AS override_recommendation,
+    o.snoozed_until,
+    o.reason AS override_reason,
+    o.set_by AS override_set_by,
+    o.set_at AS override_set_at
+"#;
+
+/// Followed by the tenant condition - see `Database::push_from`
+const TENDER_FROM: &str = r#"
+    FROM tender_records t
+    LEFT JOIN ai_summaries s ON s.resource_id = t.resource_id
+"#;
+
+/// Database access for the tender API; queries go to the read replica when there is one
+pub struct Database {
+    pool: Pool<Postgres>,
+    /// Primary, for feedback - the only route that writes
+    write_pool: Pool<Postgres>,
+    tenant_id: String,
+}
+
+impl Database {
+    /// Create new database connection
+    pub async fn new(config: &Config) -> Result<Self> {
+        let pools = db::Pools::connect(&config.database_url, 5).await?;
+        // Every tender query reads tags, so the table must exist before the first one
+        tender_tags::ensure_table(&pools.write).await?;
+        // /audit can be asked for before any stage has recorded a decision
+        decision_audit::ensure_table(&pools.write).await?;
+        // Joined into every tender query, like the tags
+        tender_overrides::ensure_table(&pools.write).await?;
+
+        info!(
+            "✅ Database connection established (tenant: {})",
+            config.tenant_id
+        );
+        Ok(Self {
+            pool: pools.read,
+            write_pool: pools.write,
+            tenant_id: config.tenant_id.clone(),
+        })
+    }
+
+    /// Read replica (or the primary when there is none)
+    pub fn pool(&self) -> &Pool<Postgres> {
+        &self.pool
+    }
+
+    pub fn write_pool(&self) -> &Pool<Postgres> {
+        &self.write_pool
+    }
+
+    /// FROM clause joining only this deployment's tenant's summaries and overrides
+    fn push_from(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        query
+            .push(TENDER_FROM)
+            .push(" AND s.tenant_id = ")
+            .push_bind(self.tenant_id.clone())
+            .push(
+                " LEFT JOIN tender_overrides o ON o.resource_id = t.resource_id AND o.tenant_id = ",
+            )
+            .push_bind(self.tenant_id.clone());
+    }
+
+    /// Fetch a single tender by resource_id
+    pub async fn get_tender(&self, resource_id: i64) -> Result<Option<Tender>> {
+        debug!("🔍 Fetching tender {}", resource_id);
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT ");
+        query.push(TENDER_COLUMNS);
+        self.push_from(&mut query);
+        query.push(" WHERE t.resource_id = ").push_bind(resource_id);
+
+        let row = query.build().fetch_optional(&self.pool).await?;
+        Ok(row.map(|r| tender_from_row(&r)))
+    }
+
+    /// Stored PDF text with its page offsets (`None` for tenders extracted before they were recorded)
+    pub async fn get_pdf_text(
+        &self,
+        resource_id: i64,
+    ) -> Result<Option<(String, Option<Vec<i32>>)>> {
+        let row =
+            sqlx::query("SELECT pdf_text, page_offsets FROM pdf_content WHERE resource_id = $1")
+                .bind(resource_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(row.map(|row| (row.get("pdf_text"), row.get("page_offsets"))))
+    }
+
+    /// List tenders matching the filter, returning one page plus the total match count.
+    /// pipeline_canary's synthetic tenders (negative ids) are left out, as in exports
+    pub async fn list_tenders(
+        &self,
+        filter: &TenderFilter,
+        sort: TenderSort,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Tender>, i64)> {
+        let mut count_query = QueryBuilder::<Postgres>::new("SELECT COUNT(*) AS total");
+        self.push_from(&mut count_query);
+        count_query.push(" WHERE t.resource_id > 0");
+        push_filters(&mut count_query, filter);
+        let total: i64 = count_query
+            .build()
+            .fetch_one(&self.pool)
+            .await?
+            .get("total");
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT ");
+        query.push(TENDER_COLUMNS);
+        self.push_from(&mut query);
+        query.push(" WHERE t.resource_id > 0");
+        push_filters(&mut query, filter);
+        query
+            .push(" ORDER BY ")
+            .push(sort_column(sort.field))
+            .push(match sort.direction {
+                SortDirection::Asc => " ASC NULLS LAST",
+                SortDirection::Desc => " DESC NULLS LAST",
+            })
+            .push(", t.resource_id DESC")
+            .push(" LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        let tenders: Vec<Tender> = rows.iter().map(tender_from_row).collect();
+
+        info!(
+            "📋 Listed {} of {} matching tenders (offset {})",
+            tenders.len(),
+            total,
+            offset
+        );
+        Ok((tenders, total))
+    }
+
+    /// One page of anonymized-export source rows, oldest tender first.
+    ///
+    /// Canary tenders (negative resource ids) are left out.
+    pub async fn list_dataset_rows(
+        &self,
+        filter: &TenderFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<DatasetSource>> {
+        let mut query = QueryBuilder::<Postgres>::new(
+            r#"SELECT
+                t.resource_id,
+                t.title,
+                t.ca,
+                t.published,
+                t.procedure,
+                t.status,
+                t.value::FLOAT8 AS value,
+                t.bid,
+                t.ml_bid,
+                t.ml_confidence::FLOAT8 AS ml_confidence,
+                s.recommendation,
+                p.detected_codes,
+                p.codes_count,
+                ARRAY(SELECT g.tag FROM tender_tags g WHERE g.resource_id = t.resource_id ORDER BY g.tag) AS tags
+            "#,
+        );
+        self.push_from(&mut query);
+        query.push(" LEFT JOIN pdf_content p ON p.resource_id = t.resource_id");
+        query.push(" WHERE t.resource_id > 0");
+        push_filters(&mut query, filter);
+        query
+            .push(" ORDER BY t.resource_id LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        Ok(rows
+            .iter()
+            .map(|row| DatasetSource {
+                resource_id: row.get("resource_id"),
+                title: row.get("title"),
+                contracting_authority: row.get("ca"),
+                published: row.get("published"),
+                procedure: row.get("procedure"),
+                status: row.get("status"),
+                value: row.get("value"),
+                detected_codes: row
+                    .get::<Option<Vec<String>>, _>("detected_codes")
+                    .unwrap_or_default(),
+                codes_count: row.get("codes_count"),
+                bid: row.get("bid"),
+                ml_bid: row.get("ml_bid"),
+                ml_confidence: row.get("ml_confidence"),
+                recommendation: row.get("recommendation"),
+                tags: row.get("tags"),
+            })
+            .collect())
+    }
+}
+
+fn push_filters(query: &mut QueryBuilder<'_, Postgres>, filter: &TenderFilter) {
+    if let Some(title) = &filter.title_contains {
+        query
+            .push(" AND t.title ILIKE ")
+            .push_bind(format!("%{}%", title));
+    }
+    if let Some(ca) = &filter.contracting_authority {
+        query
+            .push(" AND t.ca ILIKE ")
+            .push_bind(format!("%{}%", ca));
+    }
+    if let Some(status) = &filter.status {
+        query.push(" AND t.status ILIKE ").push_bind(status.clone());
+    }
+    if let Some(ml_bid) = filter.ml_bid {
+        query.push(" AND t.ml_bid = ").push_bind(ml_bid);
+    }
+    // A recommendation forced by hand, otherwise Claude's by the same rule as
+    // NotificationService::should_send_notification: "bid" but not "no bid"
+    match filter.recommendation {
+        Some(Recommendation::Bid) => {
+            query.push(
+                " AND (o.recommendation = 'bid' OR (o.recommendation IS NULL AND s.recommendation ILIKE '%bid%' AND s.recommendation NOT ILIKE '%no bid%'))",
+            );
+        }
+        Some(Recommendation::NoBid) => {
+            query.push(
+                " AND (o.recommendation = 'no_bid' OR (o.recommendation IS NULL AND s.recommendation ILIKE '%no bid%'))",
+            );
+        }
+        None => {}
+    }
+    if let Some(sent) = filter.notification_sent {
+        query
+            .push(" AND COALESCE(t.notification_sent, FALSE) = ")
+            .push_bind(sent);
+    }
+    if let Some(after) = filter.published_after {
+        query.push(" AND t.published >= ").push_bind(after);
+    }
+    if let Some(before) = filter.published_before {
+        query.push(" AND t.published < ").push_bind(before);
+    }
+    if let Some(after) = filter.deadline_after {
+        query.push(" AND t.deadline >= ").push_bind(after);
+    }
+    if let Some(before) = filter.deadline_before {
+        query.push(" AND t.deadline < ").push_bind(before);
+    }
+    if let Some(tags) = filter.tags.as_ref().filter(|tags| !tags.is_empty()) {
+        let tags: Vec<String> = tags
+            .iter()
+            .map(|tag| tender_tags::normalize(tag).unwrap_or_default())
+            .collect();
+        query
+            .push(" AND (SELECT COUNT(DISTINCT g.tag) FROM tender_tags g WHERE g.resource_id = t.resource_id AND g.tag = ANY(")
+            .push_bind(tags.clone())
+            .push(")) = ")
+            .push_bind(tags.len() as i64);
+    }
+}
+
+fn sort_column(field: TenderSortField) -> &'static str {
+    match field {
+        TenderSortField::Published => "t.published",
+        TenderSortField::Deadline => "t.deadline",
+        TenderSortField::Value => "t.value",
+        TenderSortField::MlConfidence => "t.ml_confidence",
+        TenderSortField::ResourceId => "t.resource_id",
+    }
+}
+
+fn json_string_array(value: Option<serde_json::Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array().cloned())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|item| item.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn tender_from_row(row: &PgRow) -> Tender {
+    let resource_id: i64 = row.get("resource_id");
+    let ml_status: Option<String> = row.get("ml_status");
+    let ml_bid: Option<bool> = row.get("ml_bid");
+
+    let prediction = if ml_bid.is_some() || ml_status.is_some() {
+        Some(Prediction {
+            should_bid: ml_bid,
+            confidence: row.get("ml_confidence"),
+            reasoning: row.get("ml_reasoning"),
+            status: ml_status,
+        })
+    } else {
+        None
+    };
+
+    let summary = row
+        .get::<Option<String>, _>("summary_type")
+        .map(|summary_type| Summary {
+            summary_type,
+            ai_summary: row.get("ai_summary"),
+            key_points: json_string_array(row.get("key_points")),
+            recommendation: row.get("recommendation"),
+            confidence_assessment: row.get("confidence_assessment"),
+            processing_notes: json_string_array(row.get("processing_notes")),
+            created_at: row.get("summary_created_at"),
+        });
+
+    let manual_override = row
+        .get::<Option<String>, _>("override_set_by")
+        .map(|set_by| ManualOverride {
+            recommendation: row
+                .get::<Option<String>, _>("override_recommendation")
+                .and_then(|value| Forced::parse(&value))
+                .map(|forced| match forced {
+                    Forced::Bid => Recommendation::Bid,
+                    Forced::NoBid => Recommendation::NoBid,
+                }),
+            snoozed_until: row.get("snoozed_until"),
+            reason: row.get("override_reason"),
+            set_by,
+            set_at: row.get("override_set_at"),
+        });
+
+    Tender {
+        resource_id,
+        title: row.get("title"),
+        contracting_authority: row.get("ca"),
+        info: row.get("info"),
+        published: row.get("published"),
+        deadline: row.get("deadline"),
+        procedure: row.get("procedure"),
+        status: row.get("status"),
+        pdf_url: row.get("pdf_url"),
+        value: row.get("value"),
+        bid: row.get("bid"),
+        portal_link: portal_link(resource_id),
+        tags: row.get("tags"),
+        prediction,
+        summary,
+        notification: Notification {
+            sent: row.get("notification_sent"),
+            sent_at: row.get("notification_sent_at"),
+        },
+        manual_override,
+    }
+}
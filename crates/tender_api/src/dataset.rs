@@ -0,0 +1,287 @@
+//! Anonymized dataset export for external ML collaborators.
+//!
+//! Rows keep the text features and labels the bid model learns from, but nothing that
+//! identifies the contracting authority or a person: authorities and tenders get keyed
+//! pseudonyms, titles are scrubbed, dates are coarsened to the month and values to a band.
+//! The column schema is documented in the crate README.
+
+use chrono::NaiveDateTime;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Hex characters kept from each pseudonym HMAC (48 bits - plenty for a few hundred thousand rows)
+const PSEUDONYM_LEN: usize = 12;
+
+pub const DATASET_HEADER: &[&str] = &[
+    "record_id",
+    "authority_id",
+    "title",
+    "title_words",
+    "published_month",
+    "procedure",
+    "status",
+    "value_band",
+    "detected_codes",
+    "codes_count",
+    "bid",
+    "ml_bid",
+    "ml_confidence",
+    "claude_recommendation",
+    "tags",
+];
+
+/// Value bands (upper bounds, exclusive) used instead of exact values
+const VALUE_BANDS: &[(f64, &str)] = &[
+    (25_000.0, "<25k"),
+    (100_000.0, "25k-100k"),
+    (500_000.0, "100k-500k"),
+    (1_000_000.0, "500k-1m"),
+];
+
+/// Tender fields read for the dataset, before anonymization
+#[derive(Debug, Clone)]
+pub struct DatasetSource {
+    pub resource_id: i64,
+    pub title: String,
+    pub contracting_authority: String,
+    pub published: Option<NaiveDateTime>,
+    pub procedure: String,
+    pub status: String,
+    pub value: Option<f64>,
+    pub detected_codes: Vec<String>,
+    pub codes_count: Option<i32>,
+    pub bid: Option<i32>,
+    pub ml_bid: Option<bool>,
+    pub ml_confidence: Option<f64>,
+    pub recommendation: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Deterministic keyed pseudonyms: the same key always maps a name to the same id,
+/// so datasets exported at different times can be joined, but ids can't be reversed
+/// (or recomputed from a list of authority names) without the key
+pub struct Pseudonymizer {
+    key: Vec<u8>,
+}
+
+impl Pseudonymizer {
+    pub fn new(key: &str) -> Self {
+        Self {
+            key: key.as_bytes().to_vec(),
+        }
+    }
+
+    fn pseudonym(&self, prefix: &str, domain: &str, value: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(domain.as_bytes());
+        mac.update(b":");
+        mac.update(value.as_bytes());
+        let digest = hex::encode(mac.finalize().into_bytes());
+        format!("{}-{}", prefix, &digest[..PSEUDONYM_LEN])
+    }
+
+    /// Case and whitespace differences in the portal's authority names map to the same id
+    pub fn authority(&self, name: &str) -> String {
+        let normalized = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.pseudonym("CA", "authority", &normalized.to_lowercase())
+    }
+
+    pub fn record(&self, resource_id: i64) -> String {
+        self.pseudonym("T", "tender", &resource_id.to_string())
+    }
+}
+
+/// Replace the authority's name, email addresses, URLs and phone numbers in free text
+pub fn scrub_text(text: &str, authority: &str) -> String {
+    let text = replace_case_insensitive(text, authority.trim(), "[AUTHORITY]");
+
+    text.split_whitespace()
+        .map(|word| {
+            let bare = word.trim_matches(|c: char| ",;:()[]<>\"'".contains(c));
+            let lower = bare.to_lowercase();
+            if bare.contains('@') && bare.contains('.') {
+                "[EMAIL]"
+            } else if lower.starts_with("http://")
+                || lower.starts_with("https://")
+                || lower.starts_with("www.")
+            {
+                "[URL]"
+            } else if is_phone_number(bare) {
+                "[PHONE]"
+            } else {
+                word
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn replace_case_insensitive(text: &str, needle: &str, replacement: &str) -> String {
+    if needle.is_empty() {
+        return text.to_string();
+    }
+    // ASCII lowercasing keeps byte offsets aligned with the original text
+    let haystack = text.to_ascii_lowercase();
+    let needle = needle.to_ascii_lowercase();
+
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, _) in haystack.match_indices(&needle) {
+        out.push_str(&text[last..start]);
+        out.push_str(replacement);
+        last = start + needle.len();
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// 7+ digits with only phone punctuation and a leading "+", "0" or "(",
+/// e.g. "+353-1-234-5678" or "(01)2345678" - but not "2025/2026" or "72000000"
+fn is_phone_number(word: &str) -> bool {
+    let digits = word.chars().filter(char::is_ascii_digit).count();
+    digits >= 7
+        && word.starts_with(['+', '0', '('])
+        && word
+            .chars()
+            .all(|c| c.is_ascii_digit() || "+-()".contains(c))
+}
+
+pub fn value_band(value: Option<f64>) -> &'static str {
+    match value {
+        None => "",
+        Some(v) => VALUE_BANDS
+            .iter()
+            .find(|(upper, _)| v < *upper)
+            .map(|(_, band)| *band)
+            .unwrap_or(">1m"),
+    }
+}
+
+/// BID / NO BID bucket of Claude's free-text recommendation (same rule as the notification filter)
+fn recommendation_label(recommendation: Option<&str>) -> &'static str {
+    match recommendation.map(str::to_lowercase) {
+        Some(r) if r.contains("no bid") => "NO BID",
+        Some(r) if r.contains("bid") => "BID",
+        _ => "",
+    }
+}
+
+/// One anonymized row, in `DATASET_HEADER` order
+pub fn anonymize(source: &DatasetSource, pseudonymizer: &Pseudonymizer) -> Vec<String> {
+    let title = scrub_text(&source.title, &source.contracting_authority);
+    vec![
+        pseudonymizer.record(source.resource_id),
+        pseudonymizer.authority(&source.contracting_authority),
+        title.clone(),
+        title.split_whitespace().count().to_string(),
+        source
+            .published
+            .map(|d| d.format("%Y-%m").to_string())
+            .unwrap_or_default(),
+        source.procedure.clone(),
+        source.status.clone(),
+        value_band(source.value).to_string(),
+        source.detected_codes.join(";"),
+        source
+            .codes_count
+            .map(|c| c.to_string())
+            .unwrap_or_default(),
+        source.bid.map(|b| b.to_string()).unwrap_or_default(),
+        source
+            .ml_bid
+            .map(|b| if b { "1" } else { "0" }.to_string())
+            .unwrap_or_default(),
+        source
+            .ml_confidence
+            .map(|c| format!("{:.3}", c))
+            .unwrap_or_default(),
+        recommendation_label(source.recommendation.as_deref()).to_string(),
+        // Free-form tags can name an authority or a person
+        source
+            .tags
+            .iter()
+            .filter(|tag| tender_tags::is_controlled(tag))
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(";"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pseudonyms_are_deterministic_and_keyed() {
+        let pseudonymizer = Pseudonymizer::new("secret");
+
+        let id = pseudonymizer.authority("Health Service Executive");
+        assert_eq!(id, pseudonymizer.authority("health  service EXECUTIVE "));
+        assert_eq!(id.len(), 3 + PSEUDONYM_LEN);
+        assert_ne!(
+            id,
+            Pseudonymizer::new("other").authority("Health Service Executive")
+        );
+        assert_ne!(pseudonymizer.record(1), pseudonymizer.record(2));
+    }
+
+    #[test]
+    fn test_scrub_text() {
+        let scrubbed = scrub_text(
+            "Dublin City Council IT support - contact jo.bloggs@dcc.ie or +353-1-222-2222, see https://dcc.ie",
+            "Dublin City Council",
+        );
+
+        assert_eq!(
+            scrubbed,
+            "[AUTHORITY] IT support - contact [EMAIL] or [PHONE] see [URL]"
+        );
+        assert_eq!(
+            scrub_text("Laptops 2025/2026 (CPV 30213100)", ""),
+            "Laptops 2025/2026 (CPV 30213100)"
+        );
+    }
+
+    #[test]
+    fn test_value_band() {
+        assert_eq!(value_band(None), "");
+        assert_eq!(value_band(Some(10_000.0)), "<25k");
+        assert_eq!(value_band(Some(100_000.0)), "100k-500k");
+        assert_eq!(value_band(Some(2_500_000.0)), ">1m");
+    }
+
+    #[test]
+    fn test_anonymize_row_matches_header() {
+        let source = DatasetSource {
+            resource_id: 42,
+            title: "Kerry County Council website redesign".to_string(),
+            contracting_authority: "Kerry County Council".to_string(),
+            published: chrono::NaiveDate::from_ymd_opt(2025, 3, 14)
+                .unwrap()
+                .and_hms_opt(9, 0, 0),
+            procedure: "Open".to_string(),
+            status: "Open".to_string(),
+            value: Some(80_000.0),
+            detected_codes: vec!["72000000".to_string()],
+            codes_count: Some(1),
+            bid: Some(1),
+            ml_bid: Some(true),
+            ml_confidence: Some(0.8123),
+            recommendation: Some("BID - strong fit".to_string()),
+            tags: vec!["cloud".to_string(), "kerry digital hub".to_string()],
+        };
+
+        let row = anonymize(&source, &Pseudonymizer::new("secret"));
+        assert_eq!(row.len(), DATASET_HEADER.len());
+        assert_eq!(row[2], "[AUTHORITY] website redesign");
+        assert_eq!(row[4], "2025-03");
+        assert_eq!(row[7], "25k-100k");
+        assert_eq!(row[13], "BID");
+        assert_eq!(row[14], "cloud");
+        assert!(!row
+            .iter()
+            .any(|field| field.contains("Kerry") || field == "42"));
+    }
+}
@@ -0,0 +1,302 @@
+use crate::database::Database;
+use crate::dataset::{self, DatasetSource, Pseudonymizer, DATASET_HEADER};
+use crate::types::{
+    Recommendation, SortDirection, Tender, TenderFilter, TenderSort, TenderSortField,
+};
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime};
+use std::collections::HashMap;
+
+/// Rows fetched per database round trip while building an export
+const EXPORT_PAGE_SIZE: i64 = 500;
+/// Hard cap so a missing date range can't produce an unbounded spreadsheet
+const MAX_EXPORT_ROWS: i64 = 10_000;
+
+const CSV_HEADER: &[&str] = &[
+    "resource_id",
+    "title",
+    "contracting_authority",
+    "published",
+    "deadline",
+    "status",
+    "procedure",
+    "value",
+    "ml_bid",
+    "ml_confidence",
+    "claude_recommendation",
+    "confidence_assessment",
+    "notification_sent",
+    "portal_link",
+    "tags",
+];
+
+/// What an export contains
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportMode {
+    /// Every column, for internal use
+    Full,
+    /// Pseudonymized text features and labels for external ML collaborators (see `dataset`)
+    Anonymized,
+}
+
+/// Parsed `GET /export` query parameters
+#[derive(Debug, Clone)]
+pub struct ExportRequest {
+    pub filter: TenderFilter,
+    pub mode: ExportMode,
+}
+
+impl ExportRequest {
+    /// Parse `format`, `mode`, `from`, `to`, `recommendation` and `tag` query parameters
+    ///
+    /// `from`/`to` are inclusive published dates (YYYY-MM-DD); `tag` is a comma-separated
+    /// list of tags every exported tender must carry.
+    pub fn from_params(params: &HashMap<String, String>) -> Result<Self, String> {
+        let format = params.get("format").map(|f| f.to_lowercase());
+        match format.as_deref() {
+            None | Some("csv") => {}
+            Some(other) => return Err(format!("Unsupported export format '{}'", other)),
+        }
+
+        let mode = match params.get("mode").map(|m| m.to_lowercase()).as_deref() {
+            None | Some("full") => ExportMode::Full,
+            Some("anonymized") | Some("anonymised") => ExportMode::Anonymized,
+            Some(other) => return Err(format!("Unknown export mode '{}'", other)),
+        };
+
+        let mut filter = TenderFilter::default();
+
+        if let Some(from) = params.get("from") {
+            filter.published_after = Some(parse_date(from)?);
+        }
+        if let Some(to) = params.get("to") {
+            // Inclusive end date: everything before the following midnight
+            filter.published_before = Some(parse_date(to)? + chrono::Duration::days(1));
+        }
+
+        filter.recommendation = match params.get("recommendation").map(|r| r.to_uppercase()) {
+            None => None,
+            Some(r) if r == "BID" => Some(Recommendation::Bid),
+            Some(r) if r == "NO_BID" || r == "NO BID" || r == "NOBID" => {
+                Some(Recommendation::NoBid)
+            }
+            Some(other) => return Err(format!("Unknown recommendation '{}'", other)),
+        };
+
+        if let Some(tags) = params.get("tag") {
+            filter.tags = Some(
+                tags.split(',')
+                    .filter(|tag| !tag.trim().is_empty())
+                    .map(|tag| tender_tags::normalize(tag).map_err(|e| e.to_string()))
+                    .collect::<Result<_, _>>()?,
+            );
+        }
+
+        Ok(Self { filter, mode })
+    }
+}
+
+fn parse_date(value: &str) -> Result<NaiveDateTime, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+        .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", value))
+}
+
+/// Fetch every tender matching the export request, oldest deadline first
+pub async fn fetch_export_rows(
+    database: &Database,
+    request: &ExportRequest,
+) -> Result<Vec<Tender>> {
+    let sort = TenderSort {
+        field: TenderSortField::Deadline,
+        direction: SortDirection::Asc,
+    };
+
+    let mut rows = Vec::new();
+    let mut offset = 0;
+    loop {
+        let (page, total) = database
+            .list_tenders(&request.filter, sort, EXPORT_PAGE_SIZE, offset)
+            .await?;
+        let page_len = page.len() as i64;
+        rows.extend(page);
+        offset += page_len;
+
+        if page_len == 0 || offset >= total || offset >= MAX_EXPORT_ROWS {
+            break;
+        }
+    }
+
+    rows.truncate(MAX_EXPORT_ROWS as usize);
+    Ok(rows)
+}
+
+/// Fetch the dataset source rows matching the export request, oldest tender first
+pub async fn fetch_dataset_rows(
+    database: &Database,
+    request: &ExportRequest,
+) -> Result<Vec<DatasetSource>> {
+    let mut rows = Vec::new();
+    loop {
+        let page = database
+            .list_dataset_rows(&request.filter, EXPORT_PAGE_SIZE, rows.len() as i64)
+            .await?;
+        let page_len = page.len() as i64;
+        rows.extend(page);
+
+        if page_len < EXPORT_PAGE_SIZE || rows.len() as i64 >= MAX_EXPORT_ROWS {
+            break;
+        }
+    }
+
+    rows.truncate(MAX_EXPORT_ROWS as usize);
+    Ok(rows)
+}
+
+/// Render tenders as CSV
+pub fn render_csv(tenders: &[Tender]) -> String {
+    render_rows(CSV_HEADER, tenders.iter().map(tender_fields))
+}
+
+/// Render the anonymized dataset as CSV (schema in the crate README)
+pub fn render_dataset_csv(rows: &[DatasetSource], pseudonymizer: &Pseudonymizer) -> String {
+    render_rows(
+        DATASET_HEADER,
+        rows.iter()
+            .map(|row| dataset::anonymize(row, pseudonymizer)),
+    )
+}
+
+/// CSV with a UTF-8 BOM so Excel picks up the encoding
+fn render_rows(header: &[&str], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let mut out = String::from("\u{feff}");
+    out.push_str(&header.join(","));
+    out.push_str("\r\n");
+
+    for fields in rows {
+        let line: Vec<String> = fields.iter().map(|f| escape_csv_field(f)).collect();
+        out.push_str(&line.join(","));
+        out.push_str("\r\n");
+    }
+
+    out
+}
+
+fn tender_fields(tender: &Tender) -> Vec<String> {
+    let prediction = tender.prediction.as_ref();
+    let summary = tender.summary.as_ref();
+
+    vec![
+        tender.resource_id.to_string(),
+        tender.title.clone(),
+        tender.contracting_authority.clone(),
+        format_datetime(tender.published),
+        format_datetime(tender.deadline),
+        tender.status.clone(),
+        tender.procedure.clone(),
+        tender.value.clone().unwrap_or_default(),
+        prediction
+            .and_then(|p| p.should_bid)
+            .map(|b| if b { "BID" } else { "NO BID" }.to_string())
+            .unwrap_or_default(),
+        prediction
+            .and_then(|p| p.confidence)
+            .map(|c| format!("{:.3}", c))
+            .unwrap_or_default(),
+        summary
+            .map(|s| s.recommendation.clone())
+            .unwrap_or_default(),
+        summary
+            .map(|s| s.confidence_assessment.clone())
+            .unwrap_or_default(),
+        tender.notification.sent.to_string(),
+        tender.portal_link.clone(),
+        tender.tags.join(";"),
+    ]
+}
+
+fn format_datetime(value: Option<NaiveDateTime>) -> String {
+    value
+        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// Quote a field per RFC 4180 when it contains a delimiter, quote or newline. A field a
+/// spreadsheet would read as a formula (titles and buyer names are scraped text) gets a
+/// leading `'` first, so it opens as text
+fn escape_csv_field(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_csv_field() {
+        assert_eq!(escape_csv_field("plain"), "plain");
+        assert_eq!(escape_csv_field("a,b"), "\"a,b\"");
+        assert_eq!(escape_csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_csv_field("line\nbreak"), "\"line\nbreak\"");
+    }
+
+    #[test]
+    fn test_escape_csv_field_neutralises_formulas() {
+        assert_eq!(
+            escape_csv_field("=HYPERLINK(\"x\")"),
+            "\"'=HYPERLINK(\"\"x\"\")\""
+        );
+        assert_eq!(escape_csv_field("+1"), "'+1");
+        assert_eq!(escape_csv_field("-2+3"), "'-2+3");
+        assert_eq!(escape_csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(escape_csv_field("\tcmd"), "'\tcmd");
+        assert_eq!(escape_csv_field("\rcmd"), "\"'\rcmd\"");
+        assert_eq!(escape_csv_field("a=b"), "a=b");
+    }
+
+    #[test]
+    fn test_export_request_parsing() {
+        let mut params = HashMap::new();
+        params.insert("format".to_string(), "csv".to_string());
+        params.insert("from".to_string(), "2025-01-01".to_string());
+        params.insert("to".to_string(), "2025-01-31".to_string());
+        params.insert("recommendation".to_string(), "BID".to_string());
+
+        let request = ExportRequest::from_params(&params).unwrap();
+        assert_eq!(request.filter.recommendation, Some(Recommendation::Bid));
+        assert_eq!(
+            request.filter.published_before.unwrap().date(),
+            NaiveDate::from_ymd_opt(2025, 2, 1).unwrap()
+        );
+
+        assert_eq!(request.mode, ExportMode::Full);
+
+        params.insert("mode".to_string(), "Anonymised".to_string());
+        let request = ExportRequest::from_params(&params).unwrap();
+        assert_eq!(request.mode, ExportMode::Anonymized);
+
+        params.insert("mode".to_string(), "raw".to_string());
+        assert!(ExportRequest::from_params(&params).is_err());
+        params.remove("mode");
+
+        params.insert("tag".to_string(), "Cloud, staff  aug".to_string());
+        let request = ExportRequest::from_params(&params).unwrap();
+        assert_eq!(
+            request.filter.tags,
+            Some(vec!["cloud".to_string(), "staff aug".to_string()])
+        );
+        params.remove("tag");
+
+        params.insert("format".to_string(), "pdf".to_string());
+        assert!(ExportRequest::from_params(&params).is_err());
+    }
+}
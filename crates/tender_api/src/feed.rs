@@ -0,0 +1,116 @@
+use crate::types::Tender;
+use chrono::{DateTime, Utc};
+
+/// Number of entries included in the feed
+pub const FEED_ENTRY_LIMIT: i64 = 50;
+/// Maximum characters of the Claude summary shown per entry
+const EXCERPT_CHARS: usize = 400;
+
+/// Render BID-recommended tenders as an Atom 1.0 feed
+pub fn render_atom_feed(tenders: &[Tender], feed_url: &str) -> String {
+    let updated = tenders
+        .iter()
+        .map(entry_updated)
+        .max()
+        .unwrap_or_else(Utc::now);
+
+    let mut out = String::new();
+    out.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+    out.push('\n');
+    out.push_str(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+    out.push('\n');
+    out.push_str("  <title>eTenders - Recommended BID opportunities</title>\n");
+    out.push_str(&format!("  <id>{}</id>\n", escape_xml(feed_url)));
+    out.push_str(&format!(
+        "  <link rel=\"self\" href=\"{}\"/>\n",
+        escape_xml(feed_url)
+    ));
+    out.push_str(&format!("  <updated>{}</updated>\n", updated.to_rfc3339()));
+    out.push_str("  <author><name>eTenders pipeline</name></author>\n");
+
+    for tender in tenders {
+        out.push_str(&render_entry(tender));
+    }
+
+    out.push_str("</feed>\n");
+    out
+}
+
+fn render_entry(tender: &Tender) -> String {
+    let deadline = tender
+        .deadline
+        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "Not specified".to_string());
+    let excerpt = tender
+        .summary
+        .as_ref()
+        .map(|s| excerpt(&s.ai_summary, EXCERPT_CHARS))
+        .unwrap_or_default();
+
+    let content = format!(
+        "Contracting authority: {}\nDeadline: {}\n\n{}",
+        tender.contracting_authority, deadline, excerpt
+    );
+
+    format!(
+        r#"  <entry>
+    <id>urn:etenders:tender:{id}</id>
+    <title>{title}</title>
+    <link href="{link}"/>
+    <updated>{updated}</updated>
+    <summary type="text">{content}</summary>
+  </entry>
+"#,
+        id = tender.resource_id,
+        title = escape_xml(&tender.title),
+        link = escape_xml(&tender.portal_link),
+        updated = entry_updated(tender).to_rfc3339(),
+        content = escape_xml(&content),
+    )
+}
+
+/// Entry timestamp: when Claude produced the summary, falling back to publication date
+fn entry_updated(tender: &Tender) -> DateTime<Utc> {
+    tender
+        .summary
+        .as_ref()
+        .map(|s| s.created_at)
+        .or_else(|| tender.published.map(|p| p.and_utc()))
+        .unwrap_or_else(Utc::now)
+}
+
+/// Truncate to a number of characters (not bytes) so multi-byte text stays valid
+fn excerpt(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(max_chars).collect();
+    format!("{}...", truncated.trim_end())
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml(r#"IT & <Cloud> "Services""#),
+            "IT &amp; &lt;Cloud&gt; &quot;Services&quot;"
+        );
+    }
+
+    #[test]
+    fn test_excerpt_respects_char_boundaries() {
+        assert_eq!(excerpt("short", 10), "short");
+        assert_eq!(excerpt("€€€€€", 3), "€€€...");
+    }
+}
@@ -0,0 +1,138 @@
+//! Pages for the "This was not relevant" / "Good call" links in notification emails.
+//!
+//! The email link is a GET that only shows a confirmation form; the verdict is recorded
+//! by the form's POST. That way mail scanners that prefetch links can't record feedback.
+
+use crate::feed::escape_xml;
+use feedback::Verdict;
+use std::collections::HashMap;
+
+/// A signed feedback link's parameters, from the query string or the confirmation form
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedbackParams {
+    pub resource_id: i64,
+    pub tenant_id: String,
+    pub verdict: Verdict,
+    pub signature: String,
+}
+
+impl FeedbackParams {
+    pub fn from_params(params: &HashMap<String, String>) -> Result<Self, String> {
+        let get = |name: &str| {
+            params
+                .get(name)
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| format!("Missing '{}'", name))
+        };
+
+        let resource_id = get("resource_id")?
+            .parse()
+            .map_err(|_| "Invalid 'resource_id'".to_string())?;
+        let verdict = Verdict::parse(get("verdict")?).ok_or("Invalid 'verdict'")?;
+
+        Ok(Self {
+            resource_id,
+            tenant_id: get("tenant_id")?.clone(),
+            verdict,
+            signature: get("sig")?.clone(),
+        })
+    }
+
+    pub fn is_signed_with(&self, key: &str) -> bool {
+        feedback::verify(
+            key,
+            self.resource_id,
+            &self.tenant_id,
+            self.verdict,
+            &self.signature,
+        )
+    }
+}
+
+fn page(body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Tender feedback</title></head>\n<body style=\"font-family: sans-serif; max-width: 600px; margin: 40px auto;\">\n{}\n</body></html>",
+        body
+    )
+}
+
+/// Confirmation form that POSTs the signed parameters back to `/feedback`
+pub fn render_confirmation(params: &FeedbackParams, title: Option<&str>) -> String {
+    let hidden = [
+        ("resource_id", params.resource_id.to_string()),
+        ("tenant_id", params.tenant_id.clone()),
+        ("verdict", params.verdict.as_str().to_string()),
+        ("sig", params.signature.clone()),
+    ]
+    .iter()
+    .map(|(name, value)| {
+        format!(
+            "<input type=\"hidden\" name=\"{}\" value=\"{}\">",
+            name,
+            escape_xml(value)
+        )
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+
+    page(&format!(
+        "<h2>{}?</h2>\n<p>{}</p>\n<form method=\"post\" action=\"feedback\">\n{}\n<button type=\"submit\">Confirm</button>\n</form>",
+        escape_xml(params.verdict.label()),
+        escape_xml(title.unwrap_or(&format!("Tender {}", params.resource_id))),
+        hidden
+    ))
+}
+
+pub fn render_thanks(verdict: Verdict) -> String {
+    let detail = match verdict {
+        Verdict::NotRelevant => {
+            "Repeated feedback like this is used to suggest new exclusion terms."
+        }
+        Verdict::GoodCall => "Thanks - this helps us keep sending tenders like this one.",
+    };
+    page(&format!(
+        "<h2>Feedback recorded: {}</h2>\n<p>{}</p>",
+        escape_xml(verdict.label()),
+        detail
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_params_round_trip_a_signed_link() {
+        let signature = feedback::sign("key", 42, "default", Verdict::NotRelevant);
+        let parsed = FeedbackParams::from_params(&params(&[
+            ("resource_id", "42"),
+            ("tenant_id", "default"),
+            ("verdict", "not_relevant"),
+            ("sig", &signature),
+        ]))
+        .unwrap();
+
+        assert!(parsed.is_signed_with("key"));
+        assert!(!parsed.is_signed_with("other"));
+        assert!(render_confirmation(&parsed, Some("<b>Laptops</b>")).contains("&lt;b&gt;Laptops"));
+    }
+
+    #[test]
+    fn test_params_reject_bad_input() {
+        assert!(FeedbackParams::from_params(&params(&[("resource_id", "42")])).is_err());
+        assert!(FeedbackParams::from_params(&params(&[
+            ("resource_id", "42"),
+            ("tenant_id", "default"),
+            ("verdict", "maybe"),
+            ("sig", "00"),
+        ]))
+        .is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use crate::database::Database;
+use crate::types::{Tender, TenderFilter, TenderPage, TenderSort};
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema};
+use std::sync::Arc;
+
+/// Default and maximum page sizes for list queries
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+pub type TenderSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Build the GraphQL schema with the shared database handle
+pub fn build_schema(database: Arc<Database>) -> TenderSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(database)
+        .limit_depth(6)
+        .finish()
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Look up a single tender by its eTenders resource id
+    async fn tender(
+        &self,
+        ctx: &Context<'_>,
+        resource_id: i64,
+    ) -> async_graphql::Result<Option<Tender>> {
+        let database = ctx.data::<Arc<Database>>()?;
+        Ok(database.get_tender(resource_id).await?)
+    }
+
+    /// List tenders with optional filtering, sorting and offset pagination
+    async fn tenders(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<TenderFilter>,
+        sort: Option<TenderSort>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> async_graphql::Result<TenderPage> {
+        let database = ctx.data::<Arc<Database>>()?;
+
+        let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let offset = offset.unwrap_or(0).max(0);
+        let filter = filter.unwrap_or_default();
+
+        let (items, total_count) = database
+            .list_tenders(&filter, sort.unwrap_or_default(), limit, offset)
+            .await?;
+
+        Ok(TenderPage {
+            total_count,
+            offset,
+            limit,
+            has_next_page: offset + (items.len() as i64) < total_count,
+            items,
+        })
+    }
+}
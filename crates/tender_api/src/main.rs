@@ -0,0 +1,903 @@
+use ai_summary::ai_service::AIService;
+use ai_summary::claude_budget::{self, BudgetExhausted};
+use ai_summary::qa::{self, TenderDocument};
+use analytics::public::{self, Dimension};
+use environment::Environment;
+use lambda_http::{run, service_fn, Body, Error, Request, RequestExt, RequestPayloadExt, Response};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tender_evaluation::{Evaluator, UnreadablePdf, Upload};
+use tracing::{error, info, warn, Instrument};
+
+mod calendar;
+mod database;
+mod dataset;
+mod export;
+mod feed;
+mod feedback_page;
+mod graphql;
+mod types;
+
+use database::Database;
+use dataset::Pseudonymizer;
+use export::{ExportMode, ExportRequest};
+use feedback_page::FeedbackParams;
+use graphql::{build_schema, TenderSchema};
+use types::{Config, Recommendation, TenderFilter, TenderSort};
+
+/// Shared state built once per Lambda container
+struct AppState {
+    config: Config,
+    database: Arc<Database>,
+    schema: TenderSchema,
+}
+
+fn response(status: u16, content_type: &str, body: String) -> Result<Response<Body>, Error> {
+    Ok(Response::builder()
+        .status(status)
+        .header("content-type", content_type)
+        .body(Body::from(body))?)
+}
+
+fn json_response(status: u16, body: String) -> Result<Response<Body>, Error> {
+    response(status, "application/json", body)
+}
+
+fn error_response(status: u16, message: &str) -> Result<Response<Body>, Error> {
+    json_response(status, serde_json::json!({ "error": message }).to_string())
+}
+
+async fn handle_graphql(event: &Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let request: async_graphql::Request = match serde_json::from_slice(event.body().as_ref()) {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("⚠️ Invalid GraphQL request body: {}", e);
+            return error_response(400, &format!("Invalid GraphQL request: {}", e));
+        }
+    };
+
+    let response = state.schema.execute(request).await;
+    if response.is_err() {
+        warn!("⚠️ GraphQL errors: {:?}", response.errors);
+    }
+
+    json_response(200, serde_json::to_string(&response)?)
+}
+
+fn query_params(event: &Request) -> HashMap<String, String> {
+    event
+        .query_string_parameters()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+async fn handle_export(event: &Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let request = match ExportRequest::from_params(&query_params(event)) {
+        Ok(request) => request,
+        Err(e) => return error_response(400, &e),
+    };
+
+    let (prefix, body) = match request.mode {
+        ExportMode::Full => match export::fetch_export_rows(&state.database, &request).await {
+            Ok(tenders) => {
+                info!("📤 Exporting {} tenders as CSV", tenders.len());
+                ("tenders", export::render_csv(&tenders))
+            }
+            Err(e) => {
+                error!("❌ Export query failed: {}", e);
+                return error_response(500, "Export failed");
+            }
+        },
+        ExportMode::Anonymized => {
+            let Some(key) = &state.config.export_pseudonym_key else {
+                return error_response(503, "Anonymized export is not configured");
+            };
+            match export::fetch_dataset_rows(&state.database, &request).await {
+                Ok(rows) => {
+                    info!("📤 Exporting {} anonymized tenders as CSV", rows.len());
+                    let csv = export::render_dataset_csv(&rows, &Pseudonymizer::new(key));
+                    ("tender_dataset", csv)
+                }
+                Err(e) => {
+                    error!("❌ Dataset export query failed: {}", e);
+                    return error_response(500, "Export failed");
+                }
+            }
+        }
+    };
+
+    let filename = format!(
+        "{}_{}.csv",
+        prefix,
+        chrono::Utc::now().format("%Y%m%d_%H%M%S")
+    );
+    Ok(Response::builder()
+        .status(200)
+        .header("content-type", "text/csv; charset=utf-8")
+        .header(
+            "content-disposition",
+            format!("attachment; filename=\"{}\"", filename),
+        )
+        .body(Body::from(body))?)
+}
+
+async fn handle_feed(event: &Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let filter = TenderFilter {
+        recommendation: Some(Recommendation::Bid),
+        ..Default::default()
+    };
+
+    let tenders = match state
+        .database
+        .list_tenders(&filter, TenderSort::default(), feed::FEED_ENTRY_LIMIT, 0)
+        .await
+    {
+        Ok((tenders, _)) => tenders,
+        Err(e) => {
+            error!("❌ Feed query failed: {}", e);
+            return error_response(500, "Feed unavailable");
+        }
+    };
+
+    // Prefer the configured public URL; fall back to the Host header behind API Gateway
+    let base_url = state.config.public_base_url.clone().unwrap_or_else(|| {
+        let host = event
+            .headers()
+            .get("host")
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or("localhost");
+        format!("https://{}", host)
+    });
+    let feed_url = format!("{}/feed.atom", base_url.trim_end_matches('/'));
+
+    response(
+        200,
+        "application/atom+xml; charset=utf-8",
+        feed::render_atom_feed(&tenders, &feed_url),
+    )
+}
+
+fn html_response(status: u16, body: String) -> Result<Response<Body>, Error> {
+    response(status, "text/html; charset=utf-8", body)
+}
+
+/// Signed link parameters, or the error response to send instead
+#[allow(clippy::result_large_err)]
+fn signed_feedback_params(
+    params: &HashMap<String, String>,
+    state: &AppState,
+) -> Result<FeedbackParams, Result<Response<Body>, Error>> {
+    let Some(key) = &state.config.feedback_signing_key else {
+        return Err(error_response(503, "Feedback is not configured"));
+    };
+    let params = FeedbackParams::from_params(params).map_err(|e| error_response(400, &e))?;
+    if !params.is_signed_with(key) {
+        warn!(
+            "⚠️ Rejected feedback with a bad signature for tender {}",
+            params.resource_id
+        );
+        return Err(error_response(403, "Invalid feedback link"));
+    }
+    Ok(params)
+}
+
+/// Email link target: confirm before recording, so link scanners can't leave feedback
+async fn handle_feedback_link(event: &Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let params = match signed_feedback_params(&query_params(event), state) {
+        Ok(params) => params,
+        Err(response) => return response,
+    };
+
+    let title = match state.database.get_tender(params.resource_id).await {
+        Ok(tender) => tender.map(|t| t.title),
+        Err(e) => {
+            warn!("⚠️ Could not load tender {}: {}", params.resource_id, e);
+            None
+        }
+    };
+
+    html_response(
+        200,
+        feedback_page::render_confirmation(&params, title.as_deref()),
+    )
+}
+
+async fn handle_feedback_submit(
+    event: &Request,
+    state: &AppState,
+) -> Result<Response<Body>, Error> {
+    let form: HashMap<String, String> = match event.payload() {
+        Ok(Some(form)) => form,
+        Ok(None) => return error_response(400, "Missing form body"),
+        Err(e) => return error_response(400, &format!("Invalid form body: {}", e)),
+    };
+    let params = match signed_feedback_params(&form, state) {
+        Ok(params) => params,
+        Err(response) => return response,
+    };
+
+    let pool = state.database.write_pool();
+    let recorded = match feedback::ensure_table(pool).await {
+        Ok(()) => {
+            feedback::record(pool, params.resource_id, &params.tenant_id, params.verdict).await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = recorded {
+        error!(
+            "❌ Failed to record feedback for tender {}: {}",
+            params.resource_id, e
+        );
+        return error_response(500, "Feedback could not be recorded");
+    }
+
+    info!(
+        "📝 Recorded '{}' feedback for tender {} (tenant: {})",
+        params.verdict.as_str(),
+        params.resource_id,
+        params.tenant_id
+    );
+    html_response(200, feedback_page::render_thanks(params.verdict))
+}
+
+/// Constant-time check of the `Authorization: Bearer` header against a configured token
+fn bearer_authorized(event: &Request, token: &str) -> bool {
+    let Some(presented) = event
+        .headers()
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// `GET /tags` lists every tag with its tender count; `?resource_id=` lists one tender's
+async fn handle_list_tags(event: &Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let pool = state.database.pool();
+    let body = match query_params(event).get("resource_id") {
+        Some(resource_id) => {
+            let Ok(resource_id) = resource_id.parse::<i64>() else {
+                return error_response(400, "Invalid resource_id");
+            };
+            tender_tags::for_tender(pool, resource_id)
+                .await
+                .map(|tags| serde_json::json!({ "resource_id": resource_id, "tags": tags }))
+        }
+        None => tender_tags::counts(pool).await.map(|counts| {
+            let tags: Vec<_> = counts
+                .iter()
+                .map(|(tag, tenders)| {
+                    serde_json::json!({
+                        "tag": tag,
+                        "tenders": tenders,
+                        "controlled": tender_tags::is_controlled(tag),
+                    })
+                })
+                .collect();
+            serde_json::json!({ "tags": tags })
+        }),
+    };
+
+    match body {
+        Ok(body) => json_response(200, body.to_string()),
+        Err(e) => {
+            error!("❌ Tag query failed: {}", e);
+            error_response(500, "Tags unavailable")
+        }
+    }
+}
+
+/// `POST /tags?resource_id=&tag=` tags a tender, `DELETE` with the same parameters untags it
+async fn handle_edit_tag(event: &Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let Some(token) = &state.config.tag_api_token else {
+        return error_response(503, "Tag editing is not configured");
+    };
+    if !bearer_authorized(event, token) {
+        return error_response(401, "Missing or invalid bearer token");
+    }
+
+    let params = query_params(event);
+    let Some(resource_id) = params
+        .get("resource_id")
+        .and_then(|id| id.parse::<i64>().ok())
+    else {
+        return error_response(400, "Missing or invalid resource_id");
+    };
+    let tag = match params.get("tag").map(|tag| tender_tags::normalize(tag)) {
+        Some(Ok(tag)) => tag,
+        Some(Err(e)) => return error_response(400, &e.to_string()),
+        None => return error_response(400, "Missing tag"),
+    };
+
+    let pool = state.database.write_pool();
+    let adding = event.method() == "POST";
+    let changed = if adding {
+        let created_by = params.get("by").map(String::as_str).unwrap_or("api");
+        tender_tags::add(pool, resource_id, &tag, created_by).await
+    } else {
+        tender_tags::remove(pool, resource_id, &tag).await
+    };
+
+    match changed {
+        Ok(changed) => {
+            info!(
+                "🏷️ {} '{}' on tender {}{}",
+                if adding { "Tagged" } else { "Untagged" },
+                tag,
+                resource_id,
+                if changed { "" } else { " (no change)" }
+            );
+            json_response(
+                200,
+                serde_json::json!({ "resource_id": resource_id, "tag": tag, "changed": changed })
+                    .to_string(),
+            )
+        }
+        Err(e) => {
+            error!("❌ Failed to update tag on tender {}: {}", resource_id, e);
+            error_response(500, "Tag could not be updated")
+        }
+    }
+}
+
+/// `GET /override?resource_id=` shows a tender's override, or all of this tenant's without
+/// a resource_id
+async fn handle_get_override(event: &Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let pool = state.database.pool();
+    let tenant_id = &state.config.tenant_id;
+    let body = match query_params(event).get("resource_id") {
+        Some(resource_id) => {
+            let Ok(resource_id) = resource_id.parse::<i64>() else {
+                return error_response(400, "Invalid resource_id");
+            };
+            tender_overrides::get(pool, resource_id, tenant_id)
+                .await
+                .map(|current| serde_json::json!({ "resource_id": resource_id, "override": current }))
+        }
+        None => tender_overrides::list(pool, tenant_id)
+            .await
+            .map(|overrides| serde_json::json!({ "overrides": overrides })),
+    };
+
+    match body {
+        Ok(body) => json_response(200, body.to_string()),
+        Err(e) => {
+            error!("❌ Override query failed: {}", e);
+            error_response(500, "Overrides unavailable")
+        }
+    }
+}
+
+/// `POST /override?resource_id=&recommendation=bid|no_bid` forces a tender's recommendation,
+/// `DELETE /override?resource_id=` goes back to Claude's; `POST /snooze?resource_id=&days=`
+/// holds back its emails and reminders, `DELETE /snooze?resource_id=` ends that early.
+/// All take optional `reason=` and `by=`
+async fn handle_edit_override(
+    event: &Request,
+    state: &AppState,
+    snooze: bool,
+) -> Result<Response<Body>, Error> {
+    let Some(token) = &state.config.override_api_token else {
+        return error_response(503, "Overrides are not configured");
+    };
+    if !bearer_authorized(event, token) {
+        return error_response(401, "Missing or invalid bearer token");
+    }
+
+    let params = query_params(event);
+    let Some(resource_id) = params
+        .get("resource_id")
+        .and_then(|id| id.parse::<i64>().ok())
+    else {
+        return error_response(400, "Missing or invalid resource_id");
+    };
+    let change = tender_overrides::Change {
+        tenant_id: &state.config.tenant_id,
+        set_by: params.get("by").map(String::as_str).unwrap_or("api"),
+        reason: params.get("reason").map(String::as_str),
+        component: "tender_api",
+    };
+
+    let pool = state.database.write_pool();
+    let setting = event.method() == "POST";
+    let updated = if snooze {
+        let until = if setting {
+            let days = params.get("days").and_then(|d| d.parse::<i64>().ok());
+            match days.map(|days| tender_overrides::snooze_until(days, chrono::Utc::now())) {
+                Some(Ok(until)) => Some(until),
+                Some(Err(e)) => return error_response(400, &e.to_string()),
+                None => return error_response(400, "Missing or invalid days"),
+            }
+        } else {
+            None
+        };
+        tender_overrides::snooze(pool, resource_id, until, change).await
+    } else {
+        let forced = if setting {
+            match params
+                .get("recommendation")
+                .and_then(|r| tender_overrides::Forced::parse(r))
+            {
+                Some(forced) => Some(forced),
+                None => return error_response(400, "recommendation must be bid or no_bid"),
+            }
+        } else {
+            None
+        };
+        tender_overrides::force(pool, resource_id, forced, change).await
+    };
+
+    match updated {
+        Ok(current) => {
+            info!(
+                "✋ {} {} on tender {} (tenant: {})",
+                if setting { "Set" } else { "Cleared" },
+                if snooze {
+                    "snooze"
+                } else {
+                    "forced recommendation"
+                },
+                resource_id,
+                state.config.tenant_id
+            );
+            json_response(
+                200,
+                serde_json::json!({ "resource_id": resource_id, "override": current }).to_string(),
+            )
+        }
+        Err(e) => {
+            error!(
+                "❌ Failed to update override on tender {}: {}",
+                resource_id, e
+            );
+            error_response(500, "Override could not be updated")
+        }
+    }
+}
+
+/// Body of `POST /saved-searches`
+#[derive(serde::Deserialize)]
+struct NewSavedSearch {
+    owner_email: String,
+    name: String,
+    criteria: saved_searches::SearchCriteria,
+}
+
+/// `GET /saved-searches?owner=` lists an owner's searches, `POST` saves one (JSON body)
+/// and `DELETE ?id=&owner=` stops one
+async fn handle_saved_searches(event: &Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let Some(token) = &state.config.saved_search_api_token else {
+        return error_response(503, "Saved searches are not configured");
+    };
+    if !bearer_authorized(event, token) {
+        return error_response(401, "Missing or invalid bearer token");
+    }
+
+    let pool = state.database.write_pool();
+    if let Err(e) = saved_searches::ensure_tables(pool).await {
+        error!("❌ Failed to set up saved search tables: {}", e);
+        return error_response(500, "Saved searches unavailable");
+    }
+
+    let params = query_params(event);
+    match event.method().as_str() {
+        "GET" => {
+            let Some(owner) = params.get("owner") else {
+                return error_response(400, "Missing owner");
+            };
+            match saved_searches::list_for_owner(pool, owner).await {
+                Ok(searches) => json_response(
+                    200,
+                    serde_json::json!({ "saved_searches": searches }).to_string(),
+                ),
+                Err(e) => {
+                    error!("❌ Saved search query failed: {}", e);
+                    error_response(500, "Saved searches unavailable")
+                }
+            }
+        }
+        "POST" => {
+            let request: NewSavedSearch = match serde_json::from_slice(event.body().as_ref()) {
+                Ok(request) => request,
+                Err(e) => return error_response(400, &format!("Invalid saved search: {}", e)),
+            };
+            match saved_searches::create(
+                pool,
+                &request.owner_email,
+                &request.name,
+                request.criteria,
+            )
+            .await
+            {
+                Ok(search) => {
+                    info!(
+                        "🔖 Saved search {} '{}' for {}",
+                        search.id, search.name, search.owner_email
+                    );
+                    json_response(201, serde_json::to_string(&search)?)
+                }
+                // Validation failures (no criteria, bad email, too many searches)
+                Err(e) => error_response(400, &e.to_string()),
+            }
+        }
+        _ => {
+            let (Some(id), Some(owner)) = (
+                params.get("id").and_then(|id| id.parse::<i64>().ok()),
+                params.get("owner"),
+            ) else {
+                return error_response(400, "Missing or invalid id or owner");
+            };
+            match saved_searches::deactivate(pool, id, owner).await {
+                Ok(true) => json_response(200, serde_json::json!({ "id": id }).to_string()),
+                Ok(false) => error_response(404, "No such saved search"),
+                Err(e) => {
+                    error!("❌ Failed to remove saved search {}: {}", id, e);
+                    error_response(500, "Saved search could not be removed")
+                }
+            }
+        }
+    }
+}
+
+/// `GET /analytics/monthly?months=` totals per month; `GET /analytics/authorities?months=&limit=`
+/// the same figures per month for the authorities with the most IT tenders
+/// The `months` query parameter of the analytics endpoints
+fn months_param(params: &HashMap<String, String>) -> Result<u32, String> {
+    match params.get("months").map(|m| m.parse::<u32>()) {
+        None => Ok(analytics::DEFAULT_MONTHS),
+        Some(Ok(months)) if (1..=analytics::MAX_MONTHS).contains(&months) => Ok(months),
+        Some(_) => Err(format!("months must be 1-{}", analytics::MAX_MONTHS)),
+    }
+}
+
+async fn handle_analytics(
+    event: &Request,
+    state: &AppState,
+    by_authority: bool,
+) -> Result<Response<Body>, Error> {
+    let params = query_params(event);
+    let months = match months_param(&params) {
+        Ok(months) => months,
+        Err(e) => return error_response(400, &e),
+    };
+    let limit = match params.get("limit").map(|l| l.parse::<i64>()) {
+        None => analytics::DEFAULT_AUTHORITIES,
+        Some(Ok(limit)) if (1..=analytics::MAX_AUTHORITIES).contains(&limit) => limit,
+        Some(_) => {
+            return error_response(
+                400,
+                &format!("limit must be 1-{}", analytics::MAX_AUTHORITIES),
+            )
+        }
+    };
+
+    let since = analytics::window_start(chrono::Utc::now().date_naive(), months);
+    let pool = state.database.pool();
+    let trends = if by_authority {
+        analytics::by_authority(pool, since, limit).await
+    } else {
+        analytics::monthly(pool, since).await
+    };
+
+    match trends {
+        Ok(trends) => json_response(
+            200,
+            serde_json::json!({ "since": since, "months": trends }).to_string(),
+        ),
+        Err(e) => {
+            // Usually analytics_refresh hasn't created the view yet
+            error!("❌ Analytics query failed: {}", e);
+            error_response(500, "Analytics unavailable")
+        }
+    }
+}
+
+/// `GET /public/trends?months=` IT tender counts and median values by month, CPV division
+/// and authority, for publishing externally: small groups are pooled and thin medians
+/// withheld (see `analytics::public`)
+async fn handle_public_trends(event: &Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let months = match months_param(&query_params(event)) {
+        Ok(months) => months,
+        Err(e) => return error_response(400, &e),
+    };
+    let since = analytics::window_start(chrono::Utc::now().date_naive(), months);
+    let min_cell = public::min_cell_from_env();
+
+    let mut body = serde_json::json!({ "since": since, "min_cell": min_cell });
+    for dimension in Dimension::ALL {
+        match public::breakdown(state.database.pool(), since, dimension, min_cell).await {
+            Ok(cells) => body[format!("by_{}", dimension.name())] = serde_json::to_value(cells)?,
+            Err(e) => {
+                error!("❌ Public trends query failed: {}", e);
+                return error_response(500, "Public trends unavailable");
+            }
+        }
+    }
+    json_response(200, body.to_string())
+}
+
+/// `GET /calendar?weeks=` open BID-recommended tenders by deadline week with estimated
+/// effort; `format=html` renders the dashboard view
+async fn handle_calendar(event: &Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let params = query_params(event);
+    let weeks = match calendar::weeks_param(&params) {
+        Ok(weeks) => weeks,
+        Err(e) => return error_response(400, &e),
+    };
+
+    let now = chrono::Utc::now().naive_utc();
+    let tenders = match calendar::fetch_open_bids(&state.database, now, weeks).await {
+        Ok(tenders) => tenders,
+        Err(e) => {
+            error!("❌ Calendar query failed: {}", e);
+            return error_response(500, "Calendar unavailable");
+        }
+    };
+    let calendar = calendar::group_by_week(&tenders);
+
+    if params.get("format").map(String::as_str) == Some("html") {
+        return html_response(200, calendar::render_html(&calendar));
+    }
+    json_response(
+        200,
+        serde_json::json!({ "weeks_ahead": weeks, "weeks": calendar }).to_string(),
+    )
+}
+
+/// `GET /audit?resource_id=` every automated decision recorded for the tender, oldest first
+async fn handle_audit(event: &Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let Some(Ok(resource_id)) = query_params(event)
+        .get("resource_id")
+        .map(|id| id.parse::<i64>())
+    else {
+        return error_response(400, "Missing or invalid resource_id");
+    };
+
+    match decision_audit::trail(state.database.pool(), resource_id).await {
+        Ok(decisions) => json_response(
+            200,
+            serde_json::json!({ "resource_id": resource_id, "decisions": decisions }).to_string(),
+        ),
+        Err(e) => {
+            error!("❌ Audit query failed: {}", e);
+            error_response(500, "Audit trail unavailable")
+        }
+    }
+}
+
+/// `POST /evaluate?title=&authority=` runs the tender PDF in the body through extraction,
+/// code detection, the bid model and Claude, and returns the verdict; nothing is stored
+async fn handle_evaluate(event: &Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let (Some(token), Some(api_key)) = (
+        &state.config.evaluate_api_token,
+        &state.config.anthropic_api_key,
+    ) else {
+        return error_response(503, "PDF evaluation is not configured");
+    };
+    if !bearer_authorized(event, token) {
+        return error_response(401, "Missing or invalid bearer token");
+    }
+
+    let params = query_params(event);
+    let Some(title) = params
+        .get("title")
+        .map(|title| title.trim())
+        .filter(|title| !title.is_empty())
+    else {
+        return error_response(400, "Missing title");
+    };
+    let upload = Upload {
+        title: title.to_string(),
+        contracting_authority: params
+            .get("authority")
+            .map(|authority| authority.trim())
+            .filter(|authority| !authority.is_empty())
+            .unwrap_or("Unknown")
+            .to_string(),
+        pdf: event.body().to_vec(),
+    };
+
+    let evaluator = match Evaluator::new(state.database.write_pool().clone(), api_key.clone()).await
+    {
+        Ok(evaluator) => evaluator,
+        Err(e) => {
+            error!("❌ Failed to set up PDF evaluation: {}", e);
+            return error_response(500, "PDF evaluation unavailable");
+        }
+    };
+    match evaluator.evaluate(upload).await {
+        Ok(verdict) => {
+            info!(
+                "📎 Evaluated uploaded PDF '{}': {} (decided by {})",
+                verdict.title,
+                if verdict.bid { "BID" } else { "NO BID" },
+                verdict.decided_by
+            );
+            json_response(200, serde_json::to_string(&verdict)?)
+        }
+        Err(e) if e.is::<UnreadablePdf>() => error_response(400, &e.to_string()),
+        Err(e) => {
+            error!("❌ PDF evaluation failed: {}", e);
+            error_response(500, "PDF evaluation failed")
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct QuestionRequest {
+    resource_id: i64,
+    question: String,
+}
+
+/// `POST /ask` with `{"resource_id": ..., "question": "..."}` answers from the tender's stored
+/// PDF text and summary, citing the pages used
+async fn handle_ask(event: &Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let (Some(token), Some(api_key)) =
+        (&state.config.ask_api_token, &state.config.anthropic_api_key)
+    else {
+        return error_response(503, "Tender questions are not configured");
+    };
+    if !bearer_authorized(event, token) {
+        return error_response(401, "Missing or invalid bearer token");
+    }
+
+    let request: QuestionRequest = match serde_json::from_slice(event.body().as_ref()) {
+        Ok(request) => request,
+        Err(e) => return error_response(400, &format!("Invalid question: {}", e)),
+    };
+    let question = request.question.trim();
+    if question.is_empty() {
+        return error_response(400, "Missing question");
+    }
+    if question.chars().count() > qa::MAX_QUESTION_CHARS {
+        return error_response(
+            400,
+            &format!(
+                "Questions are limited to {} characters",
+                qa::MAX_QUESTION_CHARS
+            ),
+        );
+    }
+
+    let resource_id = request.resource_id;
+    let (tender, pdf) = match tokio::try_join!(
+        state.database.get_tender(resource_id),
+        state.database.get_pdf_text(resource_id)
+    ) {
+        Ok((Some(tender), pdf)) => (tender, pdf),
+        Ok((None, _)) => return error_response(404, "Tender not found"),
+        Err(e) => {
+            error!(
+                "❌ Failed to load tender {} for a question: {}",
+                resource_id, e
+            );
+            return error_response(500, "Tender questions unavailable");
+        }
+    };
+    let (pdf_text, page_offsets) =
+        pdf.map_or((None, None), |(text, offsets)| (Some(text), offsets));
+    let document = TenderDocument {
+        title: tender.title,
+        contracting_authority: tender.contracting_authority,
+        summary: tender.summary.as_ref().map(|s| s.ai_summary.clone()),
+        key_points: tender.summary.map(|s| s.key_points).unwrap_or_default(),
+        pdf_text,
+        page_offsets,
+    };
+
+    let pool = state.database.write_pool();
+    let mut ai_service = AIService::new(api_key.clone());
+    if let Some(limit) = claude_budget::daily_limit_from_env() {
+        if let Err(e) = claude_budget::ensure_table(pool).await {
+            error!("❌ Failed to set up the Claude budget table: {}", e);
+            return error_response(500, "Tender questions unavailable");
+        }
+        ai_service = ai_service.with_budget(pool.clone(), limit);
+    }
+
+    match qa::ask(&ai_service, &document, question).await {
+        Ok(answer) => {
+            info!(
+                "❓ Answered a question about tender {} ({} citations)",
+                resource_id,
+                answer.citations.len()
+            );
+            json_response(
+                200,
+                serde_json::json!({
+                    "resource_id": resource_id,
+                    "question": question,
+                    "answer": answer.answer,
+                    "citations": answer.citations,
+                    "pages_considered": answer.pages_considered,
+                })
+                .to_string(),
+            )
+        }
+        Err(e) if e.is::<BudgetExhausted>() => error_response(503, &e.to_string()),
+        Err(e) => {
+            error!("❌ Question about tender {} failed: {}", resource_id, e);
+            error_response(500, "The question could not be answered")
+        }
+    }
+}
+
+async fn function_handler(event: Request, state: &AppState) -> Result<Response<Body>, Error> {
+    let method = event.method().as_str().to_string();
+    let path = event.uri().path().to_string();
+    info!("📥 {} {}", method, path);
+
+    match (method.as_str(), path.trim_end_matches('/')) {
+        ("POST", "/graphql") => handle_graphql(&event, state).await,
+        ("GET", "/graphql") => response(200, "text/plain; charset=utf-8", state.schema.sdl()),
+        ("GET", "/export") => handle_export(&event, state).await,
+        ("GET", "/feed.atom") => handle_feed(&event, state).await,
+        ("GET", "/feedback") => handle_feedback_link(&event, state).await,
+        ("POST", "/feedback") => handle_feedback_submit(&event, state).await,
+        ("GET", "/tags") => handle_list_tags(&event, state).await,
+        ("POST", "/tags") | ("DELETE", "/tags") => handle_edit_tag(&event, state).await,
+        ("GET", "/override") => handle_get_override(&event, state).await,
+        ("POST", "/override") | ("DELETE", "/override") => {
+            handle_edit_override(&event, state, false).await
+        }
+        ("POST", "/snooze") | ("DELETE", "/snooze") => {
+            handle_edit_override(&event, state, true).await
+        }
+        ("GET", "/saved-searches")
+        | ("POST", "/saved-searches")
+        | ("DELETE", "/saved-searches") => handle_saved_searches(&event, state).await,
+        ("GET", "/analytics/monthly") => handle_analytics(&event, state, false).await,
+        ("GET", "/analytics/authorities") => handle_analytics(&event, state, true).await,
+        ("GET", "/public/trends") => handle_public_trends(&event, state).await,
+        ("GET", "/calendar") => handle_calendar(&event, state).await,
+        ("GET", "/audit") => handle_audit(&event, state).await,
+        ("POST", "/evaluate") => handle_evaluate(&event, state).await,
+        ("POST", "/ask") => handle_ask(&event, state).await,
+        _ => error_response(404, "Not found"),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    info!("=== Tender API Starting ===");
+
+    let config = Config::from_env().map_err(|e| {
+        error!("Failed to load configuration: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+
+    let database = Database::new(&config).await.map_err(|e| {
+        error!("Failed to initialize database: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+
+    let database = Arc::new(database);
+    let state = AppState {
+        config,
+        schema: build_schema(database.clone()),
+        database,
+    };
+    let state_ref = &state;
+    let environment = Environment::from_env();
+
+    run(service_fn(move |event: Request| async move {
+        function_handler(event, state_ref)
+            .instrument(environment.span())
+            .await
+    }))
+    .await
+}
@@ -0,0 +1,224 @@
+use async_graphql::{Enum, InputObject, SimpleObject};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Tender as exposed to API consumers, joined with its ML prediction,
+/// Claude summary, notification state and manual override
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+pub struct Tender {
+    pub resource_id: i64,
+    pub title: String,
+    pub contracting_authority: String,
+    pub info: String,
+    pub published: Option<NaiveDateTime>,
+    pub deadline: Option<NaiveDateTime>,
+    pub procedure: String,
+    pub status: String,
+    pub pdf_url: String,
+    pub value: Option<String>, // Decimal rendered as string to avoid float rounding
+    pub bid: Option<i32>,      // 1 = bid, 0 = no bid, NULL = unlabeled
+    pub portal_link: String,
+    pub tags: Vec<String>,
+    pub prediction: Option<Prediction>,
+    pub summary: Option<Summary>,
+    pub notification: Notification,
+    pub manual_override: Option<ManualOverride>,
+}
+
+/// ML bid/no-bid prediction stored by ml_bid_predictor
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+pub struct Prediction {
+    pub should_bid: Option<bool>,
+    pub confidence: Option<f64>,
+    pub reasoning: Option<String>,
+    pub status: Option<String>,
+}
+
+/// Claude summary stored by ai_summary
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+pub struct Summary {
+    pub summary_type: String, // "TITLE_ONLY" or "FULL_PDF"
+    pub ai_summary: String,
+    pub key_points: Vec<String>,
+    pub recommendation: String,
+    pub confidence_assessment: String,
+    pub processing_notes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Notification state stored by sns_notification
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+pub struct Notification {
+    pub sent: bool,
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+/// Recommendation forced by hand or snooze, from `tender_overrides`
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+pub struct ManualOverride {
+    /// Replaces Claude's recommendation when set
+    pub recommendation: Option<Recommendation>,
+    /// No emails or reminders until then
+    pub snoozed_until: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+    pub set_by: String,
+    pub set_at: DateTime<Utc>,
+}
+
+/// One page of tenders plus the total number of matches
+#[derive(Debug, Clone, Serialize, SimpleObject)]
+pub struct TenderPage {
+    pub total_count: i64,
+    pub offset: i64,
+    pub limit: i64,
+    pub has_next_page: bool,
+    pub items: Vec<Tender>,
+}
+
+/// Claude recommendation bucket, matching the notification decision logic; a
+/// recommendation forced by hand takes its place
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum Recommendation {
+    Bid,
+    NoBid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum TenderSortField {
+    Published,
+    Deadline,
+    Value,
+    MlConfidence,
+    ResourceId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// Filters shared by every tender listing endpoint
+#[derive(Debug, Clone, Default, Deserialize, InputObject)]
+pub struct TenderFilter {
+    pub title_contains: Option<String>,
+    pub contracting_authority: Option<String>,
+    pub status: Option<String>,
+    pub ml_bid: Option<bool>,
+    pub recommendation: Option<Recommendation>,
+    pub notification_sent: Option<bool>,
+    pub published_after: Option<NaiveDateTime>,
+    pub published_before: Option<NaiveDateTime>,
+    pub deadline_after: Option<NaiveDateTime>,
+    pub deadline_before: Option<NaiveDateTime>,
+    /// Tenders carrying every one of these tags
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, InputObject)]
+pub struct TenderSort {
+    pub field: TenderSortField,
+    pub direction: SortDirection,
+}
+
+impl Default for TenderSort {
+    fn default() -> Self {
+        Self {
+            field: TenderSortField::Published,
+            direction: SortDirection::Desc,
+        }
+    }
+}
+
+/// Configuration from environment
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database_url: String,
+    pub public_base_url: Option<String>, // Used for self links in the Atom feed
+    pub tenant_id: String,               // Whose AI summaries are served
+    /// HMAC key for anonymized export pseudonyms; that export mode is disabled without it.
+    /// Keep it stable - changing it changes every pseudonym
+    pub export_pseudonym_key: Option<String>,
+    /// HMAC key for the feedback links in notification emails (same value as sns_notification's);
+    /// the feedback routes are disabled without it
+    pub feedback_signing_key: Option<String>,
+    /// Bearer token for the tag-editing routes; they are disabled without it
+    pub tag_api_token: Option<String>,
+    /// Bearer token for the override and snooze routes; they are disabled without it
+    pub override_api_token: Option<String>,
+    /// Bearer token for the saved-search routes; they are disabled without it
+    pub saved_search_api_token: Option<String>,
+    /// Bearer token for PDF evaluation, which also needs `anthropic_api_key`
+    pub evaluate_api_token: Option<String>,
+    /// Bearer token for tender questions, which also need `anthropic_api_key`
+    pub ask_api_token: Option<String>,
+    pub anthropic_api_key: Option<String>,
+}
+
+impl Config {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let database_url = std::env::var("DATABASE_URL")
+            .map_err(|_| anyhow::anyhow!("DATABASE_URL environment variable not set"))?;
+
+        let public_base_url = std::env::var("PUBLIC_BASE_URL").ok();
+
+        let tenant_id = std::env::var("TENANT_ID")
+            .ok()
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or_else(|| "default".to_string());
+
+        let export_pseudonym_key = std::env::var("EXPORT_PSEUDONYM_KEY")
+            .ok()
+            .filter(|k| !k.trim().is_empty());
+
+        let feedback_signing_key = std::env::var("FEEDBACK_SIGNING_KEY")
+            .ok()
+            .filter(|k| !k.trim().is_empty());
+
+        let tag_api_token = std::env::var("TAG_API_TOKEN")
+            .ok()
+            .filter(|t| !t.trim().is_empty());
+
+        let override_api_token = std::env::var("OVERRIDE_API_TOKEN")
+            .ok()
+            .filter(|t| !t.trim().is_empty());
+
+        let saved_search_api_token = std::env::var("SAVED_SEARCH_API_TOKEN")
+            .ok()
+            .filter(|t| !t.trim().is_empty());
+
+        let evaluate_api_token = std::env::var("EVALUATE_API_TOKEN")
+            .ok()
+            .filter(|t| !t.trim().is_empty());
+
+        let ask_api_token = std::env::var("ASK_API_TOKEN")
+            .ok()
+            .filter(|t| !t.trim().is_empty());
+
+        let anthropic_api_key = std::env::var("ANTHROPIC_API_KEY")
+            .ok()
+            .filter(|k| !k.trim().is_empty());
+
+        Ok(Self {
+            database_url,
+            public_base_url,
+            tenant_id,
+            export_pseudonym_key,
+            feedback_signing_key,
+            tag_api_token,
+            override_api_token,
+            saved_search_api_token,
+            evaluate_api_token,
+            ask_api_token,
+            anthropic_api_key,
+        })
+    }
+}
+
+/// Build the eTenders portal link for a tender
+pub fn portal_link(resource_id: i64) -> String {
+    format!(
+        "https://etenders.gov.ie/epps/opportunity/opportunityDetailAction.do?opportunityId={}",
+        resource_id
+    )
+}
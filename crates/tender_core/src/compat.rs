@@ -0,0 +1,103 @@
+//! Deserializers for the field shapes older stages sent.
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+use std::fmt;
+
+/// A resource id sent as a number (`TenderRecord`) or a string (the queue messages)
+pub fn resource_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    deserializer.deserialize_any(ResourceIdVisitor)
+}
+
+/// A resource id kept as the string the queue messages carry, also accepting a number
+pub fn resource_id_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    resource_id(deserializer).map(|id| id.to_string())
+}
+
+struct ResourceIdVisitor;
+
+impl Visitor<'_> for ResourceIdVisitor {
+    type Value = i64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a resource id as a number or a string of digits")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<i64, E> {
+        Ok(value)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<i64, E> {
+        i64::try_from(value).map_err(|_| E::custom(format!("resource id {} out of range", value)))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<i64, E> {
+        value
+            .trim()
+            .parse()
+            .map_err(|_| E::custom(format!("invalid resource id '{}'", value)))
+    }
+}
+
+/// An optional number that may have been serialized as a decimal string (BigDecimal does)
+pub fn optional_f64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Number {
+        Float(f64),
+        Text(String),
+    }
+
+    match Option::<Number>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Number::Float(value)) => Ok(Some(value)),
+        Some(Number::Text(text)) => text
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| de::Error::custom(format!("invalid number '{}'", text))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Message {
+        #[serde(deserialize_with = "resource_id")]
+        id: i64,
+        #[serde(deserialize_with = "resource_id_string")]
+        id_text: String,
+        #[serde(default, deserialize_with = "optional_f64")]
+        confidence: Option<f64>,
+    }
+
+    fn parse(json: &str) -> Result<Message, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    #[test]
+    fn test_resource_id_as_number_or_string() {
+        let message = parse(r#"{"id": 5850990, "id_text": "5850990"}"#).unwrap();
+        assert_eq!(message.id, 5850990);
+        assert_eq!(message.id_text, "5850990");
+        assert_eq!(message.confidence, None);
+
+        let message = parse(r#"{"id": " -7 ", "id_text": 42, "confidence": null}"#).unwrap();
+        assert_eq!(message.id, -7);
+        assert_eq!(message.id_text, "42");
+
+        assert!(parse(r#"{"id": "T-1", "id_text": "1"}"#).is_err());
+        assert!(parse(r#"{"id": 1.5, "id_text": "1"}"#).is_err());
+    }
+
+    #[test]
+    fn test_confidence_as_number_or_decimal_string() {
+        let message = parse(r#"{"id": 1, "id_text": "1", "confidence": 0.75}"#).unwrap();
+        assert_eq!(message.confidence, Some(0.75));
+        let message = parse(r#"{"id": 1, "id_text": "1", "confidence": "0.750"}"#).unwrap();
+        assert_eq!(message.confidence, Some(0.75));
+        assert!(parse(r#"{"id": 1, "id_text": "1", "confidence": "high"}"#).is_err());
+    }
+}
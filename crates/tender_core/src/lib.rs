@@ -0,0 +1,25 @@
+//! The messages the pipeline stages pass each other, defined once.
+//!
+//! A tender travels scraper → postgres_dataload → pdf_processing → ml_bid_predictor →
+//! ai_summary as a `TenderRecord`, each stage filling in its own fields; ai_summary also
+//! takes an `AISummaryMessage` carrying the ML result, and everything that emails goes to
+//! sns_notification as an `SNSMessage`. Every stage reads and writes these types, so a
+//! field is added or renamed here and nowhere else.
+//!
+//! Stages are deployed one at a time, so a message can come from an older producer. The
+//! shims in `compat` accept the shapes those have sent: a resource id as a number or a
+//! string, `ca` for the contracting authority, and a decimal ML confidence as a string.
+//! Fields a stage adds are optional and left out of the JSON until set.
+//!
+//! Database rows convert into a `TenderRecord` in `db::rows`.
+
+pub mod compat;
+mod messages;
+mod prediction;
+mod tender;
+
+pub use messages::{
+    AISummaryMessage, SNSMessage, OPS_MESSAGE_TYPES, RESEND_MESSAGE_TYPE, RUN_SUMMARY_MESSAGE_TYPE,
+};
+pub use prediction::{FeatureScores, MLPredictionResult};
+pub use tender::{BuyerContact, TenderDocument, TenderLot, TenderRecord};
@@ -0,0 +1,86 @@
+use crate::compat;
+use crate::prediction::MLPredictionResult;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The scraper's end-of-run summary
+pub const RUN_SUMMARY_MESSAGE_TYPE: &str = "SCRAPER_RUN_SUMMARY";
+/// Ops summaries (not tender notifications) carry these message types
+pub const OPS_MESSAGE_TYPES: &[&str] = &[RUN_SUMMARY_MESSAGE_TYPE];
+/// A past notification re-sent by `ops_cli notify resend`; emailed, but the tender isn't re-marked
+pub const RESEND_MESSAGE_TYPE: &str = "NOTIFICATION_RESEND";
+
+/// Asks ai_summary to summarise a tender, with the ML result that got it there
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AISummaryMessage {
+    #[serde(deserialize_with = "compat::resource_id_string")]
+    pub resource_id: String,
+    pub tender_title: String,
+    pub ml_prediction: MLPredictionResult,
+    /// May be truncated or empty; ai_summary reads the full text from pdf_content
+    #[serde(default)]
+    pub pdf_content: String,
+    pub priority: String, // "URGENT" or "NORMAL"
+    pub timestamp: DateTime<Utc>,
+    /// Evaluate for this tenant only; every active tenant when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    /// Call Claude even if a cached result exists for this content (replacing it)
+    #[serde(default)]
+    pub refresh: bool,
+    /// Released from the nightly batch, so summarised now whatever the routing policy says
+    #[serde(default)]
+    pub batched: bool,
+    /// How many invocations have already read parts of a long document (see ai_summary's `chunked`)
+    #[serde(default)]
+    pub continuation: u32,
+}
+
+/// What sns_notification emails: a tender notification or an ops summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SNSMessage {
+    pub message_type: String, // e.g. "AI_SUMMARY_COMPLETE", "ML_RESULT", "SCRAPER_RUN_SUMMARY"
+    #[serde(deserialize_with = "compat::resource_id_string")]
+    pub resource_id: String,
+    pub title: String,
+    pub priority: String, // "HIGH", "URGENT", "LOW"
+    pub summary: String,
+    pub action_required: String,
+    pub timestamp: DateTime<Utc>,
+    pub metadata: serde_json::Value,
+}
+
+impl SNSMessage {
+    pub fn is_ops_summary(&self) -> bool {
+        OPS_MESSAGE_TYPES.contains(&self.message_type.as_str())
+    }
+
+    pub fn is_resend(&self) -> bool {
+        self.message_type == RESEND_MESSAGE_TYPE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_title_only_message_from_pdf_processing() {
+        let json = r#"{
+            "resource_id": 5850990,
+            "tender_title": "Managed print services",
+            "ml_prediction": {"should_bid": false, "confidence": 0.0, "feature_scores": {}},
+            "priority": "NORMAL",
+            "timestamp": "2026-10-16T09:00:00Z"
+        }"#;
+        let message: AISummaryMessage = serde_json::from_str(json).unwrap();
+        assert_eq!(message.resource_id, "5850990");
+        assert_eq!(message.ml_prediction.reasoning, "No reasoning provided");
+        assert_eq!(message.pdf_content, "");
+        assert!(!message.refresh);
+
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["resource_id"], "5850990");
+        assert!(json.get("tenant_id").is_none());
+    }
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// ml_bid_predictor's verdict on a tender
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MLPredictionResult {
+    pub should_bid: bool,
+    pub confidence: f64,
+    #[serde(default = "default_reasoning")]
+    pub reasoning: String,
+    pub feature_scores: FeatureScores,
+}
+
+fn default_reasoning() -> String {
+    "No reasoning provided".to_string()
+}
+
+/// Feature scores for transparency and debugging
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeatureScores {
+    #[serde(default)]
+    pub codes_count_score: f64,
+    #[serde(default)]
+    pub has_codes_score: f64,
+    #[serde(default)]
+    pub title_length_score: f64,
+    #[serde(default)]
+    pub ca_score: f64,
+    #[serde(default)]
+    pub text_features_score: f64,
+    #[serde(default)]
+    pub total_score: f64,
+}
+
+impl FeatureScores {
+    /// The `count` features that moved the score most (either way), largest first
+    pub fn top(&self, count: usize) -> Vec<(&'static str, f64)> {
+        let mut features = vec![
+            ("Procurement code count", self.codes_count_score),
+            ("Has procurement codes", self.has_codes_score),
+            ("Title length", self.title_length_score),
+            ("Contracting authority history", self.ca_score),
+            ("Title and description keywords", self.text_features_score),
+        ];
+        features.retain(|(_, score)| *score != 0.0);
+        features.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+        features.truncate(count);
+        features
+    }
+}
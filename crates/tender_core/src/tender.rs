@@ -0,0 +1,168 @@
+use crate::compat;
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+/// A tender as it moves through the stages. The scraper sets the portal fields and
+/// `documents`; each later stage adds its own and forwards the rest unchanged
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TenderRecord {
+    pub title: String,
+    #[serde(deserialize_with = "compat::resource_id")]
+    pub resource_id: i64,
+    #[serde(alias = "ca")]
+    pub contracting_authority: String,
+    /// The tender's description from its detail page, from the scraper; empty when the page
+    /// wasn't read
+    pub info: String,
+    pub published: Option<NaiveDateTime>,
+    pub deadline: Option<NaiveDateTime>,
+    pub procedure: String,
+    pub status: String,
+    pub pdf_url: String,
+    pub awarddate: Option<NaiveDate>,
+    pub value: Option<BigDecimal>,
+    pub cycle: String,
+    pub bid: Option<i32>, // 1 = bid, 0 = no bid, NULL = unlabeled
+
+    /// Every document attached to the tender, from the scraper; `pdf_url` stays the notice
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub documents: Vec<TenderDocument>,
+    /// The buyer's contact point from the notice page, from the scraper. Goes in the
+    /// notification email as it is, but never into a Claude prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buyer_contact: Option<BuyerContact>,
+    /// CPV codes the detail page lists, main code first (e.g. "72000000-5"), from the
+    /// scraper. Unlike `detected_codes` these are the buyer's own classification
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpv_codes: Vec<String>,
+    /// NUTS codes of the place of performance (e.g. "IE061"), from the scraper
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nuts_codes: Vec<String>,
+    /// The lots the tender is divided into, from the scraper; empty for an undivided tender
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lots: Vec<TenderLot>,
+    /// Times postgres_dataload's backpressure handed the record back to its own queue.
+    /// Always 0 in what it forwards
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub deferrals: u32,
+
+    // Added by pdf_processing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pdf_content: Option<String>,
+    /// Codes found in the PDF
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_codes: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codes_count: Option<i32>,
+    /// "fallback" when detected_codes came from the compiled-in set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codes_source: Option<String>,
+    /// Pipeline stage; queue messages carry it as an attribute instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub processing_stage: Option<String>,
+
+    // Added by ml_bid_predictor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ml_bid: Option<bool>,
+    #[serde(
+        default,
+        deserialize_with = "compat::optional_f64",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub ml_confidence: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ml_reasoning: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ml_processed: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ml_status: Option<String>,
+
+    /// Last date for clarification questions, found in the PDF by pdf_processing and read
+    /// from pdf_content by ai_summary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clarification_deadline: Option<NaiveDateTime>,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+/// One document from the scraper's inventory of a tender
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TenderDocument {
+    pub name: String,
+    /// File type as the portal gives it, else the file extension ("pdf", "docx", ...)
+    pub file_type: String,
+    pub size_bytes: Option<i64>,
+    pub url: String,
+}
+
+/// One lot of a tender divided into lots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenderLot {
+    pub number: u32,
+    pub title: String,
+}
+
+/// Who to ask about a tender, as the notice gives it; at least one field is set
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuyerContact {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+}
+
+impl BuyerContact {
+    /// None when the notice gave none of the three
+    pub fn new(name: Option<String>, email: Option<String>, phone: Option<String>) -> Option<Self> {
+        let contact = Self { name, email, phone };
+        (contact != Self::default()).then_some(contact)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scraper_record_round_trips_without_stage_fields() {
+        let json = r#"{
+            "title": "Managed print services",
+            "resource_id": 5850990,
+            "contracting_authority": "Kerry County Council",
+            "info": "",
+            "published": "2026-10-01T09:00:00",
+            "deadline": null,
+            "procedure": "Open",
+            "status": "Open",
+            "pdf_url": "https://example.ie/notice.pdf",
+            "awarddate": null,
+            "value": "120000.00",
+            "cycle": "",
+            "bid": null
+        }"#;
+        let record: TenderRecord = serde_json::from_str(json).unwrap();
+        assert_eq!(record.resource_id, 5850990);
+        assert_eq!(record.value, Some("120000.00".parse().unwrap()));
+
+        let value = serde_json::to_value(&record).unwrap();
+        let keys: Vec<&String> = value.as_object().unwrap().keys().collect();
+        assert_eq!(keys.len(), 13, "stage fields leaked into {:?}", keys);
+    }
+
+    #[test]
+    fn test_older_shapes_are_accepted() {
+        let json = r#"{
+            "title": "Laptops", "resource_id": "42", "ca": "HSE", "info": "",
+            "published": null, "deadline": null, "procedure": "", "status": "",
+            "pdf_url": "", "awarddate": null, "value": null, "cycle": "", "bid": 1,
+            "pdf_content": null, "ml_confidence": "0.81"
+        }"#;
+        let record: TenderRecord = serde_json::from_str(json).unwrap();
+        assert_eq!(record.resource_id, 42);
+        assert_eq!(record.contracting_authority, "HSE");
+        assert_eq!(record.ml_confidence, Some(0.81));
+        assert_eq!(record.pdf_content, None);
+    }
+}
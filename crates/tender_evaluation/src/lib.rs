@@ -0,0 +1,262 @@
+//! Evaluation of a tender PDF that isn't on eTenders, e.g. one received by email.
+//!
+//! The PDF goes through the pipeline's own steps in one call: text extraction and code
+//! detection as in pdf_processing, the bid model as in ml_bid_predictor, and Claude's
+//! full-PDF analysis as in ai_summary (against the default company profile, with no
+//! buyer history since the tender has no portal record). Nothing is stored - the verdict
+//! is only returned - and the Claude call counts against `CLAUDE_DAILY_CALL_BUDGET`.
+//! tender_api serves it at `POST /evaluate`, ops_cli as `ops_cli evaluate`.
+
+use ai_summary::ai_service::{AIService, MODEL, PROMPT_VERSION};
+use ai_summary::claude_budget;
+use ai_summary::decision;
+use ai_summary::tenants::CompanyProfile;
+use ai_summary::types::{AISummaryResult, PdfContent, TenderContext};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use ml_bid_predictor::ml_predictor::{OptimizedBidPredictor, MODEL_VERSION};
+use pdf_processing::{codes, extract_text_streaming, ExtractionBudget};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::fmt;
+use tender_core::{MLPredictionResult, TenderRecord};
+use tracing::{info, warn};
+
+/// Largest upload accepted; API Gateway caps a Lambda request at 6 MB after base64
+pub const MAX_PDF_BYTES: usize = 4 * 1024 * 1024;
+
+/// Uploaded tenders have no portal resource_id; 0 is neither a real nor a canary id
+pub const UPLOAD_RESOURCE_ID: i64 = 0;
+
+/// The upload isn't a PDF we can read; the caller's fault rather than ours
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnreadablePdf {
+    pub reason: String,
+}
+
+impl fmt::Display for UnreadablePdf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unreadable PDF: {}", self.reason)
+    }
+}
+
+impl std::error::Error for UnreadablePdf {}
+
+fn unreadable(reason: impl Into<String>) -> anyhow::Error {
+    UnreadablePdf {
+        reason: reason.into(),
+    }
+    .into()
+}
+
+pub fn is_pdf(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"%PDF-")
+}
+
+#[derive(Debug, Clone)]
+pub struct Upload {
+    pub title: String,
+    pub contracting_authority: String,
+    pub pdf: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Verdict {
+    pub title: String,
+    pub contracting_authority: String,
+    pub pages: usize,
+    pub pages_failed: usize,
+    pub pdf_chars: usize,
+    pub detected_codes: Vec<String>,
+    pub ml_prediction: MLPredictionResult,
+    pub model_version: &'static str,
+    /// Missing when Claude failed or the day's budget is used up (see `ai_error`)
+    pub ai_summary: Option<AISummaryResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ai_error: Option<String>,
+    pub prompt_version: &'static str,
+    pub claude_model: &'static str,
+    /// Claude's call, or the bid model's when Claude gave no usable answer
+    pub bid: bool,
+    /// "claude" or "ml"
+    pub decided_by: &'static str,
+    pub evaluated_at: DateTime<Utc>,
+}
+
+/// Claude decides, as in the pipeline; an unparseable or missing reply defers to the model
+fn final_call(summary: Option<&AISummaryResult>, ml_bid: bool) -> (bool, &'static str) {
+    match summary {
+        Some(summary) if !decision::is_parse_fallback(summary) => {
+            (decision::recommends_bid(&summary.recommendation), "claude")
+        }
+        _ => (ml_bid, "ml"),
+    }
+}
+
+pub struct Evaluator {
+    /// Primary: loading detection codes and reserving Claude calls both write
+    pool: PgPool,
+    ai_service: AIService,
+    predictor: OptimizedBidPredictor,
+}
+
+impl Evaluator {
+    pub async fn new(pool: PgPool, anthropic_api_key: String) -> Result<Self> {
+        claude_budget::ensure_table(&pool).await?;
+        let mut ai_service = AIService::new(anthropic_api_key);
+        if let Some(limit) = claude_budget::daily_limit_from_env() {
+            ai_service = ai_service.with_budget(pool.clone(), limit);
+        }
+
+        Ok(Self {
+            pool,
+            ai_service,
+            // Uploads have no manual tags, so tag weights would change nothing
+            predictor: OptimizedBidPredictor::new(),
+        })
+    }
+
+    /// Run the upload through every step; fails with `UnreadablePdf` for a bad upload
+    pub async fn evaluate(&self, upload: Upload) -> Result<Verdict> {
+        if upload.pdf.len() > MAX_PDF_BYTES {
+            return Err(unreadable(format!(
+                "{} bytes is over the {} byte limit",
+                upload.pdf.len(),
+                MAX_PDF_BYTES
+            )));
+        }
+        if !is_pdf(&upload.pdf) {
+            return Err(unreadable("not a PDF"));
+        }
+
+        let Some(matcher) = codes::cached_matcher(&self.pool).await? else {
+            anyhow::bail!("No active detection codes; import them with `ops_cli codes import`");
+        };
+        let extraction =
+            extract_text_streaming(&upload.pdf, &matcher, &ExtractionBudget::from_env())
+                .map_err(|e| unreadable(e.to_string()))?;
+        if extraction.text.trim().is_empty() {
+            return Err(unreadable(
+                "no text could be extracted (scanned PDFs aren't supported)",
+            ));
+        }
+        info!(
+            "📄 Uploaded PDF: {} characters from {}/{} pages, {} codes",
+            extraction.text.len(),
+            extraction.pages_processed,
+            extraction.total_pages,
+            extraction.detected_codes.len()
+        );
+
+        let tender = TenderRecord {
+            resource_id: UPLOAD_RESOURCE_ID,
+            title: upload.title.clone(),
+            contracting_authority: upload.contracting_authority.clone(),
+            pdf_content: Some(extraction.text.clone()),
+            detected_codes: Some(extraction.detected_codes.clone()),
+            codes_count: Some(extraction.detected_codes.len() as i32),
+            ..Default::default()
+        };
+        let ml_prediction = self.predictor.predict(&tender)?;
+
+        let (ai_summary, ai_error) = match self.summarise(&tender, &ml_prediction).await {
+            Ok(summary) => (Some(summary), None),
+            Err(e) => {
+                warn!("⚠️ Claude analysis of the upload failed: {}", e);
+                (None, Some(e.to_string()))
+            }
+        };
+        let (bid, decided_by) = final_call(ai_summary.as_ref(), ml_prediction.should_bid);
+
+        Ok(Verdict {
+            title: upload.title,
+            contracting_authority: upload.contracting_authority,
+            pages: extraction.pages_processed,
+            pages_failed: extraction.pages_failed,
+            pdf_chars: extraction.text.len(),
+            detected_codes: extraction.detected_codes,
+            ml_prediction,
+            model_version: MODEL_VERSION,
+            ai_summary,
+            ai_error,
+            prompt_version: PROMPT_VERSION,
+            claude_model: MODEL,
+            bid,
+            decided_by,
+            evaluated_at: Utc::now(),
+        })
+    }
+
+    async fn summarise(
+        &self,
+        tender: &TenderRecord,
+        ml_prediction: &MLPredictionResult,
+    ) -> Result<AISummaryResult> {
+        let pdf_content = PdfContent {
+            resource_id: UPLOAD_RESOURCE_ID,
+            pdf_text: tender.pdf_content.clone().unwrap_or_default(),
+            detected_codes: tender.detected_codes.clone().unwrap_or_default(),
+            codes_count: tender.codes_count.unwrap_or(0),
+            extraction_timestamp: Utc::now(),
+            extraction_quality: None,
+        };
+
+        self.ai_service
+            .generate_full_summary(
+                tender,
+                &pdf_content,
+                ml_prediction,
+                &CompanyProfile::default_profile(),
+                &TenderContext::default(),
+                false,
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(recommendation: &str, notes: Vec<String>) -> AISummaryResult {
+        AISummaryResult {
+            resource_id: UPLOAD_RESOURCE_ID,
+            tenant_id: "default".to_string(),
+            summary_type: "FULL_PDF".to_string(),
+            ai_summary: "Managed IT support for county offices".to_string(),
+            one_liner: None,
+            key_points: Vec::new(),
+            recommendation: recommendation.to_string(),
+            confidence_assessment: "High".to_string(),
+            processing_notes: notes,
+            created_at: Utc::now(),
+            quality_score: None,
+        }
+    }
+
+    #[test]
+    fn test_claude_decides_unless_unparsed() {
+        assert_eq!(
+            final_call(Some(&summary("NO BID", Vec::new())), true),
+            (false, "claude")
+        );
+        assert_eq!(
+            final_call(Some(&summary("BID", Vec::new())), false),
+            (true, "claude")
+        );
+        assert_eq!(final_call(None, true), (true, "ml"));
+
+        let unparsed = summary(
+            decision::PARSE_FALLBACK_RECOMMENDATION,
+            vec![ai_summary::ai_service::UNPARSED_NOTE.to_string()],
+        );
+        assert_eq!(final_call(Some(&unparsed), false), (false, "ml"));
+    }
+
+    #[test]
+    fn test_pdf_signature() {
+        assert!(is_pdf(b"%PDF-1.7\n%\xe2\xe3\xcf\xd3"));
+        assert!(!is_pdf(b"<html><body>Not found</body></html>"));
+        assert!(!is_pdf(b""));
+    }
+}
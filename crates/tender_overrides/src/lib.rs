@@ -0,0 +1,376 @@
+//! Manual overrides of a tender's recommendation, and snoozes, kept in `tender_overrides`.
+//!
+//! Set through tender_api's `/override` and `/snooze` routes or `ops_cli override`, per
+//! tender and tenant:
+//!
+//! - a forced BID or NO BID replaces Claude's recommendation as the final one: ai_summary
+//!   notifies (or not) on it, ahead of watch rules, and tender_api's listings, BID filter
+//!   and bid calendar go by it
+//! - a snooze holds the tender back for N days: sns_notification doesn't email it and
+//!   reminder_scheduler puts its reminders off until the snooze ends (or drops them when
+//!   the date comes first). A forced NO BID holds it back in the same way, for good
+//!
+//! Each change is appended to `decision_audit` (kind `override`) with who made it and why;
+//! the row itself only keeps the latest. Clearing both the recommendation and the snooze
+//! removes the row.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use decision_audit::{Decision, Kind};
+use environment::Environment;
+use serde::Serialize;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+
+/// Longest snooze accepted
+pub const MAX_SNOOZE_DAYS: i64 = 365;
+
+/// A recommendation set by hand
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Forced {
+    Bid,
+    NoBid,
+}
+
+impl Forced {
+    pub const ALL: [Forced; 2] = [Forced::Bid, Forced::NoBid];
+
+    /// Value stored in `tender_overrides.recommendation`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Forced::Bid => "bid",
+            Forced::NoBid => "no_bid",
+        }
+    }
+
+    /// "bid", "no_bid", "NO BID", "no-bid"
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase().replace([' ', '-'], "_");
+        Self::ALL.into_iter().find(|forced| forced.name() == value)
+    }
+
+    /// As Claude would put it
+    pub fn recommendation(&self) -> &'static str {
+        match self {
+            Forced::Bid => "BID",
+            Forced::NoBid => "NO BID",
+        }
+    }
+}
+
+/// Why a tender's emails are held back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hold {
+    Snoozed { until: DateTime<Utc> },
+    NoBid,
+}
+
+impl Hold {
+    /// Recorded in the decision audit trail
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hold::Snoozed { .. } => "snoozed",
+            Hold::NoBid => "forced_no_bid",
+        }
+    }
+
+    /// When a reminder about a date on `event_at` goes out instead: as the snooze ends,
+    /// if that's still before the date. Never for a NO BID
+    pub fn reminder_due(&self, event_at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Hold::Snoozed { until } => (*until < event_at).then_some(*until),
+            Hold::NoBid => None,
+        }
+    }
+}
+
+/// A tender's override for one tenant
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TenderOverride {
+    pub resource_id: i64,
+    pub tenant_id: String,
+    pub recommendation: Option<Forced>,
+    pub snoozed_until: Option<DateTime<Utc>>,
+    /// Given with the latest change
+    pub reason: Option<String>,
+    pub set_by: String,
+    pub set_at: DateTime<Utc>,
+}
+
+impl TenderOverride {
+    pub fn is_snoozed(&self, now: DateTime<Utc>) -> bool {
+        self.snoozed_until.is_some_and(|until| until > now)
+    }
+
+    /// Whether the tender's emails are held back at `now`; a snooze comes first, so a
+    /// snoozed BID is held too
+    pub fn hold(&self, now: DateTime<Utc>) -> Option<Hold> {
+        match (self.snoozed_until, self.recommendation) {
+            (Some(until), _) if until > now => Some(Hold::Snoozed { until }),
+            (_, Some(Forced::NoBid)) => Some(Hold::NoBid),
+            _ => None,
+        }
+    }
+}
+
+/// Who is changing an override, from where, and why
+#[derive(Debug, Clone, Copy)]
+pub struct Change<'a> {
+    pub tenant_id: &'a str,
+    pub set_by: &'a str,
+    pub reason: Option<&'a str>,
+    /// Recorded as the audit entry's component, e.g. "tender_api"
+    pub component: &'static str,
+}
+
+/// End of a snooze of `days` days from `now`
+pub fn snooze_until(days: i64, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    if !(1..=MAX_SNOOZE_DAYS).contains(&days) {
+        bail!("Snooze must be 1-{} days, not {}", MAX_SNOOZE_DAYS, days);
+    }
+    Ok(now + Duration::days(days))
+}
+
+pub async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "tender_overrides", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS tender_overrides (
+                resource_id BIGINT NOT NULL,
+                tenant_id TEXT NOT NULL DEFAULT 'default',
+                recommendation TEXT,
+                snoozed_until TIMESTAMPTZ,
+                reason TEXT,
+                set_by TEXT NOT NULL,
+                set_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (resource_id, tenant_id)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        Environment::ensure_environment_column(pool, "tender_overrides").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+fn from_row(row: &PgRow) -> TenderOverride {
+    TenderOverride {
+        resource_id: row.get("resource_id"),
+        tenant_id: row.get("tenant_id"),
+        recommendation: row
+            .get::<Option<String>, _>("recommendation")
+            .and_then(|value| Forced::parse(&value)),
+        snoozed_until: row.get("snoozed_until"),
+        reason: row.get("reason"),
+        set_by: row.get("set_by"),
+        set_at: row.get("set_at"),
+    }
+}
+
+/// The tender's override for the tenant, if it has one
+pub async fn get(
+    pool: &PgPool,
+    resource_id: i64,
+    tenant_id: &str,
+) -> Result<Option<TenderOverride>> {
+    let row = sqlx::query(
+        r#"
+        SELECT resource_id, tenant_id, recommendation, snoozed_until, reason, set_by, set_at
+        FROM tender_overrides
+        WHERE resource_id = $1 AND tenant_id = $2
+        "#,
+    )
+    .bind(resource_id)
+    .bind(tenant_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.as_ref().map(from_row))
+}
+
+/// Every override of the tenant's, most recently changed first
+pub async fn list(pool: &PgPool, tenant_id: &str) -> Result<Vec<TenderOverride>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT resource_id, tenant_id, recommendation, snoozed_until, reason, set_by, set_at
+        FROM tender_overrides
+        WHERE tenant_id = $1
+        ORDER BY set_at DESC, resource_id
+        "#,
+    )
+    .bind(tenant_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(from_row).collect())
+}
+
+/// Force the tender's recommendation, or with `None` go back to Claude's. Returns the
+/// override as it now stands
+pub async fn force(
+    pool: &PgPool,
+    resource_id: i64,
+    recommendation: Option<Forced>,
+    change: Change<'_>,
+) -> Result<Option<TenderOverride>> {
+    sqlx::query(
+        r#"
+        INSERT INTO tender_overrides (resource_id, tenant_id, recommendation, reason, set_by)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (resource_id, tenant_id) DO UPDATE SET
+            recommendation = EXCLUDED.recommendation,
+            reason = EXCLUDED.reason,
+            set_by = EXCLUDED.set_by,
+            set_at = NOW()
+        "#,
+    )
+    .bind(resource_id)
+    .bind(change.tenant_id)
+    .bind(recommendation.map(|forced| forced.name()))
+    .bind(change.reason)
+    .bind(change.set_by)
+    .execute(pool)
+    .await?;
+
+    let outcome = match recommendation {
+        Some(Forced::Bid) => "force_bid",
+        Some(Forced::NoBid) => "force_no_bid",
+        None => "clear_recommendation",
+    };
+    settle(pool, resource_id, change, outcome).await
+}
+
+/// Snooze the tender until `until` (see `snooze_until`), or with `None` wake it. Returns
+/// the override as it now stands
+pub async fn snooze(
+    pool: &PgPool,
+    resource_id: i64,
+    until: Option<DateTime<Utc>>,
+    change: Change<'_>,
+) -> Result<Option<TenderOverride>> {
+    sqlx::query(
+        r#"
+        INSERT INTO tender_overrides (resource_id, tenant_id, snoozed_until, reason, set_by)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (resource_id, tenant_id) DO UPDATE SET
+            snoozed_until = EXCLUDED.snoozed_until,
+            reason = EXCLUDED.reason,
+            set_by = EXCLUDED.set_by,
+            set_at = NOW()
+        "#,
+    )
+    .bind(resource_id)
+    .bind(change.tenant_id)
+    .bind(until)
+    .bind(change.reason)
+    .bind(change.set_by)
+    .execute(pool)
+    .await?;
+
+    let outcome = if until.is_some() { "snooze" } else { "wake" };
+    settle(pool, resource_id, change, outcome).await
+}
+
+/// Drop an override left with nothing set, audit the change and return what's left
+async fn settle(
+    pool: &PgPool,
+    resource_id: i64,
+    change: Change<'_>,
+    outcome: &str,
+) -> Result<Option<TenderOverride>> {
+    sqlx::query(
+        r#"
+        DELETE FROM tender_overrides
+        WHERE resource_id = $1 AND tenant_id = $2
+        AND recommendation IS NULL AND snoozed_until IS NULL
+        "#,
+    )
+    .bind(resource_id)
+    .bind(change.tenant_id)
+    .execute(pool)
+    .await?;
+    let current = get(pool, resource_id, change.tenant_id).await?;
+
+    let decision = Decision::new(resource_id, Kind::Override, change.component, outcome)
+        .version("tenant", change.tenant_id)
+        .detail(serde_json::json!({
+            "set_by": change.set_by,
+            "reason": change.reason,
+            "recommendation": current.as_ref().and_then(|o| o.recommendation),
+            "snoozed_until": current.as_ref().and_then(|o| o.snoozed_until),
+        }));
+    decision_audit::append(pool, &decision).await?;
+    Ok(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, day, 9, 0, 0).unwrap()
+    }
+
+    fn tender_override(
+        recommendation: Option<Forced>,
+        snoozed_until: Option<u32>,
+    ) -> TenderOverride {
+        TenderOverride {
+            resource_id: 1,
+            tenant_id: "default".to_string(),
+            recommendation,
+            snoozed_until: snoozed_until.map(at),
+            reason: None,
+            set_by: "ops".to_string(),
+            set_at: at(1),
+        }
+    }
+
+    #[test]
+    fn test_forced_names_round_trip() {
+        for forced in Forced::ALL {
+            assert_eq!(Forced::parse(forced.name()), Some(forced));
+            assert_eq!(Forced::parse(forced.recommendation()), Some(forced));
+        }
+        assert_eq!(Forced::parse("No-Bid"), Some(Forced::NoBid));
+        assert_eq!(Forced::parse("maybe"), None);
+    }
+
+    #[test]
+    fn test_snooze_comes_before_a_forced_recommendation() {
+        let now = at(16);
+        assert_eq!(
+            tender_override(Some(Forced::Bid), Some(20)).hold(now),
+            Some(Hold::Snoozed { until: at(20) })
+        );
+        assert_eq!(
+            tender_override(Some(Forced::NoBid), Some(20)).hold(now),
+            Some(Hold::Snoozed { until: at(20) })
+        );
+        // A snooze that has run out no longer holds anything back
+        assert_eq!(tender_override(Some(Forced::Bid), Some(10)).hold(now), None);
+        assert!(!tender_override(None, Some(10)).is_snoozed(now));
+        assert_eq!(
+            tender_override(Some(Forced::NoBid), Some(10)).hold(now),
+            Some(Hold::NoBid)
+        );
+        assert_eq!(tender_override(None, None).hold(now), None);
+    }
+
+    #[test]
+    fn test_reminders_wait_for_the_snooze_to_end() {
+        let snoozed = Hold::Snoozed { until: at(20) };
+        assert_eq!(snoozed.reminder_due(at(25)), Some(at(20)));
+        assert_eq!(snoozed.reminder_due(at(20)), None);
+        assert_eq!(Hold::NoBid.reminder_due(at(25)), None);
+
+        assert_eq!(snooze_until(4, at(16)).unwrap(), at(20));
+        assert!(snooze_until(0, at(16)).is_err());
+        assert!(snooze_until(MAX_SNOOZE_DAYS + 1, at(16)).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! Manual tags on tenders ("cloud", "staff aug", "public health"), kept in `tender_tags`.
+//!
+//! Tags are attached through tender_api's `/tags` routes or `ops_cli tags`. They show up
+//! on the API's tenders (and can be filtered on), in both CSV exports and, when
+//! `ML_TAG_WEIGHTS` is set, as an extra term in ml_bid_predictor's score.
+//!
+//! Any tag is accepted; the ones in `CONTROLLED_TAGS` are the shared vocabulary and the
+//! only ones the anonymized dataset exports, since free-form tags can name people or
+//! authorities.
+
+use anyhow::{bail, Result};
+use environment::Environment;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+
+/// Longest tag accepted, after normalization
+pub const MAX_TAG_LEN: usize = 40;
+
+/// Shared categories, in the form `normalize` produces
+pub const CONTROLLED_TAGS: &[&str] = &[
+    "cloud",
+    "cyber security",
+    "data analytics",
+    "digital transformation",
+    "infrastructure",
+    "managed services",
+    "public health",
+    "software development",
+    "staff aug",
+    "support",
+];
+
+/// Lowercase with single spaces, so "Staff  Aug" and "staff aug" are one tag
+pub fn normalize(tag: &str) -> Result<String> {
+    let tag = tag
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    if tag.is_empty() {
+        bail!("Tag must not be empty");
+    }
+    if tag.chars().count() > MAX_TAG_LEN {
+        bail!("Tag '{}' is longer than {} characters", tag, MAX_TAG_LEN);
+    }
+    if tag.contains(',') {
+        bail!("Tag '{}' must not contain a comma", tag);
+    }
+    Ok(tag)
+}
+
+pub fn is_controlled(tag: &str) -> bool {
+    CONTROLLED_TAGS.contains(&tag)
+}
+
+pub async fn ensure_table(pool: &PgPool) -> Result<()> {
+    db::ensure_schema(pool, "tender_tags", 1, async {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS tender_tags (
+                resource_id BIGINT NOT NULL,
+                tag TEXT NOT NULL,
+                created_by TEXT NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (resource_id, tag)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_tender_tags_tag ON tender_tags (tag)")
+            .execute(pool)
+            .await?;
+
+        Environment::ensure_environment_column(pool, "tender_tags").await?;
+        anyhow::Ok(())
+    })
+    .await
+}
+
+/// Tag a tender; false if it already had the tag
+pub async fn add(pool: &PgPool, resource_id: i64, tag: &str, created_by: &str) -> Result<bool> {
+    let tag = normalize(tag)?;
+    let result = sqlx::query(
+        r#"
+        INSERT INTO tender_tags (resource_id, tag, created_by)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (resource_id, tag) DO NOTHING
+        "#,
+    )
+    .bind(resource_id)
+    .bind(&tag)
+    .bind(created_by)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Untag a tender; false if it didn't have the tag
+pub async fn remove(pool: &PgPool, resource_id: i64, tag: &str) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM tender_tags WHERE resource_id = $1 AND tag = $2")
+        .bind(resource_id)
+        .bind(normalize(tag)?)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// A tender's tags, alphabetically
+pub async fn for_tender(pool: &PgPool, resource_id: i64) -> Result<Vec<String>> {
+    let rows = sqlx::query("SELECT tag FROM tender_tags WHERE resource_id = $1 ORDER BY tag")
+        .bind(resource_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.iter().map(|row| row.get("tag")).collect())
+}
+
+/// Every tag in use with the number of tenders carrying it, most used first
+pub async fn counts(pool: &PgPool) -> Result<Vec<(String, i64)>> {
+    let rows = sqlx::query(
+        "SELECT tag, COUNT(*) AS tenders FROM tender_tags GROUP BY tag ORDER BY tenders DESC, tag",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| (row.get("tag"), row.get("tenders")))
+        .collect())
+}
+
+/// Tags of several tenders at once, for exports
+pub async fn for_tenders(pool: &PgPool, resource_ids: &[i64]) -> Result<HashMap<i64, Vec<String>>> {
+    let rows = sqlx::query(
+        "SELECT resource_id, tag FROM tender_tags WHERE resource_id = ANY($1) ORDER BY resource_id, tag",
+    )
+    .bind(resource_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    for row in &rows {
+        tags.entry(row.get("resource_id"))
+            .or_default()
+            .push(row.get("tag"));
+    }
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("  Staff   Aug ").unwrap(), "staff aug");
+        assert!(is_controlled(&normalize("Public Health").unwrap()));
+        assert!(!is_controlled("hse framework"));
+
+        assert!(normalize("   ").is_err());
+        assert!(normalize("cloud, hosting").is_err());
+        assert!(normalize(&"x".repeat(MAX_TAG_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_controlled_tags_are_normalized() {
+        for tag in CONTROLLED_TAGS {
+            assert_eq!(normalize(tag).unwrap(), *tag);
+        }
+    }
+}
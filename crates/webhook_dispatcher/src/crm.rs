@@ -0,0 +1,429 @@
+use crate::database::Database;
+use crate::types::WebhookEvent;
+use anyhow::{Context, Result};
+use reqwest::{Client, StatusCode};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tracing::info;
+
+/// Deals per HubSpot batch read (the API's limit)
+const HUBSPOT_BATCH_SIZE: usize = 100;
+
+/// Supported CRM backends, selected with CRM_PROVIDER
+#[derive(Debug, Clone)]
+pub enum CrmBackend {
+    HubSpot { api_token: String },
+    Dynamics { base_url: String, api_token: String },
+}
+
+impl CrmBackend {
+    /// Build the CRM backend from environment; `None` when CRM sync is disabled
+    pub fn from_env() -> Result<Option<Self>> {
+        let provider = match std::env::var("CRM_PROVIDER") {
+            Ok(p) if !p.trim().is_empty() => p.trim().to_lowercase(),
+            _ => return Ok(None),
+        };
+
+        let api_token = std::env::var("CRM_API_TOKEN")
+            .context("CRM_API_TOKEN must be set when CRM_PROVIDER is configured")?;
+
+        match provider.as_str() {
+            "hubspot" => Ok(Some(CrmBackend::HubSpot { api_token })),
+            "dynamics" => {
+                let base_url = std::env::var("CRM_BASE_URL")
+                    .context("CRM_BASE_URL must be set for the dynamics provider")?;
+                Ok(Some(CrmBackend::Dynamics {
+                    base_url: base_url.trim_end_matches('/').to_string(),
+                    api_token,
+                }))
+            }
+            other => Err(anyhow::anyhow!("Unsupported CRM_PROVIDER '{}'", other)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CrmBackend::HubSpot { .. } => "hubspot",
+            CrmBackend::Dynamics { .. } => "dynamics",
+        }
+    }
+}
+
+/// Fields pushed to the CRM, extracted from an AI_SUMMARY_COMPLETE payload
+#[derive(Debug, Clone)]
+pub struct DealFields {
+    pub name: String,
+    pub amount: Option<String>,
+    pub close_date: Option<String>, // YYYY-MM-DD, taken from the tender deadline
+    pub description: String,
+}
+
+impl DealFields {
+    pub fn from_event(event: &WebhookEvent) -> Self {
+        let payload = &event.payload;
+        let text = |key: &str| payload.get(key).and_then(|v| v.as_str()).unwrap_or("");
+
+        let amount = match payload.get("estimated_value") {
+            Some(Value::String(s)) if !s.is_empty() => Some(s.clone()),
+            Some(Value::Number(n)) => Some(n.to_string()),
+            _ => None,
+        };
+
+        // NaiveDateTime serializes as "2025-08-15T10:00:00"; the date part is enough
+        let close_date = payload
+            .get("deadline")
+            .and_then(|v| v.as_str())
+            .and_then(|d| d.get(..10))
+            .map(|d| d.to_string());
+
+        let description = format!(
+            "Contracting authority: {}\nRecommendation: {}\nPortal: {}\n\n{}",
+            text("contracting_authority"),
+            text("recommendation"),
+            text("portal_link"),
+            text("ai_summary")
+        );
+
+        Self {
+            name: format!("[{}] {}", event.resource_id, text("title")),
+            amount,
+            close_date,
+            description,
+        }
+    }
+}
+
+/// True when Claude's recommendation is BID (same rule as the notification decision)
+pub fn is_bid_recommendation(event: &WebhookEvent) -> bool {
+    let recommendation = event
+        .payload
+        .get("recommendation")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_lowercase();
+    recommendation.contains("bid") && !recommendation.contains("no bid")
+}
+
+/// A deal's state, read back from the CRM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DealStatus {
+    /// HubSpot deal stage id or Dynamics process stage name, lowercased
+    pub stage: String,
+    pub won: bool,
+    pub lost: bool,
+}
+
+/// Creates or updates CRM deals for BID-recommended tenders
+pub struct CrmSync {
+    backend: CrmBackend,
+    http_client: Client,
+}
+
+impl CrmSync {
+    pub fn new(backend: CrmBackend, http_client: Client) -> Self {
+        Self {
+            backend,
+            http_client,
+        }
+    }
+
+    /// Sync one event; returns the CRM id when a deal was created or updated
+    pub async fn sync(&self, database: &Database, event: &WebhookEvent) -> Result<Option<String>> {
+        if event.event_type != "AI_SUMMARY_COMPLETE" || !is_bid_recommendation(event) {
+            return Ok(None);
+        }
+
+        let fields = DealFields::from_event(event);
+        let existing_id = database.get_crm_deal_id(event.resource_id).await?;
+
+        let deal_id = match &existing_id {
+            Some(id) => {
+                self.update_deal(id, &fields).await?;
+                info!(
+                    "🔄 Updated {} deal {} for tender {}",
+                    self.backend.name(),
+                    id,
+                    event.resource_id
+                );
+                id.clone()
+            }
+            None => {
+                let id = self.create_deal(&fields).await?;
+                info!(
+                    "🆕 Created {} deal {} for tender {}",
+                    self.backend.name(),
+                    id,
+                    event.resource_id
+                );
+                id
+            }
+        };
+
+        database
+            .store_crm_deal_id(event.resource_id, self.backend.name(), &deal_id)
+            .await?;
+        Ok(Some(deal_id))
+    }
+
+    pub fn provider(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    /// Current status of each deal, keyed by deal id; deals deleted in the CRM are left out
+    pub async fn deal_statuses(&self, deal_ids: &[String]) -> Result<HashMap<String, DealStatus>> {
+        let mut statuses = HashMap::new();
+        match &self.backend {
+            CrmBackend::HubSpot { api_token } => {
+                for batch in deal_ids.chunks(HUBSPOT_BATCH_SIZE) {
+                    let inputs: Vec<Value> = batch.iter().map(|id| json!({ "id": id })).collect();
+                    let response: Value = self
+                        .http_client
+                        .post("https://api.hubapi.com/crm/v3/objects/deals/batch/read")
+                        .bearer_auth(api_token)
+                        .json(&json!({
+                            "properties": ["dealstage", "hs_is_closed", "hs_is_closed_won"],
+                            "inputs": inputs,
+                        }))
+                        .send()
+                        .await?
+                        .error_for_status()?
+                        .json()
+                        .await?;
+                    statuses.extend(hubspot_statuses(&response));
+                }
+            }
+            CrmBackend::Dynamics {
+                base_url,
+                api_token,
+            } => {
+                for deal_id in deal_ids {
+                    let response = self
+                        .http_client
+                        .get(format!(
+                            "{}/api/data/v9.2/opportunities({})?$select=statecode,statuscode,stepname",
+                            base_url, deal_id
+                        ))
+                        .bearer_auth(api_token)
+                        .send()
+                        .await?;
+                    if response.status() == StatusCode::NOT_FOUND {
+                        continue;
+                    }
+                    let body: Value = response.error_for_status()?.json().await?;
+                    statuses.insert(deal_id.clone(), dynamics_status(&body));
+                }
+            }
+        }
+        Ok(statuses)
+    }
+
+    async fn create_deal(&self, fields: &DealFields) -> Result<String> {
+        match &self.backend {
+            CrmBackend::HubSpot { api_token } => {
+                let response: Value = self
+                    .http_client
+                    .post("https://api.hubapi.com/crm/v3/objects/deals")
+                    .bearer_auth(api_token)
+                    .json(&hubspot_properties(fields))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                response
+                    .get("id")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .context("HubSpot response missing deal id")
+            }
+            CrmBackend::Dynamics {
+                base_url,
+                api_token,
+            } => {
+                let response: Value = self
+                    .http_client
+                    .post(format!("{}/api/data/v9.2/opportunities", base_url))
+                    .bearer_auth(api_token)
+                    .header("Prefer", "return=representation")
+                    .json(&dynamics_properties(fields))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                response
+                    .get("opportunityid")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .context("Dynamics response missing opportunityid")
+            }
+        }
+    }
+
+    async fn update_deal(&self, deal_id: &str, fields: &DealFields) -> Result<()> {
+        match &self.backend {
+            CrmBackend::HubSpot { api_token } => {
+                self.http_client
+                    .patch(format!(
+                        "https://api.hubapi.com/crm/v3/objects/deals/{}",
+                        deal_id
+                    ))
+                    .bearer_auth(api_token)
+                    .json(&hubspot_properties(fields))
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+            CrmBackend::Dynamics {
+                base_url,
+                api_token,
+            } => {
+                self.http_client
+                    .patch(format!(
+                        "{}/api/data/v9.2/opportunities({})",
+                        base_url, deal_id
+                    ))
+                    .bearer_auth(api_token)
+                    .json(&dynamics_properties(fields))
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Statuses from a HubSpot batch read; its boolean properties are the strings "true"/"false"
+fn hubspot_statuses(response: &Value) -> Vec<(String, DealStatus)> {
+    let results = response.get("results").and_then(|v| v.as_array());
+    results
+        .into_iter()
+        .flatten()
+        .filter_map(|deal| {
+            let id = deal.get("id")?.as_str()?.to_string();
+            let properties = deal.get("properties")?;
+            let property = |key: &str| properties.get(key).and_then(|v| v.as_str()).unwrap_or("");
+            let won = property("hs_is_closed_won") == "true";
+            Some((
+                id,
+                DealStatus {
+                    stage: property("dealstage").trim().to_lowercase(),
+                    won,
+                    lost: property("hs_is_closed") == "true" && !won,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Dynamics opportunity state: statecode 1 is won and 2 is lost
+fn dynamics_status(body: &Value) -> DealStatus {
+    let statecode = body.get("statecode").and_then(|v| v.as_i64());
+    DealStatus {
+        stage: body
+            .get("stepname")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_lowercase(),
+        won: statecode == Some(1),
+        lost: statecode == Some(2),
+    }
+}
+
+fn hubspot_properties(fields: &DealFields) -> Value {
+    let mut properties = json!({
+        "dealname": fields.name,
+        "description": fields.description,
+    });
+    if let Some(amount) = &fields.amount {
+        properties["amount"] = json!(amount);
+    }
+    if let Some(close_date) = &fields.close_date {
+        properties["closedate"] = json!(close_date);
+    }
+    json!({ "properties": properties })
+}
+
+fn dynamics_properties(fields: &DealFields) -> Value {
+    let mut body = json!({
+        "name": fields.name,
+        "description": fields.description,
+    });
+    if let Some(amount) = fields.amount.as_ref().and_then(|a| a.parse::<f64>().ok()) {
+        body["estimatedvalue"] = json!(amount);
+    }
+    if let Some(close_date) = &fields.close_date {
+        body["estimatedclosedate"] = json!(close_date);
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn summary_event(recommendation: &str) -> WebhookEvent {
+        WebhookEvent {
+            event_type: "AI_SUMMARY_COMPLETE".to_string(),
+            resource_id: 42,
+            occurred_at: Utc::now(),
+            payload: json!({
+                "title": "Cloud migration services",
+                "contracting_authority": "Revenue Commissioners",
+                "estimated_value": "250000.00",
+                "deadline": "2025-08-15T10:00:00",
+                "recommendation": recommendation,
+                "ai_summary": "Migration of legacy systems to the cloud",
+                "portal_link": "https://etenders.gov.ie/x"
+            }),
+        }
+    }
+
+    #[test]
+    fn test_bid_recommendation_detection() {
+        assert!(is_bid_recommendation(&summary_event("BID")));
+        assert!(!is_bid_recommendation(&summary_event("NO BID")));
+        assert!(!is_bid_recommendation(&summary_event("Review the summary")));
+    }
+
+    #[test]
+    fn test_deal_statuses() {
+        let response = json!({
+            "status": "COMPLETE",
+            "results": [
+                { "id": "1", "properties": { "dealstage": "closedwon", "hs_is_closed": "true", "hs_is_closed_won": "true" } },
+                { "id": "2", "properties": { "dealstage": "closedlost", "hs_is_closed": "true", "hs_is_closed_won": "false" } },
+                { "id": "3", "properties": { "dealstage": "Bid_Submitted", "hs_is_closed": "false", "hs_is_closed_won": "false" } }
+            ]
+        });
+        let statuses = hubspot_statuses(&response);
+        assert_eq!(statuses.len(), 3);
+        assert!(statuses[0].1.won && !statuses[0].1.lost);
+        assert!(statuses[1].1.lost && !statuses[1].1.won);
+        assert_eq!(
+            statuses[2].1,
+            DealStatus {
+                stage: "bid_submitted".to_string(),
+                won: false,
+                lost: false
+            }
+        );
+
+        let lost =
+            dynamics_status(&json!({ "statecode": 2, "statuscode": 5, "stepname": "3-Propose" }));
+        assert!(lost.lost && !lost.won);
+        assert_eq!(lost.stage, "3-propose");
+    }
+
+    #[test]
+    fn test_deal_fields_mapping() {
+        let fields = DealFields::from_event(&summary_event("BID"));
+        assert_eq!(fields.name, "[42] Cloud migration services");
+        assert_eq!(fields.amount.as_deref(), Some("250000.00"));
+        assert_eq!(fields.close_date.as_deref(), Some("2025-08-15"));
+        assert!(fields.description.contains("Revenue Commissioners"));
+    }
+}
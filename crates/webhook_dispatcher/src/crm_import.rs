@@ -0,0 +1,177 @@
+//! Pulls bid decisions and outcomes back from the CRM for the deals `crm` created.
+//!
+//! Runs when the lambda is invoked on a schedule with `{"crm_import": true}`. A won or lost
+//! deal records the outcome in `bid_outcomes`. CRM_NO_BID_STAGES, CRM_WITHDRAWN_STAGES and
+//! CRM_BID_STAGES map other stages. The CRM is the record of what we actually did, so its
+//! decision replaces a `bid` label set by hand.
+
+use crate::crm::{CrmSync, DealStatus};
+use crate::database::Database;
+use analytics::outcomes::{self, Outcome};
+use anyhow::Result;
+use serde::Serialize;
+use tracing::{info, warn};
+
+/// Which CRM stages mean what; a deal in any other open stage is still undecided
+#[derive(Debug, Clone, Default)]
+pub struct StageMapping {
+    no_bid: Vec<String>,
+    withdrawn: Vec<String>,
+    bid: Vec<String>,
+}
+
+fn stage_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|stage| stage.trim().to_lowercase())
+        .filter(|stage| !stage.is_empty())
+        .collect()
+}
+
+impl StageMapping {
+    pub fn from_env() -> Self {
+        let list = |key: &str| stage_list(&std::env::var(key).unwrap_or_default());
+        Self {
+            no_bid: list("CRM_NO_BID_STAGES"),
+            withdrawn: list("CRM_WITHDRAWN_STAGES"),
+            bid: list("CRM_BID_STAGES"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    NoBid,
+    /// We bid; the outcome once the deal is closed
+    Bid(Option<Outcome>),
+}
+
+/// What a deal says we did. A no-bid or withdrawn stage wins over "closed lost",
+/// since CRMs usually close those deals as lost
+pub fn decision(status: &DealStatus, mapping: &StageMapping) -> Option<Decision> {
+    if status.won {
+        Some(Decision::Bid(Some(Outcome::Won)))
+    } else if mapping.no_bid.contains(&status.stage) {
+        Some(Decision::NoBid)
+    } else if mapping.withdrawn.contains(&status.stage) {
+        Some(Decision::Bid(Some(Outcome::Withdrawn)))
+    } else if status.lost {
+        Some(Decision::Bid(Some(Outcome::Lost)))
+    } else if mapping.bid.contains(&status.stage) {
+        Some(Decision::Bid(None))
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub deals: usize,
+    /// Deals no longer in the CRM
+    pub missing: usize,
+    pub labels_changed: usize,
+    pub outcomes_changed: usize,
+}
+
+pub async fn run(
+    database: &Database,
+    crm: &CrmSync,
+    mapping: &StageMapping,
+) -> Result<ImportReport> {
+    outcomes::ensure_table(database.pool()).await?;
+
+    let deals = database.crm_deals(crm.provider()).await?;
+    let deal_ids: Vec<String> = deals.iter().map(|(_, deal_id)| deal_id.clone()).collect();
+    let statuses = crm.deal_statuses(&deal_ids).await?;
+    let recorded_by = format!("crm:{}", crm.provider());
+
+    let mut report = ImportReport {
+        deals: deals.len(),
+        ..Default::default()
+    };
+    for (resource_id, deal_id) in &deals {
+        let Some(status) = statuses.get(deal_id) else {
+            warn!(
+                "⚠️ {} deal {} for tender {} not found",
+                crm.provider(),
+                deal_id,
+                resource_id
+            );
+            report.missing += 1;
+            continue;
+        };
+        let Some(decision) = decision(status, mapping) else {
+            continue;
+        };
+
+        let bid = if decision == Decision::NoBid { 0 } else { 1 };
+        if database.set_bid_label(*resource_id, bid).await? {
+            info!(
+                "🏷️ Tender {} labelled bid = {} from the CRM",
+                resource_id, bid
+            );
+            report.labels_changed += 1;
+        }
+
+        if let Decision::Bid(Some(outcome)) = decision {
+            if outcomes::get(database.pool(), *resource_id).await? != Some(outcome) {
+                outcomes::record(database.pool(), *resource_id, outcome, &recorded_by).await?;
+                info!(
+                    "🏆 Tender {} recorded as {} from the CRM",
+                    resource_id,
+                    outcome.as_str()
+                );
+                report.outcomes_changed += 1;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(stage: &str, won: bool, lost: bool) -> DealStatus {
+        DealStatus {
+            stage: stage.to_string(),
+            won,
+            lost,
+        }
+    }
+
+    #[test]
+    fn test_decision_from_stage() {
+        let mapping = StageMapping {
+            no_bid: stage_list("No Bid, "),
+            withdrawn: stage_list("cancelled"),
+            bid: stage_list("bid_submitted"),
+        };
+
+        assert_eq!(
+            decision(&status("closedwon", true, false), &mapping),
+            Some(Decision::Bid(Some(Outcome::Won)))
+        );
+        assert_eq!(
+            decision(&status("closedlost", false, true), &mapping),
+            Some(Decision::Bid(Some(Outcome::Lost)))
+        );
+        assert_eq!(
+            decision(&status("no bid", false, true), &mapping),
+            Some(Decision::NoBid)
+        );
+        assert_eq!(
+            decision(&status("cancelled", false, true), &mapping),
+            Some(Decision::Bid(Some(Outcome::Withdrawn)))
+        );
+        assert_eq!(
+            decision(&status("bid_submitted", false, false), &mapping),
+            Some(Decision::Bid(None))
+        );
+        assert_eq!(
+            decision(&status("qualifying", false, false), &mapping),
+            None
+        );
+    }
+}
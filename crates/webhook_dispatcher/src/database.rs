@@ -0,0 +1,199 @@
+use crate::types::{Config, DeliveryAttempt, Webhook};
+use anyhow::Result;
+use environment::Environment;
+use sqlx::{Pool, Postgres, Row};
+use tracing::info;
+
+/// Database operations for webhook registration and delivery logging
+pub struct Database {
+    pool: Pool<Postgres>,
+}
+
+impl Database {
+    /// Create new database connection and ensure webhook tables exist
+    pub async fn new(config: &Config) -> Result<Self> {
+        let pool = db::connect(&config.database_url, 5).await?;
+
+        let db = Self { pool };
+        db.ensure_tables_exist().await?;
+
+        info!("✅ Database connection established");
+        Ok(db)
+    }
+
+    async fn ensure_tables_exist(&self) -> Result<()> {
+        db::ensure_schema(&self.pool, "webhook_dispatcher", 1, async {
+            sqlx::query(
+                r#"
+                CREATE TABLE IF NOT EXISTS webhooks (
+                    id SERIAL PRIMARY KEY,
+                    url TEXT NOT NULL,
+                    secret TEXT NOT NULL,
+                    event_types TEXT[] NOT NULL DEFAULT '{}',
+                    active BOOLEAN NOT NULL DEFAULT TRUE,
+                    description TEXT,
+                    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+                )
+                "#,
+            )
+            .execute(&self.pool)
+            .await?;
+
+            sqlx::query(
+                r#"
+                CREATE TABLE IF NOT EXISTS webhook_deliveries (
+                    id BIGSERIAL PRIMARY KEY,
+                    webhook_id INTEGER NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+                    event_type TEXT NOT NULL,
+                    resource_id BIGINT NOT NULL,
+                    attempt INTEGER NOT NULL,
+                    status_code INTEGER,
+                    success BOOLEAN NOT NULL,
+                    error TEXT,
+                    duration_ms BIGINT NOT NULL,
+                    delivered_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+                )
+                "#,
+            )
+            .execute(&self.pool)
+            .await?;
+
+            // CRM deal/opportunity id written back by the CRM integration
+            for query in [
+                "ALTER TABLE tender_records ADD COLUMN IF NOT EXISTS crm_provider TEXT",
+                "ALTER TABLE tender_records ADD COLUMN IF NOT EXISTS crm_deal_id TEXT",
+                "ALTER TABLE tender_records ADD COLUMN IF NOT EXISTS crm_synced_at TIMESTAMP WITH TIME ZONE",
+            ] {
+                sqlx::query(query).execute(&self.pool).await?;
+            }
+
+            for table in ["webhooks", "webhook_deliveries"] {
+                Environment::ensure_environment_column(&self.pool, table).await?;
+            }
+
+            anyhow::Ok(())
+        })
+        .await
+    }
+
+    pub fn pool(&self) -> &Pool<Postgres> {
+        &self.pool
+    }
+
+    /// Active webhooks subscribed to the given event type ("*" subscribes to everything)
+    pub async fn webhooks_for_event(&self, event_type: &str) -> Result<Vec<Webhook>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, url, secret, event_types
+            FROM webhooks
+            WHERE active = TRUE
+              AND ($1 = ANY(event_types) OR '*' = ANY(event_types))
+            ORDER BY id
+            "#,
+        )
+        .bind(event_type)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Webhook {
+                id: row.get("id"),
+                url: row.get("url"),
+                secret: row.get("secret"),
+                event_types: row.get("event_types"),
+            })
+            .collect())
+    }
+
+    /// Record a delivery attempt for auditing and debugging
+    pub async fn log_delivery(&self, attempt: &DeliveryAttempt) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_deliveries
+            (webhook_id, event_type, resource_id, attempt, status_code, success, error, duration_ms)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(attempt.webhook_id)
+        .bind(&attempt.event_type)
+        .bind(attempt.resource_id)
+        .bind(attempt.attempt)
+        .bind(attempt.status_code)
+        .bind(attempt.success)
+        .bind(&attempt.error)
+        .bind(attempt.duration_ms)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// CRM id previously stored for a tender, if any
+    pub async fn get_crm_deal_id(&self, resource_id: i64) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT crm_deal_id FROM tender_records WHERE resource_id = $1")
+            .bind(resource_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.and_then(|r| r.get::<Option<String>, _>("crm_deal_id")))
+    }
+
+    /// Store the CRM id on the tender so later events update instead of duplicating
+    pub async fn store_crm_deal_id(
+        &self,
+        resource_id: i64,
+        provider: &str,
+        deal_id: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE tender_records
+            SET crm_provider = $2,
+                crm_deal_id = $3,
+                crm_synced_at = NOW()
+            WHERE resource_id = $1
+            "#,
+        )
+        .bind(resource_id)
+        .bind(provider)
+        .bind(deal_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// (resource_id, deal id) of every tender with a deal in the given CRM
+    pub async fn crm_deals(&self, provider: &str) -> Result<Vec<(i64, String)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT resource_id, crm_deal_id
+            FROM tender_records
+            WHERE crm_provider = $1 AND crm_deal_id IS NOT NULL
+            ORDER BY resource_id
+            "#,
+        )
+        .bind(provider)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("resource_id"), row.get("crm_deal_id")))
+            .collect())
+    }
+
+    /// Set the tender's bid label; returns false when it already had that label
+    pub async fn set_bid_label(&self, resource_id: i64, bid: i32) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE tender_records SET bid = $2 WHERE resource_id = $1 AND bid IS DISTINCT FROM $2",
+        )
+        .bind(resource_id)
+        .bind(bid)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
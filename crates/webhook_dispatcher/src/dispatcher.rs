@@ -0,0 +1,194 @@
+use crate::database::Database;
+use crate::types::{Config, DeliveryAttempt, Webhook, WebhookEvent};
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use retry::Policy;
+use sha2::Sha256;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Base delay between retries; doubles on every attempt, up to `RETRY_MAX_DELAY_SECS`
+const RETRY_BASE_DELAY_MS: u64 = 500;
+const RETRY_MAX_DELAY_SECS: u64 = 30;
+
+/// Delivers webhook events with HMAC signatures, retries and delivery logging
+pub struct Dispatcher {
+    http_client: Client,
+    max_attempts: u32,
+}
+
+impl Dispatcher {
+    pub fn new(config: &Config) -> Result<Self> {
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()?;
+
+        Ok(Self {
+            http_client,
+            max_attempts: config.max_attempts.max(1),
+        })
+    }
+
+    /// Shared HTTP client (cheap to clone) for other outbound integrations
+    pub fn http_client(&self) -> Client {
+        self.http_client.clone()
+    }
+
+    /// Deliver an event to every subscribed webhook
+    ///
+    /// Returns the number of webhooks that failed after all retries.
+    pub async fn dispatch(&self, database: &Database, event: &WebhookEvent) -> Result<usize> {
+        let webhooks = database.webhooks_for_event(&event.event_type).await?;
+        info!(
+            "📨 Dispatching {} for tender {} to {} webhook(s)",
+            event.event_type,
+            event.resource_id,
+            webhooks.len()
+        );
+
+        let body = serde_json::to_string(event)?;
+        let mut failed = 0;
+
+        for webhook in &webhooks {
+            if !self
+                .deliver_with_retries(database, webhook, event, &body)
+                .await
+            {
+                failed += 1;
+            }
+        }
+
+        Ok(failed)
+    }
+
+    async fn deliver_with_retries(
+        &self,
+        database: &Database,
+        webhook: &Webhook,
+        event: &WebhookEvent,
+        body: &str,
+    ) -> bool {
+        let mut attempt = 0;
+        let delivered = Policy::new("webhook_delivery")
+            .max_tries(self.max_attempts)
+            .base_delay(Duration::from_millis(RETRY_BASE_DELAY_MS))
+            .max_delay(Duration::from_secs(RETRY_MAX_DELAY_SECS))
+            .run(|| {
+                attempt += 1;
+                let attempt = attempt;
+                async move {
+                    let started = Instant::now();
+                    let result = self.send(webhook, event, body).await;
+                    let duration_ms = started.elapsed().as_millis() as i64;
+
+                    let (success, status_code, error) = match result {
+                        Ok(status) if status.is_success() => {
+                            (true, Some(status.as_u16() as i32), None)
+                        }
+                        Ok(status) => (
+                            false,
+                            Some(status.as_u16() as i32),
+                            Some(format!("HTTP {}", status)),
+                        ),
+                        Err(e) => (false, None, Some(e.to_string())),
+                    };
+
+                    let log_entry = DeliveryAttempt {
+                        webhook_id: webhook.id,
+                        event_type: event.event_type.clone(),
+                        resource_id: event.resource_id,
+                        attempt,
+                        status_code,
+                        success,
+                        error: error.clone(),
+                        duration_ms,
+                    };
+                    if let Err(e) = database.log_delivery(&log_entry).await {
+                        warn!("⚠️ Failed to log webhook delivery: {}", e);
+                    }
+
+                    match error {
+                        None => Ok(attempt),
+                        Some(error) => Err(format!(
+                            "webhook {} ({}): {}",
+                            webhook.id, webhook.url, error
+                        )),
+                    }
+                }
+            })
+            .await;
+
+        if let Ok(attempt) = delivered {
+            info!(
+                "✅ Delivered {} to webhook {} ({}) on attempt {}",
+                event.event_type, webhook.id, webhook.url, attempt
+            );
+            return true;
+        }
+
+        warn!(
+            "❌ Giving up on webhook {} (subscribed to {:?}) for tender {}",
+            webhook.id, webhook.event_types, event.resource_id
+        );
+        false
+    }
+
+    async fn send(
+        &self,
+        webhook: &Webhook,
+        event: &WebhookEvent,
+        body: &str,
+    ) -> Result<reqwest::StatusCode> {
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let signature = sign_payload(&webhook.secret, &timestamp, body);
+
+        let response = self
+            .http_client
+            .post(&webhook.url)
+            .header("content-type", "application/json")
+            .header("x-webhook-event", &event.event_type)
+            .header("x-webhook-timestamp", &timestamp)
+            .header("x-webhook-signature", format!("sha256={}", signature))
+            .body(body.to_string())
+            .send()
+            .await?;
+
+        Ok(response.status())
+    }
+}
+
+/// HMAC-SHA256 over "{timestamp}.{body}", hex encoded
+///
+/// Receivers recompute this with their shared secret and reject stale timestamps
+/// to guard against replay.
+pub fn sign_payload(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_payload_is_deterministic() {
+        let a = sign_payload("secret", "1700000000", r#"{"a":1}"#);
+        let b = sign_payload("secret", "1700000000", r#"{"a":1}"#);
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 64);
+    }
+
+    #[test]
+    fn test_sign_payload_depends_on_secret_and_timestamp() {
+        let base = sign_payload("secret", "1700000000", "{}");
+        assert_ne!(base, sign_payload("other", "1700000000", "{}"));
+        assert_ne!(base, sign_payload("secret", "1700000001", "{}"));
+    }
+}
@@ -0,0 +1,128 @@
+use aws_lambda_events::event::sqs::SqsEvent;
+use environment::Environment;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use serde_json::Value;
+use tracing::{error, info, warn, Instrument};
+
+mod crm;
+mod crm_import;
+mod database;
+mod dispatcher;
+mod types;
+
+use crm::{CrmBackend, CrmSync};
+use crm_import::StageMapping;
+use database::Database;
+use dispatcher::Dispatcher;
+use types::{Config, WebhookEvent};
+
+/// Webhook events arrive from SQS; the scheduled CRM import is invoked with `{"crm_import": true}`
+async fn function_handler(event: LambdaEvent<Value>) -> Result<String, Error> {
+    info!("=== WEBHOOK DISPATCHER STARTED ===");
+
+    let config = Config::from_env().map_err(|e| {
+        error!("Failed to load configuration: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+
+    let database = Database::new(&config).await.map_err(|e| {
+        error!("Failed to initialize database: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+
+    let dispatcher = Dispatcher::new(&config)
+        .map_err(|e| Error::from(format!("Failed to create HTTP client: {}", e).as_str()))?;
+
+    let crm_sync = CrmBackend::from_env()
+        .map_err(|e| Error::from(format!("Invalid CRM configuration: {}", e).as_str()))?
+        .map(|backend| {
+            info!("CRM sync enabled ({})", backend.name());
+            CrmSync::new(backend, dispatcher.http_client())
+        });
+
+    if event.payload.get("crm_import").and_then(Value::as_bool) == Some(true) {
+        let Some(crm) = &crm_sync else {
+            return Err(Error::from(
+                "CRM import requested but CRM_PROVIDER is not set",
+            ));
+        };
+        let report = crm_import::run(&database, crm, &StageMapping::from_env())
+            .await
+            .map_err(|e| Error::from(format!("CRM import failed: {}", e).as_str()))?;
+        info!(
+            "📥 CRM import: {} deals, {} labels and {} outcomes changed, {} missing",
+            report.deals, report.labels_changed, report.outcomes_changed, report.missing
+        );
+        return Ok(serde_json::to_string(&report)?);
+    }
+
+    let sqs_event: SqsEvent = serde_json::from_value(event.payload)
+        .map_err(|e| Error::from(format!("Unexpected event: {}", e).as_str()))?;
+    let records = &sqs_event.records;
+    info!("Processing {} webhook events", records.len());
+
+    let mut delivered = 0;
+    let mut failed = 0;
+
+    for record in records {
+        let Some(body) = &record.body else {
+            warn!("⚠️ SQS record has no body, skipping");
+            continue;
+        };
+
+        let webhook_event: WebhookEvent = match serde_json::from_str(body) {
+            Ok(event) => event,
+            Err(e) => {
+                error!("❌ Failed to parse webhook event: {} - body: {}", e, body);
+                continue;
+            }
+        };
+
+        // Retries happen inside the dispatcher and every attempt is logged, so failures
+        // are not re-raised to SQS (which would re-deliver to webhooks that succeeded)
+        match dispatcher.dispatch(&database, &webhook_event).await {
+            Ok(0) => delivered += 1,
+            Ok(n) => {
+                warn!(
+                    "⚠️ {} webhook(s) failed for {} on tender {}",
+                    n, webhook_event.event_type, webhook_event.resource_id
+                );
+                failed += 1;
+            }
+            Err(e) => {
+                error!("❌ Failed to dispatch webhook event: {}", e);
+                failed += 1;
+            }
+        }
+
+        if let Some(crm) = &crm_sync {
+            if let Err(e) = crm.sync(&database, &webhook_event).await {
+                error!(
+                    "❌ CRM sync failed for tender {}: {}",
+                    webhook_event.resource_id, e
+                );
+            }
+        }
+    }
+
+    info!("=== WEBHOOK DISPATCHER COMPLETED ===");
+    Ok(format!(
+        "Dispatched {} events ({} with failures)",
+        delivered, failed
+    ))
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    let environment = Environment::from_env();
+    run(service_fn(move |event| {
+        function_handler(event).instrument(environment.span())
+    }))
+    .await
+}
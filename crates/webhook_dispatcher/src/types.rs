@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Event published to the webhook queue by pipeline stages
+///
+/// `event_type` is matched against the `event_types` array of each registered webhook,
+/// e.g. "AI_SUMMARY_COMPLETE" or "TENDER_UPDATED".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEvent {
+    pub event_type: String,
+    pub resource_id: i64,
+    pub occurred_at: DateTime<Utc>,
+    pub payload: serde_json::Value,
+}
+
+/// Registered webhook endpoint from the `webhooks` table
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+    pub secret: String,
+    pub event_types: Vec<String>,
+}
+
+/// Outcome of a single delivery attempt, persisted to `webhook_deliveries`
+#[derive(Debug, Clone)]
+pub struct DeliveryAttempt {
+    pub webhook_id: i32,
+    pub event_type: String,
+    pub resource_id: i64,
+    pub attempt: i32,
+    pub status_code: Option<i32>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+}
+
+/// Configuration from environment
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database_url: String,
+    pub max_attempts: u32,
+    pub request_timeout_secs: u64,
+}
+
+impl Config {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let database_url = std::env::var("DATABASE_URL")
+            .map_err(|_| anyhow::anyhow!("DATABASE_URL environment variable not set"))?;
+
+        let max_attempts = std::env::var("WEBHOOK_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3);
+
+        let request_timeout_secs = std::env::var("WEBHOOK_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+
+        Ok(Self {
+            database_url,
+            max_attempts,
+            request_timeout_secs,
+        })
+    }
+}
@@ -0,0 +1,109 @@
+//! Monthly trends from the analytics views: IT-tender volume, average value, how often
+//! Claude recommended bidding and, where outcomes are recorded, how often we won.
+
+use crate::types::ReportSection;
+use analytics::MonthlyTrend;
+
+/// "€1,234,567" (whole euros)
+fn euros(value: f64) -> String {
+    let digits = format!("{:.0}", value.max(0.0));
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("€{}", grouped)
+}
+
+fn percent(rate: Option<f64>) -> String {
+    rate.map(|r| format!("{:.0}%", r * 100.0))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn line(label: &str, trend: &MonthlyTrend) -> String {
+    let mut line = format!(
+        "{}: {} IT tenders of {}, avg value {}, {} recommended, {} bids",
+        label,
+        trend.it_tenders,
+        trend.tenders,
+        trend
+            .avg_it_value
+            .map(euros)
+            .unwrap_or_else(|| "-".to_string()),
+        percent(trend.recommendation_rate),
+        trend.bids
+    );
+    if trend.won + trend.lost > 0 {
+        line.push_str(&format!(
+            ", won {} of {} ({})",
+            trend.won,
+            trend.won + trend.lost,
+            percent(trend.win_rate)
+        ));
+    }
+    line
+}
+
+/// Totals per month (oldest first) and IT-tender volume for the busiest authorities
+pub fn sections(
+    monthly: &[MonthlyTrend],
+    by_authority: &[MonthlyTrend],
+    months: u32,
+) -> Vec<ReportSection> {
+    let lines = monthly
+        .iter()
+        .map(|trend| line(&trend.month.format("%Y-%m").to_string(), trend))
+        .collect();
+
+    // by_authority is grouped by authority, busiest first
+    let mut authorities: Vec<(String, i64)> = Vec::new();
+    for trend in by_authority {
+        let name = trend.contracting_authority.clone().unwrap_or_default();
+        match authorities.last_mut() {
+            Some((last, total)) if *last == name => *total += trend.it_tenders,
+            _ => authorities.push((name, trend.it_tenders)),
+        }
+    }
+    let authorities = authorities
+        .into_iter()
+        .map(|(name, total)| format!("{}: {} IT tenders", name, total))
+        .collect();
+
+    vec![
+        ReportSection::new("MONTHLY TRENDS", lines),
+        ReportSection::new(
+            format!("TOP CONTRACTING AUTHORITIES, LAST {} MONTHS", months),
+            authorities,
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_month_line() {
+        let trend = MonthlyTrend {
+            month: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+            contracting_authority: None,
+            tenders: 310,
+            it_tenders: 42,
+            avg_it_value: Some(1_234_567.4),
+            recommendation_rate: Some(0.25),
+            bids: 3,
+            won: 1,
+            lost: 1,
+            win_rate: Some(0.5),
+        };
+
+        assert_eq!(
+            line("2025-03", &trend),
+            "2025-03: 42 IT tenders of 310, avg value €1,234,567, 25% recommended, 3 bids, won 1 of 2 (50%)"
+        );
+        assert_eq!(euros(950.0), "€950");
+    }
+}
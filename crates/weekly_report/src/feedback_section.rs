@@ -0,0 +1,251 @@
+//! Feedback from the notification emails, and exclusion terms suggested by repeated
+//! "not relevant" verdicts.
+//!
+//! Suggestions are per tenant, since each tenant has its own exclusions. A contracting
+//! authority or title keyword is suggested once it has `min` distinct "not relevant"
+//! tenders and no "good call" ones - a single positive means the term still finds work.
+
+use crate::types::ReportSection;
+use feedback::{FeedbackEntry, Verdict};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Suggestions listed per tenant and kind
+const MAX_SUGGESTIONS: usize = 10;
+/// Shorter title words are too generic to exclude on
+const MIN_KEYWORD_LEN: usize = 4;
+
+const STOPWORDS: &[&str] = &[
+    "with",
+    "from",
+    "that",
+    "this",
+    "into",
+    "over",
+    "under",
+    "their",
+    "other",
+    "services",
+    "service",
+    "supply",
+    "provision",
+    "contract",
+    "tender",
+    "framework",
+    "works",
+    "delivery",
+    "request",
+    "lots",
+    "county",
+    "council",
+    "ireland",
+    "irish",
+    "national",
+    "public",
+    "phase",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SuggestionKind {
+    Authority,
+    Keyword,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub tenant_id: String,
+    pub kind: SuggestionKind,
+    pub term: String,
+    /// Distinct tenders marked "not relevant" that match the term
+    pub negatives: usize,
+}
+
+#[derive(Default)]
+struct TermCounts {
+    negatives: BTreeSet<i64>,
+    positives: BTreeSet<i64>,
+    /// Display form: first spelling seen
+    display: String,
+}
+
+fn keywords(title: &str) -> BTreeSet<String> {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| {
+            word.chars().count() >= MIN_KEYWORD_LEN
+                && !word.chars().all(|c| c.is_ascii_digit())
+                && !STOPWORDS.contains(&word.as_str())
+        })
+        .collect()
+}
+
+fn count(
+    counts: &mut BTreeMap<(String, SuggestionKind, String), TermCounts>,
+    entry: &FeedbackEntry,
+    kind: SuggestionKind,
+    term: &str,
+) {
+    let key = term.split_whitespace().collect::<Vec<_>>().join(" ");
+    if key.is_empty() {
+        return;
+    }
+    let counts = counts
+        .entry((entry.tenant_id.clone(), kind, key.to_lowercase()))
+        .or_default();
+    if counts.display.is_empty() {
+        counts.display = key;
+    }
+    match entry.verdict {
+        Verdict::NotRelevant => counts.negatives.insert(entry.resource_id),
+        Verdict::GoodCall => counts.positives.insert(entry.resource_id),
+    };
+}
+
+/// Exclusion terms with at least `min` negatives and no positives, most negatives first
+pub fn suggest_exclusions(entries: &[FeedbackEntry], min: usize) -> Vec<Suggestion> {
+    let mut counts = BTreeMap::new();
+    for entry in entries {
+        count(
+            &mut counts,
+            entry,
+            SuggestionKind::Authority,
+            &entry.contracting_authority,
+        );
+        for keyword in keywords(&entry.title) {
+            count(&mut counts, entry, SuggestionKind::Keyword, &keyword);
+        }
+    }
+
+    let mut suggestions: Vec<Suggestion> = counts
+        .into_iter()
+        .filter(|(_, c)| c.positives.is_empty() && c.negatives.len() >= min)
+        .map(|((tenant_id, kind, _), c)| Suggestion {
+            tenant_id,
+            kind,
+            term: c.display,
+            negatives: c.negatives.len(),
+        })
+        .collect();
+    suggestions.sort_by(|a, b| {
+        (&a.tenant_id, a.kind, b.negatives, &a.term).cmp(&(
+            &b.tenant_id,
+            b.kind,
+            a.negatives,
+            &b.term,
+        ))
+    });
+
+    let mut listed: BTreeMap<(String, SuggestionKind), usize> = BTreeMap::new();
+    suggestions.retain(|s| {
+        let n = listed.entry((s.tenant_id.clone(), s.kind)).or_default();
+        *n += 1;
+        *n <= MAX_SUGGESTIONS
+    });
+    suggestions
+}
+
+/// Verdict counts for the week, and suggestions from the longer feedback window
+pub fn sections(
+    week: &[FeedbackEntry],
+    window: &[FeedbackEntry],
+    min: usize,
+) -> Vec<ReportSection> {
+    let not_relevant = week
+        .iter()
+        .filter(|e| e.verdict == Verdict::NotRelevant)
+        .count();
+    let mut summary = vec![
+        format!("{} marked \"This was not relevant\"", not_relevant),
+        format!("{} marked \"Good call\"", week.len() - not_relevant),
+    ];
+    summary.extend(
+        week.iter()
+            .filter(|e| e.verdict == Verdict::NotRelevant)
+            .map(|e| {
+                format!(
+                    "Not relevant [{}]: {} - {} ({})",
+                    e.tenant_id, e.title, e.contracting_authority, e.resource_id
+                )
+            }),
+    );
+
+    let suggestions = suggest_exclusions(window, min)
+        .into_iter()
+        .map(|s| {
+            let kind = match s.kind {
+                SuggestionKind::Authority => "contracting authority",
+                SuggestionKind::Keyword => "keyword",
+            };
+            format!(
+                "[{}] {} \"{}\" - {} tenders marked not relevant, none marked good call",
+                s.tenant_id, kind, s.term, s.negatives
+            )
+        })
+        .collect();
+
+    vec![
+        ReportSection::new("EMAIL FEEDBACK", summary),
+        ReportSection::new("SUGGESTED EXCLUSION TERMS", suggestions),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn entry(resource_id: i64, verdict: Verdict, title: &str, authority: &str) -> FeedbackEntry {
+        FeedbackEntry {
+            resource_id,
+            tenant_id: "default".to_string(),
+            verdict,
+            title: title.to_string(),
+            contracting_authority: authority.to_string(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_repeated_negatives_suggest_terms() {
+        let entries = vec![
+            entry(
+                1,
+                Verdict::NotRelevant,
+                "Catering equipment",
+                "Dept of Defence",
+            ),
+            entry(
+                2,
+                Verdict::NotRelevant,
+                "Catering supplies",
+                "dept of  defence",
+            ),
+            entry(3, Verdict::NotRelevant, "Catering for events", "HSE"),
+            entry(4, Verdict::NotRelevant, "Software licences", "HSE"),
+            entry(5, Verdict::GoodCall, "Software development", "HSE"),
+        ];
+
+        let suggestions = suggest_exclusions(&entries, 2);
+        let terms: Vec<(SuggestionKind, &str, usize)> = suggestions
+            .iter()
+            .map(|s| (s.kind, s.term.as_str(), s.negatives))
+            .collect();
+
+        assert_eq!(
+            terms,
+            vec![
+                (SuggestionKind::Authority, "Dept of Defence", 2),
+                (SuggestionKind::Keyword, "catering", 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_suggestions_are_per_tenant() {
+        let mut other = entry(2, Verdict::NotRelevant, "Catering", "HSE");
+        other.tenant_id = "sister_co".to_string();
+        let entries = vec![entry(1, Verdict::NotRelevant, "Catering", "HSE"), other];
+
+        assert!(suggest_exclusions(&entries, 2).is_empty());
+    }
+}
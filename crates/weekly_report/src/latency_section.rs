@@ -0,0 +1,68 @@
+//! Scrape-to-notification latency per day against the p95 target (see
+//! `pipeline_status::slo`).
+
+use crate::types::ReportSection;
+use pipeline_status::slo::{format_duration, DailyLatency, Target};
+
+fn line(day: &DailyLatency, target: &Target) -> String {
+    let mut line = format!(
+        "{}: p50 {}, p95 {} ({} tenders)",
+        day.day.format("%a %Y-%m-%d"),
+        format_duration(day.latency.p50_seconds),
+        format_duration(day.latency.p95_seconds),
+        day.latency.tenders
+    );
+    if target.breached(&day.latency) {
+        line.push_str(" - over target");
+    }
+    line
+}
+
+/// Days with notifications, oldest first, after a count of the days the target was met
+pub fn section(days: &[DailyLatency], target: &Target) -> ReportSection {
+    let mut lines = Vec::new();
+    if !days.is_empty() {
+        let met = days.iter().filter(|d| !target.breached(&d.latency)).count();
+        lines.push(format!(
+            "p95 target of {}h met on {} of {} days",
+            target.p95_hours,
+            met,
+            days.len()
+        ));
+    }
+    lines.extend(days.iter().map(|day| line(day, target)));
+    ReportSection::new("SCRAPE-TO-NOTIFICATION LATENCY", lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use pipeline_status::slo::Latency;
+
+    fn day(date: u32, p95_seconds: f64) -> DailyLatency {
+        DailyLatency {
+            day: NaiveDate::from_ymd_opt(2026, 10, date).unwrap(),
+            latency: Latency {
+                tenders: 14,
+                p50_seconds: 2520.0,
+                p95_seconds,
+            },
+        }
+    }
+
+    #[test]
+    fn test_latency_section() {
+        let target = Target { p95_hours: 2.0 };
+        let section = section(&[day(12, 5400.0), day(13, 9000.0)], &target);
+
+        assert_eq!(
+            section.lines,
+            vec![
+                "p95 target of 2h met on 1 of 2 days",
+                "Mon 2026-10-12: p50 42m, p95 1h 30m (14 tenders)",
+                "Tue 2026-10-13: p50 42m, p95 2h 30m (14 tenders) - over target",
+            ]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use anyhow::Result;
+use aws_config::BehaviorVersion;
+use aws_sdk_ses::types::{Body, Content, Destination, Message};
+use aws_sdk_ses::Client as SesClient;
+use chrono::{Duration, Utc};
+use db::Pools;
+use environment::Environment;
+use lambda_runtime::{run, service_fn, Error, LambdaEvent};
+use serde_json::{json, Value};
+use tracing::{error, info, warn, Instrument};
+
+mod analytics_section;
+mod feedback_section;
+mod latency_section;
+mod types;
+mod unknown_codes_section;
+
+use types::{
+    Config, ReportSection, ANALYTICS_AUTHORITIES, ANALYTICS_MONTHS, REPORT_WINDOW_DAYS,
+    SUGGESTION_WINDOW_DAYS, UNKNOWN_CODES_LISTED,
+};
+
+/// Every section of the report, in order
+async fn build_sections(pools: &Pools, config: &Config) -> Result<Vec<ReportSection>> {
+    let now = Utc::now();
+    let week_start = now - Duration::days(REPORT_WINDOW_DAYS);
+
+    feedback::ensure_table(&pools.write).await?;
+    let window = feedback::since(&pools.read, now - Duration::days(SUGGESTION_WINDOW_DAYS)).await?;
+    let week: Vec<_> = window
+        .iter()
+        .filter(|e| e.updated_at >= week_start)
+        .cloned()
+        .collect();
+
+    let mut sections = feedback_section::sections(&week, &window, config.suggestion_min);
+
+    // unknown_codes_seen only exists once pdf_processing has counted a code
+    match unknown_codes_section::popular(
+        &pools.read,
+        now - Duration::days(SUGGESTION_WINDOW_DAYS),
+        config.unknown_code_min,
+        UNKNOWN_CODES_LISTED,
+    )
+    .await
+    {
+        Ok(codes) => sections.push(unknown_codes_section::section(&codes, week_start)),
+        Err(e) => warn!("⚠️ Unknown CPV codes unavailable, left out: {}", e),
+    }
+
+    match pipeline_status::slo::daily(&pools.read, week_start, now).await {
+        Ok(days) => sections.push(latency_section::section(&days, &config.latency_target)),
+        Err(e) => warn!("⚠️ Notification latency unavailable, left out: {}", e),
+    }
+
+    // The views only exist once analytics_refresh has run; the rest of the report still goes out
+    let since = analytics::window_start(now.date_naive(), ANALYTICS_MONTHS);
+    match analytics::monthly(&pools.read, since).await {
+        Ok(monthly) => {
+            let by_authority = analytics::by_authority(&pools.read, since, ANALYTICS_AUTHORITIES)
+                .await
+                .unwrap_or_default();
+            sections.extend(analytics_section::sections(
+                &monthly,
+                &by_authority,
+                ANALYTICS_MONTHS,
+            ));
+        }
+        Err(e) => warn!("⚠️ Analytics unavailable, trends left out: {}", e),
+    }
+
+    Ok(sections)
+}
+
+/// Triggered weekly on a schedule (EventBridge); the event body is not used
+async fn function_handler(_event: LambdaEvent<Value>) -> Result<Value, Error> {
+    info!("=== WEEKLY REPORT STARTED ===");
+
+    let config = Config::from_env().map_err(|e| {
+        error!("Failed to load configuration: {}", e);
+        Error::from(e.to_string().as_str())
+    })?;
+
+    let pools = Pools::connect(&config.database_url, 2)
+        .await
+        .map_err(|e| Error::from(format!("Failed to connect to database: {}", e).as_str()))?;
+
+    let sections = build_sections(&pools, &config)
+        .await
+        .map_err(|e| Error::from(format!("Failed to build report: {}", e).as_str()))?;
+    let body = types::render(&sections);
+    info!("📊 Weekly report:\n{}", body);
+
+    if config.report_emails.is_empty() {
+        info!("📭 REPORT_EMAILS not set - report only logged");
+    } else {
+        send(&config, &body)
+            .await
+            .map_err(|e| Error::from(format!("Failed to send report: {}", e).as_str()))?;
+        info!("📧 Report sent to {}", config.report_emails.join(", "));
+    }
+
+    info!("=== WEEKLY REPORT COMPLETED ===");
+    Ok(json!({ "sections": sections }))
+}
+
+async fn send(config: &Config, body: &str) -> Result<()> {
+    let aws_config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+    let environment = Environment::from_env();
+
+    let mut subject = format!("eTenders weekly report - {}", Utc::now().format("%Y-%m-%d"));
+    if !environment.is_production() {
+        subject = format!("[{}] {}", environment.name().to_uppercase(), subject);
+    }
+
+    SesClient::new(&aws_config)
+        .send_email()
+        .source(&config.from_email)
+        .destination(
+            Destination::builder()
+                .set_to_addresses(Some(config.report_emails.clone()))
+                .build(),
+        )
+        .message(
+            Message::builder()
+                .subject(Content::builder().data(subject).charset("UTF-8").build()?)
+                .body(
+                    Body::builder()
+                        .text(Content::builder().data(body).charset("UTF-8").build()?)
+                        .build(),
+                )
+                .build(),
+        )
+        .send()
+        .await?;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_target(false)
+        .without_time()
+        .init();
+
+    let environment = Environment::from_env();
+    run(service_fn(move |event| {
+        function_handler(event).instrument(environment.span())
+    }))
+    .await
+}
@@ -0,0 +1,110 @@
+use pipeline_status::slo::Target;
+use serde::Serialize;
+
+pub const DEFAULT_SUGGESTION_MIN: usize = 3;
+pub const DEFAULT_UNKNOWN_CODE_MIN: i32 = 3;
+/// Days of feedback considered for exclusion suggestions
+pub const SUGGESTION_WINDOW_DAYS: i64 = 90;
+/// Days covered by the rest of the report
+pub const REPORT_WINDOW_DAYS: i64 = 7;
+/// Months of trends in the report, including the current one
+pub const ANALYTICS_MONTHS: u32 = 6;
+/// Contracting authorities listed by IT-tender volume
+pub const ANALYTICS_AUTHORITIES: i64 = 10;
+/// Unknown CPV codes listed as catalogue candidates
+pub const UNKNOWN_CODES_LISTED: i64 = 10;
+
+/// One titled block of the report; each data source contributes its own sections
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportSection {
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
+impl ReportSection {
+    pub fn new(title: impl Into<String>, lines: Vec<String>) -> Self {
+        Self {
+            title: title.into(),
+            lines,
+        }
+    }
+}
+
+/// Plain-text email body
+pub fn render(sections: &[ReportSection]) -> String {
+    sections
+        .iter()
+        .map(|section| {
+            let body = if section.lines.is_empty() {
+                "Nothing to report.".to_string()
+            } else {
+                section
+                    .lines
+                    .iter()
+                    .map(|line| format!("- {}", line))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            format!(
+                "{}\n{}\n{}",
+                section.title,
+                "-".repeat(section.title.chars().count()),
+                body
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Configuration from environment
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database_url: String,
+    /// The report is only logged when empty
+    pub report_emails: Vec<String>,
+    pub from_email: String,
+    /// "Not relevant" verdicts (with no "good call") before a term is suggested as an exclusion
+    pub suggestion_min: usize,
+    /// Tenders that must declare an unknown CPV code before it is listed
+    pub unknown_code_min: i32,
+    /// Days whose scrape-to-notification p95 is over this are flagged
+    pub latency_target: Target,
+}
+
+impl Config {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let database_url = std::env::var("DATABASE_URL")
+            .map_err(|_| anyhow::anyhow!("DATABASE_URL environment variable not set"))?;
+
+        let report_emails = std::env::var("REPORT_EMAILS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|email| email.trim().to_string())
+            .filter(|email| !email.is_empty())
+            .collect();
+
+        let from_email = std::env::var("FROM_EMAIL")
+            .unwrap_or_else(|_| "etenders-noreply@robertsweetman.com".to_string());
+
+        let suggestion_min = std::env::var("FEEDBACK_SUGGESTION_MIN")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .filter(|min| *min > 0)
+            .unwrap_or(DEFAULT_SUGGESTION_MIN);
+
+        let unknown_code_min = std::env::var("UNKNOWN_CODE_MIN")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .filter(|min| *min > 0)
+            .unwrap_or(DEFAULT_UNKNOWN_CODE_MIN);
+
+        Ok(Self {
+            database_url,
+            report_emails,
+            from_email,
+            suggestion_min,
+            unknown_code_min,
+            latency_target: Target::from_env(),
+        })
+    }
+}
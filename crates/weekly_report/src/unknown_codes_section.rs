@@ -0,0 +1,102 @@
+//! CPV codes tender notices declare that aren't in detection_codes, most often seen first,
+//! as candidates for the catalogue (counted by pdf_processing, see
+//! `pdf_processing::unknown_codes`).
+
+use crate::types::ReportSection;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Row};
+
+#[derive(Debug, Clone)]
+pub struct UnknownCode {
+    pub code: String,
+    /// Tenders that declared it
+    pub tenders: i32,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_resource_id: Option<i64>,
+}
+
+/// Codes declared by at least `min_tenders` tenders and seen again since `since`, that
+/// still aren't in the catalogue
+pub async fn popular(
+    pool: &PgPool,
+    since: DateTime<Utc>,
+    min_tenders: i32,
+    limit: i64,
+) -> Result<Vec<UnknownCode>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT u.code, u.tenders, u.first_seen_at, u.last_resource_id
+        FROM unknown_codes_seen u
+        WHERE u.last_seen_at >= $1 AND u.tenders >= $2
+          AND NOT EXISTS (SELECT 1 FROM detection_codes d WHERE d.code = u.code)
+        ORDER BY u.tenders DESC, u.code
+        LIMIT $3
+        "#,
+    )
+    .bind(since)
+    .bind(min_tenders)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| UnknownCode {
+            code: row.get("code"),
+            tenders: row.get("tenders"),
+            first_seen_at: row.get("first_seen_at"),
+            last_resource_id: row.get("last_resource_id"),
+        })
+        .collect())
+}
+
+fn line(code: &UnknownCode, week_start: DateTime<Utc>) -> String {
+    let mut line = format!(
+        "{}: declared by {} tenders since {}",
+        code.code,
+        code.tenders,
+        code.first_seen_at.format("%Y-%m-%d")
+    );
+    if let Some(resource_id) = code.last_resource_id {
+        line.push_str(&format!(", latest {}", resource_id));
+    }
+    if code.first_seen_at >= week_start {
+        line.push_str(" (new this week)");
+    }
+    line
+}
+
+pub fn section(codes: &[UnknownCode], week_start: DateTime<Utc>) -> ReportSection {
+    ReportSection::new(
+        "CPV CODES TO CONSIDER (`ops_cli codes add`)",
+        codes.iter().map(|code| line(code, week_start)).collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_unknown_code_line() {
+        let week_start = Utc.with_ymd_and_hms(2026, 10, 9, 0, 0, 0).unwrap();
+        let mut code = UnknownCode {
+            code: "72212000".to_string(),
+            tenders: 7,
+            first_seen_at: Utc.with_ymd_and_hms(2026, 8, 3, 10, 0, 0).unwrap(),
+            last_resource_id: Some(6543210),
+        };
+        assert_eq!(
+            line(&code, week_start),
+            "72212000: declared by 7 tenders since 2026-08-03, latest 6543210"
+        );
+
+        code.first_seen_at = Utc.with_ymd_and_hms(2026, 10, 12, 0, 0, 0).unwrap();
+        code.last_resource_id = None;
+        assert_eq!(
+            line(&code, week_start),
+            "72212000: declared by 7 tenders since 2026-10-12 (new this week)"
+        );
+    }
+}